@@ -0,0 +1,108 @@
+//! Comparison of 16-bit Variable Word Encoding against Variable Byte
+//! Encoding.
+//!
+//! For each workload, the numbers are encoded and decoded with both codes a
+//! few times, and the size of the encoding is reported along with the
+//! fastest time of each step, in nanoseconds per number:
+//!
+//! - `hashes`: 64-bit hashes, spread over all 64 bits;
+//! - `snowflakes`: IDs made of a 41-bit millisecond timestamp, a 10-bit
+//!   machine ID and a 12-bit sequence, 63 bits in total;
+//! - `ids`: 45-bit IDs, where 16-bit words save a byte per number;
+//! - `small`: numbers of up to 20 bits, where Variable Byte pays off.
+//!
+//! Usage: `cargo bench --bench vb16`
+
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use idencode::{Decoder, Encoder, VB16Decoder, VB16Encoder, VBDecoder, VBEncoder};
+
+/// The number of numbers of each workload.
+const LEN: usize = 1 << 20;
+
+/// The number of times each step is timed.
+const RUNS: usize = 10;
+
+/// A SplitMix64 generator, which is enough to spread the numbers over all
+/// their widths.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Returns the fastest of a few runs of a function, along with its result.
+fn fastest<T>(mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut best = Duration::MAX;
+    let mut result = f();
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = black_box(f());
+        best = best.min(start.elapsed());
+    }
+    (best, result)
+}
+
+/// Encodes and decodes the numbers with an encoder and a decoder, and prints
+/// the size of the encoding and the time of each step.
+fn bench<E, D>(
+    code: &str,
+    nums: &[u64],
+    enc: impl Fn(Cursor<Vec<u8>>) -> E,
+    dec: impl Fn(Cursor<Vec<u8>>) -> D,
+) where
+    E: Encoder<Cursor<Vec<u8>>>,
+    D: Decoder<Cursor<Vec<u8>>>,
+{
+    let (encode_time, bytes) = fastest(|| {
+        let mut enc = enc(Cursor::new(vec![]));
+        enc.encode(black_box(nums)).unwrap();
+        enc.finalize().unwrap().into_inner()
+    });
+    let (decode_time, decoded) = fastest(|| {
+        dec(Cursor::new(black_box(bytes.clone())))
+            .decode::<u64>()
+            .unwrap()
+    });
+    assert_eq!(
+        decoded, nums,
+        "The {code} code does not decode the numbers back."
+    );
+
+    let per_num = |time: Duration| time.as_nanos() as f64 / nums.len() as f64;
+    println!(
+        "  {code:<5} {:>6.3} bytes/num  encode {:>6.2} ns/num  decode {:>6.2} ns/num",
+        bytes.len() as f64 / nums.len() as f64,
+        per_num(encode_time),
+        per_num(decode_time),
+    );
+}
+
+fn main() {
+    let mut rng = Rng(0x1DE7C0DE);
+    let hashes: Vec<u64> = (0..LEN).map(|_| rng.next()).collect();
+    let snowflakes: Vec<u64> = (0..LEN as u64)
+        .map(|i| ((1_700_000_000_000 + i / 4) << 22) | (rng.next() % 1024) << 12 | (i % 4096))
+        .collect();
+    let ids: Vec<u64> = (0..LEN).map(|_| rng.next() >> 19 | 1 << 44).collect();
+    let small: Vec<u64> = (0..LEN).map(|_| rng.next() >> 44).collect();
+
+    for (workload, nums) in [
+        ("hashes", &hashes),
+        ("snowflakes", &snowflakes),
+        ("ids", &ids),
+        ("small", &small),
+    ] {
+        println!("{workload}:");
+        bench("VB", nums, VBEncoder::new, VBDecoder::new);
+        bench("VB16", nums, VB16Encoder::new, VB16Decoder::new);
+    }
+}
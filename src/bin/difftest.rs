@@ -0,0 +1,276 @@
+//! Differential testing of the codecs against reference implementations.
+//!
+//! Each round generates random sequences of numbers, and checks that:
+//!
+//! - the Elias Gamma, Elias Delta and Variable Byte encoders write the same
+//!   bytes as straightforward reference coders, written here bit by bit, and
+//!   that the decoders decode the numbers back;
+//! - the numbers decoded from Variable Byte Encoding match the ones decoded
+//!   by the `vint64` crate from its own encoding;
+//! - the numbers decoded from the BP128 layout match the ones decoded by the
+//!   `stream-vbyte` crate from its own encoding.
+//!
+//! The first divergence is reported with the offset of the byte, or the
+//! index of the number, where the outputs differ, along with the seed that
+//! reproduces it.
+//!
+//! Usage: `cargo run --features difftest --bin difftest -- [ROUNDS] [SEED]`
+
+use std::io::Cursor;
+use std::process::ExitCode;
+
+use idencode::{
+    BP128Decoder, BP128Encoder, Decoder, DeltaDecoder, DeltaEncoder, Encoder, GammaDecoder,
+    GammaEncoder, VBDecoder, VBEncoder,
+};
+use stream_vbyte::scalar::Scalar;
+
+/// The default number of rounds.
+const ROUNDS: u64 = 1000;
+
+/// A SplitMix64 generator, which is enough to spread the numbers over all
+/// their widths.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a positive number of random width, so that small numbers are
+    /// as likely as large ones.
+    fn positive(&mut self) -> u64 {
+        let width = self.next() % 64 + 1;
+        (self.next() >> (64 - width)) | (1 << (width - 1))
+    }
+
+    /// Returns a sequence of 1 up to 1000 numbers.
+    fn sequence(&mut self, mut gen: impl FnMut(&mut Self) -> u64) -> Vec<u64> {
+        let len = self.next() % 1000 + 1;
+        (0..len).map(|_| gen(self)).collect()
+    }
+}
+
+/// A divergence between two outputs.
+struct Divergence {
+    check: &'static str,
+    what: &'static str,
+    offset: usize,
+    detail: String,
+}
+
+/// Compares two sequences, returning the first position where they differ.
+fn compare<T: PartialEq + std::fmt::Debug>(
+    check: &'static str,
+    what: &'static str,
+    actual: &[T],
+    expected: &[T],
+) -> Result<(), Divergence> {
+    let offset = match actual.iter().zip(expected).position(|(a, e)| a != e) {
+        Some(offset) => offset,
+        None if actual.len() == expected.len() => return Ok(()),
+        None => actual.len().min(expected.len()),
+    };
+    Err(Divergence {
+        check,
+        what,
+        offset,
+        detail: format!(
+            "idencode {:?}, reference {:?} (lengths {} and {})",
+            actual.get(offset),
+            expected.get(offset),
+            actual.len(),
+            expected.len()
+        ),
+    })
+}
+
+/// Packs bits to bytes, appending a terminating 1-bit and padding the last
+/// byte with 0-bits, like a bit writer with a terminating bit does.
+fn pack_terminated(mut bits: Vec<bool>) -> Vec<u8> {
+    bits.push(true);
+    bits.chunks(8)
+        .map(|chunk| {
+            let byte = chunk
+                .iter()
+                .fold(0, |byte, &bit| (byte << 1) | u8::from(bit));
+            byte << (8 - chunk.len())
+        })
+        .collect()
+}
+
+/// Appends the bits of a number below its leading 1-bit.
+fn push_offset(num: u64, bits: &mut Vec<bool>) {
+    let len = 63 - num.leading_zeros();
+    bits.extend((0..len).rev().map(|i| (num >> i) & 1 == 1));
+}
+
+/// Appends the Elias Gamma code of a positive number: the number of its
+/// offset bits in unary, with 1-bits terminated by a 0-bit, and the offset.
+fn push_gamma(num: u64, bits: &mut Vec<bool>) {
+    let len = 63 - num.leading_zeros();
+    bits.extend((0..len).map(|_| true));
+    bits.push(false);
+    push_offset(num, bits);
+}
+
+/// Appends the Elias Delta code of a positive number: its number of bits in
+/// Elias Gamma Encoding, and its offset.
+fn push_delta(num: u64, bits: &mut Vec<bool>) {
+    push_gamma(u64::from(64 - num.leading_zeros()), bits);
+    push_offset(num, bits);
+}
+
+fn reference_gamma(nums: &[u64]) -> Vec<u8> {
+    let mut bits = vec![];
+    nums.iter().for_each(|&num| push_gamma(num, &mut bits));
+    pack_terminated(bits)
+}
+
+fn reference_delta(nums: &[u64]) -> Vec<u8> {
+    let mut bits = vec![];
+    nums.iter().for_each(|&num| push_delta(num, &mut bits));
+    pack_terminated(bits)
+}
+
+/// Encodes the numbers in 7-bit groups, from the most significant one, with
+/// the first bit of a byte set only for the last group.
+fn reference_vb(nums: &[u64]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &num in nums {
+        let n_groups = (64 - num.leading_zeros()).div_ceil(7).max(1);
+        for group in (0..n_groups).rev() {
+            let payload = ((num >> (7 * group)) & 0x7F) as u8;
+            bytes.push(if group == 0 { payload | 0x80 } else { payload });
+        }
+    }
+    bytes
+}
+
+fn encode<E: Encoder<Cursor<Vec<u8>>>>(mut enc: E, nums: &[u64]) -> Vec<u8> {
+    enc.encode(nums)
+        .expect("Writing to a vector does not fail.");
+    enc.finalize()
+        .expect("Writing to a vector does not fail.")
+        .into_inner()
+}
+
+/// Checks the bytes written by an encoder against a reference coder, and the
+/// numbers decoded back.
+fn check_bit_code(
+    check: &'static str,
+    nums: &[u64],
+    actual: Vec<u8>,
+    expected: Vec<u8>,
+    decoded: impl FnOnce(Vec<u8>) -> Result<Vec<u64>, idencode::error::InvalidCodeError>,
+) -> Result<(), Divergence> {
+    compare(check, "byte", &actual, &expected)?;
+    let decoded = or_divergence(check, decoded(actual))?;
+    compare(check, "number", &decoded, nums)
+}
+
+/// Reports a decoding error as a divergence at the start of the stream.
+fn or_divergence<T>(
+    check: &'static str,
+    result: Result<T, idencode::error::InvalidCodeError>,
+) -> Result<T, Divergence> {
+    result.map_err(|e| Divergence {
+        check,
+        what: "decoding",
+        offset: 0,
+        detail: e.to_string(),
+    })
+}
+
+fn check_vint64(nums: &[u64]) -> Result<(), Divergence> {
+    let vb = encode(VBEncoder::new(Cursor::new(vec![])), nums);
+    let decoded = or_divergence("vb/vint64", VBDecoder::new(Cursor::new(vb)).decode::<u64>())?;
+    let mut bytes = vec![];
+    for &num in nums {
+        bytes.extend_from_slice(vint64::encode(num).as_ref());
+    }
+    let mut input = bytes.as_slice();
+    let mut expected = vec![];
+    while !input.is_empty() {
+        expected.push(vint64::decode(&mut input).expect("vint64 decodes its own encoding."));
+    }
+    compare("vb/vint64", "number", &decoded, &expected)
+}
+
+fn check_stream_vbyte(nums: &[u64]) -> Result<(), Divergence> {
+    let nums: Vec<u32> = nums.iter().map(|&num| num as u32).collect();
+    let mut bp128 = BP128Encoder::new(Cursor::new(vec![]));
+    bp128
+        .encode(&nums)
+        .expect("Writing to a vector does not fail.");
+    let bytes = bp128
+        .finalize()
+        .expect("Writing to a vector does not fail.");
+    let decoded = BP128Decoder::new(Cursor::new(bytes.into_inner())).decode::<u32>();
+    let decoded = or_divergence("bp128/stream-vbyte", decoded)?;
+
+    let mut encoded = vec![0; 5 * nums.len()];
+    let len = stream_vbyte::encode::encode::<Scalar>(&nums, &mut encoded);
+    let mut expected = vec![0; nums.len()];
+    stream_vbyte::decode::decode::<Scalar>(&encoded[..len], nums.len(), &mut expected);
+    compare("bp128/stream-vbyte", "number", &decoded, &expected)
+}
+
+fn round(rng: &mut Rng) -> Result<(), Divergence> {
+    let positive = rng.sequence(Rng::positive);
+    check_bit_code(
+        "gamma",
+        &positive,
+        encode(GammaEncoder::new(Cursor::new(vec![])), &positive),
+        reference_gamma(&positive),
+        |bytes| GammaDecoder::new(Cursor::new(bytes)).decode(),
+    )?;
+    check_bit_code(
+        "delta",
+        &positive,
+        encode(DeltaEncoder::new(Cursor::new(vec![])), &positive),
+        reference_delta(&positive),
+        |bytes| DeltaDecoder::new(Cursor::new(bytes)).decode(),
+    )?;
+
+    // Numbers whose low groups are zero are as likely as any other.
+    let nums = rng.sequence(|rng| rng.positive() >> (rng.next() % 64) << (rng.next() % 8));
+    check_bit_code(
+        "vb",
+        &nums,
+        encode(VBEncoder::new(Cursor::new(vec![])), &nums),
+        reference_vb(&nums),
+        |bytes| VBDecoder::new(Cursor::new(bytes)).decode(),
+    )?;
+    check_vint64(&nums)?;
+
+    let small = rng.sequence(|rng| rng.positive() >> 32);
+    check_stream_vbyte(&small)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let rounds = args.next().map_or(ROUNDS, |arg| {
+        arg.parse().expect("Invalid number of rounds.")
+    });
+    let seed = args
+        .next()
+        .map_or(0, |arg| arg.parse().expect("Invalid seed."));
+
+    for i in 0..rounds {
+        let seed = seed + i;
+        if let Err(div) = round(&mut Rng(seed)) {
+            eprintln!(
+                "{}: {} {} diverges: {} (seed {seed})",
+                div.check, div.what, div.offset, div.detail
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+    println!("{rounds} rounds without divergence.");
+    ExitCode::SUCCESS
+}
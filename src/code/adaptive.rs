@@ -0,0 +1,359 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::tagged::TaggedCode;
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::bits_to_numeric;
+use crate::num::convert::write_fixed_bits;
+use crate::num::Numeric;
+
+/// The default number of integers in a block.
+const BLOCK_LEN: usize = 128;
+
+/// The number of bits of the width of a bit-packed block.
+const WIDTH_BITS: u32 = 7;
+
+/// The codes that an [`AutoEncoder`] picks from for each block, along with
+/// the 2-bit selectors that identify them in the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoCode {
+    /// Elias Gamma encoding of the numbers incremented by one, selected by 00.
+    Gamma,
+    /// Elias Delta encoding of the numbers incremented by one, selected by 01.
+    Delta,
+    /// Variable Byte encoding of the numbers, not aligned to bytes, selected
+    /// by 10.
+    VB,
+    /// The numbers packed in a width of bits, which is written in 7 bits after
+    /// the selector 11.
+    BitPack(u32),
+}
+
+impl AutoCode {
+    /// Returns the code with the fewest bits for a block of numbers, along
+    /// with the number of bits. Ties are broken in the order of the selectors.
+    fn smallest(nums: &[u64]) -> (AutoCode, usize) {
+        let width = nums
+            .iter()
+            .map(|num| u64::BITS - num.leading_zeros())
+            .max()
+            .unwrap_or(0);
+        let mut best = (
+            AutoCode::BitPack(width),
+            WIDTH_BITS as usize + width as usize * nums.len(),
+        );
+        for (code, tagged) in [
+            (AutoCode::Gamma, TaggedCode::Gamma),
+            (AutoCode::Delta, TaggedCode::Delta),
+            (AutoCode::VB, TaggedCode::VB),
+        ]
+        .into_iter()
+        .rev()
+        {
+            let len: Option<usize> = nums.iter().map(|&num| tagged.code_len(num)).sum();
+            if let Some(len) = len.filter(|&len| len <= best.1) {
+                best = (code, len);
+            }
+        }
+        best
+    }
+
+    fn selector(&self) -> u8 {
+        match self {
+            AutoCode::Gamma => 0,
+            AutoCode::Delta => 1,
+            AutoCode::VB => 2,
+            AutoCode::BitPack(_) => 3,
+        }
+    }
+
+    fn tagged(&self) -> Option<TaggedCode> {
+        match self {
+            AutoCode::Gamma => Some(TaggedCode::Gamma),
+            AutoCode::Delta => Some(TaggedCode::Delta),
+            AutoCode::VB => Some(TaggedCode::VB),
+            AutoCode::BitPack(_) => None,
+        }
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers in
+/// blocks, each one in the code that encodes it in the fewest bits.
+///
+/// The integers are split in blocks of 128, by default, and each block is
+/// encoded in Elias Gamma, Elias Delta, Variable Byte Encoding and bit-packing
+/// at the width of its largest integer, and written in the smallest of them.
+/// Each block starts with the 2-bit selector of its [`AutoCode`] and its
+/// length in Elias Gamma Encoding, so the decoder dispatches each block to its
+/// code, and the stream is close to the size of the best of the codes for
+/// data whose distribution changes along the way, without choosing a code up
+/// front. The codes chosen can be inspected with [`AutoEncoder::block_codes`].
+///
+/// Full blocks are written as soon as they are encoded, and the last, partial
+/// block when the encoder is finalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{AutoCode, AutoDecoder, AutoEncoder, Decoder, Encoder};
+///
+/// // Mostly zeros, then bytes, then huge numbers between small ones.
+/// let mut nums: Vec<u64> = (0..128).map(|i| if i % 32 == 0 { 14 } else { 0 }).collect();
+/// nums.extend((0..128).map(|i| (i * 37) % 256));
+/// nums.extend((0..128).map(|i| if i % 2 == 0 { 1 << 42 } else { 1 }));
+/// let mut enc = AutoEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&nums).unwrap();
+/// assert_eq!(
+///     enc.block_codes(),
+///     &[AutoCode::Gamma, AutoCode::BitPack(8), AutoCode::Delta]
+/// );
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let dec = AutoDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u64>().unwrap(), nums);
+/// ```
+pub struct AutoEncoder<W> {
+    writer: BitWriter<W>,
+    block_len: usize,
+    block: Vec<u64>,
+    codes: Vec<AutoCode>,
+}
+
+impl<W: Write> AutoEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_block_len(writer, BLOCK_LEN)
+    }
+
+    /// Creates a new encoder that picks a code for every `block_len`
+    /// integers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_len` is 0.
+    pub fn with_block_len(writer: W, block_len: usize) -> Self {
+        assert!(block_len > 0, "The blocks must have at least one integer.");
+        let writer = BitWriter::new(writer, true);
+        AutoEncoder {
+            writer,
+            block_len,
+            block: Vec::with_capacity(block_len),
+            codes: vec![],
+        }
+    }
+
+    /// Returns the codes of the blocks written so far, in order.
+    pub fn block_codes(&self) -> &[AutoCode] {
+        &self.codes
+    }
+
+    /// Writes the buffered block in its smallest code.
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let (code, len) = AutoCode::smallest(&self.block);
+        let convention = UnaryConvention::default();
+        let mut bits = Vec::with_capacity(len + 2 * u64::BITS as usize);
+        write_fixed_bits(&code.selector(), 2, &mut bits);
+        write_gamma_bits(self.block.len(), convention, &mut bits);
+        match code {
+            AutoCode::BitPack(width) => {
+                write_fixed_bits(&width, WIDTH_BITS, &mut bits);
+                for num in &self.block {
+                    write_fixed_bits(num, width, &mut bits);
+                }
+            }
+            _ => {
+                let tagged = code.tagged().expect("The code is not bit-packing.");
+                for &num in &self.block {
+                    tagged.write_bits(num, &mut bits);
+                }
+            }
+        }
+        self.block.clear();
+        self.codes.push(code);
+        self.writer.write_bits(&bits)
+    }
+}
+
+impl<W: Write> Encoder<W> for AutoEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            self.block.push(num.to_u64());
+            if self.block.len() == self.block_len {
+                self.write_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the last block and finalizes the encoding.
+    fn finalize(mut self) -> io::Result<W> {
+        self.write_block()?;
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for AutoEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`AutoEncoder`], dispatching each block to the code of its selector.
+pub struct AutoDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> AutoDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        AutoDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for AutoDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = |_| InvalidCodeError::AutoCodeError;
+        let fixed = |bits: &[bool], width: u32| -> Result<(u64, usize), InvalidCodeError> {
+            let num_bits = bits
+                .get(..width as usize)
+                .ok_or(InvalidCodeError::AutoCodeError)?;
+            let num = bits_to_numeric(num_bits).map_err(|_| InvalidCodeError::AutoCodeError)?;
+            Ok((num, width as usize))
+        };
+
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let selector = self.cursor.decode_with(|bits| fixed(bits, 2))?;
+            let len: usize = self
+                .cursor
+                .decode_with(|bits| decode_gamma_prefix(bits, UnaryConvention::default()))
+                .map_err(err)?;
+            let code = match selector {
+                0 => AutoCode::Gamma,
+                1 => AutoCode::Delta,
+                2 => AutoCode::VB,
+                _ => {
+                    let width = self.cursor.decode_with(|bits| fixed(bits, WIDTH_BITS))?;
+                    if width > u64::BITS.into() {
+                        return Err(InvalidCodeError::AutoCodeError);
+                    }
+                    AutoCode::BitPack(width as u32)
+                }
+            };
+            for _ in 0..len {
+                let num = match (code, code.tagged()) {
+                    (AutoCode::BitPack(width), _) => {
+                        self.cursor.decode_with(|bits| fixed(bits, width))?
+                    }
+                    (_, Some(tagged)) => self
+                        .cursor
+                        .decode_with(|bits| tagged.decode_prefix(bits))
+                        .map_err(err)?,
+                    _ => unreachable!("Only bit-packing has no tagged code."),
+                };
+                nums.push(T::from_u64(num).ok_or(InvalidCodeError::AutoCodeError)?);
+            }
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::spread;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], block_len: usize) -> (Vec<AutoCode>, Vec<u8>) {
+        let mut enc = AutoEncoder::with_block_len(Cursor::new(vec![]), block_len);
+        enc.encode(nums).unwrap();
+        enc.write_block().unwrap();
+        let codes = enc.block_codes().to_vec();
+        (codes, enc.finalize().unwrap().into_inner())
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        AutoDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_smallest() {
+        assert_eq!(AutoCode::smallest(&[0; 64]), (AutoCode::BitPack(0), 7));
+        assert_eq!(AutoCode::smallest(&[1, 2, 0]), (AutoCode::Gamma, 7));
+        assert_eq!(AutoCode::smallest(&[1 << 42, 1]).0, AutoCode::Delta);
+        assert_eq!(AutoCode::smallest(&[u64::MAX, 1]).0, AutoCode::VB);
+        assert_eq!(
+            AutoCode::smallest(&[u64::MAX; 3]),
+            (AutoCode::BitPack(64), 7 + 3 * 64)
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = spread(0..1000).collect();
+        nums.extend([0, u64::MAX, 1, 0]);
+        for block_len in [1, 7, 128, 5000] {
+            let (codes, bytes) = encode(&nums, block_len);
+            assert_eq!(codes.len(), nums.len().div_ceil(block_len));
+            assert_eq!(decode(bytes), Ok(nums.clone()));
+        }
+        assert_eq!(decode(encode(&[], 128).1), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_no_larger_than_each_code() {
+        let nums: Vec<u64> = (0..128).map(|i| (i * i) % 1000).collect();
+        let (codes, bytes) = encode(&nums, 128);
+        for code in [TaggedCode::Gamma, TaggedCode::Delta, TaggedCode::VB] {
+            let len: usize = nums.iter().map(|&num| code.code_len(num).unwrap()).sum();
+            assert!(8 * bytes.len() <= len + 32, "{codes:?} {code:?}");
+        }
+        assert!(8 * bytes.len() <= 7 + 10 * 128 + 32);
+    }
+
+    #[test]
+    fn test_errs() {
+        // A block of two values with a single one written.
+        let mut bits = vec![false, false];
+        write_gamma_bits(2_u32, UnaryConvention::default(), &mut bits);
+        write_gamma_bits(5_u32, UnaryConvention::default(), &mut bits);
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        assert!(decode(writer.finalize().unwrap().into_inner()).is_err());
+
+        // A bit-packed block with a width of 65 bits.
+        let mut bits = vec![true, true, true];
+        write_fixed_bits(&65_u32, WIDTH_BITS, &mut bits);
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        assert!(decode(writer.finalize().unwrap().into_inner()).is_err());
+
+        let result = encode(&[300], 128).1;
+        assert!(AutoDecoder::new(Cursor::new(result))
+            .decode::<u8>()
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_blocks() {
+        AutoEncoder::with_block_len(Cursor::new(vec![]), 0);
+    }
+}
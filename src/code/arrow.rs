@@ -0,0 +1,152 @@
+//! Conversions between idencode streams and the primitive arrays of
+//! [Apache Arrow](https://arrow.apache.org).
+//!
+//! A [`PrimitiveArray`] of unsigned integers is encoded with a
+//! [`NullableEncoder`], so its validity bitmap is written ahead of its
+//! non-null values, which are encoded with any encoder. An array without
+//! nulls hands its value buffer to the encoder as it is, and the decoded
+//! values of a stream without nulls become the value buffer of the array
+//! without being copied. Otherwise, the non-null values are gathered before
+//! encoding, and scattered between the nulls after decoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::Cursor;
+//! use arrow_array::UInt32Array;
+//! use idencode::code::arrow::{decode_primitive_array, encode_primitive_array};
+//! use idencode::{VBDecoder, VBEncoder};
+//!
+//! let array = UInt32Array::from(vec![Some(7), None, Some(300), Some(2)]);
+//! let inner = VBEncoder::new(Cursor::new(vec![]));
+//! let result = encode_primitive_array(Cursor::new(vec![]), inner, &array)
+//!     .unwrap()
+//!     .into_inner();
+//!
+//! let decoded = decode_primitive_array(Cursor::new(result), VBDecoder::new).unwrap();
+//! assert_eq!(decoded, array);
+//! ```
+
+use std::io::{self, Cursor, Read, Write};
+
+use arrow_array::types::ArrowPrimitiveType;
+use arrow_array::{Array, PrimitiveArray};
+use arrow_buffer::{NullBuffer, ScalarBuffer};
+
+use crate::code::nullable::{NullableDecoder, NullableEncoder};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// Encodes a primitive array, along with its validity, in a writer, and
+/// returns the writer.
+///
+/// The non-null values are encoded with `inner`, an encoder that writes in
+/// memory, as in [`NullableEncoder`].
+pub fn encode_primitive_array<T, W, E>(
+    writer: W,
+    inner: E,
+    array: &PrimitiveArray<T>,
+) -> io::Result<W>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Numeric,
+    W: Write,
+    E: Encoder<Cursor<Vec<u8>>>,
+{
+    let mut enc = NullableEncoder::new(writer, inner);
+    match array.nulls() {
+        Some(nulls) if nulls.null_count() > 0 => {
+            let values: Vec<T::Native> = nulls.valid_indices().map(|i| array.value(i)).collect();
+            enc.encode_parts(&values, nulls.iter())?;
+        }
+        _ => enc.encode_parts(array.values(), std::iter::repeat_n(true, array.len()))?,
+    }
+    enc.finalize()
+}
+
+/// Decodes a primitive array, along with its validity, from a reader of a
+/// stream encoded with [`encode_primitive_array`].
+///
+/// The non-null values are decoded with the decoder created by `inner`, as
+/// in [`NullableDecoder`]. The null slots of the value buffer hold zeros.
+pub fn decode_primitive_array<T, R, D, F>(
+    reader: R,
+    inner: F,
+) -> Result<PrimitiveArray<T>, InvalidCodeError>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Numeric,
+    R: Read,
+    D: Decoder<Cursor<Vec<u8>>>,
+    F: FnOnce(Cursor<Vec<u8>>) -> D,
+{
+    let (validity, values) = NullableDecoder::new(reader).decode_parts::<T::Native, _, _>(inner)?;
+    if values.len() == validity.len() {
+        return Ok(PrimitiveArray::new(ScalarBuffer::from(values), None));
+    }
+
+    let mut values = values.into_iter();
+    let dense: Vec<T::Native> = validity
+        .iter()
+        .map(|&valid| match valid {
+            true => values.next().expect("There is a value per 1-bit."),
+            false => Default::default(),
+        })
+        .collect();
+    let nulls = NullBuffer::from(validity);
+    Ok(PrimitiveArray::new(ScalarBuffer::from(dense), Some(nulls)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GammaDecoder, GammaEncoder, VBDecoder, VBEncoder};
+    use arrow_array::{UInt64Array, UInt8Array};
+
+    fn roundtrip(array: &UInt64Array) -> UInt64Array {
+        let inner = GammaEncoder::new(Cursor::new(vec![]));
+        let result = encode_primitive_array(Cursor::new(vec![]), inner, array)
+            .unwrap()
+            .into_inner();
+        decode_primitive_array(Cursor::new(result), GammaDecoder::new).unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let dense = UInt64Array::from_iter_values((1..1000).map(|i| i * i));
+        let decoded = roundtrip(&dense);
+        assert_eq!(decoded, dense);
+        assert!(decoded.nulls().is_none());
+
+        let sparse: UInt64Array = (0..1000_u64)
+            .map(|i| (i % 3 != 0).then_some(i + 1))
+            .collect();
+        let decoded = roundtrip(&sparse);
+        assert_eq!(decoded, sparse);
+        assert_eq!(decoded.null_count(), 334);
+
+        assert_eq!(roundtrip(&UInt64Array::from(Vec::<u64>::new())).len(), 0);
+        assert_eq!(roundtrip(&UInt64Array::new_null(5)).null_count(), 5);
+    }
+
+    #[test]
+    fn test_sliced() {
+        // A slice of an array keeps the offsets of its buffers.
+        let array: UInt64Array = (0..100_u64).map(|i| (i % 10 != 0).then_some(i)).collect();
+        let slice = array.slice(15, 30);
+        assert_eq!(roundtrip(&slice), slice);
+    }
+
+    #[test]
+    fn test_errs() {
+        let array = UInt64Array::from(vec![Some(300), None]);
+        let inner = VBEncoder::new(Cursor::new(vec![]));
+        let result = encode_primitive_array(Cursor::new(vec![]), inner, &array)
+            .unwrap()
+            .into_inner();
+        let decoded: Result<UInt8Array, _> =
+            decode_primitive_array(Cursor::new(result), VBDecoder::new);
+        assert!(decoded.is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeBytes, Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The widths, in bytes, of the chunks, indexed by the number of leading
+/// 1-bits of their first byte.
+const WIDTHS: [usize; 5] = [1, 2, 4, 8, 9];
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// byte-aligned chunks of 1, 2, 4 or 8 bytes.
+///
+/// Like in UTF-8, the number of leading 1-bits of the first byte of a chunk,
+/// terminated by a 0-bit, selects its total width, and the rest of the bits of
+/// the chunk are the big-endian payload:
+///
+/// | First byte | Width   | Payload bits |
+/// |------------|---------|--------------|
+/// | `0xxxxxxx` | 1 byte  | 7            |
+/// | `10xxxxxx` | 2 bytes | 14           |
+/// | `110xxxxx` | 4 bytes | 29           |
+/// | `1110xxxx` | 8 bytes | 60           |
+/// | `11110000` | 9 bytes | 64           |
+///
+/// The last row is an escape for the integers that do not fit in 60 bits. Since
+/// the width of a chunk is known from its first byte, decoding needs a single
+/// branch per integer, instead of one per byte as in Variable Byte Encoding.
+///
+/// For example, the number 300 needs 9 bits, so it is encoded in 2 bytes as
+/// 10000001 00101100.
+pub struct ChunkEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> ChunkEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        ChunkEncoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for ChunkEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let encoded = self.writer.get_mut();
+        let mut bytes = vec![];
+
+        for num in nums {
+            bytes.clear();
+            write_chunk_bytes(num.to_u64(), &mut bytes);
+            encoded.extend_from_byte_slice(&bytes);
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for ChunkEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Calculates the bytes of the smallest chunk that fits a number.
+fn write_chunk_bytes(num: u64, buffer: &mut Vec<u8>) {
+    let (prefix, width) = match num {
+        n if n < 1 << 7 => (0x00, 1),
+        n if n < 1 << 14 => (0x80, 2),
+        n if n < 1 << 29 => (0xC0, 4),
+        n if n < 1 << 60 => (0xE0, 8),
+        _ => {
+            buffer.push(0xF0);
+            buffer.extend_from_slice(&num.to_be_bytes());
+            return;
+        }
+    };
+    let bytes = num.to_be_bytes();
+    let start = buffer.len();
+    buffer.extend_from_slice(&bytes[8 - width..]);
+    buffer[start] |= prefix;
+}
+
+/// A structure that wraps a reader and decodes a sequence of integers encoded
+/// in byte-aligned chunks of 1, 2, 4 or 8 bytes.
+///
+/// The number of leading 1-bits of the first byte of each chunk selects its
+/// width, as described in [`ChunkEncoder`].
+pub struct ChunkDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> ChunkDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        ChunkDecoder { reader }
+    }
+}
+
+impl DecodeBytes for ChunkDecoder<()> {
+    /// Reads the bytes of the chunk from the bit offset, which does not have
+    /// to be at the start of a byte.
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let first = bits.read_bits(8).ok_or(InvalidCodeError::ChunkCodeError)? as u8;
+        let prefix_len = first.leading_ones() as usize;
+        let width = *WIDTHS
+            .get(prefix_len)
+            .ok_or(InvalidCodeError::ChunkCodeError)?;
+        if prefix_len == 4 && first != 0xF0 {
+            return Err(InvalidCodeError::ChunkCodeError);
+        }
+
+        let mut n = u64::from(first & (0x7F >> prefix_len));
+        for _ in 1..width {
+            let byte = bits.read_bits(8).ok_or(InvalidCodeError::ChunkCodeError)?;
+            n = (n << 8) | byte;
+        }
+        let num = T::from_u64(n).ok_or(InvalidCodeError::ChunkCodeError)?;
+        Ok((num, 8 * width))
+    }
+}
+
+impl<R: Read> Decoder<R> for ChunkDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+
+        let mut nums = vec![];
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let first = bytes[pos];
+            let prefix_len = first.leading_ones() as usize;
+            let width = *WIDTHS
+                .get(prefix_len)
+                .ok_or(InvalidCodeError::ChunkCodeError)?;
+            if prefix_len == 4 && first != 0xF0 {
+                return Err(InvalidCodeError::ChunkCodeError);
+            }
+            let chunk = bytes
+                .get(pos..pos + width)
+                .ok_or(InvalidCodeError::ChunkCodeError)?;
+
+            let mut n = u64::from(first & (0x7F >> prefix_len));
+            for byte in &chunk[1..] {
+                n = (n << 8) | u64::from(*byte);
+            }
+            nums.push(T::from_u64(n).ok_or(InvalidCodeError::ChunkCodeError)?);
+            pos += width;
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let mut enc = ChunkEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<ChunkDecoder<()>>(&bytes, &nums);
+        assert!(ChunkDecoder::decode_one_from_bytes::<u64>(&[0xF8], 0).is_err());
+        assert!(ChunkDecoder::decode_one_from_bytes::<u64>(&[0xC0, 0x00], 0).is_err());
+    }
+
+    fn encode<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = ChunkEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_widths() {
+        assert_eq!(encode(&[5_u32]), vec![0x05]);
+        assert_eq!(encode(&[300_u32]), vec![0x81, 0x2C]);
+        assert_eq!(encode(&[1_u32 << 14]), vec![0xC0, 0x00, 0x40, 0x00]);
+        assert_eq!(encode(&[1_u64 << 29]).len(), 8);
+        assert_eq!(encode(&[(1_u64 << 60) - 1])[0], 0xEF);
+        assert_eq!(
+            encode(&[u64::MAX]),
+            vec![0xF0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [
+            0_u64,
+            127,
+            128,
+            16383,
+            16384,
+            1 << 40,
+            (1 << 60) - 1,
+            1 << 60,
+            u64::MAX,
+        ];
+        let dec = ChunkDecoder::new(Cursor::new(encode(&nums)));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let dec = ChunkDecoder::new(Cursor::new(vec![0x05, 0x81]));
+        assert!(dec.decode::<u32>().is_err());
+
+        let dec = ChunkDecoder::new(Cursor::new(vec![0xF8, 0x00]));
+        assert!(dec.decode::<u32>().is_err());
+
+        let dec = ChunkDecoder::new(Cursor::new(encode(&[300_u32])));
+        assert!(dec.decode::<u8>().is_err());
+    }
+}
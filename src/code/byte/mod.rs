@@ -0,0 +1,7 @@
+pub mod chunk;
+pub mod nibble;
+pub mod prefix_varint;
+pub mod sqlite_varint;
+pub mod stream_vbyte;
+pub mod vargroup;
+pub mod vb16;
@@ -0,0 +1,249 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// the nibble code of WebGraph.
+///
+/// It works like Variable Byte Encoding, but with 4-bit nibbles instead of
+/// bytes: the last 3 bits of a nibble are payload, and encode part of the
+/// integer, starting from the most significant group, while the first bit is
+/// set to 1 for the last nibble of the integer and to 0 otherwise. Small
+/// integers take half the bits of a byte code, and the codes are still
+/// aligned to nibbles, so they are faster to decode than bit-level codes
+/// like Elias Gamma.
+///
+/// For example, 0 is encoded as 1000, and 13, or 1 101 in binary, as
+/// 0001 1101.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, NibbleDecoder, NibbleEncoder};
+///
+/// let mut enc = NibbleEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[13_u32, 0]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b00011101, 0b10001000]);
+///
+/// let dec = NibbleDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![13, 0]);
+/// ```
+pub struct NibbleEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> NibbleEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        NibbleEncoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for NibbleEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = vec![];
+        for num in nums {
+            write_nibble_bits(num.to_u64(), &mut bits);
+        }
+        self.writer.write_bits(&bits)
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for NibbleEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl EncodeOne for NibbleEncoder<()> {
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut bits = vec![];
+        write_nibble_bits(num.to_u64(), &mut bits);
+        bits
+    }
+}
+
+/// Calculates the nibbles of a number, starting from the most significant
+/// 3-bit group, with the termination bit set on the last nibble.
+fn write_nibble_bits(num: u64, buffer: &mut Vec<bool>) {
+    let n_groups = (u64::BITS - num.leading_zeros()).div_ceil(3).max(1);
+    for group in (0..n_groups).rev() {
+        buffer.push(group == 0);
+        let nibble = num >> (3 * group);
+        buffer.extend((0..3).rev().map(|i| (nibble >> i) & 1 == 1));
+    }
+}
+
+/// Decodes the nibbles of a number read from a source of bits, returning it
+/// along with the number of bits they occupy.
+fn read_nibbles(
+    mut read_nibble: impl FnMut() -> Option<u64>,
+) -> Result<(u64, usize), InvalidCodeError> {
+    let mut num = 0_u64;
+    let mut len = 0;
+    loop {
+        let nibble = read_nibble().ok_or(InvalidCodeError::NibbleCodeError)?;
+        len += 4;
+        if num > u64::MAX >> 3 {
+            return Err(InvalidCodeError::NibbleCodeError);
+        }
+        num = (num << 3) | (nibble & 0b111);
+        if nibble & 0b1000 != 0 {
+            return Ok((num, len));
+        }
+    }
+}
+
+/// A structure that wraps a reader and decodes a sequence of integers encoded
+/// with the nibble code of WebGraph.
+///
+/// The last 3 bits of each nibble are payload, while the first bit marks the
+/// last nibble of an integer.
+pub struct NibbleDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> NibbleDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        NibbleDecoder { cursor }
+    }
+}
+
+impl DecodePrefix for NibbleDecoder<()> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        let mut nibbles = bits.chunks(4);
+        let (num, len) = read_nibbles(|| {
+            let nibble = nibbles.next().filter(|nibble| nibble.len() == 4)?;
+            Some(
+                nibble
+                    .iter()
+                    .fold(0, |acc, bit| (acc << 1) | u64::from(*bit)),
+            )
+        })?;
+        let num = T::from_u64(num).ok_or(InvalidCodeError::NibbleCodeError)?;
+        Ok((num, len))
+    }
+}
+
+impl DecodeOne for NibbleDecoder<()> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::NibbleCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl DecodeBytes for NibbleDecoder<()> {
+    /// Reads the nibbles of the code from the bit offset, which does not have
+    /// to be at the start of a nibble.
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let (num, len) = read_nibbles(|| bits.read_bits(4))?;
+        let num = T::from_u64(num).ok_or(InvalidCodeError::NibbleCodeError)?;
+        Ok((num, len))
+    }
+}
+
+impl<R: Read> Decoder<R> for NibbleDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self.cursor.decode_with(NibbleDecoder::decode_prefix)?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::{GammaEncoder, VBEncoder};
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = NibbleEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_encode_one() {
+        let bits = |s: &str| s.chars().map(|c| c == '1').collect::<Vec<_>>();
+        assert_eq!(NibbleEncoder::encode_one(0_u8), bits("1000"));
+        assert_eq!(NibbleEncoder::encode_one(7_u8), bits("1111"));
+        assert_eq!(NibbleEncoder::encode_one(8_u8), bits("00011000"));
+        assert_eq!(NibbleEncoder::encode_one(u64::MAX).len(), 22 * 4);
+        for num in [0, 1, 8, 63, 64, 1 << 40, u64::MAX] {
+            let bits = NibbleEncoder::encode_one(num);
+            assert_eq!(NibbleDecoder::decode_one(&bits), Ok(num));
+        }
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let bytes = encode(&nums);
+        assert_decodes_from_bytes::<NibbleDecoder<()>>(&bytes, &nums);
+        assert!(NibbleDecoder::decode_one_from_bytes::<u64>(&[0x01], 0).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..2000).chain([0, u64::MAX]).collect();
+        let dec = NibbleDecoder::new(Cursor::new(encode(&nums)));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        // Between Elias Gamma and Variable Byte Encoding for small gaps.
+        let gaps: Vec<u64> = (1..1000).map(|i| i % 7 + 1).collect();
+        let mut gamma = GammaEncoder::new(Cursor::new(vec![]));
+        gamma.encode(&gaps).unwrap();
+        let mut vb = VBEncoder::new(Cursor::new(vec![]));
+        vb.encode(&gaps).unwrap();
+        let len = encode(&gaps).len();
+        assert!(gamma.finalize().unwrap().into_inner().len() < len);
+        assert!(len < vb.finalize().unwrap().into_inner().len());
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let dec = NibbleDecoder::new(Cursor::new(vec![0b00011000]));
+        assert_eq!(dec.decode::<u64>(), Err(InvalidCodeError::NibbleCodeError));
+        let dec = NibbleDecoder::new(Cursor::new(encode(&[300])));
+        assert!(dec.decode::<u8>().is_err());
+        // 23 nibbles hold more than 64 bits.
+        let mut bits = vec![false; 22 * 4];
+        bits.extend([true; 4]);
+        for nibble in bits.chunks_mut(4) {
+            nibble[1] = true;
+        }
+        assert!(NibbleDecoder::decode_one::<u64>(&bits).is_err());
+    }
+}
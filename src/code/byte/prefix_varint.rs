@@ -0,0 +1,242 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeBytes, Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// prefix varints of 1 to 9 bytes.
+///
+/// The number of bytes that follow the first byte of a varint is written in
+/// unary in its first byte, as leading 1-bits terminated by a 0-bit, and the
+/// rest of the bits of the varint are the big-endian payload, so each byte
+/// adds 7 payload bits. With 8 leading 1-bits, the first byte holds no payload,
+/// and the 8 bytes that follow hold a whole 64-bit integer:
+///
+/// | First byte | Width   | Payload bits |
+/// |------------|---------|--------------|
+/// | `0xxxxxxx` | 1 byte  | 7            |
+/// | `10xxxxxx` | 2 bytes | 14           |
+/// | `110xxxxx` | 3 bytes | 21           |
+/// | ...        | ...     | ...          |
+/// | `11111110` | 8 bytes | 56           |
+/// | `11111111` | 9 bytes | 64           |
+///
+/// It takes as many bytes as Variable Byte Encoding for integers of up to 63
+/// bits, and one fewer for 64-bit ones. Since the width of a varint is known
+/// from its first byte, decoding needs a single branch per integer. Unlike
+/// [`ChunkEncoder`](crate::ChunkEncoder), every width from 1 to 9 bytes is
+/// used.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, PrefixVarintDecoder, PrefixVarintEncoder};
+///
+/// let mut enc = PrefixVarintEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[5_u32, 300, 1 << 14]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0x05, 0x81, 0x2C, 0xC0, 0x40, 0x00]);
+///
+/// let dec = PrefixVarintDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![5, 300, 1 << 14]);
+/// ```
+pub struct PrefixVarintEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> PrefixVarintEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        PrefixVarintEncoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for PrefixVarintEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let encoded = self.writer.get_mut();
+        let mut bytes = vec![];
+
+        for num in nums {
+            bytes.clear();
+            write_prefix_varint_bytes(num.to_u64(), &mut bytes);
+            encoded.extend_from_byte_slice(&bytes);
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for PrefixVarintEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Calculates the bytes of the shortest prefix varint of a number.
+fn write_prefix_varint_bytes(num: u64, buffer: &mut Vec<u8>) {
+    let n_bits = (u64::BITS - num.leading_zeros()).max(1);
+    if n_bits > 56 {
+        buffer.push(0xFF);
+        buffer.extend_from_slice(&num.to_be_bytes());
+        return;
+    }
+    let width = n_bits.div_ceil(7) as usize;
+    let bytes = num.to_be_bytes();
+    let start = buffer.len();
+    buffer.extend_from_slice(&bytes[8 - width..]);
+    buffer[start] |= !(0xFF >> (width - 1));
+}
+
+/// Returns the width, in bytes, of the varint that starts with a byte, along
+/// with the payload bits of the byte.
+fn split_first_byte(first: u8) -> (usize, u64) {
+    let n_more = first.leading_ones();
+    let payload = first & 0xFF_u8.checked_shr(n_more + 1).unwrap_or(0);
+    (n_more as usize + 1, u64::from(payload))
+}
+
+/// A structure that wraps a reader and decodes a sequence of integers encoded
+/// as prefix varints.
+///
+/// The number of leading 1-bits of the first byte of each varint is the
+/// number of bytes that follow it, as described in [`PrefixVarintEncoder`].
+pub struct PrefixVarintDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> PrefixVarintDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        PrefixVarintDecoder { reader }
+    }
+}
+
+impl DecodeBytes for PrefixVarintDecoder<()> {
+    /// Reads the bytes of the varint from the bit offset, which does not have
+    /// to be at the start of a byte.
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let first = bits
+            .read_bits(8)
+            .ok_or(InvalidCodeError::PrefixVarintCodeError)? as u8;
+        let (width, mut n) = split_first_byte(first);
+        for _ in 1..width {
+            let byte = bits
+                .read_bits(8)
+                .ok_or(InvalidCodeError::PrefixVarintCodeError)?;
+            n = (n << 8) | byte;
+        }
+        let num = T::from_u64(n).ok_or(InvalidCodeError::PrefixVarintCodeError)?;
+        Ok((num, 8 * width))
+    }
+}
+
+impl<R: Read> Decoder<R> for PrefixVarintDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self
+            .reader
+            .read_to_end()
+            .map_err(|_| InvalidCodeError::PrefixVarintCodeError)?
+            .into_bytes();
+
+        let mut nums = vec![];
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let (width, mut n) = split_first_byte(bytes[pos]);
+            let varint = bytes
+                .get(pos..pos + width)
+                .ok_or(InvalidCodeError::PrefixVarintCodeError)?;
+            for byte in &varint[1..] {
+                n = (n << 8) | u64::from(*byte);
+            }
+            nums.push(T::from_u64(n).ok_or(InvalidCodeError::PrefixVarintCodeError)?);
+            pos += width;
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::VBEncoder;
+    use std::io::Cursor;
+
+    fn encode<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = PrefixVarintEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_widths() {
+        assert_eq!(encode(&[0_u32]), vec![0x00]);
+        assert_eq!(encode(&[127_u32]), vec![0x7F]);
+        assert_eq!(encode(&[1_u32 << 20]), vec![0xD0, 0x00, 0x00]);
+        assert_eq!(encode(&[(1_u64 << 56) - 1])[..2], [0xFE, 0xFF]);
+        assert_eq!(encode(&[1_u64 << 56])[..2], [0xFF, 0x01]);
+        for width in 1..=8 {
+            let max = (1_u64 << (7 * width)) - 1;
+            assert_eq!(encode(&[max]).len(), width as usize);
+            assert_eq!(encode(&[max + 1]).len(), width as usize + 1);
+        }
+        assert_eq!(split_first_byte(0xFF), (9, 0));
+        assert_eq!(split_first_byte(0xBF), (2, 0x3F));
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let bytes = encode(&nums);
+
+        let end = assert_decodes_from_bytes::<PrefixVarintDecoder<()>>(&bytes, &nums);
+        assert_eq!(end, 8 * bytes.len());
+        assert!(PrefixVarintDecoder::decode_one_from_bytes::<u64>(&[0xC0, 0x00], 0).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 127, 128, 16383, 16384, 1 << 40, 1 << 56, u64::MAX];
+        let dec = PrefixVarintDecoder::new(Cursor::new(encode(&nums)));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        // As long as Variable Byte Encoding, but shorter for 64-bit integers.
+        let mut vb = VBEncoder::new(Cursor::new(vec![]));
+        vb.encode(&nums).unwrap();
+        let vb = vb.finalize().unwrap().into_inner();
+        assert_eq!(encode(&nums).len() + 1, vb.len());
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let dec = PrefixVarintDecoder::new(Cursor::new(vec![0x05, 0x81]));
+        assert_eq!(
+            dec.decode::<u32>(),
+            Err(InvalidCodeError::PrefixVarintCodeError)
+        );
+        let dec = PrefixVarintDecoder::new(Cursor::new(vec![0xFF; 8]));
+        assert!(dec.decode::<u64>().is_err());
+
+        let dec = PrefixVarintDecoder::new(Cursor::new(encode(&[300_u32])));
+        assert!(dec.decode::<u8>().is_err());
+    }
+}
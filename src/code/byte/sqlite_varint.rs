@@ -0,0 +1,243 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeBytes, Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The largest number whose varint fits in 8 bytes, of 7 payload bits each.
+const MAX_SHORT: u64 = (1 << 56) - 1;
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using the variable-length integers of the SQLite file format.
+///
+/// A varint takes 1 to 9 bytes, starting from the most significant group.
+/// Each of the first 8 bytes holds 7 payload bits, and its first bit is set
+/// if more bytes follow. A 9th byte holds 8 payload bits, so any 64-bit
+/// integer fits in 9 bytes, instead of the 10 bytes of Variable Byte Encoding.
+///
+/// Unlike [`VBEncoder`](crate::VBEncoder), the continuation bit is set on the
+/// bytes before the last one, rather than on the last one.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, SqliteVarintDecoder, SqliteVarintEncoder};
+///
+/// let mut enc = SqliteVarintEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[5_u64, 300, u64::MAX]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result[..3], [0x05, 0x82, 0x2C]);
+/// assert_eq!(result[3..], [0xFF; 9]);
+///
+/// let dec = SqliteVarintDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u64>().unwrap(), vec![5, 300, u64::MAX]);
+/// ```
+pub struct SqliteVarintEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> SqliteVarintEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        SqliteVarintEncoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for SqliteVarintEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let encoded = self.writer.get_mut();
+        let mut bytes = vec![];
+        for num in nums {
+            bytes.clear();
+            write_sqlite_varint_bytes(num.to_u64(), &mut bytes);
+            encoded.extend_from_byte_slice(&bytes);
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for SqliteVarintEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Calculates the bytes of the SQLite varint of a number.
+fn write_sqlite_varint_bytes(num: u64, buffer: &mut Vec<u8>) {
+    let (high, last) = match num > MAX_SHORT {
+        true => (num >> 8, Some(num as u8)),
+        false => (num, None),
+    };
+    let n_groups = match last {
+        Some(_) => 8,
+        None => (u64::BITS - high.leading_zeros()).div_ceil(7).max(1),
+    };
+    for i in (0..n_groups).rev() {
+        let group = ((high >> (7 * i)) & 0x7F) as u8;
+        let more = i > 0 || last.is_some();
+        buffer.push(group | if more { 0x80 } else { 0 });
+    }
+    buffer.extend(last);
+}
+
+/// Reads a SQLite varint from a source of bytes, returning `None` if the
+/// bytes run out before it ends.
+fn read_sqlite_varint(mut next_byte: impl FnMut() -> Option<u8>) -> Option<u64> {
+    let mut num = 0_u64;
+    for _ in 0..8 {
+        let byte = next_byte()?;
+        num = (num << 7) | u64::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            return Some(num);
+        }
+    }
+    Some((num << 8) | u64::from(next_byte()?))
+}
+
+/// A structure that wraps a reader and decodes a sequence of integers
+/// encoded as SQLite varints.
+pub struct SqliteVarintDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> SqliteVarintDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        SqliteVarintDecoder { reader }
+    }
+}
+
+impl DecodeBytes for SqliteVarintDecoder<()> {
+    /// Reads the bytes of the varint from the bit offset, which does not have
+    /// to be at the start of a byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{DecodeBytes, SqliteVarintDecoder};
+    ///
+    /// // A record header of a SQLite page: its size, and two serial types.
+    /// let header = [0x03, 0x81, 0x11, 0x01];
+    /// let (size, len) = SqliteVarintDecoder::decode_one_from_bytes::<u64>(&header, 0).unwrap();
+    /// assert_eq!((size, len), (3, 8));
+    /// let serial = SqliteVarintDecoder::decode_one_from_bytes::<u64>(&header, len);
+    /// assert_eq!(serial, Ok((145, 16)));
+    /// ```
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let num = read_sqlite_varint(|| bits.read_bits(8).map(|byte| byte as u8))
+            .ok_or(InvalidCodeError::SqliteVarintCodeError)?;
+        let num = T::from_u64(num).ok_or(InvalidCodeError::SqliteVarintCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl<R: Read> Decoder<R> for SqliteVarintDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self
+            .reader
+            .read_to_end()
+            .map_err(|_| InvalidCodeError::SqliteVarintCodeError)?
+            .into_bytes();
+
+        let mut nums = vec![];
+        let mut bytes = bytes.into_iter().peekable();
+        while bytes.peek().is_some() {
+            let num = read_sqlite_varint(|| bytes.next())
+                .and_then(T::from_u64)
+                .ok_or(InvalidCodeError::SqliteVarintCodeError)?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use std::io::Cursor;
+
+    fn varint(num: u64) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_sqlite_varint_bytes(num, &mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        assert_eq!(varint(0), vec![0x00]);
+        assert_eq!(varint(127), vec![0x7F]);
+        assert_eq!(varint(128), vec![0x81, 0x00]);
+        assert_eq!(varint(MAX_SHORT).len(), 8);
+        assert_eq!(
+            varint(MAX_SHORT + 1),
+            vec![0x80, 0xC0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]
+        );
+        for num in [0, 1, 300, 1 << 35, MAX_SHORT, MAX_SHORT + 1, u64::MAX] {
+            let bytes = varint(num);
+            let mut iter = bytes.iter().copied();
+            assert_eq!(read_sqlite_varint(|| iter.next()), Some(num));
+            assert_eq!(iter.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let mut enc = SqliteVarintEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        let end = assert_decodes_from_bytes::<SqliteVarintDecoder<()>>(&bytes, &nums);
+        assert_eq!(end, 8 * bytes.len());
+        // A varint that starts in the middle of a byte.
+        let bytes = [0b0000_1000, 0b0001_0110, 0b0000_0000];
+        assert_eq!(
+            SqliteVarintDecoder::decode_one_from_bytes::<u64>(&bytes, 4),
+            Ok((1 << 7 | 0x60, 16))
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 240, 2287, 67823, MAX_SHORT, MAX_SHORT + 1, u64::MAX];
+        let mut enc = SqliteVarintEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(result.len(), 1 + 2 + 2 + 3 + 8 + 9 + 9);
+
+        let dec = SqliteVarintDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let dec = SqliteVarintDecoder::new(Cursor::new(vec![0x05, 0x82]));
+        assert_eq!(
+            dec.decode::<u64>(),
+            Err(InvalidCodeError::SqliteVarintCodeError)
+        );
+        let dec = SqliteVarintDecoder::new(Cursor::new(vec![0x82, 0x2C]));
+        assert!(dec.decode::<u8>().is_err());
+        assert!(SqliteVarintDecoder::decode_one_from_bytes::<u64>(&[0xFF; 8], 0).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The number of integers whose lengths share a control byte.
+const QUAD: usize = 4;
+
+/// The number of data bytes of the integers of each control byte.
+#[cfg_attr(
+    not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))),
+    allow(dead_code)
+)]
+const DATA_LENS: [u8; 256] = data_lens();
+
+/// The shuffle masks that spread the data bytes of the integers of each
+/// control byte to four 32-bit words. The indices of the bytes that are left
+/// at zero are out of the range of both the SSSE3 and the NEON table lookups.
+#[cfg_attr(
+    not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))),
+    allow(dead_code)
+)]
+const SHUFFLE_MASKS: [[u8; 16]; 256] = shuffle_masks();
+
+/// A structure that wraps a writer and encodes a sequence of 32-bit integers
+/// using Stream VByte.
+///
+/// Each integer is written in the fewest little-endian bytes that hold it,
+/// from 1 to 4, and its length, minus one, is written in 2 bits of a separate
+/// stream of control bytes, four integers per byte, starting from the lowest
+/// bits. Since the lengths are not mixed with the data, the integers of a
+/// control byte can be decoded at once: with the `simd` feature, on x86-64
+/// processors with SSSE3 and on AArch64 processors with NEON, a shuffle of 16
+/// data bytes, looked up by the control byte, spreads them to their four
+/// words; otherwise, a scalar kernel is used.
+///
+/// The control stream must be complete before the data stream can follow it,
+/// so the integers are buffered, and both streams are written when the
+/// encoder is finalized. The stream starts with the number of integers in
+/// Variable Byte Encoding.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, StreamVByteDecoder, StreamVByteEncoder};
+///
+/// let mut enc = StreamVByteEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[1_u32, 300, 70000, 5]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(
+///     result,
+///     vec![0x84, 0b00_10_01_00, 0x01, 0x2C, 0x01, 0x70, 0x11, 0x01, 0x05]
+/// );
+///
+/// let dec = StreamVByteDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![1, 300, 70000, 5]);
+/// ```
+pub struct StreamVByteEncoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<u32>,
+}
+
+impl<W: Write> StreamVByteEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        StreamVByteEncoder {
+            writer,
+            nums: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for StreamVByteEncoder<W> {
+    /// Buffers a sequence of numbers, which are written when the encoder is
+    /// finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in 32 bits.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            let num = u32::try_from(num.to_u64()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Number wider than 32 bits.")
+            })?;
+            self.nums.push(num);
+        }
+        Ok(())
+    }
+
+    /// Writes the control stream, followed by the data stream.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+
+        let mut data = vec![];
+        for quad in self.nums.chunks(QUAD) {
+            let mut control = 0;
+            for (i, num) in quad.iter().enumerate() {
+                let len = byte_len(*num);
+                control |= ((len - 1) as u8) << (2 * i);
+                data.extend_from_slice(&num.to_le_bytes()[..len]);
+            }
+            bytes.push(control);
+        }
+        bytes.append(&mut data);
+
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// Returns the number of bytes that hold a number, which is at least 1.
+fn byte_len(num: u32) -> usize {
+    ((u32::BITS - num.leading_zeros()).div_ceil(8) as usize).max(1)
+}
+
+/// Returns the length of the data of the integer at index `i` of a control
+/// byte.
+const fn data_len(control: u8, i: usize) -> usize {
+    ((control >> (2 * i)) & 0b11) as usize + 1
+}
+
+/// Calculates the number of data bytes of each control byte.
+#[cfg_attr(not(all(feature = "simd", target_arch = "x86_64")), allow(dead_code))]
+const fn data_lens() -> [u8; 256] {
+    let mut lens = [0; 256];
+    let mut control = 0;
+    while control < 256 {
+        let mut i = 0;
+        while i < QUAD {
+            lens[control] += data_len(control as u8, i) as u8;
+            i += 1;
+        }
+        control += 1;
+    }
+    lens
+}
+
+/// Calculates the shuffle mask of each control byte, which moves the `j`-th
+/// byte of the data of the `i`-th integer to byte `4 i + j`, and sets the
+/// bytes past its length to zero, with the high bit of their index.
+const fn shuffle_masks() -> [[u8; 16]; 256] {
+    let mut masks = [[0x80; 16]; 256];
+    let mut control = 0;
+    while control < 256 {
+        let mut offset = 0;
+        let mut i = 0;
+        while i < QUAD {
+            let len = data_len(control as u8, i);
+            let mut j = 0;
+            while j < len {
+                masks[control][4 * i + j] = (offset + j) as u8;
+                j += 1;
+            }
+            offset += len;
+            i += 1;
+        }
+        control += 1;
+    }
+    masks
+}
+
+/// Decodes the integers of the control bytes from the data starting at `pos`,
+/// one integer at a time, returning the position past their data, or `None`
+/// if the data is too short.
+fn decode_quads_scalar(
+    controls: &[u8],
+    data: &[u8],
+    mut pos: usize,
+    out: &mut Vec<u32>,
+) -> Option<usize> {
+    for &control in controls {
+        for i in 0..QUAD {
+            let len = data_len(control, i);
+            let mut word = [0; 4];
+            word[..len].copy_from_slice(data.get(pos..pos + len)?);
+            out.push(u32::from_le_bytes(word));
+            pos += len;
+        }
+    }
+    Some(pos)
+}
+
+/// Decodes the integers of the control bytes, four at a time, for as long as
+/// 16 data bytes can be loaded, returning the number of control bytes decoded
+/// and the position past their data.
+///
+/// # Safety
+///
+/// The CPU must support SSSE3.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+unsafe fn decode_quads_ssse3(controls: &[u8], data: &[u8], out: &mut Vec<u32>) -> (usize, usize) {
+    use std::arch::x86_64::*;
+
+    let mut pos = 0;
+    let mut decoded = 0;
+    for &control in controls {
+        if pos + 16 > data.len() {
+            break;
+        }
+        let mut words = [0_u32; QUAD];
+        // SAFETY: The 16 bytes loaded from `pos` are within `data`, and the
+        // store writes the 16 bytes of `words`.
+        unsafe {
+            let bytes = _mm_loadu_si128(data.as_ptr().add(pos) as *const __m128i);
+            let mask = _mm_loadu_si128(SHUFFLE_MASKS[control as usize].as_ptr() as *const __m128i);
+            let shuffled = _mm_shuffle_epi8(bytes, mask);
+            _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, shuffled);
+        }
+        out.extend_from_slice(&words);
+        pos += DATA_LENS[control as usize] as usize;
+        decoded += 1;
+    }
+    (decoded, pos)
+}
+
+/// Decodes the integers of the control bytes, four at a time, for as long as
+/// 16 data bytes can be loaded, returning the number of control bytes decoded
+/// and the position past their data.
+///
+/// # Safety
+///
+/// The CPU must support NEON.
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+#[target_feature(enable = "neon")]
+unsafe fn decode_quads_neon(controls: &[u8], data: &[u8], out: &mut Vec<u32>) -> (usize, usize) {
+    use std::arch::aarch64::*;
+
+    let mut pos = 0;
+    let mut decoded = 0;
+    for &control in controls {
+        if pos + 16 > data.len() {
+            break;
+        }
+        let mut words = [0_u32; QUAD];
+        // SAFETY: The 16 bytes loaded from `pos` are within `data`, and the
+        // store writes the 16 bytes of `words`.
+        unsafe {
+            let bytes = vld1q_u8(data.as_ptr().add(pos));
+            let mask = vld1q_u8(SHUFFLE_MASKS[control as usize].as_ptr());
+            let shuffled = vqtbl1q_u8(bytes, mask);
+            vst1q_u8(words.as_mut_ptr() as *mut u8, shuffled);
+        }
+        out.extend_from_slice(&words);
+        pos += DATA_LENS[control as usize] as usize;
+        decoded += 1;
+    }
+    (decoded, pos)
+}
+
+/// Decodes the integers of the control bytes with the fastest available
+/// kernel, returning the position past their data, or `None` if the data is
+/// too short.
+fn decode_quads(controls: &[u8], data: &[u8], out: &mut Vec<u32>) -> Option<usize> {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("ssse3") {
+        // SAFETY: The CPU supports SSSE3.
+        let (decoded, pos) = unsafe { decode_quads_ssse3(controls, data, out) };
+        return decode_quads_scalar(&controls[decoded..], data, pos, out);
+    }
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        // SAFETY: The CPU supports NEON.
+        let (decoded, pos) = unsafe { decode_quads_neon(controls, data, out) };
+        return decode_quads_scalar(&controls[decoded..], data, pos, out);
+    }
+    decode_quads_scalar(controls, data, 0, out)
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// Stream VByte.
+pub struct StreamVByteDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> StreamVByteDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        StreamVByteDecoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for StreamVByteDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::StreamVByteCodeError;
+        let bytes = self.reader.read_to_end().map_err(|_| err())?.into_bytes();
+        let (count, pos) = decode_vb_prefix(&bytes).map_err(|_| err())?;
+        let count = usize::try_from(count).map_err(|_| err())?;
+
+        let controls = bytes.get(pos..pos + count.div_ceil(QUAD)).ok_or_else(err)?;
+        let data = &bytes[pos + controls.len()..];
+        let (full, last) = controls.split_at(count / QUAD);
+
+        let mut words = Vec::with_capacity(count);
+        let mut pos = decode_quads(full, data, &mut words).ok_or_else(err)?;
+        if let Some(&control) = last.first() {
+            let n = count % QUAD;
+            // The lengths of the missing integers must be left at zero.
+            if control >> (2 * n) != 0 {
+                return Err(err());
+            }
+            for i in 0..n {
+                let len = data_len(control, i);
+                let mut word = [0; 4];
+                word[..len].copy_from_slice(data.get(pos..pos + len).ok_or_else(err)?);
+                words.push(u32::from_le_bytes(word));
+                pos += len;
+            }
+        }
+        if pos != data.len() {
+            return Err(err());
+        }
+
+        words
+            .into_iter()
+            .map(|num| T::from_u64(u64::from(num)).ok_or_else(err))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = StreamVByteEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u32>, InvalidCodeError> {
+        StreamVByteDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(DATA_LENS[0], 4);
+        assert_eq!(DATA_LENS[0xFF], 16);
+        assert_eq!(DATA_LENS[0b00_10_01_00], 7);
+        assert_eq!(
+            SHUFFLE_MASKS[0b00_10_01_00],
+            [0, 128, 128, 128, 1, 2, 128, 128, 3, 4, 5, 128, 6, 128, 128, 128]
+        );
+        assert_eq!(
+            SHUFFLE_MASKS[0xFF],
+            std::array::from_fn::<u8, 16, _>(|i| i as u8)
+        );
+    }
+
+    #[test]
+    fn test_kernels_agree() {
+        let nums: Vec<u32> = (0..1000_u64)
+            .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15) >> (32 + i % 32)) as u32)
+            .collect();
+        let bytes = encode(&nums);
+        let controls = &bytes[2..2 + 250];
+        let data = &bytes[2 + 250..];
+
+        let mut scalar = vec![];
+        let pos = decode_quads_scalar(controls, data, 0, &mut scalar);
+        assert_eq!(pos, Some(data.len()));
+        assert_eq!(scalar, nums);
+
+        let mut fastest = vec![];
+        assert_eq!(decode_quads(controls, data, &mut fastest), pos);
+        assert_eq!(fastest, nums);
+        assert_eq!(
+            decode_quads(controls, &data[..data.len() - 1], &mut vec![]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for len in [0, 1, 3, 4, 5, 17, 1001] {
+            let nums: Vec<u32> = (0..len as u64)
+                .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15) >> (32 + i % 32)) as u32)
+                .collect();
+            assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        }
+        let nums = [0_u64, 255, 256, 65535, 65536, u32::MAX.into()];
+        assert_eq!(byte_len(0), 1);
+        assert_eq!(encode(&nums).len(), 1 + 2 + 1 + 1 + 2 + 2 + 3 + 4);
+        let dec = StreamVByteDecoder::new(Cursor::new(encode(&nums)));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = StreamVByteEncoder::new(Cursor::new(vec![]));
+        assert!(enc.encode(&[1_u64 << 32]).is_err());
+
+        let bytes = encode(&[1_u32, 300, 70000]);
+        assert!(decode(bytes[..bytes.len() - 1].to_vec()).is_err());
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(decode(longer).is_err());
+        // A length for a fourth integer that is not there.
+        let mut bytes = bytes;
+        bytes[1] |= 0b11 << 6;
+        assert_eq!(decode(bytes), Err(InvalidCodeError::StreamVByteCodeError));
+
+        let dec = StreamVByteDecoder::new(Cursor::new(encode(&[300_u32])));
+        assert!(dec.decode::<u8>().is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A group size known at compile time, that parameterizes
+/// [`VarGroupEncoder`] and [`VarGroupDecoder`] for encoding and decoding
+/// single numbers, with [`EncodeOne`], [`DecodeOne`] and [`DecodePrefix`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::byte::vargroup::GroupBits;
+/// use idencode::{DecodeOne, EncodeOne, VarGroupDecoder, VarGroupEncoder};
+///
+/// let bits = VarGroupEncoder::<GroupBits<2>>::encode_one(6_u32);
+/// assert_eq!(bits, vec![false, false, true, true, true, false]);
+/// assert_eq!(VarGroupDecoder::<GroupBits<2>>::decode_one::<u32>(&bits), Ok(6));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupBits<const G: u32>;
+
+/// Checks that a group size is one of the supported ones.
+fn check_group_bits(g: u32) -> u32 {
+    assert!(
+        matches!(g, 2 | 4 | 7),
+        "The group size must be 2, 4 or 7 bits."
+    );
+    g
+}
+
+/// Calculates the groups of a number with `g` bits of payload each, starting
+/// from the most significant group, with the termination bit set on the last
+/// group.
+fn write_vargroup_bits(num: u64, g: u32, buffer: &mut Vec<bool>) {
+    let n_groups = (u64::BITS - num.leading_zeros()).div_ceil(g).max(1);
+    for group in (0..n_groups).rev() {
+        let payload = num >> (g * group);
+        buffer.push(group == 0);
+        buffer.extend((0..g).rev().map(|i| (payload >> i) & 1 == 1));
+    }
+}
+
+/// Decodes the groups with `g` bits of payload each at the start of a buffer
+/// of bits, returning the number along with the number of bits its code
+/// occupies.
+fn decode_vargroup_prefix<T: Numeric>(
+    bits: &[bool],
+    g: u32,
+) -> Result<(T, usize), InvalidCodeError> {
+    let err = || InvalidCodeError::VarGroupCodeError;
+    let mut num = 0_u64;
+    for (i, group) in bits.chunks(g as usize + 1).enumerate() {
+        if group.len() <= g as usize || num.leading_zeros() < g {
+            return Err(err());
+        }
+        let payload = group[1..]
+            .iter()
+            .fold(0, |acc, bit| (acc << 1) | u64::from(*bit));
+        num = (num << g) | payload;
+        if group[0] {
+            let num = T::from_u64(num).ok_or_else(err)?;
+            return Ok((num, (i + 1) * group.len()));
+        }
+    }
+    Err(err())
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// variable-length groups of bits.
+///
+/// It generalizes Variable Byte Encoding to groups of 2, 4 or 7 bits of
+/// payload: each group is preceded by a bit set to 1 for the last group of the
+/// number and to 0 otherwise, and the groups start from the most significant.
+/// With 7 bits, the groups are the bytes of Variable Byte Encoding, while
+/// smaller groups waste fewer bits on tiny gaps, and still take fewer steps
+/// to decode than bit-level codes like Elias Gamma.
+///
+/// For example, with groups of 2 bits, 6, or 1 10 in binary, is encoded as
+/// 001 110.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, VarGroupDecoder, VarGroupEncoder};
+///
+/// let mut enc = VarGroupEncoder::new(Cursor::new(vec![]), 2);
+/// enc.encode(&[6_u32, 0]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b00111010, 0b01000000]);
+///
+/// let dec = VarGroupDecoder::new(Cursor::new(result), 2);
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![6, 0]);
+/// ```
+pub struct VarGroupEncoder<W> {
+    writer: BitWriter<W>,
+    g: u32,
+}
+
+impl<W: Write> VarGroupEncoder<W> {
+    /// Creates a new encoder with `g` bits of payload per group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `g` is not 2, 4 or 7.
+    pub fn new(writer: W, g: u32) -> Self {
+        let writer = BitWriter::new(writer, true);
+        VarGroupEncoder {
+            writer,
+            g: check_group_bits(g),
+        }
+    }
+
+    /// Returns the number of bits of payload per group of the encoder.
+    pub fn group_bits(&self) -> u32 {
+        self.g
+    }
+}
+
+impl<W: Write> Encoder<W> for VarGroupEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = vec![];
+        for num in nums {
+            write_vargroup_bits(num.to_u64(), self.g, &mut bits);
+        }
+        self.writer.write_bits(&bits)
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for VarGroupEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<const G: u32> EncodeOne for VarGroupEncoder<GroupBits<G>> {
+    /// Encodes a single number with groups of `G` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `G` is not 2, 4 or 7.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut bits = vec![];
+        write_vargroup_bits(num.to_u64(), check_group_bits(G), &mut bits);
+        bits
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded
+/// with variable-length groups of bits, with the same group size as the
+/// encoder's.
+pub struct VarGroupDecoder<R> {
+    cursor: BitCursor<R>,
+    g: u32,
+}
+
+impl<R: Read> VarGroupDecoder<R> {
+    /// Creates a new decoder with `g` bits of payload per group, which must
+    /// match the encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `g` is not 2, 4 or 7.
+    pub fn new(reader: R, g: u32) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        VarGroupDecoder {
+            cursor,
+            g: check_group_bits(g),
+        }
+    }
+}
+
+impl<R: Read> Decoder<R> for VarGroupDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let g = self.g;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_vargroup_prefix(bits, g))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+impl<const G: u32> DecodeOne for VarGroupDecoder<GroupBits<G>> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::VarGroupCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<const G: u32> DecodePrefix for VarGroupDecoder<GroupBits<G>> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        decode_vargroup_prefix(bits, check_group_bits(G))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::spread;
+    use crate::{EncodeInto, VBEncoder};
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], g: u32) -> Vec<u8> {
+        let mut enc = VarGroupEncoder::new(Cursor::new(vec![]), g);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_encode_one() {
+        let bits = |s: &str| s.chars().map(|c| c == '1').collect::<Vec<_>>();
+        assert_eq!(
+            VarGroupEncoder::<GroupBits<2>>::encode_one(0_u8),
+            bits("100")
+        );
+        assert_eq!(
+            VarGroupEncoder::<GroupBits<4>>::encode_one(16_u8),
+            bits("0000110000")
+        );
+        assert_eq!(
+            VarGroupEncoder::<GroupBits<2>>::encode_one(u64::MAX).len(),
+            32 * 3
+        );
+        assert_eq!(
+            VarGroupEncoder::<GroupBits<4>>::encode_one(u64::MAX).len(),
+            16 * 5
+        );
+
+        // With 7 bits, the groups are the bytes of Variable Byte Encoding.
+        for num in [0_u64, 127, 128, 300, 1 << 40, u64::MAX] {
+            let mut bytes = [0; 10];
+            let len = VBEncoder::encode_into(&[num], &mut bytes).unwrap();
+            let vb: Vec<bool> = bytes[..len]
+                .iter()
+                .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+                .collect();
+            assert_eq!(VarGroupEncoder::<GroupBits<7>>::encode_one(num), vb);
+        }
+    }
+
+    #[test]
+    fn test_decode_one() {
+        for num in [0_u64, 1, 3, 4, 100, 1 << 40, u64::MAX] {
+            let bits = VarGroupEncoder::<GroupBits<2>>::encode_one(num);
+            assert_eq!(VarGroupDecoder::<GroupBits<2>>::decode_one(&bits), Ok(num));
+            let bits = VarGroupEncoder::<GroupBits<4>>::encode_one(num);
+            assert_eq!(VarGroupDecoder::<GroupBits<4>>::decode_one(&bits), Ok(num));
+        }
+        let bits = VarGroupEncoder::<GroupBits<4>>::encode_one(300_u32);
+        assert!(VarGroupDecoder::<GroupBits<4>>::decode_one::<u8>(&bits).is_err());
+        assert!(VarGroupDecoder::<GroupBits<4>>::decode_one::<u32>(&bits[..9]).is_err());
+        // 33 groups of 2 bits hold more than 64 bits.
+        let mut bits = [false, true, true].repeat(32);
+        bits.extend([true, true, true]);
+        assert!(VarGroupDecoder::<GroupBits<2>>::decode_one::<u64>(&bits).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..2000).chain([0, u64::MAX]).collect();
+        for g in [2, 4, 7] {
+            let dec = VarGroupDecoder::new(Cursor::new(encode(&nums, g)), g);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+
+        // Small groups take less space for tiny gaps.
+        let gaps: Vec<u64> = (1..1000).map(|i| i % 4).collect();
+        let lens: Vec<usize> = [2, 4, 7].map(|g| encode(&gaps, g).len()).into();
+        assert!(lens[0] < lens[1] && lens[1] < lens[2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_group_bits() {
+        VarGroupEncoder::new(Cursor::new(vec![]), 3);
+    }
+}
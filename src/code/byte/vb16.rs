@@ -0,0 +1,207 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeBytes, Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Variable Word Encoding, with 16-bit words.
+///
+/// It works like Variable Byte Encoding, but uses an integral number of
+/// 16-bit words, written in big-endian byte order. The last 15 bits of a word
+/// are "payload" and encode part of the integer, while the first bit is set
+/// to 1 for the last word of the integer and to 0 otherwise. Spending one
+/// continuation bit per 15 payload bits, instead of one per 7, pays off for
+/// large integers like hashes and IDs: for example, a 45-bit integer takes 6
+/// bytes instead of 7, and a 60-bit integer 8 bytes instead of 9.
+pub struct VB16Encoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> VB16Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        VB16Encoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for VB16Encoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let encoded = self.writer.get_mut();
+        let mut words = vec![];
+
+        for num in nums {
+            words.clear();
+            write_vb16_words(num.to_u64(), &mut words);
+            for word in &words {
+                encoded.extend_from_byte_slice(&word.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for VB16Encoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Calculates the 16-bit words of a number, starting from the most significant
+/// 15-bit group, with the termination bit set on the last word.
+fn write_vb16_words(mut num: u64, buffer: &mut Vec<u16>) {
+    let start = buffer.len();
+    loop {
+        buffer.insert(start, (num & 0x7FFF) as u16);
+        num >>= 15;
+        if num == 0 {
+            break;
+        }
+    }
+    *buffer
+        .last_mut()
+        .expect("words are guaranteed to not be empty.") |= 0x8000;
+}
+
+/// A structure that wraps a reader and decodes a sequence of integers
+/// using Variable Word Encoding, with 16-bit words.
+///
+/// The last 15 bits of each big-endian word are payload, while the first bit
+/// marks the last word of an integer.
+pub struct VB16Decoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> VB16Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        VB16Decoder { reader }
+    }
+}
+
+impl DecodeBytes for VB16Decoder<()> {
+    /// Reads the words of the code from the bit offset, which does not have to
+    /// be at the start of a word.
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let mut n = 0_u64;
+        loop {
+            let word = bits.read_bits(16).ok_or(InvalidCodeError::VB16CodeError)?;
+            if n > u64::MAX >> 15 {
+                return Err(InvalidCodeError::VB16CodeError);
+            }
+            n = (n << 15) | (word & 0x7FFF);
+            if word & 0x8000 != 0 {
+                break;
+            }
+        }
+        let num = T::from_u64(n).ok_or(InvalidCodeError::VB16CodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl<R: Read> Decoder<R> for VB16Decoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+        if !bytes.len().is_multiple_of(2) {
+            return Err(InvalidCodeError::VB16CodeError);
+        }
+
+        let mut nums = vec![];
+        let mut n = 0_u64;
+        let mut terminated = true;
+        for pair in bytes.chunks_exact(2) {
+            let word = u16::from_be_bytes([pair[0], pair[1]]);
+            if n > u64::MAX >> 15 {
+                return Err(InvalidCodeError::VB16CodeError);
+            }
+            n = (n << 15) | u64::from(word & 0x7FFF);
+            terminated = word & 0x8000 != 0;
+            if terminated {
+                nums.push(T::from_u64(n).ok_or(InvalidCodeError::VB16CodeError)?);
+                n = 0;
+            }
+        }
+
+        if !terminated {
+            return Err(InvalidCodeError::VB16CodeError);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::VBEncoder;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let mut enc = VB16Encoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<VB16Decoder<()>>(&bytes, &nums);
+        assert!(VB16Decoder::decode_one_from_bytes::<u64>(&[0x00, 0x01, 0x80], 0).is_err());
+    }
+
+    fn encode<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = VB16Encoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 5, 0x7FFF, 0x8000, 824, u64::MAX];
+        let encoded = encode(&nums);
+        assert_eq!(
+            encoded[..10],
+            [0x80, 0x00, 0x80, 0x05, 0xFF, 0xFF, 0x00, 0x01, 0x80, 0x00]
+        );
+
+        let dec = VB16Decoder::new(Cursor::new(encoded));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let dec = VB16Decoder::new(Cursor::new(vec![0x80]));
+        assert!(dec.decode::<u64>().is_err());
+
+        let dec = VB16Decoder::new(Cursor::new(vec![0x80, 0x00, 0x00, 0x01]));
+        assert!(dec.decode::<u64>().is_err());
+
+        let dec = VB16Decoder::new(Cursor::new(encode(&[70000_u32])));
+        assert!(dec.decode::<u16>().is_err());
+    }
+
+    #[test]
+    fn test_smaller_than_vb_for_large_ids() {
+        let ids = [0x0123_4567_89AB_CDEF_u64, (1 << 59) + 12345, 1 << 44];
+        let mut vbe = VBEncoder::new(Cursor::new(vec![]));
+        vbe.encode(&ids).unwrap();
+        let vb = vbe.finalize().unwrap().into_inner();
+        assert!(encode(&ids).len() < vb.len());
+    }
+}
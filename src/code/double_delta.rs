@@ -0,0 +1,169 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::num::convert::{unzigzag, zigzag};
+use crate::num::Numeric;
+
+/// A structure that wraps an encoder and transforms the values that pass
+/// through it into their zigzagged second differences.
+///
+/// Each value is described by its delta from the previous value, and each
+/// delta by its difference from the previous delta, both starting from zero.
+/// The second differences are zigzagged, so that small differences of either
+/// sign become small numbers, and encoded with the wrapped encoder. Series
+/// that trend smoothly, but go up and down, like gauges or coordinates, thus
+/// become sequences of small numbers, which any integer code compresses well.
+///
+/// Deltas wrap around, so any sequence is transformed losslessly. The
+/// transformed values may be zero, so the wrapped code must be able to
+/// encode zero, like the multi-width chunk code of [`crate::ChunkEncoder`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{ChunkDecoder, ChunkEncoder, Decoder, DoubleDeltaDecoder, DoubleDeltaEncoder, Encoder};
+///
+/// let altitudes = [1000_u32, 1010, 1020, 1029, 1037, 1040, 1038, 1030];
+/// let mut enc = DoubleDeltaEncoder::new(ChunkEncoder::new(Cursor::new(vec![])));
+/// enc.encode(&altitudes).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result.len(), 2 + 2 + 6);
+///
+/// let dec = DoubleDeltaDecoder::new(ChunkDecoder::new(Cursor::new(result)));
+/// assert_eq!(dec.decode::<u32>().unwrap(), altitudes);
+/// ```
+pub struct DoubleDeltaEncoder<E> {
+    inner: E,
+    prev: u64,
+    prev_delta: i64,
+}
+
+impl<E> DoubleDeltaEncoder<E> {
+    pub fn new(inner: E) -> Self {
+        DoubleDeltaEncoder {
+            inner,
+            prev: 0,
+            prev_delta: 0,
+        }
+    }
+}
+
+impl<W: Write, E: Encoder<W>> Encoder<W> for DoubleDeltaEncoder<E> {
+    /// Transforms and encodes a sequence of numbers. The differences carry
+    /// over across calls, so a sequence may be encoded in parts.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let dods: Vec<u64> = nums
+            .iter()
+            .map(|num| {
+                let num = num.to_u64();
+                let delta = num.wrapping_sub(self.prev) as i64;
+                let dod = delta.wrapping_sub(self.prev_delta);
+                self.prev = num;
+                self.prev_delta = delta;
+                zigzag(dod)
+            })
+            .collect();
+        self.inner.encode(&dods)
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+impl<W: Write, E: NonBlockingEncoder<W>> NonBlockingEncoder<W> for DoubleDeltaEncoder<E> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+/// A structure that wraps a decoder, and restores the values transformed by
+/// [`DoubleDeltaEncoder`] from the decoded second differences.
+pub struct DoubleDeltaDecoder<D> {
+    inner: D,
+}
+
+impl<D> DoubleDeltaDecoder<D> {
+    pub fn new(inner: D) -> Self {
+        DoubleDeltaDecoder { inner }
+    }
+}
+
+impl<R: Read, D: Decoder<R>> Decoder<R> for DoubleDeltaDecoder<D> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let (mut prev, mut prev_delta) = (0_u64, 0_i64);
+        self.inner
+            .decode::<u64>()?
+            .into_iter()
+            .map(|dod| {
+                prev_delta = prev_delta.wrapping_add(unzigzag(dod));
+                prev = prev.wrapping_add(prev_delta as u64);
+                T::from_u64(prev).ok_or(InvalidCodeError::DoubleDeltaCodeError)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BP128Decoder, BP128Encoder, ChunkDecoder, ChunkEncoder};
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = DoubleDeltaEncoder::new(ChunkEncoder::new(Cursor::new(vec![])));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_second_differences() {
+        let mut enc = DoubleDeltaEncoder::new(ChunkEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[5_u64, 7]).unwrap();
+        enc.encode(&[9_u64, 10, 10]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        // Deltas 5, 2, 2, 1, 0 and their differences 5, -3, 0, -1, -1.
+        let dods = ChunkDecoder::new(Cursor::new(result.clone())).decode::<u64>();
+        assert_eq!(dods, Ok(vec![10, 5, 0, 1, 1]));
+        let dec = DoubleDeltaDecoder::new(ChunkDecoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<u64>(), Ok(vec![5, 7, 9, 10, 10]));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = (0..1000).map(|i| 500_000 + (i * i) % 1013).collect();
+        nums.extend([u64::MAX, 0, u64::MAX, 1 << 63, 3, 3]);
+        let dec = DoubleDeltaDecoder::new(ChunkDecoder::new(Cursor::new(encode(&nums))));
+        assert_eq!(dec.decode::<u64>(), Ok(nums));
+
+        // Any integer codec can be fed with the second differences.
+        let nums: Vec<u32> = (0..300).map(|i| 1_000_000 + 40 * i - i * i / 10).collect();
+        let mut enc = DoubleDeltaEncoder::new(BP128Encoder::new(Cursor::new(vec![])));
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let mut plain = BP128Encoder::new(Cursor::new(vec![]));
+        plain.encode(&nums).unwrap();
+        assert!(result.len() * 2 < plain.finalize().unwrap().into_inner().len());
+        let dec = DoubleDeltaDecoder::new(BP128Decoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<u32>(), Ok(nums));
+    }
+
+    #[test]
+    fn test_errs() {
+        let dec = DoubleDeltaDecoder::new(ChunkDecoder::new(Cursor::new(encode(&[1 << 40]))));
+        assert!(dec.decode::<u32>().is_err());
+        let dec = DoubleDeltaDecoder::new(ChunkDecoder::new(Cursor::new(vec![0xC0])));
+        assert!(dec.decode::<u64>().is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use std::io::{self, Read, Write};
+
+use super::model::{BitModel, DecodeBit, EncodeBit, IntegerModel, PROB_BITS};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The bounds of the interval of the coder, in units of `1 / 2^32`.
+const HALF: u64 = 1 << 31;
+const QUARTER: u64 = 1 << 30;
+
+/// The number of bits the decoder may read past the end of the bits of the
+/// coder, which are taken to be 0-bits.
+const MAX_LOOKAHEAD: usize = 32;
+
+/// The arithmetic encoder of binary decisions, that narrows an interval of
+/// 32 bits of precision in proportion to the probability of each bit, and
+/// shifts out its settled bits one at a time.
+pub(crate) struct BitArithEncoder {
+    low: u64,
+    high: u64,
+    pending: u64,
+    bits: Vec<bool>,
+}
+
+impl BitArithEncoder {
+    pub(crate) fn new() -> Self {
+        BitArithEncoder {
+            low: 0,
+            high: u64::from(u32::MAX),
+            pending: 0,
+            bits: vec![],
+        }
+    }
+
+    /// Returns the bits that have been settled since the last call.
+    pub(crate) fn take_bits(&mut self) -> Vec<bool> {
+        std::mem::take(&mut self.bits)
+    }
+
+    /// Appends a settled bit, followed by the pending bits, which are its
+    /// opposite.
+    fn write_bit(&mut self, bit: bool) {
+        self.bits.push(bit);
+        for _ in 0..self.pending {
+            self.bits.push(!bit);
+        }
+        self.pending = 0;
+    }
+
+    /// Settles the bits that pick a value in the interval, so that any bits
+    /// after them can be taken to be 0-bits.
+    pub(crate) fn flush(&mut self) {
+        self.pending += 1;
+        self.write_bit(self.low >= QUARTER);
+    }
+}
+
+impl EncodeBit for BitArithEncoder {
+    fn encode_bit(&mut self, model: &mut BitModel, bit: bool) {
+        let split = split(self.low, self.high, model.prob());
+        match bit {
+            false => self.high = split,
+            true => self.low = split + 1,
+        }
+        model.update(bit);
+        loop {
+            if self.high < HALF {
+                self.write_bit(false);
+            } else if self.low >= HALF {
+                self.write_bit(true);
+                self.low -= HALF;
+                self.high -= HALF;
+            } else if self.low >= QUARTER && self.high < HALF + QUARTER {
+                // The interval straddles the middle, so the next bit is not
+                // settled yet, but it is the opposite of the one after it.
+                self.pending += 1;
+                self.low -= QUARTER;
+                self.high -= QUARTER;
+            } else {
+                break;
+            }
+            self.low <<= 1;
+            self.high = (self.high << 1) | 1;
+        }
+    }
+}
+
+/// Returns the end of the part of an interval that stands for a 0-bit, with
+/// the probability of a model.
+fn split(low: u64, high: u64, prob: u16) -> u64 {
+    low + (((high - low + 1) * u64::from(prob)) >> PROB_BITS) - 1
+}
+
+/// The arithmetic decoder of binary decisions, that follows the interval of
+/// [`BitArithEncoder`] with the same models.
+pub(crate) struct BitArithDecoder<'a> {
+    bits: &'a [bool],
+    pos: usize,
+    low: u64,
+    high: u64,
+    value: u64,
+}
+
+impl<'a> BitArithDecoder<'a> {
+    pub(crate) fn new(bits: &'a [bool]) -> Self {
+        let mut decoder = BitArithDecoder {
+            bits,
+            pos: 0,
+            low: 0,
+            high: u64::from(u32::MAX),
+            value: 0,
+        };
+        for _ in 0..u32::BITS {
+            decoder.value = (decoder.value << 1) | u64::from(decoder.next_bit());
+        }
+        decoder
+    }
+
+    /// Returns the next bit, or a 0-bit past the end of the bits.
+    fn next_bit(&mut self) -> bool {
+        let bit = self.bits.get(self.pos).copied().unwrap_or(false);
+        self.pos += 1;
+        bit
+    }
+}
+
+impl DecodeBit for BitArithDecoder<'_> {
+    /// Returns `None` if the decoder has read further past the end of the
+    /// bits than the encoder could have left it.
+    fn decode_bit(&mut self, model: &mut BitModel) -> Option<bool> {
+        let split = split(self.low, self.high, model.prob());
+        let bit = self.value > split;
+        match bit {
+            false => self.high = split,
+            true => self.low = split + 1,
+        }
+        model.update(bit);
+        loop {
+            let offset = if self.high < HALF {
+                0
+            } else if self.low >= HALF {
+                HALF
+            } else if self.low >= QUARTER && self.high < HALF + QUARTER {
+                QUARTER
+            } else {
+                break;
+            };
+            self.low -= offset;
+            self.high -= offset;
+            self.value -= offset;
+            self.low <<= 1;
+            self.high = (self.high << 1) | 1;
+            self.value = (self.value << 1) | u64::from(self.next_bit());
+        }
+        (self.pos <= self.bits.len() + MAX_LOOKAHEAD).then_some(bit)
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// adaptive binary arithmetic coding.
+///
+/// Each integer is broken down into binary decisions, its bit length in unary
+/// and then its bits below the leading 1-bit, and each decision is encoded
+/// with an arithmetic coder, in proportion to the probability of its model. The
+/// models start out even, and adapt to the bits seen so far, so a decision
+/// that nearly always goes one way takes a small fraction of a bit. This
+/// beats the static universal codes for skewed distributions, such as the
+/// gaps of dense ID lists, where most integers have the same few bit lengths.
+///
+/// The output is a stream of bits, and the end of the sequence is encoded
+/// in it, when the encoder is finalized. The interval of the coder is
+/// renormalized a bit at a time, and its bits go straight to the bit writer.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{ArithDecoder, ArithEncoder, Decoder, Encoder, GammaEncoder};
+///
+/// let gaps: Vec<u32> = (0..1000).map(|i| if i % 50 == 0 { 9 } else { 1 }).collect();
+///
+/// let mut enc = ArithEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&gaps).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // Over a bit per gap in Elias Gamma, but less than half with adaptive models.
+/// let mut gamma = GammaEncoder::new(Cursor::new(vec![]));
+/// gamma.encode(&gaps).unwrap();
+/// assert!(result.len() * 2 < gamma.finalize().unwrap().into_inner().len());
+///
+/// let dec = ArithDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), gaps);
+/// ```
+pub struct ArithEncoder<W> {
+    writer: BitWriter<W>,
+    coder: BitArithEncoder,
+    model: IntegerModel,
+}
+
+impl<W: Write> ArithEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        ArithEncoder {
+            writer,
+            coder: BitArithEncoder::new(),
+            model: IntegerModel::new(),
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for ArithEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            self.model.encode(&mut self.coder, num.to_u64());
+        }
+        self.writer.write_bits(&self.coder.take_bits())
+    }
+
+    /// Encodes the end of the sequence, and writes the bits that are left.
+    fn finalize(mut self) -> io::Result<W> {
+        self.model.encode_end(&mut self.coder);
+        self.coder.flush();
+        self.writer.write_bits(&self.coder.take_bits())?;
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`ArithEncoder`].
+pub struct ArithDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> ArithDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, true);
+        ArithDecoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for ArithDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::ArithCodeError;
+        let bits = self.reader.read_to_end().map_err(|_| err())?.into_bits();
+        let mut coder = BitArithDecoder::new(&bits);
+        let mut model = IntegerModel::new();
+
+        let mut nums = vec![];
+        while let Some(num) = model.decode(&mut coder).map_err(|_| err())? {
+            nums.push(T::from_u64(num).ok_or_else(err)?);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::spread;
+    use crate::VBEncoder;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = ArithEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        ArithDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_coder() {
+        let bits: Vec<bool> = (1..5000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 61 == 0)
+            .collect();
+        let mut coder = BitArithEncoder::new();
+        let mut model = BitModel::new();
+        for bit in &bits {
+            coder.encode_bit(&mut model, *bit);
+        }
+        coder.flush();
+        let encoded = coder.take_bits();
+        // One bit in eight is set, which takes about 0.54 bits per bit.
+        assert!(encoded.len() < bits.len() * 6 / 10);
+
+        let mut coder = BitArithDecoder::new(&encoded);
+        let mut model = BitModel::new();
+        for bit in &bits {
+            assert_eq!(coder.decode_bit(&mut model), Some(*bit));
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..2000)
+            .chain([0, 1, u64::MAX, u64::MAX - 1])
+            .collect();
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+
+        let mut enc = ArithEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[3_u8, 0]).unwrap();
+        enc.encode(&[255_u8]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(decode(result).unwrap(), vec![3, 0, 255]);
+    }
+
+    #[test]
+    fn test_skewed() {
+        // Gaps of a dense list, mostly of a single bit length.
+        let gaps: Vec<u64> = (1..10_000_u64)
+            .map(|i| 4 + (i.wrapping_mul(0x9E3779B97F4A7C15) >> 62))
+            .collect();
+        let mut vb = VBEncoder::new(Cursor::new(vec![]));
+        vb.encode(&gaps).unwrap();
+        let vb = vb.finalize().unwrap().into_inner();
+        assert!(encode(&gaps).len() * 3 < vb.len());
+    }
+
+    #[test]
+    fn test_errs() {
+        assert_eq!(decode(vec![]), Err(InvalidCodeError::ArithCodeError));
+        // Past the end of the bits, a stream of 1-bits never ends.
+        assert_eq!(decode(vec![0xFF; 4]), Err(InvalidCodeError::ArithCodeError));
+        let bytes = encode(&(0..100_u64).collect::<Vec<_>>());
+        assert!(ArithDecoder::new(Cursor::new(bytes)).decode::<u8>().is_ok());
+        let bytes = encode(&[300]);
+        assert!(ArithDecoder::new(Cursor::new(bytes))
+            .decode::<u8>()
+            .is_err());
+    }
+}
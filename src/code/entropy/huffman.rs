@@ -0,0 +1,412 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The longest code a table can hold.
+const MAX_CODE_LEN: u32 = 127;
+
+/// A canonical Huffman code, that maps each value to a code of known length.
+///
+/// The codes are assigned in order of length, and values of the same length
+/// in increasing order, each code being the one after the previous code,
+/// extended with 0-bits to its length. So the code is determined by the code
+/// lengths of the values alone, and that is all that needs to be stored.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::HuffmanTable;
+///
+/// let table = HuffmanTable::from_frequencies([(7, 10), (3, 5), (9, 1), (4, 1)]);
+/// assert_eq!(table.code_lengths(), &[(3, 2), (4, 3), (7, 1), (9, 3)]);
+/// assert_eq!(table.code(7), Some(vec![false]));
+/// assert_eq!(table.code(3), Some(vec![true, false]));
+/// assert_eq!(table.code(9), Some(vec![true, true, true]));
+/// assert_eq!(table.code(5), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HuffmanTable {
+    /// The values with their code lengths, in increasing order of value.
+    lengths: Vec<(u64, u32)>,
+    /// The codes of the values, in the same order.
+    codes: Vec<u128>,
+}
+
+impl HuffmanTable {
+    /// Builds the canonical Huffman code of values with their frequencies.
+    /// The frequencies of repeated values are added up, and values with a
+    /// frequency of zero are left out.
+    ///
+    /// A single value gets a code of one bit, so that every value takes up
+    /// at least one bit.
+    pub fn from_frequencies(freqs: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut counts = BTreeMap::new();
+        for (value, freq) in freqs {
+            if freq > 0 {
+                let count: &mut u64 = counts.entry(value).or_default();
+                *count = count.saturating_add(freq);
+            }
+        }
+        let (values, weights): (Vec<u64>, Vec<u64>) = counts.into_iter().unzip();
+        let lengths = values.into_iter().zip(code_lengths(&weights)).collect();
+        Self::from_code_lengths(lengths).expect("Huffman code lengths are valid.")
+    }
+
+    /// Builds the canonical code of values with their code lengths, which
+    /// must be in strictly increasing order of value. Returns `None` if the
+    /// lengths do not make up a prefix code.
+    fn from_code_lengths(lengths: Vec<(u64, u32)>) -> Option<Self> {
+        let mut codes = vec![0; lengths.len()];
+        let (mut code, mut prev_len) = (0_u128, 0);
+        for (i, _) in canonical_order(&lengths) {
+            let len = lengths[i].1;
+            if len == 0 || len > MAX_CODE_LEN || code >> prev_len != 0 {
+                return None;
+            }
+            code <<= len - prev_len;
+            if code >> len != 0 {
+                return None;
+            }
+            codes[i] = code;
+            code += 1;
+            prev_len = len;
+        }
+        Some(HuffmanTable { lengths, codes })
+    }
+
+    /// Returns the values of the code with their code lengths, in increasing
+    /// order of value.
+    pub fn code_lengths(&self) -> &[(u64, u32)] {
+        &self.lengths
+    }
+
+    /// Returns the code of a value, or `None` if the value is not in the table.
+    pub fn code(&self, value: u64) -> Option<Vec<bool>> {
+        let mut bits = vec![];
+        self.write_code(value, &mut bits).then_some(bits)
+    }
+
+    /// Appends the code of a value to a buffer of bits, returning `false` if
+    /// the value is not in the table.
+    fn write_code(&self, value: u64, buffer: &mut Vec<bool>) -> bool {
+        let Ok(i) = self.lengths.binary_search_by_key(&value, |(v, _)| *v) else {
+            return false;
+        };
+        let (len, code) = (self.lengths[i].1, self.codes[i]);
+        buffer.extend((0..len).rev().map(|shift| (code >> shift) & 1 == 1));
+        true
+    }
+
+    /// Writes the table: the number of values in Elias Gamma Encoding,
+    /// incremented by one, and then, for each value, its gap from the previous
+    /// value, or the value incremented by one for the first, and its code
+    /// length, both in Elias Gamma Encoding.
+    fn write_bits(&self, buffer: &mut Vec<bool>) {
+        let convention = UnaryConvention::default();
+        write_gamma_bits(self.lengths.len() + 1, convention, buffer);
+        let mut prev = None;
+        for &(value, len) in &self.lengths {
+            let gap = prev.map_or(value + 1, |prev| value - prev);
+            write_gamma_bits(gap, convention, buffer);
+            write_gamma_bits(u64::from(len), convention, buffer);
+            prev = Some(value);
+        }
+    }
+
+    /// Decodes the table written at the start of a buffer of bits, returning
+    /// it along with the number of bits it occupies.
+    fn decode_prefix(bits: &[bool]) -> Result<(Self, usize), InvalidCodeError> {
+        let err = |_| InvalidCodeError::HuffmanCodeError;
+        let convention = UnaryConvention::default();
+        let (count, mut pos) = decode_gamma_prefix::<u64>(bits, convention).map_err(err)?;
+        // Each value takes at least two bits.
+        let count = usize::try_from(count - 1)
+            .ok()
+            .filter(|count| *count <= bits.len() / 2)
+            .ok_or(InvalidCodeError::HuffmanCodeError)?;
+
+        let mut lengths = Vec::with_capacity(count);
+        let mut prev = None;
+        for _ in 0..count {
+            let (gap, len) = decode_gamma_prefix::<u64>(&bits[pos..], convention).map_err(err)?;
+            pos += len;
+            let value = match prev {
+                None => gap - 1,
+                Some(prev) => {
+                    u64::checked_add(prev, gap).ok_or(InvalidCodeError::HuffmanCodeError)?
+                }
+            };
+            let (code_len, len) =
+                decode_gamma_prefix::<u32>(&bits[pos..], convention).map_err(err)?;
+            pos += len;
+            lengths.push((value, code_len));
+            prev = Some(value);
+        }
+        let table = Self::from_code_lengths(lengths).ok_or(InvalidCodeError::HuffmanCodeError)?;
+        Ok((table, pos))
+    }
+}
+
+/// Returns the indices of values with their code lengths in canonical order,
+/// by code length, and by value for the same length.
+fn canonical_order(lengths: &[(u64, u32)]) -> Vec<(usize, u32)> {
+    let mut order: Vec<(usize, u32)> = lengths
+        .iter()
+        .enumerate()
+        .map(|(i, (_, len))| (i, *len))
+        .collect();
+    order.sort_by_key(|(i, len)| (*len, *i));
+    order
+}
+
+/// Calculates the Huffman code lengths of symbols with their weights, by
+/// merging the two lightest trees until a single tree is left. Ties are
+/// broken by the order in which the trees were made, so the lengths only
+/// depend on the weights.
+fn code_lengths(weights: &[u64]) -> Vec<u32> {
+    if weights.len() <= 1 {
+        return vec![1; weights.len()];
+    }
+    let mut parents = vec![0; 2 * weights.len() - 1];
+    let mut heap: BinaryHeap<_> = weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| Reverse((u128::from(*weight), i)))
+        .collect();
+    for node in weights.len()..parents.len() {
+        let Reverse((first, i)) = heap.pop().expect("Two trees are left.");
+        let Reverse((second, j)) = heap.pop().expect("Two trees are left.");
+        parents[i] = node;
+        parents[j] = node;
+        heap.push(Reverse((first + second, node)));
+    }
+    // Parents are made after their children, so depths are filled from the root.
+    let mut depths = vec![0; parents.len()];
+    for node in (0..parents.len() - 1).rev() {
+        depths[node] = depths[parents[node]] + 1;
+    }
+    depths.truncate(weights.len());
+    depths
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using a
+/// canonical Huffman code built from their frequencies.
+///
+/// The integers are buffered, and encoded when the encoder is finalized. The
+/// code lengths of the distinct integers are written first, as described in
+/// [`HuffmanTable`], followed by the code of each integer. Integers must be
+/// less than `u64::MAX`. Frequent integers take fewer bits, whatever their
+/// magnitude, so this suits sequences of few distinct values, such as
+/// categories or term frequencies.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, HuffmanDecoder, HuffmanEncoder};
+///
+/// let mut enc = HuffmanEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[1000_u32, 1000, 1000, 1000]).unwrap();
+/// enc.encode(&[7_u32, 1000, 42]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let dec = HuffmanDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![1000, 1000, 1000, 1000, 7, 1000, 42]);
+/// ```
+pub struct HuffmanEncoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> HuffmanEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        HuffmanEncoder {
+            writer,
+            nums: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for HuffmanEncoder<W> {
+    /// Buffers a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is `u64::MAX`.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let start = self.nums.len();
+        self.nums.extend(nums.iter().map(|num| num.to_u64()));
+        if self.nums[start..].contains(&u64::MAX) {
+            self.nums.truncate(start);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Huffman Encoding does not support u64::MAX.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds the code of the buffered numbers, and writes it, followed by
+    /// the codes of the numbers.
+    fn finalize(mut self) -> io::Result<W> {
+        let table = HuffmanTable::from_frequencies(self.nums.iter().map(|num| (*num, 1)));
+        let mut bits = vec![];
+        table.write_bits(&mut bits);
+        for num in &self.nums {
+            table.write_code(*num, &mut bits);
+        }
+        self.writer.write_bits(&bits)?;
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`HuffmanEncoder`].
+pub struct HuffmanDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> HuffmanDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        HuffmanDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for HuffmanDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let table = self.cursor.decode_with(HuffmanTable::decode_prefix)?;
+
+        // The first code, the number of codes and the position of the first
+        // value in canonical order, for each code length.
+        let order = canonical_order(&table.lengths);
+        let max_len = order.last().map_or(0, |(_, len)| *len) as usize;
+        let mut firsts = vec![(0_u128, 0_u128, 0_usize); max_len + 1];
+        for (pos, (i, len)) in order.iter().enumerate().rev() {
+            firsts[*len as usize] = (table.codes[*i], firsts[*len as usize].1 + 1, pos);
+        }
+
+        let bits = self.cursor.remaining()?;
+        let mut nums = vec![];
+        let mut pos = 0;
+        while pos < bits.len() {
+            let mut code = 0_u128;
+            let mut num = None;
+            for (len, (first, count, start)) in firsts.iter().enumerate().skip(1) {
+                let bit = *bits
+                    .get(pos + len - 1)
+                    .ok_or(InvalidCodeError::HuffmanCodeError)?;
+                code = (code << 1) | u128::from(bit);
+                if code >= *first && code - first < *count {
+                    let (i, _) = order[start + (code - first) as usize];
+                    num = Some((table.lengths[i].0, len));
+                    break;
+                }
+            }
+            let (num, len) = num.ok_or(InvalidCodeError::HuffmanCodeError)?;
+            nums.push(T::from_u64(num).ok_or(InvalidCodeError::HuffmanCodeError)?);
+            pos += len;
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = HuffmanEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        HuffmanDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_code_lengths() {
+        assert_eq!(code_lengths(&[]), vec![]);
+        assert_eq!(code_lengths(&[5]), vec![1]);
+        assert_eq!(code_lengths(&[1, 1, 1, 1]), vec![2, 2, 2, 2]);
+        assert_eq!(code_lengths(&[16, 8, 4, 2, 1, 1]), vec![1, 2, 3, 4, 5, 5]);
+        assert_eq!(code_lengths(&[u64::MAX, u64::MAX, 1]), vec![2, 1, 2]);
+    }
+
+    #[test]
+    fn test_table() {
+        let table = HuffmanTable::from_frequencies([(4, 1), (0, 1), (9, 2), (4, 1), (3, 0)]);
+        assert_eq!(table.code_lengths(), &[(0, 2), (4, 2), (9, 1)]);
+        assert_eq!(table.code(0), Some(vec![true, false]));
+        assert_eq!(table.code(4), Some(vec![true, true]));
+
+        let mut bits = vec![];
+        table.write_bits(&mut bits);
+        bits.push(true);
+        assert_eq!(
+            HuffmanTable::decode_prefix(&bits),
+            Ok((table, bits.len() - 1))
+        );
+        // Three codes of one bit are not a prefix code.
+        let lengths = vec![(0, 1), (1, 1), (2, 1)];
+        assert_eq!(HuffmanTable::from_code_lengths(lengths), None);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = (1..2000_u64)
+            .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15) >> 60).pow(3))
+            .chain([u64::MAX - 1, 0])
+            .collect();
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+        assert_eq!(
+            decode(encode(&[u64::MAX - 1; 20])).unwrap(),
+            vec![u64::MAX - 1; 20]
+        );
+
+        // Skewed values take fewer bits than their magnitude.
+        let skewed: Vec<u64> = (0..1000)
+            .map(|i| if i % 10 == 0 { 1 << 40 } else { 1 << 50 })
+            .collect();
+        assert!(encode(&skewed).len() < 1000 / 8 * 2);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = HuffmanEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[1_u64, 2]).unwrap();
+        assert!(enc.encode(&[3, u64::MAX]).is_err());
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(decode(result), Ok(vec![1, 2]));
+
+        assert!(HuffmanDecoder::new(Cursor::new(encode(&[1, 300])))
+            .decode::<u8>()
+            .is_err());
+        let bytes = encode(&(0..50_u64).map(|n| n * 1000).collect::<Vec<_>>());
+        assert_eq!(
+            decode(bytes[..40].to_vec()),
+            Err(InvalidCodeError::HuffmanCodeError)
+        );
+        // A code of a single value leaves the 1-bit unused.
+        let bytes = encode(&[5, 5]);
+        let mut bits = BitReader::new(Cursor::new(bytes), true)
+            .read_to_end()
+            .unwrap()
+            .into_bits();
+        *bits.last_mut().unwrap() = true;
+        let mut enc = BitWriter::new(Cursor::new(vec![]), true);
+        enc.write_bits(&bits).unwrap();
+        assert!(decode(enc.finalize().unwrap().into_inner()).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod arith;
+pub mod huffman;
+pub(crate) mod model;
+pub mod range;
+pub mod rans;
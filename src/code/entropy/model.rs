@@ -0,0 +1,178 @@
+/// The number of bits of the probabilities of the bit models.
+pub(crate) const PROB_BITS: u32 = 11;
+
+/// The probability of a 0-bit that the bit models start from, one half.
+const PROB_INIT: u16 = 1 << (PROB_BITS - 1);
+
+/// How fast the bit models adapt: each bit moves the probability by
+/// `1 / 2^MOVE_BITS` of its distance from certainty.
+const MOVE_BITS: u32 = 5;
+
+/// An adaptive model of a binary decision, that holds the probability that
+/// the decision is a 0-bit, in units of `1 / 2^PROB_BITS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BitModel {
+    prob: u16,
+}
+
+impl BitModel {
+    pub(crate) fn new() -> Self {
+        BitModel { prob: PROB_INIT }
+    }
+
+    /// Returns the probability of a 0-bit.
+    pub(crate) fn prob(&self) -> u16 {
+        self.prob
+    }
+
+    /// Moves the probability towards the bit that occurred.
+    pub(crate) fn update(&mut self, bit: bool) {
+        match bit {
+            false => self.prob += ((1 << PROB_BITS) - self.prob) >> MOVE_BITS,
+            true => self.prob -= self.prob >> MOVE_BITS,
+        }
+    }
+}
+
+/// An entropy coder that encodes binary decisions with their models, which
+/// it updates.
+pub(crate) trait EncodeBit {
+    fn encode_bit(&mut self, model: &mut BitModel, bit: bool);
+}
+
+/// An entropy coder that decodes binary decisions with their models, which
+/// it updates the same way as its encoder.
+pub(crate) trait DecodeBit {
+    /// Decodes a bit, returning `None` if the input runs out.
+    fn decode_bit(&mut self, model: &mut BitModel) -> Option<bool>;
+}
+
+/// The adaptive order-0 models of the binary decisions that make up the
+/// encoding of a sequence of integers, which work with any entropy coder.
+///
+/// An integer is preceded by a 1-bit, and the end of the sequence is marked by
+/// a 0-bit. Its bit length, from 0 to 64, follows in unary, with a model for
+/// each position, and then the bits below its leading 1-bit, with a model for
+/// each bit position.
+pub(crate) struct IntegerModel {
+    more: BitModel,
+    lens: [BitModel; 65],
+    bits: [BitModel; 64],
+}
+
+impl IntegerModel {
+    pub(crate) fn new() -> Self {
+        IntegerModel {
+            more: BitModel::new(),
+            lens: [BitModel::new(); 65],
+            bits: [BitModel::new(); 64],
+        }
+    }
+
+    /// Encodes an integer of the sequence.
+    pub(crate) fn encode(&mut self, coder: &mut impl EncodeBit, num: u64) {
+        let n_bits = u64::BITS - num.leading_zeros();
+        coder.encode_bit(&mut self.more, true);
+        for len in 0..n_bits as usize {
+            coder.encode_bit(&mut self.lens[len], true);
+        }
+        if n_bits < u64::BITS {
+            coder.encode_bit(&mut self.lens[n_bits as usize], false);
+        }
+        for pos in (0..n_bits.saturating_sub(1)).rev() {
+            let bit = (num >> pos) & 1 == 1;
+            coder.encode_bit(&mut self.bits[pos as usize], bit);
+        }
+    }
+
+    /// Encodes the end of the sequence.
+    pub(crate) fn encode_end(&mut self, coder: &mut impl EncodeBit) {
+        coder.encode_bit(&mut self.more, false);
+    }
+
+    /// Decodes the next integer of the sequence, returning `Ok(None)` at its
+    /// end, and `Err(())` if the input runs out.
+    pub(crate) fn decode(&mut self, coder: &mut impl DecodeBit) -> Result<Option<u64>, ()> {
+        if !coder.decode_bit(&mut self.more).ok_or(())? {
+            return Ok(None);
+        }
+        let mut n_bits = 0;
+        while n_bits < u64::BITS
+            && coder
+                .decode_bit(&mut self.lens[n_bits as usize])
+                .ok_or(())?
+        {
+            n_bits += 1;
+        }
+        let mut num = u64::from(n_bits > 0);
+        for pos in (0..n_bits.saturating_sub(1)).rev() {
+            let bit = coder.decode_bit(&mut self.bits[pos as usize]).ok_or(())?;
+            num = (num << 1) | u64::from(bit);
+        }
+        Ok(Some(num))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_model() {
+        let mut model = BitModel::new();
+        assert_eq!(model.prob(), 1 << 10);
+        model.update(false);
+        assert_eq!(model.prob(), 1024 + 32);
+        model.update(true);
+        assert_eq!(model.prob(), 1056 - 33);
+        // The probability never reaches certainty.
+        for _ in 0..1000 {
+            model.update(true);
+        }
+        assert_eq!(model.prob(), 31);
+        for _ in 0..1000 {
+            model.update(false);
+        }
+        assert_eq!(model.prob(), (1 << PROB_BITS) - 31);
+    }
+
+    /// A coder that records the decisions it encodes, and plays them back.
+    struct Recorder(Vec<bool>);
+
+    impl EncodeBit for Recorder {
+        fn encode_bit(&mut self, model: &mut BitModel, bit: bool) {
+            model.update(bit);
+            self.0.push(bit);
+        }
+    }
+
+    impl DecodeBit for Recorder {
+        fn decode_bit(&mut self, model: &mut BitModel) -> Option<bool> {
+            let bit = (!self.0.is_empty()).then(|| self.0.remove(0))?;
+            model.update(bit);
+            Some(bit)
+        }
+    }
+
+    #[test]
+    fn test_integer_model() {
+        let mut recorder = Recorder(vec![]);
+        let mut model = IntegerModel::new();
+        for num in [0, 1, 6, u64::MAX] {
+            model.encode(&mut recorder, num);
+        }
+        model.encode_end(&mut recorder);
+        // 6 is a 1-bit, 110 for its length of 3, and 10 below its leading 1-bit.
+        let six = [true, true, true, true, false, true, false];
+        assert_eq!(recorder.0[5..12], six);
+        assert_eq!(recorder.0.len(), 2 + 3 + 7 + 1 + 64 + 63 + 1);
+
+        let mut model = IntegerModel::new();
+        let mut nums = vec![];
+        while let Some(num) = model.decode(&mut recorder).unwrap() {
+            nums.push(num);
+        }
+        assert_eq!(nums, vec![0, 1, 6, u64::MAX]);
+        assert_eq!(model.decode(&mut recorder), Err(()));
+    }
+}
@@ -0,0 +1,317 @@
+use std::io::{self, Read, Write};
+
+use super::model::{BitModel, DecodeBit, EncodeBit, IntegerModel, PROB_BITS};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The range is renormalized when it falls below this value.
+const TOP: u32 = 1 << 24;
+
+/// The range encoder of binary decisions, that narrows an interval of 32 bits
+/// of precision in proportion to the probability of each bit, and shifts out
+/// its settled bytes.
+pub(crate) struct ByteRangeEncoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    bytes: Vec<u8>,
+}
+
+impl ByteRangeEncoder {
+    pub(crate) fn new() -> Self {
+        ByteRangeEncoder {
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            bytes: vec![],
+        }
+    }
+
+    /// Returns the bytes that have been settled since the last call.
+    pub(crate) fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+
+    /// Shifts the top byte out of the low end of the interval. A byte of 0xFF
+    /// may still change by a carry, so it is held back until it cannot.
+    fn shift_low(&mut self) {
+        if self.low < 0xFF00_0000 || self.low > u64::from(u32::MAX) {
+            let carry = (self.low >> 32) as u8;
+            self.bytes.push(self.cache.wrapping_add(carry));
+            for _ in 1..self.cache_size {
+                self.bytes.push(0xFF_u8.wrapping_add(carry));
+            }
+            self.cache_size = 0;
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00FF_FFFF) << 8;
+    }
+
+    /// Shifts out the bytes that are left, so that the interval is settled.
+    pub(crate) fn flush(&mut self) {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+    }
+}
+
+impl EncodeBit for ByteRangeEncoder {
+    fn encode_bit(&mut self, model: &mut BitModel, bit: bool) {
+        let bound = (self.range >> PROB_BITS) * u32::from(model.prob());
+        match bit {
+            false => self.range = bound,
+            true => {
+                self.low += u64::from(bound);
+                self.range -= bound;
+            }
+        }
+        model.update(bit);
+        while self.range < TOP {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+}
+
+/// The range decoder of binary decisions, that follows the interval of
+/// [`ByteRangeEncoder`] with the same models.
+pub(crate) struct ByteRangeDecoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> ByteRangeDecoder<'a> {
+    /// Creates a decoder over a buffer of bytes, returning `None` if it is too
+    /// short to hold a flushed encoder.
+    pub(crate) fn new(bytes: &'a [u8]) -> Option<Self> {
+        // The first byte is the initial cache of the encoder, always 0.
+        let code = bytes.get(1..5)?.try_into().ok()?;
+        Some(ByteRangeDecoder {
+            bytes,
+            pos: 5,
+            range: u32::MAX,
+            code: u32::from_be_bytes(code),
+        })
+    }
+
+    /// Returns `true` if all the bytes have been consumed.
+    pub(crate) fn is_done(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+}
+
+impl DecodeBit for ByteRangeDecoder<'_> {
+    fn decode_bit(&mut self, model: &mut BitModel) -> Option<bool> {
+        let bound = (self.range >> PROB_BITS) * u32::from(model.prob());
+        let bit = self.code >= bound;
+        match bit {
+            false => self.range = bound,
+            true => {
+                self.code -= bound;
+                self.range -= bound;
+            }
+        }
+        model.update(bit);
+        while self.range < TOP {
+            let byte = *self.bytes.get(self.pos)?;
+            self.pos += 1;
+            self.range <<= 8;
+            self.code = (self.code << 8) | u32::from(byte);
+        }
+        Some(bit)
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// adaptive range coding.
+///
+/// The integers are broken down into binary decisions with the same adaptive
+/// models as [`ArithEncoder`](crate::ArithEncoder), so they take about the
+/// same space, but the interval of the coder is renormalized a byte at a time
+/// rather than a bit at a time, which takes far fewer steps. The output is a
+/// stream of whole bytes, and the end of the sequence is encoded in it, when
+/// the encoder is finalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, RangeDecoder, RangeEncoder, VBEncoder};
+///
+/// let gaps: Vec<u32> = (0..1000).map(|i| if i % 50 == 0 { 900 } else { 3 }).collect();
+///
+/// let mut enc = RangeEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&gaps).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let mut vb = VBEncoder::new(Cursor::new(vec![]));
+/// vb.encode(&gaps).unwrap();
+/// assert!(result.len() * 8 < vb.finalize().unwrap().into_inner().len());
+///
+/// let dec = RangeDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), gaps);
+/// ```
+pub struct RangeEncoder<W> {
+    writer: BitWriter<W>,
+    coder: ByteRangeEncoder,
+    model: IntegerModel,
+}
+
+impl<W: Write> RangeEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        RangeEncoder {
+            writer,
+            coder: ByteRangeEncoder::new(),
+            model: IntegerModel::new(),
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for RangeEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            self.model.encode(&mut self.coder, num.to_u64());
+        }
+        let bytes = self.coder.take_bytes();
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        Ok(())
+    }
+
+    /// Encodes the end of the sequence, and writes the bytes that are left.
+    fn finalize(mut self) -> io::Result<W> {
+        self.model.encode_end(&mut self.coder);
+        self.coder.flush();
+        let bytes = self.coder.take_bytes();
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`RangeEncoder`].
+pub struct RangeDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> RangeDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        RangeDecoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for RangeDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::RangeCodeError;
+        let bytes = self.reader.read_to_end().map_err(|_| err())?.into_bytes();
+        let mut coder = ByteRangeDecoder::new(&bytes).ok_or_else(err)?;
+        let mut model = IntegerModel::new();
+
+        let mut nums = vec![];
+        while let Some(num) = model.decode(&mut coder).map_err(|_| err())? {
+            nums.push(T::from_u64(num).ok_or_else(err)?);
+        }
+        if !coder.is_done() {
+            return Err(err());
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::entropy::arith::BitArithEncoder;
+    use crate::code::testing::spread;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = RangeEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        RangeDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_coder() {
+        let bits: Vec<bool> = (1..5000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 61 == 0)
+            .collect();
+        let mut coder = ByteRangeEncoder::new();
+        let mut model = BitModel::new();
+        for bit in &bits {
+            coder.encode_bit(&mut model, *bit);
+        }
+        coder.flush();
+        let bytes = coder.take_bytes();
+        // One bit in eight is set, which takes about 0.54 bits per bit.
+        assert!(bytes.len() < bits.len() * 6 / 80);
+
+        let mut coder = ByteRangeDecoder::new(&bytes).unwrap();
+        let mut model = BitModel::new();
+        for bit in &bits {
+            assert_eq!(coder.decode_bit(&mut model), Some(*bit));
+        }
+        assert!(coder.is_done());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..2000)
+            .chain([0, 1, u64::MAX, u64::MAX - 1])
+            .collect();
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+
+        let mut enc = RangeEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[3_u8, 0]).unwrap();
+        enc.encode(&[255_u8]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(decode(result).unwrap(), vec![3, 0, 255]);
+    }
+
+    #[test]
+    fn test_shared_models() {
+        // With the same models, the two backends take about the same space.
+        let gaps: Vec<u64> = (1..10_000_u64)
+            .map(|i| 4 + (i.wrapping_mul(0x9E3779B97F4A7C15) >> 62))
+            .collect();
+        let mut coder = BitArithEncoder::new();
+        let mut model = IntegerModel::new();
+        for gap in &gaps {
+            model.encode(&mut coder, *gap);
+        }
+        model.encode_end(&mut coder);
+        coder.flush();
+        let arith_len = coder.take_bits().len().div_ceil(8);
+        let range_len = encode(&gaps).len();
+        assert!(range_len.abs_diff(arith_len) <= 5);
+    }
+
+    #[test]
+    fn test_errs() {
+        assert_eq!(decode(vec![0; 4]), Err(InvalidCodeError::RangeCodeError));
+        let bytes = encode(&(0..100_u64).collect::<Vec<_>>());
+        assert!(decode(bytes[..bytes.len() - 3].to_vec()).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(decode(trailing).is_err());
+        assert!(RangeDecoder::new(Cursor::new(bytes)).decode::<u8>().is_ok());
+        let bytes = encode(&[300]);
+        assert!(RangeDecoder::new(Cursor::new(bytes))
+            .decode::<u8>()
+            .is_err());
+    }
+}
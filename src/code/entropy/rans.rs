@@ -0,0 +1,492 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The number of bits of the normalized frequencies, which add up to
+/// `2^SCALE_BITS`.
+const SCALE_BITS: u32 = 16;
+
+/// The lower bound of the states, which are renormalized a byte at a time
+/// when they fall below it.
+const LOWER: u32 = 1 << 23;
+
+/// The number of interleaved states.
+const N_STATES: usize = 2;
+
+/// A table of values with their frequencies, normalized so that they add up
+/// to `2^16`, which is all that rANS needs to encode and decode them.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::RansTable;
+///
+/// let table = RansTable::from_frequencies([(7, 3), (2, 1)]).unwrap();
+/// assert_eq!(table.frequencies(), &[(2, 16384), (7, 49152)]);
+///
+/// // Every value keeps a frequency of at least 1.
+/// let table = RansTable::from_frequencies([(0, 1), (1, u64::MAX)]).unwrap();
+/// assert_eq!(table.frequencies(), &[(0, 1), (1, 65535)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RansTable {
+    /// The values with their normalized frequencies, in increasing order of
+    /// value.
+    freqs: Vec<(u64, u32)>,
+    /// The cumulative frequencies of the values before each value.
+    starts: Vec<u32>,
+}
+
+impl RansTable {
+    /// Builds the table of values with their frequencies, which are scaled to
+    /// add up to `2^16`. The frequencies of repeated values are added up, and
+    /// values with a frequency of zero are left out.
+    ///
+    /// Returns `None` if there are more than `2^16` distinct values, which
+    /// cannot all have a frequency.
+    pub fn from_frequencies(freqs: impl IntoIterator<Item = (u64, u64)>) -> Option<Self> {
+        let mut counts = BTreeMap::new();
+        for (value, freq) in freqs {
+            if freq > 0 {
+                *counts.entry(value).or_default() += u128::from(freq);
+            }
+        }
+        if counts.len() > 1 << SCALE_BITS {
+            return None;
+        }
+        let total: u128 = counts.values().sum();
+        let mut freqs: Vec<(u64, u32)> = counts
+            .into_iter()
+            .map(|(value, count)| {
+                let freq = (count << SCALE_BITS) / total;
+                (value, freq.max(1) as u32)
+            })
+            .collect();
+        normalize(&mut freqs);
+        Self::from_normalized(freqs)
+    }
+
+    /// Builds the table of values with their normalized frequencies, which
+    /// must be in strictly increasing order of value. Returns `None` if the
+    /// frequencies do not add up to `2^16`, unless there are none.
+    fn from_normalized(freqs: Vec<(u64, u32)>) -> Option<Self> {
+        let mut starts = Vec::with_capacity(freqs.len());
+        let mut total = 0_u32;
+        for (_, freq) in &freqs {
+            starts.push(total);
+            total = total.checked_add(*freq).filter(|_| *freq > 0)?;
+        }
+        if !freqs.is_empty() && total != 1 << SCALE_BITS {
+            return None;
+        }
+        Some(RansTable { freqs, starts })
+    }
+
+    /// Returns the values of the table with their normalized frequencies, in
+    /// increasing order of value.
+    pub fn frequencies(&self) -> &[(u64, u32)] {
+        &self.freqs
+    }
+
+    /// Returns the index of a value in the table.
+    fn index(&self, value: u64) -> Option<usize> {
+        self.freqs.binary_search_by_key(&value, |(v, _)| *v).ok()
+    }
+
+    /// Returns the index of the value whose slot, between its start and the
+    /// start of the next value, holds a number below `2^16`.
+    fn index_of_slot(&self, slot: u32) -> usize {
+        self.starts.partition_point(|start| *start <= slot) - 1
+    }
+
+    /// Writes the table: the number of values in Elias Gamma Encoding,
+    /// incremented by one, and then, for each value, its gap from the previous
+    /// value, or the value incremented by one for the first, and its
+    /// frequency, both in Elias Gamma Encoding.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the first
+    /// value is `u64::MAX`, which cannot be incremented.
+    fn write_bits(&self, buffer: &mut Vec<bool>) -> io::Result<()> {
+        let convention = UnaryConvention::default();
+        write_gamma_bits(self.freqs.len() + 1, convention, buffer);
+        let mut prev = None;
+        for &(value, freq) in &self.freqs {
+            let gap = match prev {
+                None => value.checked_add(1).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Number out of the range of the rANS table.",
+                    )
+                })?,
+                Some(prev) => value - prev,
+            };
+            write_gamma_bits(gap, convention, buffer);
+            write_gamma_bits(freq, convention, buffer);
+            prev = Some(value);
+        }
+        Ok(())
+    }
+
+    /// Decodes the table written at the start of a buffer of bits, returning
+    /// it along with the number of bits it occupies.
+    fn decode_prefix(bits: &[bool]) -> Result<(Self, usize), InvalidCodeError> {
+        let err = |_| InvalidCodeError::RansCodeError;
+        let convention = UnaryConvention::default();
+        let (count, mut pos) = decode_gamma_prefix::<u64>(bits, convention).map_err(err)?;
+        let count = usize::try_from(count - 1)
+            .ok()
+            .filter(|count| *count <= 1 << SCALE_BITS)
+            .ok_or(InvalidCodeError::RansCodeError)?;
+
+        let mut freqs = Vec::with_capacity(count);
+        let mut prev = None;
+        for _ in 0..count {
+            let (gap, len) = decode_gamma_prefix::<u64>(&bits[pos..], convention).map_err(err)?;
+            pos += len;
+            let value = match prev {
+                None => gap - 1,
+                Some(prev) => u64::checked_add(prev, gap).ok_or(InvalidCodeError::RansCodeError)?,
+            };
+            let (freq, len) = decode_gamma_prefix::<u32>(&bits[pos..], convention).map_err(err)?;
+            pos += len;
+            freqs.push((value, freq));
+            prev = Some(value);
+        }
+        let table = Self::from_normalized(freqs).ok_or(InvalidCodeError::RansCodeError)?;
+        Ok((table, pos))
+    }
+}
+
+/// Adjusts scaled frequencies of at least 1 so that they add up to `2^16`,
+/// adding the shortfall to the largest frequency, or taking the excess from
+/// the largest ones, down to 1.
+fn normalize(freqs: &mut [(u64, u32)]) {
+    let Some(largest) = (0..freqs.len()).max_by_key(|i| freqs[*i].1) else {
+        return;
+    };
+    let total: u32 = freqs.iter().map(|(_, freq)| freq).sum();
+    if total <= 1 << SCALE_BITS {
+        freqs[largest].1 += (1 << SCALE_BITS) - total;
+        return;
+    }
+    let mut excess = total - (1 << SCALE_BITS);
+    let mut order: Vec<usize> = (0..freqs.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(freqs[*i].1));
+    while excess > 0 {
+        for i in &order {
+            let cut = excess.min(freqs[*i].1 / 2).max(1).min(freqs[*i].1 - 1);
+            freqs[*i].1 -= cut;
+            excess -= cut;
+            if excess == 0 {
+                break;
+            }
+        }
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// static range Asymmetric Numeral Systems (rANS).
+///
+/// Each integer is coded in proportion to its frequency in a [`RansTable`],
+/// which comes within a fraction of a bit of the entropy of the sequence for
+/// the whole sequence, unlike the whole bits of a Huffman code. The table is
+/// built from the frequencies of the integers, or given up front, and written
+/// first. Integers must be less than `u64::MAX`.
+///
+/// The integers are buffered, and encoded in reverse order when the encoder
+/// is finalized, so that they are decoded in order. They take turns between
+/// two states, which the decoder can update independently of each other.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, RansDecoder, RansEncoder};
+///
+/// let nums: Vec<u32> = (0..1000).map(|i| [1, 1, 1, 2, 1, 3, 1, 1][i % 8]).collect();
+///
+/// let mut enc = RansEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// // About 1.06 bits per number, after the table and the states.
+/// assert!(result.len() < 160);
+///
+/// let dec = RansDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct RansEncoder<W> {
+    writer: BitWriter<W>,
+    table: Option<RansTable>,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> RansEncoder<W> {
+    /// Creates an encoder that builds its table from the frequencies of the
+    /// integers.
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        RansEncoder {
+            writer,
+            table: None,
+            nums: vec![],
+        }
+    }
+
+    /// Creates an encoder with a given table, which must hold every integer
+    /// that is encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Decoder, Encoder, RansDecoder, RansEncoder, RansTable};
+    ///
+    /// let table = RansTable::from_frequencies([(0, 90), (1, 9), (2, 1)]).unwrap();
+    /// let mut enc = RansEncoder::with_table(Cursor::new(vec![]), table);
+    /// enc.encode(&[0_u8, 0, 2, 0, 1]).unwrap();
+    /// assert!(enc.encode(&[3_u8]).is_err());
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let dec = RansDecoder::new(Cursor::new(result));
+    /// assert_eq!(dec.decode::<u8>().unwrap(), vec![0, 0, 2, 0, 1]);
+    /// ```
+    pub fn with_table(writer: W, table: RansTable) -> Self {
+        RansEncoder {
+            table: Some(table),
+            ..Self::new(writer)
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for RansEncoder<W> {
+    /// Buffers a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is `u64::MAX`, or is not in the table of the encoder.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let nums: Vec<u64> = nums.iter().map(|num| num.to_u64()).collect();
+        let valid = match &self.table {
+            Some(table) => nums.iter().all(|num| table.index(*num).is_some()),
+            None => !nums.contains(&u64::MAX),
+        };
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Number cannot be encoded with the rANS table.",
+            ));
+        }
+        self.nums.extend(nums);
+        Ok(())
+    }
+
+    /// Builds the table of the buffered numbers, unless it was given, and
+    /// writes it, followed by the number of numbers in Elias Gamma Encoding,
+    /// incremented by one, and the bytes of the states.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if there are
+    /// more than `2^16` distinct numbers.
+    fn finalize(mut self) -> io::Result<W> {
+        let table = match self.table.take() {
+            Some(table) => table,
+            None => RansTable::from_frequencies(self.nums.iter().map(|num| (*num, 1))).ok_or_else(
+                || {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Too many distinct numbers for rANS.",
+                    )
+                },
+            )?,
+        };
+        let mut bits = vec![];
+        table.write_bits(&mut bits)?;
+        write_gamma_bits(self.nums.len() + 1, UnaryConvention::default(), &mut bits);
+        for byte in encode_rans(&table, &self.nums) {
+            bits.extend((0..8).rev().map(|shift| (byte >> shift) & 1 == 1));
+        }
+        self.writer.write_bits(&bits)?;
+        self.writer.finalize()
+    }
+}
+
+/// Encodes numbers, all of which are in the table, with interleaved states,
+/// returning the bytes in the order they are decoded.
+fn encode_rans(table: &RansTable, nums: &[u64]) -> Vec<u8> {
+    let mut states = [LOWER; N_STATES];
+    let mut bytes = vec![];
+    for (i, num) in nums.iter().enumerate().rev() {
+        let index = table.index(*num).expect("Number is in the table.");
+        let (freq, start) = (table.freqs[index].1, table.starts[index]);
+        let state = &mut states[i % N_STATES];
+        let max = ((LOWER >> SCALE_BITS) << 8) * freq;
+        while *state >= max {
+            bytes.push(*state as u8);
+            *state >>= 8;
+        }
+        *state = ((*state / freq) << SCALE_BITS) + *state % freq + start;
+    }
+    for state in states.iter().rev() {
+        bytes.extend(state.to_le_bytes());
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`RansEncoder`].
+pub struct RansDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> RansDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        RansDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for RansDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::RansCodeError;
+        let table = self.cursor.decode_with(RansTable::decode_prefix)?;
+        let count = self.cursor.decode_with(|bits| {
+            decode_gamma_prefix::<u64>(bits, UnaryConvention::default()).map_err(|_| err())
+        })? - 1;
+        // A stream of numbers needs a table to decode them from.
+        if count > 0 && table.freqs.is_empty() {
+            return Err(err());
+        }
+
+        let bits = self.cursor.remaining()?;
+        if !bits.len().is_multiple_of(8) {
+            return Err(err());
+        }
+        let mut bytes = bits.chunks(8).map(|byte| {
+            byte.iter()
+                .fold(0_u32, |acc, bit| (acc << 1) | u32::from(*bit))
+        });
+        let mut states = [0_u32; N_STATES];
+        for state in &mut states {
+            for _ in 0..4 {
+                *state = (*state << 8) | bytes.next().ok_or_else(err)?;
+            }
+        }
+
+        let mask = (1 << SCALE_BITS) - 1;
+        let mut nums = vec![];
+        for i in 0..count {
+            let state = &mut states[i as usize % N_STATES];
+            let slot = *state & mask;
+            let index = table.index_of_slot(slot);
+            let (num, freq) = table.freqs[index];
+            *state = freq * (*state >> SCALE_BITS) + slot - table.starts[index];
+            while *state < LOWER {
+                *state = (*state << 8) | bytes.next().ok_or_else(err)?;
+            }
+            nums.push(T::from_u64(num).ok_or_else(err)?);
+        }
+        // The states end where the encoder started them.
+        if states != [LOWER; N_STATES] || bytes.next().is_some() {
+            return Err(err());
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = RansEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        RansDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_table() {
+        let table = RansTable::from_frequencies([(5, 1), (9, 1), (5, 1), (7, 0)]).unwrap();
+        assert_eq!(table.frequencies(), &[(5, 43691), (9, 21845)]);
+        assert_eq!(table.index_of_slot(43690), 0);
+        assert_eq!(table.index_of_slot(43691), 1);
+
+        // Many rare values take the excess from the common one.
+        let freqs = (0..60_000).map(|i| (i, 1)).chain([(60_000, 1 << 40)]);
+        let table = RansTable::from_frequencies(freqs).unwrap();
+        let total: u32 = table.frequencies().iter().map(|(_, freq)| freq).sum();
+        assert_eq!(total, 1 << 16);
+        assert_eq!(table.frequencies()[0], (0, 1));
+        assert!(RansTable::from_frequencies((0..70_000).map(|i| (i, 1))).is_none());
+
+        let mut bits = vec![];
+        table.write_bits(&mut bits).unwrap();
+        assert_eq!(RansTable::decode_prefix(&bits), Ok((table, bits.len())));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = (1..5000_u64)
+            .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15) >> 58).pow(2))
+            .chain([u64::MAX - 1, 0])
+            .collect();
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+        assert_eq!(decode(encode(&[42])).unwrap(), vec![42]);
+
+        // A single value takes no bits at all, but for the header and states.
+        assert!(encode(&[7; 10_000]).len() < 20);
+    }
+
+    #[test]
+    fn test_entropy() {
+        // The entropy of one in sixteen is about 0.34 bits per number.
+        let nums: Vec<u64> = (0..16_000).map(|i| u64::from(i % 16 == 0)).collect();
+        let bytes = encode(&nums);
+        assert!(bytes.len() < 16_000 * 35 / 800);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = RansEncoder::new(Cursor::new(vec![]));
+        assert!(enc.encode(&[1, u64::MAX]).is_err());
+        let mut enc = RansEncoder::new(Cursor::new(vec![]));
+        enc.encode(&(0..70_000_u64).collect::<Vec<_>>()).unwrap();
+        assert!(enc.finalize().is_err());
+
+        let bytes = encode(&(0..300_u64).map(|n| n % 7).collect::<Vec<_>>());
+        assert!(decode(bytes[..bytes.len() - 2].to_vec()).is_err());
+        // The last bytes the decoder reads are the first the encoder shifted
+        // out of its states, so the states do not end where they started.
+        let mut corrupt = bytes.clone();
+        corrupt[bytes.len() - 2] ^= 0x10;
+        assert_eq!(decode(corrupt), Err(InvalidCodeError::RansCodeError));
+        let bytes = encode(&[300]);
+        assert!(RansDecoder::new(Cursor::new(bytes)).decode::<u8>().is_err());
+
+        let table = RansTable::from_frequencies([(u64::MAX, 1)]).unwrap();
+        let mut enc = RansEncoder::with_table(Cursor::new(vec![]), table);
+        enc.encode(&[u64::MAX]).unwrap();
+        assert!(enc.finalize().is_err());
+
+        // An empty table, followed by a count of one number and the states.
+        let mut bits = vec![false, true, false, false];
+        for byte in [LOWER, LOWER].iter().flat_map(|state| state.to_be_bytes()) {
+            bits.extend((0..8).rev().map(|shift| (byte >> shift) & 1 == 1));
+        }
+        let mut writer = BitWriter::new(vec![], true);
+        writer.write_bits(&bits).unwrap();
+        let bytes = writer.finalize().unwrap();
+        assert_eq!(decode(bytes), Err(InvalidCodeError::RansCodeError));
+    }
+}
@@ -0,0 +1,415 @@
+use std::io::{self, Read, Write};
+
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::bits_to_numeric;
+use crate::num::convert::write_fixed_bits;
+
+/// The number of bits of the header, that holds the base-2 logarithm of the
+/// number of previous values.
+const HEADER_BITS: u32 = 3;
+
+/// The largest number of previous values that a value can be compared with.
+const MAX_PREVIOUS_VALUES: usize = 128;
+
+/// The number of leading zeros that each 3-bit code stands for. The leading
+/// zeros of an XOR are rounded down to the closest of them.
+const LEADING_ZEROS: [u32; 8] = [0, 8, 12, 16, 18, 20, 22, 24];
+
+/// The number of bits of the number of significant bits of an XOR.
+const SIGNIFICANT_BITS: u32 = 6;
+
+/// The leading zeros of the previous XOR, when they cannot be reused.
+const NO_LEADING_ZEROS: u32 = u32::MAX;
+
+/// Returns the 3-bit code of the leading zeros of an XOR, along with the
+/// leading zeros it stands for.
+fn round_leading_zeros(xor: u64) -> (u8, u32) {
+    let lead = xor.leading_zeros();
+    let code = LEADING_ZEROS
+        .iter()
+        .rposition(|l| *l <= lead)
+        .expect("The first code is 0.");
+    (code as u8, LEADING_ZEROS[code])
+}
+
+/// Reads `width` bits at a position of a buffer of bits.
+fn read_bits(bits: &[bool], pos: usize, width: u32) -> Result<u64, InvalidCodeError> {
+    let bits = bits
+        .get(pos..pos + width as usize)
+        .ok_or(InvalidCodeError::ChimpCodeError)?;
+    bits_to_numeric(bits).map_err(|_| InvalidCodeError::ChimpCodeError)
+}
+
+/// The previous values of a stream, shared by the encoder and the decoder.
+#[derive(Debug, Clone)]
+struct Window {
+    /// The base-2 logarithm of the number of previous values.
+    log_len: u32,
+    /// The previous values, in a ring buffer.
+    values: Vec<u64>,
+    /// The index, plus one, of the last value with each key, or zero if no
+    /// value has the key.
+    last_seen: Vec<usize>,
+    /// The number of values of the stream so far.
+    count: usize,
+    /// The leading zeros of the previous XOR.
+    leading_zeros: u32,
+}
+
+impl Window {
+    fn new(log_len: u32) -> Self {
+        let mut window = Window {
+            log_len,
+            values: vec![0; 1 << log_len],
+            last_seen: vec![],
+            count: 0,
+            leading_zeros: NO_LEADING_ZEROS,
+        };
+        window.last_seen = vec![0; 1 << window.threshold()];
+        window
+    }
+
+    /// Returns the number of trailing zeros above which an XOR is written
+    /// without them.
+    fn threshold(&self) -> u32 {
+        6 + self.log_len
+    }
+
+    /// Returns the key of a value, that is its lowest bits.
+    fn key(&self, value: u64) -> usize {
+        (value & ((1 << self.threshold()) - 1)) as usize
+    }
+
+    fn push(&mut self, value: u64) {
+        let slot = self.count % self.values.len();
+        self.values[slot] = value;
+        let key = self.key(value);
+        self.count += 1;
+        self.last_seen[key] = self.count;
+    }
+
+    /// Calculates the Chimp code of the next value, comparing it with the
+    /// previous value with the same key, if they share enough trailing bits,
+    /// and with the immediately previous value otherwise.
+    fn write_bits(&mut self, value: u64, buffer: &mut Vec<bool>) {
+        if self.count == 0 {
+            write_fixed_bits(&value, u64::BITS, buffer);
+            self.push(value);
+            return;
+        }
+
+        let len = self.values.len();
+        let threshold = self.threshold();
+        let mut reference = self.count - 1;
+        if let Some(idx) = self.last_seen[self.key(value)].checked_sub(1) {
+            let in_window = self.count - idx <= len;
+            if in_window && (value ^ self.values[idx % len]).trailing_zeros() > threshold {
+                reference = idx;
+            }
+        }
+
+        let xor = value ^ self.values[reference % len];
+        if xor == 0 {
+            buffer.extend([false, false]);
+            write_fixed_bits(&(reference % len), self.log_len, buffer);
+            self.leading_zeros = NO_LEADING_ZEROS;
+        } else {
+            let (code, lead) = round_leading_zeros(xor);
+            let trail = xor.trailing_zeros();
+            if trail > threshold {
+                let significant = u64::BITS - lead - trail;
+                buffer.extend([false, true]);
+                write_fixed_bits(&(reference % len), self.log_len, buffer);
+                write_fixed_bits(&code, 3, buffer);
+                write_fixed_bits(&significant, SIGNIFICANT_BITS, buffer);
+                write_fixed_bits(&(xor >> trail), significant, buffer);
+                self.leading_zeros = NO_LEADING_ZEROS;
+            } else if lead == self.leading_zeros {
+                buffer.extend([true, false]);
+                write_fixed_bits(&xor, u64::BITS - lead, buffer);
+            } else {
+                buffer.extend([true, true]);
+                write_fixed_bits(&code, 3, buffer);
+                write_fixed_bits(&xor, u64::BITS - lead, buffer);
+                self.leading_zeros = lead;
+            }
+        }
+        self.push(value);
+    }
+
+    /// Decodes the Chimp code of the next value at the start of a buffer of
+    /// bits, returning the value along with the number of bits its code
+    /// occupies.
+    fn decode_prefix(&mut self, bits: &[bool]) -> Result<(u64, usize), InvalidCodeError> {
+        if self.count == 0 {
+            let value = read_bits(bits, 0, u64::BITS)?;
+            self.push(value);
+            return Ok((value, u64::BITS as usize));
+        }
+
+        let len = self.values.len();
+        let flag = read_bits(bits, 0, 2)?;
+        let mut pos = 2;
+        let (reference, xor) = match flag {
+            0b00 | 0b01 => {
+                let reference = read_bits(bits, pos, self.log_len)? as usize;
+                pos += self.log_len as usize;
+                if reference >= self.count {
+                    return Err(InvalidCodeError::ChimpCodeError);
+                }
+                self.leading_zeros = NO_LEADING_ZEROS;
+                if flag == 0b00 {
+                    (reference, 0)
+                } else {
+                    let lead = LEADING_ZEROS[read_bits(bits, pos, 3)? as usize];
+                    let significant = read_bits(bits, pos + 3, SIGNIFICANT_BITS)? as u32;
+                    pos += 3 + SIGNIFICANT_BITS as usize;
+                    if significant == 0 || lead + significant > u64::BITS {
+                        return Err(InvalidCodeError::ChimpCodeError);
+                    }
+                    let xor = read_bits(bits, pos, significant)?;
+                    pos += significant as usize;
+                    (reference, xor << (u64::BITS - lead - significant))
+                }
+            }
+            _ => {
+                if flag == 0b11 {
+                    self.leading_zeros = LEADING_ZEROS[read_bits(bits, pos, 3)? as usize];
+                    pos += 3;
+                } else if self.leading_zeros == NO_LEADING_ZEROS {
+                    return Err(InvalidCodeError::ChimpCodeError);
+                }
+                let width = u64::BITS - self.leading_zeros;
+                let xor = read_bits(bits, pos, width)?;
+                pos += width as usize;
+                ((self.count - 1) % len, xor)
+            }
+        };
+
+        let value = self.values[reference] ^ xor;
+        self.push(value);
+        Ok((value, pos))
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of floating-point
+/// numbers using Chimp compression.
+///
+/// Like Gorilla compression, each value is XORed with a previous value, and
+/// only the meaningful bits of the XOR are written. Chimp rounds the leading
+/// zeros of the XOR to one of eight values, writes the XORs with many
+/// trailing zeros without them, and, in the Chimp128 variant, compares each
+/// value with the most recent of up to 128 previous values whose lowest bits
+/// are the same, instead of only the immediately previous one. This suits
+/// time series, whose values often repeat or differ only in their lowest
+/// mantissa bits.
+///
+/// Each XOR is prefixed by a 2-bit flag:
+/// - `00`: the value is equal to the previous value at the following index.
+/// - `01`: the XOR with the previous value at the following index is written
+///   without its leading and trailing zeros, whose number follows.
+/// - `10`: the XOR with the immediately previous value has the same leading
+///   zeros as the last XOR, and is written without them.
+/// - `11`: the XOR with the immediately previous value is written without its
+///   leading zeros, whose 3-bit code follows.
+///
+/// The number of previous values is written in the header of the stream, so
+/// the decoder does not need to know it in advance.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{ChimpDecoder, ChimpEncoder};
+///
+/// let values = [21.5, 21.5, 21.75, 22.0, 21.5];
+/// let mut enc = ChimpEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&values).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert!(result.len() < values.len() * 8);
+///
+/// let dec = ChimpDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode().unwrap(), values);
+/// ```
+pub struct ChimpEncoder<W> {
+    writer: BitWriter<W>,
+    window: Window,
+}
+
+impl<W: Write> ChimpEncoder<W> {
+    /// Creates a new Chimp128 encoder, that compares each value with up to
+    /// 128 previous values.
+    pub fn new(writer: W) -> Self {
+        Self::with_previous_values(writer, MAX_PREVIOUS_VALUES)
+    }
+
+    /// Creates a new encoder that compares each value with up to
+    /// `previous_values` previous values. With a single previous value, this
+    /// is the original Chimp compression.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `previous_values` is not a power of two, or is greater than
+    /// 128.
+    pub fn with_previous_values(writer: W, previous_values: usize) -> Self {
+        assert!(
+            previous_values.is_power_of_two() && previous_values <= MAX_PREVIOUS_VALUES,
+            "The number of previous values must be a power of two up to 128."
+        );
+        let log_len = previous_values.trailing_zeros();
+
+        let mut writer = BitWriter::new(writer, true);
+        let mut header = Vec::with_capacity(HEADER_BITS as usize);
+        write_fixed_bits(&log_len, HEADER_BITS, &mut header);
+        for bit in header {
+            writer.get_mut().push(bit);
+        }
+        ChimpEncoder {
+            writer,
+            window: Window::new(log_len),
+        }
+    }
+
+    /// Returns the number of previous values that each value is compared
+    /// with.
+    pub fn previous_values(&self) -> usize {
+        self.window.values.len()
+    }
+
+    /// Encodes and writes a sequence of floating-point numbers.
+    pub fn encode(&mut self, values: &[f64]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for value in values {
+            bits.clear();
+            self.window.write_bits(value.to_bits(), &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    pub fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using Chimp
+/// compression.
+///
+/// The number of previous values is read from the header of the stream, as
+/// written by [`ChimpEncoder`].
+pub struct ChimpDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> ChimpDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        ChimpDecoder { cursor }
+    }
+
+    /// Reads and decodes the encoded floating-point numbers.
+    pub fn decode(mut self) -> Result<Vec<f64>, InvalidCodeError> {
+        let log_len = self.cursor.decode_with(|bits| {
+            let log_len = read_bits(bits, 0, HEADER_BITS)? as u32;
+            Ok((log_len, HEADER_BITS as usize))
+        })?;
+        let mut window = Window::new(log_len);
+        let mut values = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let value = self.cursor.decode_with(|bits| window.decode_prefix(bits))?;
+            values.push(f64::from_bits(value));
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(values: &[f64], previous_values: usize) -> Vec<u8> {
+        let mut enc = ChimpEncoder::with_previous_values(Cursor::new(vec![]), previous_values);
+        enc.encode(values).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Vec<f64> {
+        ChimpDecoder::new(Cursor::new(bytes)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_round_leading_zeros() {
+        assert_eq!(round_leading_zeros(u64::MAX), (0, 0));
+        assert_eq!(round_leading_zeros(1 << 52), (1, 8));
+        assert_eq!(round_leading_zeros(1 << 46), (3, 16));
+        assert_eq!(round_leading_zeros(1), (7, 24));
+    }
+
+    #[test]
+    fn test_write_bits() {
+        let mut window = Window::new(0);
+        let mut bits = vec![];
+        window.write_bits(1.0_f64.to_bits(), &mut bits);
+        assert_eq!(bits.len(), 64);
+
+        bits.clear();
+        window.write_bits(1.0_f64.to_bits(), &mut bits);
+        assert_eq!(bits, vec![false, false]);
+
+        // The XOR of 1.0 and 1.5 is a single bit, with many trailing zeros.
+        bits.clear();
+        window.write_bits(1.5_f64.to_bits(), &mut bits);
+        assert_eq!(bits.len(), 2 + 3 + 6 + 1);
+        assert_eq!(&bits[..2], &[false, true]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut values: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.37).sin() * 100.0).collect();
+        values.extend([
+            0.0,
+            -0.0,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            1e-300,
+        ]);
+        values.extend((0..200).map(|i| [1.5, 2.25, 3.125][i % 3]));
+        for previous_values in [1, 2, 16, 128] {
+            let decoded = decode(encode(&values, previous_values));
+            let bits: Vec<u64> = decoded.iter().map(|v| v.to_bits()).collect();
+            let expected: Vec<u64> = values.iter().map(|v| v.to_bits()).collect();
+            assert_eq!(bits, expected);
+        }
+        assert!(decode(encode(&[], 128)).is_empty());
+        assert!(decode(encode(&[f64::NAN], 1))[0].is_nan());
+    }
+
+    #[test]
+    fn test_previous_values_window() {
+        // Values that repeat with a period longer than one are found in the
+        // window of Chimp128, but not by Chimp.
+        let values: Vec<f64> = (0..1000).map(|i| [3.7, 12.9, -8.1, 0.3][i % 4]).collect();
+        let chimp = encode(&values, 1);
+        let chimp128 = encode(&values, 128);
+        assert!(chimp128.len() * 2 < chimp.len());
+        assert_eq!(decode(chimp128), values);
+    }
+
+    #[test]
+    fn test_errs() {
+        let dec = ChimpDecoder::new(Cursor::new(vec![0b00000001]));
+        assert!(dec.decode().is_err());
+        let dec = ChimpDecoder::new(Cursor::new(vec![0x00, 0x01]));
+        assert!(dec.decode().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_previous_values() {
+        ChimpEncoder::with_previous_values(Cursor::new(vec![]), 3);
+    }
+}
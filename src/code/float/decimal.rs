@@ -0,0 +1,231 @@
+use std::io::{self, Cursor, Read, Write};
+
+use crate::code::float::chimp::{ChimpDecoder, ChimpEncoder};
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::convert::{unzigzag, zigzag};
+
+/// The largest number of fractional digits that values are scaled by.
+pub const MAX_SCALE: u32 = 9;
+
+/// The header byte of a stream whose values are compressed with Chimp128.
+const FALLBACK: u8 = 0xFF;
+
+/// The powers of ten up to the largest scale, written out so that they are
+/// exact.
+const POWERS_OF_TEN: [f64; MAX_SCALE as usize + 1] =
+    [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9];
+
+/// The largest magnitude of a scaled value, below which all integers are
+/// represented exactly in an `f64`.
+const MAX_SCALED: f64 = (1_u64 << f64::MANTISSA_DIGITS) as f64;
+
+/// Returns the scaled integer of a value, if dividing it by the power of ten
+/// of the scale restores the value exactly.
+fn scale_value(value: f64, scale: u32) -> Option<i64> {
+    let power = POWERS_OF_TEN[scale as usize];
+    let scaled = (value * power).round();
+    if !scaled.is_finite() || scaled.abs() >= MAX_SCALED {
+        return None;
+    }
+    let num = scaled as i64;
+    let restored = num as f64 / power;
+    (restored.to_bits() == value.to_bits()).then_some(num)
+}
+
+/// Returns the smallest number of fractional digits, up to [`MAX_SCALE`],
+/// such that all the values are integers when multiplied by its power of
+/// ten, and are restored exactly when divided back.
+///
+/// Returns `None` if there is no such scale, for example if a value is not
+/// finite or has too many fractional digits.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::float::decimal::decimal_scale;
+///
+/// assert_eq!(decimal_scale(&[1.0, -3.0]), Some(0));
+/// assert_eq!(decimal_scale(&[21.5, 19.25, 20.125]), Some(3));
+/// assert_eq!(decimal_scale(&[0.1, std::f64::consts::PI]), None);
+/// ```
+pub fn decimal_scale(values: &[f64]) -> Option<u32> {
+    (0..=MAX_SCALE).find(|scale| values.iter().all(|v| scale_value(*v, *scale).is_some()))
+}
+
+/// A structure that wraps a writer and encodes a sequence of floating-point
+/// numbers, by scaling them to integers when they have few fractional
+/// digits.
+///
+/// Sensor readings, prices and other measurements are often quantized to a
+/// fixed number of decimal digits, and compress far better as integers than
+/// as floating-point numbers. The encoder buffers the values, and when it is
+/// finalized, looks for the smallest [`decimal_scale`] of the values. If there
+/// is one, the values are multiplied by its power of ten, and the gaps between
+/// the resulting integers are written, zigzagged, in Variable Byte Encoding,
+/// after a header byte with the scale. Otherwise, the header byte is `0xFF`
+/// and the values are compressed with [`ChimpEncoder`], so the encoding is
+/// lossless in both cases.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{DecimalDecoder, DecimalEncoder};
+///
+/// let values = [21.37, 21.4, 21.38, -0.5];
+/// let mut enc = DecimalEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&values).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result[0], 2);
+/// assert!(result.len() < 12);
+///
+/// let dec = DecimalDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode().unwrap(), values);
+/// ```
+pub struct DecimalEncoder<W> {
+    writer: BitWriter<W>,
+    values: Vec<f64>,
+}
+
+impl<W: Write> DecimalEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        DecimalEncoder {
+            writer,
+            values: vec![],
+        }
+    }
+
+    /// Buffers a sequence of floating-point numbers, to be encoded when the
+    /// encoder is finalized.
+    pub fn encode(&mut self, values: &[f64]) -> io::Result<()> {
+        self.values.extend_from_slice(values);
+        Ok(())
+    }
+
+    /// Encodes the buffered numbers, and writes them in the wrapped writer.
+    pub fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        match decimal_scale(&self.values) {
+            Some(scale) => {
+                bytes.push(scale as u8);
+                let mut prev = 0;
+                for value in &self.values {
+                    let num = scale_value(*value, scale).expect("The scale fits all values.");
+                    write_vb_bytes(zigzag(num - prev), &mut bytes);
+                    prev = num;
+                }
+            }
+            None => {
+                bytes.push(FALLBACK);
+                let mut ce = ChimpEncoder::new(Cursor::new(vec![]));
+                ce.encode(&self.values)?;
+                bytes.extend(ce.finalize()?.into_inner());
+            }
+        }
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`DecimalEncoder`].
+pub struct DecimalDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> DecimalDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        DecimalDecoder { reader }
+    }
+
+    /// Reads and decodes the encoded floating-point numbers.
+    pub fn decode(self) -> Result<Vec<f64>, InvalidCodeError> {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+        let (&scale, bytes) = bytes
+            .split_first()
+            .ok_or(InvalidCodeError::DecimalCodeError)?;
+        if scale == FALLBACK {
+            return ChimpDecoder::new(Cursor::new(bytes)).decode();
+        }
+        if u32::from(scale) > MAX_SCALE {
+            return Err(InvalidCodeError::DecimalCodeError);
+        }
+
+        let power = POWERS_OF_TEN[scale as usize];
+        let (mut values, mut pos, mut prev) = (vec![], 0, 0_i64);
+        while pos < bytes.len() {
+            let (gap, len) =
+                decode_vb_prefix(&bytes[pos..]).map_err(|_| InvalidCodeError::DecimalCodeError)?;
+            let num = prev
+                .checked_add(unzigzag(gap))
+                .ok_or(InvalidCodeError::DecimalCodeError)?;
+            values.push(num as f64 / power);
+            prev = num;
+            pos += len;
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(values: &[f64]) -> Vec<u8> {
+        let mut enc = DecimalEncoder::new(Cursor::new(vec![]));
+        enc.encode(values).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Vec<f64> {
+        DecimalDecoder::new(Cursor::new(bytes)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_decimal_scale() {
+        assert_eq!(decimal_scale(&[]), Some(0));
+        assert_eq!(decimal_scale(&[0.1, 0.25]), Some(2));
+        assert_eq!(decimal_scale(&[123.456789]), Some(6));
+        assert_eq!(decimal_scale(&[0.1234567891]), None);
+        assert_eq!(decimal_scale(&[-0.0]), None);
+        assert_eq!(decimal_scale(&[f64::NAN]), None);
+        assert_eq!(decimal_scale(&[1e20]), None);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let values: Vec<f64> = (0..1000)
+            .map(|i| ((i * 37) % 2001 - 1000) as f64 / 100.0)
+            .collect();
+        let result = encode(&values);
+        assert_eq!(result[0], 2);
+        assert!(result.len() < values.len() * 3);
+        assert_eq!(decode(result), values);
+        assert!(decode(encode(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_fallback() {
+        let values = [1.5, std::f64::consts::E, -0.0, f64::INFINITY];
+        let result = encode(&values);
+        assert_eq!(result[0], FALLBACK);
+        let decoded: Vec<u64> = decode(result).iter().map(|v| v.to_bits()).collect();
+        let expected: Vec<u64> = values.iter().map(|v| v.to_bits()).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_errs() {
+        let dec = DecimalDecoder::new(Cursor::new(vec![]));
+        assert!(dec.decode().is_err());
+        let dec = DecimalDecoder::new(Cursor::new(vec![10, 0x81]));
+        assert!(dec.decode().is_err());
+        let dec = DecimalDecoder::new(Cursor::new(vec![1, 0x01]));
+        assert!(dec.decode().is_err());
+    }
+}
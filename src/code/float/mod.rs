@@ -0,0 +1,2 @@
+pub mod chimp;
+pub mod decimal;
@@ -0,0 +1,263 @@
+use std::io::{self, Read, Write};
+
+use super::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Binary Adaptive Sequential Coding (BASC).
+///
+/// Each number is written with the bit-width of its predecessor, which adapts
+/// the code to the local magnitude of the sequence without per-block headers.
+/// If a number *n* of width *c* fits in the width *b* of its predecessor, it is
+/// encoded as a 0-bit followed by *n* in *b* bits. Otherwise, the increase
+/// *c - b* is encoded in unary, followed by all the bits of *n* except the
+/// leading 1-bit. The width of the first predecessor is 0.
+///
+/// For example, after the number 5 (101), the number 6 (110) is encoded as
+/// 0 110, and the number 9 (1001) as 10 001.
+pub struct BascEncoder<W> {
+    writer: BitWriter<W>,
+    width: u32,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> BascEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_convention(writer, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the increases of the width in unary
+    /// with the specified convention.
+    pub fn with_convention(writer: W, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::new(writer, true);
+        BascEncoder {
+            writer,
+            width: 0,
+            convention,
+        }
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for BascEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            self.width = write_basc_bits(n.to_u64(), self.width, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for BascEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// Returns the number of significant bits of a number.
+fn bit_width(num: u64) -> u32 {
+    u64::BITS - num.leading_zeros()
+}
+
+/// Calculates the BASC code of a number, given the width of its predecessor,
+/// and returns the width of the number.
+pub(crate) fn write_basc_bits(
+    num: u64,
+    width: u32,
+    convention: UnaryConvention,
+    buffer: &mut Vec<bool>,
+) -> u32 {
+    let num_width = bit_width(num);
+    if num_width <= width {
+        buffer.push(convention.stop_bit());
+        write_fixed_bits(&num, width, buffer);
+    } else {
+        let increase = (num_width - width) as usize;
+        buffer.extend(UnaryEncoder::encode_one_with(increase, convention));
+        write_fixed_bits(&num, num_width - 1, buffer);
+    }
+    num_width
+}
+
+/// Decodes the BASC code at the start of a buffer of bits, given the width of
+/// its predecessor, returning the number along with the number of bits its
+/// code occupies.
+pub(crate) fn decode_basc_prefix(
+    bits: &[bool],
+    width: u32,
+    convention: UnaryConvention,
+) -> Result<(u64, usize), InvalidCodeError> {
+    let (increase, unary_len) = UnaryDecoder::decode_prefix_with(bits, convention)
+        .map_err(|_| InvalidCodeError::BascCodeError)?;
+    let idx = unary_len - 1;
+    let increase = u32::try_from(increase).map_err(|_| InvalidCodeError::BascCodeError)?;
+
+    let num_width = width
+        .checked_add(increase)
+        .filter(|w| *w <= u64::BITS)
+        .ok_or(InvalidCodeError::BascCodeError)?;
+    let (len, leading) = if increase == 0 {
+        (width as usize, None)
+    } else {
+        (num_width as usize - 1, Some(true))
+    };
+
+    let value_bits = bits[idx + 1..]
+        .get(..len)
+        .ok_or(InvalidCodeError::BascCodeError)?;
+    let mut n_bits = Vec::with_capacity(len + 1);
+    n_bits.extend(leading);
+    n_bits.extend_from_slice(value_bits);
+
+    let num = bits_to_numeric::<u64>(&n_bits).map_err(|_| InvalidCodeError::BascCodeError)?;
+    Ok((num, idx + 1 + len))
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Binary Adaptive Sequential Coding (BASC).
+///
+/// Each number is read with the bit-width of its predecessor, unless it is
+/// preceded by a unary encoded increase of the width, as described in
+/// [`BascEncoder`].
+///
+/// The unary convention is not recorded in the stream, so a stream encoded
+/// with [`BascEncoder::with_convention`] must be decoded with the same
+/// convention.
+pub struct BascDecoder<R> {
+    cursor: BitCursor<R>,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> BascDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_convention(reader, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the increases of the width in unary
+    /// with the specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        BascDecoder { cursor, convention }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<R: Read> Decoder<R> for BascDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let convention = self.convention;
+        let mut width = 0;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_basc_prefix(bits, width, convention))?;
+            width = bit_width(num);
+            nums.push(T::from_u64(num).ok_or(InvalidCodeError::BascCodeError)?);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GammaEncoder;
+    use std::io::Cursor;
+
+    fn encode_one(num: u64, width: u32) -> Vec<bool> {
+        let mut bits = vec![];
+        write_basc_bits(num, width, UnaryConvention::default(), &mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        assert_eq!(encode_one(6, 3), vec![false, true, true, false]);
+        assert_eq!(encode_one(9, 3), vec![true, false, false, false, true]);
+        assert_eq!(encode_one(1, 3), vec![false, false, false, true]);
+        assert_eq!(encode_one(0, 0), vec![false]);
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let ones = UnaryConvention::Ones;
+        let bits = encode_one(9, 3);
+        assert_eq!(decode_basc_prefix(&bits, 3, ones), Ok((9, 5)));
+        assert!(decode_basc_prefix(&bits[..4], 3, ones).is_err());
+        assert!(decode_basc_prefix(&[true; 4], 3, ones).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [5_u64, 6, 9, 1, 0, 1000, 1001, 0, u64::MAX, 3];
+        let mut enc = BascEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = BascDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut bits = vec![];
+        write_basc_bits(9, 3, zeros, &mut bits);
+        assert_eq!(bits, vec![false, true, false, false, true]);
+
+        let nums = [5_u64, 6, 9, 1, 0, 1000, u64::MAX, 3];
+        let mut enc = BascEncoder::with_convention(Cursor::new(vec![]), zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = BascDecoder::with_convention(Cursor::new(result.clone()), zeros);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        let dec = BascDecoder::new(Cursor::new(result));
+        assert_ne!(dec.decode::<u64>().ok(), Some(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_smooth_sequence() {
+        let timestamps: Vec<u32> = (0..64).map(|i| 1_700_000_000 + 17 * i).collect();
+        let mut enc = BascEncoder::new(Cursor::new(vec![]));
+        enc.encode(&timestamps).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&timestamps).unwrap();
+        let gamma = ge.finalize().unwrap().into_inner();
+        assert!(result.len() < gamma.len());
+
+        let dec = BascDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u32>().unwrap(), timestamps);
+    }
+}
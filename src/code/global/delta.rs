@@ -1,11 +1,19 @@
 use std::io::{self, Read, Write};
 
-use crate::error::InvalidCodeError;
+use super::gamma::{
+    decode_gamma_prefix, decode_terminated, read_gamma, write_gamma, write_gamma_bits,
+};
+use super::unary::UnaryConvention;
+use crate::error::{BufferError, InvalidCodeError};
+use crate::io::read::{BitCursor, ByteBits};
+use crate::io::write::ByteBitsMut;
 use crate::num::convert::write_offset_bits;
 use crate::num::{bits_to_numeric, Numeric};
 use crate::{BitReader, BitWriter};
-use crate::{DecodeOne, Decoder, EncodeOne, Encoder};
-use crate::{GammaDecoder, GammaEncoder, UnaryDecoder};
+use crate::{
+    DecodeBytes, DecodeFrom, DecodeOne, DecodePrefix, Decoder, EncodeInto, EncodeOne, Encoder,
+    NonBlockingEncoder, SkipPrefix,
+};
 
 /// A structure that wraps a writer and encodes a sequence of integers
 /// using Elias Delta Encoding.
@@ -21,35 +29,124 @@ use crate::{GammaDecoder, GammaEncoder, UnaryDecoder};
 /// encoding of 9 is 11000001.
 pub struct DeltaEncoder<W> {
     writer: BitWriter<W>,
+    convention: UnaryConvention,
 }
 
 impl<W: Write> DeltaEncoder<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_convention(writer, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the unary part of the Elias Gamma
+    /// encoded lengths with the specified convention.
+    pub fn with_convention(writer: W, convention: UnaryConvention) -> Self {
         let writer = BitWriter::new(writer, true);
-        DeltaEncoder { writer }
+        DeltaEncoder { writer, convention }
+    }
+
+    /// Creates a new encoder that writes its segments after sync markers, with
+    /// [`BitWriter::with_sync_markers`], using the specified unary convention.
+    /// The stream is decoded with [`DeltaDecoder::synced`], which skips the
+    /// segments that are corrupted.
+    pub fn with_sync_markers(writer: W, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::with_sync_markers(writer, true);
+        DeltaEncoder { writer, convention }
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+
+    /// Writes the numbers encoded so far as a segment, that can be decoded
+    /// with [`DeltaDecoder::segmented`] before the encoding is finalized.
+    /// The rest of the numbers are written in new segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Decoder, Encoder, DeltaDecoder, DeltaEncoder};
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode(&[2_u32, 3]).unwrap();
+    /// enc.flush_segment().unwrap();
+    /// enc.encode(&[9_u32, 40]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let dec = DeltaDecoder::segmented(Cursor::new(&result), UnaryConvention::default());
+    /// assert_eq!(dec.decode::<u32>().unwrap(), vec![2, 3, 9, 40]);
+    ///
+    /// // The first segment is decodable before the second one is written.
+    /// let partial = Cursor::new(&result[..result.len() - 1]);
+    /// let dec = DeltaDecoder::segmented(partial, UnaryConvention::default());
+    /// assert_eq!(dec.decode::<u32>().unwrap(), vec![2, 3]);
+    /// ```
+    pub fn flush_segment(&mut self) -> io::Result<()> {
+        self.writer.flush_segment()
     }
 }
 
 impl EncodeOne for DeltaEncoder<()> {
     fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
-        let mut offset_bits = vec![];
-        write_offset_bits(&num, &mut offset_bits);
-        let mut bits = GammaEncoder::encode_one(offset_bits.len() + 1);
-        bits.append(&mut offset_bits);
+        let mut bits = vec![];
+        write_delta_bits(num, UnaryConvention::default(), &mut bits);
         bits
     }
 }
 
+/// Calculates the Elias Delta code of a number, writing the unary part of
+/// its Elias Gamma encoded length with the specified convention.
+pub(crate) fn write_delta_bits<T: Numeric>(
+    num: T,
+    convention: UnaryConvention,
+    buffer: &mut Vec<bool>,
+) {
+    let mut offset_bits = vec![];
+    write_offset_bits(&num, &mut offset_bits);
+    write_gamma_bits(offset_bits.len() + 1, convention, buffer);
+    buffer.append(&mut offset_bits);
+}
+
+impl EncodeInto for DeltaEncoder<()> {
+    fn encode_into<T: Numeric>(nums: &[T], buf: &mut [u8]) -> Result<usize, BufferError> {
+        let mut bits = ByteBitsMut::new(buf);
+        for num in nums {
+            let num = num.to_u64();
+            if num == 0 {
+                return Err(BufferError::InvalidInput);
+            }
+            let len = u64::BITS - num.leading_zeros();
+            write_gamma(&mut bits, u64::from(len), UnaryConvention::default())?;
+            bits.write_bits(num, len - 1)?;
+        }
+        bits.finish(true)
+    }
+}
+
+impl<W: Write> DeltaEncoder<W> {
+    /// Encodes and writes a sequence of numbers, prefixed by their count, so
+    /// that several independent sequences can be written in the same writer.
+    ///
+    /// The count is incremented by one, to allow empty sequences, and encoded
+    /// using Elias Gamma Encoding.
+    pub fn encode_sequence<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut count_bits = vec![];
+        write_gamma_bits(nums.len() + 1, self.convention, &mut count_bits);
+        self.writer.write_bits(&count_bits)?;
+        self.encode(nums)
+    }
+}
+
 impl<W: Write> Encoder<W> for DeltaEncoder<W> {
     fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
-        let mut offset_bits = Vec::new();
+        let mut bits = Vec::new();
 
         for n in nums {
-            offset_bits.clear();
-            write_offset_bits(n, &mut offset_bits);
-            let len_bits = GammaEncoder::encode_one(offset_bits.len() + 1);
-            self.writer.write_bits(&len_bits)?;
-            self.writer.write_bits(&offset_bits)?;
+            bits.clear();
+            write_delta_bits(*n, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
         }
         Ok(())
     }
@@ -59,6 +156,20 @@ impl<W: Write> Encoder<W> for DeltaEncoder<W> {
     }
 }
 
+impl<W: Write> NonBlockingEncoder<W> for DeltaEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
 /// A structure that wraps a reader and decodes a stream of bytes using
 /// Elias Delta Encoding.
 ///
@@ -71,87 +182,268 @@ impl<W: Write> Encoder<W> for DeltaEncoder<W> {
 /// For example, the number 9 in binary is 1001. Its length is 4 (100),
 /// which in Elias gamma encoding is 11000. Therefore, the Elias Delta
 /// encoding of 9 is 11000001.
+///
+/// The unary convention of the Elias Gamma encoded lengths is not recorded
+/// in the stream, so it must be passed to [`DeltaDecoder::with_convention`]
+/// as it was passed to the encoder.
 pub struct DeltaDecoder<R> {
-    reader: BitReader<R>,
+    cursor: BitCursor<R>,
+    convention: UnaryConvention,
 }
 
 impl<R: Read> DeltaDecoder<R> {
     pub fn new(reader: R) -> Self {
-        let reader = BitReader::new(reader, true);
-        DeltaDecoder { reader }
+        Self::with_convention(reader, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the unary part of the Elias Gamma
+    /// encoded lengths with the specified convention, which must match the
+    /// encoder's.
+    pub fn with_convention(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        DeltaDecoder { cursor, convention }
+    }
+
+    /// Creates a new decoder for a stream written in segments, with
+    /// [`DeltaEncoder::flush_segment`], using the specified unary convention.
+    /// A trailing segment that is not completely written yet is ignored.
+    pub fn segmented(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::segmented(BitReader::new(reader, true));
+        DeltaDecoder { cursor, convention }
+    }
+
+    /// Creates a new decoder for a stream written with
+    /// [`DeltaEncoder::with_sync_markers`], using the specified unary
+    /// convention. The numbers of the intact segments are decoded, and the
+    /// corrupted segments are skipped.
+    pub fn synced(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::synced(BitReader::new(reader, true));
+        DeltaDecoder { cursor, convention }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
     }
 }
 
 impl DecodeOne for DeltaDecoder<()> {
     fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
-        let idx = bits
-            .iter()
-            .position(|b| !b)
-            .ok_or_else(|| InvalidCodeError::DeltaCodeError)?;
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::DeltaCodeError);
+        }
+        Ok(num)
+    }
+}
 
-        let (lb_len_bits, rest) = bits.split_at(idx + 1);
-        let len_len_bits = UnaryDecoder::decode_one(&lb_len_bits)?;
+impl DecodePrefix for DeltaDecoder<()> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        decode_delta_prefix(bits, UnaryConvention::default())
+    }
+}
 
-        let (offset_len_bits, offset_bits) = rest
-            .split_at_checked(len_len_bits)
+impl DecodeBytes for DeltaDecoder<()> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let num = read_delta(&mut bits)
+            .and_then(T::from_u64)
             .ok_or(InvalidCodeError::DeltaCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
 
-        let mut len_bits = Vec::with_capacity(lb_len_bits.len() + offset_len_bits.len());
-        len_bits.extend_from_slice(lb_len_bits);
-        len_bits.extend_from_slice(offset_len_bits);
-        let len = GammaDecoder::decode_one::<usize>(&len_bits)? - 1;
+/// Reads the Elias Delta code at the position of a cursor over bytes,
+/// returning `None` if it is cut short or its number does not fit in 64 bits.
+fn read_delta(bits: &mut ByteBits<'_>) -> Option<u64> {
+    let len = read_gamma(bits, UnaryConvention::default())?;
+    if len > 64 {
+        return None;
+    }
+    let len = len as usize - 1;
+    Some((1 << len) | bits.read_bits(len)?)
+}
 
-        if offset_bits.len() != len {
-            return Err(InvalidCodeError::DeltaCodeError);
-        }
+impl DecodeFrom for DeltaDecoder<()> {
+    fn decode_from<T: Numeric>(bytes: &[u8], out: &mut [T]) -> Result<usize, BufferError> {
+        decode_terminated(bytes, out, InvalidCodeError::DeltaCodeError, read_delta)
+    }
+}
 
-        let mut bits = Vec::with_capacity(len);
-        bits.push(true);
-        bits.extend_from_slice(offset_bits);
-        bits_to_numeric::<T>(&bits).or_else(|_| Err(InvalidCodeError::DeltaCodeError))
+impl SkipPrefix for DeltaDecoder<()> {
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        delta_code_len(bits, UnaryConvention::default())
     }
 }
 
-impl<R: Read> Decoder<R> for DeltaDecoder<R> {
-    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
-        let mut nums = vec![];
-        let bitvec = self.reader.read_to_end().expect("Failed to read reader.");
-        let bits = bitvec.into_bits();
-        let mut current_bits = bits.as_slice();
-
-        while !current_bits.is_empty() {
-            let idx = current_bits
-                .iter()
-                .position(|b| !b)
-                .ok_or(InvalidCodeError::DeltaCodeError)?;
-            let (unary_bits, rest) = current_bits.split_at(idx + 1);
-
-            let length_of_binary = UnaryDecoder::decode_one(&unary_bits)?;
-            if rest.len() < length_of_binary {
-                return Err(InvalidCodeError::DeltaCodeError);
-            }
+/// Decodes the Elias Delta code at the start of a buffer of bits, whose
+/// length is Elias Gamma encoded with the specified unary convention,
+/// returning the number along with the number of bits its code occupies.
+pub(crate) fn decode_delta_prefix<T: Numeric>(
+    bits: &[bool],
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let (len, len_bits) = decode_gamma_prefix::<usize>(bits, convention)?;
+    let len = len - 1;
+
+    let offset_bits = bits[len_bits..]
+        .get(..len)
+        .ok_or(InvalidCodeError::DeltaCodeError)?;
+
+    let mut n_bits = Vec::with_capacity(len + 1);
+    n_bits.push(true);
+    n_bits.extend_from_slice(offset_bits);
+    let num = bits_to_numeric::<T>(&n_bits).map_err(|_| InvalidCodeError::DeltaCodeError)?;
+    Ok((num, len_bits + len))
+}
 
-            let (binary_bits, rest) = rest.split_at(length_of_binary);
-            let mut length_bits = Vec::with_capacity(unary_bits.len() + binary_bits.len());
-            length_bits.extend_from_slice(unary_bits);
-            length_bits.extend_from_slice(binary_bits);
-            let value_length = GammaDecoder::decode_one::<usize>(&length_bits)? - 1;
+/// Returns the number of bits of the Elias Delta code at the start of a
+/// buffer of bits, decoding only its length, without decoding the number.
+pub(crate) fn delta_code_len(
+    bits: &[bool],
+    convention: UnaryConvention,
+) -> Result<usize, InvalidCodeError> {
+    let (len, len_bits) = decode_gamma_prefix::<usize>(bits, convention)
+        .map_err(|_| InvalidCodeError::DeltaCodeError)?;
+    match len_bits + len - 1 <= bits.len() {
+        true => Ok(len_bits + len - 1),
+        false => Err(InvalidCodeError::DeltaCodeError),
+    }
+}
 
-            if rest.len() < value_length {
-                return Err(InvalidCodeError::DeltaCodeError);
-            }
+impl<R: Read> DeltaDecoder<R> {
+    /// Decodes the next number without consuming it.
+    ///
+    /// Returns `None` if there are no more numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{DeltaDecoder, DeltaEncoder, Encoder};
+    ///
+    /// let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode(&[2_u32, 3, 9, 40, 7]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let mut dec = DeltaDecoder::new(Cursor::new(result));
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(2)));
+    /// dec.skip(3).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(40)));
+    /// dec.skip(1).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(7)));
+    /// dec.skip(1).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(None));
+    /// ```
+    pub fn peek<T: Numeric>(&mut self) -> Result<Option<T>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(None);
+        }
+        let convention = self.convention;
+        self.cursor
+            .peek_with(|bits| decode_delta_prefix(bits, convention))
+            .map(Some)
+    }
+
+    /// Advances past the next `n` numbers without decoding them. Only the
+    /// Elias Gamma encoded length of each code is decoded, so the offset bits
+    /// are jumped over.
+    ///
+    /// Returns an error, without advancing, if there are fewer than `n`
+    /// numbers left.
+    pub fn skip(&mut self, n: usize) -> Result<(), InvalidCodeError> {
+        let convention = self.convention;
+        self.cursor
+            .skip_with(n, |bits| delta_code_len(bits, convention))
+    }
 
-            let (value_bits, remaining) = rest.split_at(value_length);
+    /// Decodes up to `N` numbers into an array on the stack, and returns it
+    /// along with the number of numbers decoded, which is less than `N` only
+    /// at the end of the stream. The rest of the array is filled with 0.
+    ///
+    /// Returns an error if a code is invalid, in which case the numbers
+    /// before it are consumed.
+    pub fn decode_batch<T: Numeric, const N: usize>(
+        &mut self,
+    ) -> Result<([T; N], usize), InvalidCodeError> {
+        let convention = self.convention;
+        self.cursor
+            .decode_batch_with(|bits| decode_delta_prefix(bits, convention))
+    }
 
-            let mut final_bits = Vec::with_capacity(value_length + 1);
-            final_bits.push(true);
-            final_bits.extend_from_slice(value_bits);
+    /// Returns the position of the decoder in the stream, in bits, so that
+    /// it can be rewound to it with [`DeltaDecoder::reset`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{DeltaDecoder, DeltaEncoder, Encoder};
+    ///
+    /// let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode_sequence(&[2_u32, 3, 9]).unwrap();
+    /// enc.encode_sequence(&[40_u32]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let mut dec = DeltaDecoder::new(Cursor::new(result));
+    /// let mark = dec.mark();
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+    /// dec.reset(mark).unwrap();
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![40])));
+    /// ```
+    pub fn mark(&self) -> usize {
+        self.cursor.position()
+    }
 
-            let num =
-                bits_to_numeric::<T>(&final_bits).map_err(|_| InvalidCodeError::DeltaCodeError)?;
+    /// Rewinds, or advances, the decoder to a position returned by
+    /// [`DeltaDecoder::mark`], so that decoding continues from there.
+    ///
+    /// Returns an error if the stream cannot be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is past the end of the stream.
+    pub fn reset(&mut self, mark: usize) -> Result<(), InvalidCodeError> {
+        self.cursor.set_position(mark)
+    }
 
+    /// Reads and decodes the next sequence of numbers, that was written with
+    /// [`DeltaEncoder::encode_sequence`].
+    ///
+    /// Returns `None` if there are no more sequences.
+    pub fn next_sequence<T: Numeric>(&mut self) -> Result<Option<Vec<T>>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(None);
+        }
+        let convention = self.convention;
+        let count: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+        // Each number takes at least one bit, which bounds an untrusted count.
+        let mut nums = Vec::with_capacity((count - 1).min(self.cursor.remaining()?.len()));
+        for _ in 1..count {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_delta_prefix(bits, convention))?;
+            nums.push(num);
+        }
+        Ok(Some(nums))
+    }
+}
+
+impl<R: Read> Decoder<R> for DeltaDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let convention = self.convention;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_delta_prefix(bits, convention))?;
             nums.push(num);
-            current_bits = remaining;
         }
         Ok(nums)
     }
@@ -161,6 +453,56 @@ impl<R: Read> Decoder<R> for DeltaDecoder<R> {
 mod tests {
 
     use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::GammaEncoder;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).map(|n| n | 1).collect();
+        let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<DeltaDecoder<()>>(&bytes, &nums);
+        // A length of more than 64 bits.
+        let mut bits = vec![];
+        write_gamma_bits(65_u32, UnaryConvention::default(), &mut bits);
+        bits.resize(bits.len() + 64, true);
+        let mut bitvec = crate::BitVec::default();
+        bitvec.extend_from_slice(&bits);
+        let bytes = bitvec.into_bytes();
+        assert!(DeltaDecoder::decode_one_from_bytes::<u64>(&bytes, 0).is_err());
+    }
+
+    #[test]
+    fn test_synced_recovery() {
+        let nums: Vec<u64> = (1..=400_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 20)
+            .collect();
+        let convention = UnaryConvention::default();
+        let mut enc = DeltaEncoder::with_sync_markers(Cursor::new(vec![]), convention);
+        for chunk in nums.chunks(100) {
+            enc.encode(chunk).unwrap();
+            enc.flush_segment().unwrap();
+        }
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        let dec = DeltaDecoder::synced(Cursor::new(bytes.clone()), convention);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        // Dropping bytes from the end of the third segment loses only its numbers.
+        let last = bytes
+            .windows(4)
+            .rposition(|window| window == crate::io::SYNC_MARKER)
+            .unwrap();
+        let mut corrupted = bytes[..last - 20].to_vec();
+        corrupted.extend_from_slice(&bytes[last - 10..]);
+        let dec = DeltaDecoder::synced(Cursor::new(corrupted), convention);
+        let mut expected = nums[..200].to_vec();
+        expected.extend_from_slice(&nums[300..]);
+        assert_eq!(dec.decode::<u64>().unwrap(), expected);
+    }
 
     #[test]
     fn test_encode_one() {
@@ -224,4 +566,135 @@ mod tests {
         let nums = de.decode::<u32>().unwrap();
         assert_eq!(nums, vec![2, 3, 9]);
     }
+
+    #[test]
+    fn test_sequences() {
+        let mut de = DeltaEncoder::new(Cursor::new(vec![]));
+        de.encode_sequence(&[2_u32, 3, 9]).unwrap();
+        de.encode_sequence::<u32>(&[]).unwrap();
+        de.encode_sequence(&[1_u32, 1000]).unwrap();
+        let result = de.finalize().unwrap().into_inner();
+
+        let mut dd = DeltaDecoder::new(Cursor::new(result));
+        assert_eq!(dd.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+        assert_eq!(dd.next_sequence::<u32>(), Ok(Some(vec![])));
+        assert_eq!(dd.next_sequence::<u32>(), Ok(Some(vec![1, 1000])));
+        assert_eq!(dd.next_sequence::<u32>(), Ok(None));
+
+        // A huge count in the header fails on the missing numbers, instead of
+        // allocating for them.
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&[u64::MAX, 1, 1]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+        let mut dd = DeltaDecoder::new(Cursor::new(result));
+        assert!(dd.next_sequence::<u32>().is_err());
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let nums = [2_u32, 3, 9, 1000];
+        let zeros = UnaryConvention::Zeros;
+        let mut de = DeltaEncoder::with_convention(Cursor::new(vec![]), zeros);
+        assert_eq!(de.convention(), zeros);
+        de.encode(&nums).unwrap();
+        let result = de.finalize().unwrap().into_inner();
+        assert_eq!(result[0], 0b01000101);
+
+        let dd = DeltaDecoder::with_convention(Cursor::new(result.clone()), zeros);
+        assert_eq!(dd.decode::<u32>().unwrap(), nums);
+
+        let dd = DeltaDecoder::new(Cursor::new(result));
+        assert_ne!(dd.decode::<u32>().ok(), Some(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_peek_skip() {
+        let nums: Vec<u64> = (1..200).map(|i| i * i * i * 31).collect();
+        let zeros = UnaryConvention::Zeros;
+        let mut de = DeltaEncoder::with_convention(Cursor::new(vec![]), zeros);
+        de.encode(&nums).unwrap();
+        let result = de.finalize().unwrap().into_inner();
+
+        let mut dd = DeltaDecoder::with_convention(Cursor::new(result), zeros);
+        assert_eq!(dd.peek::<u64>(), Ok(Some(nums[0])));
+        dd.skip(120).unwrap();
+        assert_eq!(dd.peek::<u64>(), Ok(Some(nums[120])));
+        assert!(dd.skip(80).is_err());
+        dd.skip(78).unwrap();
+        assert_eq!(dd.decode::<u64>(), Ok(vec![nums[198]]));
+
+        // The offset bits of 9 are cut short.
+        let bits = DeltaEncoder::encode_one(9_u32);
+        assert_eq!(delta_code_len(&bits, UnaryConvention::Ones), Ok(8));
+        assert!(delta_code_len(&bits[..7], UnaryConvention::Ones).is_err());
+    }
+
+    #[test]
+    fn test_mark_reset() {
+        let mut de = DeltaEncoder::new(Cursor::new(vec![]));
+        de.encode(&[9_u32, 1000, 1]).unwrap();
+        let result = de.finalize().unwrap().into_inner();
+
+        let mut dd = DeltaDecoder::new(Cursor::new(result));
+        dd.skip(1).unwrap();
+        let mark = dd.mark();
+        assert_eq!(mark, 8);
+        dd.skip(2).unwrap();
+        assert_eq!(dd.peek::<u32>(), Ok(None));
+        dd.reset(mark).unwrap();
+        assert_eq!(dd.decode::<u32>(), Ok(vec![1000, 1]));
+    }
+
+    #[test]
+    fn test_decode_batch() {
+        let nums: Vec<u64> = (1..=20).map(|i| i * 1000).collect();
+        let mut de = DeltaEncoder::new(Cursor::new(vec![]));
+        de.encode(&nums).unwrap();
+        let result = de.finalize().unwrap().into_inner();
+
+        let mut dd = DeltaDecoder::new(Cursor::new(result));
+        let (batch, count) = dd.decode_batch::<u64, 8>().unwrap();
+        assert_eq!((&batch[..], count), (&nums[..8], 8));
+        dd.skip(8).unwrap();
+        let (batch, count) = dd.decode_batch::<u64, 8>().unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(batch, [17000, 18000, 19000, 20000, 0, 0, 0, 0]);
+        assert_eq!(dd.decode_batch::<u64, 8>(), Ok(([0; 8], 0)));
+        assert_eq!(dd.decode_batch::<u64, 0>(), Ok(([], 0)));
+    }
+
+    #[test]
+    fn test_encode_into_decode_from() {
+        let nums: Vec<u64> = (1..=500_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 30)
+            .map(|n| n.max(1))
+            .collect();
+        let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let expected = enc.finalize().unwrap().into_inner();
+
+        let mut buf = vec![0; expected.len()];
+        assert_eq!(
+            DeltaEncoder::encode_into(&nums, &mut buf),
+            Ok(expected.len())
+        );
+        assert_eq!(buf, expected);
+        assert_eq!(
+            DeltaEncoder::encode_into(&nums, &mut buf[..expected.len() - 1]),
+            Err(BufferError::BufferTooSmall)
+        );
+
+        let mut out = vec![0_u64; nums.len()];
+        assert_eq!(DeltaDecoder::decode_from(&buf, &mut out), Ok(nums.len()));
+        assert_eq!(out, nums);
+        assert_eq!(
+            DeltaDecoder::decode_from(&buf, &mut out[..499]),
+            Err(BufferError::BufferTooSmall)
+        );
+        assert_eq!(DeltaDecoder::decode_from::<u64>(&[], &mut []), Ok(0));
+        assert_eq!(
+            DeltaDecoder::decode_from::<u8>(&buf, &mut [0; 500]),
+            Err(BufferError::InvalidCode(InvalidCodeError::DeltaCodeError))
+        );
+    }
 }
@@ -0,0 +1,303 @@
+use std::io::{self, Read, Write};
+
+use super::unary::{UnaryConvention, UnaryEncoder};
+use super::vb::write_vb_bytes;
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Rice Encoding with an escape for outliers.
+///
+/// In Rice Encoding with parameter *k*, each number *n* is split into a
+/// quotient *q = n / 2^k*, encoded in unary, and a remainder *n mod 2^k*,
+/// written in exactly *k* bits. Since the unary code grows linearly with the
+/// quotient, a single outlier produces a pathologically long run of 1-bits.
+/// To bound the length of the run, quotients greater than or equal to a cap
+/// are replaced by an escape of `cap` 1-bits, followed by the excess
+/// *q - cap* in Variable Byte Encoding, before the remainder.
+///
+/// For example, with *k = 2* and a cap of 4, the number 9 has a quotient of 2
+/// and a remainder of 1, so it is encoded as 110 01. The number 100 has a
+/// quotient of 25, so it is encoded as 1111 10010101 00.
+pub struct EscapedRiceEncoder<W> {
+    writer: BitWriter<W>,
+    k: u32,
+    cap: usize,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> EscapedRiceEncoder<W> {
+    /// Creates a new encoder with Rice parameter `k` and the maximum length
+    /// `cap` of the unary encoded quotient.
+    ///
+    /// # Panics
+    ///
+    /// Encoding panics if `k` is not less than the number of bits of the
+    /// encoded type.
+    pub fn new(writer: W, k: u32, cap: usize) -> Self {
+        Self::with_convention(writer, k, cap, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the quotient, and the escape, in
+    /// unary with the specified convention.
+    ///
+    /// With [`UnaryConvention::Zeros`] and a large enough cap, the codes are
+    /// the Rice codes used by FLAC for non-negative residuals.
+    pub fn with_convention(writer: W, k: u32, cap: usize, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::new(writer, true);
+        EscapedRiceEncoder {
+            writer,
+            k,
+            cap,
+            convention,
+        }
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for EscapedRiceEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            write_escaped_rice_bits(*n, self.k, self.cap, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for EscapedRiceEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Rice Encoding with an escape for outliers.
+///
+/// Quotients are encoded in unary, unless they reach the cap, in which case
+/// the escape of `cap` 1-bits is followed by the excess of the quotient over
+/// the cap in Variable Byte Encoding. The remainder is always written in
+/// exactly *k* bits.
+///
+/// Neither the parameters nor the unary convention are recorded in the
+/// stream, so all of them must match the encoder's.
+pub struct EscapedRiceDecoder<R> {
+    cursor: BitCursor<R>,
+    k: u32,
+    cap: usize,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> EscapedRiceDecoder<R> {
+    /// Creates a new decoder with Rice parameter `k` and the maximum length
+    /// `cap` of the unary encoded quotient, which must match the encoder's.
+    pub fn new(reader: R, k: u32, cap: usize) -> Self {
+        Self::with_convention(reader, k, cap, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the quotient, and the escape, in
+    /// unary with the specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, k: u32, cap: usize, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        EscapedRiceDecoder {
+            cursor,
+            k,
+            cap,
+            convention,
+        }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<R: Read> Decoder<R> for EscapedRiceDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let (k, cap, convention) = (self.k, self.cap, self.convention);
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_escaped_rice_prefix(bits, k, cap, convention))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+/// Calculates the escaped Rice code of a number.
+pub(crate) fn write_escaped_rice_bits<T: Numeric>(
+    num: T,
+    k: u32,
+    cap: usize,
+    convention: UnaryConvention,
+    buffer: &mut Vec<bool>,
+) {
+    let quotient = (num >> k).to_u64();
+    if quotient < cap as u64 {
+        buffer.extend(UnaryEncoder::encode_one_with(quotient as usize, convention));
+    } else {
+        buffer.extend(std::iter::repeat_n(convention.run_bit(), cap));
+        let mut bytes = vec![];
+        write_vb_bytes(quotient - cap as u64, &mut bytes);
+        for byte in bytes {
+            write_fixed_bits(&byte, 8, buffer);
+        }
+    }
+    write_fixed_bits(&num, k, buffer);
+}
+
+/// Decodes the escaped Rice code at the start of a buffer of bits, returning
+/// the number along with the number of bits its code occupies.
+pub(crate) fn decode_escaped_rice_prefix<T: Numeric>(
+    bits: &[bool],
+    k: u32,
+    cap: usize,
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let run = bits
+        .iter()
+        .take(cap)
+        .take_while(|b| **b == convention.run_bit())
+        .count();
+    let (quotient, mut pos) = if run < cap {
+        if bits.get(run) != Some(&convention.stop_bit()) {
+            return Err(InvalidCodeError::RiceCodeError);
+        }
+        (run as u64, run + 1)
+    } else {
+        let mut excess = 0_u64;
+        let mut pos = cap;
+        loop {
+            let group = bits
+                .get(pos..pos + 8)
+                .ok_or(InvalidCodeError::RiceCodeError)?;
+            let byte = bits_to_numeric::<u8>(group).expect("A byte fits in u8.");
+            if excess > u64::MAX >> 7 {
+                return Err(InvalidCodeError::RiceCodeError);
+            }
+            excess = (excess << 7) | u64::from(byte & 0x7F);
+            pos += 8;
+            if byte >= 0x80 {
+                break;
+            }
+        }
+        let quotient = excess
+            .checked_add(cap as u64)
+            .ok_or(InvalidCodeError::RiceCodeError)?;
+        (quotient, pos)
+    };
+
+    let k_bits = k as usize;
+    let remainder_bits = bits
+        .get(pos..pos + k_bits)
+        .ok_or(InvalidCodeError::RiceCodeError)?;
+    let remainder =
+        bits_to_numeric::<u64>(remainder_bits).map_err(|_| InvalidCodeError::RiceCodeError)?;
+    pos += k_bits;
+
+    let num = u64::try_from((u128::from(quotient) << k) | u128::from(remainder))
+        .ok()
+        .and_then(T::from_u64)
+        .ok_or(InvalidCodeError::RiceCodeError)?;
+    Ok((num, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode_one(num: u32, k: u32, cap: usize) -> Vec<bool> {
+        let mut bits = vec![];
+        write_escaped_rice_bits(num, k, cap, UnaryConvention::Ones, &mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        assert_eq!(encode_one(9, 2, 4), vec![true, true, false, false, true]);
+        let mut expected = vec![true; 4];
+        expected.extend([true, false, false, true, false, true, false, true]);
+        expected.extend([false, false]);
+        assert_eq!(encode_one(100, 2, 4), expected);
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let ones = UnaryConvention::Ones;
+        let bits = encode_one(100, 2, 4);
+        assert_eq!(
+            decode_escaped_rice_prefix::<u32>(&bits, 2, 4, ones),
+            Ok((100, 14))
+        );
+        assert!(decode_escaped_rice_prefix::<u32>(&bits[..13], 2, 4, ones).is_err());
+        assert!(decode_escaped_rice_prefix::<u32>(&bits[..6], 2, 4, ones).is_err());
+        assert!(decode_escaped_rice_prefix::<u8>(&bits, 2, 4, ones).is_ok());
+        assert!(decode_escaped_rice_prefix::<u8>(&encode_one(300, 2, 4), 2, 4, ones).is_err());
+    }
+
+    #[test]
+    fn test_bounded_run() {
+        let bits = encode_one(u32::MAX, 3, 8);
+        let run = bits.iter().take_while(|b| **b).count();
+        assert!(run <= 9);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 1, 9, 15, 16, 100, 5000, 1 << 40, u64::MAX >> 3];
+        for (k, cap) in [(0, 0), (0, 16), (3, 4), (7, 32)] {
+            let mut enc = EscapedRiceEncoder::new(Cursor::new(vec![]), k, cap);
+            enc.encode(&nums).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+
+            let dec = EscapedRiceDecoder::new(Cursor::new(result), k, cap);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut bits = vec![];
+        write_escaped_rice_bits(9_u32, 2, 4, zeros, &mut bits);
+        assert_eq!(bits, vec![false, false, true, false, true]);
+
+        let nums = [0_u64, 9, 100, 5000, 1 << 40];
+        let mut enc = EscapedRiceEncoder::with_convention(Cursor::new(vec![]), 3, 4, zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = EscapedRiceDecoder::with_convention(Cursor::new(result.clone()), 3, 4, zeros);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        let dec = EscapedRiceDecoder::new(Cursor::new(result), 3, 4);
+        assert_ne!(dec.decode::<u64>().ok(), Some(nums.to_vec()));
+    }
+}
@@ -1,10 +1,13 @@
 use std::io::{self, Read, Write};
 
-use super::unary::{UnaryDecoder, UnaryEncoder};
-use crate::code::{DecodeOne, Decoder, EncodeOne, Encoder};
-use crate::error::InvalidCodeError;
-use crate::io::read::BitReader;
-use crate::io::write::BitWriter;
+use super::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
+use crate::code::{
+    DecodeBytes, DecodeFrom, DecodeOne, DecodePrefix, Decoder, EncodeInto, EncodeOne, Encoder,
+    NonBlockingEncoder, SkipPrefix,
+};
+use crate::error::{BufferError, InvalidCodeError};
+use crate::io::read::{terminated_len, BitCursor, BitReader, ByteBits};
+use crate::io::write::{BitWriter, ByteBitsMut};
 use crate::num::convert::write_offset_bits;
 use crate::num::{bits_to_numeric, Numeric};
 
@@ -22,35 +25,192 @@ use crate::num::{bits_to_numeric, Numeric};
 /// is 1110001.
 pub struct GammaEncoder<W> {
     writer: BitWriter<W>,
+    convention: UnaryConvention,
 }
 
 impl<W: Write> GammaEncoder<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_convention(writer, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the length of the offset bits in
+    /// unary with the specified convention.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder};
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let zeros = UnaryConvention::Zeros;
+    /// let mut ge = GammaEncoder::with_convention(Cursor::new(vec![]), zeros);
+    /// ge.encode(&[9_u32]).unwrap();
+    /// let result = ge.finalize().unwrap().into_inner();
+    /// assert_eq!(result, vec![0b00010011]);
+    ///
+    /// let gd = GammaDecoder::with_convention(Cursor::new(result), zeros);
+    /// assert_eq!(gd.decode::<u32>().unwrap(), vec![9]);
+    /// ```
+    pub fn with_convention(writer: W, convention: UnaryConvention) -> Self {
         let writer = BitWriter::new(writer, true);
-        GammaEncoder { writer }
+        GammaEncoder { writer, convention }
+    }
+
+    /// Creates a new encoder that writes its segments after sync markers, with
+    /// [`BitWriter::with_sync_markers`], using the specified unary convention.
+    /// The stream is decoded with [`GammaDecoder::synced`], which skips the
+    /// segments that are corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder};
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let zeros = UnaryConvention::Zeros;
+    /// let mut enc = GammaEncoder::with_sync_markers(Cursor::new(vec![]), zeros);
+    /// enc.encode(&[2_u32, 3]).unwrap();
+    /// enc.flush_segment().unwrap();
+    /// enc.encode(&[9_u32, 40]).unwrap();
+    /// let mut result = enc.finalize().unwrap().into_inner();
+    ///
+    /// // The numbers of the segments that follow a corrupted one are recovered.
+    /// result[8] = 0x83;
+    /// let dec = GammaDecoder::synced(Cursor::new(result), zeros);
+    /// assert_eq!(dec.decode::<u32>().unwrap(), vec![9, 40]);
+    /// ```
+    pub fn with_sync_markers(writer: W, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::with_sync_markers(writer, true);
+        GammaEncoder { writer, convention }
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+
+    /// Writes the numbers encoded so far as a segment, that can be decoded
+    /// with [`GammaDecoder::segmented`] before the encoding is finalized.
+    /// The rest of the numbers are written in new segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder};
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode(&[2_u32, 3]).unwrap();
+    /// enc.flush_segment().unwrap();
+    /// enc.encode(&[9_u32, 40]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let dec = GammaDecoder::segmented(Cursor::new(&result), UnaryConvention::default());
+    /// assert_eq!(dec.decode::<u32>().unwrap(), vec![2, 3, 9, 40]);
+    ///
+    /// // The first segment is decodable before the second one is written.
+    /// let partial = Cursor::new(&result[..result.len() - 1]);
+    /// let dec = GammaDecoder::segmented(partial, UnaryConvention::default());
+    /// assert_eq!(dec.decode::<u32>().unwrap(), vec![2, 3]);
+    /// ```
+    pub fn flush_segment(&mut self) -> io::Result<()> {
+        self.writer.flush_segment()
     }
 }
 
 impl EncodeOne for GammaEncoder<()> {
     fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
-        let mut offset_bits = vec![];
-        write_offset_bits(&num, &mut offset_bits);
-        let mut bits = UnaryEncoder::encode_one(offset_bits.len());
-        bits.append(&mut offset_bits);
+        let mut bits = vec![];
+        write_gamma_bits(num, UnaryConvention::default(), &mut bits);
         bits
     }
 }
 
+/// Calculates the Elias Gamma code of a number, writing the length of the
+/// offset bits in unary with the specified convention.
+pub(crate) fn write_gamma_bits<T: Numeric>(
+    num: T,
+    convention: UnaryConvention,
+    buffer: &mut Vec<bool>,
+) {
+    let mut offset_bits = vec![];
+    write_offset_bits(&num, &mut offset_bits);
+    buffer.extend(UnaryEncoder::encode_one_with(offset_bits.len(), convention));
+    buffer.append(&mut offset_bits);
+}
+
+impl EncodeInto for GammaEncoder<()> {
+    fn encode_into<T: Numeric>(nums: &[T], buf: &mut [u8]) -> Result<usize, BufferError> {
+        let mut bits = ByteBitsMut::new(buf);
+        for num in nums {
+            write_gamma(&mut bits, num.to_u64(), UnaryConvention::default())?;
+        }
+        bits.finish(true)
+    }
+}
+
+/// Writes the Elias Gamma code of a number in place, whose length is written
+/// in unary with the specified convention.
+pub(crate) fn write_gamma(
+    bits: &mut ByteBitsMut<'_>,
+    num: u64,
+    convention: UnaryConvention,
+) -> Result<(), BufferError> {
+    if num == 0 {
+        return Err(BufferError::InvalidInput);
+    }
+    let len = u64::BITS - 1 - num.leading_zeros();
+    for _ in 0..len {
+        bits.write_bit(convention.run_bit())?;
+    }
+    bits.write_bit(convention.stop_bit())?;
+    bits.write_bits(num, len)
+}
+
+impl<W: Write> GammaEncoder<W> {
+    /// Encodes and writes a sequence of numbers, prefixed by their count, so
+    /// that several independent sequences can be written in the same writer.
+    ///
+    /// The count is incremented by one, to allow empty sequences, and encoded
+    /// using Elias Gamma Encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, GammaDecoder, GammaEncoder};
+    ///
+    /// let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+    /// ge.encode_sequence(&[2_u32, 3]).unwrap();
+    /// ge.encode_sequence::<u32>(&[]).unwrap();
+    /// ge.encode_sequence(&[9_u32]).unwrap();
+    /// let result = ge.finalize().unwrap().into_inner();
+    ///
+    /// let mut gd = GammaDecoder::new(Cursor::new(result));
+    /// assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![2, 3])));
+    /// assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![])));
+    /// assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![9])));
+    /// assert_eq!(gd.next_sequence::<u32>(), Ok(None));
+    /// ```
+    pub fn encode_sequence<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut count_bits = vec![];
+        write_gamma_bits(nums.len() + 1, self.convention, &mut count_bits);
+        self.writer.write_bits(&count_bits)?;
+        self.encode(nums)
+    }
+}
+
 impl<W: Write> Encoder<W> for GammaEncoder<W> {
     fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
-        let mut offset_bits = Vec::new();
+        let mut bits = Vec::new();
 
         for n in nums {
-            offset_bits.clear();
-            write_offset_bits(n, &mut offset_bits);
-            let len_bits = UnaryEncoder::encode_one(offset_bits.len());
-            self.writer.write_bits(&len_bits)?;
-            self.writer.write_bits(&offset_bits)?;
+            bits.clear();
+            write_gamma_bits(*n, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
         }
         Ok(())
     }
@@ -60,6 +220,20 @@ impl<W: Write> Encoder<W> for GammaEncoder<W> {
     }
 }
 
+impl<W: Write> NonBlockingEncoder<W> for GammaEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
 /// A structure that wraps a reader and decodes a stream of bytes
 /// using Elias Gamma Encoding.
 ///
@@ -72,73 +246,320 @@ impl<W: Write> Encoder<W> for GammaEncoder<W> {
 /// remaining digits (001), and the length of these offset bits (3) is
 /// encoded in unary as 1110. Therefore, the Elias Gamma encoding of 9
 /// is 1110001.
+///
+/// The unary convention of the lengths is not recorded in the stream, so it
+/// must be passed to [`GammaDecoder::with_convention`] as it was passed to
+/// the encoder.
 pub struct GammaDecoder<R> {
-    reader: BitReader<R>,
+    cursor: BitCursor<R>,
+    convention: UnaryConvention,
 }
 
 impl<R: Read> GammaDecoder<R> {
     pub fn new(reader: R) -> Self {
-        let reader = BitReader::new(reader, true);
-        GammaDecoder { reader }
+        Self::with_convention(reader, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the length of the offset bits in
+    /// unary with the specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        GammaDecoder { cursor, convention }
+    }
+
+    /// Creates a new decoder for a stream written in segments, with
+    /// [`GammaEncoder::flush_segment`], using the specified unary convention.
+    /// A trailing segment that is not completely written yet is ignored.
+    pub fn segmented(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::segmented(BitReader::new(reader, true));
+        GammaDecoder { cursor, convention }
+    }
+
+    /// Creates a new decoder for a stream written with
+    /// [`GammaEncoder::with_sync_markers`], using the specified unary
+    /// convention. The numbers of the intact segments are decoded, and the
+    /// corrupted segments are skipped.
+    pub fn synced(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::synced(BitReader::new(reader, true));
+        GammaDecoder { cursor, convention }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
     }
 }
 
 impl DecodeOne for GammaDecoder<()> {
     fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
-        let idx = bits
-            .iter()
-            .position(|b| !b)
-            .ok_or_else(|| InvalidCodeError::GammaCodeError)?;
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::GammaCodeError);
+        }
+        Ok(num)
+    }
+}
 
-        let (len_bits, rest) = bits.split_at(idx + 1);
-        let len = UnaryDecoder::decode_one(len_bits)?;
+impl DecodePrefix for GammaDecoder<()> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        decode_gamma_prefix(bits, UnaryConvention::default())
+    }
+}
 
-        if rest.len() != len {
-            return Err(InvalidCodeError::GammaCodeError);
+impl DecodeBytes for GammaDecoder<()> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let num = read_gamma(&mut bits, UnaryConvention::default())
+            .and_then(T::from_u64)
+            .ok_or(InvalidCodeError::GammaCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl DecodeFrom for GammaDecoder<()> {
+    fn decode_from<T: Numeric>(bytes: &[u8], out: &mut [T]) -> Result<usize, BufferError> {
+        decode_terminated(bytes, out, InvalidCodeError::GammaCodeError, |bits| {
+            read_gamma(bits, UnaryConvention::default())
+        })
+    }
+}
+
+/// Decodes the numbers of a stream of bytes that ends with a terminating bit
+/// into a buffer, reading each one with a function, and returning the number
+/// of values decoded.
+pub(crate) fn decode_terminated<T: Numeric>(
+    bytes: &[u8],
+    out: &mut [T],
+    err: InvalidCodeError,
+    mut read: impl FnMut(&mut ByteBits<'_>) -> Option<u64>,
+) -> Result<usize, BufferError> {
+    let Some(end) = terminated_len(bytes) else {
+        return Err(BufferError::InvalidCode(err));
+    };
+    let mut bits = ByteBits::new(bytes, 0);
+    let mut n = 0;
+    while bits.position() < end {
+        let num = read(&mut bits)
+            .filter(|_| bits.position() <= end)
+            .and_then(T::from_u64);
+        let Some(num) = num else {
+            return Err(BufferError::InvalidCode(err));
+        };
+        *out.get_mut(n).ok_or(BufferError::BufferTooSmall)? = num;
+        n += 1;
+    }
+    Ok(n)
+}
+
+impl SkipPrefix for GammaDecoder<()> {
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        gamma_code_len(bits, UnaryConvention::default())
+    }
+}
+
+/// Reads the Elias Gamma code at the position of a cursor over bytes, whose
+/// length is written in unary with the specified convention, returning `None`
+/// if it is cut short or its number does not fit in 64 bits.
+pub(crate) fn read_gamma(bits: &mut ByteBits<'_>, convention: UnaryConvention) -> Option<u64> {
+    let len = bits.read_run(convention.stop_bit())?;
+    if len >= 64 {
+        return None;
+    }
+    Some((1 << len) | bits.read_bits(len)?)
+}
+
+/// Decodes the Elias Gamma code at the start of a buffer of bits, whose
+/// length is written in unary with the specified convention, returning the
+/// number along with the number of bits its code occupies.
+pub(crate) fn decode_gamma_prefix<T: Numeric>(
+    bits: &[bool],
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let (len, len_bits) = UnaryDecoder::decode_prefix_with(bits, convention)
+        .map_err(|_| InvalidCodeError::GammaCodeError)?;
+    let offset_bits = bits[len_bits..]
+        .get(..len)
+        .ok_or(InvalidCodeError::GammaCodeError)?;
+
+    let mut n_bits = Vec::with_capacity(len + 1);
+    n_bits.push(true);
+    n_bits.extend_from_slice(offset_bits);
+
+    match bits_to_numeric(n_bits.as_slice()) {
+        Ok(num) => Ok((num, len_bits + len)),
+        _ => Err(InvalidCodeError::GammaCodeError),
+    }
+}
+
+/// Returns the number of bits of the Elias Gamma code at the start of a
+/// buffer of bits, from its unary part alone, without decoding the number.
+pub(crate) fn gamma_code_len(
+    bits: &[bool],
+    convention: UnaryConvention,
+) -> Result<usize, InvalidCodeError> {
+    let (len, len_bits) = UnaryDecoder::decode_prefix_with(bits, convention)
+        .map_err(|_| InvalidCodeError::GammaCodeError)?;
+    match len_bits + len <= bits.len() {
+        true => Ok(len_bits + len),
+        false => Err(InvalidCodeError::GammaCodeError),
+    }
+}
+
+impl<R: Read> GammaDecoder<R> {
+    /// Decodes the next number without consuming it.
+    ///
+    /// Returns `None` if there are no more numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, GammaDecoder, GammaEncoder};
+    ///
+    /// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode(&[2_u32, 3, 9, 40, 7]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let mut dec = GammaDecoder::new(Cursor::new(result));
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(2)));
+    /// dec.skip(3).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(40)));
+    /// dec.skip(1).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(Some(7)));
+    /// dec.skip(1).unwrap();
+    /// assert_eq!(dec.peek::<u32>(), Ok(None));
+    /// ```
+    pub fn peek<T: Numeric>(&mut self) -> Result<Option<T>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(None);
         }
+        let convention = self.convention;
+        self.cursor
+            .peek_with(|bits| decode_gamma_prefix(bits, convention))
+            .map(Some)
+    }
 
-        let mut n_bits = Vec::with_capacity(len);
-        n_bits.push(true);
-        n_bits.extend_from_slice(&rest[..len]);
+    /// Advances past the next `n` numbers without decoding them. The length
+    /// of each code is read from its unary part, so the offset bits are
+    /// jumped over.
+    ///
+    /// Returns an error, without advancing, if there are fewer than `n`
+    /// numbers left.
+    pub fn skip(&mut self, n: usize) -> Result<(), InvalidCodeError> {
+        let convention = self.convention;
+        self.cursor
+            .skip_with(n, |bits| gamma_code_len(bits, convention))
+    }
+
+    /// Decodes up to `N` numbers into an array on the stack, and returns it
+    /// along with the number of numbers decoded, which is less than `N` only
+    /// at the end of the stream. The rest of the array is filled with 0.
+    ///
+    /// Returns an error if a code is invalid, in which case the numbers
+    /// before it are consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, GammaDecoder, GammaEncoder};
+    ///
+    /// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode(&(1..=10_u32).collect::<Vec<_>>()).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let mut dec = GammaDecoder::new(Cursor::new(result));
+    /// let mut sum = 0;
+    /// loop {
+    ///     let (batch, count) = dec.decode_batch::<u32, 4>().unwrap();
+    ///     sum += batch[..count].iter().sum::<u32>();
+    ///     if count < 4 {
+    ///         break;
+    ///     }
+    /// }
+    /// assert_eq!(sum, 55);
+    /// ```
+    pub fn decode_batch<T: Numeric, const N: usize>(
+        &mut self,
+    ) -> Result<([T; N], usize), InvalidCodeError> {
+        let convention = self.convention;
+        self.cursor
+            .decode_batch_with(|bits| decode_gamma_prefix(bits, convention))
+    }
+
+    /// Returns the position of the decoder in the stream, in bits, so that
+    /// it can be rewound to it with [`GammaDecoder::reset`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, GammaDecoder, GammaEncoder};
+    ///
+    /// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+    /// enc.encode_sequence(&[2_u32, 3, 9]).unwrap();
+    /// enc.encode_sequence(&[40_u32]).unwrap();
+    /// let result = enc.finalize().unwrap().into_inner();
+    ///
+    /// let mut dec = GammaDecoder::new(Cursor::new(result));
+    /// let mark = dec.mark();
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+    /// dec.reset(mark).unwrap();
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+    /// assert_eq!(dec.next_sequence::<u32>(), Ok(Some(vec![40])));
+    /// ```
+    pub fn mark(&self) -> usize {
+        self.cursor.position()
+    }
+
+    /// Rewinds, or advances, the decoder to a position returned by
+    /// [`GammaDecoder::mark`], so that decoding continues from there.
+    ///
+    /// Returns an error if the stream cannot be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is past the end of the stream.
+    pub fn reset(&mut self, mark: usize) -> Result<(), InvalidCodeError> {
+        self.cursor.set_position(mark)
+    }
 
-        match bits_to_numeric(n_bits.as_slice()) {
-            Ok(num) => Ok(num),
-            _ => Err(InvalidCodeError::GammaCodeError),
+    /// Reads and decodes the next sequence of numbers, that was written with
+    /// [`GammaEncoder::encode_sequence`].
+    ///
+    /// Returns `None` if there are no more sequences.
+    pub fn next_sequence<T: Numeric>(&mut self) -> Result<Option<Vec<T>>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(None);
         }
+        let convention = self.convention;
+        let count: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+        // Each number takes at least one bit, which bounds an untrusted count.
+        let mut nums = Vec::with_capacity((count - 1).min(self.cursor.remaining()?.len()));
+        for _ in 1..count {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+            nums.push(num);
+        }
+        Ok(Some(nums))
     }
 }
 
 impl<R: Read> Decoder<R> for GammaDecoder<R> {
-    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let convention = self.convention;
         let mut nums = vec![];
-        let bitvec = self.reader.read_to_end().expect("Failed to read reader.");
-        let bits = bitvec.into_bits();
-        let mut bits = bits.as_slice();
-
-        while !bits.is_empty() {
-            let idx = bits
-                .iter()
-                .position(|b| !b)
-                .ok_or_else(|| InvalidCodeError::GammaCodeError)?;
-
-            let (len_bits, rest) = bits.split_at(idx + 1);
-            let len = UnaryDecoder::decode_one(len_bits)?;
-
-            if rest.len() < len {
-                return Err(InvalidCodeError::GammaCodeError);
-            }
-
-            let mut n_bits = Vec::with_capacity(len);
-            n_bits.push(true);
-            n_bits.extend_from_slice(&rest[..len]);
-            let numeric = bits_to_numeric(n_bits.as_slice()).unwrap();
-            nums.push(numeric);
-
-            if let Some((_, r)) = rest.split_at_checked(len) {
-                bits = r;
-            } else {
-                return Err(InvalidCodeError::GammaCodeError);
-            }
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+            nums.push(num);
         }
         Ok(nums)
     }
@@ -147,8 +568,22 @@ impl<R: Read> Decoder<R> for GammaDecoder<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
     use std::io::Cursor;
 
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).map(|n| n | 1).collect();
+        let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<GammaDecoder<()>>(&bytes, &nums);
+        // The number does not fit in the type, or the code is cut short.
+        assert!(GammaDecoder::decode_one_from_bytes::<u8>(&[0xFF, 0x00], 0).is_err());
+        assert!(GammaDecoder::decode_one_from_bytes::<u32>(&[0b11111110], 0).is_err());
+    }
+
     #[test]
     fn test_encode_1() {
         assert_eq!(GammaEncoder::encode_one(0b10_u32), vec![true, false, false]);
@@ -194,4 +629,161 @@ mod tests {
         let de = GammaDecoder::new(reader);
         assert!(de.decode::<u8>().is_err());
     }
+
+    #[test]
+    fn test_decode_prefix() {
+        let bits = [true, true, true, false, false, false, true, true, false];
+        assert_eq!(GammaDecoder::decode_prefix::<u32>(&bits), Ok((9, 7)));
+        assert!(GammaDecoder::decode_prefix::<u32>(&bits[..6]).is_err());
+        assert!(GammaDecoder::decode_one::<u32>(&bits).is_err());
+    }
+
+    #[test]
+    fn test_sequence_errs() {
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode_sequence(&[2_u32, 3, 9]).unwrap();
+        ge.encode(&[2_u32]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+
+        let mut gd = GammaDecoder::new(Cursor::new(result));
+        assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+        assert!(gd.next_sequence::<u32>().is_err());
+
+        // A huge count in the header fails on the missing numbers, instead of
+        // allocating for them.
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&[u64::MAX, 1, 2]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+        let mut gd = GammaDecoder::new(Cursor::new(result));
+        assert!(gd.next_sequence::<u32>().is_err());
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut bits = vec![];
+        write_gamma_bits(9_u32, zeros, &mut bits);
+        assert_eq!(bits, vec![false, false, false, true, false, false, true]);
+        assert_eq!(decode_gamma_prefix::<u32>(&bits, zeros), Ok((9, 7)));
+        assert_ne!(
+            decode_gamma_prefix::<u32>(&bits, UnaryConvention::Ones),
+            Ok((9, 7))
+        );
+
+        let mut ge = GammaEncoder::with_convention(Cursor::new(vec![]), zeros);
+        assert_eq!(ge.convention(), zeros);
+        ge.encode_sequence(&[2_u32, 3, 9]).unwrap();
+        ge.encode_sequence::<u32>(&[]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+
+        let mut gd = GammaDecoder::with_convention(Cursor::new(result), zeros);
+        assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![2, 3, 9])));
+        assert_eq!(gd.next_sequence::<u32>(), Ok(Some(vec![])));
+        assert_eq!(gd.next_sequence::<u32>(), Ok(None));
+    }
+
+    #[test]
+    fn test_peek_skip() {
+        let nums: Vec<u64> = (1..200).map(|i| i * i * 31).collect();
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+
+        let mut gd = GammaDecoder::new(Cursor::new(result));
+        assert_eq!(gd.peek::<u64>(), Ok(Some(nums[0])));
+        assert_eq!(gd.peek::<u64>(), Ok(Some(nums[0])));
+        gd.skip(0).unwrap();
+        gd.skip(150).unwrap();
+        assert_eq!(gd.peek::<u64>(), Ok(Some(nums[150])));
+        assert!(gd.skip(50).is_err());
+        assert_eq!(gd.peek::<u64>(), Ok(Some(nums[150])));
+        gd.skip(48).unwrap();
+        assert_eq!(gd.decode::<u64>(), Ok(vec![nums[198]]));
+
+        // The code of the last number is cut short.
+        assert!(gamma_code_len(&[true, true, false, true], UnaryConvention::Ones).is_err());
+        assert_eq!(
+            gamma_code_len(&[true, true, false, true, true], UnaryConvention::Ones),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn test_mark_reset() {
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&[7_u32, 1, 2, 3]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+
+        // Probe for a header of 8, and rewind when it is not found.
+        let mut gd = GammaDecoder::new(Cursor::new(result));
+        assert_eq!(gd.mark(), 0);
+        let mark = gd.mark();
+        assert_ne!(gd.peek::<u32>().unwrap(), Some(8));
+        gd.skip(2).unwrap();
+        assert_eq!(gd.mark(), 6);
+        gd.reset(mark).unwrap();
+        assert_eq!(gd.decode::<u32>(), Ok(vec![7, 1, 2, 3]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_reset_past_end() {
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&[7_u32]).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+        GammaDecoder::new(Cursor::new(result)).reset(6).unwrap();
+    }
+
+    #[test]
+    fn test_no_terminating_bit() {
+        // A stream whose last byte is 0 has no terminating bit.
+        let dec = || GammaDecoder::new(Cursor::new(vec![0_u8]));
+        assert_eq!(dec().decode::<u32>(), Err(InvalidCodeError::StreamError));
+        assert_eq!(
+            dec().next_sequence::<u32>(),
+            Err(InvalidCodeError::StreamError)
+        );
+        assert_eq!(dec().peek::<u32>(), Err(InvalidCodeError::StreamError));
+        assert_eq!(dec().skip(1), Err(InvalidCodeError::StreamError));
+        assert_eq!(dec().reset(0), Err(InvalidCodeError::StreamError));
+    }
+
+    #[test]
+    fn test_encode_into_decode_from() {
+        let nums: Vec<u64> = (1..=500_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 40)
+            .map(|n| n.max(1))
+            .collect();
+        let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let expected = enc.finalize().unwrap().into_inner();
+
+        let mut buf = vec![0; expected.len()];
+        assert_eq!(
+            GammaEncoder::encode_into(&nums, &mut buf),
+            Ok(expected.len())
+        );
+        assert_eq!(buf, expected);
+        assert_eq!(
+            GammaEncoder::encode_into(&nums, &mut buf[..expected.len() - 1]),
+            Err(BufferError::BufferTooSmall)
+        );
+
+        let mut out = vec![0_u64; nums.len()];
+        assert_eq!(GammaDecoder::decode_from(&buf, &mut out), Ok(nums.len()));
+        assert_eq!(out, nums);
+        assert_eq!(
+            GammaDecoder::decode_from(&buf, &mut out[..499]),
+            Err(BufferError::BufferTooSmall)
+        );
+        assert_eq!(GammaDecoder::decode_from::<u64>(&[], &mut []), Ok(0));
+        assert_eq!(
+            GammaEncoder::encode_into(&[0_u32], &mut buf),
+            Err(BufferError::InvalidInput)
+        );
+        assert_eq!(
+            GammaDecoder::decode_from::<u64>(&[0b11110000, 0], &mut out),
+            Err(BufferError::InvalidCode(InvalidCodeError::GammaCodeError))
+        );
+    }
 }
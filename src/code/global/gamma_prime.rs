@@ -0,0 +1,268 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+    SkipPrefix,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_offset_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using the interleaved variant of Elias Gamma Encoding (Elias Gamma').
+///
+/// Elias Gamma' uses the same bits as Elias Gamma Encoding, but instead of
+/// writing the unary encoded length of the "offset" bits before them, each
+/// offset bit is preceded by a 1-bit flag, and the code is terminated by a
+/// 0-bit. The decoder therefore handles a single kind of step per bit pair,
+/// instead of counting the length first.
+///
+/// For example, the number 9 in binary is 1001. The offset bits are 001,
+/// so the Elias Gamma' encoding of 9 is 10 10 11 0, while its Elias Gamma
+/// encoding is 1110 001.
+pub struct GammaPrimeEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> GammaPrimeEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        GammaPrimeEncoder { writer }
+    }
+}
+
+impl EncodeOne for GammaPrimeEncoder<()> {
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut offset_bits = vec![];
+        write_offset_bits(&num, &mut offset_bits);
+        let mut bits = Vec::with_capacity(2 * offset_bits.len() + 1);
+        for bit in offset_bits {
+            bits.push(true);
+            bits.push(bit);
+        }
+        bits.push(false);
+        bits
+    }
+}
+
+impl<W: Write> Encoder<W> for GammaPrimeEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for n in nums {
+            let bits = GammaPrimeEncoder::encode_one(*n);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for GammaPrimeEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using the
+/// interleaved variant of Elias Gamma Encoding (Elias Gamma').
+///
+/// Each "offset" bit of the number is preceded by a 1-bit flag, and the code
+/// is terminated by a 0-bit. For example, the Elias Gamma' encoding of 9
+/// (1001) is 1010110.
+pub struct GammaPrimeDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> GammaPrimeDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        GammaPrimeDecoder { cursor }
+    }
+}
+
+impl DecodeOne for GammaPrimeDecoder<()> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::GammaPrimeCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl DecodePrefix for GammaPrimeDecoder<()> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        let mut n_bits = vec![true];
+        let mut pos = 0;
+        loop {
+            match bits.get(pos) {
+                Some(false) => break,
+                Some(true) => {
+                    let bit = bits
+                        .get(pos + 1)
+                        .ok_or(InvalidCodeError::GammaPrimeCodeError)?;
+                    n_bits.push(*bit);
+                    pos += 2;
+                }
+                None => return Err(InvalidCodeError::GammaPrimeCodeError),
+            }
+        }
+
+        match bits_to_numeric(n_bits.as_slice()) {
+            Ok(num) => Ok((num, pos + 1)),
+            _ => Err(InvalidCodeError::GammaPrimeCodeError),
+        }
+    }
+}
+
+impl DecodeBytes for GammaPrimeDecoder<()> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let mut n = 1_u64;
+        while bits
+            .read_bit()
+            .ok_or(InvalidCodeError::GammaPrimeCodeError)?
+        {
+            let bit = bits
+                .read_bit()
+                .ok_or(InvalidCodeError::GammaPrimeCodeError)?;
+            if n > u64::MAX >> 1 {
+                return Err(InvalidCodeError::GammaPrimeCodeError);
+            }
+            n = (n << 1) | u64::from(bit);
+        }
+        let num = T::from_u64(n).ok_or(InvalidCodeError::GammaPrimeCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl SkipPrefix for GammaPrimeDecoder<()> {
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        // Jump over the flag and offset bit pairs, up to the terminating 0-bit.
+        let mut pos = 0;
+        loop {
+            match bits.get(pos) {
+                Some(false) => return Ok(pos + 1),
+                Some(true) if pos + 1 < bits.len() => pos += 2,
+                _ => return Err(InvalidCodeError::GammaPrimeCodeError),
+            }
+        }
+    }
+}
+
+impl<R: Read> Decoder<R> for GammaPrimeDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            nums.push(self.cursor.decode_next::<GammaPrimeDecoder<()>, T>()?);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::{GammaDecoder, GammaEncoder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).map(|n| n | 1).collect();
+        let mut enc = GammaPrimeEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<GammaPrimeDecoder<()>>(&bytes, &nums);
+        assert!(GammaPrimeDecoder::decode_one_from_bytes::<u64>(&[0xFF; 17], 0).is_err());
+    }
+
+    #[test]
+    fn test_encode_one() {
+        assert_eq!(GammaPrimeEncoder::encode_one(1_u32), vec![false]);
+        assert_eq!(
+            GammaPrimeEncoder::encode_one(0b10_u32),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            GammaPrimeEncoder::encode_one(9_u32),
+            vec![true, false, true, false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_decode_one() {
+        let bits = [true, false, true, false, true, true, false];
+        assert_eq!(GammaPrimeDecoder::decode_one::<u32>(&bits), Ok(9));
+        assert!(GammaPrimeDecoder::decode_one::<u32>(&bits[..6]).is_err());
+        assert!(GammaPrimeDecoder::decode_one::<u32>(&[true]).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [2_u32, 3, 9, 1, 1000];
+        let mut ge = GammaPrimeEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let result = ge.finalize().unwrap().into_inner();
+
+        let gd = GammaPrimeDecoder::new(Cursor::new(result));
+        assert_eq!(gd.decode::<u32>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_distinct_from_gamma() {
+        for n in [9_u32, 1000, u16::MAX as u32] {
+            let prime = GammaPrimeEncoder::encode_one(n);
+            let classic = GammaEncoder::encode_one(n);
+            assert_eq!(prime.len(), classic.len());
+            assert_ne!(prime, classic);
+        }
+
+        let nums = [2_u32, 3, 9];
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let classic = ge.finalize().unwrap().into_inner();
+
+        let mut ge = GammaPrimeEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let prime = ge.finalize().unwrap().into_inner();
+        assert_eq!(classic.len(), prime.len());
+        assert_ne!(classic, prime);
+
+        let gd = GammaDecoder::new(Cursor::new(prime));
+        assert_ne!(gd.decode::<u32>().ok(), Some(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_skip_values() {
+        let nums = [2_u32, 3, 9, 1, 1000];
+        let bits: Vec<bool> = nums
+            .iter()
+            .flat_map(|&n| GammaPrimeEncoder::encode_one(n))
+            .collect();
+        for (i, &num) in nums.iter().enumerate() {
+            let pos = GammaPrimeDecoder::skip_values(&bits, i).unwrap();
+            let (decoded, _) = GammaPrimeDecoder::decode_prefix::<u32>(&bits[pos..]).unwrap();
+            assert_eq!(decoded, num);
+        }
+        assert_eq!(GammaPrimeDecoder::skip_values(&bits, 5), Ok(bits.len()));
+        assert!(GammaPrimeDecoder::skip_values(&bits[..bits.len() - 1], 5).is_err());
+        assert!(GammaPrimeDecoder::skip_prefix(&[true]).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::io::{self, Read, Write};
+
+use super::unary::UnaryConvention;
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::convert::{read_minimal_binary, read_minimal_binary_with, write_minimal_binary};
+use crate::num::Numeric;
+
+/// A divisor known at compile time, that parameterizes [`GolombEncoder`] and
+/// [`GolombDecoder`] for encoding and decoding single numbers, with
+/// [`EncodeOne`], [`DecodeOne`], [`DecodePrefix`] and [`DecodeBytes`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::global::golomb::Divisor;
+/// use idencode::{DecodeOne, EncodeOne, GolombDecoder, GolombEncoder};
+///
+/// let bits = GolombEncoder::<Divisor<3>>::encode_one(7_u32);
+/// assert_eq!(bits, vec![true, true, false, true, false]);
+/// assert_eq!(GolombDecoder::<Divisor<3>>::decode_one::<u32>(&bits), Ok(7));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divisor<const B: u64>;
+
+/// Calculates the Golomb code of a number with divisor `b`, writing the
+/// quotient in unary with the specified convention.
+fn write_golomb_bits(num: u64, b: u64, convention: UnaryConvention, buffer: &mut Vec<bool>) {
+    buffer.extend(std::iter::repeat_n(
+        convention.run_bit(),
+        (num / b) as usize,
+    ));
+    buffer.push(convention.stop_bit());
+    write_minimal_binary(u128::from(num % b), u128::from(b), buffer);
+}
+
+/// Decodes the Golomb code with divisor `b` at the start of a buffer of bits,
+/// returning the number along with the number of bits its code occupies.
+fn decode_golomb_prefix<T: Numeric>(
+    bits: &[bool],
+    b: u64,
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let quotient = bits
+        .iter()
+        .position(|b| *b == convention.stop_bit())
+        .ok_or(InvalidCodeError::GolombCodeError)?;
+    let (remainder, len) = read_minimal_binary(&bits[quotient + 1..], u128::from(b))
+        .ok_or(InvalidCodeError::GolombCodeError)?;
+    let num = (quotient as u64)
+        .checked_mul(b)
+        .and_then(|n| n.checked_add(remainder as u64))
+        .and_then(T::from_u64)
+        .ok_or(InvalidCodeError::GolombCodeError)?;
+    Ok((num, quotient + 1 + len))
+}
+
+/// Checks that a divisor is valid.
+fn check_divisor(b: u64) -> u64 {
+    assert!(b > 0, "The Golomb divisor must be greater than 0.");
+    b
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Golomb Encoding.
+///
+/// In Golomb Encoding with divisor *b*, each number *n* is split into a
+/// quotient *q = n / b*, encoded in unary, and a remainder *r = n mod b*,
+/// encoded in truncated binary: with *k = ⌈log2 b⌉*, the first *2^k - b*
+/// remainders are written in *k - 1* bits, and the rest are written as
+/// *r + 2^k - b* in *k* bits. When *b* is a power of two, the code is the
+/// Rice code with parameter *k*.
+///
+/// Golomb codes are optimal for geometrically distributed numbers, like the
+/// gaps of a random set, with *b* close to 0.69 times their mean. The unary
+/// quotient grows linearly with the numbers, so a divisor that is too small
+/// for the numbers produces very long codes.
+///
+/// For example, with *b = 3*, the remainders 0, 1 and 2 are encoded as 0, 10
+/// and 11, so the number 7 is encoded as 110 10.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, GolombDecoder, GolombEncoder};
+///
+/// let mut enc = GolombEncoder::new(Cursor::new(vec![]), 3);
+/// enc.encode(&[7_u32, 0, 2]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b11010000, 0b11100000]);
+///
+/// let dec = GolombDecoder::new(Cursor::new(result), 3);
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![7, 0, 2]);
+/// ```
+pub struct GolombEncoder<W> {
+    writer: BitWriter<W>,
+    b: u64,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> GolombEncoder<W> {
+    /// Creates a new encoder with divisor `b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` is 0.
+    pub fn new(writer: W, b: u64) -> Self {
+        Self::with_convention(writer, b, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the quotient in unary with the
+    /// specified convention.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` is 0.
+    pub fn with_convention(writer: W, b: u64, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::new(writer, true);
+        GolombEncoder {
+            writer,
+            b: check_divisor(b),
+            convention,
+        }
+    }
+
+    /// Returns the divisor of the encoder.
+    pub fn divisor(&self) -> u64 {
+        self.b
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for GolombEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            write_golomb_bits(n.to_u64(), self.b, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for GolombEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<const B: u64> EncodeOne for GolombEncoder<Divisor<B>> {
+    /// Encodes a single number with divisor `B`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is 0.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut bits = vec![];
+        let convention = UnaryConvention::default();
+        write_golomb_bits(num.to_u64(), check_divisor(B), convention, &mut bits);
+        bits
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Golomb Encoding, with the same divisor as the encoder's.
+///
+/// The unary convention of the quotients is not recorded in the stream
+/// either, so a stream encoded with [`GolombEncoder::with_convention`] must
+/// be decoded with the same convention.
+pub struct GolombDecoder<R> {
+    cursor: BitCursor<R>,
+    b: u64,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> GolombDecoder<R> {
+    /// Creates a new decoder with divisor `b`, which must match the
+    /// encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` is 0.
+    pub fn new(reader: R, b: u64) -> Self {
+        Self::with_convention(reader, b, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the quotient in unary with the
+    /// specified convention, which must match the encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` is 0.
+    pub fn with_convention(reader: R, b: u64, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        GolombDecoder {
+            cursor,
+            b: check_divisor(b),
+            convention,
+        }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<R: Read> Decoder<R> for GolombDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let (b, convention) = (self.b, self.convention);
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_golomb_prefix(bits, b, convention))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+impl<const B: u64> DecodeOne for GolombDecoder<Divisor<B>> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::GolombCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<const B: u64> DecodePrefix for GolombDecoder<Divisor<B>> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        decode_golomb_prefix(bits, check_divisor(B), UnaryConvention::default())
+    }
+}
+
+impl<const B: u64> DecodeBytes for GolombDecoder<Divisor<B>> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let b = check_divisor(B);
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let num = bits
+            .read_run(UnaryConvention::default().stop_bit())
+            .and_then(|quotient| {
+                let (remainder, _) = read_minimal_binary_with(u128::from(b), |n| {
+                    bits.read_bits(n as usize).map(u128::from)
+                })?;
+                (quotient as u64)
+                    .checked_mul(b)?
+                    .checked_add(remainder as u64)
+            })
+            .and_then(T::from_u64)
+            .ok_or(InvalidCodeError::GolombCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::assert_decodes_from_bytes;
+    use std::io::Cursor;
+
+    fn encode_one(num: u64, b: u64) -> Vec<bool> {
+        let mut bits = vec![];
+        write_golomb_bits(num, b, UnaryConvention::default(), &mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        // The remainders of divisor 5 are 00, 01, 10, 110 and 111.
+        let codes: Vec<Vec<bool>> = (0..6).map(|n| encode_one(n, 5)).collect();
+        assert_eq!(codes[0], vec![false, false, false]);
+        assert_eq!(codes[2], vec![false, true, false]);
+        assert_eq!(codes[3], vec![false, true, true, false]);
+        assert_eq!(codes[4], vec![false, true, true, true]);
+        assert_eq!(codes[5], vec![true, false, false, false]);
+        // Divisor 1 is unary, and powers of two are Rice codes.
+        assert_eq!(encode_one(3, 1), vec![true, true, true, false]);
+        assert_eq!(encode_one(9, 4), vec![true, true, false, false, true]);
+        assert_eq!(GolombEncoder::<Divisor<5>>::encode_one(5_u8), codes[5]);
+    }
+
+    #[test]
+    fn test_decode_one() {
+        for b in [1, 3, 5, 8, 1000] {
+            for n in [0, 1, 2, 7, 100, 12345] {
+                let bits = encode_one(n, b);
+                assert_eq!(
+                    decode_golomb_prefix::<u64>(&bits, b, UnaryConvention::default()),
+                    Ok((n, bits.len()))
+                );
+            }
+        }
+        let bits = encode_one(300, 100);
+        assert_eq!(
+            GolombDecoder::<Divisor<100>>::decode_one(&bits),
+            Ok(300_u32)
+        );
+        assert!(GolombDecoder::<Divisor<100>>::decode_one::<u8>(&bits).is_err());
+        assert!(GolombDecoder::<Divisor<100>>::decode_one::<u32>(&bits[..bits.len() - 1]).is_err());
+        assert!(GolombDecoder::<Divisor<100>>::decode_one::<u32>(&[true, true]).is_err());
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = (0..300_u64).map(|i| i * i % 1000).collect();
+        let mut enc = GolombEncoder::new(Cursor::new(vec![]), 100);
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<GolombDecoder<Divisor<100>>>(&bytes, &nums);
+        // The number does not fit in the type, or the code is cut short.
+        let bytes = [0b11111111, 0b11111111, 0b11101010];
+        assert!(GolombDecoder::<Divisor<1>>::decode_one_from_bytes::<u8>(&bytes, 0).is_ok());
+        assert!(GolombDecoder::<Divisor<100>>::decode_one_from_bytes::<u8>(&bytes, 0).is_err());
+        assert!(
+            GolombDecoder::<Divisor<3>>::decode_one_from_bytes::<u32>(&[0b11110000], 7).is_err()
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = (1..=1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 54)
+            .collect();
+        for b in [1, 2, 7, 100, 1 << 20, u64::MAX] {
+            let mut enc = GolombEncoder::new(Cursor::new(vec![]), b);
+            assert_eq!(enc.divisor(), b);
+            enc.encode(&nums).unwrap();
+            let last = b.saturating_mul(3).saturating_sub(1);
+            enc.encode(&[last]).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+
+            let dec = GolombDecoder::new(Cursor::new(result), b);
+            let mut expected = nums.clone();
+            expected.push(last);
+            assert_eq!(dec.decode::<u64>().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut enc = GolombEncoder::with_convention(Cursor::new(vec![]), 3, zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&[7_u32, 0, 2]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0b00110101, 0b11100000]);
+
+        let dec = GolombDecoder::with_convention(Cursor::new(result.clone()), 3, zeros);
+        assert_eq!(dec.decode::<u32>().unwrap(), vec![7, 0, 2]);
+        let dec = GolombDecoder::new(Cursor::new(result), 3);
+        assert_ne!(dec.decode::<u32>().ok(), Some(vec![7, 0, 2]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_divisor() {
+        GolombEncoder::new(Cursor::new(vec![]), 0);
+    }
+}
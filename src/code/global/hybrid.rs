@@ -0,0 +1,303 @@
+use std::io::{self, Read, Write};
+
+use super::gamma::{decode_gamma_prefix, write_gamma_bits};
+use super::unary::UnaryConvention;
+use super::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// A structure that wraps a writer and encodes a sequence of integers in
+/// Elias Gamma Encoding, with an escape to Variable Byte Encoding for the
+/// integers above a threshold.
+///
+/// A number *n* less than the threshold *t* is encoded as *n + 1* in Elias
+/// Gamma Encoding, so it may be zero. A larger number is encoded as the
+/// escape, *t + 1* in Elias Gamma Encoding, followed by the excess *n - t* in
+/// Variable Byte Encoding. Small numbers take the few bits of their Elias
+/// Gamma code, while the code of a rare large number grows by a byte per 7
+/// bits, instead of by two bits per bit.
+///
+/// For example, with a threshold of 4, the number 2 is encoded as 101, and
+/// the number 300 as the escape 11001, followed by 00000010 10101000.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, HybridDecoder, HybridEncoder};
+///
+/// let gaps = [1_u64, 3, 0, 2, 1_000_000, 1, 4];
+/// let mut enc = HybridEncoder::new(Cursor::new(vec![]), 16);
+/// enc.encode(&gaps).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result.len(), 7);
+///
+/// let dec = HybridDecoder::new(Cursor::new(result), 16);
+/// assert_eq!(dec.decode::<u64>().unwrap(), gaps);
+/// ```
+pub struct HybridEncoder<W> {
+    writer: BitWriter<W>,
+    threshold: u64,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> HybridEncoder<W> {
+    /// Creates a new encoder, that escapes the numbers greater than or equal
+    /// to `threshold`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is `u64::MAX`.
+    pub fn new(writer: W, threshold: u64) -> Self {
+        Self::with_convention(writer, threshold, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the lengths of the Elias Gamma codes
+    /// in unary with the specified convention.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threshold` is `u64::MAX`.
+    pub fn with_convention(writer: W, threshold: u64, convention: UnaryConvention) -> Self {
+        assert!(
+            threshold < u64::MAX,
+            "The threshold must be less than u64::MAX."
+        );
+        let writer = BitWriter::new(writer, true);
+        HybridEncoder {
+            writer,
+            threshold,
+            convention,
+        }
+    }
+
+    /// Returns the smallest number that is escaped.
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for HybridEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            write_hybrid_bits(n.to_u64(), self.threshold, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for HybridEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`HybridEncoder`].
+///
+/// Neither the threshold nor the unary convention is recorded in the stream,
+/// so both must match the encoder's.
+pub struct HybridDecoder<R> {
+    cursor: BitCursor<R>,
+    threshold: u64,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> HybridDecoder<R> {
+    /// Creates a new decoder, whose threshold must match the encoder's.
+    pub fn new(reader: R, threshold: u64) -> Self {
+        Self::with_convention(reader, threshold, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the lengths of the Elias Gamma codes
+    /// in unary with the specified convention, which must match the
+    /// encoder's.
+    pub fn with_convention(reader: R, threshold: u64, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        HybridDecoder {
+            cursor,
+            threshold,
+            convention,
+        }
+    }
+
+    /// Returns the smallest number that is escaped.
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<R: Read> Decoder<R> for HybridDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let (threshold, convention) = (self.threshold, self.convention);
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_hybrid_prefix(bits, threshold, convention))?;
+            nums.push(T::from_u64(num).ok_or(InvalidCodeError::HybridCodeError)?);
+        }
+        Ok(nums)
+    }
+}
+
+/// Calculates the hybrid code of a number.
+fn write_hybrid_bits(
+    num: u64,
+    threshold: u64,
+    convention: UnaryConvention,
+    buffer: &mut Vec<bool>,
+) {
+    if num < threshold {
+        write_gamma_bits(num + 1, convention, buffer);
+    } else {
+        write_gamma_bits(threshold + 1, convention, buffer);
+        let mut bytes = vec![];
+        write_vb_bytes(num - threshold, &mut bytes);
+        for byte in bytes {
+            write_fixed_bits(&byte, 8, buffer);
+        }
+    }
+}
+
+/// Decodes the hybrid code at the start of a buffer of bits, returning the
+/// number along with the number of bits its code occupies.
+fn decode_hybrid_prefix(
+    bits: &[bool],
+    threshold: u64,
+    convention: UnaryConvention,
+) -> Result<(u64, usize), InvalidCodeError> {
+    let (value, mut pos) = decode_gamma_prefix::<u64>(bits, convention)
+        .map_err(|_| InvalidCodeError::HybridCodeError)?;
+    let num = value - 1;
+    if num < threshold {
+        return Ok((num, pos));
+    }
+    if num > threshold {
+        return Err(InvalidCodeError::HybridCodeError);
+    }
+
+    let mut bytes = vec![];
+    loop {
+        let group = bits
+            .get(pos..pos + 8)
+            .ok_or(InvalidCodeError::HybridCodeError)?;
+        let byte = bits_to_numeric::<u8>(group).expect("A byte fits in u8.");
+        bytes.push(byte);
+        pos += 8;
+        if byte >= 0x80 {
+            break;
+        }
+    }
+    let (excess, _) = decode_vb_prefix(&bytes).map_err(|_| InvalidCodeError::HybridCodeError)?;
+    let num = excess
+        .checked_add(threshold)
+        .ok_or(InvalidCodeError::HybridCodeError)?;
+    Ok((num, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode_one(num: u64, threshold: u64) -> Vec<bool> {
+        let mut bits = vec![];
+        write_hybrid_bits(num, threshold, UnaryConvention::Ones, &mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        assert_eq!(encode_one(2, 4), vec![true, false, true]);
+        let mut expected = vec![true, true, false, false, true];
+        expected.extend([false, false, false, false, false, false, true, false]);
+        expected.extend([true, false, true, false, true, false, false, false]);
+        assert_eq!(encode_one(300, 4), expected);
+        // With a threshold of 0, every number is escaped behind a single bit.
+        assert_eq!(
+            encode_one(0, 0),
+            vec![false, true, false, false, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let ones = UnaryConvention::Ones;
+        let bits = encode_one(300, 4);
+        assert_eq!(decode_hybrid_prefix(&bits, 4, ones), Ok((300, 21)));
+        assert!(decode_hybrid_prefix(&bits[..20], 4, ones).is_err());
+        // A gamma code above the escape is invalid.
+        assert!(decode_hybrid_prefix(&encode_one(3, 8), 2, ones).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 1, 9, 15, 16, 100, 5000, 1 << 40, u64::MAX];
+        for threshold in [0, 1, 16, 1 << 20, u64::MAX - 1] {
+            let mut enc = HybridEncoder::new(Cursor::new(vec![]), threshold);
+            enc.encode(&nums).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+
+            let dec = HybridDecoder::new(Cursor::new(result), threshold);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut bits = vec![];
+        write_hybrid_bits(2, 4, zeros, &mut bits);
+        assert_eq!(bits, vec![false, true, true]);
+
+        let nums = [0_u64, 3, 15, 16, 1000, u64::MAX];
+        let mut enc = HybridEncoder::with_convention(Cursor::new(vec![]), 16, zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = HybridDecoder::with_convention(Cursor::new(result.clone()), 16, zeros);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        let dec = HybridDecoder::new(Cursor::new(result), 16);
+        assert_ne!(dec.decode::<u64>().ok(), Some(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = HybridEncoder::new(Cursor::new(vec![]), 16);
+        enc.encode(&[3_u64, 300]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = HybridDecoder::new(Cursor::new(result), 16);
+        assert_eq!(dec.decode::<u8>(), Err(InvalidCodeError::HybridCodeError));
+    }
+}
@@ -0,0 +1,275 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::{read_minimal_binary, write_minimal_binary};
+use crate::num::Numeric;
+
+/// Checks that a universe can hold a stream of numbers.
+fn check_universe(universe: u64) -> u64 {
+    assert!(
+        universe >= 2,
+        "The universe of a stream must have at least 2 values."
+    );
+    universe
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers from a
+/// bounded universe using minimal binary, also known as truncated binary.
+///
+/// With *s* the bits of *u - 1*, for a universe of *u* values from 0 to
+/// *u - 1*, the first *2^s - u* numbers are written in *s - 1* bits, and the
+/// rest are written as *n + 2^s - u* in *s* bits. So the numbers take
+/// *⌈log2 u⌉* bits at most, and one fewer for the short codewords, which is
+/// the least a prefix code can take when all the numbers are as likely.
+///
+/// For example, for a universe of 5 values, the numbers 0 to 4 are encoded as
+/// 00, 01, 10, 110 and 111.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, MinimalBinaryDecoder, MinimalBinaryEncoder};
+///
+/// let mut enc = MinimalBinaryEncoder::new(Cursor::new(vec![]), 5);
+/// enc.encode(&[0_u32, 4, 2]).unwrap();
+/// assert!(enc.encode(&[5_u32]).is_err());
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b00111101]);
+///
+/// let dec = MinimalBinaryDecoder::new(Cursor::new(result), 5);
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![0, 4, 2]);
+/// ```
+pub struct MinimalBinaryEncoder<W> {
+    writer: BitWriter<W>,
+    universe: u64,
+}
+
+impl<W: Write> MinimalBinaryEncoder<W> {
+    /// Creates a new encoder for the numbers less than `universe`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `universe` is less than 2, since the only number of a
+    /// universe of 1 value takes no bits, and could not be counted.
+    pub fn new(writer: W, universe: u64) -> Self {
+        let writer = BitWriter::new(writer, true);
+        MinimalBinaryEncoder {
+            writer,
+            universe: check_universe(universe),
+        }
+    }
+
+    /// Returns the number of values of the universe of the encoder.
+    pub fn universe(&self) -> u64 {
+        self.universe
+    }
+}
+
+impl MinimalBinaryEncoder<()> {
+    /// Encodes a single number less than `universe`, returning a buffer of
+    /// bits. The only number of a universe of 1 value takes no bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num` is not less than `universe`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::MinimalBinaryEncoder;
+    ///
+    /// assert_eq!(MinimalBinaryEncoder::encode_one(1_u8, 5), vec![false, true]);
+    /// assert_eq!(MinimalBinaryEncoder::encode_one(3_u8, 5), vec![true, true, false]);
+    /// assert_eq!(MinimalBinaryEncoder::encode_one(0_u8, 1), Vec::<bool>::new());
+    /// ```
+    pub fn encode_one<T: Numeric>(num: T, universe: u64) -> Vec<bool> {
+        let num = num.to_u64();
+        assert!(
+            num < universe,
+            "The number must be less than the size of the universe."
+        );
+        let mut bits = vec![];
+        write_minimal_binary(u128::from(num), u128::from(universe), &mut bits);
+        bits
+    }
+}
+
+impl<W: Write> Encoder<W> for MinimalBinaryEncoder<W> {
+    /// Encodes a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is not less than the universe, in which case none are written.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        if nums.iter().any(|num| num.to_u64() >= self.universe) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Number is out of the universe.",
+            ));
+        }
+        let mut bits = vec![];
+        for num in nums {
+            let num = u128::from(num.to_u64());
+            write_minimal_binary(num, u128::from(self.universe), &mut bits);
+        }
+        self.writer.write_bits(&bits)
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for MinimalBinaryEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded in
+/// minimal binary, with the same universe as the encoder's.
+pub struct MinimalBinaryDecoder<R> {
+    cursor: BitCursor<R>,
+    universe: u64,
+}
+
+impl<R: Read> MinimalBinaryDecoder<R> {
+    /// Creates a new decoder for the numbers less than `universe`, which
+    /// must match the encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `universe` is less than 2.
+    pub fn new(reader: R, universe: u64) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        MinimalBinaryDecoder {
+            cursor,
+            universe: check_universe(universe),
+        }
+    }
+}
+
+impl MinimalBinaryDecoder<()> {
+    /// Decodes the number less than `universe` encoded at the start of a
+    /// buffer of bits, returning it along with the number of bits that its
+    /// code occupies.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `universe` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::MinimalBinaryDecoder;
+    ///
+    /// let bits = [true, true, false, false, true];
+    /// assert_eq!(MinimalBinaryDecoder::decode_prefix::<u8>(&bits, 5), Ok((3, 3)));
+    /// assert_eq!(MinimalBinaryDecoder::decode_prefix::<u8>(&bits[3..], 5), Ok((1, 2)));
+    /// ```
+    pub fn decode_prefix<T: Numeric>(
+        bits: &[bool],
+        universe: u64,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        assert!(universe > 0, "The universe must not be empty.");
+        let (num, len) = read_minimal_binary(bits, u128::from(universe))
+            .ok_or(InvalidCodeError::MinimalBinaryCodeError)?;
+        let num = T::from_u64(num as u64).ok_or(InvalidCodeError::MinimalBinaryCodeError)?;
+        Ok((num, len))
+    }
+
+    /// Decodes a buffer of bits to a single number less than `universe`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `universe` is 0.
+    pub fn decode_one<T: Numeric>(bits: &[bool], universe: u64) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits, universe)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::MinimalBinaryCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<R: Read> Decoder<R> for MinimalBinaryDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let universe = self.universe;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| MinimalBinaryDecoder::decode_prefix(bits, universe))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_encode_one() {
+        // A power of two has no short codewords.
+        let codes: Vec<Vec<bool>> = (0..4)
+            .map(|n| MinimalBinaryEncoder::encode_one(n as u8, 4))
+            .collect();
+        assert!(codes.iter().all(|code| code.len() == 2));
+        assert_eq!(MinimalBinaryEncoder::encode_one(0_u8, 2), vec![false]);
+        assert_eq!(MinimalBinaryEncoder::encode_one(0_u8, 3), vec![false]);
+        assert_eq!(MinimalBinaryEncoder::encode_one(2_u8, 3), vec![true, true]);
+        let max = MinimalBinaryEncoder::encode_one(u64::MAX - 1, u64::MAX);
+        assert_eq!(max, vec![true; 64]);
+    }
+
+    #[test]
+    fn test_decode_one() {
+        for universe in [1, 2, 3, 5, 1000, u64::MAX] {
+            for num in [0, 1, 2, 999, u64::MAX - 1]
+                .into_iter()
+                .filter(|n| *n < universe)
+            {
+                let bits = MinimalBinaryEncoder::encode_one(num, universe);
+                assert_eq!(MinimalBinaryDecoder::decode_one(&bits, universe), Ok(num));
+            }
+        }
+        let bits = MinimalBinaryEncoder::encode_one(300_u32, 1000);
+        assert!(MinimalBinaryDecoder::decode_one::<u8>(&bits, 1000).is_err());
+        assert!(MinimalBinaryDecoder::decode_one::<u32>(&bits[1..], 1000).is_err());
+        assert!(MinimalBinaryDecoder::decode_one::<u32>(&[true, true], 5).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = (1..=1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 54)
+            .collect();
+        for universe in [1024, 1025, 3000, u64::MAX] {
+            let mut enc = MinimalBinaryEncoder::new(Cursor::new(vec![]), universe);
+            enc.encode(&nums).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+            let dec = MinimalBinaryDecoder::new(Cursor::new(result), universe);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_universe_of_one() {
+        MinimalBinaryEncoder::new(Cursor::new(vec![]), 1);
+    }
+}
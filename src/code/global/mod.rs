@@ -1,4 +1,14 @@
+pub mod basc;
 pub mod delta;
+pub mod escaped_rice;
 pub mod gamma;
+pub mod gamma_prime;
+pub mod golomb;
+pub mod hybrid;
+pub mod minimal_binary;
+pub mod omega;
+pub mod rice;
+pub mod sss;
 pub mod unary;
 pub mod vb;
+pub mod zeta;
@@ -0,0 +1,295 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+    SkipPrefix,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Elias Omega Encoding.
+///
+/// In Elias Omega Encoding, the binary digits of a number are preceded by
+/// their length, minus one, which is itself written in binary, preceded by
+/// its own length, recursively, until the length is 1. The code ends with a
+/// 0-bit. Every group starts with a 1-bit, so the 0-bit tells the end of the
+/// groups apart from the start of a new one.
+///
+/// For example, the number 17 in binary is 10001, so it is preceded by 4,
+/// which is 100, which is preceded by 2, which is 10. Therefore, the Elias
+/// Omega encoding of 17 is 10 100 10001 0.
+///
+/// The lengths grow with the iterated logarithm of the numbers, so omega
+/// codes are shorter than delta codes for very large numbers.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, OmegaDecoder, OmegaEncoder};
+///
+/// let mut enc = OmegaEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[17_u32, 1]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b10100100, 0b01001000]);
+///
+/// let dec = OmegaDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![17, 1]);
+/// ```
+pub struct OmegaEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> OmegaEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        OmegaEncoder { writer }
+    }
+}
+
+impl<W: Write> Encoder<W> for OmegaEncoder<W> {
+    /// Encodes and writes a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is 0, which has no Elias Omega code.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            let n = n.to_u64();
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Elias Omega Encoding cannot encode 0.",
+                ));
+            }
+            bits.clear();
+            write_omega_bits(n, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for OmegaEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl EncodeOne for OmegaEncoder<()> {
+    /// Encodes a single number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number is 0.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let num = num.to_u64();
+        assert!(num > 0, "Elias Omega Encoding cannot encode 0.");
+        let mut bits = vec![];
+        write_omega_bits(num, &mut bits);
+        bits
+    }
+}
+
+/// Calculates the Elias Omega code of a positive number.
+fn write_omega_bits(num: u64, buffer: &mut Vec<bool>) {
+    let mut groups = vec![];
+    let mut n = num;
+    while n > 1 {
+        let width = u64::BITS - n.leading_zeros();
+        groups.push((n, width));
+        n = u64::from(width - 1);
+    }
+    for (n, width) in groups.into_iter().rev() {
+        write_fixed_bits(&n, width, buffer);
+    }
+    buffer.push(false);
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Elias Omega Encoding.
+pub struct OmegaDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> OmegaDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        OmegaDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for OmegaDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            nums.push(self.cursor.decode_next::<OmegaDecoder<()>, T>()?);
+        }
+        Ok(nums)
+    }
+}
+
+impl DecodeOne for OmegaDecoder<()> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::OmegaCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl DecodePrefix for OmegaDecoder<()> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        let (num, len) = decode_omega_prefix(bits)?;
+        let num = T::from_u64(num).ok_or(InvalidCodeError::OmegaCodeError)?;
+        Ok((num, len))
+    }
+}
+
+impl DecodeBytes for OmegaDecoder<()> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let mut num = 1_u64;
+        loop {
+            match bits.read_bit() {
+                Some(false) => break,
+                Some(true) if num < u64::from(u64::BITS) => {
+                    let rest = bits
+                        .read_bits(num as usize)
+                        .ok_or(InvalidCodeError::OmegaCodeError)?;
+                    num = (1 << num) | rest;
+                }
+                _ => return Err(InvalidCodeError::OmegaCodeError),
+            }
+        }
+        let num = T::from_u64(num).ok_or(InvalidCodeError::OmegaCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl SkipPrefix for OmegaDecoder<()> {
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        decode_omega_prefix(bits).map(|(_, len)| len)
+    }
+}
+
+/// Decodes the Elias Omega code at the start of a buffer of bits, returning
+/// the number along with the number of bits its code occupies.
+fn decode_omega_prefix(bits: &[bool]) -> Result<(u64, usize), InvalidCodeError> {
+    let mut num = 1_u64;
+    let mut pos = 0;
+    loop {
+        match bits.get(pos) {
+            Some(false) => return Ok((num, pos + 1)),
+            Some(true) if num < u64::from(u64::BITS) => {
+                let width = num as usize + 1;
+                let group = bits
+                    .get(pos..pos + width)
+                    .ok_or(InvalidCodeError::OmegaCodeError)?;
+                num = bits_to_numeric(group).expect("A group of at most 64 bits fits in u64.");
+                pos += width;
+            }
+            _ => return Err(InvalidCodeError::OmegaCodeError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use std::io::Cursor;
+
+    fn to_bits(s: &str) -> Vec<bool> {
+        s.chars().map(|c| c == '1').collect()
+    }
+
+    #[test]
+    fn test_encode_one() {
+        assert_eq!(OmegaEncoder::encode_one(1_u8), to_bits("0"));
+        assert_eq!(OmegaEncoder::encode_one(2_u8), to_bits("100"));
+        assert_eq!(OmegaEncoder::encode_one(4_u8), to_bits("101000"));
+        assert_eq!(OmegaEncoder::encode_one(17_u8), to_bits("10100100010"));
+        assert_eq!(OmegaEncoder::encode_one(100_u8), to_bits("1011011001000"));
+        let bits = OmegaEncoder::encode_one(u64::MAX);
+        assert_eq!(bits.len(), 2 + 3 + 6 + 64 + 1);
+    }
+
+    #[test]
+    fn test_decode_one() {
+        for num in [1_u64, 2, 3, 7, 8, 17, 100, 1 << 20, u64::MAX - 1, u64::MAX] {
+            let bits = OmegaEncoder::encode_one(num);
+            assert_eq!(OmegaDecoder::decode_one(&bits), Ok(num));
+            assert_eq!(OmegaDecoder::skip_prefix(&bits), Ok(bits.len()));
+        }
+        assert_eq!(OmegaDecoder::decode_prefix(&to_bits("1101")), Ok((3_u8, 3)));
+        let bits = to_bits("10100100010");
+        assert!(OmegaDecoder::decode_one::<u16>(&bits[..10]).is_err());
+        assert!(OmegaDecoder::decode_one::<u16>(&to_bits("1000")).is_err());
+        assert!(OmegaDecoder::decode_one::<u8>(&OmegaEncoder::encode_one(256_u16)).is_err());
+        assert_eq!(
+            OmegaDecoder::decode_one::<u16>(&[]),
+            Err(InvalidCodeError::OmegaCodeError)
+        );
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).map(|n| n | 1).collect();
+        let mut enc = OmegaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<OmegaDecoder<()>>(&bytes, &nums);
+        // The number does not fit in the type, or the code is cut short.
+        let mut enc = OmegaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[1000_u32]).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+        assert!(OmegaDecoder::decode_one_from_bytes::<u8>(&bytes, 0).is_err());
+        assert!(OmegaDecoder::decode_one_from_bytes::<u32>(&[0b11111111], 0).is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        // The length group 1000000 announces a group of 65 bits.
+        let mut bits = to_bits("101101000000");
+        bits.extend([true; 65]);
+        bits.push(false);
+        assert_eq!(
+            OmegaDecoder::decode_one::<u64>(&bits),
+            Err(InvalidCodeError::OmegaCodeError)
+        );
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..=1000).map(|n| n.max(1)).collect();
+        let mut enc = OmegaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        assert!(enc.encode(&[0_u32]).is_err());
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = OmegaDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+    }
+}
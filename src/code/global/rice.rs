@@ -0,0 +1,395 @@
+use std::io::{self, Read, Write};
+
+use super::unary::UnaryConvention;
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A Rice parameter known at compile time, that parameterizes
+/// [`RiceEncoder`] and [`RiceDecoder`] for encoding and decoding single
+/// numbers, with [`EncodeOne`], [`DecodeOne`], [`DecodePrefix`] and
+/// [`DecodeBytes`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::global::rice::Parameter;
+/// use idencode::{DecodeBytes, EncodeOne, RiceDecoder, RiceEncoder};
+///
+/// let bits = RiceEncoder::<Parameter<2>>::encode_one(9_u32);
+/// assert_eq!(bits, vec![true, true, false, false, true]);
+///
+/// // The Rice codes of 9 (11001) and 2 (010) with k = 2.
+/// let bytes = [0b11001010];
+/// assert_eq!(RiceDecoder::<Parameter<2>>::decode_one_from_bytes::<u32>(&bytes, 0), Ok((9, 5)));
+/// assert_eq!(RiceDecoder::<Parameter<2>>::decode_one_from_bytes::<u32>(&bytes, 5), Ok((2, 3)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameter<const K: u32>;
+
+/// Checks that a Rice parameter is valid.
+fn check_parameter(k: u32) -> u32 {
+    assert!(k < u64::BITS, "The Rice parameter must be less than 64.");
+    k
+}
+
+/// Calculates the Rice code of a number with parameter `k`, writing the
+/// quotient in unary with the specified convention.
+fn write_rice_bits(num: u64, k: u32, convention: UnaryConvention, buffer: &mut Vec<bool>) {
+    buffer.extend(std::iter::repeat_n(
+        convention.run_bit(),
+        (num >> k) as usize,
+    ));
+    buffer.push(convention.stop_bit());
+    buffer.extend((0..k).rev().map(|i| (num >> i) & 1 == 1));
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using Rice Encoding.
+///
+/// Rice Encoding with parameter *k* is the Golomb Encoding with divisor
+/// *2^k*: each number *n* is split into a quotient *n >> k*, encoded in
+/// unary, and its *k* low bits, written as they are. Since the divisor is a
+/// power of two, the numbers are split with shifts and masks instead of
+/// divisions, and the remainders have a fixed width, so it is faster than
+/// [`GolombEncoder`](crate::GolombEncoder).
+///
+/// For example, with *k = 2*, the number 9 has a quotient of 2 and a
+/// remainder of 1, so it is encoded as 110 01. Outliers produce long unary
+/// quotients; [`EscapedRiceEncoder`](crate::EscapedRiceEncoder) bounds them.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, RiceDecoder, RiceEncoder};
+///
+/// let mut enc = RiceEncoder::new(Cursor::new(vec![]), 2);
+/// enc.encode(&[9_u32, 2]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b11001010, 0b10000000]);
+///
+/// let dec = RiceDecoder::new(Cursor::new(result), 2);
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![9, 2]);
+/// ```
+pub struct RiceEncoder<W> {
+    writer: BitWriter<W>,
+    k: u32,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> RiceEncoder<W> {
+    /// Creates a new encoder with Rice parameter `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than 64.
+    pub fn new(writer: W, k: u32) -> Self {
+        Self::with_convention(writer, k, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the quotient in unary with the
+    /// specified convention.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than 64.
+    pub fn with_convention(writer: W, k: u32, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::new(writer, true);
+        RiceEncoder {
+            writer,
+            k: check_parameter(k),
+            convention,
+        }
+    }
+
+    /// Creates a new encoder whose stream is padded with 0-bits to a whole
+    /// byte, without a terminating bit, as in the filters of BIP158. The
+    /// number of codes must be known to decode it, with
+    /// [`RiceDecoder::read_code`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than 64.
+    pub(crate) fn unterminated(writer: W, k: u32) -> Self {
+        RiceEncoder {
+            writer: BitWriter::new(writer, false),
+            k: check_parameter(k),
+            convention: UnaryConvention::default(),
+        }
+    }
+
+    /// Returns the Rice parameter of the encoder.
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for RiceEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            write_rice_bits(n.to_u64(), self.k, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for RiceEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Rice Encoding, with the same parameter as the encoder's.
+///
+/// The unary convention of the quotients is not recorded in the stream
+/// either, so a stream encoded with [`RiceEncoder::with_convention`] must be
+/// decoded with the same convention.
+pub struct RiceDecoder<R> {
+    cursor: BitCursor<R>,
+    k: u32,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> RiceDecoder<R> {
+    /// Creates a new decoder with Rice parameter `k`, which must match the
+    /// encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than 64.
+    pub fn new(reader: R, k: u32) -> Self {
+        Self::with_convention(reader, k, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the quotient in unary with the
+    /// specified convention, which must match the encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than 64.
+    pub fn with_convention(reader: R, k: u32, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        RiceDecoder {
+            cursor,
+            k: check_parameter(k),
+            convention,
+        }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<R: Read> Decoder<R> for RiceDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let (k, convention) = (self.k, self.convention);
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_rice_prefix(bits, k, convention))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+impl RiceDecoder<()> {
+    /// Reads the Rice code with parameter `k` at the position of the bits,
+    /// moving past it, or returns `None` if the bits end before it or the
+    /// number does not fit in 64 bits.
+    pub(crate) fn read_code(bits: &mut ByteBits<'_>, k: u32) -> Option<u64> {
+        let quotient = bits.read_run(UnaryConvention::default().stop_bit())? as u64;
+        if quotient.leading_zeros() < k {
+            return None;
+        }
+        Some((quotient << k) | bits.read_bits(k as usize)?)
+    }
+}
+
+impl<const K: u32> EncodeOne for RiceEncoder<Parameter<K>> {
+    /// Encodes a single number with Rice parameter `K`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is not less than 64.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut bits = vec![];
+        let convention = UnaryConvention::default();
+        write_rice_bits(num.to_u64(), check_parameter(K), convention, &mut bits);
+        bits
+    }
+}
+
+impl<const K: u32> DecodeOne for RiceDecoder<Parameter<K>> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::RiceCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<const K: u32> DecodePrefix for RiceDecoder<Parameter<K>> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        decode_rice_prefix(bits, check_parameter(K), UnaryConvention::default())
+    }
+}
+
+impl<const K: u32> DecodeBytes for RiceDecoder<Parameter<K>> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let num = RiceDecoder::read_code(&mut bits, check_parameter(K))
+            .and_then(T::from_u64)
+            .ok_or(InvalidCodeError::RiceCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+/// Decodes the Rice code at the start of a buffer of bits, returning the
+/// number along with the number of bits its code occupies.
+fn decode_rice_prefix<T: Numeric>(
+    bits: &[bool],
+    k: u32,
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let quotient = bits
+        .iter()
+        .position(|b| *b == convention.stop_bit())
+        .ok_or(InvalidCodeError::RiceCodeError)?;
+    let end = quotient + 1 + k as usize;
+    let remainder = bits
+        .get(quotient + 1..end)
+        .ok_or(InvalidCodeError::RiceCodeError)?
+        .iter()
+        .fold(0_u64, |acc, bit| (acc << 1) | u64::from(*bit));
+    if (quotient as u64).leading_zeros() < k {
+        return Err(InvalidCodeError::RiceCodeError);
+    }
+    let num =
+        T::from_u64(((quotient as u64) << k) | remainder).ok_or(InvalidCodeError::RiceCodeError)?;
+    Ok((num, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::assert_decodes_from_bytes;
+    use crate::GolombEncoder;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], k: u32) -> Vec<u8> {
+        let mut enc = RiceEncoder::new(Cursor::new(vec![]), k);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let ones = UnaryConvention::Ones;
+        let bits = [true, true, false, false, true];
+        assert_eq!(decode_rice_prefix::<u32>(&bits, 2, ones), Ok((9, 5)));
+        assert_eq!(decode_rice_prefix::<u32>(&bits, 0, ones), Ok((2, 3)));
+        assert!(decode_rice_prefix::<u32>(&bits[..4], 2, ones).is_err());
+        assert!(decode_rice_prefix::<u32>(&[true; 3], 2, ones).is_err());
+        assert!(decode_rice_prefix::<u8>(&[true, true, false, false], 7, ones).is_err());
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = (0..300_u64).map(|i| i * i % 1000).collect();
+        let bytes = encode(&nums, 5);
+        assert_decodes_from_bytes::<RiceDecoder<Parameter<5>>>(&bytes, &nums);
+        // The number does not fit in the type, or the code is cut short.
+        let bytes = [0b11111111, 0b11111111, 0b00000000];
+        assert!(RiceDecoder::<Parameter<4>>::decode_one_from_bytes::<u8>(&bytes, 0).is_err());
+        assert!(RiceDecoder::<Parameter<4>>::decode_one_from_bytes::<u32>(&bytes, 0).is_ok());
+        assert!(RiceDecoder::<Parameter<4>>::decode_one_from_bytes::<u32>(&bytes, 21).is_err());
+        assert_eq!(
+            RiceDecoder::<Parameter<4>>::decode_one(&RiceEncoder::<Parameter<4>>::encode_one(
+                77_u8
+            )),
+            Ok(77_u8)
+        );
+    }
+
+    #[test]
+    fn test_matches_golomb() {
+        let nums: Vec<u64> = (1..=1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 52)
+            .collect();
+        for k in [0, 1, 5, 12] {
+            let mut golomb = GolombEncoder::new(Cursor::new(vec![]), 1 << k);
+            golomb.encode(&nums).unwrap();
+            let expected = golomb.finalize().unwrap().into_inner();
+            assert_eq!(encode(&nums, k), expected);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 1, 9, 15, 16, 100, 5000];
+        for k in [0, 3, 10] {
+            let dec = RiceDecoder::new(Cursor::new(encode(&nums, k)), k);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+        let nums = [0_u64, 5000, u64::MAX >> 3, u64::MAX];
+        for k in [60, 63] {
+            let dec = RiceDecoder::new(Cursor::new(encode(&nums, k)), k);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let mut enc = RiceEncoder::with_convention(Cursor::new(vec![]), 2, zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&[9_u32, 2]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0b00101110, 0b10000000]);
+
+        let dec = RiceDecoder::with_convention(Cursor::new(result.clone()), 2, zeros);
+        assert_eq!(dec.decode::<u32>().unwrap(), vec![9, 2]);
+        let dec = RiceDecoder::new(Cursor::new(result), 2);
+        assert_ne!(dec.decode::<u32>().ok(), Some(vec![9, 2]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_parameter() {
+        RiceEncoder::new(Cursor::new(vec![]), 64);
+    }
+}
@@ -0,0 +1,542 @@
+use std::io::{self, Read, Write};
+
+use super::unary::UnaryConvention;
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Numeric};
+
+/// The number of bits of each parameter in the header of the stream.
+const PARAM_BITS: u32 = 7;
+
+/// Parameters known at compile time, that parameterize
+/// [`StartStepStopEncoder`] and [`StartStepStopDecoder`] for encoding and
+/// decoding single numbers, with [`EncodeOne`], [`DecodeOne`],
+/// [`DecodePrefix`] and [`DecodeBytes`]. Single codes have no header, so the
+/// parameters are not written.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::global::sss::Parameters;
+/// use idencode::{DecodeBytes, EncodeOne, StartStepStopDecoder, StartStepStopEncoder};
+///
+/// type Sss = Parameters<1, 2, 5>;
+/// let bits = StartStepStopEncoder::<Sss>::encode_one(4_u32);
+/// assert_eq!(bits, vec![true, false, false, true, false]);
+///
+/// let bytes = [0b10010000];
+/// assert_eq!(StartStepStopDecoder::<Sss>::decode_one_from_bytes::<u32>(&bytes, 0), Ok((4, 5)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameters<const START: u32, const STEP: u32, const STOP: u32>;
+
+impl<const START: u32, const STEP: u32, const STOP: u32> Parameters<START, STEP, STOP> {
+    /// Returns the parameters, checking that they are valid.
+    fn params() -> Params {
+        let params = Params {
+            start: START,
+            step: STEP,
+            stop: STOP,
+        };
+        assert!(params.is_valid(), "Invalid Start-Step-Stop parameters.");
+        params
+    }
+}
+
+/// The parameters of a Start-Step-Stop code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    start: u32,
+    step: u32,
+    stop: u32,
+}
+
+impl Params {
+    /// Returns whether the widths start at `start` and reach exactly `stop`,
+    /// in increments of `step`.
+    fn is_valid(&self) -> bool {
+        if self.start > self.stop || self.stop > u64::BITS {
+            return false;
+        }
+        match self.step {
+            0 => self.start == self.stop,
+            step => (self.stop - self.start).is_multiple_of(step),
+        }
+    }
+
+    /// Returns the index of the last group of codewords.
+    fn last_group(&self) -> usize {
+        match self.step {
+            0 => 0,
+            step => ((self.stop - self.start) / step) as usize,
+        }
+    }
+
+    /// Returns the width of the value bits of a group.
+    fn width(&self, group: usize) -> u32 {
+        self.start + group as u32 * self.step
+    }
+
+    /// Returns the largest number in the range of the code.
+    fn max_value(&self) -> u64 {
+        let size: u128 = (0..=self.last_group())
+            .map(|g| 1_u128 << self.width(g))
+            .sum();
+        u64::try_from(size - 1).unwrap_or(u64::MAX)
+    }
+
+    /// Calculates the Start-Step-Stop code of a number, writing the index of
+    /// its group in unary with the specified convention, and returns `false`
+    /// if the number is out of the range of the code.
+    fn write_bits(&self, num: u64, convention: UnaryConvention, buffer: &mut Vec<bool>) -> bool {
+        let mut rest = u128::from(num);
+        for group in 0..=self.last_group() {
+            let width = self.width(group);
+            let size = 1_u128 << width;
+            if rest < size {
+                buffer.extend(std::iter::repeat_n(convention.run_bit(), group));
+                if group < self.last_group() {
+                    buffer.push(convention.stop_bit());
+                }
+                write_fixed_bits(&(rest as u64), width, buffer);
+                return true;
+            }
+            rest -= size;
+        }
+        false
+    }
+
+    /// Decodes the Start-Step-Stop code at the start of a buffer of bits,
+    /// returning the number along with the number of bits its code occupies.
+    fn decode_prefix(
+        &self,
+        bits: &[bool],
+        convention: UnaryConvention,
+    ) -> Result<(u64, usize), InvalidCodeError> {
+        let last = self.last_group();
+        let group = bits
+            .iter()
+            .take(last)
+            .take_while(|b| **b == convention.run_bit())
+            .count();
+        let mut pos = group;
+        if group < last {
+            if bits.get(pos) != Some(&convention.stop_bit()) {
+                return Err(InvalidCodeError::StartStepStopCodeError);
+            }
+            pos += 1;
+        }
+
+        let width = self.width(group);
+        let value_bits = bits
+            .get(pos..pos + width as usize)
+            .ok_or(InvalidCodeError::StartStepStopCodeError)?;
+        let value = bits_to_numeric::<u64>(value_bits)
+            .map_err(|_| InvalidCodeError::StartStepStopCodeError)?;
+        let base: u128 = (0..group).map(|g| 1_u128 << self.width(g)).sum();
+
+        let num = u64::try_from(base + u128::from(value))
+            .map_err(|_| InvalidCodeError::StartStepStopCodeError)?;
+        Ok((num, pos + width as usize))
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// a Start-Step-Stop code.
+///
+/// A Start-Step-Stop code with parameters *(start, step, stop)* splits the
+/// numbers into groups of codewords, whose values are written with *start*,
+/// *start + step*, *start + 2 step*, ... up to *stop* bits. The index of the
+/// group of a number is encoded in unary, without the terminating 0-bit for
+/// the last group, followed by the position of the number within the group.
+/// Choosing the parameters fits the code to a known distribution of values,
+/// without going all the way to a Huffman code.
+///
+/// The parameters are written in the header of the stream, so the decoder
+/// does not need to know them in advance.
+///
+/// For example, with parameters (1, 2, 5), the numbers 0-1 are encoded with 1
+/// value bit, the numbers 2-9 with 3 value bits, and the numbers 10-41 with 5
+/// value bits. The number 4 is encoded as 10 010.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, StartStepStopDecoder, StartStepStopEncoder};
+///
+/// let mut enc = StartStepStopEncoder::new(Cursor::new(vec![]), 1, 2, 5);
+/// enc.encode(&[4_u32, 0, 41]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let dec = StartStepStopDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![4, 0, 41]);
+/// ```
+pub struct StartStepStopEncoder<W> {
+    writer: BitWriter<W>,
+    params: Params,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> StartStepStopEncoder<W> {
+    /// Creates a new encoder with the specified parameters, and writes them
+    /// in the header of the stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stop` is less than `start` or greater than 64, or if `stop`
+    /// cannot be reached from `start` in increments of `step`.
+    pub fn new(writer: W, start: u32, step: u32, stop: u32) -> Self {
+        Self::with_convention(writer, start, step, stop, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the indices of the groups in unary
+    /// with the specified convention. The convention is not written in the
+    /// header, unlike the parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stop` is less than `start` or greater than 64, or if `stop`
+    /// cannot be reached from `start` in increments of `step`.
+    pub fn with_convention(
+        writer: W,
+        start: u32,
+        step: u32,
+        stop: u32,
+        convention: UnaryConvention,
+    ) -> Self {
+        let params = Params { start, step, stop };
+        assert!(params.is_valid(), "Invalid Start-Step-Stop parameters.");
+
+        let mut writer = BitWriter::new(writer, true);
+        let mut header = Vec::with_capacity(3 * PARAM_BITS as usize);
+        for param in [start, step, stop] {
+            write_fixed_bits(&param, PARAM_BITS, &mut header);
+        }
+        for bit in header {
+            writer.get_mut().push(bit);
+        }
+        StartStepStopEncoder {
+            writer,
+            params,
+            convention,
+        }
+    }
+
+    /// Returns the largest number that the code can encode.
+    pub fn max_value(&self) -> u64 {
+        self.params.max_value()
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<W: Write> Encoder<W> for StartStepStopEncoder<W> {
+    /// Encodes and writes a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is greater than the largest number of the code.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            bits.clear();
+            if !self
+                .params
+                .write_bits(n.to_u64(), self.convention, &mut bits)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Number out of the range of the Start-Step-Stop code.",
+                ));
+            }
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for StartStepStopEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using a
+/// Start-Step-Stop code.
+///
+/// The parameters of the code are read from the header of the stream, as
+/// written by [`StartStepStopEncoder`]. The unary convention is not recorded
+/// in the stream, so a stream encoded with
+/// [`StartStepStopEncoder::with_convention`] must be decoded with the same
+/// convention.
+pub struct StartStepStopDecoder<R> {
+    cursor: BitCursor<R>,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> StartStepStopDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_convention(reader, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the indices of the groups in unary
+    /// with the specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        StartStepStopDecoder { cursor, convention }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
+
+impl<const START: u32, const STEP: u32, const STOP: u32> EncodeOne
+    for StartStepStopEncoder<Parameters<START, STEP, STOP>>
+{
+    /// Encodes a single number with the parameters `START`, `STEP` and
+    /// `STOP`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters are invalid, or if the number is out of the
+    /// range of the code.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let mut bits = vec![];
+        let params = Parameters::<START, STEP, STOP>::params();
+        assert!(
+            params.write_bits(num.to_u64(), UnaryConvention::default(), &mut bits),
+            "Number out of the range of the Start-Step-Stop code."
+        );
+        bits
+    }
+}
+
+impl<const START: u32, const STEP: u32, const STOP: u32> DecodeOne
+    for StartStepStopDecoder<Parameters<START, STEP, STOP>>
+{
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::StartStepStopCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<const START: u32, const STEP: u32, const STOP: u32> DecodePrefix
+    for StartStepStopDecoder<Parameters<START, STEP, STOP>>
+{
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        let params = Parameters::<START, STEP, STOP>::params();
+        let (num, len) = params.decode_prefix(bits, UnaryConvention::default())?;
+        let num = T::from_u64(num).ok_or(InvalidCodeError::StartStepStopCodeError)?;
+        Ok((num, len))
+    }
+}
+
+impl<const START: u32, const STEP: u32, const STOP: u32> DecodeBytes
+    for StartStepStopDecoder<Parameters<START, STEP, STOP>>
+{
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let params = Parameters::<START, STEP, STOP>::params();
+        let run_bit = UnaryConvention::default().run_bit();
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let mut group = 0;
+        while group < params.last_group() {
+            match bits.read_bit() {
+                Some(bit) if bit == run_bit => group += 1,
+                Some(_) => break,
+                None => return Err(InvalidCodeError::StartStepStopCodeError),
+            }
+        }
+        let base: u128 = (0..group).map(|g| 1_u128 << params.width(g)).sum();
+        let num = bits
+            .read_bits(params.width(group) as usize)
+            .and_then(|value| u64::try_from(base + u128::from(value)).ok())
+            .and_then(T::from_u64)
+            .ok_or(InvalidCodeError::StartStepStopCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+/// Decodes the parameters in the header of the stream.
+fn decode_header(bits: &[bool]) -> Result<(Params, usize), InvalidCodeError> {
+    let len = 3 * PARAM_BITS as usize;
+    let header = bits
+        .get(..len)
+        .ok_or(InvalidCodeError::StartStepStopCodeError)?;
+    let mut params = header
+        .chunks(PARAM_BITS as usize)
+        .map(|chunk| bits_to_numeric::<u32>(chunk).expect("A parameter fits in u32."));
+    let params = Params {
+        start: params.next().expect("The header has three parameters."),
+        step: params.next().expect("The header has three parameters."),
+        stop: params.next().expect("The header has three parameters."),
+    };
+    if !params.is_valid() {
+        return Err(InvalidCodeError::StartStepStopCodeError);
+    }
+    Ok((params, len))
+}
+
+impl<R: Read> Decoder<R> for StartStepStopDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let params = self.cursor.decode_with(decode_header)?;
+        let convention = self.convention;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| params.decode_prefix(bits, convention))?;
+            nums.push(T::from_u64(num).ok_or(InvalidCodeError::StartStepStopCodeError)?);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::assert_decodes_from_bytes;
+    use std::io::Cursor;
+
+    fn encode_one(num: u64, params: Params) -> Vec<bool> {
+        let mut bits = vec![];
+        assert!(params.write_bits(num, UnaryConvention::Ones, &mut bits));
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        let params = Params {
+            start: 1,
+            step: 2,
+            stop: 5,
+        };
+        assert_eq!(encode_one(1, params), vec![false, true]);
+        assert_eq!(encode_one(4, params), vec![true, false, false, true, false]);
+        assert_eq!(encode_one(41, params), vec![true; 7]);
+        assert!(!params.write_bits(42, UnaryConvention::Ones, &mut vec![]));
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let params = Params {
+            start: 1,
+            step: 2,
+            stop: 5,
+        };
+        let ones = UnaryConvention::Ones;
+        assert_eq!(params.decode_prefix(&[true; 7], ones), Ok((41, 7)));
+        assert_eq!(
+            params.decode_prefix(&[true, false, false, true, false], ones),
+            Ok((4, 5))
+        );
+        assert!(params.decode_prefix(&[true, false, false], ones).is_err());
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        type Sss = Parameters<2, 31, 64>;
+        let nums = [0_u64, 3, 4, 1 << 32, u64::MAX, 7];
+        let mut bits = vec![];
+        for &num in &nums {
+            bits.extend(StartStepStopEncoder::<Sss>::encode_one(num));
+        }
+        bits.resize(bits.len().next_multiple_of(8), false);
+        let bytes: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| bits_to_numeric(byte).unwrap())
+            .collect();
+
+        assert_decodes_from_bytes::<StartStepStopDecoder<Sss>>(&bytes, &nums);
+        // The number does not fit in the type, or the code is cut short.
+        type Small = Parameters<1, 2, 5>;
+        assert!(StartStepStopDecoder::<Small>::decode_one_from_bytes::<u8>(&[0xFF], 0).is_ok());
+        assert!(StartStepStopDecoder::<Sss>::decode_one_from_bytes::<u8>(&[0xFF; 9], 0).is_err());
+        assert!(StartStepStopDecoder::<Small>::decode_one_from_bytes::<u8>(&[0xFF], 2).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums = [0_u64, 1, 2, 100, 5000, 1 << 40, u64::MAX];
+        for (start, step, stop) in [(0, 4, 64), (3, 0, 3), (8, 8, 64), (2, 31, 64)] {
+            let mut enc = StartStepStopEncoder::new(Cursor::new(vec![]), start, step, stop);
+            let in_range: Vec<u64> = nums
+                .iter()
+                .copied()
+                .filter(|n| enc.max_value() >= *n)
+                .collect();
+            enc.encode(&in_range).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+
+            let dec = StartStepStopDecoder::new(Cursor::new(result));
+            assert_eq!(dec.decode::<u64>().unwrap(), in_range);
+        }
+    }
+
+    #[test]
+    fn test_zeros_convention() {
+        let zeros = UnaryConvention::Zeros;
+        let params = Params {
+            start: 1,
+            step: 2,
+            stop: 5,
+        };
+        let mut bits = vec![];
+        assert!(params.write_bits(4, zeros, &mut bits));
+        assert_eq!(bits, vec![false, true, false, true, false]);
+        assert_eq!(params.decode_prefix(&bits, zeros), Ok((4, 5)));
+
+        let nums = [4_u32, 0, 41, 9];
+        let mut enc = StartStepStopEncoder::with_convention(Cursor::new(vec![]), 1, 2, 5, zeros);
+        assert_eq!(enc.convention(), zeros);
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let dec = StartStepStopDecoder::with_convention(Cursor::new(result.clone()), zeros);
+        assert_eq!(dec.decode::<u32>().unwrap(), nums);
+        let dec = StartStepStopDecoder::new(Cursor::new(result));
+        assert_ne!(dec.decode::<u32>().ok(), Some(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = StartStepStopEncoder::new(Cursor::new(vec![]), 1, 2, 5);
+        assert!(enc.encode(&[42_u32]).is_err());
+
+        let dec = StartStepStopDecoder::new(Cursor::new(vec![0b00000001]));
+        assert!(dec.decode::<u32>().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_params() {
+        StartStepStopEncoder::new(Cursor::new(vec![]), 1, 2, 6);
+    }
+}
@@ -1,14 +1,127 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{DecodeBytes, Decoder, Encoder, NonBlockingEncoder, SkipPrefix};
 use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The convention used to write a number in unary.
+///
+/// The convention is part of the format of the encoded stream, but it is not
+/// recorded in the stream, so the caller must decode a stream with the same
+/// convention that it was encoded with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnaryConvention {
+    /// A number *n* is represented by *n* 1-bits followed by a 0-bit.
+    #[default]
+    Ones,
+    /// A number *n* is represented by *n* 0-bits followed by a 1-bit, as
+    /// required by some external formats, like the Rice codes of FLAC.
+    Zeros,
+}
+
+impl UnaryConvention {
+    /// Returns the bit that is repeated *n* times.
+    #[inline]
+    pub fn run_bit(self) -> bool {
+        self == UnaryConvention::Ones
+    }
+
+    /// Returns the bit that terminates the run.
+    #[inline]
+    pub fn stop_bit(self) -> bool {
+        !self.run_bit()
+    }
+}
 
 /// A structure that encodes a non-negative integer using unary encoding.
 ///
 /// In this version of unary encoding, a number *n* is represented by *n*
 /// consecutive 1-bits followed by a terminating 0-bit.
 ///
-/// For example, the number 3 is encoded as 1110 in unary.
-pub struct UnaryEncoder;
+/// For example, the number 3 is encoded as 1110 in unary. Unary encoding is
+/// optimal for numbers that are geometrically distributed with a ratio of
+/// 1/2, like the flags of runs, but its codes grow linearly with the numbers.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, UnaryDecoder, UnaryEncoder};
+///
+/// let mut enc = UnaryEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[0_u32, 3, 1]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b01110101]);
+///
+/// let dec = UnaryDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![0, 3, 1]);
+/// ```
+pub struct UnaryEncoder<W> {
+    writer: BitWriter<W>,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> UnaryEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_convention(writer, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the numbers in unary with the
+    /// specified convention.
+    pub fn with_convention(writer: W, convention: UnaryConvention) -> Self {
+        let writer = BitWriter::new(writer, true);
+        UnaryEncoder { writer, convention }
+    }
+
+    /// Returns the unary convention of the encoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
 
-impl UnaryEncoder {
+impl<W: Write> Encoder<W> for UnaryEncoder<W> {
+    /// Encodes the numbers in unary.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in a `usize`.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            let n = usize::try_from(num.to_u64()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Number too large to encode in unary.",
+                )
+            })?;
+            for _ in 0..n {
+                self.writer.write_bit(self.convention.run_bit())?;
+            }
+            self.writer.write_bit(self.convention.stop_bit())?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for UnaryEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl UnaryEncoder<()> {
     /// Encodes a unary encoded number in bits.
     ///
     /// # Examples
@@ -21,9 +134,23 @@ impl UnaryEncoder {
     /// assert_eq!(UnaryEncoder::encode_one(3), vec![true, true, true, false]);
     /// ```
     pub fn encode_one(n: usize) -> Vec<bool> {
+        Self::encode_one_with(n, UnaryConvention::default())
+    }
+
+    /// Encodes a number in unary, using the specified convention.
+    ///
+    /// # Examples
+    /// ```
+    /// use idencode::UnaryEncoder;
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let bits = UnaryEncoder::encode_one_with(2, UnaryConvention::Zeros);
+    /// assert_eq!(bits, vec![false, false, true]);
+    /// ```
+    pub fn encode_one_with(n: usize, convention: UnaryConvention) -> Vec<bool> {
         let mut bits = Vec::with_capacity(n + 1);
-        bits.extend(vec![true; n]);
-        bits.push(false);
+        bits.extend(vec![convention.run_bit(); n]);
+        bits.push(convention.stop_bit());
         bits
     }
 }
@@ -34,9 +161,48 @@ impl UnaryEncoder {
 /// consecutive 1-bits followed by a terminating 0-bit.
 ///
 /// For example, the number 3 is encoded as 1110 in unary.
-pub struct UnaryDecoder;
+///
+/// The convention is not recorded in the stream, so a stream encoded with
+/// [`UnaryEncoder::with_convention`] must be decoded with
+/// [`UnaryDecoder::with_convention`] and the same convention.
+pub struct UnaryDecoder<R> {
+    cursor: BitCursor<R>,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> UnaryDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_convention(reader, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the numbers in unary with the
+    /// specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, convention: UnaryConvention) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        UnaryDecoder { cursor, convention }
+    }
+
+    /// Returns the unary convention of the decoder.
+    pub fn convention(&self) -> UnaryConvention {
+        self.convention
+    }
+}
 
-impl UnaryDecoder {
+impl<R: Read> Decoder<R> for UnaryDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let convention = self.convention;
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let n = self
+                .cursor
+                .decode_with(|bits| UnaryDecoder::decode_prefix_with(bits, convention))?;
+            nums.push(T::from_u64(n as u64).ok_or(InvalidCodeError::UnaryCodeError)?);
+        }
+        Ok(nums)
+    }
+}
+
+impl UnaryDecoder<()> {
     /// Decodes a unary encoded number from bits.
     ///
     /// # Examples
@@ -50,18 +216,120 @@ impl UnaryDecoder {
     /// assert!(UnaryDecoder::decode_one(&[true, false, true]).is_err());
     /// ```
     pub fn decode_one(code: &[bool]) -> Result<usize, InvalidCodeError> {
-        // Check if the code is terminated by '0'.
-        if code.last() != Some(&false) {
+        Self::decode_one_with(code, UnaryConvention::default())
+    }
+
+    /// Decodes a number encoded in unary, using the specified convention.
+    ///
+    /// # Examples
+    /// ```
+    /// use idencode::UnaryDecoder;
+    /// use idencode::code::global::unary::UnaryConvention;
+    ///
+    /// let zeros = UnaryConvention::Zeros;
+    /// assert_eq!(UnaryDecoder::decode_one_with(&[false, false, true], zeros), Ok(2));
+    /// assert!(UnaryDecoder::decode_one_with(&[true, true, false], zeros).is_err());
+    /// ```
+    pub fn decode_one_with(
+        code: &[bool],
+        convention: UnaryConvention,
+    ) -> Result<usize, InvalidCodeError> {
+        // Check if the code is terminated by the stop bit.
+        if code.last() != Some(&convention.stop_bit()) {
             return Err(InvalidCodeError::UnaryCodeError);
         }
 
-        // Check if the rest of the characters are '1's.
+        // Check if the rest of the bits are run bits.
         for c in code[..code.len() - 1].iter() {
-            if !(*c) {
+            if *c != convention.run_bit() {
                 return Err(InvalidCodeError::UnaryCodeError);
             }
         }
 
         Ok(code.len() - 1)
     }
+
+    /// Decodes the unary encoded number at the start of a buffer of bits,
+    /// returning it along with the number of bits its code occupies.
+    pub(crate) fn decode_prefix_with(
+        bits: &[bool],
+        convention: UnaryConvention,
+    ) -> Result<(usize, usize), InvalidCodeError> {
+        let n = bits
+            .iter()
+            .position(|b| *b == convention.stop_bit())
+            .ok_or(InvalidCodeError::UnaryCodeError)?;
+        Ok((n, n + 1))
+    }
+}
+
+impl DecodeBytes for UnaryDecoder<()> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let n = bits
+            .read_run(UnaryConvention::default().stop_bit())
+            .ok_or(InvalidCodeError::UnaryCodeError)?;
+        let num = T::from_u64(n as u64).ok_or(InvalidCodeError::UnaryCodeError)?;
+        Ok((num, n + 1))
+    }
+}
+
+impl SkipPrefix for UnaryDecoder<()> {
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        Self::decode_prefix_with(bits, UnaryConvention::default()).map(|(_, len)| len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+        let nums: Vec<u8> = (0..500_u32)
+            .map(|i| (i.wrapping_mul(0x9E3779B9) >> 29) as u8)
+            .collect();
+        for convention in [UnaryConvention::Ones, UnaryConvention::Zeros] {
+            let mut enc = UnaryEncoder::with_convention(Cursor::new(vec![]), convention);
+            enc.encode(&nums).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+            let len: usize = nums.iter().map(|&n| n as usize + 1).sum();
+            assert_eq!(result.len(), len / 8 + 1);
+
+            let dec = UnaryDecoder::with_convention(Cursor::new(result), convention);
+            assert_eq!(dec.convention(), convention);
+            assert_eq!(dec.decode::<u8>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_terminating_bit() {
+        // The stream of a multiple of 8 bits is followed by a terminating byte.
+        let mut enc = UnaryEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[7_u32]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0b11111110, 0b10000000]);
+        let dec = UnaryDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u32>().unwrap(), vec![7]);
+
+        let enc = UnaryEncoder::new(Cursor::new(vec![]));
+        assert!(enc.finalize().unwrap().into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        // A run of 1-bits without its 0-bit.
+        let dec = UnaryDecoder::new(Cursor::new(vec![0b11111111]));
+        assert!(dec.decode::<u32>().is_err());
+
+        // A number that does not fit in the type.
+        let mut enc = UnaryEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[300_u32]).unwrap();
+        let dec = UnaryDecoder::new(Cursor::new(enc.finalize().unwrap().into_inner()));
+        assert_eq!(dec.decode::<u8>(), Err(InvalidCodeError::UnaryCodeError));
+    }
 }
@@ -1,8 +1,10 @@
 use std::io::{self, Read, Write};
 
-use crate::code::{Decoder, Encoder};
-use crate::error::InvalidCodeError;
-use crate::io::read::BitReader;
+use crate::code::{
+    DecodeBytes, DecodeFrom, Decoder, EncodeInto, Encoder, NonBlockingEncoder, SkipPrefix,
+};
+use crate::error::{BufferError, InvalidCodeError};
+use crate::io::read::{BitReader, ByteBits};
 use crate::io::write::BitWriter;
 use crate::num::Numeric;
 
@@ -27,27 +29,11 @@ impl<W: Write> VBEncoder<W> {
 impl<W: Write> Encoder<W> for VBEncoder<W> {
     fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
         let encoded = self.writer.get_mut();
-        let base = T::from(0x80_u8);
         let mut num_bytes = vec![];
 
         for num in nums {
-            let mut num = num.to_owned();
             num_bytes.clear();
-
-            loop {
-                // Get the 7 bits of the lowest byte.
-                let byte = (num % base).to_u8().expect("Guaranteed to be u8.");
-                num_bytes.insert(0, byte);
-                if num < base {
-                    break;
-                }
-                num /= base; // Keep the rest of the bytes.
-            }
-
-            *num_bytes // Add the termination bit for the last byte.
-                .last_mut()
-                .expect("bytes is guaranteed to not be empty.") += 0x80;
-
+            write_vb_bytes(*num, &mut num_bytes);
             // Push them to the encoded buffer.
             encoded.extend_from_byte_slice(num_bytes.as_slice());
         }
@@ -59,6 +45,87 @@ impl<W: Write> Encoder<W> for VBEncoder<W> {
     }
 }
 
+impl<W: Write> NonBlockingEncoder<W> for VBEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl EncodeInto for VBEncoder<()> {
+    fn encode_into<T: Numeric>(nums: &[T], buf: &mut [u8]) -> Result<usize, BufferError> {
+        let mut pos = 0;
+        for num in nums {
+            let num = num.to_u64();
+            let len = ((u64::BITS - num.leading_zeros()).div_ceil(7) as usize).max(1);
+            let bytes = buf
+                .get_mut(pos..pos + len)
+                .ok_or(BufferError::BufferTooSmall)?;
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (num >> (7 * (len - 1 - i))) as u8 & 0x7F;
+            }
+            bytes[len - 1] |= 0x80;
+            pos += len;
+        }
+        Ok(pos)
+    }
+}
+
+/// Calculate the variable byte representation of a number.
+///
+/// The bytes are appended to the buffer, starting from the most significant
+/// 7-bit group, and the termination bit is set on the last byte.
+pub(crate) fn write_vb_bytes<T: Numeric>(num: T, buffer: &mut Vec<u8>) {
+    let start = buffer.len();
+    let base = T::from(0x80_u8);
+    let mut num = num;
+
+    loop {
+        // Get the 7 bits of the lowest byte.
+        let byte = (num % base).to_u8().expect("Guaranteed to be u8.");
+        buffer.insert(start, byte);
+        if num < base {
+            break;
+        }
+        num /= base; // Keep the rest of the bytes.
+    }
+
+    *buffer // Add the termination bit for the last byte.
+        .last_mut()
+        .expect("bytes is guaranteed to not be empty.") += 0x80;
+}
+
+/// Decodes the variable byte code at the start of a buffer of bytes, returning
+/// the number along with the number of bytes its code occupies.
+pub(crate) fn decode_vb_prefix(bytes: &[u8]) -> Result<(u64, usize), InvalidCodeError> {
+    let mut n = 0_u64;
+    for (i, byte) in bytes.iter().enumerate() {
+        if n > u64::MAX >> 7 {
+            return Err(InvalidCodeError::VBCodeError);
+        }
+        n = (n << 7) | u64::from(byte & 0x7F);
+        if *byte >= 0x80 {
+            return Ok((n, i + 1));
+        }
+    }
+    Err(InvalidCodeError::VBCodeError)
+}
+
+/// Decodes the variable byte code at an offset of a buffer of bytes, and moves
+/// the offset past it.
+pub(crate) fn read_vb_bytes(bytes: &[u8], pos: &mut usize) -> Result<u64, InvalidCodeError> {
+    let (n, len) = decode_vb_prefix(bytes.get(*pos..).unwrap_or_default())?;
+    *pos += len;
+    Ok(n)
+}
+
 /// A structure that wraps a reader and decodes a sequence of integers
 /// using Variable Byte Encoding.
 ///
@@ -77,6 +144,56 @@ impl<R: Read> VBDecoder<R> {
     }
 }
 
+impl DecodeFrom for VBDecoder<()> {
+    fn decode_from<T: Numeric>(bytes: &[u8], out: &mut [T]) -> Result<usize, BufferError> {
+        let mut pos = 0;
+        let mut n = 0;
+        while pos < bytes.len() {
+            let (num, len) = decode_vb_prefix(&bytes[pos..])?;
+            let num = T::from_u64(num).ok_or(InvalidCodeError::VBCodeError)?;
+            *out.get_mut(n).ok_or(BufferError::BufferTooSmall)? = num;
+            pos += len;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+impl DecodeBytes for VBDecoder<()> {
+    /// Reads the bytes of the code from the bit offset, which does not have to
+    /// be at the start of a byte.
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let mut n = 0_u64;
+        loop {
+            let byte = bits.read_bits(8).ok_or(InvalidCodeError::VBCodeError)?;
+            if n > u64::MAX >> 7 {
+                return Err(InvalidCodeError::VBCodeError);
+            }
+            n = (n << 7) | (byte & 0x7F);
+            if byte >= 0x80 {
+                break;
+            }
+        }
+        let num = T::from_u64(n).ok_or(InvalidCodeError::VBCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+impl SkipPrefix for VBDecoder<()> {
+    /// Scans the continuation bits at the start of each byte, up to the one of
+    /// the last byte of the code.
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError> {
+        bits.chunks_exact(8)
+            .position(|byte| byte[0])
+            .map(|i| 8 * (i + 1))
+            .ok_or(InvalidCodeError::VBCodeError)
+    }
+}
+
 impl<R: Read> Decoder<R> for VBDecoder<R> {
     fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
         let mut nums = vec![];
@@ -94,9 +211,11 @@ impl<R: Read> Decoder<R> for VBDecoder<R> {
 
         let mut n = T::ZERO;
         for byte in bitvec.into_bytes() {
-            n = T::from(0x80) * n + T::from(byte);
-            if byte > 128 {
-                n = n - T::from(0x80);
+            if n > T::MAX >> 7 {
+                return Err(InvalidCodeError::VBCodeError);
+            }
+            n = T::from(0x80) * n + T::from(byte & 0x7F);
+            if byte >= 0x80 {
                 nums.push(n);
                 n = T::ZERO;
             }
@@ -110,8 +229,27 @@ impl<R: Read> Decoder<R> for VBDecoder<R> {
 mod tests {
 
     use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::BitVec;
     use std::io::Cursor;
 
+    #[test]
+    fn test_decode_one_from_bytes() {
+        let nums: Vec<u64> = spread(1..300).collect();
+        let mut enc = VBEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        assert_decodes_from_bytes::<VBDecoder<()>>(&bytes, &nums);
+        // A code that is not at the start of a byte.
+        let bytes = [0b00000001, 0b01010110, 0b00000000];
+        assert_eq!(
+            VBDecoder::decode_one_from_bytes::<u32>(&bytes, 1),
+            Ok((300, 16))
+        );
+        assert!(VBDecoder::decode_one_from_bytes::<u32>(&bytes, 10).is_err());
+    }
+
     #[test]
     fn test_encode_decode_u8() {
         let nums = vec![5, 10, 33];
@@ -166,4 +304,62 @@ mod tests {
         let decoded = vbd.decode::<u64>().unwrap();
         assert_eq!(decoded, nums);
     }
+
+    #[test]
+    fn test_skip_values() {
+        let mut vbe = VBEncoder::new(Cursor::new(vec![]));
+        vbe.encode::<u64>(&[214577, 824, 8]).unwrap();
+        let bits = BitVec::new(vbe.finalize().unwrap().into_inner()).into_bits();
+        assert_eq!(VBDecoder::skip_prefix(&bits), Ok(24));
+        assert_eq!(VBDecoder::skip_values(&bits, 2), Ok(40));
+        assert_eq!(VBDecoder::skip_values(&bits, 3), Ok(48));
+        assert!(VBDecoder::skip_values(&bits, 4).is_err());
+        assert!(VBDecoder::skip_prefix(&bits[..23]).is_err());
+    }
+
+    #[test]
+    fn test_zero_last_group() {
+        // The last byte of 0 and of 128 holds a zero payload.
+        let nums = vec![0_u64, 128, 7, u64::MAX];
+        let mut vbe = VBEncoder::new(Cursor::new(vec![]));
+        vbe.encode(&nums).unwrap();
+        let encoded = vbe.finalize().unwrap().into_inner();
+        assert_eq!(encoded[..3], [0b10000000, 0b00000001, 0b10000000]);
+
+        let vbd = VBDecoder::new(Cursor::new(encoded.clone()));
+        assert_eq!(vbd.decode::<u64>(), Ok(nums));
+        let vbd = VBDecoder::new(Cursor::new(encoded));
+        assert!(vbd.decode::<u32>().is_err());
+    }
+
+    #[test]
+    fn test_encode_into_decode_from() {
+        let nums: Vec<u64> = (1..=500_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 20)
+            .collect();
+        let mut enc = VBEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        let expected = enc.finalize().unwrap().into_inner();
+
+        let mut buf = vec![0; expected.len()];
+        assert_eq!(VBEncoder::encode_into(&nums, &mut buf), Ok(expected.len()));
+        assert_eq!(buf, expected);
+        assert_eq!(
+            VBEncoder::encode_into(&nums, &mut buf[..expected.len() - 1]),
+            Err(BufferError::BufferTooSmall)
+        );
+
+        let mut out = vec![0_u64; nums.len()];
+        assert_eq!(VBDecoder::decode_from(&buf, &mut out), Ok(nums.len()));
+        assert_eq!(out, nums);
+        assert_eq!(
+            VBDecoder::decode_from(&buf, &mut out[..499]),
+            Err(BufferError::BufferTooSmall)
+        );
+        assert_eq!(VBDecoder::decode_from::<u64>(&[], &mut []), Ok(0));
+        assert_eq!(
+            VBDecoder::decode_from::<u64>(&[0x01, 0x02], &mut out),
+            Err(BufferError::InvalidCode(InvalidCodeError::VBCodeError))
+        );
+    }
 }
@@ -0,0 +1,402 @@
+use std::io::{self, Read, Write};
+
+use super::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
+use crate::code::{
+    DecodeBytes, DecodeOne, DecodePrefix, Decoder, EncodeOne, Encoder, NonBlockingEncoder,
+};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader, ByteBits};
+use crate::io::write::BitWriter;
+use crate::num::convert::{read_minimal_binary, read_minimal_binary_with, write_minimal_binary};
+use crate::num::Numeric;
+
+/// A shrinking factor known at compile time, that parameterizes
+/// [`ZetaEncoder`] and [`ZetaDecoder`] for encoding and decoding single
+/// numbers, with [`EncodeOne`], [`DecodeOne`], [`DecodePrefix`] and
+/// [`DecodeBytes`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::global::zeta::ShrinkingFactor;
+/// use idencode::{DecodeBytes, DecodeOne, EncodeOne, ZetaDecoder, ZetaEncoder};
+///
+/// let bits = ZetaEncoder::<ShrinkingFactor<2>>::encode_one(5_u32);
+/// assert_eq!(bits, vec![true, false, false, false, true]);
+/// assert_eq!(ZetaDecoder::<ShrinkingFactor<2>>::decode_one::<u32>(&bits), Ok(5));
+///
+/// let bytes = [0b10001000];
+/// assert_eq!(ZetaDecoder::<ShrinkingFactor<2>>::decode_one_from_bytes::<u32>(&bytes, 0), Ok((5, 5)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkingFactor<const K: u32>;
+
+/// A structure that wraps a writer and encodes a sequence of integers
+/// using the Zeta codes of Boldi and Vigna.
+///
+/// In the Zeta code with shrinking factor *k*, the positive numbers are split
+/// in the intervals *[2^hk, 2^(h+1)k)*. The index *h* of the interval of a
+/// number is encoded in unary, followed by the offset of the number in the
+/// interval, in minimal binary. Zeta codes are intended for numbers that
+/// follow a power law, like the gaps of the adjacency lists of web graphs,
+/// and the Zeta code with *k = 1* is the Elias Gamma code.
+///
+/// For example, with *k = 2*, the number 5 is in the interval *[4, 16)*, of
+/// 12 numbers, so its index 1 is encoded as 10, and its offset 1 as 001.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, ZetaDecoder, ZetaEncoder};
+///
+/// let mut enc = ZetaEncoder::new(Cursor::new(vec![]), 2);
+/// enc.encode(&[5_u32, 1, 3]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0b10001000, 0b11100000]);
+///
+/// let dec = ZetaDecoder::new(Cursor::new(result), 2);
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![5, 1, 3]);
+/// ```
+pub struct ZetaEncoder<W> {
+    writer: BitWriter<W>,
+    k: u32,
+    convention: UnaryConvention,
+}
+
+impl<W: Write> ZetaEncoder<W> {
+    /// Creates a new encoder with shrinking factor `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0, or not less than 64.
+    pub fn new(writer: W, k: u32) -> Self {
+        Self::with_convention(writer, k, UnaryConvention::default())
+    }
+
+    /// Creates a new encoder that writes the indices of the intervals in
+    /// unary with the specified convention.
+    ///
+    /// With [`UnaryConvention::Zeros`], the codes are the ones of the
+    /// WebGraph framework.
+    pub fn with_convention(writer: W, k: u32, convention: UnaryConvention) -> Self {
+        check_shrinking_factor(k);
+        let writer = BitWriter::new(writer, true);
+        ZetaEncoder {
+            writer,
+            k,
+            convention,
+        }
+    }
+
+    /// Returns the shrinking factor of the encoder.
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+}
+
+impl<W: Write> Encoder<W> for ZetaEncoder<W> {
+    /// Encodes and writes a sequence of numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is 0, which has no Zeta code.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for n in nums {
+            let n = n.to_u64();
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Zeta codes cannot encode 0.",
+                ));
+            }
+            bits.clear();
+            write_zeta_bits(n, self.k, self.convention, &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for ZetaEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using the
+/// Zeta codes, with the same shrinking factor as the encoder's.
+///
+/// The unary convention of the indices is not recorded in the stream either,
+/// so a stream encoded with [`ZetaEncoder::with_convention`] must be decoded
+/// with the same convention.
+pub struct ZetaDecoder<R> {
+    cursor: BitCursor<R>,
+    k: u32,
+    convention: UnaryConvention,
+}
+
+impl<R: Read> ZetaDecoder<R> {
+    /// Creates a new decoder with shrinking factor `k`, which must match the
+    /// encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0, or not less than 64.
+    pub fn new(reader: R, k: u32) -> Self {
+        Self::with_convention(reader, k, UnaryConvention::default())
+    }
+
+    /// Creates a new decoder that reads the indices of the intervals in unary
+    /// with the specified convention, which must match the encoder's.
+    pub fn with_convention(reader: R, k: u32, convention: UnaryConvention) -> Self {
+        check_shrinking_factor(k);
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        ZetaDecoder {
+            cursor,
+            k,
+            convention,
+        }
+    }
+}
+
+impl<R: Read> Decoder<R> for ZetaDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let (k, convention) = (self.k, self.convention);
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let num = self
+                .cursor
+                .decode_with(|bits| decode_zeta_prefix(bits, k, convention))?;
+            nums.push(num);
+        }
+        Ok(nums)
+    }
+}
+
+/// Checks that a shrinking factor is valid.
+fn check_shrinking_factor(k: u32) -> u32 {
+    assert!(
+        k > 0 && k < u64::BITS,
+        "The shrinking factor must be between 1 and 63."
+    );
+    k
+}
+
+impl<const K: u32> EncodeOne for ZetaEncoder<ShrinkingFactor<K>> {
+    /// Encodes a single number with shrinking factor `K`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number is 0, or if `K` is 0 or not less than 64.
+    fn encode_one<T: Numeric>(num: T) -> Vec<bool> {
+        let num = num.to_u64();
+        assert!(num > 0, "Zeta codes cannot encode 0.");
+        let mut bits = vec![];
+        let k = check_shrinking_factor(K);
+        write_zeta_bits(num, k, UnaryConvention::default(), &mut bits);
+        bits
+    }
+}
+
+impl<const K: u32> DecodeOne for ZetaDecoder<ShrinkingFactor<K>> {
+    fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError> {
+        let (num, len) = Self::decode_prefix(bits)?;
+        if len != bits.len() {
+            return Err(InvalidCodeError::ZetaCodeError);
+        }
+        Ok(num)
+    }
+}
+
+impl<const K: u32> DecodePrefix for ZetaDecoder<ShrinkingFactor<K>> {
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError> {
+        let k = check_shrinking_factor(K);
+        decode_zeta_prefix(bits, k, UnaryConvention::default())
+    }
+}
+
+impl<const K: u32> DecodeBytes for ZetaDecoder<ShrinkingFactor<K>> {
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError> {
+        let k = check_shrinking_factor(K);
+        let mut bits = ByteBits::new(bytes, bit_offset);
+        let h = bits
+            .read_run(UnaryConvention::default().stop_bit())
+            .and_then(|h| checked_index(h, k))
+            .ok_or(InvalidCodeError::ZetaCodeError)?;
+        let (start, size) = interval(h, k);
+        // The offsets of the last intervals take more than 64 bits.
+        let read = |n: u32| {
+            let high = bits.read_bits(n.saturating_sub(64) as usize)?;
+            let low = bits.read_bits(n.min(64) as usize)?;
+            Some((u128::from(high) << 64) | u128::from(low))
+        };
+        let num = read_minimal_binary_with(size, read)
+            .and_then(|(offset, _)| u64::try_from(start + offset).ok())
+            .and_then(T::from_u64)
+            .ok_or(InvalidCodeError::ZetaCodeError)?;
+        Ok((num, bits.position() - bit_offset))
+    }
+}
+
+/// Returns the index of an interval as a `u32`, if its first number fits in
+/// a `u64`.
+fn checked_index(h: usize, k: u32) -> Option<u32> {
+    u32::try_from(h)
+        .ok()
+        .filter(|h| h.checked_mul(k).is_some_and(|hk| hk < u64::BITS))
+}
+
+/// Returns the first number, and the size, of an interval of a Zeta code.
+fn interval(h: u32, k: u32) -> (u128, u128) {
+    let start = 1_u128 << (h * k);
+    (start, (start << k) - start)
+}
+
+/// Calculates the Zeta code of a positive number.
+fn write_zeta_bits(num: u64, k: u32, convention: UnaryConvention, buffer: &mut Vec<bool>) {
+    let h = (u64::BITS - 1 - num.leading_zeros()) / k;
+    buffer.extend(UnaryEncoder::encode_one_with(h as usize, convention));
+    let (start, size) = interval(h, k);
+    write_minimal_binary(u128::from(num) - start, size, buffer);
+}
+
+/// Decodes the Zeta code at the start of a buffer of bits, returning the
+/// number along with the number of bits its code occupies.
+fn decode_zeta_prefix<T: Numeric>(
+    bits: &[bool],
+    k: u32,
+    convention: UnaryConvention,
+) -> Result<(T, usize), InvalidCodeError> {
+    let (h, pos) = UnaryDecoder::decode_prefix_with(bits, convention)
+        .map_err(|_| InvalidCodeError::ZetaCodeError)?;
+    let h = checked_index(h, k).ok_or(InvalidCodeError::ZetaCodeError)?;
+    let (start, size) = interval(h, k);
+    let (offset, len) =
+        read_minimal_binary(&bits[pos..], size).ok_or(InvalidCodeError::ZetaCodeError)?;
+    let num = u64::try_from(start + offset)
+        .ok()
+        .and_then(T::from_u64)
+        .ok_or(InvalidCodeError::ZetaCodeError)?;
+    Ok((num, pos + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::testing::{assert_decodes_from_bytes, spread};
+    use crate::GammaEncoder;
+    use std::io::Cursor;
+
+    fn encode_one(num: u64, k: u32) -> Vec<bool> {
+        let mut bits = vec![];
+        write_zeta_bits(num, k, UnaryConvention::Ones, &mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_encode_one() {
+        let to_bits = |s: &str| s.chars().map(|c| c == '1').collect::<Vec<_>>();
+        // The first interval of the Zeta-3 code holds the numbers 1 to 7.
+        assert_eq!(encode_one(1, 3), to_bits("000"));
+        assert_eq!(encode_one(2, 3), to_bits("0010"));
+        assert_eq!(encode_one(7, 3), to_bits("0111"));
+        assert_eq!(encode_one(8, 3), to_bits("1000000"));
+        assert_eq!(encode_one(63, 3), to_bits("10111111"));
+        assert_eq!(encode_one(64, 3), to_bits("11000000000"));
+        // The last interval of 64-bit numbers is larger than 2^64.
+        let bits = encode_one(u64::MAX, 5);
+        assert_eq!(bits.len(), 12 + 1 + 65);
+    }
+
+    #[test]
+    fn test_decode_prefix() {
+        let ones = UnaryConvention::Ones;
+        for k in [1, 2, 3, 7, 30, 63] {
+            for num in [1, 2, 7, 8, 100, 1 << 40, u64::MAX - 1, u64::MAX] {
+                let bits = encode_one(num, k);
+                assert_eq!(
+                    decode_zeta_prefix::<u64>(&bits, k, ones),
+                    Ok((num, bits.len()))
+                );
+                assert!(decode_zeta_prefix::<u64>(&bits[..bits.len() - 1], k, ones).is_err());
+            }
+        }
+        let bits = encode_one(300, 3);
+        assert!(decode_zeta_prefix::<u8>(&bits, 3, ones).is_err());
+        // An index past the last interval of 64-bit numbers.
+        let mut bits = vec![true; 22];
+        bits.extend([false; 70]);
+        assert!(decode_zeta_prefix::<u64>(&bits, 3, ones).is_err());
+        assert!(decode_zeta_prefix::<u64>(&bits[1..], 3, ones).is_ok());
+    }
+
+    #[test]
+    fn test_decode_one_from_bytes() {
+        // The offsets of the last intervals of k = 30 take up to 90 bits.
+        let nums = [1_u64, 2, 1000, 1 << 40, u64::MAX - 1, u64::MAX, 7];
+        let mut enc = ZetaEncoder::new(Cursor::new(vec![]), 30);
+        enc.encode(&nums).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        let end = assert_decodes_from_bytes::<ZetaDecoder<ShrinkingFactor<30>>>(&bytes, &nums);
+        let len: usize = nums.iter().map(|&num| encode_one(num, 30).len()).sum();
+        assert_eq!(end, len);
+        // The number does not fit in the type, or the code is cut short.
+        let bits = ZetaEncoder::<ShrinkingFactor<3>>::encode_one(1000_u32);
+        assert_eq!(
+            ZetaDecoder::<ShrinkingFactor<3>>::decode_one(&bits),
+            Ok(1000_u32)
+        );
+        let bytes = [0b11111111];
+        assert!(
+            ZetaDecoder::<ShrinkingFactor<3>>::decode_one_from_bytes::<u64>(&bytes, 0).is_err()
+        );
+        assert!(
+            ZetaDecoder::<ShrinkingFactor<3>>::decode_one_from_bytes::<u8>(&[0b11100000, 0], 0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_gamma_equivalence() {
+        let nums: Vec<u64> = spread(1..=1000).map(|n| n.max(1)).collect();
+        let mut enc = ZetaEncoder::new(Cursor::new(vec![]), 1);
+        enc.encode(&nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+
+        let mut gamma = GammaEncoder::new(Cursor::new(vec![]));
+        gamma.encode(&nums).unwrap();
+        assert_eq!(result, gamma.finalize().unwrap().into_inner());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = spread(1..=1000).map(|n| n.max(1)).collect();
+        for (k, convention) in [(2, UnaryConvention::Ones), (5, UnaryConvention::Zeros)] {
+            let mut enc = ZetaEncoder::with_convention(Cursor::new(vec![]), k, convention);
+            assert_eq!(enc.k(), k);
+            enc.encode(&nums).unwrap();
+            assert!(enc.encode(&[0_u32]).is_err());
+            let result = enc.finalize().unwrap().into_inner();
+
+            let dec = ZetaDecoder::with_convention(Cursor::new(result), k, convention);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+}
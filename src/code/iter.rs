@@ -0,0 +1,443 @@
+use std::collections::VecDeque;
+use std::iter::Peekable;
+use std::marker::PhantomData;
+
+use crate::code::global::vb::write_vb_bytes;
+use crate::code::EncodeOne;
+use crate::error::InvalidCodeError;
+use crate::num::{bits_to_numeric, Numeric};
+use crate::{DeltaEncoder, GammaEncoder};
+
+/// An extension trait for iterators over numbers, providing adaptors that
+/// transform and lazily encode the numbers.
+///
+/// The encoded bytes are identical to the ones written by the corresponding
+/// encoder, so they can be decoded either with the decoders or with the
+/// adaptors of [`DecodeIterExt`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::EncodeIterExt;
+///
+/// let ids = vec![3_u32, 5, 14];
+/// let bytes = ids.iter().copied().deltas().gamma_encoded().collect::<Vec<u8>>();
+/// assert_eq!(bytes, vec![0b10110011, 0b10001100]);
+/// ```
+pub trait EncodeIterExt: Iterator + Sized
+where
+    Self::Item: Numeric,
+{
+    /// Transforms a non-decreasing sequence of numbers into the sequence of
+    /// their first differences. The first number is yielded as is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence is not sorted in non-decreasing order.
+    fn deltas(self) -> Deltas<Self> {
+        Deltas {
+            iter: self,
+            prev: None,
+        }
+    }
+
+    /// Lazily encodes the numbers using Elias Gamma Encoding.
+    fn gamma_encoded(self) -> BitEncoded<Self, GammaEncoder<()>> {
+        BitEncoded::new(self)
+    }
+
+    /// Lazily encodes the numbers using Elias Delta Encoding.
+    fn delta_encoded(self) -> BitEncoded<Self, DeltaEncoder<()>> {
+        BitEncoded::new(self)
+    }
+
+    /// Lazily encodes the numbers using Variable Byte Encoding.
+    fn vb_encoded(self) -> VBEncoded<Self> {
+        VBEncoded {
+            iter: self,
+            bytes: VecDeque::new(),
+        }
+    }
+}
+
+impl<I: Iterator> EncodeIterExt for I where I::Item: Numeric {}
+
+/// An extension trait for iterators over bytes, providing adaptors that
+/// lazily decode the bytes into numbers.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::DecodeIterExt;
+///
+/// let bytes = vec![0b10000101, 0b10001010, 0b10100001];
+/// let nums = bytes.into_iter().vb_decoded::<u8>().collect::<Result<Vec<_>, _>>();
+/// assert_eq!(nums, Ok(vec![5, 10, 33]));
+/// ```
+pub trait DecodeIterExt: Iterator<Item = u8> + Sized {
+    /// Lazily decodes bytes encoded using Elias Gamma Encoding.
+    fn gamma_decoded<T: Numeric>(self) -> GammaDecoded<Self, T> {
+        GammaDecoded {
+            iter: self.peekable(),
+            bits: VecDeque::new(),
+            failed: false,
+            _num: PhantomData,
+        }
+    }
+
+    /// Lazily decodes bytes encoded using Variable Byte Encoding.
+    fn vb_decoded<T: Numeric>(self) -> VBDecoded<Self, T> {
+        VBDecoded {
+            iter: self,
+            failed: false,
+            _num: PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> DecodeIterExt for I {}
+
+/// An iterator that yields the first differences of a sequence of numbers.
+///
+/// This struct is created by [`EncodeIterExt::deltas`].
+pub struct Deltas<I: Iterator> {
+    iter: I,
+    prev: Option<I::Item>,
+}
+
+impl<I: Iterator> Iterator for Deltas<I>
+where
+    I::Item: Numeric,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let num = self.iter.next()?;
+        let delta = match self.prev {
+            Some(prev) => {
+                assert!(prev <= num, "The sequence must be sorted.");
+                num - prev
+            }
+            None => num,
+        };
+        self.prev = Some(num);
+        Some(delta)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// An iterator that lazily encodes numbers with a bit-level code, yielding
+/// the encoded bytes.
+///
+/// When the numbers are exhausted, a terminating 1-bit is appended and the
+/// last byte is padded with 0-bits, exactly like [`crate::BitWriter`] does.
+///
+/// This struct is created by [`EncodeIterExt::gamma_encoded`] and
+/// [`EncodeIterExt::delta_encoded`].
+pub struct BitEncoded<I, C> {
+    iter: I,
+    bits: VecDeque<bool>,
+    written: bool,
+    done: bool,
+    _code: PhantomData<C>,
+}
+
+impl<I, C> BitEncoded<I, C> {
+    fn new(iter: I) -> Self {
+        BitEncoded {
+            iter,
+            bits: VecDeque::new(),
+            written: false,
+            done: false,
+            _code: PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator, C: EncodeOne> Iterator for BitEncoded<I, C>
+where
+    I::Item: Numeric,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.bits.len() < 8 && !self.done {
+            match self.iter.next() {
+                Some(num) => {
+                    self.bits.extend(C::encode_one(num));
+                    self.written = true;
+                }
+                None => {
+                    self.done = true;
+                    if self.written {
+                        self.bits.push_back(true); // Add the terminating bit.
+                    }
+                }
+            }
+        }
+
+        if self.bits.is_empty() {
+            return None;
+        }
+        let mut byte = 0;
+        for i in 0..8 {
+            if self.bits.pop_front().unwrap_or(false) {
+                byte |= 1 << (7 - i);
+            }
+        }
+        Some(byte)
+    }
+}
+
+/// An iterator that lazily encodes numbers using Variable Byte Encoding,
+/// yielding the encoded bytes.
+///
+/// This struct is created by [`EncodeIterExt::vb_encoded`].
+pub struct VBEncoded<I> {
+    iter: I,
+    bytes: VecDeque<u8>,
+}
+
+impl<I: Iterator> Iterator for VBEncoded<I>
+where
+    I::Item: Numeric,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.bytes.is_empty() {
+            let num = self.iter.next()?;
+            let mut num_bytes = Vec::new();
+            write_vb_bytes(num, &mut num_bytes);
+            self.bytes.extend(num_bytes);
+        }
+        self.bytes.pop_front()
+    }
+}
+
+/// An iterator that lazily decodes bytes encoded using Elias Gamma Encoding.
+///
+/// The iterator yields an error if the bytes are not a valid encoding, after
+/// which it is exhausted.
+///
+/// This struct is created by [`DecodeIterExt::gamma_decoded`].
+pub struct GammaDecoded<I: Iterator<Item = u8>, T> {
+    iter: Peekable<I>,
+    bits: VecDeque<bool>,
+    failed: bool,
+    _num: PhantomData<T>,
+}
+
+impl<I: Iterator<Item = u8>, T> GammaDecoded<I, T> {
+    // Moves the bits of the next byte to the bit buffer, returning `false` if
+    // the bytes are exhausted.
+    //
+    // The last byte is recognized by peeking, and its terminating bit along
+    // with the padding are removed.
+    fn fill(&mut self) -> Result<bool, InvalidCodeError> {
+        let Some(byte) = self.iter.next() else {
+            return Ok(false);
+        };
+        let n_bits = if self.iter.peek().is_some() {
+            8
+        } else if byte == 0 {
+            return Err(InvalidCodeError::GammaCodeError);
+        } else {
+            7 - byte.trailing_zeros()
+        };
+        for i in 0..n_bits {
+            self.bits.push_back(byte & (1 << (7 - i)) != 0);
+        }
+        Ok(true)
+    }
+
+    // Makes sure that the bit buffer contains at least `n` bits.
+    fn fill_to(&mut self, n: usize) -> Result<(), InvalidCodeError> {
+        while self.bits.len() < n {
+            if !self.fill()? {
+                return Err(InvalidCodeError::GammaCodeError);
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_next(&mut self) -> Result<Option<T>, InvalidCodeError>
+    where
+        T: Numeric,
+    {
+        while self.bits.is_empty() {
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+
+        // Count the 1-bits of the unary encoded length.
+        let mut len = 0;
+        loop {
+            self.fill_to(len + 1)?;
+            if !self.bits[len] {
+                break;
+            }
+            len += 1;
+        }
+        self.fill_to(2 * len + 1)?;
+        self.bits.drain(..len + 1);
+
+        let mut n_bits = Vec::with_capacity(len + 1);
+        n_bits.push(true);
+        n_bits.extend(self.bits.drain(..len));
+        bits_to_numeric(&n_bits)
+            .map(Some)
+            .map_err(|_| InvalidCodeError::GammaCodeError)
+    }
+}
+
+impl<I: Iterator<Item = u8>, T: Numeric> Iterator for GammaDecoded<I, T> {
+    type Item = Result<T, InvalidCodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.decode_next();
+        self.failed = result.is_err();
+        result.transpose()
+    }
+}
+
+/// An iterator that lazily decodes bytes encoded using Variable Byte Encoding.
+///
+/// The iterator yields an error if the bytes are not a valid encoding, after
+/// which it is exhausted.
+///
+/// This struct is created by [`DecodeIterExt::vb_decoded`].
+pub struct VBDecoded<I, T> {
+    iter: I,
+    failed: bool,
+    _num: PhantomData<T>,
+}
+
+impl<I: Iterator<Item = u8>, T: Numeric> Iterator for VBDecoded<I, T> {
+    type Item = Result<T, InvalidCodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let mut n = T::ZERO;
+        let mut started = false;
+        for byte in self.iter.by_ref() {
+            if n > T::MAX >> 7 {
+                break;
+            }
+            started = true;
+            n = T::from(0x80) * n + T::from(byte & 0x7F);
+            if byte >= 0x80 {
+                return Some(Ok(n));
+            }
+        }
+
+        if started {
+            self.failed = true;
+            Some(Err(InvalidCodeError::VBCodeError))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Decoder, Encoder, GammaDecoder, VBEncoder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_gamma_matches_encoder() {
+        let nums = [2_u32, 3, 9, 1, 1000, 7];
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let expected = ge.finalize().unwrap().into_inner();
+
+        let bytes = nums.iter().copied().gamma_encoded().collect::<Vec<u8>>();
+        assert_eq!(bytes, expected);
+
+        let decoded = bytes.into_iter().gamma_decoded::<u32>();
+        assert_eq!(decoded.collect::<Result<Vec<_>, _>>(), Ok(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_delta_encoded() {
+        let bytes = [2_u32, 3, 9]
+            .into_iter()
+            .delta_encoded()
+            .collect::<Vec<_>>();
+        assert_eq!(bytes, vec![0b10001001, 0b11000001, 0b10000000]);
+    }
+
+    #[test]
+    fn test_vb_matches_encoder() {
+        let nums = [214577_u64, 824, 8, 128];
+        let mut vbe = VBEncoder::new(Cursor::new(vec![]));
+        vbe.encode(&nums).unwrap();
+        let expected = vbe.finalize().unwrap().into_inner();
+
+        let bytes = nums.iter().copied().vb_encoded().collect::<Vec<u8>>();
+        assert_eq!(bytes, expected);
+
+        let decoded = bytes.into_iter().vb_decoded::<u64>();
+        assert_eq!(decoded.collect::<Result<Vec<_>, _>>(), Ok(nums.to_vec()));
+    }
+
+    #[test]
+    fn test_deltas_round_trip() {
+        let ids = [3_u32, 5, 14, 14, 200];
+        let bytes = ids
+            .iter()
+            .copied()
+            .deltas()
+            .vb_encoded()
+            .collect::<Vec<_>>();
+        let gaps = bytes.into_iter().vb_decoded::<u32>();
+        let decoded = gaps
+            .scan(0, |sum, gap| {
+                *sum += gap.unwrap();
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, ids);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(std::iter::empty::<u32>().gamma_encoded().next(), None);
+        assert_eq!(std::iter::empty().gamma_decoded::<u32>().next(), None);
+        assert_eq!(std::iter::empty().vb_decoded::<u32>().next(), None);
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let mut decoded = vec![0b10010111, 0b11100110]
+            .into_iter()
+            .gamma_decoded::<u8>();
+        assert_eq!(decoded.next(), Some(Ok(2)));
+        assert_eq!(decoded.next(), Some(Ok(3)));
+        assert!(decoded.next().unwrap().is_err());
+        assert_eq!(decoded.next(), None);
+
+        let mut decoded = vec![0b00000101].into_iter().vb_decoded::<u8>();
+        assert_eq!(decoded.next(), Some(Err(InvalidCodeError::VBCodeError)));
+        assert_eq!(decoded.next(), None);
+
+        let bytes = vec![0b10110011, 0b10001100];
+        let decoded = bytes.into_iter().gamma_decoded::<u32>();
+        let gaps = GammaDecoder::new(Cursor::new(vec![0b10110011, 0b10001100]))
+            .decode::<u32>()
+            .unwrap();
+        assert_eq!(decoded.collect::<Result<Vec<_>, _>>(), Ok(gaps));
+    }
+}
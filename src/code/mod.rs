@@ -1,8 +1,34 @@
+pub mod adaptive;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod byte;
+pub mod double_delta;
+pub mod entropy;
+pub mod float;
 pub mod global;
+pub mod iter;
+pub mod nullable;
+pub mod packed;
+pub mod positions;
+pub mod progress;
+pub mod rle;
+pub mod sorted;
+pub mod spec;
+pub mod split;
+pub mod sprintz;
+pub mod stats;
+pub mod tagged;
+#[cfg(test)]
+mod testing;
+pub mod timeseries;
+pub mod timestamp;
+#[cfg(feature = "tracing")]
+pub mod traced;
+pub mod transform;
 
 use std::io::{self, Read, Write};
 
-use crate::error::InvalidCodeError;
+use crate::error::{BufferError, InvalidCodeError};
 use crate::num::Numeric;
 
 pub trait EncodeOne {
@@ -15,6 +41,130 @@ pub trait DecodeOne {
     fn decode_one<T: Numeric>(bits: &[bool]) -> Result<T, InvalidCodeError>;
 }
 
+pub trait DecodePrefix {
+    /// Decodes the number encoded at the start of a buffer of bits, returning
+    /// it along with the number of bits that its code occupies.
+    fn decode_prefix<T: Numeric>(bits: &[bool]) -> Result<(T, usize), InvalidCodeError>;
+}
+
+pub trait DecodeBytes {
+    /// Decodes the number whose code starts at a bit offset of a buffer of
+    /// bytes, returning it along with the number of bits its code occupies.
+    ///
+    /// The bits of the bytes are read in most-significant-bit first order,
+    /// straight from the buffer, without allocating, so this is the primitive
+    /// for decoding values in place, at positions known from an index.
+    ///
+    /// It is implemented by the decoders of the codes in which every number
+    /// has a code of its own. The decoders of parameterized codes implement
+    /// it for their parameters known at compile time, like
+    /// `GolombDecoder<Divisor<B>>`, `RiceDecoder<Parameter<K>>`,
+    /// `ZetaDecoder<ShrinkingFactor<K>>` and
+    /// `StartStepStopDecoder<Parameters<START, STEP, STOP>>`. The code of a
+    /// number in Binary Adaptive Sequential Coding depends on its
+    /// predecessor, and the block, word-aligned and entropy codes do not
+    /// place the numbers at bit offsets of their own, so they do not
+    /// implement it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{DecodeBytes, GammaDecoder, VBDecoder};
+    ///
+    /// // The Elias Gamma codes of 3 (101) and 9 (1110001).
+    /// let bytes = [0b10111100, 0b01000000];
+    /// assert_eq!(GammaDecoder::decode_one_from_bytes::<u32>(&bytes, 0), Ok((3, 3)));
+    /// assert_eq!(GammaDecoder::decode_one_from_bytes::<u32>(&bytes, 3), Ok((9, 7)));
+    /// assert!(GammaDecoder::decode_one_from_bytes::<u32>(&bytes, 16).is_err());
+    ///
+    /// let bytes = [0x02, 0xAC];
+    /// assert_eq!(VBDecoder::decode_one_from_bytes::<u16>(&bytes, 0), Ok((300, 16)));
+    /// assert!(VBDecoder::decode_one_from_bytes::<u8>(&bytes, 0).is_err());
+    /// ```
+    fn decode_one_from_bytes<T: Numeric>(
+        bytes: &[u8],
+        bit_offset: usize,
+    ) -> Result<(T, usize), InvalidCodeError>;
+}
+
+pub trait EncodeInto {
+    /// Encodes a sequence of numbers into a buffer provided by the caller,
+    /// returning the number of bytes written.
+    ///
+    /// The bytes are the same as the ones the encoder of the code writes for
+    /// the numbers, but they are written in place, without allocating, so
+    /// this suits embedded and latency-sensitive code. Returns
+    /// [`BufferError::BufferTooSmall`] if the buffer cannot hold them, in
+    /// which case its contents are unspecified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::error::BufferError;
+    /// use idencode::{DecodeFrom, EncodeInto, GammaDecoder, GammaEncoder};
+    ///
+    /// let mut buf = [0; 4];
+    /// let n = GammaEncoder::encode_into(&[3_u32, 9, 1], &mut buf).unwrap();
+    /// assert_eq!(&buf[..n], &[0b10111100, 0b01010000]);
+    ///
+    /// let mut nums = [0_u32; 3];
+    /// assert_eq!(GammaDecoder::decode_from(&buf[..n], &mut nums), Ok(3));
+    /// assert_eq!(nums, [3, 9, 1]);
+    ///
+    /// assert_eq!(
+    ///     GammaEncoder::encode_into(&[1_u32 << 20], &mut buf),
+    ///     Err(BufferError::BufferTooSmall)
+    /// );
+    /// ```
+    fn encode_into<T: Numeric>(nums: &[T], buf: &mut [u8]) -> Result<usize, BufferError>;
+}
+
+pub trait DecodeFrom {
+    /// Decodes the numbers of a buffer of bytes into a buffer provided by the
+    /// caller, returning the number of values decoded.
+    ///
+    /// The bytes are a whole stream of the code, as its encoder writes it, and
+    /// are decoded in place, without allocating. Returns
+    /// [`BufferError::BufferTooSmall`] if the output buffer cannot hold all the
+    /// values, and [`BufferError::InvalidCode`] if the bytes are not a valid
+    /// stream, or a number does not fit in the type.
+    fn decode_from<T: Numeric>(bytes: &[u8], out: &mut [T]) -> Result<usize, BufferError>;
+}
+
+pub trait SkipPrefix {
+    /// Returns the number of bits that the code at the start of a buffer of
+    /// bits occupies, without decoding the number.
+    fn skip_prefix(bits: &[bool]) -> Result<usize, InvalidCodeError>;
+
+    /// Returns the number of bits that the first `n` codes of a buffer of
+    /// bits occupy, without decoding the numbers.
+    ///
+    /// Along with the bit positions of a sample of the codes, it gives
+    /// positional access to a stream without an index: the codes after the
+    /// nearest sample are skipped, and only the requested one is decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{DecodePrefix, EncodeOne, GammaDecoder, GammaEncoder, SkipPrefix};
+    ///
+    /// let bits: Vec<bool> = [5_u32, 300, 2, 17]
+    ///     .into_iter()
+    ///     .flat_map(GammaEncoder::encode_one)
+    ///     .collect();
+    /// let pos = GammaDecoder::skip_values(&bits, 3).unwrap();
+    /// assert_eq!(GammaDecoder::decode_prefix::<u32>(&bits[pos..]), Ok((17, 9)));
+    /// assert!(GammaDecoder::skip_values(&bits, 5).is_err());
+    /// ```
+    fn skip_values(bits: &[bool], n: usize) -> Result<usize, InvalidCodeError> {
+        let mut len = 0;
+        for _ in 0..n {
+            len += Self::skip_prefix(&bits[len..])?;
+        }
+        Ok(len)
+    }
+}
+
 pub trait Encoder<W: Write> {
     /// Encodes and writes the specified numbers in the wrapped writer.
     fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()>;
@@ -23,6 +173,61 @@ pub trait Encoder<W: Write> {
     fn finalize(self) -> io::Result<W>;
 }
 
+/// An encoder that can write to a non-blocking writer.
+///
+/// [`Encoder::finalize`] consumes the encoder, so the bytes that a writer does
+/// not accept, because it returns an error of kind
+/// [`io::ErrorKind::WouldBlock`], are lost. Instead, [`NonBlockingEncoder::finish`]
+/// keeps the bytes that have not been written in the encoder, and
+/// [`NonBlockingEncoder::resume_flush`] writes them when the writer is ready
+/// again. Once either of them succeeds, [`NonBlockingEncoder::into_inner`]
+/// returns the writer.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, Write};
+/// use idencode::{Encoder, GammaEncoder, NonBlockingEncoder};
+///
+/// // A writer that is not ready on every other write.
+/// struct Socket(Vec<u8>, bool);
+///
+/// impl Write for Socket {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.1 = !self.1;
+///         if self.1 {
+///             return Err(io::ErrorKind::WouldBlock.into());
+///         }
+///         self.0.push(buf[0]);
+///         Ok(1)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut enc = GammaEncoder::new(Socket(vec![], false));
+/// enc.encode(&[3_u32, 5, 14]).unwrap();
+/// let mut result = enc.finish();
+/// while let Err(e) = result {
+///     assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+///     result = enc.resume_flush();
+/// }
+/// assert_eq!(enc.into_inner().0, vec![0b10111001, 0b11101101]);
+/// ```
+pub trait NonBlockingEncoder<W: Write>: Encoder<W> {
+    /// Finishes the encoding like [`Encoder::finalize`], without consuming the
+    /// encoder. No numbers should be encoded afterward.
+    fn finish(&mut self) -> io::Result<()>;
+
+    /// Writes the bytes that the wrapped writer has not accepted yet.
+    fn resume_flush(&mut self) -> io::Result<()>;
+
+    /// Consumes the encoder, returning the wrapped writer.
+    fn into_inner(self) -> W;
+}
+
 pub trait Decoder<R: Read> {
     /// Reads and decodes the encoded numbers in the wrapped reader.
     fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError>;
@@ -0,0 +1,281 @@
+use std::io::{self, Cursor, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+use crate::BitVec;
+
+/// Calculates the run-length code of a validity bitmap: the lengths of the
+/// alternating runs of valid and null values, starting with a run of valid
+/// values, in Elias Gamma Encoding. The first run may be empty, so its
+/// length is incremented by one.
+fn write_runs_bits(validity: &[bool], buffer: &mut Vec<bool>) {
+    let convention = UnaryConvention::default();
+    let mut runs = validity.chunk_by(|a, b| a == b).map(|run| run.len());
+    if validity.first() == Some(&false) {
+        write_gamma_bits(1_usize, convention, buffer);
+    } else if let Some(run) = runs.next() {
+        write_gamma_bits(run + 1, convention, buffer);
+    }
+    for run in runs {
+        write_gamma_bits(run, convention, buffer);
+    }
+}
+
+/// Decodes the validity bitmap of `count` values, written either as it is or
+/// as runs, at the start of a buffer of bits, returning it along with the
+/// number of bits it occupies.
+fn decode_validity(bits: &[bool], count: usize) -> Result<(Vec<bool>, usize), InvalidCodeError> {
+    let is_runs = *bits.first().ok_or(InvalidCodeError::NullableCodeError)?;
+    let mut pos = 1;
+    if !is_runs {
+        let validity = bits
+            .get(pos..pos + count)
+            .ok_or(InvalidCodeError::NullableCodeError)?;
+        return Ok((validity.to_vec(), pos + count));
+    }
+
+    let convention = UnaryConvention::default();
+    let mut validity = Vec::with_capacity(count.min(bits.len()));
+    let mut valid = true;
+    while validity.len() < count {
+        let (run, len) = decode_gamma_prefix::<usize>(&bits[pos..], convention)
+            .map_err(|_| InvalidCodeError::NullableCodeError)?;
+        let run = if pos == 1 { run - 1 } else { run };
+        if run > count - validity.len() {
+            return Err(InvalidCodeError::NullableCodeError);
+        }
+        validity.extend(std::iter::repeat_n(valid, run));
+        valid = !valid;
+        pos += len;
+    }
+    Ok((validity, pos))
+}
+
+/// A structure that wraps a writer and encodes a sequence of optional
+/// integers, as a validity bitmap followed by the non-null values.
+///
+/// The non-null values are encoded with the wrapped encoder, which writes in
+/// memory, while the validity of the values is kept in a [`BitVec`]. When
+/// the encoder is finalized, the number of values is written in Elias Gamma
+/// Encoding, incremented by one, followed by the validity bitmap. The bitmap
+/// is written either as it is, after a 0-bit, or after a 1-bit as the lengths
+/// of its runs of valid and null values in Elias Gamma Encoding, whichever is
+/// shorter. The bits are padded with 0-bits to a whole byte, and followed by
+/// the bytes of the wrapped encoder.
+///
+/// Columns whose nulls are rare, or come in long stretches, thus pay a few
+/// bits for their validity, without reserving a sentinel value for nulls.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{NullableDecoder, NullableEncoder, VB16Decoder, VB16Encoder};
+///
+/// let ages = [Some(34_u8), None, Some(27), Some(61), None];
+/// let inner = VB16Encoder::new(Cursor::new(vec![]));
+/// let mut enc = NullableEncoder::new(Cursor::new(vec![]), inner);
+/// enc.encode(&ages).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result.len(), 2 + 3 * 2);
+///
+/// let dec = NullableDecoder::new(Cursor::new(result));
+/// let decoded: Vec<Option<u8>> = dec.decode(VB16Decoder::new).unwrap();
+/// assert_eq!(decoded, ages);
+/// ```
+pub struct NullableEncoder<W, E> {
+    writer: BitWriter<W>,
+    inner: E,
+    validity: BitVec,
+}
+
+impl<W: Write, E: Encoder<Cursor<Vec<u8>>>> NullableEncoder<W, E> {
+    /// Creates a new encoder, that encodes the non-null values with an
+    /// encoder that writes in memory.
+    pub fn new(writer: W, inner: E) -> Self {
+        let writer = BitWriter::new(writer, false);
+        NullableEncoder {
+            writer,
+            inner,
+            validity: BitVec::default(),
+        }
+    }
+
+    /// Encodes the non-null values of a sequence, and records which values
+    /// are null.
+    pub fn encode<T: Numeric>(&mut self, nums: &[Option<T>]) -> io::Result<()> {
+        let values: Vec<T> = nums.iter().flatten().copied().collect();
+        self.encode_parts(&values, nums.iter().map(Option::is_some))
+    }
+
+    /// Encodes the non-null values of a sequence, given apart from the
+    /// validity of the whole sequence.
+    pub(crate) fn encode_parts<T: Numeric>(
+        &mut self,
+        values: &[T],
+        validity: impl IntoIterator<Item = bool>,
+    ) -> io::Result<()> {
+        self.inner.encode(values)?;
+        for valid in validity {
+            self.validity.push(valid);
+        }
+        Ok(())
+    }
+
+    /// Writes the validity bitmap, followed by the encoded non-null values,
+    /// in the wrapped writer.
+    pub fn finalize(mut self) -> io::Result<W> {
+        let validity = self.validity.into_bits();
+        let mut bits = vec![];
+        write_gamma_bits(validity.len() + 1, UnaryConvention::default(), &mut bits);
+
+        let mut runs = vec![true];
+        write_runs_bits(&validity, &mut runs);
+        if runs.len() < validity.len() + 1 {
+            bits.extend(runs);
+        } else {
+            bits.push(false);
+            bits.extend(&validity);
+        }
+        bits.resize(bits.len().next_multiple_of(8), false);
+
+        let values = self.inner.finalize()?.into_inner();
+        let writer = self.writer.get_mut();
+        writer.extend_from_slice(&bits);
+        writer.extend_from_byte_slice(&values);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`NullableEncoder`].
+pub struct NullableDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> NullableDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        NullableDecoder { reader }
+    }
+
+    /// Reads the validity bitmap, and decodes the non-null values with the
+    /// decoder created by `inner` over the rest of the bytes.
+    pub fn decode<T, D, F>(self, inner: F) -> Result<Vec<Option<T>>, InvalidCodeError>
+    where
+        T: Numeric,
+        D: Decoder<Cursor<Vec<u8>>>,
+        F: FnOnce(Cursor<Vec<u8>>) -> D,
+    {
+        let (validity, values) = self.decode_parts(inner)?;
+        let mut values = values.into_iter();
+        Ok(validity
+            .iter()
+            .map(|&valid| valid.then(|| values.next().expect("There is a value per 1-bit.")))
+            .collect())
+    }
+
+    /// Reads the validity bitmap and the non-null values, checking that
+    /// there is a value for every 1-bit of the bitmap.
+    pub(crate) fn decode_parts<T, D, F>(
+        self,
+        inner: F,
+    ) -> Result<(Vec<bool>, Vec<T>), InvalidCodeError>
+    where
+        T: Numeric,
+        D: Decoder<Cursor<Vec<u8>>>,
+        F: FnOnce(Cursor<Vec<u8>>) -> D,
+    {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+        let bits = BitVec::new(bytes.clone()).into_bits();
+        let (count, len) = decode_gamma_prefix::<usize>(&bits, UnaryConvention::default())
+            .map_err(|_| InvalidCodeError::NullableCodeError)?;
+        let count = count - 1;
+        let (validity, validity_len) = decode_validity(&bits[len..], count)?;
+
+        let offset = (len + validity_len).div_ceil(8);
+        let values = inner(Cursor::new(bytes[offset..].to_vec())).decode::<T>()?;
+        match validity.iter().filter(|&&valid| valid).count() == values.len() {
+            true => Ok((validity, values)),
+            false => Err(InvalidCodeError::NullableCodeError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkDecoder, ChunkEncoder};
+
+    fn encode(nums: &[Option<u64>]) -> Vec<u8> {
+        let inner = ChunkEncoder::new(Cursor::new(vec![]));
+        let mut enc = NullableEncoder::new(Cursor::new(vec![]), inner);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<Option<u64>>, InvalidCodeError> {
+        NullableDecoder::new(Cursor::new(bytes)).decode(ChunkDecoder::new)
+    }
+
+    #[test]
+    fn test_validity() {
+        for validity in [
+            vec![],
+            vec![false],
+            vec![true, true, false, false, false, true],
+            vec![false, true, false, true, false],
+        ] {
+            let mut bits = vec![];
+            write_runs_bits(&validity, &mut bits);
+            bits.insert(0, true);
+            assert_eq!(
+                decode_validity(&bits, validity.len()),
+                Ok((validity, bits.len()))
+            );
+        }
+
+        // Long runs are shorter than the bitmap, scattered nulls are not.
+        let runs = encode(&[vec![Some(1); 1000], vec![None; 1000]].concat());
+        assert_eq!(runs.len(), 8 + 1000);
+        let scattered: Vec<Option<u64>> = (0..64).map(|i| (i % 2 == 0).then_some(1)).collect();
+        assert_eq!(encode(&scattered).len(), 2 + 8 + 32);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<Option<u64>> = (0..5000_u64)
+            .map(|i| (i % 7 != 3 && i % 1000 > 100).then_some(i * i))
+            .collect();
+        assert_eq!(decode(encode(&nums)), Ok(nums));
+
+        let mut enc =
+            NullableEncoder::new(Cursor::new(vec![]), ChunkEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[None, Some(u64::MAX)]).unwrap();
+        enc.encode::<u64>(&[None; 3]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(
+            decode(result),
+            Ok(vec![None, Some(u64::MAX), None, None, None])
+        );
+        assert_eq!(decode(encode(&[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_errs() {
+        assert!(decode(vec![]).is_err());
+        // Two valid values, but a single encoded value.
+        assert!(decode(vec![0b01101110, 0x05]).is_err());
+        // A run longer than the number of values.
+        assert!(decode(vec![0b01011000, 0b10000000]).is_err());
+
+        let mut result = encode(&[Some(1), None]);
+        result.push(0x05);
+        assert!(decode(result).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// Returns the largest number that fits in `width` bits.
+fn max_value(width: u32) -> u64 {
+    u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0)
+}
+
+/// Checks that a width is at most 64 bits.
+fn check_width(width: u32) -> u32 {
+    assert!(width <= u64::BITS, "The width must be at most 64 bits.");
+    width
+}
+
+/// Packs numbers in exactly `width` bits each, from the most significant bit,
+/// padding the last byte with zeros.
+///
+/// The numbers are gathered in a 64-bit word, which is written out whenever
+/// it is full, so a number is packed with at most two shifts, whatever its
+/// width.
+///
+/// # Panics
+///
+/// Panics if `width` is greater than 64, or if a number does not fit in
+/// `width` bits.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::packed::bitpack::{pack, unpack};
+///
+/// let bytes = pack(&[5_u32, 3, 6], 3);
+/// assert_eq!(bytes, vec![0b10101111, 0b00000000]);
+/// assert_eq!(unpack::<u32>(&bytes, 3, 3).unwrap(), vec![5, 3, 6]);
+/// ```
+pub fn pack<T: Numeric>(values: &[T], width: u32) -> Vec<u8> {
+    let max = max_value(check_width(width));
+    let mut bytes = Vec::with_capacity((values.len() * width as usize).div_ceil(8));
+    // The bits of the word are filled from the most significant one.
+    let mut word = 0_u64;
+    let mut n_bits = 0;
+    for value in values {
+        let value = value.to_u64();
+        assert!(value <= max, "The number does not fit in the width.");
+        let free = u64::BITS - n_bits;
+        if width < free {
+            word |= value.checked_shl(free - width).unwrap_or(0);
+            n_bits += width;
+        } else {
+            let rest = width - free;
+            word |= value >> rest;
+            bytes.extend_from_slice(&word.to_be_bytes());
+            word = value.checked_shl(u64::BITS - rest).unwrap_or(0);
+            n_bits = rest;
+        }
+    }
+    bytes.extend_from_slice(&word.to_be_bytes()[..n_bits.div_ceil(8) as usize]);
+    bytes
+}
+
+/// Unpacks `count` numbers of exactly `width` bits each, packed with [`pack`].
+/// Any bytes after the numbers are ignored.
+///
+/// Each number is extracted from the bytes that hold it, loaded as a single
+/// 128-bit word, with two shifts.
+///
+/// Returns an error if the bytes are too few for the numbers, or if a number
+/// does not fit in the type.
+///
+/// # Panics
+///
+/// Panics if `width` is greater than 64.
+pub fn unpack<T: Numeric>(
+    bytes: &[u8],
+    width: u32,
+    count: usize,
+) -> Result<Vec<T>, InvalidCodeError> {
+    check_width(width);
+    let n_bits = count
+        .checked_mul(width as usize)
+        .ok_or(InvalidCodeError::BitPackCodeError)?;
+    if n_bits.div_ceil(8) > bytes.len() {
+        return Err(InvalidCodeError::BitPackCodeError);
+    }
+    if width == 0 {
+        return Ok(vec![T::ZERO; count]);
+    }
+
+    (0..count)
+        .map(|i| {
+            let pos = i * width as usize;
+            let word = load_word(bytes, pos / 8) << (pos % 8);
+            let value = (word >> (u128::BITS - width)) as u64;
+            T::from_u64(value).ok_or(InvalidCodeError::BitPackCodeError)
+        })
+        .collect()
+}
+
+/// Loads the 16 bytes from a position as a big-endian word, padding the bytes
+/// past the end with zeros.
+#[inline]
+fn load_word(bytes: &[u8], pos: usize) -> u128 {
+    match bytes.get(pos..pos + 16) {
+        Some(word) => u128::from_be_bytes(word.try_into().expect("The slice has 16 bytes.")),
+        None => {
+            let mut word = [0; 16];
+            let rest = &bytes[pos..];
+            word[..rest.len()].copy_from_slice(rest);
+            u128::from_be_bytes(word)
+        }
+    }
+}
+
+/// A structure that wraps a writer and packs a sequence of integers in
+/// exactly `width` bits each.
+///
+/// The stream starts with the width in one byte and the number of integers in
+/// Variable Byte Encoding, followed by the integers packed with [`pack`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{BitPackDecoder, BitPackEncoder, Decoder, Encoder};
+///
+/// let mut enc = BitPackEncoder::new(Cursor::new(vec![]), 3);
+/// enc.encode(&[5_u32, 3]).unwrap();
+/// enc.encode(&[6_u32]).unwrap();
+/// assert!(enc.encode(&[8_u32]).is_err());
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![3, 0x83, 0b10101111, 0b00000000]);
+///
+/// let dec = BitPackDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![5, 3, 6]);
+/// ```
+pub struct BitPackEncoder<W> {
+    writer: BitWriter<W>,
+    width: u32,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> BitPackEncoder<W> {
+    /// Creates a new encoder for numbers of `width` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 64.
+    pub fn new(writer: W, width: u32) -> Self {
+        let writer = BitWriter::new(writer, false);
+        BitPackEncoder {
+            writer,
+            width: check_width(width),
+            nums: vec![],
+        }
+    }
+
+    /// Returns the width of the numbers, in bits.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+}
+
+impl<W: Write> Encoder<W> for BitPackEncoder<W> {
+    /// Buffers a sequence of numbers, which are packed when the encoder is
+    /// finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in the width, in which case none are buffered.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let max = max_value(self.width);
+        if nums.iter().any(|num| num.to_u64() > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Number does not fit in the width.",
+            ));
+        }
+        self.nums.extend(nums.iter().map(|num| num.to_u64()));
+        Ok(())
+    }
+
+    /// Packs the numbers and writes them.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![self.width as u8];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+        bytes.extend(pack(&self.nums, self.width));
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`BitPackEncoder`].
+pub struct BitPackDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> BitPackDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        BitPackDecoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for BitPackDecoder<R> {
+    /// Decodes the stream.
+    ///
+    /// Returns an error if the stream is cut short, or has bytes after the
+    /// numbers, or if a number does not fit in the type.
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self
+            .reader
+            .read_to_end()
+            .map_err(|_| InvalidCodeError::BitPackCodeError)?
+            .into_bytes();
+        let (&width, rest) = bytes
+            .split_first()
+            .ok_or(InvalidCodeError::BitPackCodeError)?;
+        let width = u32::from(width);
+        if width > u64::BITS {
+            return Err(InvalidCodeError::BitPackCodeError);
+        }
+        let (count, len) =
+            decode_vb_prefix(rest).map_err(|_| InvalidCodeError::BitPackCodeError)?;
+        let count = usize::try_from(count).map_err(|_| InvalidCodeError::BitPackCodeError)?;
+        let packed = &rest[len..];
+        let n_bytes = count
+            .checked_mul(width as usize)
+            .map(|n_bits| n_bits.div_ceil(8))
+            .ok_or(InvalidCodeError::BitPackCodeError)?;
+        if packed.len() != n_bytes {
+            return Err(InvalidCodeError::BitPackCodeError);
+        }
+        unpack(packed, width, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_pack_unpack() {
+        for width in 0..=64 {
+            let nums: Vec<u64> = (0..77_u64)
+                .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) & max_value(width))
+                .collect();
+            let bytes = pack(&nums, width);
+            assert_eq!(bytes.len(), (77 * width as usize).div_ceil(8));
+            assert_eq!(unpack::<u64>(&bytes, width, nums.len()), Ok(nums));
+        }
+    }
+
+    #[test]
+    fn test_unpack_errs() {
+        let bytes = pack(&[1000_u32, 1, 2], 10);
+        assert!(unpack::<u32>(&bytes, 10, 4).is_err());
+        assert!(unpack::<u8>(&bytes, 10, 3).is_err());
+        assert_eq!(unpack::<u8>(&bytes[..2], 10, 0), Ok(vec![]));
+        assert_eq!(unpack::<u8>(&[], 0, 3), Ok(vec![0, 0, 0]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_pack_too_wide() {
+        pack(&[8_u32], 3);
+    }
+
+    fn encode(nums: &[u64], width: u32) -> Vec<u8> {
+        let mut enc = BitPackEncoder::new(Cursor::new(vec![]), width);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode<T: Numeric>(bytes: Vec<u8>) -> Result<Vec<T>, InvalidCodeError> {
+        BitPackDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for width in [0, 1, 17, 63, 64] {
+            let nums: Vec<u64> = (0..300_u64)
+                .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) & max_value(width))
+                .collect();
+            assert_eq!(decode::<u64>(encode(&nums, width)).unwrap(), nums);
+        }
+        assert_eq!(encode(&[], 5), vec![5, 0x80]);
+        assert_eq!(decode::<u8>(encode(&[], 5)).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut bytes = encode(&[1, 2, 3], 7);
+        bytes.push(0);
+        assert!(decode::<u8>(bytes.clone()).is_err());
+        bytes.truncate(bytes.len() - 2);
+        assert!(decode::<u8>(bytes).is_err());
+
+        // A width wider than 64 bits.
+        assert!(decode::<u64>(vec![65, 0x80]).is_err());
+        assert!(decode::<u8>(encode(&[300], 9)).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The number of integers in a miniblock.
+const MINIBLOCK_LEN: usize = 128;
+
+/// The number of miniblocks in a superblock.
+const SUPERBLOCK_LEN: usize = 16;
+
+/// The number of lanes that the integers of a miniblock are interleaved in.
+const LANES: usize = 4;
+
+/// The header byte of a miniblock whose integers form an arithmetic
+/// progression.
+const PROGRESSION: u8 = 0xFF;
+
+/// A structure that wraps a writer and encodes a sequence of 32-bit integers
+/// using the two-level layout of SIMD-BP128.
+///
+/// The integers are split in miniblocks of 128 integers, each of which is
+/// packed with the bit-width of its largest integer. Every 16 miniblocks form
+/// a superblock, whose header holds the 16 widths in one byte each. Within a
+/// miniblock of width *b*, the integers are interleaved in 4 lanes of 32-bit
+/// little-endian words, the *i*-th integer going to lane *i mod 4*, so the
+/// miniblock takes *16 b* bytes and can be unpacked with 128-bit vectors. The
+/// stream starts with the number of integers in Variable Byte Encoding, and
+/// the last miniblock is padded with zeros.
+///
+/// A miniblock whose integers form an exact arithmetic progression, such as
+/// consecutive IDs, is instead marked with the width `0xFF`, and stored as its
+/// first integer and its stride, in two words, regardless of the width of its
+/// integers. The stride wraps around, so decreasing progressions qualify too.
+///
+/// With the `simd` feature, miniblocks are unpacked with SSE2 instructions on
+/// x86-64; otherwise, a scalar kernel is used.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{BP128Decoder, BP128Encoder, Decoder, Encoder};
+///
+/// let nums: Vec<u32> = (0..300).map(|i| i % 7).collect();
+/// let mut enc = BP128Encoder::new(Cursor::new(vec![]));
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, one superblock header and three miniblocks of width 3.
+/// assert_eq!(result.len(), 2 + 16 + 3 * 16 * 3);
+///
+/// let dec = BP128Decoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+///
+/// // Auto-increment keys take two words per miniblock.
+/// let keys: Vec<u32> = (1_000_000..1_000_256).collect();
+/// let mut enc = BP128Encoder::new(Cursor::new(vec![]));
+/// enc.encode(&keys).unwrap();
+/// assert_eq!(enc.finalize().unwrap().into_inner().len(), 2 + 16 + 2 * 8);
+/// ```
+pub struct BP128Encoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<u32>,
+}
+
+impl<W: Write> BP128Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        BP128Encoder {
+            writer,
+            nums: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for BP128Encoder<W> {
+    /// Buffers a sequence of numbers, which are packed in blocks when the
+    /// encoder is finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in 32 bits.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            let num = u32::try_from(num.to_u64()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Number wider than 32 bits.")
+            })?;
+            self.nums.push(num);
+        }
+        Ok(())
+    }
+
+    /// Packs the numbers in superblocks and writes them.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+
+        for superblock in self.nums.chunks(SUPERBLOCK_LEN * MINIBLOCK_LEN) {
+            let mut header = [0_u8; SUPERBLOCK_LEN];
+            let mut words = vec![];
+            for (i, miniblock) in superblock.chunks(MINIBLOCK_LEN).enumerate() {
+                let width = miniblock
+                    .iter()
+                    .map(|n| u32::BITS - n.leading_zeros())
+                    .max()
+                    .unwrap_or(0);
+                match progression(miniblock) {
+                    Some((start, stride)) if width > 0 => {
+                        header[i] = PROGRESSION;
+                        words.extend([start, stride]);
+                    }
+                    _ => {
+                        header[i] = width as u8;
+                        pack_miniblock(miniblock, width, &mut words);
+                    }
+                }
+            }
+            bytes.extend_from_slice(&header);
+            for word in words {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// Returns the first integer and the stride of a miniblock, if its integers
+/// form an arithmetic progression, with wrapping arithmetic.
+fn progression(nums: &[u32]) -> Option<(u32, u32)> {
+    let start = *nums.first()?;
+    let stride = nums.get(1).map_or(0, |next| next.wrapping_sub(start));
+    nums.windows(2)
+        .all(|w| w[1].wrapping_sub(w[0]) == stride)
+        .then_some((start, stride))
+}
+
+/// Returns the mask of the lowest `width` bits.
+fn mask(width: u32) -> u32 {
+    match width {
+        0 => 0,
+        width => u32::MAX >> (32 - width),
+    }
+}
+
+/// Packs up to 128 integers of a miniblock with the specified width, appending
+/// the `4 width` words of the miniblock to the buffer.
+fn pack_miniblock(nums: &[u32], width: u32, buffer: &mut Vec<u32>) {
+    if width == 0 {
+        return;
+    }
+    let start = buffer.len();
+    buffer.resize(start + LANES * width as usize, 0);
+    let words = &mut buffer[start..];
+    for (i, &num) in nums.iter().enumerate() {
+        let offset = (i / LANES) as u32 * width;
+        let idx = LANES * (offset / 32) as usize + i % LANES;
+        let shift = offset % 32;
+        words[idx] |= num << shift;
+        if shift + width > 32 {
+            words[idx + LANES] |= num >> (32 - shift);
+        }
+    }
+}
+
+/// Unpacks the 128 integers of a miniblock with the specified width from its
+/// `4 width` words, with the scalar kernel.
+#[cfg_attr(all(feature = "simd", target_arch = "x86_64"), allow(dead_code))]
+fn unpack_miniblock_scalar(words: &[u32], width: u32, out: &mut [u32; MINIBLOCK_LEN]) {
+    if width == 0 {
+        out.fill(0);
+        return;
+    }
+    let mask = mask(width);
+    for (i, num) in out.iter_mut().enumerate() {
+        let offset = (i / LANES) as u32 * width;
+        let idx = LANES * (offset / 32) as usize + i % LANES;
+        let shift = offset % 32;
+        let mut value = words[idx] >> shift;
+        if shift + width > 32 {
+            value |= words[idx + LANES] << (32 - shift);
+        }
+        *num = value & mask;
+    }
+}
+
+/// Unpacks the 128 integers of a miniblock with the specified width from its
+/// `4 width` words, with the SSE2 kernel, which unpacks the 4 lanes at once.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn unpack_miniblock_simd(words: &[u32], width: u32, out: &mut [u32; MINIBLOCK_LEN]) {
+    use std::arch::x86_64::*;
+
+    assert_eq!(words.len(), LANES * width as usize);
+    if width == 0 {
+        out.fill(0);
+        return;
+    }
+    // SAFETY: SSE2 is available on every x86-64 target, and all the loads and
+    // stores are unaligned and within the bounds of `words` and `out`, since
+    // `words` holds `4 width` words and the offsets of the 32 integers of a
+    // lane are less than `32 width` bits.
+    unsafe {
+        let mask = _mm_set1_epi32(mask(width) as i32);
+        let load = |i: usize| _mm_loadu_si128(words.as_ptr().add(LANES * i) as *const __m128i);
+        for k in 0..MINIBLOCK_LEN / LANES {
+            let offset = k as u32 * width;
+            let idx = (offset / 32) as usize;
+            let shift = offset % 32;
+            let mut value = _mm_srl_epi32(load(idx), _mm_cvtsi32_si128(shift as i32));
+            if shift + width > 32 {
+                let high = _mm_sll_epi32(load(idx + 1), _mm_cvtsi32_si128((32 - shift) as i32));
+                value = _mm_or_si128(value, high);
+            }
+            value = _mm_and_si128(value, mask);
+            _mm_storeu_si128(out.as_mut_ptr().add(LANES * k) as *mut __m128i, value);
+        }
+    }
+}
+
+/// Unpacks the 128 integers of a miniblock with the fastest available kernel.
+fn unpack_miniblock(words: &[u32], width: u32, out: &mut [u32; MINIBLOCK_LEN]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    unpack_miniblock_simd(words, width, out);
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    unpack_miniblock_scalar(words, width, out);
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// the two-level layout of SIMD-BP128.
+///
+/// Each superblock starts with the widths of its 16 miniblocks of 128
+/// integers, as described in [`BP128Encoder`].
+pub struct BP128Decoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> BP128Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        BP128Decoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for BP128Decoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+        let (count, mut pos) =
+            decode_vb_prefix(&bytes).map_err(|_| InvalidCodeError::BP128CodeError)?;
+        let count = usize::try_from(count).map_err(|_| InvalidCodeError::BP128CodeError)?;
+
+        let mut nums = Vec::with_capacity(count.min(bytes.len() * 8));
+        let mut words = vec![];
+        let mut out = [0_u32; MINIBLOCK_LEN];
+        while nums.len() < count {
+            let header = bytes
+                .get(pos..pos + SUPERBLOCK_LEN)
+                .ok_or(InvalidCodeError::BP128CodeError)?;
+            pos += SUPERBLOCK_LEN;
+
+            for &width in header {
+                if nums.len() == count {
+                    break;
+                }
+                let n = MINIBLOCK_LEN.min(count - nums.len());
+                if width == PROGRESSION {
+                    let block = bytes
+                        .get(pos..pos + 8)
+                        .ok_or(InvalidCodeError::BP128CodeError)?;
+                    pos += 8;
+                    let start = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+                    let stride = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+                    for i in 0..n as u32 {
+                        let num = start.wrapping_add(i.wrapping_mul(stride));
+                        nums.push(
+                            T::from_u64(u64::from(num)).ok_or(InvalidCodeError::BP128CodeError)?,
+                        );
+                    }
+                    continue;
+                }
+
+                let width = u32::from(width);
+                if width > u32::BITS {
+                    return Err(InvalidCodeError::BP128CodeError);
+                }
+                let len = 4 * LANES * width as usize;
+                let block = bytes
+                    .get(pos..pos + len)
+                    .ok_or(InvalidCodeError::BP128CodeError)?;
+                pos += len;
+
+                words.clear();
+                words.extend(
+                    block
+                        .chunks_exact(4)
+                        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])),
+                );
+                unpack_miniblock(&words, width, &mut out);
+
+                for num in &out[..n] {
+                    nums.push(
+                        T::from_u64(u64::from(*num)).ok_or(InvalidCodeError::BP128CodeError)?,
+                    );
+                }
+            }
+        }
+
+        if pos != bytes.len() {
+            return Err(InvalidCodeError::BP128CodeError);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = BP128Encoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let nums: Vec<u32> = (0..128).map(|i| i * 37 % 101).collect();
+        let mut words = vec![];
+        pack_miniblock(&nums, 7, &mut words);
+        assert_eq!(words.len(), 28);
+        assert_eq!(words[0] & 0x7F, 0);
+        assert_eq!(words[1] & 0x7F, 37);
+
+        let mut out = [0; MINIBLOCK_LEN];
+        unpack_miniblock_scalar(&words, 7, &mut out);
+        assert_eq!(out.to_vec(), nums);
+    }
+
+    #[test]
+    fn test_kernels_agree() {
+        for width in 0..=32 {
+            let nums: Vec<u32> = (0..128_u32)
+                .map(|i| i.wrapping_mul(0x9E37_79B9) & mask(width))
+                .collect();
+            let mut words = vec![];
+            pack_miniblock(&nums, width, &mut words);
+
+            let mut out = [0; MINIBLOCK_LEN];
+            unpack_miniblock_scalar(&words, width, &mut out);
+            assert_eq!(out.to_vec(), nums);
+
+            let mut out = [0; MINIBLOCK_LEN];
+            unpack_miniblock(&words, width, &mut out);
+            assert_eq!(out.to_vec(), nums);
+        }
+    }
+
+    #[test]
+    fn test_progression() {
+        assert_eq!(progression(&[5, 8, 11]), Some((5, 3)));
+        assert_eq!(progression(&[5, 3, 1]), Some((5, u32::MAX - 1)));
+        assert_eq!(progression(&[7]), Some((7, 0)));
+        assert_eq!(progression(&[1, 2, 4]), None);
+        assert_eq!(progression(&[]), None);
+
+        // Consecutive IDs, a decreasing progression that wraps around, a
+        // constant run, and a partial miniblock.
+        let mut nums: Vec<u32> = (1 << 20..(1 << 20) + 128).collect();
+        nums.extend((0..128_u32).map(|i| 100_u32.wrapping_sub(3 * i)));
+        nums.extend([9; 128]);
+        nums.extend((0..50).map(|i| i * 1000));
+        let result = encode(&nums);
+        assert_eq!(result.len(), 2 + 16 + 4 * 8);
+        assert_eq!(&result[2..6], &[PROGRESSION; 4]);
+        let dec = BP128Decoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u32>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = (0..5000).map(|i| i * i % 1000).collect();
+        nums.extend([u32::MAX as u64, 0, 1]);
+        let dec = BP128Decoder::new(Cursor::new(encode(&nums)));
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        let dec = BP128Decoder::new(Cursor::new(encode::<u32>(&[])));
+        assert_eq!(dec.decode::<u32>().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = BP128Encoder::new(Cursor::new(vec![]));
+        assert!(enc.encode(&[1_u64 << 32]).is_err());
+
+        let mut result = encode(&[1_u32, 2, 3]);
+        result.push(0);
+        let dec = BP128Decoder::new(Cursor::new(result.clone()));
+        assert!(dec.decode::<u32>().is_err());
+
+        result.truncate(result.len() - 2);
+        let dec = BP128Decoder::new(Cursor::new(result));
+        assert!(dec.decode::<u32>().is_err());
+
+        let dec = BP128Decoder::new(Cursor::new(encode(&[300_u32])));
+        assert!(dec.decode::<u8>().is_err());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod bitpack;
+pub mod bp128;
+pub mod pfor;
+pub mod signed;
+pub mod simple;
+pub mod simple16;
+pub mod simple9;
@@ -0,0 +1,328 @@
+use std::io::{self, Read, Write};
+
+use super::bitpack::{pack, unpack};
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The number of integers in a block.
+const BLOCK_LEN: usize = 128;
+
+/// How a [`PForEncoder`] chooses the width of the integers of each block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PForMode {
+    /// The smallest width that leaves at most a tenth of the integers of the
+    /// block as exceptions, as in the original PForDelta.
+    #[default]
+    Threshold,
+    /// The width that minimizes the size of the block, exceptions included,
+    /// as in OptPFor.
+    Optimal,
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// Patched Frame of Reference (PFor).
+///
+/// The integers are split in blocks of 128, and the low bits of all the
+/// integers of a block are bit-packed at a common width, chosen as described
+/// by the [`PForMode`]. The few integers that do not fit in the width, the
+/// exceptions, are patched in after the packed bits: each one is stored as
+/// the gap from the position of the previous exception and its high bits, in
+/// Variable Byte Encoding. Each block starts with its width in one byte and
+/// its number of exceptions in Variable Byte Encoding. The stream starts with
+/// the number of integers in Variable Byte Encoding.
+///
+/// Compared to [`BP128Encoder`](crate::BP128Encoder), a single large integer,
+/// like a long gap of a list of document IDs, does not widen its whole block.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, PForDecoder, PForEncoder};
+///
+/// let mut nums = vec![5_u32; 128];
+/// nums[70] = 1 << 20;
+/// let mut enc = PForEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, the width and the number of exceptions, 128 3-bit integers,
+/// // and the position and the high bits of the exception.
+/// assert_eq!(result.len(), 2 + 2 + 48 + 1 + 3);
+///
+/// let dec = PForDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct PForEncoder<W> {
+    writer: BitWriter<W>,
+    mode: PForMode,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> PForEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_mode(writer, PForMode::default())
+    }
+
+    /// Creates a new encoder that chooses the widths of the blocks with the
+    /// specified mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, PForEncoder};
+    /// use idencode::code::packed::pfor::PForMode;
+    ///
+    /// // A quarter of the integers need 12 bits, and the rest 1 bit.
+    /// let nums: Vec<u32> = (0..128).map(|i| if i % 4 == 0 { 4000 } else { 1 }).collect();
+    /// let mut sizes = vec![];
+    /// for mode in [PForMode::Threshold, PForMode::Optimal] {
+    ///     let mut enc = PForEncoder::with_mode(Cursor::new(vec![]), mode);
+    ///     enc.encode(&nums).unwrap();
+    ///     sizes.push(enc.finalize().unwrap().into_inner().len());
+    /// }
+    /// assert!(sizes[1] < sizes[0]);
+    /// ```
+    pub fn with_mode(writer: W, mode: PForMode) -> Self {
+        let writer = BitWriter::new(writer, false);
+        PForEncoder {
+            writer,
+            mode,
+            nums: vec![],
+        }
+    }
+
+    /// Returns the mode of the encoder.
+    pub fn mode(&self) -> PForMode {
+        self.mode
+    }
+}
+
+impl<W: Write> Encoder<W> for PForEncoder<W> {
+    /// Buffers a sequence of numbers, which are packed in blocks when the
+    /// encoder is finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.nums.extend(nums.iter().map(|n| n.to_u64()));
+        Ok(())
+    }
+
+    /// Packs the numbers in blocks and writes them.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+        for block in self.nums.chunks(BLOCK_LEN) {
+            let width = match self.mode {
+                PForMode::Threshold => threshold_width(block),
+                PForMode::Optimal => optimal_width(block),
+            };
+            write_block(block, width, &mut bytes);
+        }
+
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// Returns the number of bits of a number.
+fn bit_len(num: u64) -> u32 {
+    u64::BITS - num.leading_zeros()
+}
+
+/// Returns the number of bytes of the Variable Byte code of a number.
+fn vb_len(num: u64) -> usize {
+    bit_len(num).div_ceil(7).max(1) as usize
+}
+
+/// Returns the smallest width that leaves at most a tenth of the integers of
+/// a block as exceptions.
+fn threshold_width(block: &[u64]) -> u32 {
+    let mut lens: Vec<u32> = block.iter().map(|num| bit_len(*num)).collect();
+    lens.sort_unstable();
+    lens[block.len() - 1 - block.len() / 10]
+}
+
+/// Returns the width that minimizes the size of a block.
+fn optimal_width(block: &[u64]) -> u32 {
+    let max = block.iter().map(|num| bit_len(*num)).max().unwrap_or(0);
+    (0..=max)
+        .min_by_key(|&width| block_size(block, width))
+        .expect("There is at least one width.")
+}
+
+/// Returns the number of bytes of a block packed with the specified width.
+fn block_size(block: &[u64], width: u32) -> usize {
+    let mut size = 1 + (block.len() * width as usize).div_ceil(8);
+    let mut n_exceptions = 0;
+    let mut prev = 0;
+    for (pos, num) in block.iter().enumerate() {
+        if bit_len(*num) > width {
+            size += vb_len((pos - prev) as u64) + vb_len(num >> width);
+            n_exceptions += 1;
+            prev = pos + 1;
+        }
+    }
+    size + vb_len(n_exceptions)
+}
+
+/// Writes a block packed with the specified width, followed by its
+/// exceptions.
+fn write_block(block: &[u64], width: u32, buffer: &mut Vec<u8>) {
+    let mask = u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0);
+    let exceptions: Vec<usize> = (0..block.len())
+        .filter(|pos| bit_len(block[*pos]) > width)
+        .collect();
+
+    buffer.push(width as u8);
+    write_vb_bytes(exceptions.len() as u64, buffer);
+    let low: Vec<u64> = block.iter().map(|num| num & mask).collect();
+    buffer.extend(pack(&low, width));
+    let mut prev = 0;
+    for pos in exceptions {
+        write_vb_bytes((pos - prev) as u64, buffer);
+        write_vb_bytes(block[pos] >> width, buffer);
+        prev = pos + 1;
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`PForEncoder`], in either mode.
+pub struct PForDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> PForDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        PForDecoder { reader }
+    }
+}
+
+impl<R: Read> Decoder<R> for PForDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::PForCodeError;
+        let bytes = self.reader.read_to_end().map_err(|_| err())?.into_bytes();
+        let (count, mut pos) = decode_vb_prefix(&bytes).map_err(|_| err())?;
+        let count = usize::try_from(count).map_err(|_| err())?;
+
+        let mut nums = Vec::with_capacity(count.min(bytes.len() * 8));
+        while nums.len() < count {
+            let width = u32::from(*bytes.get(pos).ok_or_else(err)?);
+            if width > u64::BITS {
+                return Err(err());
+            }
+            let (n_exceptions, len) = decode_vb_prefix(&bytes[pos + 1..]).map_err(|_| err())?;
+            pos += 1 + len;
+
+            let n = BLOCK_LEN.min(count - nums.len());
+            let len = (n * width as usize).div_ceil(8);
+            let packed = bytes.get(pos..pos + len).ok_or_else(err)?;
+            pos += len;
+            let mut block = unpack::<u64>(packed, width, n).map_err(|_| err())?;
+
+            let mut next = 0;
+            for _ in 0..n_exceptions {
+                let (gap, len) = decode_vb_prefix(&bytes[pos..]).map_err(|_| err())?;
+                pos += len;
+                let (high, len) = decode_vb_prefix(&bytes[pos..]).map_err(|_| err())?;
+                pos += len;
+
+                let i = usize::try_from(gap)
+                    .ok()
+                    .and_then(|gap| gap.checked_add(next))
+                    .filter(|i| *i < n)
+                    .ok_or_else(err)?;
+                if high.checked_shl(width).is_none_or(|h| h >> width != high) {
+                    return Err(err());
+                }
+                block[i] |= high << width;
+                next = i + 1;
+            }
+
+            for num in &block {
+                nums.push(T::from_u64(*num).ok_or_else(err)?);
+            }
+        }
+
+        if pos != bytes.len() {
+            return Err(err());
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], mode: PForMode) -> Vec<u8> {
+        let mut enc = PForEncoder::with_mode(Cursor::new(vec![]), mode);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        PForDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_widths() {
+        let mut block = vec![3_u64; 100];
+        block.extend([1 << 10; 10]);
+        block.extend([1 << 40; 18]);
+        // 28 integers are wider than 2 bits, and 18 are wider than 11 bits.
+        assert_eq!(threshold_width(&block), 41);
+        assert_eq!(threshold_width(&block[..110]), 2);
+        assert_eq!(threshold_width(&[0]), 0);
+
+        let width = optimal_width(&block);
+        assert!((0..=41).all(|w| block_size(&block, width) <= block_size(&block, w)));
+        let mut bytes = vec![];
+        write_block(&block, width, &mut bytes);
+        assert_eq!(bytes.len(), block_size(&block, width));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = (0..1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 58)
+            .collect();
+        for i in (0..1000).step_by(37) {
+            nums[i] = u64::MAX >> (i % 64);
+        }
+        for mode in [PForMode::Threshold, PForMode::Optimal] {
+            assert_eq!(decode(encode(&nums, mode)).unwrap(), nums);
+            assert_eq!(decode(encode(&[], mode)).unwrap(), vec![]);
+            assert_eq!(decode(encode(&[0; 5], mode)).unwrap(), vec![0; 5]);
+        }
+        assert!(encode(&nums, PForMode::Optimal).len() <= encode(&nums, PForMode::Threshold).len());
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut nums = vec![1_u64; 20];
+        nums[7] = 1000;
+        let mut bytes = encode(&nums, PForMode::Threshold);
+        bytes.push(0);
+        assert!(decode(bytes.clone()).is_err());
+        bytes.truncate(bytes.len() - 2);
+        assert!(decode(bytes).is_err());
+
+        // An exception past the end of its block.
+        let bytes = vec![0x80 | 1, 1, 0x80 | 1, 0, 0x80 | 1, 0x80 | 1];
+        assert!(decode(bytes).is_err());
+        // High bits that overflow 64 bits.
+        let mut bytes = vec![0x80 | 1, 63, 0x80 | 1];
+        bytes.extend([0; 8]);
+        bytes.extend([0x80, 0x80 | 2]);
+        assert!(decode(bytes).is_err());
+
+        let dec = PForDecoder::new(Cursor::new(encode(&[1 << 40], PForMode::Optimal)));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::io::{self, Read, Write};
+
+use super::bitpack::{pack, unpack};
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::convert::{unzigzag, zigzag};
+use crate::num::Numeric;
+
+/// The number of integers in a block.
+const BLOCK_LEN: usize = 128;
+
+/// A structure that wraps a writer and bit-packs a sequence of signed
+/// integers, each block at the exact width of its range.
+///
+/// The integers are split in blocks of 128, and each integer is stored as its
+/// offset from the minimum of its block, in two's complement arithmetic, so
+/// the offsets of a block of signed integers whose range fits in *n* bits take
+/// exactly *n* bits each, whatever their sign. Each block starts with its
+/// width in one byte and its minimum zigzagged in Variable Byte Encoding,
+/// followed by its offsets, packed from the most significant bit and padded to
+/// a byte. A block of equal integers has a width of zero, and takes only its
+/// header. The stream starts with the number of integers in Variable Byte
+/// Encoding.
+///
+/// Unlike zigzag encoding, which favours integers close to zero, the width
+/// depends only on the range of a block, so it suits integers whose range is
+/// known, like the small residuals of a forecast around any level.
+///
+/// Signed integers are encoded with [`SignedPackEncoder::encode_signed`], and
+/// unsigned ones with [`Encoder::encode`], which packs their offsets in the
+/// same way.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Encoder, SignedPackDecoder, SignedPackEncoder};
+///
+/// let residuals: Vec<i64> = (0..128).map(|i| i % 16 - 8).collect();
+/// let mut enc = SignedPackEncoder::new(Cursor::new(vec![]));
+/// enc.encode_signed(&residuals).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, the width and the zigzagged minimum, and 128 4-bit offsets.
+/// assert_eq!(result.len(), 2 + 1 + 1 + 64);
+///
+/// let dec = SignedPackDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode_signed().unwrap(), residuals);
+/// ```
+pub struct SignedPackEncoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<i64>,
+}
+
+impl<W: Write> SignedPackEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, false);
+        SignedPackEncoder {
+            writer,
+            nums: vec![],
+        }
+    }
+
+    /// Buffers a sequence of signed integers, which are packed in blocks when
+    /// the encoder is finalized.
+    pub fn encode_signed(&mut self, nums: &[i64]) -> io::Result<()> {
+        self.nums.extend_from_slice(nums);
+        Ok(())
+    }
+}
+
+impl<W: Write> Encoder<W> for SignedPackEncoder<W> {
+    /// Buffers a sequence of numbers, which are packed in blocks when the
+    /// encoder is finalized.
+    ///
+    /// The numbers are reinterpreted as signed 64-bit integers, which the
+    /// offsets from the minimum of a block wrap around, so any number can be
+    /// encoded.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.nums.extend(nums.iter().map(|num| num.to_u64() as i64));
+        Ok(())
+    }
+
+    /// Packs the numbers in blocks and writes them.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+
+        for block in self.nums.chunks(BLOCK_LEN) {
+            let min = *block.iter().min().expect("Blocks are not empty.");
+            let offsets: Vec<u64> = block
+                .iter()
+                .map(|num| num.wrapping_sub(min) as u64)
+                .collect();
+            let width = offsets
+                .iter()
+                .map(|offset| u64::BITS - offset.leading_zeros())
+                .max()
+                .expect("Blocks are not empty.");
+            bytes.push(width as u8);
+            write_vb_bytes(zigzag(min), &mut bytes);
+            bytes.extend(pack(&offsets, width));
+        }
+
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`SignedPackEncoder`].
+pub struct SignedPackDecoder<R> {
+    reader: BitReader<R>,
+}
+
+impl<R: Read> SignedPackDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = BitReader::new(reader, false);
+        SignedPackDecoder { reader }
+    }
+
+    /// Decodes the stream as signed integers.
+    pub fn decode_signed(self) -> Result<Vec<i64>, InvalidCodeError> {
+        let bytes = self
+            .reader
+            .read_to_end()
+            .map_err(|_| InvalidCodeError::SignedPackCodeError)?
+            .into_bytes();
+        let (count, mut pos) =
+            decode_vb_prefix(&bytes).map_err(|_| InvalidCodeError::SignedPackCodeError)?;
+        let count = usize::try_from(count).map_err(|_| InvalidCodeError::SignedPackCodeError)?;
+
+        let mut nums = Vec::with_capacity(count.min(bytes.len() * 8));
+        while nums.len() < count {
+            let width = u32::from(
+                *bytes
+                    .get(pos)
+                    .ok_or(InvalidCodeError::SignedPackCodeError)?,
+            );
+            if width > u64::BITS {
+                return Err(InvalidCodeError::SignedPackCodeError);
+            }
+            let (min, len) = decode_vb_prefix(&bytes[pos + 1..])
+                .map_err(|_| InvalidCodeError::SignedPackCodeError)?;
+            pos += 1 + len;
+
+            let n = BLOCK_LEN.min(count - nums.len());
+            let len = (n * width as usize).div_ceil(8);
+            let block = bytes
+                .get(pos..pos + len)
+                .ok_or(InvalidCodeError::SignedPackCodeError)?;
+            pos += len;
+
+            let offsets = unpack::<u64>(block, width, n)
+                .map_err(|_| InvalidCodeError::SignedPackCodeError)?;
+            let min = unzigzag(min);
+            nums.extend(
+                offsets
+                    .iter()
+                    .map(|offset| min.wrapping_add(*offset as i64)),
+            );
+        }
+
+        if pos != bytes.len() {
+            return Err(InvalidCodeError::SignedPackCodeError);
+        }
+        Ok(nums)
+    }
+}
+
+impl<R: Read> Decoder<R> for SignedPackDecoder<R> {
+    /// Decodes the stream, reinterpreting the signed integers as numbers of
+    /// the specified type.
+    ///
+    /// Returns an error if a number does not fit in the type.
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        self.decode_signed()?
+            .into_iter()
+            .map(|num| T::from_u64(num as u64).ok_or(InvalidCodeError::SignedPackCodeError))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[i64]) -> Vec<u8> {
+        let mut enc = SignedPackEncoder::new(Cursor::new(vec![]));
+        enc.encode_signed(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<i64>, InvalidCodeError> {
+        SignedPackDecoder::new(Cursor::new(bytes)).decode_signed()
+    }
+
+    #[test]
+    fn test_exact_widths() {
+        // Residuals in -4..4 take 3 bits, around any level.
+        for level in [0, -1_000_000, 1 << 40] {
+            let nums: Vec<i64> = (0..128).map(|i| level + i % 8 - 4).collect();
+            let bytes = encode(&nums);
+            assert_eq!(bytes[2], 3);
+            let mut min = vec![];
+            write_vb_bytes(zigzag(level - 4), &mut min);
+            assert_eq!(&bytes[3..3 + min.len()], &min);
+            assert_eq!(bytes.len(), 3 + min.len() + 128 * 3 / 8);
+            assert_eq!(decode(bytes).unwrap(), nums);
+        }
+
+        // A constant block takes only its header.
+        let bytes = encode(&[-5; 128]);
+        assert_eq!(bytes, vec![0x01, 0x80, 0, 0x80 | 9]);
+        assert_eq!(decode(bytes).unwrap(), vec![-5; 128]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<i64> = (0..1000_i64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15_u64 as i64) >> 50)
+            .collect();
+        nums.extend([i64::MIN, i64::MAX, 0, -1]);
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+
+        // Unsigned numbers of any width round-trip too.
+        let unsigned = [u64::MAX, 0, 1 << 63, 7];
+        let mut enc = SignedPackEncoder::new(Cursor::new(vec![]));
+        enc.encode(&unsigned).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+        let dec = SignedPackDecoder::new(Cursor::new(bytes));
+        assert_eq!(dec.decode::<u64>().unwrap(), unsigned);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut bytes = encode(&[1, -2, 3]);
+        bytes.push(0);
+        assert!(decode(bytes.clone()).is_err());
+
+        bytes.truncate(bytes.len() - 2);
+        assert!(decode(bytes).is_err());
+
+        // A width wider than 64 bits.
+        assert!(decode(vec![0x80 | 1, 65, 0x80]).is_err());
+
+        let dec = SignedPackDecoder::new(Cursor::new(encode(&[-1])));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,451 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder};
+use crate::error::{InvalidCodeError, SelectorTableError};
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A table of selectors, that describes how integers are packed in the words
+/// of a selector-based ("Simple") code.
+///
+/// Each word starts with a selector, that indexes a case of the table, followed
+/// by the integers of the case. A case is a list of runs of `count` integers
+/// of `width` bits each, so the integers of a word can have different widths.
+/// The selector takes as many bits as are needed to index all the cases, and
+/// the integers take the rest of the word, from the most significant bits.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::code::packed::simple::SelectorTable;
+///
+/// // 32-bit words, with a 2-bit selector and 30 bits of payload.
+/// let table = SelectorTable::new(32, &[(30, 1), (15, 2), (10, 3), (1, 30)]).unwrap();
+/// assert_eq!(table.selector_bits(), 2);
+///
+/// // 31 integers of 1 bit do not fit in the payload.
+/// assert!(SelectorTable::new(32, &[(31, 1), (15, 2), (10, 3), (1, 30)]).is_err());
+///
+/// // A case with 10 integers of 2 bits, followed by 10 integers of 1 bit.
+/// let table = SelectorTable::with_layouts(32, &[&[(10, 2), (10, 1)], &[(1, 30)]]).unwrap();
+/// assert_eq!(table.cases()[0], vec![(10, 2), (10, 1)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorTable {
+    word_bits: u32,
+    selector_bits: u32,
+    cases: Vec<Vec<(usize, u32)>>,
+}
+
+impl SelectorTable {
+    /// Creates a new table for words of `word_bits` bits, with cases of
+    /// `(count, width)` pairs.
+    ///
+    /// The word size must be a multiple of 8, up to 64 bits, and the integers
+    /// of each case must fit in the word after the selector.
+    pub fn new(word_bits: u32, cases: &[(usize, u32)]) -> Result<Self, SelectorTableError> {
+        let layouts: Vec<&[(usize, u32)]> = cases.iter().map(std::slice::from_ref).collect();
+        Self::with_layouts(word_bits, &layouts)
+    }
+
+    /// Creates a new table for words of `word_bits` bits, whose cases are
+    /// lists of runs of `(count, width)` pairs, with the same requirements as
+    /// [`SelectorTable::new`].
+    pub fn with_layouts(
+        word_bits: u32,
+        layouts: &[&[(usize, u32)]],
+    ) -> Result<Self, SelectorTableError> {
+        if layouts.is_empty()
+            || word_bits == 0
+            || word_bits > u64::BITS
+            || !word_bits.is_multiple_of(8)
+        {
+            return Err(SelectorTableError);
+        }
+        let selector_bits = usize::BITS - (layouts.len() - 1).leading_zeros();
+        let payload_bits = word_bits
+            .checked_sub(selector_bits)
+            .ok_or(SelectorTableError)?;
+        for layout in layouts {
+            let mut bits = Some(0_usize);
+            for &(count, width) in *layout {
+                if count == 0 || width > u64::BITS {
+                    return Err(SelectorTableError);
+                }
+                bits = (width as usize)
+                    .checked_mul(count)
+                    .zip(bits)
+                    .and_then(|(run, bits)| bits.checked_add(run));
+            }
+            if layout.is_empty() || bits.is_none_or(|b| b > payload_bits as usize) {
+                return Err(SelectorTableError);
+            }
+        }
+        Ok(SelectorTable {
+            word_bits,
+            selector_bits,
+            cases: layouts.iter().map(|layout| layout.to_vec()).collect(),
+        })
+    }
+
+    /// Returns the table of Simple-9, whose 32-bit words hold a 4-bit
+    /// selector and 28 bits of payload, split in 9 ways.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::code::packed::simple::SelectorTable;
+    ///
+    /// let table = SelectorTable::simple9();
+    /// assert_eq!(table.selector_bits(), 4);
+    /// assert_eq!(table.cases()[0], vec![(28, 1)]);
+    /// ```
+    pub fn simple9() -> Self {
+        let cases = [
+            (28, 1),
+            (14, 2),
+            (9, 3),
+            (7, 4),
+            (5, 5),
+            (4, 7),
+            (3, 9),
+            (2, 14),
+            (1, 28),
+        ];
+        SelectorTable::new(32, &cases).expect("The cases of Simple-9 fit in 32-bit words.")
+    }
+
+    /// Returns the table of Simple-16, whose 32-bit words hold a 4-bit
+    /// selector and 28 bits of payload, split in 16 ways, some of which mix
+    /// integers of different widths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::code::packed::simple::SelectorTable;
+    ///
+    /// let table = SelectorTable::simple16();
+    /// assert_eq!(table.cases().len(), 16);
+    /// assert_eq!(table.cases()[1], vec![(7, 2), (14, 1)]);
+    /// ```
+    pub fn simple16() -> Self {
+        let layouts: [&[(usize, u32)]; 16] = [
+            &[(28, 1)],
+            &[(7, 2), (14, 1)],
+            &[(7, 1), (7, 2), (7, 1)],
+            &[(14, 1), (7, 2)],
+            &[(14, 2)],
+            &[(1, 4), (8, 3)],
+            &[(1, 3), (4, 4), (3, 3)],
+            &[(7, 4)],
+            &[(4, 5), (2, 4)],
+            &[(2, 4), (4, 5)],
+            &[(3, 6), (2, 5)],
+            &[(2, 5), (3, 6)],
+            &[(4, 7)],
+            &[(1, 10), (2, 9)],
+            &[(2, 14)],
+            &[(1, 28)],
+        ];
+        SelectorTable::with_layouts(32, &layouts)
+            .expect("The cases of Simple-16 fit in 32-bit words.")
+    }
+
+    /// Returns the number of bits of a word.
+    pub fn word_bits(&self) -> u32 {
+        self.word_bits
+    }
+
+    /// Returns the number of bits of the selector.
+    pub fn selector_bits(&self) -> u32 {
+        self.selector_bits
+    }
+
+    /// Returns the cases of the table, as lists of runs of `(count, width)`
+    /// pairs.
+    pub fn cases(&self) -> &[Vec<(usize, u32)>] {
+        &self.cases
+    }
+
+    /// Returns the number of bytes of a word.
+    fn word_bytes(&self) -> usize {
+        (self.word_bits / 8) as usize
+    }
+
+    /// Returns the widths of the integers of a case, in order.
+    fn widths(layout: &[(usize, u32)]) -> impl Iterator<Item = u32> + '_ {
+        layout
+            .iter()
+            .flat_map(|&(count, width)| std::iter::repeat_n(width, count))
+    }
+
+    /// Packs the integers at the start of a slice in a word, with the first
+    /// case that fits them, and returns the word along with the number of
+    /// integers it holds.
+    ///
+    /// A case with more integers than are left is used only at the end of the
+    /// slice, with the rest of its integers being zero.
+    fn pack(&self, nums: &[u64]) -> Option<(u64, usize)> {
+        let (selector, n) = self.cases.iter().enumerate().find_map(|(s, layout)| {
+            let mut n = 0;
+            for (num, width) in nums.iter().zip(Self::widths(layout)) {
+                if u64::BITS - num.leading_zeros() > width {
+                    return None;
+                }
+                n += 1;
+            }
+            Some((s, n))
+        })?;
+
+        let mut shift = self.word_bits - self.selector_bits;
+        let mut word = (selector as u128) << shift;
+        for (num, width) in nums[..n].iter().zip(Self::widths(&self.cases[selector])) {
+            shift -= width;
+            word |= u128::from(*num) << shift;
+        }
+        Some((word as u64, n))
+    }
+
+    /// Unpacks the integers of a word, returning `None` if its selector does
+    /// not index a case of the table.
+    fn unpack(&self, word: u64) -> Option<impl Iterator<Item = u64> + '_> {
+        let word = u128::from(word);
+        let mut shift = self.word_bits - self.selector_bits;
+        let layout = self.cases.get((word >> shift) as usize)?;
+        Some(Self::widths(layout).map(move |width| {
+            shift -= width;
+            ((word >> shift) & ((1 << width) - 1)) as u64
+        }))
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers using a
+/// selector-based ("Simple") code, with a user-defined [`SelectorTable`].
+///
+/// The integers are greedily packed in words: each word uses the first case of
+/// the table, in order, that fits the next integers, so the cases should be
+/// listed from the most to the fewest integers per word. The stream starts with
+/// the number of integers in Variable Byte Encoding, followed by the words in
+/// big-endian byte order.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, SimpleDecoder, SimpleEncoder};
+/// use idencode::code::packed::simple::SelectorTable;
+///
+/// let table = SelectorTable::new(32, &[(30, 1), (15, 2), (10, 3), (1, 30)]).unwrap();
+/// let mut enc = SimpleEncoder::new(Cursor::new(vec![]), table.clone());
+/// let nums = [1_u32, 0, 3, 2, 3, 1, 2, 0, 1, 3, 2, 0, 1, 1, 2, 1000];
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, a word with 15 integers of 2 bits and a word with 1000.
+/// assert_eq!(result.len(), 1 + 2 * 4);
+///
+/// let dec = SimpleDecoder::new(Cursor::new(result), table);
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct SimpleEncoder<W> {
+    writer: BitWriter<W>,
+    table: SelectorTable,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> SimpleEncoder<W> {
+    pub fn new(writer: W, table: SelectorTable) -> Self {
+        let writer = BitWriter::new(writer, false);
+        SimpleEncoder {
+            writer,
+            table,
+            nums: vec![],
+        }
+    }
+
+    /// Returns the selector table of the encoder.
+    pub fn table(&self) -> &SelectorTable {
+        &self.table
+    }
+}
+
+impl<W: Write> Encoder<W> for SimpleEncoder<W> {
+    /// Buffers a sequence of numbers, which are packed in words when the
+    /// encoder is finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.nums.extend(nums.iter().map(|n| n.to_u64()));
+        Ok(())
+    }
+
+    /// Packs the numbers in words and writes them.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in any case of the table.
+    fn finalize(mut self) -> io::Result<W> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.nums.len() as u64, &mut bytes);
+
+        let word_bytes = self.table.word_bytes();
+        let mut pos = 0;
+        while pos < self.nums.len() {
+            let (word, n) = self.table.pack(&self.nums[pos..]).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Number too wide for the selector table.",
+                )
+            })?;
+            bytes.extend_from_slice(&word.to_be_bytes()[8 - word_bytes..]);
+            pos += n;
+        }
+
+        self.writer.get_mut().extend_from_byte_slice(&bytes);
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using a
+/// selector-based ("Simple") code, with a user-defined [`SelectorTable`].
+///
+/// The table must be the same as the encoder's.
+pub struct SimpleDecoder<R> {
+    reader: BitReader<R>,
+    table: SelectorTable,
+}
+
+impl<R: Read> SimpleDecoder<R> {
+    pub fn new(reader: R, table: SelectorTable) -> Self {
+        let reader = BitReader::new(reader, false);
+        SimpleDecoder { reader, table }
+    }
+}
+
+impl<R: Read> Decoder<R> for SimpleDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bytes = self.reader.read_to_end().unwrap().into_bytes();
+        let (count, len) =
+            decode_vb_prefix(&bytes).map_err(|_| InvalidCodeError::SimpleCodeError)?;
+
+        let table = &self.table;
+        let words = bytes[len..].chunks_exact(table.word_bytes());
+        if !words.remainder().is_empty() {
+            return Err(InvalidCodeError::SimpleCodeError);
+        }
+
+        let mut nums = vec![];
+        for chunk in words {
+            let mut buf = [0; 8];
+            buf[8 - chunk.len()..].copy_from_slice(chunk);
+            let word = u64::from_be_bytes(buf);
+
+            let left = (count as usize)
+                .checked_sub(nums.len())
+                .filter(|left| *left > 0)
+                .ok_or(InvalidCodeError::SimpleCodeError)?;
+            let unpacked = table
+                .unpack(word)
+                .ok_or(InvalidCodeError::SimpleCodeError)?;
+            for num in unpacked.take(left) {
+                nums.push(T::from_u64(num).ok_or(InvalidCodeError::SimpleCodeError)?);
+            }
+        }
+
+        if nums.len() as u64 != count {
+            return Err(InvalidCodeError::SimpleCodeError);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn table() -> SelectorTable {
+        SelectorTable::new(32, &[(30, 1), (15, 2), (10, 3), (1, 30)]).unwrap()
+    }
+
+    fn encode<T: Numeric>(nums: &[T], table: SelectorTable) -> io::Result<Vec<u8>> {
+        let mut enc = SimpleEncoder::new(Cursor::new(vec![]), table);
+        enc.encode(nums)?;
+        Ok(enc.finalize()?.into_inner())
+    }
+
+    #[test]
+    fn test_table_errs() {
+        assert!(SelectorTable::new(32, &[]).is_err());
+        assert!(SelectorTable::new(12, &[(1, 8)]).is_err());
+        assert!(SelectorTable::new(32, &[(0, 8)]).is_err());
+        assert!(SelectorTable::new(8, &[(1, 7), (1, 8)]).is_err());
+        assert!(SelectorTable::new(64, &[(1, 64)]).is_ok());
+        assert!(SelectorTable::with_layouts(32, &[&[]]).is_err());
+        assert!(SelectorTable::with_layouts(32, &[&[(10, 2), (13, 1)]]).is_err());
+        assert!(SelectorTable::with_layouts(32, &[&[(usize::MAX, 2), (1, 1)]]).is_err());
+    }
+
+    #[test]
+    fn test_pack() {
+        let table = table();
+        assert_eq!(table.pack(&[1, 2, 3]), Some((0b01_011011 << 24, 3)));
+        assert_eq!(table.pack(&[1, 1 << 29, 1]), Some((0xC000_0001, 1)));
+        assert_eq!(table.pack(&[1 << 30]), None);
+
+        // The widths of the integers change within the word.
+        let table = SelectorTable::with_layouts(8, &[&[(1, 3), (2, 2)], &[(1, 7)]]).unwrap();
+        assert_eq!(table.pack(&[5, 3, 1, 1]), Some((0b0101_1101, 3)));
+        assert_eq!(table.pack(&[1, 4]), Some((0b1000_0001, 1)));
+        let unpacked: Vec<u64> = table.unpack(0b0101_1101).unwrap().collect();
+        assert_eq!(unpacked, vec![5, 3, 1]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u32> = (0..100).map(|i| i % 2).collect();
+        nums.extend([7, 0, 1 << 29, 3, 3, 1]);
+        let result = encode(&nums, table()).unwrap();
+
+        let dec = SimpleDecoder::new(Cursor::new(result), table());
+        assert_eq!(dec.decode::<u32>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_custom_word_sizes() {
+        let nums = [0_u64, 5, 255, 1 << 40, 3, u64::MAX];
+        let table = SelectorTable::new(64, &[(240, 0), (8, 7), (2, 31), (1, 62)]).unwrap();
+        assert!(encode(&nums, table).is_err());
+
+        let table = SelectorTable::new(64, &[(1, 64)]).unwrap();
+        let result = encode(&nums, table.clone()).unwrap();
+        let dec = SimpleDecoder::new(Cursor::new(result), table);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums);
+
+        let table = SelectorTable::new(8, &[(3, 2), (1, 6)]).unwrap();
+        let small = [1_u8, 2, 3, 60, 0];
+        let result = encode(&small, table.clone()).unwrap();
+        assert_eq!(result.len(), 1 + 3);
+        let dec = SimpleDecoder::new(Cursor::new(result), table);
+        assert_eq!(dec.decode::<u8>().unwrap(), small);
+
+        let zeros = vec![0_u32; 500];
+        let table = SelectorTable::new(64, &[(240, 0), (8, 7), (2, 31), (1, 62)]).unwrap();
+        let result = encode(&zeros, table.clone()).unwrap();
+        assert_eq!(result.len(), 2 + 3 * 8);
+        let dec = SimpleDecoder::new(Cursor::new(result), table);
+        assert_eq!(dec.decode::<u32>().unwrap(), zeros);
+    }
+
+    #[test]
+    fn test_decode_errs() {
+        let mut result = encode(&[1_u32, 2, 3], table()).unwrap();
+        result.push(0);
+        let dec = SimpleDecoder::new(Cursor::new(result.clone()), table());
+        assert!(dec.decode::<u32>().is_err());
+
+        result.pop();
+        result[0] = 0x80;
+        let dec = SimpleDecoder::new(Cursor::new(result), table());
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{self, Read, Write};
+
+use crate::code::packed::simple::{SelectorTable, SimpleDecoder, SimpleEncoder};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// Simple-16.
+///
+/// Simple-16 packs the integers in 32-bit words, each with a 4-bit selector
+/// and 28 bits of payload, like [`Simple9Encoder`](crate::Simple9Encoder),
+/// but the 16 values of the selector index 16 layouts, some of which mix
+/// integers of different widths, so that fewer bits are wasted. It is the
+/// [`SimpleEncoder`] with the [`SelectorTable::simple16`] table, so the
+/// integers are buffered, and packed when the encoder is finalized, and must
+/// be less than 2^28.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, Simple16Decoder, Simple16Encoder};
+///
+/// let mut enc = Simple16Encoder::new(Cursor::new(vec![]));
+/// let mut nums = vec![3_u32, 2, 3, 1, 2, 3, 2];
+/// nums.extend([1; 14]);
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, and a word with 7 integers of 2 bits and 14 of 1 bit, where
+/// // Simple-9 would take two words.
+/// assert_eq!(result.len(), 1 + 4);
+///
+/// let dec = Simple16Decoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct Simple16Encoder<W> {
+    inner: SimpleEncoder<W>,
+}
+
+impl<W: Write> Simple16Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        let inner = SimpleEncoder::new(writer, SelectorTable::simple16());
+        Simple16Encoder { inner }
+    }
+}
+
+impl<W: Write> Encoder<W> for Simple16Encoder<W> {
+    /// Buffers a sequence of numbers, which are packed in words when the
+    /// encoder is finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.inner.encode(nums)
+    }
+
+    /// Packs the numbers in words and writes them.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is not less than 2^28.
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Simple-16.
+pub struct Simple16Decoder<R> {
+    inner: SimpleDecoder<R>,
+}
+
+impl<R: Read> Simple16Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        let inner = SimpleDecoder::new(reader, SelectorTable::simple16());
+        Simple16Decoder { inner }
+    }
+}
+
+impl<R: Read> Decoder<R> for Simple16Decoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        self.inner.decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u32]) -> io::Result<Vec<u8>> {
+        let mut enc = Simple16Encoder::new(Cursor::new(vec![]));
+        enc.encode(nums)?;
+        Ok(enc.finalize()?.into_inner())
+    }
+
+    #[test]
+    fn test_word_layouts() {
+        // Each case of the table, filled with the largest integers of its
+        // widths, fills exactly one word.
+        for (selector, case) in SelectorTable::simple16().cases().iter().enumerate() {
+            let nums: Vec<u32> = case
+                .iter()
+                .flat_map(|&(count, width)| vec![(1 << width) - 1; count])
+                .collect();
+            let result = encode(&nums).unwrap();
+            assert_eq!(result.len(), 1 + 4);
+            assert_eq!(usize::from(result[1] >> 4), selector);
+            let dec = Simple16Decoder::new(Cursor::new(result));
+            assert_eq!(dec.decode::<u32>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u32> = (1..=1000_u32)
+            .map(|i| i.wrapping_mul(0x9E3779B9) >> (4 + i % 28))
+            .collect();
+        let result = encode(&nums).unwrap();
+        let dec = Simple16Decoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u32>().unwrap(), nums);
+
+        assert!(encode(&[1 << 28]).is_err());
+        let dec = Simple16Decoder::new(Cursor::new(vec![0x01, 0xF0, 0, 0, 0]));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::{self, Read, Write};
+
+use crate::code::packed::simple::{SelectorTable, SimpleDecoder, SimpleEncoder};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a sequence of integers using
+/// Simple-9.
+///
+/// Simple-9 packs the integers in 32-bit words, each with a 4-bit selector
+/// and 28 bits of payload, that hold 28 integers of 1 bit, 14 of 2 bits, 9 of
+/// 3 bits, 7 of 4 bits, 5 of 5 bits, 4 of 7 bits, 3 of 9 bits, 2 of 14 bits,
+/// or 1 of 28 bits. It is the [`SimpleEncoder`] with the
+/// [`SelectorTable::simple9`] table, so the integers are buffered, and packed
+/// when the encoder is finalized, and must be less than 2^28.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, Simple9Decoder, Simple9Encoder};
+///
+/// let mut enc = Simple9Encoder::new(Cursor::new(vec![]));
+/// let nums = [1_u32, 0, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 70000];
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// // The count, a word with 14 integers of 2 bits and a word with 70000.
+/// assert_eq!(result.len(), 1 + 2 * 4);
+///
+/// let dec = Simple9Decoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct Simple9Encoder<W> {
+    inner: SimpleEncoder<W>,
+}
+
+impl<W: Write> Simple9Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        let inner = SimpleEncoder::new(writer, SelectorTable::simple9());
+        Simple9Encoder { inner }
+    }
+}
+
+impl<W: Write> Encoder<W> for Simple9Encoder<W> {
+    /// Buffers a sequence of numbers, which are packed in words when the
+    /// encoder is finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.inner.encode(nums)
+    }
+
+    /// Packs the numbers in words and writes them.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is not less than 2^28.
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes using
+/// Simple-9.
+pub struct Simple9Decoder<R> {
+    inner: SimpleDecoder<R>,
+}
+
+impl<R: Read> Simple9Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        let inner = SimpleDecoder::new(reader, SelectorTable::simple9());
+        Simple9Decoder { inner }
+    }
+}
+
+impl<R: Read> Decoder<R> for Simple9Decoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        self.inner.decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u32]) -> io::Result<Vec<u8>> {
+        let mut enc = Simple9Encoder::new(Cursor::new(vec![]));
+        enc.encode(nums)?;
+        Ok(enc.finalize()?.into_inner())
+    }
+
+    #[test]
+    fn test_word_layouts() {
+        // Each case of the table fills exactly one word.
+        for (selector, case) in SelectorTable::simple9().cases().iter().enumerate() {
+            let (count, width) = case[0];
+            let nums = vec![(1 << width) - 1; count];
+            let result = encode(&nums).unwrap();
+            assert_eq!(result.len(), 1 + 4);
+            assert_eq!(usize::from(result[1] >> 4), selector);
+            let dec = Simple9Decoder::new(Cursor::new(result));
+            assert_eq!(dec.decode::<u32>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u32> = (1..=1000_u32)
+            .map(|i| i.wrapping_mul(0x9E3779B9) >> (4 + i % 28))
+            .collect();
+        let result = encode(&nums).unwrap();
+        let dec = Simple9Decoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u32>().unwrap(), nums);
+
+        assert!(encode(&[1 << 28]).is_err());
+        let dec = Simple9Decoder::new(Cursor::new(vec![0x01, 0xF0, 0, 0, 0]));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
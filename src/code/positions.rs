@@ -0,0 +1,238 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::delta::{decode_delta_prefix, write_delta_bits};
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes the position lists of a
+/// sequence of documents.
+///
+/// For each document, the number of positions and the number of bits of the
+/// positions are written in Elias Gamma Encoding, both incremented by one,
+/// followed by the gaps between the positions in Elias Delta Encoding. The
+/// first gap is the first position incremented by one, so positions must be
+/// less than `u64::MAX`. Since the size of the positions of each document is
+/// known in advance, the positions of documents that are not needed can be
+/// skipped without decoding them.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{PositionsDecoder, PositionsEncoder};
+///
+/// let mut enc = PositionsEncoder::new(Cursor::new(vec![]));
+/// enc.encode_document(&[3_u32, 7, 8]).unwrap();
+/// enc.encode_document::<u32>(&[]).unwrap();
+/// enc.encode_document(&[0_u32, 100]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let mut dec = PositionsDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.skip_document(), Ok(Some(3)));
+/// assert_eq!(dec.next_document::<u32>(), Ok(Some(vec![])));
+/// assert_eq!(dec.next_document::<u32>(), Ok(Some(vec![0, 100])));
+/// assert_eq!(dec.next_document::<u32>(), Ok(None));
+/// ```
+pub struct PositionsEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> PositionsEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        PositionsEncoder { writer }
+    }
+
+    /// Encodes and writes the positions of the next document.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the
+    /// positions are not strictly increasing.
+    pub fn encode_document<T: Numeric>(&mut self, positions: &[T]) -> io::Result<()> {
+        let mut gap_bits = vec![];
+        let mut prev = None;
+        for pos in positions {
+            let pos = pos.to_u64();
+            let gap = match prev {
+                None => pos.checked_add(1),
+                Some(prev) => pos.checked_sub(prev).filter(|gap| *gap > 0),
+            };
+            let gap = gap.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Positions are not strictly increasing.",
+                )
+            })?;
+            write_delta_bits(gap, UnaryConvention::default(), &mut gap_bits);
+            prev = Some(pos);
+        }
+
+        let mut header = vec![];
+        let convention = UnaryConvention::default();
+        write_gamma_bits(positions.len() + 1, convention, &mut header);
+        write_gamma_bits(gap_bits.len() + 1, convention, &mut header);
+        self.writer.write_bits(&header)?;
+        self.writer.write_bits(&gap_bits)
+    }
+
+    pub fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes the position lists of a
+/// sequence of documents, written with [`PositionsEncoder`].
+pub struct PositionsDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> PositionsDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        PositionsDecoder { cursor }
+    }
+
+    /// Reads the number of positions and the number of bits of the positions
+    /// of the next document, or `None` if there are no more documents.
+    fn next_header(&mut self) -> Result<Option<(usize, usize)>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(None);
+        }
+        let convention = UnaryConvention::default();
+        let count: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+        let len: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, convention))?;
+        Ok(Some((count - 1, len - 1)))
+    }
+
+    /// Reads and decodes the positions of the next document.
+    ///
+    /// Returns `None` if there are no more documents.
+    pub fn next_document<T: Numeric>(&mut self) -> Result<Option<Vec<T>>, InvalidCodeError> {
+        let Some((count, len)) = self.next_header()? else {
+            return Ok(None);
+        };
+        let positions = self.cursor.decode_with(|bits| {
+            let bits = bits
+                .get(..len)
+                .ok_or(InvalidCodeError::PositionsCodeError)?;
+            // Each position takes at least one bit.
+            if count > len {
+                return Err(InvalidCodeError::PositionsCodeError);
+            }
+            let mut positions = Vec::with_capacity(count);
+            let (mut pos, mut prev) = (0, None);
+            for _ in 0..count {
+                let (gap, gap_len) =
+                    decode_delta_prefix::<u64>(&bits[pos..], UnaryConvention::default())?;
+                let position = match prev {
+                    None => Some(gap - 1),
+                    Some(prev) => gap.checked_add(prev),
+                };
+                let position = position.ok_or(InvalidCodeError::PositionsCodeError)?;
+                positions.push(T::from_u64(position).ok_or(InvalidCodeError::PositionsCodeError)?);
+                prev = Some(position);
+                pos += gap_len;
+            }
+            if pos != len {
+                return Err(InvalidCodeError::PositionsCodeError);
+            }
+            Ok((positions, len))
+        })?;
+        Ok(Some(positions))
+    }
+
+    /// Skips the positions of the next document without decoding them,
+    /// returning their number.
+    ///
+    /// Returns `None` if there are no more documents.
+    pub fn skip_document(&mut self) -> Result<Option<usize>, InvalidCodeError> {
+        let Some((count, len)) = self.next_header()? else {
+            return Ok(None);
+        };
+        self.cursor.decode_with(|bits| match bits.get(..len) {
+            Some(_) => Ok(((), len)),
+            None => Err(InvalidCodeError::PositionsCodeError),
+        })?;
+        Ok(Some(count))
+    }
+
+    /// Reads and decodes the positions of all the remaining documents.
+    pub fn decode<T: Numeric>(mut self) -> Result<Vec<Vec<T>>, InvalidCodeError> {
+        let mut documents = vec![];
+        while let Some(positions) = self.next_document()? {
+            documents.push(positions);
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(documents: &[Vec<u64>]) -> Vec<u8> {
+        let mut enc = PositionsEncoder::new(Cursor::new(vec![]));
+        for positions in documents {
+            enc.encode_document(positions).unwrap();
+        }
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let documents = vec![
+            vec![0, 1, 2],
+            vec![],
+            vec![5, 1000, 1 << 40],
+            vec![u64::MAX - 2, u64::MAX - 1],
+        ];
+        let dec = PositionsDecoder::new(Cursor::new(encode(&documents)));
+        assert_eq!(dec.decode::<u64>().unwrap(), documents);
+    }
+
+    #[test]
+    fn test_skip() {
+        let documents = vec![vec![4, 9], vec![1, 2, 3, 50], vec![7]];
+        let mut dec = PositionsDecoder::new(Cursor::new(encode(&documents)));
+        assert_eq!(dec.skip_document(), Ok(Some(2)));
+        assert_eq!(dec.skip_document(), Ok(Some(4)));
+        assert_eq!(dec.next_document::<u32>(), Ok(Some(vec![7])));
+        assert_eq!(dec.skip_document(), Ok(None));
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = PositionsEncoder::new(Cursor::new(vec![]));
+        assert!(enc.encode_document(&[3_u32, 3]).is_err());
+        assert!(enc.encode_document(&[3_u32, 2]).is_err());
+        assert!(enc.encode_document(&[u64::MAX]).is_err());
+
+        let mut result = encode(&[vec![1 << 20, 1 << 30]]);
+        result.truncate(result.len() - 2);
+        result.push(0x80);
+        let mut dec = PositionsDecoder::new(Cursor::new(result));
+        assert!(dec.next_document::<u64>().is_err());
+
+        // A huge number of positions in a few bits is rejected before any
+        // allocation.
+        let mut bits = vec![];
+        write_gamma_bits((1_u64 << 60) + 1, UnaryConvention::default(), &mut bits);
+        write_gamma_bits(3_u64, UnaryConvention::default(), &mut bits);
+        bits.extend([true, true]);
+        let mut writer = BitWriter::new(vec![], true);
+        writer.write_bits(&bits).unwrap();
+        let mut dec = PositionsDecoder::new(Cursor::new(writer.finalize().unwrap()));
+        assert_eq!(
+            dec.next_document::<u64>(),
+            Err(InvalidCodeError::PositionsCodeError)
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::code::{DecodePrefix, Decoder, Encoder, NonBlockingEncoder};
+use crate::error::{CancelledError, InvalidCodeError};
+use crate::io::read::{BitCursor, BitReader};
+use crate::num::Numeric;
+
+/// The progress of a long encoding or decoding, that is passed to its
+/// progress callback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    values: u64,
+    bytes: u64,
+}
+
+impl Progress {
+    /// Returns the number of values encoded or decoded so far.
+    #[inline]
+    pub fn values(&self) -> u64 {
+        self.values
+    }
+
+    /// Returns the number of bytes written by the encoder, or consumed by the
+    /// decoder, so far.
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+/// A writer that counts the bytes written to it, for a [`ProgressEncoder`].
+pub struct CountingWriter<W> {
+    inner: W,
+    bytes: Arc<AtomicU64>,
+}
+
+impl<W> CountingWriter<W> {
+    /// Consumes the `CountingWriter`, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A structure that wraps an encoder and reports its progress to a callback,
+/// every time a number of values has been encoded, so that a long encoding
+/// can be monitored, and cancelled.
+///
+/// The callback receives the number of values encoded and of bytes written so
+/// far. Most encoders buffer their bits, and write them when a segment is
+/// flushed or when they are finalized, so the bytes grow in steps. The
+/// callback is called once more when the encoder is finalized, with the totals.
+///
+/// The callback cancels the encoding by returning [`ControlFlow::Break`], in
+/// which case [`Encoder::encode`] returns an error that wraps a
+/// [`CancelledError`], after encoding the values up to the cancellation.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::ops::ControlFlow;
+/// use idencode::{Encoder, GammaEncoder, ProgressEncoder};
+///
+/// let mut reports = vec![];
+/// let callback = |progress: idencode::Progress| {
+///     reports.push(progress.values());
+///     ControlFlow::Continue(())
+/// };
+/// let mut enc = ProgressEncoder::new(Cursor::new(vec![]), 100, callback, GammaEncoder::new);
+/// enc.encode(&vec![7_u32; 250]).unwrap();
+/// enc.finalize().unwrap();
+/// assert_eq!(reports, vec![100, 200, 250]);
+/// ```
+pub struct ProgressEncoder<E, F> {
+    inner: E,
+    bytes: Arc<AtomicU64>,
+    values: u64,
+    every: u64,
+    callback: F,
+}
+
+impl<E, F: FnMut(Progress) -> ControlFlow<()>> ProgressEncoder<E, F> {
+    /// Creates a new encoder with the encoder that `new` creates over the
+    /// writer, that calls the callback every `every` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is 0.
+    pub fn new<W: Write>(
+        writer: W,
+        every: u64,
+        callback: F,
+        new: impl FnOnce(CountingWriter<W>) -> E,
+    ) -> Self {
+        assert!(
+            every > 0,
+            "The callback must be called every 1 or more values."
+        );
+        let bytes = Arc::new(AtomicU64::new(0));
+        let writer = CountingWriter {
+            inner: writer,
+            bytes: Arc::clone(&bytes),
+        };
+        ProgressEncoder {
+            inner: new(writer),
+            bytes,
+            values: 0,
+            every,
+            callback,
+        }
+    }
+
+    /// Returns the progress of the encoding so far.
+    pub fn progress(&self) -> Progress {
+        Progress {
+            values: self.values,
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<W, E, F> Encoder<W> for ProgressEncoder<E, F>
+where
+    W: Write,
+    E: Encoder<CountingWriter<W>>,
+    F: FnMut(Progress) -> ControlFlow<()>,
+{
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut nums = nums;
+        while !nums.is_empty() {
+            let until_report = self.every - self.values % self.every;
+            let n = nums
+                .len()
+                .min(usize::try_from(until_report).unwrap_or(usize::MAX));
+            self.inner.encode(&nums[..n])?;
+            self.values += n as u64;
+            nums = &nums[n..];
+            if self.values.is_multiple_of(self.every) {
+                let progress = self.progress();
+                if (self.callback)(progress).is_break() {
+                    return Err(io::Error::other(CancelledError));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<W> {
+        let writer = self.inner.finalize()?;
+        let progress = Progress {
+            values: self.values,
+            bytes: self.bytes.load(Ordering::Relaxed),
+        };
+        // The encoding is complete, so it can no longer be cancelled.
+        let _ = (self.callback)(progress);
+        Ok(writer.into_inner())
+    }
+}
+
+impl<W, E, F> NonBlockingEncoder<W> for ProgressEncoder<E, F>
+where
+    W: Write,
+    E: NonBlockingEncoder<CountingWriter<W>>,
+    F: FnMut(Progress) -> ControlFlow<()>,
+{
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+/// A structure that decodes a stream of a prefix code one value at a time,
+/// and reports its progress to a callback every time a number of values has
+/// been decoded, so that a long decoding can be monitored, and cancelled.
+///
+/// The code is any code whose decoder implements [`DecodePrefix`], like
+/// [`GammaDecoder<()>`](crate::GammaDecoder), with its default parameters and
+/// a terminating bit. The callback receives the number of values decoded, and
+/// of bytes consumed, so far.
+///
+/// The callback cancels the decoding by returning [`ControlFlow::Break`], in
+/// which case [`Decoder::decode`] returns [`InvalidCodeError::CancelledError`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::ops::ControlFlow;
+/// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder, ProgressDecoder};
+///
+/// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&vec![7_u32; 1000]).unwrap();
+/// let bytes = enc.finalize().unwrap().into_inner();
+///
+/// // Cancel the decoding once 300 bytes have been consumed.
+/// let callback = |progress: idencode::Progress| match progress.bytes() < 300 {
+///     true => ControlFlow::Continue(()),
+///     false => ControlFlow::Break(()),
+/// };
+/// let dec = ProgressDecoder::<GammaDecoder<()>, _, _>::new(Cursor::new(bytes), 100, callback);
+/// assert!(dec.decode::<u32>().is_err());
+/// ```
+pub struct ProgressDecoder<C, R, F> {
+    cursor: BitCursor<R>,
+    every: u64,
+    callback: F,
+    code: PhantomData<C>,
+}
+
+impl<C: DecodePrefix, R: Read, F: FnMut(Progress) -> ControlFlow<()>> ProgressDecoder<C, R, F> {
+    /// Creates a new decoder over the reader, that calls the callback every
+    /// `every` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is 0.
+    pub fn new(reader: R, every: u64, callback: F) -> Self {
+        assert!(
+            every > 0,
+            "The callback must be called every 1 or more values."
+        );
+        ProgressDecoder {
+            cursor: BitCursor::new(BitReader::new(reader, true)),
+            every,
+            callback,
+            code: PhantomData,
+        }
+    }
+}
+
+impl<C, R, F> Decoder<R> for ProgressDecoder<C, R, F>
+where
+    C: DecodePrefix,
+    R: Read,
+    F: FnMut(Progress) -> ControlFlow<()>,
+{
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            nums.push(self.cursor.decode_next::<C, T>()?);
+            if (nums.len() as u64).is_multiple_of(self.every) {
+                let progress = Progress {
+                    values: nums.len() as u64,
+                    bytes: self.cursor.position().div_ceil(8) as u64,
+                };
+                if (self.callback)(progress).is_break() {
+                    return Err(InvalidCodeError::CancelledError);
+                }
+            }
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeltaDecoder, DeltaEncoder, VBEncoder};
+    use std::io::Cursor;
+
+    fn nums() -> Vec<u64> {
+        (1..=1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 44)
+            .collect()
+    }
+
+    #[test]
+    fn test_encoder_reports() {
+        let mut reports = vec![];
+        let callback = |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        };
+        let mut enc = ProgressEncoder::new(vec![], 300, callback, VBEncoder::new);
+        let nums = nums();
+        // The reports do not depend on how the values are split in calls.
+        enc.encode(&nums[..250]).unwrap();
+        enc.encode(&nums[250..]).unwrap();
+        assert_eq!(enc.progress().values(), 1000);
+        let bytes = enc.finalize().unwrap();
+
+        let mut expected = VBEncoder::new(vec![]);
+        expected.encode(&nums).unwrap();
+        assert_eq!(bytes, expected.finalize().unwrap());
+        let values: Vec<u64> = reports.iter().map(Progress::values).collect();
+        assert_eq!(values, vec![300, 600, 900, 1000]);
+        assert_eq!(reports.last().unwrap().bytes(), bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_encoder_cancellation() {
+        let callback = |progress: Progress| match progress.values() < 200 {
+            true => ControlFlow::Continue(()),
+            false => ControlFlow::Break(()),
+        };
+        let mut enc = ProgressEncoder::new(vec![], 100, callback, DeltaEncoder::new);
+        let err = enc.encode(&nums()).unwrap_err();
+        assert!(err.get_ref().unwrap().is::<CancelledError>());
+        assert_eq!(enc.progress().values(), 200);
+    }
+
+    #[test]
+    fn test_decoder_reports() {
+        let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums()).unwrap();
+        let bytes = enc.finalize().unwrap().into_inner();
+
+        let mut reports = vec![];
+        let callback = |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        };
+        let dec =
+            ProgressDecoder::<DeltaDecoder<()>, _, _>::new(Cursor::new(&bytes), 250, callback);
+        assert_eq!(dec.decode::<u64>().unwrap(), nums());
+        let values: Vec<u64> = reports.iter().map(Progress::values).collect();
+        assert_eq!(values, vec![250, 500, 750, 1000]);
+        assert!(reports.windows(2).all(|w| w[0].bytes() < w[1].bytes()));
+        assert_eq!(reports[3].bytes(), bytes.len() as u64);
+
+        let callback = |_| ControlFlow::Break(());
+        let dec =
+            ProgressDecoder::<DeltaDecoder<()>, _, _>::new(Cursor::new(&bytes), 250, callback);
+        assert_eq!(dec.decode::<u64>(), Err(InvalidCodeError::CancelledError));
+    }
+}
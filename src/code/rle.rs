@@ -0,0 +1,273 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::delta::{decode_delta_prefix, write_delta_bits};
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The default smallest number of repetitions that are encoded as a run.
+const DEFAULT_MIN_RUN: usize = 3;
+
+/// A structure that wraps a writer and encodes a sequence of integers,
+/// replacing runs of repeated values with (value, run-length) pairs.
+///
+/// Runs of at least `min_run` equal values are written as a 1-bit, followed
+/// by the length of the run, minus `min_run - 1`, in Elias Gamma Encoding,
+/// and the value. Shorter runs are written value by value, each as a 0-bit
+/// followed by the value. Values are incremented by one and written in Elias
+/// Delta Encoding, so they must be less than `u64::MAX`. The smallest length
+/// of a run is written in Elias Gamma Encoding in the header of the stream.
+///
+/// Columns of statuses or categories, that are mostly constant, take a few
+/// bits per run, instead of a codeword per repetition, while the threshold
+/// keeps values that rarely repeat from paying for a run length.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, RleDecoder, RleEncoder};
+///
+/// let statuses = [200_u16, 200, 200, 200, 200, 404, 200, 200, 200, 200];
+/// let mut enc = RleEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&statuses[..3]).unwrap();
+/// enc.encode(&statuses[3..]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert!(result.len() < 10);
+///
+/// let dec = RleDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u16>().unwrap(), statuses);
+/// ```
+pub struct RleEncoder<W> {
+    writer: BitWriter<W>,
+    min_run: usize,
+    run: Option<(u64, usize)>,
+}
+
+impl<W: Write> RleEncoder<W> {
+    /// Creates a new encoder, that encodes runs of at least three values.
+    pub fn new(writer: W) -> Self {
+        Self::with_min_run(writer, DEFAULT_MIN_RUN)
+    }
+
+    /// Creates a new encoder, that encodes runs of at least `min_run` values,
+    /// and writes the threshold in the header of the stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_run` is less than two.
+    pub fn with_min_run(writer: W, min_run: usize) -> Self {
+        assert!(min_run >= 2, "Runs must have at least two values.");
+        let mut writer = BitWriter::new(writer, true);
+        let mut header = vec![];
+        write_gamma_bits(min_run, UnaryConvention::default(), &mut header);
+        for bit in header {
+            writer.get_mut().push(bit);
+        }
+        RleEncoder {
+            writer,
+            min_run,
+            run: None,
+        }
+    }
+
+    /// Returns the smallest number of repetitions that are encoded as a run.
+    pub fn min_run(&self) -> usize {
+        self.min_run
+    }
+
+    /// Writes the current run, if any.
+    fn flush_run(&mut self) -> io::Result<()> {
+        let Some((value, len)) = self.run.take() else {
+            return Ok(());
+        };
+        let convention = UnaryConvention::default();
+        let mut bits = vec![];
+        if len >= self.min_run {
+            bits.push(true);
+            write_gamma_bits(len - self.min_run + 1, convention, &mut bits);
+            write_delta_bits(value + 1, convention, &mut bits);
+        } else {
+            for _ in 0..len {
+                bits.push(false);
+                write_delta_bits(value + 1, convention, &mut bits);
+            }
+        }
+        self.writer.write_bits(&bits)
+    }
+}
+
+impl<W: Write> Encoder<W> for RleEncoder<W> {
+    /// Encodes and writes a sequence of numbers. Runs may continue across
+    /// calls, so the last run is only written when the encoder is finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is `u64::MAX`.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            let num = num.to_u64();
+            if num == u64::MAX {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Number out of the range of the run-length code.",
+                ));
+            }
+            match &mut self.run {
+                Some((value, len)) if *value == num => *len += 1,
+                _ => {
+                    self.flush_run()?;
+                    self.run = Some((num, 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<W> {
+        self.flush_run()?;
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for RleEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush_run()?;
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`RleEncoder`].
+///
+/// The smallest length of a run is read from the header of the stream.
+pub struct RleDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> RleDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        RleDecoder { cursor }
+    }
+}
+
+/// Decodes the run or the single value at the start of a buffer of bits,
+/// returning the value and its number of repetitions, along with the number
+/// of bits they occupy.
+fn decode_record(bits: &[bool], min_run: usize) -> Result<((u64, usize), usize), InvalidCodeError> {
+    let convention = UnaryConvention::default();
+    let is_run = *bits.first().ok_or(InvalidCodeError::RleCodeError)?;
+    let mut pos = 1;
+    let mut len = 1;
+    if is_run {
+        let (count, count_len) = decode_gamma_prefix::<usize>(&bits[pos..], convention)
+            .map_err(|_| InvalidCodeError::RleCodeError)?;
+        len = count
+            .checked_add(min_run - 1)
+            .ok_or(InvalidCodeError::RleCodeError)?;
+        pos += count_len;
+    }
+    let (value, value_len) = decode_delta_prefix::<u64>(&bits[pos..], convention)
+        .map_err(|_| InvalidCodeError::RleCodeError)?;
+    Ok(((value - 1, len), pos + value_len))
+}
+
+impl<R: Read> Decoder<R> for RleDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let min_run: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, UnaryConvention::default()))
+            .map_err(|_| InvalidCodeError::RleCodeError)?;
+        if min_run < 2 {
+            return Err(InvalidCodeError::RleCodeError);
+        }
+
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let (value, len) = self
+                .cursor
+                .decode_with(|bits| decode_record(bits, min_run))?;
+            let value = T::from_u64(value).ok_or(InvalidCodeError::RleCodeError)?;
+            nums.extend(std::iter::repeat_n(value, len));
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], min_run: usize) -> Vec<u8> {
+        let mut enc = RleEncoder::with_min_run(Cursor::new(vec![]), min_run);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Vec<u64> {
+        RleDecoder::new(Cursor::new(bytes)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_runs() {
+        let nums = vec![0; 1_000_000];
+        let result = encode(&nums, 3);
+        assert!(result.len() <= 6);
+        assert_eq!(decode(result), nums);
+
+        // Below the threshold, values are written one by one.
+        assert_eq!(encode(&[7, 7], 3).len(), encode(&[7, 8], 3).len());
+        assert!(encode(&[7, 7, 7, 7], 3).len() < encode(&[7, 8, 7, 8], 3).len());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = (0..2000).map(|i| (i / 7) % 3).collect();
+        nums.extend([1, 2, 3, u64::MAX - 1, u64::MAX - 1, 0]);
+        for min_run in [2, 3, 10, 100] {
+            assert_eq!(decode(encode(&nums, min_run)), nums);
+        }
+        assert!(decode(encode(&[], 3)).is_empty());
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = RleEncoder::new(Cursor::new(vec![]));
+        assert!(enc.encode(&[u64::MAX]).is_err());
+
+        let dec = RleDecoder::new(Cursor::new(vec![0x01]));
+        assert!(dec.decode::<u64>().is_err());
+        let dec = RleDecoder::new(Cursor::new(encode(&[300, 300, 300], 2)));
+        assert!(dec.decode::<u8>().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_min_run() {
+        RleEncoder::with_min_run(Cursor::new(vec![]), 1);
+    }
+
+    #[test]
+    fn test_finish() {
+        let nums = [4_u64, 4, 4, 4, 9, 9, 1];
+        let mut enc = RleEncoder::with_min_run(Cursor::new(vec![]), 3);
+        enc.encode(&nums).unwrap();
+        // The pending run is written once, however many times it is finished.
+        enc.finish().unwrap();
+        enc.finish().unwrap();
+        assert_eq!(enc.into_inner().into_inner(), encode(&nums, 3));
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::delta::{decode_delta_prefix, write_delta_bits};
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::{read_minimal_binary, write_minimal_binary};
+use crate::num::Numeric;
+
+/// A structure that wraps a writer and encodes a strictly increasing sequence
+/// of integers using Binary Interpolative Coding.
+///
+/// The number of integers is written in Elias Gamma Encoding, incremented by
+/// one, and the last integer in Elias Delta Encoding, incremented by one, so
+/// integers must be less than `u64::MAX`. Then, the middle integer of the
+/// sequence is written in minimal binary for the range of values it can take
+/// between the bounds of the sequence, leaving room for the integers on
+/// either side of it, and the two halves are written recursively with the
+/// middle integer as one of their bounds. The integers of a dense cluster
+/// have narrow ranges, and the ranges of runs of consecutive integers are
+/// empty, so they take no bits at all.
+///
+/// The integers are buffered, and encoded when the encoder is finalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, InterpolativeDecoder, InterpolativeEncoder};
+///
+/// let mut enc = InterpolativeEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[0_u32, 1, 2, 3, 4]).unwrap();
+/// enc.encode(&[5_u32]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// // The count, 110 11, and the last integer, 101 10, leave no room for the
+/// // rest of the integers, which take no bits.
+/// assert_eq!(result, vec![0b11011101, 0b10100000]);
+///
+/// let dec = InterpolativeDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![0, 1, 2, 3, 4, 5]);
+/// ```
+pub struct InterpolativeEncoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> InterpolativeEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        InterpolativeEncoder {
+            writer,
+            nums: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for InterpolativeEncoder<W> {
+    /// Buffers a sequence of numbers, which continues the numbers buffered
+    /// so far.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the
+    /// numbers are not strictly increasing, or a number is `u64::MAX`.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        for num in nums {
+            let num = num.to_u64();
+            if num == u64::MAX || self.nums.last().is_some_and(|prev| *prev >= num) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Numbers are not strictly increasing.",
+                ));
+            }
+            self.nums.push(num);
+        }
+        Ok(())
+    }
+
+    /// Encodes the buffered numbers and writes them.
+    fn finalize(mut self) -> io::Result<W> {
+        let convention = UnaryConvention::default();
+        let mut bits = vec![];
+        write_gamma_bits(self.nums.len() + 1, convention, &mut bits);
+        if let Some(last) = self.nums.last() {
+            write_delta_bits(last + 1, convention, &mut bits);
+            let rest = &self.nums[..self.nums.len() - 1];
+            write_interpolative_bits(rest, 0, u128::from(*last).saturating_sub(1), &mut bits);
+        }
+        self.writer.write_bits(&bits)?;
+        self.writer.finalize()
+    }
+}
+
+/// Calculates the interpolative codes of strictly increasing numbers, all of
+/// which are between `low` and `high`, inclusive.
+fn write_interpolative_bits(nums: &[u64], low: u128, high: u128, buffer: &mut Vec<bool>) {
+    if nums.is_empty() {
+        return;
+    }
+    let mid = nums.len() / 2;
+    let (start, end) = (low + mid as u128, high - (nums.len() - 1 - mid) as u128);
+    let num = u128::from(nums[mid]);
+    write_minimal_binary(num - start, end - start + 1, buffer);
+    write_interpolative_bits(&nums[..mid], low, num.saturating_sub(1), buffer);
+    write_interpolative_bits(&nums[mid + 1..], num + 1, high, buffer);
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`InterpolativeEncoder`].
+pub struct InterpolativeDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> InterpolativeDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        InterpolativeDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for InterpolativeDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let err = || InvalidCodeError::InterpolativeCodeError;
+        let convention = UnaryConvention::default();
+        let nums = self.cursor.decode_with(|bits| {
+            let (count, mut pos) =
+                decode_gamma_prefix::<u64>(bits, convention).map_err(|_| err())?;
+            if count == 1 {
+                return Ok((vec![], pos));
+            }
+            let (last, len) =
+                decode_delta_prefix::<u64>(&bits[pos..], convention).map_err(|_| err())?;
+            pos += len;
+            let last = last - 1;
+            // The numbers before the last one take distinct values below it.
+            let count = usize::try_from(count - 2)
+                .ok()
+                .filter(|count| (*count as u64) <= last)
+                .ok_or_else(err)?;
+
+            let mut nums = Vec::with_capacity(count.min(bits.len()) + 1);
+            let high = u128::from(last).saturating_sub(1);
+            pos += decode_interpolative_prefix(&bits[pos..], count, 0, high, &mut nums)?;
+            nums.push(last);
+            Ok((nums, pos))
+        })?;
+        if !self.cursor.remaining()?.is_empty() {
+            return Err(err());
+        }
+
+        nums.into_iter()
+            .map(|num| T::from_u64(num).ok_or_else(err))
+            .collect()
+    }
+}
+
+/// Decodes the interpolative codes of `count` numbers between `low` and
+/// `high`, inclusive, at the start of a buffer of bits, appending them to
+/// `nums` and returning the number of bits their codes occupy.
+fn decode_interpolative_prefix(
+    bits: &[bool],
+    count: usize,
+    low: u128,
+    high: u128,
+    nums: &mut Vec<u64>,
+) -> Result<usize, InvalidCodeError> {
+    if count == 0 {
+        return Ok(0);
+    }
+    let mid = count / 2;
+    let (start, end) = (low + mid as u128, high - (count - 1 - mid) as u128);
+    let (offset, mut pos) = read_minimal_binary(bits, end - start + 1)
+        .ok_or(InvalidCodeError::InterpolativeCodeError)?;
+    let num = start + offset;
+    pos += decode_interpolative_prefix(&bits[pos..], mid, low, num.saturating_sub(1), nums)?;
+    nums.push(num as u64);
+    pos += decode_interpolative_prefix(&bits[pos..], count - 1 - mid, num + 1, high, nums)?;
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = InterpolativeEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        InterpolativeDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_write_bits() {
+        let mut bits = vec![];
+        // 5 is the 4th of 9 values, 100, and then 2 and 8 are the 3rd of 5, 10.
+        write_interpolative_bits(&[2, 5, 8], 0, 10, &mut bits);
+        let expected = [true, false, false, true, false, true, false];
+        assert_eq!(bits, expected);
+
+        let mut nums = vec![];
+        assert_eq!(
+            decode_interpolative_prefix(&bits, 3, 0, 10, &mut nums),
+            Ok(7)
+        );
+        assert_eq!(nums, vec![2, 5, 8]);
+        assert!(decode_interpolative_prefix(&bits[..6], 3, 0, 10, &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums = vec![0_u64];
+        for i in 1..1000_u64 {
+            let gap = (i.wrapping_mul(0x9E3779B97F4A7C15) >> (40 + i % 24)).max(1);
+            nums.push(nums[nums.len() - 1] + gap);
+        }
+        nums.extend([u64::MAX - 2, u64::MAX - 1]);
+        assert_eq!(decode(encode(&nums)).unwrap(), nums);
+        assert_eq!(decode(encode(&[])).unwrap(), vec![]);
+        assert_eq!(decode(encode(&[0])).unwrap(), vec![0]);
+
+        // A dense cluster takes far fewer bits than its gaps in Elias Gamma.
+        let cluster: Vec<u64> = (1000..2000).filter(|n| n % 7 != 0).collect();
+        assert!(encode(&cluster).len() < cluster.len() / 8);
+    }
+
+    #[test]
+    fn test_unsorted() {
+        let mut enc = InterpolativeEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[1_u32, 5]).unwrap();
+        assert!(enc.encode(&[5_u32]).is_err());
+        assert!(enc.encode(&[u64::MAX]).is_err());
+        assert!(InterpolativeEncoder::new(Cursor::new(vec![]))
+            .encode(&[3_u32, 2])
+            .is_err());
+    }
+
+    #[test]
+    fn test_errs() {
+        // Three distinct numbers cannot end at 1.
+        let mut bits = vec![];
+        write_gamma_bits(4_u8, UnaryConvention::default(), &mut bits);
+        write_delta_bits(2_u8, UnaryConvention::default(), &mut bits);
+        let mut bytes = vec![];
+        for chunk in bits.chunks(8) {
+            let byte = chunk
+                .iter()
+                .fold(0_u8, |acc, bit| (acc << 1) | u8::from(*bit));
+            bytes.push(byte << (8 - chunk.len()));
+        }
+        bytes[bits.len() / 8] |= 0x80 >> (bits.len() % 8);
+        assert_eq!(decode(bytes), Err(InvalidCodeError::InterpolativeCodeError));
+
+        let dec = InterpolativeDecoder::new(Cursor::new(encode(&[1, 300])));
+        assert!(dec.decode::<u8>().is_err());
+        let bytes = encode(&(0..50_u64).map(|n| n * 1000).collect::<Vec<_>>());
+        assert!(decode(bytes[..bytes.len() - 2].to_vec()).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+use std::io::Write;
+
+use crate::code::adaptive::AutoEncoder;
+use crate::code::byte::chunk::ChunkEncoder;
+use crate::code::byte::nibble::NibbleEncoder;
+use crate::code::byte::prefix_varint::PrefixVarintEncoder;
+use crate::code::byte::sqlite_varint::SqliteVarintEncoder;
+use crate::code::byte::stream_vbyte::StreamVByteEncoder;
+use crate::code::byte::vargroup::VarGroupEncoder;
+use crate::code::byte::vb16::VB16Encoder;
+use crate::code::entropy::arith::ArithEncoder;
+use crate::code::entropy::huffman::HuffmanEncoder;
+use crate::code::entropy::range::RangeEncoder;
+use crate::code::entropy::rans::RansEncoder;
+use crate::code::global::basc::BascEncoder;
+use crate::code::global::delta::DeltaEncoder;
+use crate::code::global::escaped_rice::EscapedRiceEncoder;
+use crate::code::global::gamma::GammaEncoder;
+use crate::code::global::gamma_prime::GammaPrimeEncoder;
+use crate::code::global::golomb::GolombEncoder;
+use crate::code::global::hybrid::HybridEncoder;
+use crate::code::global::minimal_binary::MinimalBinaryEncoder;
+use crate::code::global::omega::OmegaEncoder;
+use crate::code::global::rice::RiceEncoder;
+use crate::code::global::sss::StartStepStopEncoder;
+use crate::code::global::unary::UnaryEncoder;
+use crate::code::global::vb::VBEncoder;
+use crate::code::global::zeta::ZetaEncoder;
+use crate::code::packed::bitpack::BitPackEncoder;
+use crate::code::packed::bp128::BP128Encoder;
+use crate::code::packed::pfor::PForEncoder;
+use crate::code::packed::signed::SignedPackEncoder;
+use crate::code::packed::simple::SimpleEncoder;
+use crate::code::packed::simple16::Simple16Encoder;
+use crate::code::packed::simple9::Simple9Encoder;
+use crate::code::positions::PositionsEncoder;
+use crate::code::rle::RleEncoder;
+use crate::code::sorted::interpolative::InterpolativeEncoder;
+use crate::code::split::{SplitCode, SplitEncoder};
+use crate::code::sprintz::SprintzEncoder;
+use crate::code::tagged::TaggedEncoder;
+use crate::code::timestamp::TimestampEncoder;
+
+/// The properties of a codec, that tooling can query at runtime to pick a
+/// codec for a column, or to reject input that a codec cannot encode before
+/// encoding it.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{CodecSpec, DescribeCodec, GammaEncoder, VBEncoder};
+///
+/// let spec: CodecSpec = GammaEncoder::new(Cursor::new(vec![])).spec();
+/// assert_eq!(spec.name(), "Elias Gamma");
+/// assert!(!spec.encodes_zero());
+/// assert!(spec.prefix_free());
+/// assert!(!spec.byte_aligned());
+///
+/// let spec = VBEncoder::new(Cursor::new(vec![])).spec();
+/// assert!(spec.byte_aligned());
+/// assert_eq!(spec.max_value_for(8), 255);
+/// assert!(spec.can_encode(0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodecSpec {
+    name: &'static str,
+    encodes_zero: bool,
+    max_value: u64,
+    prefix_free: bool,
+    byte_aligned: bool,
+    requires_sorted: bool,
+}
+
+impl CodecSpec {
+    /// Returns the name of the codec.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns `true` if the codec can encode the number 0.
+    #[inline]
+    pub fn encodes_zero(&self) -> bool {
+        self.encodes_zero
+    }
+
+    /// Returns the largest number that the codec can encode.
+    #[inline]
+    pub fn max_value(&self) -> u64 {
+        self.max_value
+    }
+
+    /// Returns the largest number of `width` bits that the codec can encode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 64.
+    pub fn max_value_for(&self, width: u32) -> u64 {
+        assert!(width <= u64::BITS, "The width must be at most 64 bits.");
+        let max = u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0);
+        max.min(self.max_value)
+    }
+
+    /// Returns `true` if each number is encoded as a self-delimiting codeword,
+    /// that can be decoded without the context of the rest of the stream.
+    #[inline]
+    pub fn prefix_free(&self) -> bool {
+        self.prefix_free
+    }
+
+    /// Returns `true` if the code of each number, or block of numbers, takes
+    /// a whole number of bytes.
+    #[inline]
+    pub fn byte_aligned(&self) -> bool {
+        self.byte_aligned
+    }
+
+    /// Returns `true` if the codec only accepts sorted input.
+    #[inline]
+    pub fn requires_sorted(&self) -> bool {
+        self.requires_sorted
+    }
+
+    /// Returns `true` if the codec can encode a number, on its own.
+    ///
+    /// Codecs that require sorted input may still reject a sequence of
+    /// numbers that can each be encoded.
+    pub fn can_encode(&self, num: u64) -> bool {
+        (num != 0 || self.encodes_zero) && num <= self.max_value
+    }
+}
+
+/// Describes the properties of a codec with a [`CodecSpec`].
+pub trait DescribeCodec {
+    /// Returns the properties of the codec, with its parameters.
+    fn spec(&self) -> CodecSpec;
+}
+
+/// The properties of a bit-level code, that encodes each number as a
+/// self-delimiting codeword.
+const fn codeword(name: &'static str, encodes_zero: bool, max_value: u64) -> CodecSpec {
+    CodecSpec {
+        name,
+        encodes_zero,
+        max_value,
+        prefix_free: true,
+        byte_aligned: false,
+        requires_sorted: false,
+    }
+}
+
+/// The properties of a code that encodes blocks, or the whole sequence, of
+/// numbers at once.
+const fn block(name: &'static str, max_value: u64, byte_aligned: bool) -> CodecSpec {
+    CodecSpec {
+        name,
+        encodes_zero: true,
+        max_value,
+        prefix_free: false,
+        byte_aligned,
+        requires_sorted: false,
+    }
+}
+
+impl<W: Write> DescribeCodec for UnaryEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Unary", true, usize::MAX as u64)
+    }
+}
+
+impl<W: Write> DescribeCodec for GammaEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Elias Gamma", false, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for GammaPrimeEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Elias Gamma'", false, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for DeltaEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Elias Delta", false, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for OmegaEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Elias Omega", false, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for ZetaEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Zeta", false, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for EscapedRiceEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Escaped Rice", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for HybridEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Hybrid", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for GolombEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Golomb", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for MinimalBinaryEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Minimal Binary", true, self.universe() - 1)
+    }
+}
+
+impl<W: Write> DescribeCodec for RiceEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Rice", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for StartStepStopEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Start-Step-Stop", true, self.max_value())
+    }
+}
+
+impl<W: Write> DescribeCodec for VBEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            byte_aligned: true,
+            ..codeword("Variable Byte", true, u64::MAX)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for VB16Encoder<W> {
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            byte_aligned: true,
+            ..codeword("Variable Word", true, u64::MAX)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for NibbleEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Nibble", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for VarGroupEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        codeword("Variable Group", true, u64::MAX)
+    }
+}
+
+impl<W: Write> DescribeCodec for PrefixVarintEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            byte_aligned: true,
+            ..codeword("Prefix Varint", true, u64::MAX)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for SqliteVarintEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            byte_aligned: true,
+            ..codeword("SQLite Varint", true, u64::MAX)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for StreamVByteEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Stream VByte", u32::MAX.into(), true)
+    }
+}
+
+impl<W: Write> DescribeCodec for ChunkEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            byte_aligned: true,
+            ..codeword("Chunk", true, u64::MAX)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for BascEncoder<W> {
+    /// A BASC code depends on the width of the previous number, so it is not
+    /// prefix-free.
+    fn spec(&self) -> CodecSpec {
+        block("BASC", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for BP128Encoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("BP128", u32::MAX.into(), true)
+    }
+}
+
+impl<W: Write> DescribeCodec for PForEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("PFor", u64::MAX, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for SignedPackEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Signed Packing", u64::MAX, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for AutoEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Adaptive", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for BitPackEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        let max = u64::MAX.checked_shr(u64::BITS - self.width()).unwrap_or(0);
+        block("Bit Packing", max, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for SimpleEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        let cases = self.table().cases();
+        let width = cases.iter().flatten().map(|c| c.1).max().unwrap_or(0);
+        let max = u64::MAX.checked_shr(u64::BITS - width).unwrap_or(0);
+        block("Simple", max, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for Simple9Encoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Simple-9", (1 << 28) - 1, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for Simple16Encoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Simple-16", (1 << 28) - 1, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for SprintzEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Sprintz", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for RleEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Run-Length", u64::MAX - 1, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for TimestampEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Timestamp", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for TaggedEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Tagged", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for SplitEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        let encodes_zero = matches!(self.code(), SplitCode::Rice(_));
+        CodecSpec {
+            encodes_zero,
+            ..block("Split", u64::MAX, false)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for PositionsEncoder<W> {
+    /// The first position of a document is encoded incremented by one, so
+    /// the largest position cannot start a document.
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            requires_sorted: true,
+            ..block("Positions", u64::MAX - 1, false)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for InterpolativeEncoder<W> {
+    /// The last number is encoded incremented by one, so it cannot be the
+    /// largest number.
+    fn spec(&self) -> CodecSpec {
+        CodecSpec {
+            requires_sorted: true,
+            ..block("Binary Interpolative", u64::MAX - 1, false)
+        }
+    }
+}
+
+impl<W: Write> DescribeCodec for HuffmanEncoder<W> {
+    /// The first number of the table is encoded incremented by one, so it
+    /// cannot be the largest number.
+    fn spec(&self) -> CodecSpec {
+        block("Huffman", u64::MAX - 1, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for ArithEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Adaptive Arithmetic", u64::MAX, false)
+    }
+}
+
+impl<W: Write> DescribeCodec for RangeEncoder<W> {
+    fn spec(&self) -> CodecSpec {
+        block("Adaptive Range", u64::MAX, true)
+    }
+}
+
+impl<W: Write> DescribeCodec for RansEncoder<W> {
+    /// The first number of the table is encoded incremented by one, so it
+    /// cannot be the largest number.
+    fn spec(&self) -> CodecSpec {
+        block("rANS", u64::MAX - 1, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code::Encoder;
+    use crate::SelectorTable;
+    use std::io::Cursor;
+
+    // Returns whether an encoder accepts a number.
+    fn accepts<E: Encoder<Cursor<Vec<u8>>>>(mut enc: E, num: u64) -> bool {
+        enc.encode(&[num]).is_ok() && enc.finalize().is_ok()
+    }
+
+    #[test]
+    fn test_max_value_for() {
+        let spec = BP128Encoder::new(Cursor::new(vec![])).spec();
+        assert_eq!(spec.max_value_for(0), 0);
+        assert_eq!(spec.max_value_for(16), u16::MAX.into());
+        assert_eq!(spec.max_value_for(64), u32::MAX.into());
+        assert!(spec.can_encode(u32::MAX.into()));
+        assert!(!spec.can_encode(u64::from(u32::MAX) + 1));
+    }
+
+    #[test]
+    fn test_specs_match_encoders() {
+        let new = || Cursor::new(vec![]);
+        let spec = GammaEncoder::new(new()).spec();
+        assert!(!spec.can_encode(0) && spec.can_encode(u64::MAX));
+        let spec = RleEncoder::new(new()).spec();
+        assert!(!spec.can_encode(u64::MAX));
+        assert!(!accepts(RleEncoder::new(new()), u64::MAX));
+        assert!(accepts(RleEncoder::new(new()), spec.max_value()));
+        let spec = BP128Encoder::new(new()).spec();
+        assert!(!accepts(BP128Encoder::new(new()), spec.max_value() + 1));
+        assert!(accepts(BP128Encoder::new(new()), spec.max_value()));
+
+        for code in [SplitCode::Gamma, SplitCode::Rice(3)] {
+            let spec = SplitEncoder::new(new(), code).spec();
+            assert_eq!(
+                spec.can_encode(0),
+                accepts(SplitEncoder::new(new(), code), 0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parameterized_specs() {
+        let new = || Cursor::new(vec![]);
+        // The widths 1, 3 and 5 cover the numbers 0-41.
+        let spec = StartStepStopEncoder::new(new(), 1, 2, 5).spec();
+        assert_eq!(spec.max_value(), 41);
+        assert!(accepts(StartStepStopEncoder::new(new(), 1, 2, 5), 41));
+        assert!(!accepts(StartStepStopEncoder::new(new(), 1, 2, 5), 42));
+        let spec = StartStepStopEncoder::new(new(), 0, 32, 64).spec();
+        assert_eq!(spec.max_value(), u64::MAX);
+
+        let table = SelectorTable::new(32, &[(1, 30), (2, 15), (30, 1)]).unwrap();
+        let spec = SimpleEncoder::new(new(), table).spec();
+        assert_eq!(spec.max_value(), (1 << 30) - 1);
+        assert!(spec.byte_aligned() && !spec.prefix_free());
+    }
+
+    #[test]
+    fn test_requires_sorted() {
+        let spec = PositionsEncoder::new(Cursor::new(vec![])).spec();
+        assert!(spec.requires_sorted());
+        assert!(!VBEncoder::new(Cursor::new(vec![])).spec().requires_sorted());
+        assert!(!UnaryEncoder::new(Cursor::new(vec![])).spec().byte_aligned());
+    }
+}
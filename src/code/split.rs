@@ -0,0 +1,313 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::convert::{write_fixed_bits, write_offset_bits};
+use crate::num::{bits_to_numeric, Numeric};
+
+/// The codes whose control and payload bits a [`SplitEncoder`] separates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitCode {
+    /// Elias Gamma Encoding: the lengths of the offset bits in unary are the
+    /// control bits, and the offset bits are the payload.
+    Gamma,
+    /// Elias Delta Encoding: the lengths of the numbers in Elias Gamma
+    /// Encoding are the control bits, and the offset bits are the payload.
+    Delta,
+    /// Rice Encoding with parameter *k*: the quotients in unary are the
+    /// control bits, and the remainders in *k* bits are the payload.
+    Rice(u32),
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers, writing
+/// the control bits of their codes, which determine the lengths of the codes,
+/// apart from the payload bits.
+///
+/// At finalization, the length of the control bits, incremented by one, is
+/// written in Elias Gamma Encoding, followed by the control bits of all the
+/// numbers and then by their payload bits. The decoder reads all the lengths
+/// from the control bits first, so extracting the payload of each number does
+/// not wait for the length of the previous one to be decoded.
+///
+/// Elias Gamma and Delta codes can only encode positive numbers, while Rice
+/// codes can also encode 0.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, SplitCode, SplitDecoder, SplitEncoder};
+///
+/// let nums = [9_u32, 2, 1, 300];
+/// let mut enc = SplitEncoder::new(Cursor::new(vec![]), SplitCode::Gamma);
+/// enc.encode(&nums).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// // 111100001 | 1110 10 0 111111110 | 001 0 00101100 | 1
+/// assert_eq!(result, vec![0b11110000, 0b11110100, 0b11111111, 0b00010001, 0b01100100]);
+///
+/// let dec = SplitDecoder::new(Cursor::new(result), SplitCode::Gamma);
+/// assert_eq!(dec.decode::<u32>().unwrap(), nums);
+/// ```
+pub struct SplitEncoder<W> {
+    writer: BitWriter<W>,
+    code: SplitCode,
+    control: Vec<bool>,
+    payload: Vec<bool>,
+}
+
+impl<W: Write> SplitEncoder<W> {
+    /// Creates a new encoder that splits the codes of the specified code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Rice parameter is not less than 64.
+    pub fn new(writer: W, code: SplitCode) -> Self {
+        if let SplitCode::Rice(k) = code {
+            assert!(k < u64::BITS, "The Rice parameter must be less than 64.");
+        }
+        let writer = BitWriter::new(writer, true);
+        SplitEncoder {
+            writer,
+            code,
+            control: vec![],
+            payload: vec![],
+        }
+    }
+
+    /// Returns the code whose control and payload bits are split.
+    pub fn code(&self) -> SplitCode {
+        self.code
+    }
+}
+
+impl<W: Write> Encoder<W> for SplitEncoder<W> {
+    /// Encodes the numbers, buffering their control and payload bits until
+    /// the encoder is finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is 0 and the code is Elias Gamma or Delta Encoding.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let convention = UnaryConvention::default();
+        for num in nums {
+            let num = num.to_u64();
+            match self.code {
+                SplitCode::Rice(k) => {
+                    let quotient = usize::try_from(num >> k).map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "Quotient out of range.")
+                    })?;
+                    self.control
+                        .extend(UnaryEncoder::encode_one_with(quotient, convention));
+                    write_fixed_bits(&num, k, &mut self.payload);
+                }
+                _ if num == 0 => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Elias codes can only encode positive numbers.",
+                    ));
+                }
+                SplitCode::Gamma => {
+                    let len = (u64::BITS - num.leading_zeros() - 1) as usize;
+                    self.control
+                        .extend(UnaryEncoder::encode_one_with(len, convention));
+                    write_offset_bits(&num, &mut self.payload);
+                }
+                SplitCode::Delta => {
+                    let len = u64::BITS - num.leading_zeros();
+                    write_gamma_bits(len, convention, &mut self.control);
+                    write_offset_bits(&num, &mut self.payload);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<W> {
+        if !self.control.is_empty() {
+            let mut bits = vec![];
+            write_gamma_bits(
+                self.control.len() + 1,
+                UnaryConvention::default(),
+                &mut bits,
+            );
+            bits.append(&mut self.control);
+            bits.append(&mut self.payload);
+            self.writer.write_bits(&bits)?;
+        }
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`SplitEncoder`].
+///
+/// The lengths of all the codes are decoded from the control bits before any
+/// payload bits are read.
+pub struct SplitDecoder<R> {
+    reader: BitReader<R>,
+    code: SplitCode,
+}
+
+impl<R: Read> SplitDecoder<R> {
+    /// Creates a new decoder for the specified code, which must match the
+    /// encoder's.
+    pub fn new(reader: R, code: SplitCode) -> Self {
+        let reader = BitReader::new(reader, true);
+        SplitDecoder { reader, code }
+    }
+}
+
+/// Decodes the control bits to the lengths of the payloads, along with the
+/// quotients for Rice codes.
+fn decode_control(
+    code: SplitCode,
+    control: &[bool],
+) -> Result<Vec<(usize, u64)>, InvalidCodeError> {
+    let convention = UnaryConvention::default();
+    let mut lengths = vec![];
+    let mut pos = 0;
+    while pos < control.len() {
+        let (length, len) = match code {
+            SplitCode::Gamma => {
+                let (len, len_bits) =
+                    UnaryDecoder::decode_prefix_with(&control[pos..], convention)?;
+                ((len, 1), len_bits)
+            }
+            SplitCode::Delta => {
+                let (len, len_bits) = decode_gamma_prefix::<usize>(&control[pos..], convention)?;
+                ((len - 1, 1), len_bits)
+            }
+            SplitCode::Rice(k) => {
+                let (quotient, len_bits) =
+                    UnaryDecoder::decode_prefix_with(&control[pos..], convention)?;
+                ((k as usize, quotient as u64), len_bits)
+            }
+        };
+        lengths.push(length);
+        pos += len;
+    }
+    Ok(lengths)
+}
+
+impl<R: Read> Decoder<R> for SplitDecoder<R> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let bits = self.reader.read_to_end().unwrap().into_bits();
+        if bits.is_empty() {
+            return Ok(vec![]);
+        }
+        let (control_len, header_len) =
+            decode_gamma_prefix::<usize>(&bits, UnaryConvention::default())
+                .map_err(|_| InvalidCodeError::SplitCodeError)?;
+        let control = bits
+            .get(header_len..header_len + control_len - 1)
+            .ok_or(InvalidCodeError::SplitCodeError)?;
+        let payload = &bits[header_len + control_len - 1..];
+        let lengths =
+            decode_control(self.code, control).map_err(|_| InvalidCodeError::SplitCodeError)?;
+
+        let mut nums = Vec::with_capacity(lengths.len());
+        let mut pos = 0;
+        for (len, high) in lengths {
+            let offset = payload
+                .get(pos..pos + len)
+                .ok_or(InvalidCodeError::SplitCodeError)?;
+            let low: u64 = bits_to_numeric(offset).map_err(|_| InvalidCodeError::SplitCodeError)?;
+            let num = match high.checked_shl(len as u32) {
+                Some(shifted) if shifted >> len == high => shifted | low,
+                _ => return Err(InvalidCodeError::SplitCodeError),
+            };
+            nums.push(T::from_u64(num).ok_or(InvalidCodeError::SplitCodeError)?);
+            pos += len;
+        }
+        if pos != payload.len() {
+            return Err(InvalidCodeError::SplitCodeError);
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeltaEncoder, GammaEncoder};
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64], code: SplitCode) -> Vec<u8> {
+        let mut enc = SplitEncoder::new(Cursor::new(vec![]), code);
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>, code: SplitCode) -> Result<Vec<u64>, InvalidCodeError> {
+        SplitDecoder::new(Cursor::new(bytes), code).decode()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let nums: Vec<u64> = (1..500).map(|i| i * i % 1009 + 1).collect();
+        for code in [
+            SplitCode::Gamma,
+            SplitCode::Delta,
+            SplitCode::Rice(0),
+            SplitCode::Rice(5),
+        ] {
+            assert_eq!(decode(encode(&nums, code), code), Ok(nums.clone()));
+            assert_eq!(decode(encode(&[], code), code), Ok(vec![]));
+        }
+        let big = [u64::MAX, 1 << 63, 1];
+        assert_eq!(
+            decode(encode(&big, SplitCode::Delta), SplitCode::Delta),
+            Ok(big.to_vec())
+        );
+        let zeros = [0_u64, 0, 1 << 20];
+        let rice = SplitCode::Rice(63);
+        assert_eq!(decode(encode(&zeros, rice), rice), Ok(zeros.to_vec()));
+    }
+
+    #[test]
+    fn test_same_size_as_interleaved() {
+        let nums: Vec<u64> = (1..100).map(|i| i * 37).collect();
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        ge.encode(&nums).unwrap();
+        let gamma = ge.finalize().unwrap().into_inner();
+        let mut de = DeltaEncoder::new(Cursor::new(vec![]));
+        de.encode(&nums).unwrap();
+        let delta = de.finalize().unwrap().into_inner();
+
+        // Only the header of the length of the control bits is added.
+        assert!(encode(&nums, SplitCode::Gamma).len() <= gamma.len() + 3);
+        assert!(encode(&nums, SplitCode::Delta).len() <= delta.len() + 3);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = SplitEncoder::new(Cursor::new(vec![]), SplitCode::Gamma);
+        assert!(enc.encode(&[3_u32, 0]).is_err());
+
+        // The payload of the last number is cut short.
+        let result = encode(&[9, 2, 1, 300], SplitCode::Gamma);
+        let reader = BitReader::new(Cursor::new(result), true);
+        let mut bits = reader.read_to_end().unwrap().into_bits();
+        bits.pop();
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        let result = writer.finalize().unwrap().into_inner();
+        assert!(decode(result, SplitCode::Gamma).is_err());
+
+        // A value that does not fit in the decoded type.
+        let rice = SplitCode::Rice(60);
+        let result = encode(&[u64::MAX], rice);
+        assert_eq!(decode(result.clone(), rice), Ok(vec![u64::MAX]));
+        let dec = SplitDecoder::new(Cursor::new(result), rice);
+        assert!(dec.decode::<u8>().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_rice_parameter() {
+        SplitEncoder::new(Cursor::new(vec![]), SplitCode::Rice(64));
+    }
+}
@@ -0,0 +1,260 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::{unzigzag, write_fixed_bits, zigzag};
+use crate::num::{bits_to_numeric, Numeric};
+
+/// The number of values in a block.
+const BLOCK_LEN: usize = 8;
+
+/// The number of bits of the header of a block, that holds its width.
+const WIDTH_BITS: u32 = 7;
+
+/// Returns the zigzagged errors of the delta forecaster, that predicts each
+/// value to be equal to the previous one.
+fn forecast_errors(nums: &[u64]) -> Vec<u64> {
+    let mut prev = 0_u64;
+    nums.iter()
+        .map(|num| {
+            let err = num.wrapping_sub(prev) as i64;
+            prev = *num;
+            zigzag(err)
+        })
+        .collect()
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers with
+/// the Sprintz approach, designed for streams of sensor readings.
+///
+/// Each value is forecast to be equal to the previous one, and the error of
+/// the forecast is zigzagged, so that small errors of either sign become
+/// small numbers. The errors are split in blocks of eight, and each block is
+/// bit-packed with the width of its largest error, written in a 7-bit header.
+/// Consecutive blocks whose errors are all zero, like readings of an idle
+/// sensor, are written as a single header of width zero, followed by the
+/// number of blocks in Elias Gamma Encoding.
+///
+/// The number of values is written in Elias Gamma Encoding, incremented by
+/// one, at the start of the stream, so the encoder buffers the values and
+/// encodes them when it is finalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, SprintzDecoder, SprintzEncoder};
+///
+/// let mut readings = vec![512_u16; 1000];
+/// readings.extend([515, 513, 510, 511, 512, 509, 508, 511]);
+///
+/// let mut enc = SprintzEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&readings).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert!(result.len() < 32);
+///
+/// let dec = SprintzDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u16>().unwrap(), readings);
+/// ```
+pub struct SprintzEncoder<W> {
+    writer: BitWriter<W>,
+    nums: Vec<u64>,
+}
+
+impl<W: Write> SprintzEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        SprintzEncoder {
+            writer,
+            nums: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for SprintzEncoder<W> {
+    /// Buffers a sequence of numbers, to be encoded when the encoder is
+    /// finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.nums.extend(nums.iter().map(|n| n.to_u64()));
+        Ok(())
+    }
+
+    /// Encodes the buffered numbers, and writes them in the wrapped writer.
+    fn finalize(mut self) -> io::Result<W> {
+        let convention = UnaryConvention::default();
+        let mut bits = vec![];
+        write_gamma_bits(self.nums.len() + 1, convention, &mut bits);
+
+        let errors = forecast_errors(&self.nums);
+        let blocks: Vec<&[u64]> = errors.chunks(BLOCK_LEN).collect();
+        let mut i = 0;
+        while i < blocks.len() {
+            let width = blocks[i]
+                .iter()
+                .map(|err| u64::BITS - err.leading_zeros())
+                .max()
+                .expect("Blocks are not empty.");
+            write_fixed_bits(&width, WIDTH_BITS, &mut bits);
+            if width == 0 {
+                let run = blocks[i..]
+                    .iter()
+                    .take_while(|block| block.iter().all(|err| *err == 0))
+                    .count();
+                write_gamma_bits(run, convention, &mut bits);
+                i += run;
+            } else {
+                for err in blocks[i] {
+                    write_fixed_bits(err, width, &mut bits);
+                }
+                i += 1;
+            }
+        }
+        self.writer.write_bits(&bits)?;
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`SprintzEncoder`].
+pub struct SprintzDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> SprintzDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        SprintzDecoder { cursor }
+    }
+}
+
+/// Decodes the block at the start of a buffer of bits, holding up to `len`
+/// errors, and appends its errors, returning the number of bits it occupies.
+fn decode_block(
+    bits: &[bool],
+    len: usize,
+    errors: &mut Vec<u64>,
+) -> Result<usize, InvalidCodeError> {
+    let header = bits
+        .get(..WIDTH_BITS as usize)
+        .ok_or(InvalidCodeError::SprintzCodeError)?;
+    let width = bits_to_numeric::<u32>(header).expect("The width fits in u32.");
+    let mut pos = WIDTH_BITS as usize;
+    if width > u64::BITS {
+        return Err(InvalidCodeError::SprintzCodeError);
+    }
+
+    if width == 0 {
+        let (run, run_len) = decode_gamma_prefix::<usize>(&bits[pos..], UnaryConvention::default())
+            .map_err(|_| InvalidCodeError::SprintzCodeError)?;
+        let count = run
+            .checked_mul(BLOCK_LEN)
+            .filter(|count| *count < len + BLOCK_LEN)
+            .ok_or(InvalidCodeError::SprintzCodeError)?;
+        errors.extend(std::iter::repeat_n(0, count.min(len)));
+        return Ok(pos + run_len);
+    }
+
+    let width = width as usize;
+    for _ in 0..len.min(BLOCK_LEN) {
+        let err_bits = bits
+            .get(pos..pos + width)
+            .ok_or(InvalidCodeError::SprintzCodeError)?;
+        errors.push(bits_to_numeric(err_bits).expect("The width is at most 64 bits."));
+        pos += width;
+    }
+    Ok(pos)
+}
+
+impl<R: Read> Decoder<R> for SprintzDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Err(InvalidCodeError::SprintzCodeError);
+        }
+        let count: usize = self
+            .cursor
+            .decode_with(|bits| decode_gamma_prefix(bits, UnaryConvention::default()))
+            .map_err(|_| InvalidCodeError::SprintzCodeError)?;
+        let count = count - 1;
+
+        let mut errors = Vec::with_capacity(count.min(self.cursor.remaining()?.len()));
+        while errors.len() < count {
+            let len = count - errors.len();
+            self.cursor.decode_with(|bits| {
+                let block_len = decode_block(bits, len, &mut errors)?;
+                Ok(((), block_len))
+            })?;
+        }
+
+        let mut prev = 0_u64;
+        errors
+            .iter()
+            .map(|err| {
+                prev = prev.wrapping_add(unzigzag(*err) as u64);
+                T::from_u64(prev).ok_or(InvalidCodeError::SprintzCodeError)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = SprintzEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Vec<u64> {
+        SprintzDecoder::new(Cursor::new(bytes)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_forecast_errors() {
+        assert_eq!(forecast_errors(&[3, 5, 5, 4]), vec![6, 4, 0, 1]);
+        assert_eq!(forecast_errors(&[u64::MAX]), vec![1]);
+    }
+
+    #[test]
+    fn test_zero_runs() {
+        let nums = vec![7; 100_000];
+        let result = encode(&nums);
+        assert!(result.len() < 16);
+        assert_eq!(decode(result), nums);
+
+        // A run of zero blocks that ends in a partial block.
+        let mut nums = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        nums.extend([8; 13]);
+        assert_eq!(decode(encode(&nums)), nums);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums: Vec<u64> = (0..1000).map(|i| 1000 + (i * 7919 % 23)).collect();
+        nums.extend([u64::MAX, 0, u64::MAX, 1 << 63, 5]);
+        assert_eq!(decode(encode(&nums)), nums);
+        assert!(decode(encode(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_errs() {
+        // A block of width 127.
+        let dec = SprintzDecoder::new(Cursor::new(vec![0b10011111, 0b11100000]));
+        assert!(dec.decode::<u64>().is_err());
+
+        let mut result = encode(&[1, 100, 10_000]);
+        result.truncate(1);
+        result[0] |= 1;
+        let dec = SprintzDecoder::new(Cursor::new(result));
+        assert!(dec.decode::<u64>().is_err());
+
+        let dec = SprintzDecoder::new(Cursor::new(encode(&[1 << 40])));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,601 @@
+use std::io::{self, Write};
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::code::global::vb::{read_vb_bytes, write_vb_bytes};
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// Running statistics of the values that have been encoded.
+///
+/// The statistics are kept in the widest supported integer type, so that
+/// values of different numeric types can be accumulated together.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    min: Option<u64>,
+    max: Option<u64>,
+    count: usize,
+    sum: u128,
+}
+
+impl Stats {
+    /// Updates the statistics with a single value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::Stats;
+    ///
+    /// let mut stats = Stats::default();
+    /// stats.update(5_u32);
+    /// stats.update(2_u8);
+    /// assert_eq!(stats.min(), Some(2));
+    /// assert_eq!(stats.max(), Some(5));
+    /// assert_eq!(stats.count(), 2);
+    /// assert_eq!(stats.sum(), 7);
+    /// ```
+    pub fn update<T: Numeric>(&mut self, num: T) {
+        let num = num.to_u64();
+        self.min = Some(self.min.map_or(num, |min| min.min(num)));
+        self.max = Some(self.max.map_or(num, |max| max.max(num)));
+        self.count += 1;
+        self.sum += u128::from(num);
+    }
+
+    /// Returns the minimum value seen, or `None` if no value has been seen.
+    #[inline]
+    pub fn min(&self) -> Option<u64> {
+        self.min
+    }
+
+    /// Returns the maximum value seen, or `None` if no value has been seen.
+    #[inline]
+    pub fn max(&self) -> Option<u64> {
+        self.max
+    }
+
+    /// Returns the number of values seen.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the sum of the values seen.
+    #[inline]
+    pub fn sum(&self) -> u128 {
+        self.sum
+    }
+}
+
+/// A structure that wraps an encoder and keeps running statistics of the
+/// values that pass through it.
+///
+/// The statistics (minimum, maximum, count and sum) are the ones needed for
+/// zone-map style pruning, and can be retrieved either while encoding with
+/// [`StatsEncoder::stats`] or after the encoding is complete with
+/// [`StatsEncoder::finalize_with_stats`].
+pub struct StatsEncoder<E> {
+    inner: E,
+    stats: Stats,
+}
+
+impl<E> StatsEncoder<E> {
+    pub fn new(inner: E) -> Self {
+        StatsEncoder {
+            inner,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Returns the statistics of the values encoded so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Finalizes the encoding returning the wrapped writer, along with the
+    /// statistics of all the encoded values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::{Encoder, GammaEncoder, StatsEncoder};
+    ///
+    /// let mut enc = StatsEncoder::new(GammaEncoder::new(Cursor::new(vec![])));
+    /// enc.encode(&[2_u32, 3, 9]).unwrap();
+    /// let (writer, stats) = enc.finalize_with_stats().unwrap();
+    /// assert_eq!(writer.into_inner(), vec![0b10010111, 0b10001100]);
+    /// assert_eq!(stats.min(), Some(2));
+    /// assert_eq!(stats.max(), Some(9));
+    /// assert_eq!(stats.count(), 3);
+    /// assert_eq!(stats.sum(), 14);
+    /// ```
+    pub fn finalize_with_stats<W: Write>(self) -> io::Result<(W, Stats)>
+    where
+        E: Encoder<W>,
+    {
+        let writer = self.inner.finalize()?;
+        Ok((writer, self.stats))
+    }
+}
+
+impl<W: Write, E: Encoder<W>> Encoder<W> for StatsEncoder<E> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.inner.encode(nums)?;
+        for &n in nums {
+            self.stats.update(n);
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+impl<W: Write, E: NonBlockingEncoder<W>> NonBlockingEncoder<W> for StatsEncoder<E> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+/// The statistics of a chunk of a column, in the directory of a [`ZoneMap`]:
+/// the minimum and the maximum of its values, their number, and the index of
+/// the first one in the column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zone {
+    min: u64,
+    max: u64,
+    count: usize,
+    start: u64,
+}
+
+impl Zone {
+    /// Returns the minimum value of the chunk.
+    #[inline]
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    /// Returns the maximum value of the chunk.
+    #[inline]
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the number of values of the chunk.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the index of the first value of the chunk in the column.
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns whether some of the values from the minimum to the maximum
+    /// of the chunk are in a range, so that the chunk may hold a value of
+    /// the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{Encoder, VBEncoder, ZoneMap, ZoneMapEncoder};
+    ///
+    /// let mut enc = ZoneMapEncoder::new(vec![], 4, VBEncoder::new);
+    /// enc.encode(&[10_u32, 12, 15, 11]).unwrap();
+    /// let map = ZoneMap::open(enc.finalize().unwrap()).unwrap();
+    /// let zone = map.zone(0).unwrap();
+    /// assert!(zone.overlaps(13..14));
+    /// assert!(zone.overlaps(..=10));
+    /// assert!(!zone.overlaps(16..));
+    /// assert!(!zone.overlaps(0..10));
+    /// ```
+    pub fn overlaps(&self, range: impl RangeBounds<u64>) -> bool {
+        let above_start = match range.start_bound() {
+            Bound::Included(&start) => self.max >= start,
+            Bound::Excluded(&start) => self.max > start,
+            Bound::Unbounded => true,
+        };
+        let below_end = match range.end_bound() {
+            Bound::Included(&end) => self.min <= end,
+            Bound::Excluded(&end) => self.min < end,
+            Bound::Unbounded => true,
+        };
+        above_start && below_end
+    }
+}
+
+/// A structure that encodes a column of numbers in chunks, each with its own
+/// encoder wrapped in a [`StatsEncoder`], and writes a directory with the
+/// [`Zone`] of each chunk after them, so that a [`ZoneMap`] skips the chunks
+/// that cannot match a predicate without decoding them.
+///
+/// A chunk ends once it has `chunk_len` numbers, or when
+/// [`ZoneMapEncoder::flush_chunk`] is called. The directory holds the number
+/// of chunks, and the number of bytes, the count, the minimum and the
+/// maximum of each chunk, in Variable Byte Encoding, and is followed by its
+/// own number of bytes, as a 64-bit little-endian number.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::{Decoder, DeltaDecoder, DeltaEncoder, Encoder, ZoneMap, ZoneMapEncoder};
+///
+/// // Readings that drift upwards, in chunks of 100.
+/// let readings: Vec<u32> = (0..1000).map(|i| 500 + i + (i * 37) % 50).collect();
+/// let mut enc = ZoneMapEncoder::new(vec![], 100, DeltaEncoder::new);
+/// enc.encode(&readings).unwrap();
+/// let map = ZoneMap::open(enc.finalize().unwrap()).unwrap();
+/// assert_eq!(map.num_chunks(), 10);
+///
+/// // Only the 3 chunks that may hold readings from 1000 to 1100 are decoded.
+/// let wanted = 1000..=1100;
+/// let mut found = vec![];
+/// for (i, decoder) in map.scan_with_predicate(|zone| zone.overlaps(1000..=1100), DeltaDecoder::new) {
+///     let start = map.zone(i).unwrap().start() as usize;
+///     let nums = decoder.decode::<u32>().unwrap();
+///     found.extend((start..).zip(nums).filter(|(_, n)| wanted.contains(n)));
+/// }
+/// let expected: Vec<_> = (0..).zip(readings).filter(|(_, n)| wanted.contains(n)).collect();
+/// assert_eq!(found, expected);
+/// assert_eq!(map.zones().filter(|zone| zone.overlaps(1000..=1100)).count(), 3);
+/// ```
+pub struct ZoneMapEncoder<W, E, F> {
+    writer: W,
+    new_encoder: F,
+    chunk_len: usize,
+    /// The encoder of the current chunk, if it has any numbers.
+    chunk: Option<StatsEncoder<E>>,
+    directory: Vec<u8>,
+    num_chunks: u64,
+}
+
+impl<W, E, F> ZoneMapEncoder<W, E, F>
+where
+    W: Write,
+    E: Encoder<Vec<u8>>,
+    F: FnMut(Vec<u8>) -> E,
+{
+    /// Creates an encoder that writes chunks of `chunk_len` numbers at most,
+    /// each encoded with the encoder that `new_encoder` creates over a
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` is 0.
+    pub fn new(writer: W, chunk_len: usize, new_encoder: F) -> Self {
+        assert!(chunk_len > 0, "A chunk must hold at least one number.");
+        ZoneMapEncoder {
+            writer,
+            new_encoder,
+            chunk_len,
+            chunk: None,
+            directory: vec![],
+            num_chunks: 0,
+        }
+    }
+
+    /// Ends the current chunk, writing its bytes, and recording its zone in
+    /// the directory. Does nothing if the chunk has no numbers.
+    pub fn flush_chunk(&mut self) -> io::Result<()> {
+        let Some(chunk) = self.chunk.take() else {
+            return Ok(());
+        };
+        let (bytes, stats) = chunk.finalize_with_stats()?;
+        self.writer.write_all(&bytes)?;
+        write_vb_bytes(bytes.len(), &mut self.directory);
+        write_vb_bytes(stats.count(), &mut self.directory);
+        write_vb_bytes(
+            stats.min().expect("A chunk has numbers."),
+            &mut self.directory,
+        );
+        write_vb_bytes(
+            stats.max().expect("A chunk has numbers."),
+            &mut self.directory,
+        );
+        self.num_chunks += 1;
+        Ok(())
+    }
+}
+
+impl<W, E, F> Encoder<W> for ZoneMapEncoder<W, E, F>
+where
+    W: Write,
+    E: Encoder<Vec<u8>>,
+    F: FnMut(Vec<u8>) -> E,
+{
+    fn encode<T: Numeric>(&mut self, mut nums: &[T]) -> io::Result<()> {
+        while !nums.is_empty() {
+            let new_encoder = &mut self.new_encoder;
+            let chunk = self
+                .chunk
+                .get_or_insert_with(|| StatsEncoder::new(new_encoder(vec![])));
+            let n = nums.len().min(self.chunk_len - chunk.stats().count());
+            chunk.encode(&nums[..n])?;
+            if chunk.stats().count() == self.chunk_len {
+                self.flush_chunk()?;
+            }
+            nums = &nums[n..];
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<W> {
+        self.flush_chunk()?;
+        let mut directory = vec![];
+        write_vb_bytes(self.num_chunks, &mut directory);
+        directory.extend_from_slice(&self.directory);
+        self.writer.write_all(&directory)?;
+        self.writer
+            .write_all(&(directory.len() as u64).to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// A reader of a column written by a [`ZoneMapEncoder`], over its bytes,
+/// that reads the directory of its chunks, and decodes only the chunks whose
+/// [`Zone`] matches a predicate.
+#[derive(Debug, Clone)]
+pub struct ZoneMap<B> {
+    bytes: B,
+    /// The byte range of each chunk, along with its zone.
+    chunks: Vec<(Range<usize>, Zone)>,
+}
+
+impl<B: AsRef<[u8]>> ZoneMap<B> {
+    /// Reads the directory of the column in a buffer of bytes.
+    ///
+    /// Returns an error if the bytes do not end with a directory whose
+    /// chunks take the bytes before it, or if a chunk has no numbers, or a
+    /// minimum greater than its maximum.
+    pub fn open(bytes: B) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::ZoneMapCodeError;
+        let data = bytes.as_ref();
+        let footer = data.len().checked_sub(8).ok_or_else(err)?;
+        let len = u64::from_le_bytes(data[footer..].try_into().expect("The footer has 8 bytes."));
+        let start = usize::try_from(len)
+            .ok()
+            .and_then(|len| footer.checked_sub(len))
+            .ok_or_else(err)?;
+        let directory = &data[start..footer];
+
+        let read = |pos: &mut usize| read_vb_bytes(directory, pos).map_err(|_| err());
+        let mut pos = 0;
+        let num_chunks = read(&mut pos)?;
+        // Each chunk takes 4 bytes of the directory at least.
+        if num_chunks > directory.len() as u64 / 4 {
+            return Err(err());
+        }
+        let mut chunks = Vec::with_capacity(num_chunks as usize);
+        let (mut end, mut rows) = (0_usize, 0_u64);
+        for _ in 0..num_chunks {
+            let len = read(&mut pos)?;
+            let count = read(&mut pos)?;
+            let (min, max) = (read(&mut pos)?, read(&mut pos)?);
+            let range = usize::try_from(len)
+                .ok()
+                .and_then(|len| end.checked_add(len))
+                .filter(|&chunk_end| chunk_end <= start)
+                .map(|chunk_end| end..chunk_end)
+                .ok_or_else(err)?;
+            if count == 0 || min > max {
+                return Err(err());
+            }
+            let zone = Zone {
+                min,
+                max,
+                count: usize::try_from(count).map_err(|_| err())?,
+                start: rows,
+            };
+            rows = rows.checked_add(count).ok_or_else(err)?;
+            end = range.end;
+            chunks.push((range, zone));
+        }
+        if end != start || pos != directory.len() {
+            return Err(err());
+        }
+        Ok(ZoneMap { bytes, chunks })
+    }
+
+    /// Returns the number of chunks of the column.
+    #[inline]
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the number of values of the column.
+    pub fn len(&self) -> u64 {
+        self.chunks
+            .last()
+            .map_or(0, |(_, zone)| zone.start + zone.count as u64)
+    }
+
+    /// Returns whether the column has no values.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the zone of a chunk, or `None` if there is no such chunk.
+    pub fn zone(&self, index: usize) -> Option<&Zone> {
+        self.chunks.get(index).map(|(_, zone)| zone)
+    }
+
+    /// Returns an iterator over the zones of the chunks, in order.
+    pub fn zones(&self) -> impl Iterator<Item = &Zone> {
+        self.chunks.iter().map(|(_, zone)| zone)
+    }
+
+    /// Returns the bytes of a chunk, or `None` if there is no such chunk.
+    pub fn chunk(&self, index: usize) -> Option<&[u8]> {
+        let (range, _) = self.chunks.get(index)?;
+        Some(&self.bytes.as_ref()[range.clone()])
+    }
+
+    /// Returns an iterator over the chunks whose zone matches a predicate,
+    /// each as its index, along with the decoder that `new` creates over its
+    /// bytes. The chunks that do not match are never decoded.
+    pub fn scan_with_predicate<'a, D, P, N>(
+        &'a self,
+        predicate: P,
+        new: N,
+    ) -> impl Iterator<Item = (usize, D)> + 'a
+    where
+        D: Decoder<&'a [u8]>,
+        P: Fn(&Zone) -> bool + 'a,
+        N: Fn(&'a [u8]) -> D + 'a,
+    {
+        let bytes = self.bytes.as_ref();
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(move |(_, (_, zone))| predicate(zone))
+            .map(move |(i, (range, _))| (i, new(&bytes[range.clone()])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeltaDecoder, DeltaEncoder, GammaDecoder, GammaEncoder, VBDecoder, VBEncoder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_empty_stats() {
+        let stats = Stats::default();
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.sum(), 0);
+    }
+
+    #[test]
+    fn test_stats_across_calls() {
+        let mut enc = StatsEncoder::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[824_u32, 8]).unwrap();
+        enc.encode(&[(1_u64 << 40) + 1]).unwrap();
+        assert_eq!(enc.stats().count(), 3);
+
+        let (writer, stats) = enc.finalize_with_stats().unwrap();
+        assert_eq!(stats.min(), Some(8));
+        assert_eq!(stats.max(), Some((1 << 40) + 1));
+        assert_eq!(stats.sum(), 833 + (1 << 40));
+
+        let nums = VBDecoder::new(Cursor::new(writer.into_inner()))
+            .decode::<u64>()
+            .unwrap();
+        assert_eq!(nums, vec![824, 8, (1 << 40) + 1]);
+    }
+
+    #[test]
+    fn test_zone_map() {
+        let nums: Vec<u64> = (0..1000_u64).map(|i| (i * 7919) % 1000 + i + 1).collect();
+        let mut enc = ZoneMapEncoder::new(Cursor::new(vec![]), 64, GammaEncoder::new);
+        // The chunks do not follow the slices.
+        for slice in nums.chunks(100) {
+            enc.encode(slice).unwrap();
+        }
+        let map = ZoneMap::open(enc.finalize().unwrap().into_inner()).unwrap();
+        assert_eq!(map.num_chunks(), 16);
+        assert_eq!(map.len(), 1000);
+
+        for (i, (zone, chunk)) in map.zones().zip(nums.chunks(64)).enumerate() {
+            assert_eq!(zone.start(), 64 * i as u64);
+            assert_eq!(zone.count(), chunk.len());
+            assert_eq!(Some(zone.min()), chunk.iter().min().copied());
+            assert_eq!(Some(zone.max()), chunk.iter().max().copied());
+            let decoded = GammaDecoder::new(map.chunk(i).unwrap()).decode::<u64>();
+            assert_eq!(decoded.unwrap(), chunk);
+        }
+        assert_eq!(map.chunk(16), None);
+
+        for range in [0..1, 100..300, 700..1200, 1500..2000, 2000..3000] {
+            let scanned: Vec<usize> = map
+                .scan_with_predicate(|zone| zone.overlaps(range.clone()), GammaDecoder::new)
+                .map(|(i, _)| i)
+                .collect();
+            let expected: Vec<usize> = nums
+                .chunks(64)
+                .enumerate()
+                .filter(|(_, chunk)| {
+                    let (min, max) = (chunk.iter().min().unwrap(), chunk.iter().max().unwrap());
+                    *min < range.end && *max >= range.start
+                })
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(scanned, expected, "The chunks of {range:?} are wrong.");
+        }
+    }
+
+    #[test]
+    fn test_flush_chunk() {
+        let mut enc = ZoneMapEncoder::new(vec![], 10, DeltaEncoder::new);
+        enc.encode(&[5_u32, 3]).unwrap();
+        enc.flush_chunk().unwrap();
+        enc.flush_chunk().unwrap();
+        enc.encode(&[u32::MAX]).unwrap();
+        let map = ZoneMap::open(enc.finalize().unwrap()).unwrap();
+        let zones: Vec<_> = map
+            .zones()
+            .map(|z| (z.min(), z.max(), z.count(), z.start()))
+            .collect();
+        assert_eq!(
+            zones,
+            vec![
+                (3, 5, 2, 0),
+                (u64::from(u32::MAX), u64::from(u32::MAX), 1, 2)
+            ]
+        );
+        let decoded: Vec<Vec<u32>> = map
+            .scan_with_predicate(|_| true, DeltaDecoder::new)
+            .map(|(_, decoder)| decoder.decode().unwrap())
+            .collect();
+        assert_eq!(decoded, vec![vec![5, 3], vec![u32::MAX]]);
+
+        let empty = ZoneMapEncoder::new(vec![], 10, VBEncoder::new)
+            .finalize()
+            .unwrap();
+        assert_eq!(empty, [0x80, 1, 0, 0, 0, 0, 0, 0, 0]);
+        let empty = ZoneMap::open(empty).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_zone_map_errs() {
+        let mut enc = ZoneMapEncoder::new(vec![], 2, VBEncoder::new);
+        enc.encode(&[1_u8, 2, 3]).unwrap();
+        let bytes = enc.finalize().unwrap();
+        // The chunks, and the directory of 9 bytes.
+        assert_eq!(bytes.len(), 3 + 9 + 8);
+        assert!(ZoneMap::open(&bytes).is_ok());
+
+        let err = Err(InvalidCodeError::ZoneMapCodeError);
+        assert_eq!(ZoneMap::open(&bytes[..7]).map(|_| ()), err);
+        assert_eq!(ZoneMap::open(&bytes[1..]).map(|_| ()), err);
+        let mut long = bytes.clone();
+        long[12] = 13;
+        assert_eq!(ZoneMap::open(&long).map(|_| ()), err);
+        // A chunk that overlaps the directory.
+        let mut overlapping = bytes.clone();
+        overlapping[8] = 2;
+        assert_eq!(ZoneMap::open(&overlapping).map(|_| ()), err);
+        // A chunk with no numbers, and one with its minimum above its maximum.
+        for (i, byte) in [(5, 0), (6, 3)] {
+            let mut corrupt = bytes.clone();
+            corrupt[i] = byte;
+            assert_eq!(ZoneMap::open(&corrupt).map(|_| ()), err);
+        }
+    }
+}
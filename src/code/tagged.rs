@@ -0,0 +1,366 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::delta::{decode_delta_prefix, write_delta_bits};
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::Numeric;
+
+/// The codes that a [`TaggedEncoder`] switches between, along with the 2-bit
+/// tags that identify them in the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaggedCode {
+    /// Unary encoding of the number, tagged with 00.
+    Unary,
+    /// Elias Gamma encoding of the number incremented by one, tagged with 01.
+    Gamma,
+    /// Elias Delta encoding of the number incremented by one, tagged with 10.
+    Delta,
+    /// Variable Byte encoding of the number, not aligned to bytes, tagged
+    /// with 11.
+    VB,
+}
+
+impl TaggedCode {
+    const ALL: [TaggedCode; 4] = [
+        TaggedCode::Unary,
+        TaggedCode::Gamma,
+        TaggedCode::Delta,
+        TaggedCode::VB,
+    ];
+
+    /// Returns the number of bits of the code of a number, or `None` if the
+    /// number is out of the range of the code.
+    ///
+    /// The length of unary codes is capped at 64 bits, since longer ones are
+    /// never the shortest code of a number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::TaggedCode;
+    ///
+    /// assert_eq!(TaggedCode::Unary.code_len(1), Some(2));
+    /// assert_eq!(TaggedCode::Gamma.code_len(1), Some(3));
+    /// assert_eq!(TaggedCode::VB.code_len(300), Some(16));
+    /// assert_eq!(TaggedCode::Unary.code_len(1000), None);
+    /// ```
+    pub fn code_len(&self, num: u64) -> Option<usize> {
+        match self {
+            TaggedCode::Unary => (num < 64).then_some(num as usize + 1),
+            TaggedCode::Gamma => {
+                let len = u64::BITS - num.checked_add(1)?.leading_zeros() - 1;
+                Some(2 * len as usize + 1)
+            }
+            TaggedCode::Delta => {
+                let len = u64::BITS - num.checked_add(1)?.leading_zeros();
+                let len_len = u32::BITS - len.leading_zeros() - 1;
+                Some((2 * len_len + len) as usize)
+            }
+            TaggedCode::VB => {
+                let len = u64::BITS - num.leading_zeros();
+                Some(8 * len.div_ceil(7).max(1) as usize)
+            }
+        }
+    }
+
+    /// Returns the code with the shortest code of a number, preferring the
+    /// `current` code on ties.
+    fn shortest(num: u64, current: TaggedCode) -> TaggedCode {
+        let mut best = current;
+        for code in TaggedCode::ALL {
+            match (code.code_len(num), best.code_len(num)) {
+                (Some(len), Some(best_len)) if len < best_len => best = code,
+                (Some(_), None) => best = code,
+                _ => {}
+            }
+        }
+        best
+    }
+
+    fn tag(&self) -> [bool; 2] {
+        match self {
+            TaggedCode::Unary => [false, false],
+            TaggedCode::Gamma => [false, true],
+            TaggedCode::Delta => [true, false],
+            TaggedCode::VB => [true, true],
+        }
+    }
+
+    fn from_tag(tag: &[bool]) -> Result<TaggedCode, InvalidCodeError> {
+        match tag {
+            [false, false] => Ok(TaggedCode::Unary),
+            [false, true] => Ok(TaggedCode::Gamma),
+            [true, false] => Ok(TaggedCode::Delta),
+            [true, true] => Ok(TaggedCode::VB),
+            _ => Err(InvalidCodeError::TaggedCodeError),
+        }
+    }
+
+    /// Appends the code of a number to a buffer of bits. The number must be
+    /// in the range of the code.
+    pub(crate) fn write_bits(&self, num: u64, buffer: &mut Vec<bool>) {
+        let convention = UnaryConvention::default();
+        match self {
+            TaggedCode::Unary => {
+                buffer.extend(UnaryEncoder::encode_one_with(num as usize, convention))
+            }
+            TaggedCode::Gamma => write_gamma_bits(num + 1, convention, buffer),
+            TaggedCode::Delta => write_delta_bits(num + 1, convention, buffer),
+            TaggedCode::VB => {
+                let mut bytes = vec![];
+                write_vb_bytes(num, &mut bytes);
+                for byte in bytes {
+                    buffer.extend((0..8).rev().map(|i| byte & (1 << i) != 0));
+                }
+            }
+        }
+    }
+
+    /// Decodes the code at the start of a buffer of bits, returning the
+    /// number along with the number of bits its code occupies.
+    pub(crate) fn decode_prefix(&self, bits: &[bool]) -> Result<(u64, usize), InvalidCodeError> {
+        let convention = UnaryConvention::default();
+        let (num, len) = match self {
+            TaggedCode::Unary => UnaryDecoder::decode_prefix_with(bits, convention)
+                .map(|(num, len)| (num as u64, len))?,
+            TaggedCode::Gamma => {
+                decode_gamma_prefix::<u64>(bits, convention).map(|(num, len)| (num - 1, len))?
+            }
+            TaggedCode::Delta => {
+                decode_delta_prefix::<u64>(bits, convention).map(|(num, len)| (num - 1, len))?
+            }
+            TaggedCode::VB => {
+                let mut bytes = vec![];
+                for byte_bits in bits.chunks_exact(8) {
+                    let byte = byte_bits.iter().fold(0_u8, |b, &bit| (b << 1) | bit as u8);
+                    bytes.push(byte);
+                    if byte >= 0x80 {
+                        break;
+                    }
+                }
+                let (num, len) = decode_vb_prefix(&bytes)?;
+                (num, 8 * len)
+            }
+        };
+        Ok((num, len))
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of integers,
+/// switching between codes from one value to the next.
+///
+/// The values are written in runs, and each run is preceded by the 2-bit tag
+/// of its [`TaggedCode`] and its length in Elias Gamma Encoding. [`Encoder::encode`]
+/// picks the code with the shortest code for each value, and starts a new run
+/// when the code changes, so that small gaps are written in unary, and
+/// occasional huge jumps in Variable Byte Encoding, without committing a whole
+/// block to one code. Runs in a specific code can be written with
+/// [`TaggedEncoder::encode_with`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, TaggedCode, TaggedDecoder, TaggedEncoder};
+///
+/// let gaps = [1_u64, 1, 0, 1, 1, 1 << 40, 1, 1];
+/// let mut enc = TaggedEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&gaps).unwrap();
+/// enc.encode_with(TaggedCode::Delta, &[1000_u64]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result.len(), 12);
+///
+/// let dec = TaggedDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u64>().unwrap(), [&gaps[..], &[1000]].concat());
+/// ```
+pub struct TaggedEncoder<W> {
+    writer: BitWriter<W>,
+}
+
+impl<W: Write> TaggedEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        TaggedEncoder { writer }
+    }
+
+    /// Encodes and writes a sequence of numbers as a single run, in the
+    /// specified code.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// is out of the range of the code.
+    pub fn encode_with<T: Numeric>(&mut self, code: TaggedCode, nums: &[T]) -> io::Result<()> {
+        let nums: Vec<u64> = nums.iter().map(|num| num.to_u64()).collect();
+        if nums.iter().any(|&num| code.code_len(num).is_none()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Number out of the range of the code.",
+            ));
+        }
+        self.write_run(code, &nums)
+    }
+
+    /// Writes a run of numbers, that are in the range of the code.
+    fn write_run(&mut self, code: TaggedCode, nums: &[u64]) -> io::Result<()> {
+        if nums.is_empty() {
+            return Ok(());
+        }
+        let mut bits = code.tag().to_vec();
+        write_gamma_bits(nums.len(), UnaryConvention::default(), &mut bits);
+        for &num in nums {
+            code.write_bits(num, &mut bits);
+        }
+        self.writer.write_bits(&bits)
+    }
+}
+
+impl<W: Write> Encoder<W> for TaggedEncoder<W> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let nums: Vec<u64> = nums.iter().map(|num| num.to_u64()).collect();
+        let mut start = 0;
+        let mut code = TaggedCode::Gamma;
+        for (i, &num) in nums.iter().enumerate() {
+            let best = TaggedCode::shortest(num, code);
+            if best != code {
+                self.write_run(code, &nums[start..i])?;
+                start = i;
+                code = best;
+            }
+        }
+        self.write_run(code, &nums[start..])
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+impl<W: Write> NonBlockingEncoder<W> for TaggedEncoder<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.writer.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`TaggedEncoder`], dispatching each run to the code of its tag.
+pub struct TaggedDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> TaggedDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        TaggedDecoder { cursor }
+    }
+}
+
+impl<R: Read> Decoder<R> for TaggedDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut nums = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let code = self.cursor.decode_with(|bits| {
+                let tag = bits.get(..2).ok_or(InvalidCodeError::TaggedCodeError)?;
+                Ok((TaggedCode::from_tag(tag)?, 2))
+            })?;
+            let len: usize = self
+                .cursor
+                .decode_with(|bits| decode_gamma_prefix(bits, UnaryConvention::default()))
+                .map_err(|_| InvalidCodeError::TaggedCodeError)?;
+            for _ in 0..len {
+                let num = self
+                    .cursor
+                    .decode_with(|bits| code.decode_prefix(bits))
+                    .map_err(|_| InvalidCodeError::TaggedCodeError)?;
+                nums.push(T::from_u64(num).ok_or(InvalidCodeError::TaggedCodeError)?);
+            }
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn decode(bytes: Vec<u8>) -> Result<Vec<u64>, InvalidCodeError> {
+        TaggedDecoder::new(Cursor::new(bytes)).decode()
+    }
+
+    #[test]
+    fn test_code_len() {
+        for code in TaggedCode::ALL {
+            for num in [0_u64, 1, 2, 5, 63, 64, 127, 128, 300, 1 << 40, u64::MAX - 1] {
+                let Some(len) = code.code_len(num) else {
+                    continue;
+                };
+                let mut bits = vec![];
+                code.write_bits(num, &mut bits);
+                assert_eq!(bits.len(), len, "{code:?} {num}");
+                assert_eq!(code.decode_prefix(&bits), Ok((num, len)), "{code:?} {num}");
+            }
+        }
+        assert_eq!(TaggedCode::Gamma.code_len(u64::MAX), None);
+        assert_eq!(TaggedCode::VB.code_len(u64::MAX), Some(80));
+    }
+
+    #[test]
+    fn test_runs() {
+        let nums = [1_u64, 1, 1, 1, 9, 9, 9, 1 << 50, 0, 1];
+        let mut enc = TaggedEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums[..6]).unwrap();
+        enc.encode(&nums[6..]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(decode(result), Ok(nums.to_vec()));
+
+        // The first run is four 1s in unary.
+        let mut enc = TaggedEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums[..4]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0b00110001, 0b01010101]);
+    }
+
+    #[test]
+    fn test_encode_with() {
+        let mut enc = TaggedEncoder::new(Cursor::new(vec![]));
+        for code in TaggedCode::ALL {
+            enc.encode_with(code, &[0_u32, 3, 50]).unwrap();
+        }
+        enc.encode_with::<u32>(TaggedCode::VB, &[]).unwrap();
+        assert!(enc.encode_with(TaggedCode::Unary, &[64_u32]).is_err());
+        assert!(enc.encode_with(TaggedCode::Delta, &[u64::MAX]).is_err());
+        let result = enc.finalize().unwrap().into_inner();
+        assert_eq!(decode(result), Ok([0, 3, 50].repeat(4)));
+    }
+
+    #[test]
+    fn test_errs() {
+        // A run of two values with a single one written.
+        let mut bits = vec![false, true];
+        write_gamma_bits(2_u32, UnaryConvention::default(), &mut bits);
+        write_gamma_bits(5_u32, UnaryConvention::default(), &mut bits);
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        assert!(decode(writer.finalize().unwrap().into_inner()).is_err());
+
+        // A value that does not fit in the decoded type.
+        let mut enc = TaggedEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[300_u32]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        assert!(TaggedDecoder::new(Cursor::new(result))
+            .decode::<u8>()
+            .is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Fixtures and checks shared by the tests of the codes.
+
+use crate::code::DecodeBytes;
+
+/// Returns a number for each index of a range, scrambled with a
+/// multiplicative hash and shifted by the index, so the numbers are spread
+/// over all the bit lengths from 0 to 64.
+pub(crate) fn spread(indexes: impl IntoIterator<Item = u64>) -> impl Iterator<Item = u64> {
+    indexes
+        .into_iter()
+        .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> (i % 64))
+}
+
+/// Asserts that the numbers, encoded one after the other in a buffer of
+/// bytes, are decoded back with [`DecodeBytes::decode_one_from_bytes`], and
+/// returns the bit offset where the last code ends.
+pub(crate) fn assert_decodes_from_bytes<D: DecodeBytes>(bytes: &[u8], nums: &[u64]) -> usize {
+    let mut pos = 0;
+    for (i, &num) in nums.iter().enumerate() {
+        let (n, len) = D::decode_one_from_bytes::<u64>(bytes, pos)
+            .unwrap_or_else(|e| panic!("The code of number {i} at bit {pos} fails: {e}."));
+        assert_eq!(
+            n, num,
+            "The code of number {i} at bit {pos} is decoded wrong."
+        );
+        pos += len;
+    }
+    assert!(pos <= 8 * bytes.len());
+    pos
+}
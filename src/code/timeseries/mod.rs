@@ -0,0 +1 @@
+pub mod xor_float;
@@ -0,0 +1,313 @@
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::write_fixed_bits;
+use crate::num::{bits_to_numeric, Float};
+
+/// Reads `width` bits at a position of a buffer of bits.
+fn read_bits(bits: &[bool], pos: usize, width: u32) -> Result<u64, InvalidCodeError> {
+    let bits = bits
+        .get(pos..pos + width as usize)
+        .ok_or(InvalidCodeError::GorillaCodeError)?;
+    bits_to_numeric(bits).map_err(|_| InvalidCodeError::GorillaCodeError)
+}
+
+/// The previous value of a stream, and the window of meaningful bits of the
+/// last XOR written with its leading zeros, shared by the encoder and the
+/// decoder.
+#[derive(Debug, Clone, Default)]
+struct State {
+    prev: Option<u64>,
+    /// The leading and trailing zeros of the window.
+    window: Option<(u32, u32)>,
+}
+
+impl State {
+    /// Returns the number of bits of the leading zeros of an XOR, that is 5
+    /// for `f64` and 4 for `f32`.
+    fn lead_bits<F: Float>() -> u32 {
+        F::BITS.trailing_zeros() - 1
+    }
+
+    /// Returns the number of bits of the number of meaningful bits of an
+    /// XOR, minus one, that is 6 for `f64` and 5 for `f32`.
+    fn len_bits<F: Float>() -> u32 {
+        F::BITS.trailing_zeros()
+    }
+
+    /// Calculates the Gorilla code of the next value, from its XOR with the
+    /// previous value.
+    fn write_bits<F: Float>(&mut self, value: u64, buffer: &mut Vec<bool>) {
+        let Some(prev) = self.prev.replace(value) else {
+            write_fixed_bits(&value, F::BITS, buffer);
+            return;
+        };
+
+        let xor = value ^ prev;
+        if xor == 0 {
+            buffer.push(false);
+            return;
+        }
+        buffer.push(true);
+
+        let max_lead = (1 << Self::lead_bits::<F>()) - 1;
+        let lead = (xor.leading_zeros() - (u64::BITS - F::BITS)).min(max_lead);
+        let trail = xor.trailing_zeros();
+        match self.window {
+            Some((window_lead, window_trail)) if lead >= window_lead && trail >= window_trail => {
+                buffer.push(false);
+                let len = F::BITS - window_lead - window_trail;
+                write_fixed_bits(&(xor >> window_trail), len, buffer);
+            }
+            _ => {
+                let len = F::BITS - lead - trail;
+                buffer.push(true);
+                write_fixed_bits(&lead, Self::lead_bits::<F>(), buffer);
+                write_fixed_bits(&(len - 1), Self::len_bits::<F>(), buffer);
+                write_fixed_bits(&(xor >> trail), len, buffer);
+                self.window = Some((lead, trail));
+            }
+        }
+    }
+
+    /// Decodes the Gorilla code of the next value at the start of a buffer of
+    /// bits, returning the value along with the number of bits its code
+    /// occupies.
+    fn decode_prefix<F: Float>(&mut self, bits: &[bool]) -> Result<(u64, usize), InvalidCodeError> {
+        let Some(prev) = self.prev else {
+            let value = read_bits(bits, 0, F::BITS)?;
+            self.prev = Some(value);
+            return Ok((value, F::BITS as usize));
+        };
+
+        let mut pos = 1;
+        let xor = match read_bits(bits, 0, 1)? {
+            0 => 0,
+            _ => {
+                pos += 1;
+                let (lead, trail) = if read_bits(bits, 1, 1)? == 0 {
+                    self.window.ok_or(InvalidCodeError::GorillaCodeError)?
+                } else {
+                    let lead = read_bits(bits, pos, Self::lead_bits::<F>())? as u32;
+                    pos += Self::lead_bits::<F>() as usize;
+                    let len = read_bits(bits, pos, Self::len_bits::<F>())? as u32 + 1;
+                    pos += Self::len_bits::<F>() as usize;
+                    if lead + len > F::BITS {
+                        return Err(InvalidCodeError::GorillaCodeError);
+                    }
+                    self.window = Some((lead, F::BITS - lead - len));
+                    (lead, F::BITS - lead - len)
+                };
+                let len = F::BITS - lead - trail;
+                let xor = read_bits(bits, pos, len)?;
+                pos += len as usize;
+                xor << trail
+            }
+        };
+
+        let value = prev ^ xor;
+        self.prev = Some(value);
+        Ok((value, pos))
+    }
+}
+
+/// A structure that wraps a writer and encodes a sequence of floating-point
+/// numbers, either `f64` or `f32`, using Gorilla compression.
+///
+/// The first value is written in full. Each following value is XORed with
+/// the previous value, and the XOR is prefixed by a flag:
+/// - `0`: the value is equal to the previous value.
+/// - `10`: the meaningful bits of the XOR fall within the window of the last
+///   XOR written with a header, and the bits of the window are written.
+/// - `11`: the leading zeros of the XOR, up to 31 for `f64` and 15 for `f32`,
+///   in 5 or 4 bits, and the number of meaningful bits, minus one, in 6 or 5
+///   bits, are followed by the meaningful bits, and open a new window.
+///
+/// Consecutive values of gauges and counters often repeat or share their
+/// sign, exponent and highest mantissa bits, so their XORs are zero or have
+/// a few meaningful bits, that mostly fall within the same window.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{GorillaDecoder, GorillaEncoder};
+///
+/// let values = [12.0_f64, 12.0, 24.0, 15.0, 12.0, 12.0];
+/// let mut enc = GorillaEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&values).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result.len(), 13);
+///
+/// let dec = GorillaDecoder::<_, f64>::new(Cursor::new(result));
+/// assert_eq!(dec.decode().unwrap(), values);
+///
+/// let values = [0.5_f32, 0.5, 0.75];
+/// let mut enc = GorillaEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&values).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// let dec = GorillaDecoder::<_, f32>::new(Cursor::new(result));
+/// assert_eq!(dec.decode().unwrap(), values);
+/// ```
+pub struct GorillaEncoder<W, F> {
+    writer: BitWriter<W>,
+    state: State,
+    float: PhantomData<F>,
+}
+
+impl<W: Write, F: Float> GorillaEncoder<W, F> {
+    pub fn new(writer: W) -> Self {
+        GorillaEncoder {
+            writer: BitWriter::new(writer, true),
+            state: State::default(),
+            float: PhantomData,
+        }
+    }
+
+    /// Encodes and writes a sequence of floating-point numbers.
+    pub fn encode(&mut self, values: &[F]) -> io::Result<()> {
+        let mut bits = Vec::new();
+        for value in values {
+            bits.clear();
+            self.state.write_bits::<F>(value.to_u64_bits(), &mut bits);
+            self.writer.write_bits(&bits)?;
+        }
+        Ok(())
+    }
+
+    pub fn finalize(self) -> io::Result<W> {
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`GorillaEncoder`], whose floating-point type must match the encoder's.
+pub struct GorillaDecoder<R, F> {
+    cursor: BitCursor<R>,
+    float: PhantomData<F>,
+}
+
+impl<R: Read, F: Float> GorillaDecoder<R, F> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        GorillaDecoder {
+            cursor,
+            float: PhantomData,
+        }
+    }
+
+    /// Reads and decodes the encoded floating-point numbers.
+    pub fn decode(mut self) -> Result<Vec<F>, InvalidCodeError> {
+        let mut state = State::default();
+        let mut values = vec![];
+        while !self.cursor.remaining()?.is_empty() {
+            let value = self
+                .cursor
+                .decode_with(|bits| state.decode_prefix::<F>(bits))?;
+            values.push(F::from_u64_bits(value));
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode<F: Float>(values: &[F]) -> Vec<u8> {
+        let mut enc = GorillaEncoder::new(Cursor::new(vec![]));
+        enc.encode(values).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode<F: Float>(bytes: Vec<u8>) -> Vec<u64> {
+        let dec = GorillaDecoder::<_, F>::new(Cursor::new(bytes));
+        dec.decode()
+            .unwrap()
+            .into_iter()
+            .map(F::to_u64_bits)
+            .collect()
+    }
+
+    #[test]
+    fn test_write_bits() {
+        let mut state = State::default();
+        let mut bits = vec![];
+        state.write_bits::<f64>(12.0_f64.to_bits(), &mut bits);
+        assert_eq!(bits.len(), 64);
+
+        bits.clear();
+        state.write_bits::<f64>(12.0_f64.to_bits(), &mut bits);
+        assert_eq!(bits, vec![false]);
+
+        // The XOR of 12 and 24 has 11 leading zeros and a meaningful bit.
+        bits.clear();
+        state.write_bits::<f64>(24.0_f64.to_bits(), &mut bits);
+        assert_eq!(bits.len(), 2 + 5 + 6 + 1);
+        assert_eq!(&bits[..2], &[true, true]);
+        assert_eq!(state.window, Some((11, 52)));
+
+        // The XOR of 24 and 15 does not fit in the window.
+        bits.clear();
+        state.write_bits::<f64>(15.0_f64.to_bits(), &mut bits);
+        assert_eq!(&bits[..2], &[true, true]);
+
+        // The XOR of 15 and 12 fits in the window of the last XOR.
+        bits.clear();
+        state.write_bits::<f64>(12.0_f64.to_bits(), &mut bits);
+        assert_eq!(&bits[..2], &[true, false]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut values: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.37).sin() * 100.0).collect();
+        values.extend([
+            0.0,
+            -0.0,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            1e-300,
+        ]);
+        values.extend((0..200).map(|i| [1.5, 2.25, 3.125][i % 3]));
+        values.extend([1.0, f64::from_bits(1.0_f64.to_bits() ^ 1), -1.0]);
+        let expected: Vec<u64> = values.iter().map(|v| v.to_bits()).collect();
+        assert_eq!(decode::<f64>(encode(&values)), expected);
+        assert!(decode::<f64>(encode::<f64>(&[])).is_empty());
+
+        let values: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+        let expected: Vec<u64> = values.iter().map(|v| u64::from(v.to_bits())).collect();
+        assert_eq!(decode::<f32>(encode(&values)), expected);
+        let nan = u64::from(f32::NAN.to_bits());
+        assert_eq!(decode::<f32>(encode(&[f32::NAN])), vec![nan]);
+    }
+
+    #[test]
+    fn test_compression() {
+        // A gauge that changes rarely takes a bit per repeated value.
+        let values: Vec<f64> = (0..1000).map(|i| [20.5, 21.0][i / 100 % 2]).collect();
+        assert!(encode(&values).len() < 150);
+        let values: Vec<f32> = (0..1000).map(|i| [20.5, 21.0][i / 100 % 2]).collect();
+        assert!(encode(&values).len() < 150);
+    }
+
+    #[test]
+    fn test_errs() {
+        // A window is reused before any window is opened.
+        let mut bits = vec![];
+        write_fixed_bits(&1.0_f64.to_bits(), 64, &mut bits);
+        bits.extend([true, false, true]);
+        let mut bytes = BitWriter::new(vec![], true);
+        bytes.write_bits(&bits).unwrap();
+        let result = bytes.finalize().unwrap();
+        let dec = GorillaDecoder::<_, f64>::new(Cursor::new(result));
+        assert_eq!(dec.decode(), Err(InvalidCodeError::GorillaCodeError));
+
+        let dec = GorillaDecoder::<_, f64>::new(Cursor::new(vec![0x00, 0x01]));
+        assert!(dec.decode().is_err());
+    }
+}
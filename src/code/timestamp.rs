@@ -0,0 +1,236 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::delta::{decode_delta_prefix, write_delta_bits};
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::io::read::{BitCursor, BitReader};
+use crate::io::write::BitWriter;
+use crate::num::convert::{unzigzag, write_fixed_bits, zigzag};
+use crate::num::{bits_to_numeric, Numeric};
+
+/// The smallest number of equal deltas that are encoded as a run.
+const MIN_RUN: usize = 4;
+
+/// Calculates the code of a delta of delta, that is its zigzagged value,
+/// incremented by one, in Elias Delta Encoding.
+fn write_dod_bits(dod: i64, buffer: &mut Vec<bool>) -> io::Result<()> {
+    let code = zigzag(dod).checked_add(1).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Delta of delta out of the range of the timestamp code.",
+        )
+    })?;
+    write_delta_bits(code, UnaryConvention::default(), buffer);
+    Ok(())
+}
+
+/// A structure that wraps a writer and encodes a sequence of timestamps,
+/// using delta-of-delta encoding with a run-length mode for regular
+/// intervals.
+///
+/// The first timestamp is written in 64 bits. Each following timestamp is
+/// described by its delta from the previous one, and each delta by its
+/// difference from the previous delta, which is zero for timestamps at
+/// regular intervals. A run of at least four equal deltas is written as a
+/// 1-bit, followed by the delta of delta of the run and the length of the
+/// run, minus three, in Elias Gamma Encoding. Any other delta is written as
+/// a 0-bit, followed by its delta of delta. Deltas of delta are zigzagged and
+/// incremented by one, and written in Elias Delta Encoding.
+///
+/// Metrics scraped at a fixed interval thus take a few bits per run, instead
+/// of a few bits per timestamp. Since runs are only known once they end, the
+/// timestamps are buffered, and encoded when the encoder is finalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, TimestampDecoder, TimestampEncoder};
+///
+/// // A scrape every 15 seconds, with a late one.
+/// let mut timestamps: Vec<u64> = (0..1000).map(|i| 1_700_000_000 + 15 * i).collect();
+/// timestamps[500] += 2;
+///
+/// let mut enc = TimestampEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&timestamps).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert!(result.len() < 20);
+///
+/// let dec = TimestampDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u64>().unwrap(), timestamps);
+/// ```
+pub struct TimestampEncoder<W> {
+    writer: BitWriter<W>,
+    timestamps: Vec<u64>,
+}
+
+impl<W: Write> TimestampEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        let writer = BitWriter::new(writer, true);
+        TimestampEncoder {
+            writer,
+            timestamps: vec![],
+        }
+    }
+}
+
+impl<W: Write> Encoder<W> for TimestampEncoder<W> {
+    /// Buffers a sequence of timestamps, to be encoded when the encoder is
+    /// finalized.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        self.timestamps.extend(nums.iter().map(|n| n.to_u64()));
+        Ok(())
+    }
+
+    /// Encodes the buffered timestamps, and writes them in the wrapped
+    /// writer.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a delta
+    /// of delta is `i64::MIN`.
+    fn finalize(mut self) -> io::Result<W> {
+        let Some(first) = self.timestamps.first() else {
+            return self.writer.finalize();
+        };
+        let mut bits = vec![];
+        write_fixed_bits(first, u64::BITS, &mut bits);
+
+        // Timestamps may decrease, so deltas wrap around, which the decoder
+        // undoes by wrapping around as well.
+        let deltas: Vec<i64> = self
+            .timestamps
+            .windows(2)
+            .map(|w| w[1].wrapping_sub(w[0]) as i64)
+            .collect();
+        let mut prev_delta = 0_i64;
+        for run in deltas.chunk_by(|a, b| a == b) {
+            let dod = run[0].wrapping_sub(prev_delta);
+            if run.len() >= MIN_RUN {
+                bits.push(true);
+                write_dod_bits(dod, &mut bits)?;
+                let convention = UnaryConvention::default();
+                write_gamma_bits(run.len() - MIN_RUN + 1, convention, &mut bits);
+            } else {
+                for i in 0..run.len() {
+                    bits.push(false);
+                    write_dod_bits(if i == 0 { dod } else { 0 }, &mut bits)?;
+                }
+            }
+            prev_delta = run[0];
+        }
+        self.writer.write_bits(&bits)?;
+        self.writer.finalize()
+    }
+}
+
+/// A structure that wraps a reader and decodes a stream of bytes encoded with
+/// [`TimestampEncoder`].
+pub struct TimestampDecoder<R> {
+    cursor: BitCursor<R>,
+}
+
+impl<R: Read> TimestampDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        let cursor = BitCursor::new(BitReader::new(reader, true));
+        TimestampDecoder { cursor }
+    }
+}
+
+/// Decodes the record of a single delta or of a run of deltas at the start
+/// of a buffer of bits, returning the delta of delta and the number of
+/// deltas, along with the number of bits the record occupies.
+fn decode_record(bits: &[bool]) -> Result<((i64, usize), usize), InvalidCodeError> {
+    let is_run = *bits.first().ok_or(InvalidCodeError::TimestampCodeError)?;
+    let convention = UnaryConvention::default();
+    let (code, len) = decode_delta_prefix::<u64>(&bits[1..], convention)
+        .map_err(|_| InvalidCodeError::TimestampCodeError)?;
+    let dod = unzigzag(code - 1);
+    let mut pos = 1 + len;
+
+    let mut run = 1;
+    if is_run {
+        let (count, len) = decode_gamma_prefix::<usize>(&bits[pos..], convention)
+            .map_err(|_| InvalidCodeError::TimestampCodeError)?;
+        run = count + MIN_RUN - 1;
+        pos += len;
+    }
+    Ok(((dod, run), pos))
+}
+
+impl<R: Read> Decoder<R> for TimestampDecoder<R> {
+    fn decode<T: Numeric>(mut self) -> Result<Vec<T>, InvalidCodeError> {
+        if self.cursor.remaining()?.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut prev = self.cursor.decode_with(|bits| {
+            let bits = bits
+                .get(..u64::BITS as usize)
+                .ok_or(InvalidCodeError::TimestampCodeError)?;
+            let first = bits_to_numeric::<u64>(bits).expect("64 bits fit in u64.");
+            Ok((first, bits.len()))
+        })?;
+
+        let mut timestamps = vec![T::from_u64(prev).ok_or(InvalidCodeError::TimestampCodeError)?];
+        let mut prev_delta = 0_i64;
+        while !self.cursor.remaining()?.is_empty() {
+            let (dod, run) = self.cursor.decode_with(decode_record)?;
+            let delta = prev_delta.wrapping_add(dod);
+            for _ in 0..run {
+                prev = prev.wrapping_add(delta as u64);
+                timestamps.push(T::from_u64(prev).ok_or(InvalidCodeError::TimestampCodeError)?);
+            }
+            prev_delta = delta;
+        }
+        Ok(timestamps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(timestamps: &[u64]) -> Vec<u8> {
+        let mut enc = TimestampEncoder::new(Cursor::new(vec![]));
+        enc.encode(timestamps).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    fn decode(bytes: Vec<u8>) -> Vec<u64> {
+        TimestampDecoder::new(Cursor::new(bytes)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_regular_runs() {
+        let timestamps: Vec<u64> = (0..100_000).map(|i| 1_000 * i).collect();
+        let result = encode(&timestamps);
+        assert!(result.len() <= 16);
+        assert_eq!(decode(result), timestamps);
+
+        // Runs shorter than the minimum are written per timestamp.
+        let timestamps = [10, 20, 30, 40, 45, 50, 55, 60, 65];
+        assert_eq!(decode(encode(&timestamps)), timestamps);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut timestamps: Vec<u64> = (0..1000).map(|i| i * i % 977 + 60 * i).collect();
+        timestamps.extend([u64::MAX, 0, u64::MAX - 1, 5, 5, 5, 5, 5, 5, 3]);
+        assert_eq!(decode(encode(&timestamps)), timestamps);
+        assert_eq!(decode(encode(&[42])), vec![42]);
+        assert!(decode(encode(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = TimestampEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[0_u64, 1 << 62, 0]).unwrap();
+        assert!(enc.finalize().is_err());
+
+        let dec = TimestampDecoder::new(Cursor::new(vec![0x00, 0x01]));
+        assert!(dec.decode::<u64>().is_err());
+        let dec = TimestampDecoder::new(Cursor::new(encode(&[1 << 40, 0])));
+        assert!(dec.decode::<u32>().is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Instrumentation of encoders and decoders with the [`tracing`] crate.
+//!
+//! [`TracedEncoder`] and [`TracedDecoder`] wrap any encoder or decoder, and
+//! run it in a span named after the codec. Counters are emitted as events
+//! whose fields follow the `monotonic_counter.` convention of the
+//! `tracing-opentelemetry` metrics layer, so they can be exported as metrics:
+//!
+//! | Counter                   | Emitted by                      |
+//! |---------------------------|---------------------------------|
+//! | `idencode_values_encoded` | [`TracedEncoder`]               |
+//! | `idencode_values_decoded` | [`TracedDecoder`]               |
+//! | `idencode_decode_errors`  | [`TracedDecoder`]               |
+//! | `idencode_bits_written`   | [`BitWriter`](crate::BitWriter) |
+//! | `idencode_blocks_flushed` | [`BitWriter`](crate::BitWriter) |
+//!
+//! The bit writer emits its counters whenever it writes out its buffer, at
+//! the end of a segment or when it is finalized, whether or not the encoder
+//! is wrapped, so they are attributed to the span of the traced encoder that
+//! finalizes it.
+
+use std::io::{Read, Write};
+
+use tracing::Span;
+
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// A structure that wraps an encoder, running it in a span and counting the
+/// values that pass through it.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::code::traced::{TracedDecoder, TracedEncoder};
+/// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder};
+///
+/// let mut enc = TracedEncoder::new(GammaEncoder::new(Cursor::new(vec![])), "gamma");
+/// enc.encode(&[3_u32, 5, 14]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+///
+/// let dec = TracedDecoder::new(GammaDecoder::new(Cursor::new(result)), "gamma");
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![3, 5, 14]);
+/// ```
+pub struct TracedEncoder<E> {
+    inner: E,
+    span: Span,
+}
+
+impl<E> TracedEncoder<E> {
+    /// Creates a new encoder that traces the wrapped encoder under the name
+    /// of its codec.
+    pub fn new(inner: E, codec: &'static str) -> Self {
+        let span = tracing::debug_span!("idencode_encode", codec);
+        TracedEncoder { inner, span }
+    }
+
+    /// Returns a reference to the wrapped encoder.
+    pub fn get_ref(&self) -> &E {
+        &self.inner
+    }
+}
+
+impl<W: Write, E: Encoder<W>> Encoder<W> for TracedEncoder<E> {
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> std::io::Result<()> {
+        let _entered = self.span.enter();
+        tracing::trace!(monotonic_counter.idencode_values_encoded = nums.len() as u64);
+        self.inner.encode(nums)
+    }
+
+    fn finalize(self) -> std::io::Result<W> {
+        let _entered = self.span.enter();
+        self.inner.finalize()
+    }
+}
+
+impl<W: Write, E: NonBlockingEncoder<W>> NonBlockingEncoder<W> for TracedEncoder<E> {
+    fn finish(&mut self) -> std::io::Result<()> {
+        let _entered = self.span.enter();
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> std::io::Result<()> {
+        let _entered = self.span.enter();
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+/// A structure that wraps a decoder, running it in a span and counting the
+/// values it decodes, or its errors.
+pub struct TracedDecoder<D> {
+    inner: D,
+    span: Span,
+}
+
+impl<D> TracedDecoder<D> {
+    /// Creates a new decoder that traces the wrapped decoder under the name
+    /// of its codec.
+    pub fn new(inner: D, codec: &'static str) -> Self {
+        let span = tracing::debug_span!("idencode_decode", codec);
+        TracedDecoder { inner, span }
+    }
+}
+
+impl<R: Read, D: Decoder<R>> Decoder<R> for TracedDecoder<D> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let _entered = self.span.enter();
+        let result = self.inner.decode();
+        match &result {
+            Ok(nums) => {
+                tracing::trace!(monotonic_counter.idencode_values_decoded = nums.len() as u64)
+            }
+            Err(e) => tracing::warn!(monotonic_counter.idencode_decode_errors = 1_u64, error = %e),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GammaDecoder, GammaEncoder, VBDecoder};
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // A subscriber that records the counters of the events.
+    #[derive(Clone, Default)]
+    struct Counters(Arc<Mutex<Vec<(String, u64)>>>);
+
+    impl Visit for Counters {
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if let Some(name) = field.name().strip_prefix("monotonic_counter.") {
+                self.0.lock().unwrap().push((name.to_string(), value));
+            }
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    impl Subscriber for Counters {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    impl Counters {
+        fn sum(&self, name: &str) -> u64 {
+            let counters = self.0.lock().unwrap();
+            counters.iter().filter(|c| c.0 == name).map(|c| c.1).sum()
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_counters() {
+        let counters = Counters::default();
+        tracing::subscriber::with_default(counters.clone(), || {
+            let mut enc = TracedEncoder::new(GammaEncoder::new(Cursor::new(vec![])), "gamma");
+            enc.encode(&[3_u32, 5]).unwrap();
+            enc.encode(&[14_u32]).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+            let dec = TracedDecoder::new(GammaDecoder::new(Cursor::new(result)), "gamma");
+            assert_eq!(dec.decode::<u32>().unwrap(), vec![3, 5, 14]);
+        });
+        assert_eq!(counters.sum("idencode_values_encoded"), 3);
+        assert_eq!(counters.sum("idencode_values_decoded"), 3);
+        // 101 + 11001 + 1110110, without the terminating bit.
+        assert_eq!(counters.sum("idencode_bits_written"), 15);
+        assert_eq!(counters.sum("idencode_blocks_flushed"), 1);
+        assert_eq!(counters.sum("idencode_decode_errors"), 0);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let counters = Counters::default();
+        tracing::subscriber::with_default(counters.clone(), || {
+            let dec = TracedDecoder::new(VBDecoder::new(Cursor::new(vec![0b00000101])), "vb");
+            assert!(dec.decode::<u32>().is_err());
+        });
+        assert_eq!(counters.sum("idencode_decode_errors"), 1);
+        assert_eq!(counters.sum("idencode_values_decoded"), 0);
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// A structure that wraps an encoder and transforms a sorted list of IDs
+/// into its gaps, the differences between consecutive IDs, before encoding
+/// them with the wrapped encoder.
+///
+/// The first ID is encoded as is. The gaps of a dense list are much smaller
+/// than its IDs, so they take fewer bits with any universal or byte-aligned
+/// code. The gaps of a strictly increasing list are positive, but the first
+/// ID, or the gaps of repeated IDs, may be zero, so codes that cannot encode
+/// zero, like Elias Gamma, need lists that start from 1 and have no repeats.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder, GapDecoder, GapEncoder};
+///
+/// let mut enc = GapEncoder::new(GammaEncoder::new(Cursor::new(vec![])));
+/// enc.encode(&[3_u32, 5]).unwrap();
+/// enc.encode(&[14_u32]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// // The gaps 3, 2 and 9 in Elias Gamma.
+/// assert_eq!(result, vec![0b10110011, 0b10001100]);
+///
+/// let dec = GapDecoder::new(GammaDecoder::new(Cursor::new(result)));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![3, 5, 14]);
+/// ```
+pub struct GapEncoder<E> {
+    inner: E,
+    prev: u64,
+}
+
+impl<E> GapEncoder<E> {
+    pub fn new(inner: E) -> Self {
+        GapEncoder { inner, prev: 0 }
+    }
+}
+
+impl<W: Write, E: Encoder<W>> Encoder<W> for GapEncoder<E> {
+    /// Transforms a sequence of numbers into gaps, and encodes them. The
+    /// list carries over across calls, so it may be encoded in parts.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the
+    /// numbers are not sorted in non-decreasing order.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut gaps = Vec::with_capacity(nums.len());
+        let mut prev = self.prev;
+        for num in nums {
+            let num = num.to_u64();
+            let gap = num.checked_sub(prev).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Numbers are not sorted.")
+            })?;
+            gaps.push(gap);
+            prev = num;
+        }
+        self.inner.encode(&gaps)?;
+        self.prev = prev;
+        Ok(())
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+impl<W: Write, E: NonBlockingEncoder<W>> NonBlockingEncoder<W> for GapEncoder<E> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+/// A structure that wraps a decoder, and restores the sorted list of IDs
+/// transformed by [`GapEncoder`] by summing up the decoded gaps.
+pub struct GapDecoder<D> {
+    inner: D,
+}
+
+impl<D> GapDecoder<D> {
+    pub fn new(inner: D) -> Self {
+        GapDecoder { inner }
+    }
+}
+
+impl<R: Read, D: Decoder<R>> Decoder<R> for GapDecoder<D> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let mut prev = 0_u64;
+        self.inner
+            .decode::<u64>()?
+            .into_iter()
+            .map(|gap| {
+                prev = prev
+                    .checked_add(gap)
+                    .ok_or(InvalidCodeError::GapCodeError)?;
+                T::from_u64(prev).ok_or(InvalidCodeError::GapCodeError)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncodeIterExt, VBDecoder, VBEncoder};
+    use std::io::Cursor;
+
+    fn encode(nums: &[u64]) -> Vec<u8> {
+        let mut enc = GapEncoder::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut ids = vec![0_u64, 0, 7];
+        for i in 1..1000_u64 {
+            ids.push(ids[ids.len() - 1] + (i.wrapping_mul(0x9E3779B97F4A7C15) >> 54));
+        }
+        ids.push(u64::MAX);
+        let dec = GapDecoder::new(VBDecoder::new(Cursor::new(encode(&ids))));
+        assert_eq!(dec.decode::<u64>().unwrap(), ids);
+
+        // The same bytes as the gaps of the iterator adaptor.
+        let ids: Vec<u64> = (1..300).map(|i| i * i).collect();
+        let bytes: Vec<u8> = ids.iter().copied().deltas().vb_encoded().collect();
+        assert_eq!(encode(&ids), bytes);
+    }
+
+    #[test]
+    fn test_unsorted() {
+        let mut enc = GapEncoder::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[5_u32, 9]).unwrap();
+        assert!(enc.encode(&[8_u32]).is_err());
+        // A failed call does not move the list on.
+        enc.encode(&[9_u32, 10]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = GapDecoder::new(VBDecoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<u32>().unwrap(), vec![5, 9, 9, 10]);
+    }
+
+    #[test]
+    fn test_errs() {
+        let dec = GapDecoder::new(VBDecoder::new(Cursor::new(encode(&[200, 300]))));
+        assert_eq!(dec.decode::<u8>(), Err(InvalidCodeError::GapCodeError));
+
+        let mut enc = VBEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[u64::MAX, 1]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = GapDecoder::new(VBDecoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<u64>(), Err(InvalidCodeError::GapCodeError));
+    }
+}
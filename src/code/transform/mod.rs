@@ -0,0 +1,179 @@
+pub mod gaps;
+pub mod rle0;
+
+use std::io::{self, Read, Write};
+
+use crate::code::{Decoder, Encoder};
+use crate::error::InvalidCodeError;
+use crate::num::convert::{unzigzag, zigzag};
+use crate::num::Signed;
+
+/// A structure that wraps an encoder or a decoder of unsigned integers, and
+/// encodes or decodes signed integers with it, by zigzagging them.
+///
+/// Zigzagging interleaves the negative and the positive integers, mapping 0,
+/// -1, 1, -2, 2, ... to 0, 1, 2, 3, 4, ..., so integers of small magnitude, of
+/// either sign, become small unsigned integers, which any integer code
+/// compresses well. The zigzagged integers are shifted up by an offset, which
+/// is 0 with [`ZigZag::new`]; codes that cannot encode 0, like Elias Gamma and
+/// Elias Delta, need an offset of 1. The signed integers are encoded with
+/// [`EncodeSigned`] and decoded with [`DecodeSigned`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{DecodeSigned, EncodeSigned, GammaDecoder, GammaEncoder, VBDecoder, VBEncoder, ZigZag};
+///
+/// let mut enc = ZigZag::new(VBEncoder::new(Cursor::new(vec![])));
+/// enc.encode(&[-3_i32, 7, -1, 0]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// assert_eq!(result, vec![0x85, 0x8E, 0x81, 0x80]);
+///
+/// let dec = ZigZag::new(VBDecoder::new(Cursor::new(result)));
+/// assert_eq!(dec.decode::<i32>().unwrap(), vec![-3, 7, -1, 0]);
+///
+/// // Elias Gamma cannot encode 0, so the zigzagged integers start from 1.
+/// let mut enc = ZigZag::with_offset(GammaEncoder::new(Cursor::new(vec![])), 1);
+/// enc.encode(&[0_i64, -1]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// let dec = ZigZag::with_offset(GammaDecoder::new(Cursor::new(result)), 1);
+/// assert_eq!(dec.decode::<i64>().unwrap(), vec![0, -1]);
+/// ```
+pub struct ZigZag<C> {
+    inner: C,
+    offset: u64,
+}
+
+impl<C> ZigZag<C> {
+    pub fn new(inner: C) -> Self {
+        Self::with_offset(inner, 0)
+    }
+
+    /// Wraps an encoder or a decoder, shifting the zigzagged integers up by
+    /// `offset`, which must match between the encoder and the decoder.
+    pub fn with_offset(inner: C, offset: u64) -> Self {
+        ZigZag { inner, offset }
+    }
+
+    /// Returns the offset of the zigzagged integers.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Unwraps the encoder or the decoder.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+/// This trait provides the encoding of signed integers, through an encoder
+/// of unsigned integers.
+pub trait EncodeSigned<W: Write> {
+    /// Encodes and writes a sequence of signed numbers.
+    fn encode<T: Signed>(&mut self, nums: &[T]) -> io::Result<()>;
+
+    /// Finalizes the encoding, returning the writer.
+    fn finalize(self) -> io::Result<W>;
+}
+
+/// This trait provides the decoding of signed integers, through a decoder of
+/// unsigned integers.
+pub trait DecodeSigned<R: Read> {
+    /// Reads and decodes a sequence of signed numbers.
+    fn decode<T: Signed>(self) -> Result<Vec<T>, InvalidCodeError>;
+}
+
+impl<W: Write, E: Encoder<W>> EncodeSigned<W> for ZigZag<E> {
+    /// Zigzags a sequence of signed numbers, and encodes them with the wrapped
+    /// encoder.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a
+    /// zigzagged number, shifted up by the offset, does not fit in 64 bits.
+    fn encode<T: Signed>(&mut self, nums: &[T]) -> io::Result<()> {
+        let nums = nums
+            .iter()
+            .map(|num| {
+                zigzag(num.to_i64())
+                    .checked_add(self.offset)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Zigzagged number does not fit in 64 bits with the offset.",
+                        )
+                    })
+            })
+            .collect::<io::Result<Vec<u64>>>()?;
+        self.inner.encode(&nums)
+    }
+
+    fn finalize(self) -> io::Result<W> {
+        self.inner.finalize()
+    }
+}
+
+impl<R: Read, D: Decoder<R>> DecodeSigned<R> for ZigZag<D> {
+    /// Decodes a sequence of zigzagged numbers with the wrapped decoder, and
+    /// maps them back to signed numbers.
+    fn decode<T: Signed>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let offset = self.offset;
+        self.inner
+            .decode::<u64>()?
+            .into_iter()
+            .map(|num| {
+                num.checked_sub(offset)
+                    .map(unzigzag)
+                    .and_then(T::from_i64)
+                    .ok_or(InvalidCodeError::ZigZagCodeError)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeltaDecoder, DeltaEncoder, VBDecoder, VBEncoder};
+    use std::io::Cursor;
+
+    fn nums() -> Vec<i64> {
+        let mut nums: Vec<i64> = (0..1000_u64)
+            .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15) as i64) >> (i % 64))
+            .collect();
+        nums.extend([0, -1, 1, i64::MIN, i64::MAX]);
+        nums
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut enc = ZigZag::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(&nums()).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = ZigZag::new(VBDecoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<i64>().unwrap(), nums());
+
+        let small: Vec<i8> = (-128..=127).collect();
+        let mut enc = ZigZag::with_offset(DeltaEncoder::new(Cursor::new(vec![])), 1);
+        assert_eq!(enc.offset(), 1);
+        enc.encode(&small).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = ZigZag::with_offset(DeltaDecoder::new(Cursor::new(result)), 1);
+        assert_eq!(dec.decode::<i8>().unwrap(), small);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut enc = ZigZag::with_offset(DeltaEncoder::new(Cursor::new(vec![])), 1);
+        assert!(enc.encode(&[i64::MIN]).is_err());
+        assert!(enc.encode(&[i64::MAX]).is_ok());
+
+        let mut enc = ZigZag::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[-200_i32, 0]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = ZigZag::new(VBDecoder::new(Cursor::new(result.clone())));
+        assert_eq!(dec.decode::<i8>(), Err(InvalidCodeError::ZigZagCodeError));
+        // The 0 is below the offset.
+        let dec = ZigZag::with_offset(VBDecoder::new(Cursor::new(result)), 1);
+        assert_eq!(dec.decode::<i32>(), Err(InvalidCodeError::ZigZagCodeError));
+    }
+}
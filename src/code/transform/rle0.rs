@@ -0,0 +1,262 @@
+use std::io::{self, Read, Write};
+
+use crate::code::{Decoder, Encoder, NonBlockingEncoder};
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// The value that marks a run of sentinels in the transformed sequence.
+const ESCAPE: u64 = 1;
+
+/// A structure that wraps an encoder, and replaces each run of a sentinel
+/// value, 0 by default, with an escape followed by the length of the run,
+/// before encoding the sequence with the wrapped encoder.
+///
+/// A run of sentinels becomes the escape 1 and the length of the run. Every
+/// other value is shifted past the escape and the sentinel, so the value `v`
+/// becomes `v + 2` if it is less than the sentinel, and `v + 1` otherwise.
+/// The transformed sequence starts from 1, so it can be encoded with any
+/// integer code, including Elias Gamma and Elias Delta. The sentinel must
+/// match between the encoder and the decoder.
+///
+/// Gaps of update logs, or counters that rarely change, are mostly zeros, and
+/// a run of thousands of zeros takes two codewords, instead of one per zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Decoder, Encoder, GammaDecoder, GammaEncoder, Rle0Decoder, Rle0Encoder};
+///
+/// let mut deltas = vec![0_u32; 1000];
+/// deltas[500] = 3;
+/// let mut enc = Rle0Encoder::new(GammaEncoder::new(Cursor::new(vec![])));
+/// enc.encode(&deltas[..200]).unwrap();
+/// enc.encode(&deltas[200..]).unwrap();
+/// let result = enc.finalize().unwrap().into_inner();
+/// // The escape, 500, 4, the escape and 499 in Elias Gamma.
+/// assert_eq!(result.len(), 6);
+///
+/// let dec = Rle0Decoder::new(GammaDecoder::new(Cursor::new(result)));
+/// assert_eq!(dec.decode::<u32>().unwrap(), deltas);
+/// ```
+pub struct Rle0Encoder<E> {
+    inner: E,
+    sentinel: u64,
+    run: u64,
+}
+
+impl<E> Rle0Encoder<E> {
+    /// Creates a new encoder, that replaces the runs of zeros.
+    pub fn new(inner: E) -> Self {
+        Self::with_sentinel(inner, 0)
+    }
+
+    /// Creates a new encoder, that replaces the runs of `sentinel`.
+    pub fn with_sentinel(inner: E, sentinel: u64) -> Self {
+        Rle0Encoder {
+            inner,
+            sentinel,
+            run: 0,
+        }
+    }
+
+    /// Returns the value whose runs are replaced.
+    pub fn sentinel(&self) -> u64 {
+        self.sentinel
+    }
+
+    /// Maps a value other than the sentinel past the escape and the sentinel.
+    fn shift(&self, num: u64) -> Option<u64> {
+        let num = if num < self.sentinel { num } else { num - 1 };
+        num.checked_add(2)
+    }
+
+    /// Encodes the current run of sentinels, if any.
+    fn flush_run<W: Write>(&mut self) -> io::Result<()>
+    where
+        E: Encoder<W>,
+    {
+        if self.run > 0 {
+            self.inner.encode(&[ESCAPE, self.run])?;
+            self.run = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, E: Encoder<W>> Encoder<W> for Rle0Encoder<E> {
+    /// Transforms a sequence of numbers, and encodes it. Runs may continue
+    /// across calls, so the last run is only encoded when the encoder is
+    /// finalized.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if a number
+    /// does not fit in a `u64` once shifted, which only happens for the
+    /// largest number other than the sentinel.
+    fn encode<T: Numeric>(&mut self, nums: &[T]) -> io::Result<()> {
+        let mut values = Vec::with_capacity(nums.len());
+        let mut run = self.run;
+        for num in nums {
+            let num = num.to_u64();
+            if num == self.sentinel {
+                run += 1;
+                continue;
+            }
+            let value = self.shift(num).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Number out of the range of the RLE0 transform.",
+                )
+            })?;
+            if run > 0 {
+                values.extend([ESCAPE, run]);
+                run = 0;
+            }
+            values.push(value);
+        }
+        self.inner.encode(&values)?;
+        self.run = run;
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<W> {
+        self.flush_run()?;
+        self.inner.finalize()
+    }
+}
+
+impl<W: Write, E: NonBlockingEncoder<W>> NonBlockingEncoder<W> for Rle0Encoder<E> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush_run()?;
+        self.inner.finish()
+    }
+
+    fn resume_flush(&mut self) -> io::Result<()> {
+        self.inner.resume_flush()
+    }
+
+    fn into_inner(self) -> W {
+        self.inner.into_inner()
+    }
+}
+
+/// A structure that wraps a decoder, and restores the sequence transformed
+/// by [`Rle0Encoder`] by expanding the escaped runs of the sentinel.
+pub struct Rle0Decoder<D> {
+    inner: D,
+    sentinel: u64,
+}
+
+impl<D> Rle0Decoder<D> {
+    /// Creates a new decoder, that expands the runs of zeros.
+    pub fn new(inner: D) -> Self {
+        Self::with_sentinel(inner, 0)
+    }
+
+    /// Creates a new decoder, that expands the runs of `sentinel`.
+    pub fn with_sentinel(inner: D, sentinel: u64) -> Self {
+        Rle0Decoder { inner, sentinel }
+    }
+}
+
+impl<R: Read, D: Decoder<R>> Decoder<R> for Rle0Decoder<D> {
+    fn decode<T: Numeric>(self) -> Result<Vec<T>, InvalidCodeError> {
+        let sentinel = T::from_u64(self.sentinel);
+        let mut values = self.inner.decode::<u64>()?.into_iter();
+        let mut nums = vec![];
+        while let Some(value) = values.next() {
+            match value {
+                ESCAPE => {
+                    let run = values.next().ok_or(InvalidCodeError::Rle0CodeError)?;
+                    let sentinel = sentinel.ok_or(InvalidCodeError::Rle0CodeError)?;
+                    if run == 0 {
+                        return Err(InvalidCodeError::Rle0CodeError);
+                    }
+                    nums.extend((0..run).map(|_| sentinel));
+                }
+                0 => return Err(InvalidCodeError::Rle0CodeError),
+                _ => {
+                    let num = value - 2;
+                    let num = if num < self.sentinel { num } else { num + 1 };
+                    nums.push(T::from_u64(num).ok_or(InvalidCodeError::Rle0CodeError)?);
+                }
+            }
+        }
+        Ok(nums)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeltaDecoder, DeltaEncoder, VBDecoder, VBEncoder};
+    use std::io::Cursor;
+
+    fn transformed(nums: &[u64], sentinel: u64) -> Vec<u64> {
+        let mut enc = Rle0Encoder::with_sentinel(VBEncoder::new(Cursor::new(vec![])), sentinel);
+        enc.encode(nums).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        VBDecoder::new(Cursor::new(result)).decode().unwrap()
+    }
+
+    #[test]
+    fn test_transform() {
+        assert_eq!(transformed(&[0, 0, 0, 5, 0, 1], 0), vec![1, 3, 6, 1, 1, 2]);
+        assert_eq!(
+            transformed(&[7, 7, 0, 6, 8, 7], 7),
+            vec![1, 2, 2, 8, 9, 1, 1]
+        );
+        assert_eq!(transformed(&[], 0), Vec::<u64>::new());
+        assert_eq!(transformed(&[u64::MAX], u64::MAX), vec![1, 1]);
+        assert_eq!(transformed(&[u64::MAX - 2], u64::MAX), vec![u64::MAX]);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut nums = vec![];
+        for i in 0..2000_u64 {
+            let hash = i.wrapping_mul(0x9E3779B97F4A7C15) >> 58;
+            nums.push(if hash < 40 { 0 } else { hash });
+        }
+        nums.extend([0; 5000]);
+        for sentinel in [0, 1, 42, 63] {
+            let mut enc =
+                Rle0Encoder::with_sentinel(DeltaEncoder::new(Cursor::new(vec![])), sentinel);
+            for chunk in nums.chunks(333) {
+                enc.encode(chunk).unwrap();
+            }
+            let result = enc.finalize().unwrap().into_inner();
+            let dec = Rle0Decoder::with_sentinel(DeltaDecoder::new(Cursor::new(result)), sentinel);
+            assert_eq!(dec.decode::<u64>().unwrap(), nums);
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut enc = Rle0Encoder::new(VBEncoder::new(Cursor::new(vec![])));
+        enc.encode(&[0_u64, 0]).unwrap();
+        assert!(enc.encode(&[3, u64::MAX]).is_err());
+        let mut max_enc = Rle0Encoder::with_sentinel(VBEncoder::new(vec![]), u64::MAX);
+        assert!(max_enc.encode(&[u64::MAX - 1]).is_err());
+        // A failed call does not move the sequence on.
+        enc.encode(&[0_u64, 2]).unwrap();
+        let result = enc.finalize().unwrap().into_inner();
+        let dec = Rle0Decoder::new(VBDecoder::new(Cursor::new(result)));
+        assert_eq!(dec.decode::<u64>().unwrap(), vec![0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_errs() {
+        let decode = |values: &[u64], sentinel: u64| {
+            let mut enc = VBEncoder::new(Cursor::new(vec![]));
+            enc.encode(values).unwrap();
+            let result = enc.finalize().unwrap().into_inner();
+            Rle0Decoder::with_sentinel(VBDecoder::new(Cursor::new(result)), sentinel).decode::<u8>()
+        };
+        assert_eq!(decode(&[2, 1], 0), Err(InvalidCodeError::Rle0CodeError));
+        assert_eq!(decode(&[1, 0], 0), Err(InvalidCodeError::Rle0CodeError));
+        assert_eq!(decode(&[0], 0), Err(InvalidCodeError::Rle0CodeError));
+        assert_eq!(decode(&[300], 0), Err(InvalidCodeError::Rle0CodeError));
+        assert_eq!(decode(&[1, 2], 300), Err(InvalidCodeError::Rle0CodeError));
+        assert_eq!(decode(&[2, 3, 2], 300), Ok(vec![0, 1, 0]));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::BitVecLengthError;
+use crate::BitVec;
+
+const WORD_BITS: usize = 64;
+
+/// A bit-array of fixed size, stored on the stack in `N` 64-bit words, so
+/// it holds `64 * N` bits.
+///
+/// The size is given in words, rather than bits, because the length of an
+/// array cannot be computed from a const generic parameter on stable Rust.
+/// The bits are in most-significant-bit first order, like in [`BitVec`], so
+/// converting between the two keeps the positions of the bits.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::BitArray;
+///
+/// let mut flags = BitArray::<1>::new();
+/// flags.set(3, true);
+/// flags.set(40, true);
+/// assert_eq!(flags.get(3), Some(true));
+/// assert_eq!(flags.get(4), Some(false));
+/// assert_eq!(flags.get(64), None);
+/// assert_eq!(flags.count_ones(), 2);
+/// assert_eq!(flags.iter_ones().collect::<Vec<_>>(), vec![3, 40]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitArray<const N: usize> {
+    words: [u64; N],
+}
+
+impl<const N: usize> BitArray<N> {
+    /// The number of bits in the bit-array.
+    pub const LEN: usize = N * WORD_BITS;
+
+    /// Creates a bit-array with all the bits set to 0.
+    pub const fn new() -> Self {
+        BitArray { words: [0; N] }
+    }
+
+    /// Returns the number of bits in the bit-array.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        Self::LEN
+    }
+
+    /// Returns `true` if the bit-array holds no bits, that is if `N` is 0.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns the bit at position `index`, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        let word = self.words.get(index / WORD_BITS)?;
+        Some(word & (1 << (WORD_BITS - 1 - index % WORD_BITS)) != 0)
+    }
+
+    /// Sets the bit at position `index` to `bit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= 64 * N`.
+    #[inline]
+    pub fn set(&mut self, index: usize, bit: bool) {
+        assert!(index < Self::LEN, "The index is out of bounds.");
+        let mask = 1 << (WORD_BITS - 1 - index % WORD_BITS);
+        match bit {
+            true => self.words[index / WORD_BITS] |= mask,
+            false => self.words[index / WORD_BITS] &= !mask,
+        }
+    }
+
+    /// Returns the number of bits set to 1.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns an iterator over the bits.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..Self::LEN).map(|i| self.get(i).expect("The position is within the bit-array."))
+    }
+
+    /// Returns an iterator over the positions of the 1-bits, in increasing
+    /// order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                let pos = word.leading_zeros() as usize;
+                (word != 0).then(|| {
+                    word &= !(1 << (WORD_BITS - 1 - pos));
+                    idx * WORD_BITS + pos
+                })
+            })
+        })
+    }
+}
+
+impl<const N: usize> Default for BitArray<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> From<BitArray<N>> for BitVec {
+    fn from(array: BitArray<N>) -> Self {
+        let bytes = array.words.iter().flat_map(|w| w.to_be_bytes()).collect();
+        BitVec::new(bytes)
+    }
+}
+
+impl<const N: usize> TryFrom<&BitVec> for BitArray<N> {
+    type Error = BitVecLengthError;
+
+    /// Copies the bits of a bit-vector to a bit-array, setting the rest of
+    /// the bits to 0.
+    ///
+    /// Returns an error if the bit-vector is longer than the bit-array.
+    fn try_from(bitvec: &BitVec) -> Result<Self, Self::Error> {
+        if bitvec.len() > Self::LEN {
+            return Err(BitVecLengthError);
+        }
+        let mut array = Self::new();
+        let bytes = &bitvec.as_bytes()[..bitvec.len().div_ceil(8)];
+        for (word, chunk) in array.words.iter_mut().zip(bytes.chunks(8)) {
+            let mut word_bytes = [0; 8];
+            word_bytes[..chunk.len()].copy_from_slice(chunk);
+            *word = u64::from_be_bytes(word_bytes);
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+
+    #[test]
+    fn test_get_set() {
+        let mut array = BitArray::<3>::default();
+        assert_eq!(array.len(), 192);
+        for index in [0, 63, 64, 100, 191] {
+            array.set(index, true);
+        }
+        array.set(100, false);
+        assert_eq!(array.count_ones(), 4);
+        assert_eq!(array.iter_ones().collect::<Vec<_>>(), vec![0, 63, 64, 191]);
+        let ones: Vec<usize> = (0..192)
+            .filter(|i| array.iter().nth(*i) == Some(true))
+            .collect();
+        assert_eq!(ones, vec![0, 63, 64, 191]);
+        assert!(BitArray::<0>::new().is_empty());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let bitvec = bitvec![true, false, true, true, false, false, true, true, true, false];
+        let array = BitArray::<1>::try_from(&bitvec).unwrap();
+        assert_eq!(
+            array.iter_ones().collect::<Vec<_>>(),
+            vec![0, 2, 3, 6, 7, 8]
+        );
+        let back = BitVec::from(array);
+        assert_eq!(back.len(), 64);
+        assert_eq!(back.subvec(..10), bitvec);
+
+        assert!(BitArray::<1>::try_from(&bitvec![false; 65]).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        BitArray::<1>::new().set(64, true);
+    }
+}
@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::BitVec;
+
+const WORD_BITS: usize = 64;
+
+/// A bitset of fixed length, whose bits can be set and cleared concurrently
+/// through a shared reference.
+///
+/// The bits are grouped in 64-bit atomic words, in most-significant-bit first
+/// order, like in [`BitVec`]. It can be shared between threads, for example as
+/// the set of the IDs that have been seen while building an index in
+/// parallel, and converted to a [`BitVec`] once the threads are done.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use idencode::collections::AtomicBitVec;
+///
+/// let seen = AtomicBitVec::new(1000);
+/// thread::scope(|s| {
+///     for t in 0..4 {
+///         let seen = &seen;
+///         s.spawn(move || {
+///             for id in (t..1000).step_by(3) {
+///                 seen.set(id);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(seen.to_bitvec().iter_ones().count(), 1000);
+/// ```
+#[derive(Debug)]
+pub struct AtomicBitVec {
+    words: Vec<AtomicU64>,
+    len: usize,
+}
+
+impl AtomicBitVec {
+    /// Creates a bitset of `len` bits, all set to 0.
+    pub fn new(len: usize) -> Self {
+        let words = (0..len.div_ceil(WORD_BITS))
+            .map(|_| AtomicU64::new(0))
+            .collect();
+        AtomicBitVec { words, len }
+    }
+
+    /// Returns the number of bits in the bitset.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the bitset contains no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns the word that holds a bit, along with the mask of the bit.
+    fn locate(&self, index: usize) -> (&AtomicU64, u64) {
+        assert!(index < self.len, "The index is out of bounds.");
+        let mask = 1 << (WORD_BITS - 1 - index % WORD_BITS);
+        (&self.words[index / WORD_BITS], mask)
+    }
+
+    /// Returns the bit at position `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn get(&self, index: usize) -> bool {
+        let (word, mask) = self.locate(index);
+        word.load(Ordering::Acquire) & mask != 0
+    }
+
+    /// Sets the bit at position `index` to 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&self, index: usize) {
+        self.test_and_set(index);
+    }
+
+    /// Sets the bit at position `index` to 1, returning its previous value.
+    /// Exactly one of the threads that set the same bit concurrently sees
+    /// `false`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::AtomicBitVec;
+    ///
+    /// let seen = AtomicBitVec::new(10);
+    /// assert!(!seen.test_and_set(7));
+    /// assert!(seen.test_and_set(7));
+    /// ```
+    pub fn test_and_set(&self, index: usize) -> bool {
+        let (word, mask) = self.locate(index);
+        word.fetch_or(mask, Ordering::AcqRel) & mask != 0
+    }
+
+    /// Sets the bit at position `index` to 0, returning its previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn clear(&self, index: usize) -> bool {
+        let (word, mask) = self.locate(index);
+        word.fetch_and(!mask, Ordering::AcqRel) & mask != 0
+    }
+
+    /// Copies the bits to a bit-vector.
+    pub fn to_bitvec(&self) -> BitVec {
+        if self.len == 0 {
+            return BitVec::default();
+        }
+        let mut bytes: Vec<u8> = self
+            .words
+            .iter()
+            .flat_map(|word| word.load(Ordering::Acquire).to_be_bytes())
+            .collect();
+        bytes.truncate(self.len.div_ceil(8));
+        BitVec::with_len(bytes, self.len).expect("The length matches the bytes.")
+    }
+}
+
+impl From<&BitVec> for AtomicBitVec {
+    fn from(bitvec: &BitVec) -> Self {
+        let n_bytes = bitvec.len().div_ceil(8);
+        let words = bitvec.as_bytes()[..n_bytes]
+            .chunks(8)
+            .map(|chunk| {
+                let mut bytes = [0; 8];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                AtomicU64::new(u64::from_be_bytes(bytes))
+            })
+            .collect();
+        AtomicBitVec {
+            words,
+            len: bitvec.len(),
+        }
+    }
+}
+
+impl From<AtomicBitVec> for BitVec {
+    fn from(bitset: AtomicBitVec) -> Self {
+        bitset.to_bitvec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+    use std::thread;
+
+    #[test]
+    fn test_set_clear() {
+        let bitset = AtomicBitVec::new(130);
+        assert_eq!(bitset.len(), 130);
+        for index in [0, 63, 64, 129] {
+            assert!(!bitset.get(index));
+            assert!(!bitset.test_and_set(index));
+            assert!(bitset.get(index));
+        }
+        assert!(bitset.clear(63));
+        assert!(!bitset.clear(63));
+        let ones: Vec<usize> = bitset.to_bitvec().iter_ones().collect();
+        assert_eq!(ones, vec![0, 64, 129]);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let bitvec = bitvec![true, false, true, true, false, false, true, true, true, false];
+        let bitset = AtomicBitVec::from(&bitvec);
+        assert!(bitset.get(3) && !bitset.get(4));
+        assert_eq!(BitVec::from(bitset), bitvec);
+        assert!(AtomicBitVec::new(0).to_bitvec().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let bitset = AtomicBitVec::new(10_000);
+        let first = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| (0..10_000).filter(|i| !bitset.test_and_set(*i)).count()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .sum::<usize>()
+        });
+        assert_eq!(first, 10_000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        AtomicBitVec::new(64).set(64);
+    }
+}
@@ -0,0 +1,164 @@
+//! Conversions between the bit-vectors of this crate and the ones of the
+//! [`bitvec`] crate.
+//!
+//! The bits of a [`BitVec`] are stored in most-significant-bit first order,
+//! so they have the same layout as a `bitvec::vec::BitVec<u8, Msb0>`, and the
+//! conversions between the two move the bytes without copying the bits. The
+//! conversions to and from any other storage type or bit order copy the bits
+//! one at a time, keeping their positions, so the bytes differ.
+//!
+//! # Examples
+//!
+//! ```
+//! use bitvec::prelude::{bitvec, Lsb0, Msb0};
+//! use idencode::BitVec;
+//!
+//! let bits = BitVec::with_len(vec![0b10110000], 4).unwrap();
+//!
+//! let msb0: bitvec::vec::BitVec<u8, Msb0> = bits.clone().into();
+//! assert_eq!(msb0, bitvec![u8, Msb0; 1, 0, 1, 1]);
+//! assert_eq!(msb0.as_raw_slice(), [0b10110000]);
+//!
+//! let lsb0 = bitvec::vec::BitVec::<u8, Lsb0>::from(&bits);
+//! assert_eq!(lsb0, bitvec![u8, Lsb0; 1, 0, 1, 1]);
+//! assert_eq!(lsb0.as_raw_slice(), [0b00001101]);
+//!
+//! assert_eq!(BitVec::from(lsb0.as_bitslice()), bits);
+//! assert_eq!(BitVec::from(msb0), bits);
+//! ```
+
+use ::bitvec::order::{BitOrder, Msb0};
+use ::bitvec::slice::BitSlice as ExtBitSlice;
+use ::bitvec::store::BitStore;
+use ::bitvec::vec::BitVec as ExtBitVec;
+
+use crate::collections::{BitArray, BitSlice, BitVec};
+use crate::error::BitVecLengthError;
+
+impl From<BitVec> for ExtBitVec<u8, Msb0> {
+    /// Moves the bytes of a bit-vector, which have the same layout.
+    fn from(bitvec: BitVec) -> Self {
+        let len = bitvec.len();
+        let mut ext = ExtBitVec::from_vec(bitvec.into_bytes());
+        ext.truncate(len);
+        ext
+    }
+}
+
+impl<T: BitStore, O: BitOrder> From<&BitVec> for ExtBitVec<T, O> {
+    /// Copies the bits of a bit-vector, in the bit order `O`.
+    fn from(bitvec: &BitVec) -> Self {
+        bitvec.slice(..).into()
+    }
+}
+
+impl<T: BitStore, O: BitOrder> From<BitSlice<'_>> for ExtBitVec<T, O> {
+    /// Copies the bits of a view, in the bit order `O`.
+    fn from(slice: BitSlice<'_>) -> Self {
+        (0..slice.len())
+            .map(|i| slice.get(i).expect("The position is within the view."))
+            .collect()
+    }
+}
+
+impl From<ExtBitVec<u8, Msb0>> for BitVec {
+    /// Moves the bytes of a bit-vector, which have the same layout.
+    fn from(mut ext: ExtBitVec<u8, Msb0>) -> Self {
+        let len = ext.len();
+        if len == 0 {
+            return BitVec::default();
+        }
+        ext.force_align();
+        ext.set_uninitialized(false);
+        BitVec::with_len(ext.into_vec(), len).expect("The length matches the bytes.")
+    }
+}
+
+impl<T: BitStore, O: BitOrder> From<&ExtBitSlice<T, O>> for BitVec {
+    /// Copies the bits of a bit-slice, in most-significant-bit first order.
+    fn from(slice: &ExtBitSlice<T, O>) -> Self {
+        let mut bitvec = BitVec::default();
+        for bit in slice.iter().by_vals() {
+            bitvec.push(bit);
+        }
+        bitvec
+    }
+}
+
+impl<const N: usize, T: BitStore, O: BitOrder> TryFrom<&ExtBitSlice<T, O>> for BitArray<N> {
+    type Error = BitVecLengthError;
+
+    /// Copies the bits of a bit-slice to a bit-array, setting the rest of the
+    /// bits to 0.
+    ///
+    /// Returns an error if the bit-slice is longer than the bit-array.
+    fn try_from(slice: &ExtBitSlice<T, O>) -> Result<Self, Self::Error> {
+        if slice.len() > Self::LEN {
+            return Err(BitVecLengthError);
+        }
+        let mut array = Self::new();
+        for index in slice.iter_ones() {
+            array.set(index, true);
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::bitvec::order::Lsb0;
+    use ::bitvec::view::BitView;
+
+    fn bits() -> BitVec {
+        let mut bitvec = BitVec::default();
+        for i in 0..77_u32 {
+            bitvec.push(i.wrapping_mul(0x9E3779B9) >> 31 == 1);
+        }
+        bitvec
+    }
+
+    #[test]
+    fn test_msb0_round_trip() {
+        let bitvec = bits();
+        let ext = ExtBitVec::<u8, Msb0>::from(bitvec.clone());
+        assert_eq!(ext.len(), 77);
+        assert_eq!(ext.as_raw_slice(), bitvec.as_bytes());
+        assert_eq!(BitVec::from(ext), bitvec);
+        assert_eq!(
+            BitVec::from(ExtBitVec::<u8, Msb0>::new()),
+            BitVec::default()
+        );
+
+        // A bit-vector whose first bits have been removed is realigned.
+        let mut ext = ExtBitVec::<u8, Msb0>::from(bitvec.clone());
+        ext.drain(..3);
+        assert_eq!(BitVec::from(ext), bitvec.subvec(3..));
+    }
+
+    #[test]
+    fn test_other_orders() {
+        let bitvec = bits();
+        let lsb0 = ExtBitVec::<u16, Lsb0>::from(&bitvec);
+        assert!((0..77).all(|i| lsb0[i] == bitvec.slice(..).get(i).unwrap()));
+        assert_eq!(BitVec::from(lsb0.as_bitslice()), bitvec);
+
+        let slice = bitvec.slice(5..20);
+        let ext = ExtBitVec::<u8, Lsb0>::from(slice);
+        assert_eq!(BitVec::from(ext.as_bitslice()), slice.to_bitvec());
+
+        let bytes = [0b00000001_u8];
+        assert_eq!(
+            BitVec::from(bytes.view_bits::<Lsb0>()).as_bytes(),
+            [0b10000000]
+        );
+    }
+
+    #[test]
+    fn test_bit_array() {
+        let bytes = [0b00000101_u8, 0b1];
+        let array = BitArray::<1>::try_from(bytes.view_bits::<Lsb0>()).unwrap();
+        assert_eq!(array.iter_ones().collect::<Vec<_>>(), vec![0, 2, 8]);
+        assert!(BitArray::<1>::try_from([0_u64; 2].view_bits::<Msb0>()).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use std::hash::Hash;
+use std::io::Cursor;
+
+use super::hash::{hash, reduce};
+use crate::code::global::vb::{decode_vb_prefix, write_vb_bytes};
+use crate::error::InvalidCodeError;
+use crate::{BitVec, Decoder, Encoder, RleDecoder, RleEncoder};
+
+/// The tag of a filter whose bytes are serialized as they are.
+const TAG_RAW: u8 = 0;
+
+/// The tag of a filter whose bytes are serialized with run-length encoding.
+const TAG_RLE: u8 = 1;
+
+/// A Bloom filter, a probabilistic set whose bits are stored in a [`BitVec`].
+///
+/// An element is inserted by setting the bits at `k` positions derived from
+/// its hash, and is found if all of them are set. An element that was inserted
+/// is always found, while one that was not may be found, with a rate of false
+/// positives that depends on the number of bits, of positions, and of inserted
+/// elements.
+///
+/// The elements are hashed with 64-bit FNV-1a, so a filter can be serialized
+/// with [`BloomFilter::to_bytes`] and queried with the same results on any
+/// platform. The bytes of the filter are run-length encoded when that makes
+/// them smaller, which is the case for sparse filters.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::BloomFilter;
+///
+/// let mut filter = BloomFilter::with_rate(100, 0.01);
+/// filter.insert("alpha");
+/// filter.insert("beta");
+/// assert!(filter.contains("alpha"));
+///
+/// let bytes = filter.to_bytes();
+/// assert!(bytes.len() < filter.num_bits() / 8);
+/// assert_eq!(BloomFilter::from_bytes(&bytes).unwrap(), filter);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    bits: BitVec,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Creates an empty filter of `num_bits` bits, that sets `num_hashes` bits
+    /// for each element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` or `num_hashes` is 0.
+    pub fn new(num_bits: usize, num_hashes: u32) -> Self {
+        assert!(num_bits > 0, "A filter must have at least one bit.");
+        assert!(num_hashes > 0, "A filter must set at least one bit.");
+        let bits = BitVec::with_len(vec![0; num_bits.div_ceil(8)], num_bits)
+            .expect("The length matches the bytes.");
+        BloomFilter { bits, num_hashes }
+    }
+
+    /// Creates an empty filter with the number of bits and of positions that
+    /// give the specified rate of false positives, once the expected number of
+    /// elements have been inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expected number of elements is 0, or if the rate is not
+    /// between 0 and 1.
+    pub fn with_rate(expected_items: usize, fp_rate: f64) -> Self {
+        assert!(
+            expected_items > 0,
+            "A filter must expect at least one element."
+        );
+        assert!(
+            fp_rate > 0.0 && fp_rate < 1.0,
+            "The false-positive rate must be between 0 and 1."
+        );
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-(expected_items as f64) * fp_rate.ln() / (ln2 * ln2)).ceil();
+        let num_hashes = (num_bits / expected_items as f64 * ln2).round();
+        Self::new(num_bits as usize, (num_hashes as u32).max(1))
+    }
+
+    /// Returns the number of bits of the filter.
+    #[inline]
+    pub fn num_bits(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns the number of bits that are set for each element.
+    #[inline]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the bits of the filter.
+    #[inline]
+    pub fn as_bitvec(&self) -> &BitVec {
+        &self.bits
+    }
+
+    /// Returns the number of bits that are set.
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter_ones().count()
+    }
+
+    /// Inserts an element, setting the bits at its positions.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for pos in self.positions(item) {
+            self.bits.as_bytes_mut()[pos / 8] |= 0x80 >> (pos % 8);
+        }
+    }
+
+    /// Returns whether the filter may contain an element, which is the case if
+    /// all the bits at its positions are set.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        let bytes = self.bits.as_bytes();
+        self.positions(item)
+            .all(|pos| bytes[pos / 8] & (0x80 >> (pos % 8)) != 0)
+    }
+
+    /// Returns the filter of the elements of both filters, whose bits are set
+    /// if they are set in either filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filters differ in their number of bits or of positions.
+    pub fn union(&self, other: &BloomFilter) -> BloomFilter {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Returns the filter whose bits are set if they are set in both filters.
+    ///
+    /// It contains the common elements of the filters, with a rate of false
+    /// positives that is at most the one of either filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filters differ in their number of bits or of positions.
+    pub fn intersect(&self, other: &BloomFilter) -> BloomFilter {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Serializes the filter to bytes: its number of bits and of positions in
+    /// Variable Byte Encoding, followed by its bytes, run-length encoded if
+    /// that makes them smaller.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.num_bits() as u64, &mut bytes);
+        write_vb_bytes(self.num_hashes, &mut bytes);
+
+        let mut enc = RleEncoder::new(Cursor::new(vec![]));
+        enc.encode(self.bits.as_bytes())
+            .expect("Writing to a vector does not fail.");
+        let rle = enc
+            .finalize()
+            .expect("Writing to a vector does not fail.")
+            .into_inner();
+        if rle.len() < self.bits.n_bytes() {
+            bytes.push(TAG_RLE);
+            bytes.extend_from_slice(&rle);
+        } else {
+            bytes.push(TAG_RAW);
+            bytes.extend_from_slice(self.bits.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes a filter from the bytes written by
+    /// [`BloomFilter::to_bytes`].
+    ///
+    /// Returns an error if the bytes are not a serialized filter.
+    pub fn from_bytes(bytes: &[u8]) -> Result<BloomFilter, InvalidCodeError> {
+        let err = || InvalidCodeError::BloomFilterCodeError;
+        let (num_bits, n) = decode_vb_prefix(bytes).map_err(|_| err())?;
+        let (num_hashes, m) = decode_vb_prefix(&bytes[n..]).map_err(|_| err())?;
+        let num_bits = usize::try_from(num_bits).map_err(|_| err())?;
+        let num_hashes = u32::try_from(num_hashes).map_err(|_| err())?;
+        if num_bits == 0 || num_hashes == 0 {
+            return Err(err());
+        }
+
+        let (&tag, rest) = bytes[n + m..].split_first().ok_or_else(err)?;
+        let filter_bytes = match tag {
+            TAG_RAW => rest.to_vec(),
+            TAG_RLE => RleDecoder::new(Cursor::new(rest))
+                .decode::<u8>()
+                .map_err(|_| err())?,
+            _ => return Err(err()),
+        };
+        if filter_bytes.len() != num_bits.div_ceil(8) {
+            return Err(err());
+        }
+        let bits = BitVec::with_len(filter_bytes, num_bits).map_err(|_| err())?;
+        Ok(BloomFilter { bits, num_hashes })
+    }
+
+    // Returns the positions of the bits of an element, derived from its hash
+    // with double hashing.
+    fn positions<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> {
+        let h = hash(item);
+        let step = h.rotate_left(32) | 1;
+        let num_bits = self.num_bits() as u64;
+        (0..u64::from(self.num_hashes))
+            .map(move |i| reduce(h.wrapping_add(i.wrapping_mul(step)), num_bits) as usize)
+    }
+
+    // Combines the bytes of two filters of the same shape.
+    fn combine(&self, other: &BloomFilter, op: impl Fn(u8, u8) -> u8) -> BloomFilter {
+        assert!(
+            self.num_bits() == other.num_bits() && self.num_hashes == other.num_hashes,
+            "The filters must have the same number of bits and of positions."
+        );
+        let mut combined = self.clone();
+        for (a, b) in combined
+            .bits
+            .as_bytes_mut()
+            .iter_mut()
+            .zip(other.bits.as_bytes())
+        {
+            *a = op(*a, *b);
+        }
+        combined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(range: std::ops::Range<u64>) -> BloomFilter {
+        let mut filter = BloomFilter::with_rate(1000, 0.01);
+        for i in range {
+            filter.insert(&i.wrapping_mul(0x9E3779B97F4A7C15));
+        }
+        filter
+    }
+
+    #[test]
+    fn test_fp_rate() {
+        let filter = filter(0..1000);
+        assert_eq!(filter.num_bits(), 9586);
+        assert_eq!(filter.num_hashes(), 7);
+        assert!((0..1000_u64).all(|i| filter.contains(&i.wrapping_mul(0x9E3779B97F4A7C15))));
+
+        let fps = (1000..11_000_u64)
+            .filter(|i| filter.contains(&i.wrapping_mul(0x9E3779B97F4A7C15)))
+            .count();
+        // The expected number is 10000 * 0.01 = 100.
+        assert!((50..200).contains(&fps), "{fps} false positives");
+    }
+
+    #[test]
+    fn test_union_intersect() {
+        let a = filter(0..500);
+        let b = filter(250..750);
+        let union = a.union(&b);
+        assert_eq!(union, filter(0..750));
+        let common = a.intersect(&b);
+        assert!((250..500_u64).all(|i| common.contains(&i.wrapping_mul(0x9E3779B97F4A7C15))));
+        assert!(common.count_ones() <= a.count_ones().min(b.count_ones()));
+    }
+
+    #[test]
+    fn test_serialization() {
+        // A sparse filter is run-length encoded, while a full one is not.
+        let sparse = filter(0..20);
+        let bytes = sparse.to_bytes();
+        assert_eq!(bytes[3], TAG_RLE);
+        assert!(bytes.len() < sparse.num_bits() / 32);
+        assert_eq!(BloomFilter::from_bytes(&bytes).unwrap(), sparse);
+
+        let full = filter(0..1000);
+        let bytes = full.to_bytes();
+        assert_eq!(bytes[3], TAG_RAW);
+        assert_eq!(bytes.len(), 4 + full.as_bitvec().n_bytes());
+        assert_eq!(BloomFilter::from_bytes(&bytes).unwrap(), full);
+
+        assert!(BloomFilter::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(BloomFilter::from_bytes(&[0x81, 0x81, 2]).is_err());
+        assert!(BloomFilter::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mismatched_filters() {
+        BloomFilter::new(64, 3).union(&BloomFilter::new(64, 4));
+    }
+}
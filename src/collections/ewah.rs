@@ -0,0 +1,654 @@
+use std::io::{self, Read, Write};
+use std::ops::{BitAnd, BitOr, BitXor};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::bits_to_numeric;
+use crate::num::convert::write_fixed_bits;
+use crate::BitVec;
+
+const WORD_BITS: usize = 64;
+
+// Layout of a marker word: the lowest bit is the value of the fill, the next
+// 32 bits are the number of fill words, and the highest 31 bits are the number
+// of literal words that follow the marker.
+const RUN_LEN_SHIFT: u32 = 1;
+const LIT_COUNT_SHIFT: u32 = 33;
+const MAX_RUN_LEN: u64 = (1 << 32) - 1;
+const MAX_LIT_COUNT: u64 = (1 << 31) - 1;
+
+/// A bitmap compressed with the Enhanced Word-Aligned Hybrid (EWAH) scheme.
+///
+/// The bits are grouped in 64-bit words, in most-significant-bit first order,
+/// like in [`BitVec`]. Consecutive words whose bits are all 0 or all 1 ("fills")
+/// are run-length encoded, while the rest of the words ("literals") are stored
+/// as they are. The compressed form is a sequence of marker words, each one
+/// describing a fill and the number of literal words that follow it.
+///
+/// Logical operations are performed directly on the compressed form, so long
+/// fills are processed in a single step.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::Ewah;
+/// use idencode::{bitvec, BitVec};
+///
+/// let a = Ewah::from(&bitvec![true, true, false, false]);
+/// let b = Ewah::from(&bitvec![false, true, true, false]);
+/// assert_eq!((&a & &b).to_bitvec(), bitvec![false, true, false, false]);
+/// assert_eq!((&a | &b).to_bitvec(), bitvec![true, true, true, false]);
+/// assert_eq!((&a ^ &b).to_bitvec(), bitvec![true, false, true, false]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ewah {
+    words: Vec<u64>,
+    last_marker: usize,
+    len: usize,
+}
+
+impl Ewah {
+    // Creates an empty bitmap of the specified length, to which the words are
+    // appended.
+    fn with_len(len: usize) -> Self {
+        Ewah {
+            words: vec![0],
+            last_marker: 0,
+            len,
+        }
+    }
+
+    /// Returns the number of bits in the bitmap.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the bitmap contains no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the compressed words, including the marker words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::Ewah;
+    /// use idencode::{bitvec, BitVec};
+    ///
+    /// let ewah = Ewah::from(&bitvec![false; 1000]);
+    /// assert_eq!(ewah.as_words().len(), 1);
+    /// ```
+    #[inline]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the number of bits set to 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::Ewah;
+    /// use idencode::{bitvec, BitVec};
+    ///
+    /// let ewah = Ewah::from(&bitvec![true; 200]);
+    /// assert_eq!(ewah.count_ones(), 200);
+    /// ```
+    pub fn count_ones(&self) -> usize {
+        let mut count = 0;
+        let mut runs = Runs::new(self);
+        while let Some(run) = runs.peek() {
+            let n = match run {
+                Run::Fill(bit, n) => {
+                    if bit {
+                        count += n as usize * WORD_BITS;
+                    }
+                    n
+                }
+                Run::Literal(word) => {
+                    count += word.count_ones() as usize;
+                    1
+                }
+            };
+            runs.advance(n);
+        }
+        count
+    }
+
+    /// Returns an iterator over the positions of the bits set to 1, in
+    /// increasing order, without decompressing the bitmap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::Ewah;
+    /// use idencode::{bitvec, BitVec};
+    ///
+    /// let mut bitvec = bitvec![false; 1000];
+    /// bitvec.extend_from_slice(&[true; 130]);
+    /// bitvec.extend_from_slice(&[false, true]);
+    /// let ewah = Ewah::from(&bitvec);
+    /// let ones: Vec<usize> = ewah.iter_ones().collect();
+    /// assert_eq!(ones.len(), 131);
+    /// assert_eq!((ones[0], ones[129], ones[130]), (1000, 1129, 1131));
+    /// ```
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut runs = Runs::new(self);
+        let mut next_word = 0;
+        std::iter::from_fn(move || {
+            let run = runs.peek()?;
+            let n = match run {
+                Run::Fill(_, n) => n,
+                Run::Literal(_) => 1,
+            };
+            runs.advance(n);
+            let start = next_word as usize * WORD_BITS;
+            next_word += n;
+            Some((start, run))
+        })
+        .flat_map(move |(start, run)| {
+            let (end, literal) = match run {
+                Run::Fill(true, n) => (start + n as usize * WORD_BITS, None),
+                Run::Fill(false, _) => (start, None),
+                Run::Literal(word) => (start + WORD_BITS, Some(word)),
+            };
+            (start..end.min(self.len)).filter(move |pos| {
+                literal.is_none_or(|word| word >> (WORD_BITS - 1 - (pos - start)) & 1 == 1)
+            })
+        })
+    }
+
+    /// Decompresses the bitmap into a bit-vector.
+    pub fn to_bitvec(&self) -> BitVec {
+        if self.len == 0 {
+            return BitVec::default();
+        }
+        let mut bytes = Vec::with_capacity(self.len.div_ceil(8));
+        let mut runs = Runs::new(self);
+        while let Some(run) = runs.peek() {
+            let n = match run {
+                Run::Fill(bit, n) => {
+                    let byte = if bit { u8::MAX } else { 0 };
+                    bytes.resize(bytes.len() + n as usize * 8, byte);
+                    n
+                }
+                Run::Literal(word) => {
+                    bytes.extend_from_slice(&word.to_be_bytes());
+                    1
+                }
+            };
+            runs.advance(n);
+        }
+        bytes.truncate(self.len.div_ceil(8));
+        BitVec::with_len(bytes, self.len).expect("The length matches the bytes.")
+    }
+
+    /// Computes the bitwise AND of two bitmaps.
+    ///
+    /// If the bitmaps have different lengths, the shorter one is treated as if
+    /// it was padded with 0-bits.
+    pub fn and(&self, other: &Ewah) -> Ewah {
+        self.binary_op(other, |a, b| a & b)
+    }
+
+    /// Computes the bitwise OR of two bitmaps.
+    ///
+    /// If the bitmaps have different lengths, the shorter one is treated as if
+    /// it was padded with 0-bits.
+    pub fn or(&self, other: &Ewah) -> Ewah {
+        self.binary_op(other, |a, b| a | b)
+    }
+
+    /// Computes the bitwise XOR of two bitmaps.
+    ///
+    /// If the bitmaps have different lengths, the shorter one is treated as if
+    /// it was padded with 0-bits.
+    pub fn xor(&self, other: &Ewah) -> Ewah {
+        self.binary_op(other, |a, b| a ^ b)
+    }
+
+    /// Writes the bitmap with a bit writer: its length and its number of
+    /// compressed words, each incremented by one, in Elias Gamma Encoding,
+    /// followed by the compressed words in 64 bits each, most significant bit
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::collections::Ewah;
+    /// use idencode::{bitvec, BitReader, BitVec, BitWriter};
+    ///
+    /// let ewah = Ewah::from(&bitvec![true; 500]);
+    /// let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+    /// ewah.write(&mut writer).unwrap();
+    /// let bytes = writer.finalize().unwrap().into_inner();
+    /// assert_eq!(bytes.len(), 19);
+    ///
+    /// let reader = BitReader::new(Cursor::new(bytes), true);
+    /// assert_eq!(Ewah::read(reader).unwrap(), ewah);
+    /// ```
+    pub fn write<W: Write>(&self, writer: &mut BitWriter<W>) -> io::Result<()> {
+        let convention = UnaryConvention::default();
+        let mut bits = vec![];
+        write_gamma_bits(self.len + 1, convention, &mut bits);
+        write_gamma_bits(self.words.len() + 1, convention, &mut bits);
+        for word in &self.words {
+            write_fixed_bits(word, u64::BITS, &mut bits);
+        }
+        writer.write_bits(&bits)
+    }
+
+    /// Reads a bitmap written with [`Ewah::write`]. Any bits after the words
+    /// are ignored.
+    ///
+    /// Returns an error if the bits are not a written bitmap, or if its marker
+    /// words do not describe exactly the words of its length.
+    pub fn read<R: Read>(reader: BitReader<R>) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::EwahCodeError;
+        let bits = reader.read_to_end().map_err(|_| err())?.into_bits();
+        let convention = UnaryConvention::default();
+        let (len, len_len) = decode_gamma_prefix::<usize>(&bits, convention).map_err(|_| err())?;
+        let (n_words, n_words_len) =
+            decode_gamma_prefix::<usize>(&bits[len_len..], convention).map_err(|_| err())?;
+        let (len, n_words) = (len - 1, n_words - 1);
+        if n_words == 0 || n_words.saturating_mul(WORD_BITS) > bits.len() {
+            return Err(err());
+        }
+
+        let mut ewah = Ewah::with_len(len);
+        ewah.words = bits[len_len + n_words_len..]
+            .chunks(WORD_BITS)
+            .take(n_words)
+            .map(|word| bits_to_numeric(word).map_err(|_| err()))
+            .collect::<Result<_, _>>()?;
+        if ewah.words.len() != n_words {
+            return Err(err());
+        }
+
+        // The markers must cover the words of the length, and the bits of the
+        // last word after the length must be 0.
+        let mut pos = 0;
+        let mut covered = 0;
+        while pos < n_words {
+            ewah.last_marker = pos;
+            let marker = ewah.words[pos];
+            covered += run_len(marker) + lit_count(marker);
+            pos += 1 + lit_count(marker) as usize;
+        }
+        if pos != n_words || covered != ewah.n_words() {
+            return Err(err());
+        }
+        let tail = len % WORD_BITS;
+        let marker = ewah.words[ewah.last_marker];
+        let last = match lit_count(marker) {
+            0 => fill_word(run_bit(marker)),
+            _ => ewah.words[n_words - 1],
+        };
+        if tail != 0 && last & (u64::MAX >> tail) != 0 {
+            return Err(err());
+        }
+        Ok(ewah)
+    }
+
+    fn n_words(&self) -> u64 {
+        self.len.div_ceil(WORD_BITS) as u64
+    }
+
+    fn binary_op(&self, other: &Ewah, op: fn(u64, u64) -> u64) -> Ewah {
+        let mut result = Ewah::with_len(self.len.max(other.len));
+        let n_words = self.n_words().max(other.n_words());
+        let mut a = Runs::new(self);
+        let mut b = Runs::new(other);
+
+        let mut done = 0;
+        while done < n_words {
+            // An exhausted bitmap behaves as an endless fill of 0-bits.
+            let run_a = a.peek().unwrap_or(Run::Fill(false, u64::MAX));
+            let run_b = b.peek().unwrap_or(Run::Fill(false, u64::MAX));
+            let n = match (run_a, run_b) {
+                (Run::Fill(x, n), Run::Fill(y, m)) => {
+                    let n = n.min(m).min(n_words - done);
+                    result.push_fill(op(fill_word(x), fill_word(y)) != 0, n);
+                    n
+                }
+                (Run::Fill(x, _), Run::Literal(word)) => {
+                    result.push_literal(op(fill_word(x), word));
+                    1
+                }
+                (Run::Literal(word), Run::Fill(y, _)) => {
+                    result.push_literal(op(word, fill_word(y)));
+                    1
+                }
+                (Run::Literal(x), Run::Literal(y)) => {
+                    result.push_literal(op(x, y));
+                    1
+                }
+            };
+            a.advance(n);
+            b.advance(n);
+            done += n;
+        }
+        result
+    }
+
+    // Appends `n` fill words with all their bits set to `bit`.
+    fn push_fill(&mut self, bit: bool, mut n: u64) {
+        while n > 0 {
+            let marker = self.words[self.last_marker];
+            let run_len = run_len(marker);
+            let extendable = lit_count(marker) == 0
+                && (run_len == 0 || run_bit(marker) == bit)
+                && run_len < MAX_RUN_LEN;
+            if !extendable {
+                self.last_marker = self.words.len();
+                self.words.push(0);
+                continue;
+            }
+            let added = n.min(MAX_RUN_LEN - run_len);
+            self.words[self.last_marker] = (run_len + added) << RUN_LEN_SHIFT | bit as u64;
+            n -= added;
+        }
+    }
+
+    // Appends a single word, which is stored as a fill if possible.
+    fn push_literal(&mut self, word: u64) {
+        if word == 0 || word == u64::MAX {
+            return self.push_fill(word != 0, 1);
+        }
+        if lit_count(self.words[self.last_marker]) == MAX_LIT_COUNT {
+            self.last_marker = self.words.len();
+            self.words.push(0);
+        }
+        self.words[self.last_marker] += 1 << LIT_COUNT_SHIFT;
+        self.words.push(word);
+    }
+}
+
+#[inline]
+fn run_bit(marker: u64) -> bool {
+    marker & 1 == 1
+}
+
+#[inline]
+fn run_len(marker: u64) -> u64 {
+    (marker >> RUN_LEN_SHIFT) & MAX_RUN_LEN
+}
+
+#[inline]
+fn lit_count(marker: u64) -> u64 {
+    marker >> LIT_COUNT_SHIFT
+}
+
+#[inline]
+fn fill_word(bit: bool) -> u64 {
+    if bit {
+        u64::MAX
+    } else {
+        0
+    }
+}
+
+// A run of uncompressed words in the bitmap.
+#[derive(Debug, Clone, Copy)]
+enum Run {
+    // A number of words with all their bits set to the same value.
+    Fill(bool, u64),
+    // A single word stored as is.
+    Literal(u64),
+}
+
+// A cursor over the runs of a compressed bitmap, which can be advanced by a
+// number of uncompressed words.
+struct Runs<'a> {
+    words: &'a [u64],
+    // Position of the next marker word.
+    next_marker: usize,
+    // Position of the next literal word.
+    literal: usize,
+    fill_bit: bool,
+    fill_left: u64,
+    literals_left: u64,
+}
+
+impl<'a> Runs<'a> {
+    fn new(ewah: &'a Ewah) -> Self {
+        let mut runs = Runs {
+            words: &ewah.words,
+            next_marker: 0,
+            literal: 0,
+            fill_bit: false,
+            fill_left: 0,
+            literals_left: 0,
+        };
+        runs.load_markers();
+        runs
+    }
+
+    // Loads markers until a non-empty one is found.
+    fn load_markers(&mut self) {
+        while self.fill_left == 0 && self.literals_left == 0 {
+            let Some(&marker) = self.words.get(self.next_marker) else {
+                return;
+            };
+            self.fill_bit = run_bit(marker);
+            self.fill_left = run_len(marker);
+            self.literals_left = lit_count(marker);
+            self.literal = self.next_marker + 1;
+            self.next_marker = self.literal + self.literals_left as usize;
+        }
+    }
+
+    fn peek(&self) -> Option<Run> {
+        if self.fill_left > 0 {
+            Some(Run::Fill(self.fill_bit, self.fill_left))
+        } else if self.literals_left > 0 {
+            Some(Run::Literal(self.words[self.literal]))
+        } else {
+            None
+        }
+    }
+
+    // Advances the cursor by `n` words, which must not exceed the current run.
+    fn advance(&mut self, n: u64) {
+        if self.fill_left > 0 {
+            self.fill_left -= n;
+        } else if self.literals_left > 0 {
+            self.literals_left -= 1;
+            self.literal += 1;
+        }
+        self.load_markers();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Implementation of common traits
+////////////////////////////////////////////////////////////////////////////////
+
+impl From<&BitVec> for Ewah {
+    fn from(bitvec: &BitVec) -> Self {
+        let mut ewah = Ewah::with_len(bitvec.len());
+        let n_bytes = bitvec.len().div_ceil(8);
+        for chunk in bitvec.as_bytes()[..n_bytes].chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            ewah.push_literal(u64::from_be_bytes(bytes));
+        }
+        ewah
+    }
+}
+
+impl From<&Ewah> for BitVec {
+    fn from(ewah: &Ewah) -> Self {
+        ewah.to_bitvec()
+    }
+}
+
+impl BitAnd for &Ewah {
+    type Output = Ewah;
+
+    fn bitand(self, rhs: &Ewah) -> Ewah {
+        self.and(rhs)
+    }
+}
+
+impl BitOr for &Ewah {
+    type Output = Ewah;
+
+    fn bitor(self, rhs: &Ewah) -> Ewah {
+        self.or(rhs)
+    }
+}
+
+impl BitXor for &Ewah {
+    type Output = Ewah;
+
+    fn bitxor(self, rhs: &Ewah) -> Ewah {
+        self.xor(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+    use std::io::Cursor;
+
+    // Creates a bit-vector with runs of bits.
+    fn runs(runs: &[(bool, usize)]) -> BitVec {
+        let mut bitvec = BitVec::default();
+        for &(bit, n) in runs {
+            for _ in 0..n {
+                bitvec.push(bit);
+            }
+        }
+        bitvec
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bitvecs = [
+            bitvec![],
+            bitvec![true, false, true],
+            runs(&[(false, 1000), (true, 3), (false, 60), (true, 500)]),
+            runs(&[(true, 64 * 5), (false, 64 * 3 + 1)]),
+        ];
+        for bitvec in bitvecs {
+            let ewah = Ewah::from(&bitvec);
+            assert_eq!(ewah.len(), bitvec.len());
+            assert_eq!(ewah.to_bitvec(), bitvec);
+        }
+    }
+
+    #[test]
+    fn test_compression() {
+        let bitvec = runs(&[(false, 64 * 100), (true, 64 * 100), (false, 10)]);
+        let ewah = Ewah::from(&bitvec);
+        assert_eq!(ewah.as_words().len(), 3);
+        assert_eq!(ewah.count_ones(), 64 * 100);
+    }
+
+    // Checks a logical operation against the same operation on the bits.
+    fn check_op(
+        a: &BitVec,
+        b: &BitVec,
+        ewah_op: fn(&Ewah, &Ewah) -> Ewah,
+        op: fn(bool, bool) -> bool,
+    ) {
+        let (a_bits, b_bits) = (a.clone().into_bits(), b.clone().into_bits());
+        let expected = (0..a_bits.len().max(b_bits.len()))
+            .map(|i| {
+                let x = a_bits.get(i).copied().unwrap_or(false);
+                let y = b_bits.get(i).copied().unwrap_or(false);
+                op(x, y)
+            })
+            .collect::<Vec<_>>();
+        let (a, b) = (Ewah::from(a), Ewah::from(b));
+        assert_eq!(ewah_op(&a, &b).to_bitvec().into_bits(), expected);
+        assert_eq!(ewah_op(&b, &a).to_bitvec().into_bits(), expected);
+    }
+
+    #[test]
+    fn test_logical_ops() {
+        let a = runs(&[(false, 1000), (true, 300), (false, 60), (true, 7)]);
+        let b = runs(&[(true, 100), (false, 1100), (true, 250)]);
+        check_op(&a, &b, Ewah::and, |x, y| x & y);
+        check_op(&a, &b, Ewah::or, |x, y| x | y);
+        check_op(&a, &b, Ewah::xor, |x, y| x ^ y);
+    }
+
+    #[test]
+    fn test_iter_ones() {
+        let bitvecs = [
+            bitvec![],
+            bitvec![false, true, true],
+            runs(&[(false, 1000), (true, 300), (false, 60), (true, 7)]),
+            runs(&[(true, 64 * 3), (false, 64), (true, 10)]),
+        ];
+        for bitvec in bitvecs {
+            let ewah = Ewah::from(&bitvec);
+            let expected: Vec<usize> = bitvec.iter_ones().collect();
+            assert_eq!(ewah.iter_ones().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_write_read() {
+        let bitvecs = [
+            bitvec![],
+            runs(&[(false, 1000), (true, 3), (false, 60), (true, 500)]),
+            runs(&[(true, 64 * 5), (false, 64 * 3 + 1)]),
+        ];
+        for bitvec in bitvecs {
+            let ewah = Ewah::from(&bitvec);
+            let mut writer = BitWriter::new(Cursor::new(vec![]), false);
+            ewah.write(&mut writer).unwrap();
+            let bytes = writer.finalize().unwrap().into_inner();
+            let read = Ewah::read(BitReader::new(Cursor::new(bytes), false)).unwrap();
+            assert_eq!(read.to_bitvec(), bitvec);
+            assert_eq!(read, ewah);
+        }
+    }
+
+    #[test]
+    fn test_read_errs() {
+        // Writes a bitmap of a length with the specified words.
+        fn written(len: usize, words: &[u64]) -> Vec<u8> {
+            let ewah = Ewah {
+                words: words.to_vec(),
+                last_marker: 0,
+                len,
+            };
+            let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+            ewah.write(&mut writer).unwrap();
+            writer.finalize().unwrap().into_inner()
+        }
+        let read = |bytes: Vec<u8>| Ewah::read(BitReader::new(Cursor::new(bytes), true));
+
+        // Two fill words of 1-bits.
+        assert!(read(written(128, &[2 << RUN_LEN_SHIFT | 1])).is_ok());
+        // Too few, or too many, words for the length.
+        assert!(read(written(129, &[2 << RUN_LEN_SHIFT | 1])).is_err());
+        assert!(read(written(64, &[2 << RUN_LEN_SHIFT | 1])).is_err());
+        // A literal word that is missing.
+        assert!(read(written(64, &[1 << LIT_COUNT_SHIFT])).is_err());
+        // 1-bits after the length.
+        assert!(read(written(100, &[2 << RUN_LEN_SHIFT | 1])).is_err());
+        assert!(read(written(
+            100,
+            &[1 << RUN_LEN_SHIFT | 1 << LIT_COUNT_SHIFT, 1]
+        ))
+        .is_err());
+        assert!(read(written(
+            100,
+            &[1 << RUN_LEN_SHIFT | 1 << LIT_COUNT_SHIFT, 1 << 63]
+        ))
+        .is_ok());
+    }
+}
@@ -0,0 +1,508 @@
+use std::hash::Hash;
+use std::io::Cursor;
+
+use super::hash::{hash, reduce, siphash24};
+use crate::code::Encoder;
+use crate::error::InvalidCodeError;
+use crate::io::read::ByteBits;
+use crate::{RiceDecoder, RiceEncoder};
+
+/// The largest number of bits of the false-positive rate of a set.
+const MAX_FP_BITS: u32 = 32;
+
+/// The Golomb-Rice parameter of the basic filters of BIP158.
+pub const BIP158_P: u32 = 19;
+
+/// The inverse of the false-positive rate of the basic filters of BIP158.
+pub const BIP158_M: u64 = 784_931;
+
+/// The hash function of the elements of a set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hashing {
+    /// 64-bit FNV-1a over the [`Hash`] of the elements.
+    Fnv,
+    /// SipHash-2-4 over the bytes of the elements, with a 128-bit key.
+    SipHash(u64, u64),
+}
+
+/// A Golomb-compressed set (GCS), a probabilistic set that answers membership
+/// queries with a configurable rate of false positives, in less space than a
+/// Bloom filter.
+///
+/// The elements are hashed into a universe of `n * m` values, where `n` is
+/// the number of elements and `1 / m` the false-positive rate. The hashes
+/// are sorted, and the gaps between them, which are geometrically distributed
+/// with a mean of `m`, are encoded with Rice Encoding with parameter `p`,
+/// which is optimal for them when `m` is close to `2^p`. A set takes about
+/// `p + 1.5` bits per element.
+///
+/// An element that was inserted is always found, while one that was not is
+/// found with the false-positive rate. A query decodes the gaps up to the hash
+/// of the element, so it takes time linear in the number of elements.
+///
+/// A set is built either from elements of any [`Hash`] type, which are hashed
+/// with 64-bit FNV-1a and queried with [`GolombSet::contains`], or from byte
+/// strings, which are hashed with keyed SipHash-2-4 and queried with
+/// [`GolombSet::contains_bytes`]. Both hashes give the same results on any
+/// platform. The latter, with [`GolombSet::bip158`], builds the basic compact
+/// block filters of BIP158, which [`GolombSet::to_bytes`] serializes.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::GolombSet;
+///
+/// let words = ["alpha", "beta", "gamma", "delta"];
+/// let set = GolombSet::new(words, 0.01).unwrap();
+/// assert_eq!(set.len(), 4);
+/// assert_eq!(set.fp_bits(), 7);
+/// assert!(words.iter().all(|word| set.contains(word) == Ok(true)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GolombSet {
+    len: usize,
+    fp_bits: u32,
+    modulus: u64,
+    hashing: Hashing,
+    bytes: Vec<u8>,
+}
+
+impl GolombSet {
+    /// Creates a set of the elements, with at most the specified rate of
+    /// false positives, which is rounded down to a power of 1/2.
+    ///
+    /// Returns an error if the universe of the hashes does not fit in 64
+    /// bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the false-positive rate is not less than 1, or is less than
+    /// `1 / 2^32`.
+    pub fn new<T: Hash, I: IntoIterator<Item = T>>(
+        items: I,
+        fp_rate: f64,
+    ) -> Result<Self, InvalidCodeError> {
+        assert!(
+            fp_rate < 1.0 && fp_rate >= 0.5_f64.powi(MAX_FP_BITS as i32),
+            "The false-positive rate must be in [2^-32, 1)."
+        );
+        let fp_bits = (1.0 / fp_rate).log2().ceil() as u32;
+        Self::with_fp_bits(items, fp_bits.max(1))
+    }
+
+    /// Creates a set of the elements, with a false-positive rate of `1 / 2^k`.
+    ///
+    /// Returns an error if the universe of the hashes does not fit in 64
+    /// bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or greater than 32.
+    pub fn with_fp_bits<T: Hash, I: IntoIterator<Item = T>>(
+        items: I,
+        k: u32,
+    ) -> Result<Self, InvalidCodeError> {
+        assert!(
+            (1..=MAX_FP_BITS).contains(&k),
+            "The number of bits of the false-positive rate must be in 1..=32."
+        );
+        let hashes = items.into_iter().map(|item| hash(&item)).collect();
+        Self::from_hashes(hashes, k, 1 << k, Hashing::Fnv)
+    }
+
+    /// Creates a set of byte strings, hashed with SipHash-2-4 with a 128-bit
+    /// key, with a false-positive rate of `1 / m` and gaps encoded with Rice
+    /// parameter `p`.
+    ///
+    /// The first 8 bytes of the key are the first key word, and the last 8
+    /// the second one, both in little-endian order.
+    ///
+    /// Returns an error if the universe of `n * m` hashes does not fit in 64
+    /// bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is greater than 32, or if `m` is 0.
+    pub fn with_params<B: AsRef<[u8]>, I: IntoIterator<Item = B>>(
+        items: I,
+        p: u32,
+        m: u64,
+        key: [u8; 16],
+    ) -> Result<Self, InvalidCodeError> {
+        assert!(p <= MAX_FP_BITS, "The Rice parameter must be at most 32.");
+        assert!(
+            m > 0,
+            "The inverse of the false-positive rate must be positive."
+        );
+        let (k0, k1) = key_words(key);
+        let hashes = items
+            .into_iter()
+            .map(|item| siphash24(k0, k1, item.as_ref()))
+            .collect();
+        Self::from_hashes(hashes, p, m, Hashing::SipHash(k0, k1))
+    }
+
+    /// Creates a basic compact block filter of BIP158, with `P = 19` and
+    /// `M = 784931`, keyed with the first 16 bytes of the hash of the block,
+    /// in the order the hash is serialized in.
+    ///
+    /// The elements are the output scripts of the block, and the scripts of
+    /// the outputs that it spends, which are deduplicated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::GolombSet;
+    ///
+    /// let key = *b"0123456789abcdef";
+    /// let scripts: [&[u8]; 2] = [b"\x51", b"\x00\x14"];
+    /// let filter = GolombSet::bip158(key, scripts).unwrap();
+    /// assert_eq!(filter.contains_bytes(b"\x51"), Ok(true));
+    ///
+    /// let bytes = filter.to_bytes();
+    /// assert_eq!(bytes[0], 2);
+    /// assert_eq!(GolombSet::bip158_from_bytes(key, &bytes), Ok(filter));
+    /// ```
+    pub fn bip158<B: AsRef<[u8]>, I: IntoIterator<Item = B>>(
+        key: [u8; 16],
+        items: I,
+    ) -> Result<Self, InvalidCodeError> {
+        Self::with_params(items, BIP158_P, BIP158_M, key)
+    }
+
+    // Creates a set of the hashes of its elements.
+    fn from_hashes(
+        mut hashes: Vec<u64>,
+        p: u32,
+        m: u64,
+        hashing: Hashing,
+    ) -> Result<Self, InvalidCodeError> {
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        let len = hashes.len();
+        let universe = universe(len, m)?;
+        let mut values: Vec<u64> = hashes.iter().map(|&h| reduce(h, universe)).collect();
+        values.sort_unstable();
+
+        // Values that collide are kept, with gaps of 0, as in BIP158.
+        let gaps: Vec<u64> = values
+            .iter()
+            .scan(0, |prev, &value| {
+                let gap = value - *prev;
+                *prev = value;
+                Some(gap)
+            })
+            .collect();
+        let mut enc = RiceEncoder::unterminated(Cursor::new(vec![]), p);
+        enc.encode(&gaps)
+            .expect("Writing to a vector does not fail.");
+        let bytes = enc
+            .finalize()
+            .expect("Writing to a vector does not fail.")
+            .into_inner();
+        Ok(GolombSet {
+            len,
+            fp_bits: p,
+            modulus: m,
+            hashing,
+            bytes,
+        })
+    }
+
+    /// Returns the number of distinct elements of the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the Rice parameter `p` of the gaps, which is also the number of
+    /// bits `k` of the false-positive rate `1 / 2^k`, unless the set was
+    /// created with a different rate.
+    #[inline]
+    pub fn fp_bits(&self) -> u32 {
+        self.fp_bits
+    }
+
+    /// Returns the inverse `m` of the false-positive rate `1 / m`.
+    #[inline]
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// Returns whether the set may contain an element of a set created with
+    /// [`GolombSet::new`] or [`GolombSet::with_fp_bits`].
+    ///
+    /// The elements of the set are always found, while any other element is
+    /// found with a probability of `1 / m`. Returns an error if the gaps are
+    /// not valid Rice codes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set was created from byte strings.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> Result<bool, InvalidCodeError> {
+        assert_eq!(
+            self.hashing,
+            Hashing::Fnv,
+            "A set of byte strings is queried with `contains_bytes`."
+        );
+        self.contains_hash(hash(item))
+    }
+
+    /// Returns whether the set may contain a byte string, for a set created
+    /// with [`GolombSet::with_params`] or [`GolombSet::bip158`]. Returns an
+    /// error if the gaps are not valid Rice codes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set was not created from byte strings.
+    pub fn contains_bytes(&self, item: &[u8]) -> Result<bool, InvalidCodeError> {
+        match self.hashing {
+            Hashing::SipHash(k0, k1) => self.contains_hash(siphash24(k0, k1, item)),
+            Hashing::Fnv => panic!("A set of hashable elements is queried with `contains`."),
+        }
+    }
+
+    fn contains_hash(&self, hash: u64) -> Result<bool, InvalidCodeError> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        let target = reduce(hash, universe(self.len, self.modulus)?);
+        let mut bits = ByteBits::new(&self.bytes, 0);
+        let mut value = 0_u64;
+        for _ in 0..self.len {
+            let gap = RiceDecoder::read_code(&mut bits, self.fp_bits)
+                .ok_or(InvalidCodeError::GolombSetCodeError)?;
+            value = value.saturating_add(gap);
+            if value >= target {
+                return Ok(value == target);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the number of bytes of the encoded gaps.
+    pub fn encoded_size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Serializes the set as in BIP158: the number of elements as a Bitcoin
+    /// CompactSize integer, followed by the Rice codes of the gaps, padded
+    /// with zeros to a byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.bytes.len());
+        let len = self.len as u64;
+        match len {
+            0..=0xFC => bytes.push(len as u8),
+            0xFD..=0xFFFF => {
+                bytes.push(0xFD);
+                bytes.extend_from_slice(&(len as u16).to_le_bytes());
+            }
+            0x10000..=0xFFFF_FFFF => {
+                bytes.push(0xFE);
+                bytes.extend_from_slice(&(len as u32).to_le_bytes());
+            }
+            _ => {
+                bytes.push(0xFF);
+                bytes.extend_from_slice(&len.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+
+    /// Deserializes a basic compact block filter of BIP158, written by
+    /// [`GolombSet::to_bytes`], keyed with the first 16 bytes of the hash of
+    /// the block.
+    ///
+    /// Returns an error if the bytes are not a serialized filter.
+    pub fn bip158_from_bytes(key: [u8; 16], bytes: &[u8]) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::GolombSetCodeError;
+        let (&tag, rest) = bytes.split_first().ok_or_else(err)?;
+        let (len, rest) = match tag {
+            0..=0xFC => (u64::from(tag), rest),
+            _ => {
+                let width = 2 << (tag - 0xFD);
+                let len_bytes = rest.get(..width).ok_or_else(err)?;
+                let mut le = [0; 8];
+                le[..width].copy_from_slice(len_bytes);
+                (u64::from_le_bytes(le), &rest[width..])
+            }
+        };
+        let len = usize::try_from(len).map_err(|_| err())?;
+        if len > 8 * rest.len() {
+            return Err(err());
+        }
+        universe(len, BIP158_M)?;
+
+        // All the gaps must be complete, and followed only by padding.
+        let (k0, k1) = key_words(key);
+        let mut bits = ByteBits::new(rest, 0);
+        for _ in 0..len {
+            RiceDecoder::read_code(&mut bits, BIP158_P).ok_or_else(err)?;
+        }
+        if bits.position().div_ceil(8) != rest.len() {
+            return Err(err());
+        }
+        Ok(GolombSet {
+            len,
+            fp_bits: BIP158_P,
+            modulus: BIP158_M,
+            hashing: Hashing::SipHash(k0, k1),
+            bytes: rest.to_vec(),
+        })
+    }
+}
+
+/// Splits a 128-bit SipHash key into its two little-endian words.
+fn key_words(key: [u8; 16]) -> (u64, u64) {
+    let (k0, k1) = key.split_at(8);
+    (
+        u64::from_le_bytes(k0.try_into().expect("The key word has 8 bytes.")),
+        u64::from_le_bytes(k1.try_into().expect("The key word has 8 bytes.")),
+    )
+}
+
+/// Returns the number of values that the hashes of `len` elements are reduced
+/// to, for a false-positive rate of `1 / m`, or an error if it does not fit
+/// in 64 bits.
+fn universe(len: usize, m: u64) -> Result<u64, InvalidCodeError> {
+    (len as u64)
+        .checked_mul(m)
+        .ok_or(InvalidCodeError::GolombSetCodeError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_members() {
+        let ids: Vec<u64> = (0..1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15))
+            .collect();
+        let set = GolombSet::with_fp_bits(&ids, 10).unwrap();
+        assert_eq!(set.len(), 1000);
+        assert!(ids.iter().all(|id| set.contains(id) == Ok(true)));
+
+        // About 10 + 1.5 bits per element.
+        let bits = 8 * set.encoded_size();
+        assert!(bits > 1000 * 10 && bits < 1000 * 12, "{bits} bits");
+    }
+
+    #[test]
+    fn test_fp_rate() {
+        let set = GolombSet::new(0..500_u32, 1.0 / 16.0).unwrap();
+        assert_eq!(set.fp_bits(), 4);
+        let fps = (1_000_000..1_004_000_u32)
+            .filter(|n| set.contains(n).unwrap())
+            .count();
+        // The expected number is 4000 / 16 = 250.
+        assert!((170..340).contains(&fps), "{fps} false positives");
+
+        // A rate of 1 / 100, with gaps in Rice parameter 6.
+        let items: Vec<[u8; 4]> = (0..500_u32).map(u32::to_le_bytes).collect();
+        let set = GolombSet::with_params(&items, 6, 100, [7; 16]).unwrap();
+        assert!(items
+            .iter()
+            .all(|item| set.contains_bytes(item) == Ok(true)));
+        let fps = (1_000_000..1_010_000_u32)
+            .filter(|n| set.contains_bytes(&n.to_le_bytes()).unwrap())
+            .count();
+        assert!((60..140).contains(&fps), "{fps} false positives");
+    }
+
+    #[test]
+    fn test_empty_and_duplicates() {
+        let set = GolombSet::new(Vec::<String>::new(), 0.1).unwrap();
+        assert!(set.is_empty());
+        assert_eq!(set.contains("a"), Ok(false));
+
+        let set = GolombSet::new(["a", "b", "a"], 0.1).unwrap();
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.contains("a"), Ok(true));
+        assert_eq!(set.contains("b"), Ok(true));
+        assert_eq!(set, GolombSet::new(["b", "a"], 0.1).unwrap());
+    }
+
+    #[test]
+    fn test_bip158_genesis() {
+        // The filter of the genesis block of testnet3, from the test vectors of
+        // BIP158, whose only element is the output script of its coinbase.
+        let mut hash = [0; 32];
+        let hex = "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943";
+        for (i, byte) in hash.iter_mut().rev().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        let key: [u8; 16] = hash[..16].try_into().unwrap();
+        let script = "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb6\
+                      49f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac";
+        let script: Vec<u8> = (0..script.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&script[i..i + 2], 16).unwrap())
+            .collect();
+
+        let filter = GolombSet::bip158(key, [&script]).unwrap();
+        assert_eq!(filter.to_bytes(), vec![0x01, 0x9D, 0xFC, 0xA8]);
+        assert_eq!(filter.contains_bytes(&script), Ok(true));
+        assert_eq!(
+            GolombSet::bip158_from_bytes(key, &[0x01, 0x9D, 0xFC, 0xA8]),
+            Ok(filter)
+        );
+    }
+
+    #[test]
+    fn test_bip158_bytes() {
+        let key = [42; 16];
+        for n in [0_u32, 1, 300, 70_000] {
+            let filter = GolombSet::bip158(key, (0..n).map(u32::to_be_bytes)).unwrap();
+            let bytes = filter.to_bytes();
+            let read = GolombSet::bip158_from_bytes(key, &bytes).unwrap();
+            assert_eq!(read.len(), n as usize);
+            assert_eq!(read, filter);
+            assert!((0..n.min(100)).all(|i| read.contains_bytes(&i.to_be_bytes()) == Ok(true)));
+        }
+        assert_eq!(
+            GolombSet::bip158(key, [[0_u8; 0]; 0]).unwrap().to_bytes(),
+            vec![0]
+        );
+
+        let bytes = GolombSet::bip158(key, [b"a", b"b"]).unwrap().to_bytes();
+        let bip158 = |bytes: &[u8]| GolombSet::bip158_from_bytes(key, bytes);
+        assert!(bip158(&bytes[..bytes.len() - 1]).is_err());
+        assert!(bip158(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(bip158(&[0xFD, 1]).is_err());
+        assert!(bip158(&[]).is_err());
+    }
+
+    #[test]
+    fn test_errs() {
+        // The universe of 2 hashes for a rate of 1 / 2^63 does not fit in 64 bits.
+        let items = [b"a", b"b"];
+        assert!(GolombSet::with_params(items, 32, 1 << 63, [0; 16]).is_err());
+        assert!(GolombSet::with_params(items, 32, 1 << 33, [0; 16]).is_ok());
+
+        // Gaps that end before the number of elements.
+        let mut set = GolombSet::bip158([0; 16], items).unwrap();
+        set.bytes.truncate(1);
+        assert!(set.contains_bytes(b"c").is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_contains_mode() {
+        let _ = GolombSet::bip158([0; 16], [b"a"]).unwrap().contains("a");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fp_rate() {
+        let _ = GolombSet::new([1_u32], 1.0);
+    }
+}
@@ -0,0 +1,95 @@
+use std::hash::{Hash, Hasher};
+
+/// Hashes an element with 64-bit FNV-1a, whose bits are then mixed so that
+/// the highest ones depend on all the bytes.
+pub(crate) fn hash<T: Hash + ?Sized>(item: &T) -> u64 {
+    let mut hasher = Fnv1a(0xCBF29CE484222325);
+    item.hash(&mut hasher);
+    let mut z = hasher.finish();
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Maps a hash to the range `0..n`, by its highest bits.
+pub(crate) fn reduce(hash: u64, n: u64) -> u64 {
+    ((u128::from(hash) * u128::from(n)) >> 64) as u64
+}
+
+/// The 64-bit FNV-1a hash function.
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100000001B3);
+        }
+    }
+}
+
+/// Hashes a byte string with SipHash-2-4, keyed with two 64-bit words, as
+/// specified by BIP158 for the elements of compact block filters.
+pub(crate) fn siphash24(k0: u64, k1: u64, bytes: &[u8]) -> u64 {
+    let mut v = [
+        k0 ^ 0x736F6D6570736575,
+        k1 ^ 0x646F72616E646F6D,
+        k0 ^ 0x6C7967656E657261,
+        k1 ^ 0x7465646279746573,
+    ];
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let m = u64::from_le_bytes(chunk.try_into().expect("The chunk has 8 bytes."));
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+    let mut last = (bytes.len() as u64) << 56;
+    for (i, &byte) in chunks.remainder().iter().enumerate() {
+        last |= u64::from(byte) << (8 * i);
+    }
+    v[3] ^= last;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= last;
+
+    v[2] ^= 0xFF;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline]
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_siphash24() {
+        // The test vectors of the reference implementation, with the key
+        // 00 01 .. 0f and the messages 00 01 .. (n - 1).
+        let (k0, k1) = (0x0706050403020100, 0x0F0E0D0C0B0A0908);
+        let msg: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(k0, k1, &[]), 0x726FDB47DD0E0E31);
+        assert_eq!(siphash24(k0, k1, &msg), 0xA129CA6149BE45E5);
+    }
+}
@@ -0,0 +1,639 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::bits_to_numeric;
+use crate::num::convert::write_fixed_bits;
+
+/// The number of bits of an ID that select its chunk.
+const CHUNK_BITS: u32 = 16;
+
+/// The number of IDs in the range of a chunk.
+const CHUNK_RANGE: usize = 1 << CHUNK_BITS;
+
+/// The number of bytes of the bitmap of a dense chunk.
+const BITMAP_BYTES: usize = CHUNK_RANGE / 8;
+
+/// The largest number of IDs of a sparse chunk, above which its sorted array
+/// would be larger than a bitmap.
+const MAX_SPARSE_LEN: usize = BITMAP_BYTES / 2;
+
+/// The number of bits of the tag of the representation of a serialized chunk.
+const KIND_BITS: u32 = 2;
+
+/// The representation of the IDs of a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkKind {
+    /// The IDs are a sorted array, serialized as gaps encoded with Elias Gamma
+    /// Encoding.
+    Sparse,
+    /// The IDs are the set bits of a bitmap over the range of the chunk.
+    Dense,
+    /// The IDs are runs of consecutive IDs, each stored as its first ID and
+    /// its length.
+    Runs,
+}
+
+impl ChunkKind {
+    fn tag(self) -> u8 {
+        match self {
+            ChunkKind::Sparse => 0,
+            ChunkKind::Dense => 1,
+            ChunkKind::Runs => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Repr {
+    Sparse(Vec<u16>),
+    Dense(Vec<u64>),
+    /// The first ID of each run, along with the length of the run minus one.
+    Runs(Vec<(u16, u16)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    key: u16,
+    repr: Repr,
+}
+
+impl Chunk {
+    /// Creates a chunk from the sorted and deduplicated lowest 16 bits of its
+    /// IDs, with the smallest of the three representations.
+    fn new(key: u16, lows: &[u16]) -> Self {
+        let runs = to_runs(lows);
+        let sparse_size = 2 * lows.len();
+        let runs_size = 4 * runs.len();
+
+        let repr = if runs_size < sparse_size.min(BITMAP_BYTES) {
+            Repr::Runs(runs)
+        } else if lows.len() <= MAX_SPARSE_LEN {
+            Repr::Sparse(lows.to_vec())
+        } else {
+            let mut bitmap = vec![0_u64; CHUNK_RANGE / 64];
+            for &low in lows {
+                bitmap[low as usize / 64] |= 1 << (low % 64);
+            }
+            Repr::Dense(bitmap)
+        };
+        Chunk { key, repr }
+    }
+
+    fn kind(&self) -> ChunkKind {
+        match self.repr {
+            Repr::Sparse(_) => ChunkKind::Sparse,
+            Repr::Dense(_) => ChunkKind::Dense,
+            Repr::Runs(_) => ChunkKind::Runs,
+        }
+    }
+
+    /// Returns the sorted lowest 16 bits of the IDs of the chunk.
+    fn lows(&self) -> Vec<u16> {
+        match &self.repr {
+            Repr::Sparse(lows) => lows.clone(),
+            Repr::Dense(bitmap) => (0..CHUNK_RANGE)
+                .filter(|low| bitmap[low / 64] & (1 << (low % 64)) != 0)
+                .map(|low| low as u16)
+                .collect(),
+            Repr::Runs(runs) => runs
+                .iter()
+                .flat_map(|&(start, len)| start..=start + len)
+                .collect(),
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match &self.repr {
+            Repr::Sparse(lows) => lows.binary_search(&low).is_ok(),
+            Repr::Dense(bitmap) => bitmap[low as usize / 64] & (1 << (low % 64)) != 0,
+            Repr::Runs(runs) => {
+                let idx = runs.partition_point(|&(start, _)| start <= low);
+                idx > 0 && low - runs[idx - 1].0 <= runs[idx - 1].1
+            }
+        }
+    }
+
+    /// Inserts the lowest 16 bits of an ID, returning whether it was not
+    /// already in the chunk.
+    ///
+    /// A sparse chunk that grows larger than a bitmap becomes dense, and a
+    /// chunk of runs that grows larger than either of the other two
+    /// representations is converted to the smallest of them.
+    fn insert(&mut self, low: u16) -> bool {
+        if self.contains(low) {
+            return false;
+        }
+        match &mut self.repr {
+            Repr::Sparse(lows) => {
+                let idx = lows.partition_point(|&l| l < low);
+                lows.insert(idx, low);
+                if lows.len() > MAX_SPARSE_LEN {
+                    *self = Chunk::new(self.key, &self.lows());
+                }
+            }
+            Repr::Dense(bitmap) => bitmap[low as usize / 64] |= 1 << (low % 64),
+            Repr::Runs(runs) => {
+                insert_into_runs(runs, low);
+                let len: usize = runs.iter().map(|&(_, len)| len as usize + 1).sum();
+                if 4 * runs.len() >= (2 * len).min(BITMAP_BYTES) {
+                    *self = Chunk::new(self.key, &self.lows());
+                }
+            }
+        }
+        true
+    }
+
+    fn encoded_size(&self) -> usize {
+        match &self.repr {
+            Repr::Sparse(lows) => 2 * lows.len(),
+            Repr::Dense(_) => BITMAP_BYTES,
+            Repr::Runs(runs) => 4 * runs.len(),
+        }
+    }
+
+    /// Writes the chunk: its key in 16 bits and its representation in 2 bits,
+    /// followed by its IDs in the representation.
+    fn write_bits(&self, bits: &mut Vec<bool>) {
+        let convention = UnaryConvention::default();
+        write_fixed_bits(&self.key, CHUNK_BITS, bits);
+        write_fixed_bits(&self.kind().tag(), KIND_BITS, bits);
+        match &self.repr {
+            Repr::Sparse(lows) => {
+                write_gamma_bits(lows.len(), convention, bits);
+                let mut next = 0;
+                for &low in lows {
+                    write_gamma_bits(u32::from(low) - next + 1, convention, bits);
+                    next = u32::from(low) + 1;
+                }
+            }
+            Repr::Dense(bitmap) => {
+                for word in bitmap {
+                    write_fixed_bits(word, u64::BITS, bits);
+                }
+            }
+            Repr::Runs(runs) => {
+                write_gamma_bits(runs.len(), convention, bits);
+                let mut next = 0;
+                for &(start, len) in runs {
+                    write_gamma_bits(u32::from(start) - next + 1, convention, bits);
+                    write_gamma_bits(u32::from(len) + 1, convention, bits);
+                    next = u32::from(start) + u32::from(len) + 1;
+                }
+            }
+        }
+    }
+
+    /// Reads a chunk written with [`Chunk::write_bits`], returning it along
+    /// with the number of bits it occupies.
+    fn read_bits(bits: &[bool]) -> Result<(Self, usize), InvalidCodeError> {
+        let err = || InvalidCodeError::IdSetCodeError;
+        let convention = UnaryConvention::default();
+        let fixed = |pos: usize, width: u32| -> Result<u64, InvalidCodeError> {
+            let num_bits = bits.get(pos..pos + width as usize).ok_or_else(err)?;
+            bits_to_numeric(num_bits).map_err(|_| err())
+        };
+        let gamma = |pos: &mut usize| -> Result<u64, InvalidCodeError> {
+            let rest = bits.get(*pos..).ok_or_else(err)?;
+            let (num, len) = decode_gamma_prefix::<u32>(rest, convention).map_err(|_| err())?;
+            *pos += len;
+            Ok(u64::from(num))
+        };
+
+        let key = fixed(0, CHUNK_BITS)? as u16;
+        let mut pos = (CHUNK_BITS + KIND_BITS) as usize;
+        let repr = match fixed(CHUNK_BITS as usize, KIND_BITS)? {
+            0 => {
+                let len = gamma(&mut pos)? as usize;
+                if len == 0 || len > CHUNK_RANGE {
+                    return Err(err());
+                }
+                let mut lows = Vec::with_capacity(len);
+                let mut next = 0;
+                for _ in 0..len {
+                    let low = next + gamma(&mut pos)? - 1;
+                    lows.push(u16::try_from(low).map_err(|_| err())?);
+                    next = low + 1;
+                }
+                Repr::Sparse(lows)
+            }
+            1 => {
+                let mut bitmap = Vec::with_capacity(CHUNK_RANGE / 64);
+                for _ in 0..CHUNK_RANGE / 64 {
+                    bitmap.push(fixed(pos, u64::BITS)?);
+                    pos += u64::BITS as usize;
+                }
+                Repr::Dense(bitmap)
+            }
+            2 => {
+                let len = gamma(&mut pos)? as usize;
+                if len == 0 || len > CHUNK_RANGE / 2 {
+                    return Err(err());
+                }
+                let mut runs = Vec::with_capacity(len);
+                let mut next = 0;
+                for _ in 0..len {
+                    let start = next + gamma(&mut pos)? - 1;
+                    let len = gamma(&mut pos)? - 1;
+                    if start + len >= CHUNK_RANGE as u64 {
+                        return Err(err());
+                    }
+                    runs.push((start as u16, len as u16));
+                    next = start + len + 1;
+                }
+                Repr::Runs(runs)
+            }
+            _ => return Err(err()),
+        };
+        Ok((Chunk { key, repr }, pos))
+    }
+}
+
+/// Returns the runs of consecutive numbers of a sorted and deduplicated
+/// sequence, as their first number along with their length minus one.
+fn to_runs(lows: &[u16]) -> Vec<(u16, u16)> {
+    let mut runs: Vec<(u16, u16)> = vec![];
+    for &low in lows {
+        match runs.last_mut() {
+            Some((start, len)) if u32::from(*start) + u32::from(*len) + 1 == u32::from(low) => {
+                *len += 1
+            }
+            _ => runs.push((low, 0)),
+        }
+    }
+    runs
+}
+
+/// Inserts a number that is not in any of the runs, extending or merging the
+/// runs that it is adjacent to.
+fn insert_into_runs(runs: &mut Vec<(u16, u16)>, low: u16) {
+    let idx = runs.partition_point(|&(start, _)| start < low);
+    let extends_prev = idx > 0 && {
+        let (start, len) = runs[idx - 1];
+        u32::from(start) + u32::from(len) + 1 == u32::from(low)
+    };
+    let extends_next = idx < runs.len() && u32::from(low) + 1 == u32::from(runs[idx].0);
+    match (extends_prev, extends_next) {
+        (true, true) => {
+            let (_, next_len) = runs.remove(idx);
+            runs[idx - 1].1 += next_len + 2;
+        }
+        (true, false) => runs[idx - 1].1 += 1,
+        (false, true) => {
+            runs[idx].0 = low;
+            runs[idx].1 += 1;
+        }
+        (false, false) => runs.insert(idx, (low, 0)),
+    }
+}
+
+/// A set of 32-bit IDs, that switches between a sparse, a dense and a
+/// run-length representation depending on the distribution of the IDs, like
+/// Roaring bitmaps.
+///
+/// The IDs are split in chunks by their highest 16 bits. Each chunk stores the
+/// lowest 16 bits of its IDs either as a sorted array, as a bitmap over the
+/// 65536 IDs of its range, or as runs of consecutive IDs, whichever is
+/// smallest. The representation of each chunk is recorded, and can be
+/// inspected with [`IdSet::chunk_kinds`].
+///
+/// IDs inserted one at a time change the representation of a chunk when it
+/// grows larger than another one, and [`IdSet::optimize`] picks the smallest
+/// representation of every chunk again.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::{ChunkKind, IdSet};
+///
+/// let mut ids: Vec<u32> = (0..65_536).step_by(2).collect();
+/// ids.extend([1 << 20, (1 << 20) + 9, u32::MAX]);
+/// ids.extend((1 << 24)..(1 << 24) + 10_000);
+/// let set: IdSet = ids.iter().copied().collect();
+///
+/// assert_eq!(set.len(), 42_771);
+/// assert!(set.contains(1 << 20));
+/// assert!(!set.contains(1));
+/// assert_eq!(
+///     set.chunk_kinds().collect::<Vec<_>>(),
+///     vec![
+///         (0, ChunkKind::Dense),
+///         (16, ChunkKind::Sparse),
+///         (256, ChunkKind::Runs),
+///         (65535, ChunkKind::Sparse),
+///     ]
+/// );
+/// ids.sort();
+/// assert_eq!(set.iter().collect::<Vec<_>>(), ids);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdSet {
+    chunks: Vec<Chunk>,
+    len: usize,
+}
+
+impl IdSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of IDs in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set has no IDs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the set contains an ID.
+    pub fn contains(&self, id: u32) -> bool {
+        let key = (id >> CHUNK_BITS) as u16;
+        match self.chunks.binary_search_by_key(&key, |chunk| chunk.key) {
+            Ok(idx) => self.chunks[idx].contains(id as u16),
+            Err(_) => false,
+        }
+    }
+
+    /// Inserts an ID, returning whether it was not already in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::collections::{ChunkKind, IdSet};
+    ///
+    /// let mut set = IdSet::new();
+    /// assert!(set.insert(5));
+    /// assert!(!set.insert(5));
+    /// assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Sparse)));
+    ///
+    /// for id in 0..5000 {
+    ///     set.insert(id * 3);
+    /// }
+    /// assert_eq!(set.len(), 5001);
+    /// assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Dense)));
+    /// ```
+    pub fn insert(&mut self, id: u32) -> bool {
+        let key = (id >> CHUNK_BITS) as u16;
+        let inserted = match self.chunks.binary_search_by_key(&key, |chunk| chunk.key) {
+            Ok(idx) => self.chunks[idx].insert(id as u16),
+            Err(idx) => {
+                self.chunks.insert(idx, Chunk::new(key, &[id as u16]));
+                true
+            }
+        };
+        self.len += usize::from(inserted);
+        inserted
+    }
+
+    /// Converts every chunk to the smallest of its representations.
+    pub fn optimize(&mut self) {
+        for chunk in &mut self.chunks {
+            *chunk = Chunk::new(chunk.key, &chunk.lows());
+        }
+    }
+
+    /// Returns an iterator over the IDs of the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.chunks.iter().flat_map(|chunk| {
+            let high = u32::from(chunk.key) << CHUNK_BITS;
+            chunk
+                .lows()
+                .into_iter()
+                .map(move |low| high | u32::from(low))
+        })
+    }
+
+    /// Returns an iterator over the highest 16 bits of the IDs of each chunk,
+    /// along with the representation of the chunk.
+    pub fn chunk_kinds(&self) -> impl Iterator<Item = (u16, ChunkKind)> + '_ {
+        self.chunks.iter().map(|chunk| (chunk.key, chunk.kind()))
+    }
+
+    /// Returns the number of bytes that the IDs occupy in their in-memory
+    /// representations: 2 per ID of a sparse chunk, 8192 per dense chunk, and
+    /// 4 per run.
+    pub fn encoded_size(&self) -> usize {
+        self.chunks.iter().map(Chunk::encoded_size).sum()
+    }
+
+    /// Writes the set with a bit writer: the number of chunks, incremented by
+    /// one, in Elias Gamma Encoding, followed by each chunk. A chunk is written
+    /// as its highest 16 bits and a 2-bit tag of its representation, followed
+    /// by
+    ///
+    /// - the number of IDs and their gaps, in Elias Gamma Encoding, for a
+    ///   sparse chunk,
+    /// - the 65536 bits of the bitmap, for a dense chunk, and
+    /// - the number of runs, and the gap before and the length of each run, in
+    ///   Elias Gamma Encoding, for a chunk of runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::collections::IdSet;
+    /// use idencode::{BitReader, BitWriter};
+    ///
+    /// let set: IdSet = (0..100_000).step_by(3).chain(1 << 30..(1 << 30) + 500).collect();
+    /// let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+    /// set.write(&mut writer).unwrap();
+    /// let bytes = writer.finalize().unwrap().into_inner();
+    ///
+    /// let reader = BitReader::new(Cursor::new(bytes), true);
+    /// assert_eq!(IdSet::read(reader).unwrap(), set);
+    /// ```
+    pub fn write<W: Write>(&self, writer: &mut BitWriter<W>) -> io::Result<()> {
+        let mut bits = vec![];
+        write_gamma_bits(self.chunks.len() + 1, UnaryConvention::default(), &mut bits);
+        for chunk in &self.chunks {
+            chunk.write_bits(&mut bits);
+        }
+        writer.write_bits(&bits)
+    }
+
+    /// Reads a set written with [`IdSet::write`]. Any bits after the chunks
+    /// are ignored.
+    ///
+    /// Returns an error if the bits are not a written set, or if its chunks
+    /// are not in increasing order.
+    pub fn read<R: Read>(reader: BitReader<R>) -> Result<Self, InvalidCodeError> {
+        let bits = reader
+            .read_to_end()
+            .map_err(|_| InvalidCodeError::IdSetCodeError)?
+            .into_bits();
+        let (num_chunks, mut pos) = decode_gamma_prefix::<usize>(&bits, UnaryConvention::default())
+            .map_err(|_| InvalidCodeError::IdSetCodeError)?;
+
+        let mut set = IdSet::new();
+        for _ in 0..num_chunks - 1 {
+            let (chunk, len) = Chunk::read_bits(&bits[pos..])?;
+            if set.chunks.last().is_some_and(|last| last.key >= chunk.key) {
+                return Err(InvalidCodeError::IdSetCodeError);
+            }
+            set.len += match &chunk.repr {
+                Repr::Sparse(lows) => lows.len(),
+                Repr::Dense(bitmap) => bitmap.iter().map(|w| w.count_ones() as usize).sum(),
+                Repr::Runs(runs) => runs.iter().map(|&(_, len)| len as usize + 1).sum(),
+            };
+            set.chunks.push(chunk);
+            pos += len;
+        }
+        Ok(set)
+    }
+}
+
+impl FromIterator<u32> for IdSet {
+    /// Creates a set from IDs in any order, ignoring duplicates.
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut ids: Vec<u32> = iter.into_iter().collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let chunks: Vec<Chunk> = ids
+            .chunk_by(|a, b| a >> CHUNK_BITS == b >> CHUNK_BITS)
+            .map(|group| {
+                let key = (group[0] >> CHUNK_BITS) as u16;
+                let lows: Vec<u16> = group.iter().map(|id| *id as u16).collect();
+                Chunk::new(key, &lows)
+            })
+            .collect();
+        IdSet {
+            chunks,
+            len: ids.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_representation_switch() {
+        let sparse: IdSet = (0..1000).map(|i| i * 61).collect();
+        assert_eq!(sparse.chunk_kinds().next(), Some((0, ChunkKind::Sparse)));
+        assert!(sparse.encoded_size() < BITMAP_BYTES);
+
+        let dense: IdSet = (0..60_000).filter(|i| i % 3 != 0).collect();
+        assert_eq!(dense.chunk_kinds().next(), Some((0, ChunkKind::Dense)));
+        assert_eq!(dense.encoded_size(), BITMAP_BYTES);
+
+        let runs: IdSet = (0..60_000).filter(|i| i % 1000 < 900).collect();
+        assert_eq!(runs.chunk_kinds().next(), Some((0, ChunkKind::Runs)));
+        assert_eq!(runs.encoded_size(), 4 * 60);
+    }
+
+    #[test]
+    fn test_contains_and_iter() {
+        let ids = [7_u32, 3, 7, 65_535, 65_536, 1 << 31, 0];
+        let set: IdSet = ids.iter().copied().collect();
+        assert_eq!(set.len(), 6);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![0, 3, 7, 65_535, 65_536, 1 << 31]
+        );
+        assert!(set.contains(65_536));
+        assert!(!set.contains(65_537));
+        assert!(!set.contains(4));
+
+        let dense: IdSet = (100..70_000).collect();
+        assert!(dense.contains(65_535));
+        assert!(!dense.contains(99));
+        assert_eq!(dense.iter().count(), 69_900);
+    }
+
+    #[test]
+    fn test_insert() {
+        // Inserting in a scattered order merges and extends the runs.
+        let mut set: IdSet = (0..1000).chain(2000..3000).collect();
+        assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Runs)));
+        for i in 0..1000 {
+            assert!(set.insert(1000 + (i * 7) % 1000));
+        }
+        assert!(!set.insert(1500));
+        assert_eq!(set.len(), 3000);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            (0..3000).collect::<Vec<_>>()
+        );
+        assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Runs)));
+        assert_eq!(set.encoded_size(), 4);
+
+        // Breaking up the runs turns them into a sorted array.
+        let mut set: IdSet = (0..10).collect();
+        for i in 0..100 {
+            set.insert(20 + 2 * i);
+        }
+        assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Sparse)));
+        assert_eq!(set.len(), 110);
+
+        // A full chunk inserted one ID at a time is dense, until optimized.
+        let mut set = IdSet::new();
+        let evens = (0..CHUNK_RANGE as u32).step_by(2);
+        for id in evens.chain((1..CHUNK_RANGE as u32).step_by(2)) {
+            set.insert(id);
+        }
+        assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Dense)));
+        set.optimize();
+        assert_eq!(set.chunk_kinds().next(), Some((0, ChunkKind::Runs)));
+        assert_eq!(set.len(), CHUNK_RANGE);
+        assert!(set.contains(u16::MAX as u32));
+    }
+
+    #[test]
+    fn test_write_read() {
+        let sets: Vec<IdSet> = vec![
+            IdSet::new(),
+            [0, u32::MAX].into_iter().collect(),
+            (0..70_000).filter(|i| i % 3 != 0).collect(),
+            (0..200_000).filter(|i| i % 5000 < 4000).collect(),
+            (0..u32::MAX).step_by(65_537).collect(),
+        ];
+        for set in sets {
+            let mut writer = BitWriter::new(Cursor::new(vec![]), false);
+            set.write(&mut writer).unwrap();
+            let bytes = writer.finalize().unwrap().into_inner();
+            let read = IdSet::read(BitReader::new(Cursor::new(bytes), false)).unwrap();
+            assert_eq!(read.len(), set.len());
+            assert_eq!(read, set);
+        }
+    }
+
+    #[test]
+    fn test_read_errs() {
+        let set: IdSet = [3, 1 << 20].into_iter().collect();
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        set.write(&mut writer).unwrap();
+        let mut bytes = writer.finalize().unwrap().into_inner();
+        bytes.truncate(bytes.len() - 2);
+        assert!(IdSet::read(BitReader::new(Cursor::new(bytes), true)).is_err());
+
+        // Two chunks with the same key.
+        let mut bits = vec![];
+        write_gamma_bits(3_u32, UnaryConvention::default(), &mut bits);
+        let chunk = Chunk::new(4, &[1]);
+        chunk.write_bits(&mut bits);
+        chunk.write_bits(&mut bits);
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        let bytes = writer.finalize().unwrap().into_inner();
+        assert!(IdSet::read(BitReader::new(Cursor::new(bytes), true)).is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        let set = IdSet::new();
+        assert!(set.is_empty());
+        assert!(!set.contains(0));
+        assert_eq!(set.iter().next(), None);
+    }
+}
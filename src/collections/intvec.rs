@@ -0,0 +1,272 @@
+use std::io::{self, Read, Write};
+
+use crate::code::global::gamma::{decode_gamma_prefix, write_gamma_bits};
+use crate::code::global::unary::UnaryConvention;
+use crate::error::InvalidCodeError;
+use crate::io::read::BitReader;
+use crate::io::write::BitWriter;
+use crate::num::bits_to_numeric;
+use crate::num::convert::write_fixed_bits;
+
+const WORD_BITS: usize = 64;
+
+/// A vector of unsigned integers of a fixed width, chosen at construction,
+/// packed contiguously in 64-bit words.
+///
+/// The integer at position `i` occupies the bits `width * i` up to
+/// `width * (i + 1)` of the words, starting from the least significant bit of
+/// the first word, so an integer may straddle two words. Integers that need
+/// `width` bits, like the low bits of an Elias–Fano sequence, take exactly
+/// `width` bits each.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::collections::IntVec;
+///
+/// let mut ints = IntVec::new(5);
+/// for num in [3, 31, 0, 17] {
+///     ints.push(num);
+/// }
+/// ints.set(2, 9);
+/// assert_eq!(ints.get(1), Some(31));
+/// assert_eq!(ints.iter().collect::<Vec<_>>(), vec![3, 31, 9, 17]);
+/// assert_eq!(ints.as_words().len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntVec {
+    words: Vec<u64>,
+    width: u32,
+    len: usize,
+}
+
+impl IntVec {
+    /// Creates an empty vector of integers of `width` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is greater than 64.
+    pub fn new(width: u32) -> Self {
+        assert!(width <= u64::BITS, "The width must be at most 64 bits.");
+        IntVec {
+            words: vec![],
+            width,
+            len: 0,
+        }
+    }
+
+    /// Returns the width of the integers, in bits.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the number of integers in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no integers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the words that the integers are packed in.
+    #[inline]
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    // Returns the mask of the lowest `width` bits.
+    fn mask(&self) -> u64 {
+        u64::MAX.checked_shr(u64::BITS - self.width).unwrap_or(0)
+    }
+
+    /// Appends an integer to the back of the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer does not fit in the width of the vector.
+    pub fn push(&mut self, num: u64) {
+        self.len += 1;
+        self.words
+            .resize((self.len * self.width as usize).div_ceil(WORD_BITS), 0);
+        self.set(self.len - 1, num);
+    }
+
+    /// Returns the integer at position `index`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+        if self.width == 0 {
+            return Some(0);
+        }
+        let pos = index * self.width as usize;
+        let (word, offset) = (pos / WORD_BITS, pos % WORD_BITS);
+        let mut num = self.words[word] >> offset;
+        if offset + self.width as usize > WORD_BITS {
+            num |= self.words[word + 1] << (WORD_BITS - offset);
+        }
+        Some(num & self.mask())
+    }
+
+    /// Replaces the integer at position `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`, or if the integer does not fit in the width
+    /// of the vector.
+    pub fn set(&mut self, index: usize, num: u64) {
+        assert!(index < self.len, "The index is out of bounds.");
+        let mask = self.mask();
+        assert!(num & !mask == 0, "The integer does not fit in the width.");
+        if self.width == 0 {
+            return;
+        }
+        let pos = index * self.width as usize;
+        let (word, offset) = (pos / WORD_BITS, pos % WORD_BITS);
+        self.words[word] &= !(mask << offset);
+        self.words[word] |= num << offset;
+        if offset + self.width as usize > WORD_BITS {
+            let shift = WORD_BITS - offset;
+            self.words[word + 1] &= !(mask >> shift);
+            self.words[word + 1] |= num >> shift;
+        }
+    }
+
+    /// Returns an iterator over the integers.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(|i| self.get(i).expect("The position is within the vector."))
+    }
+
+    /// Writes the vector with a bit writer: the width and the number of
+    /// integers, incremented by one, in Elias Gamma Encoding, followed by the
+    /// integers in `width` bits each, most significant bit first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::collections::IntVec;
+    /// use idencode::{BitReader, BitWriter};
+    ///
+    /// let mut ints = IntVec::new(3);
+    /// ints.push(5);
+    /// ints.push(2);
+    /// let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+    /// ints.write(&mut writer).unwrap();
+    /// let bytes = writer.finalize().unwrap().into_inner();
+    /// assert_eq!(bytes, vec![0b11000101, 0b10101010]);
+    ///
+    /// let reader = BitReader::new(Cursor::new(bytes), true);
+    /// assert_eq!(IntVec::read(reader).unwrap(), ints);
+    /// ```
+    pub fn write<W: Write>(&self, writer: &mut BitWriter<W>) -> io::Result<()> {
+        let convention = UnaryConvention::default();
+        let mut bits = vec![];
+        write_gamma_bits(self.width + 1, convention, &mut bits);
+        write_gamma_bits(self.len + 1, convention, &mut bits);
+        for num in self.iter() {
+            write_fixed_bits(&num, self.width, &mut bits);
+        }
+        writer.write_bits(&bits)
+    }
+
+    /// Reads a vector written with [`IntVec::write`]. Any bits after the
+    /// integers are ignored.
+    pub fn read<R: Read>(reader: BitReader<R>) -> Result<Self, InvalidCodeError> {
+        let bits = reader.read_to_end().unwrap().into_bits();
+        let convention = UnaryConvention::default();
+        let (width, width_len) = decode_gamma_prefix::<u32>(&bits, convention)
+            .map_err(|_| InvalidCodeError::IntVecCodeError)?;
+        let width = width - 1;
+        let (len, len_len) = decode_gamma_prefix::<usize>(&bits[width_len..], convention)
+            .map_err(|_| InvalidCodeError::IntVecCodeError)?;
+        let len = len - 1;
+        if width > u64::BITS || len.saturating_mul(width as usize) > bits.len() {
+            return Err(InvalidCodeError::IntVecCodeError);
+        }
+
+        let mut ints = IntVec::new(width);
+        let mut pos = width_len + len_len;
+        for _ in 0..len {
+            let num_bits = bits
+                .get(pos..pos + width as usize)
+                .ok_or(InvalidCodeError::IntVecCodeError)?;
+            ints.push(bits_to_numeric(num_bits).map_err(|_| InvalidCodeError::IntVecCodeError)?);
+            pos += width as usize;
+        }
+        Ok(ints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_get_set() {
+        for width in [0, 1, 7, 13, 32, 63, 64] {
+            let mut ints = IntVec::new(width);
+            let mask = u64::MAX.checked_shr(64 - width).unwrap_or(0);
+            let nums: Vec<u64> = (0..200_u64)
+                .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) & mask)
+                .collect();
+            for &num in &nums {
+                ints.push(num);
+            }
+            assert_eq!(ints.len(), 200);
+            assert_eq!(ints.as_words().len(), (200 * width as usize).div_ceil(64));
+            assert_eq!(ints.iter().collect::<Vec<_>>(), nums);
+
+            // Overwriting an integer leaves its neighbours intact.
+            ints.set(100, mask);
+            assert_eq!(ints.get(99), Some(nums[99]));
+            assert_eq!(ints.get(100), Some(mask));
+            assert_eq!(ints.get(101), Some(nums[101]));
+            assert_eq!(ints.get(200), None);
+        }
+    }
+
+    #[test]
+    fn test_write_read() {
+        for width in [0, 5, 64] {
+            let mut ints = IntVec::new(width);
+            for i in 0..100 {
+                ints.push(u64::MAX.checked_shr(64 - width).unwrap_or(0) & (i * 7));
+            }
+            let mut writer = BitWriter::new(Cursor::new(vec![]), false);
+            ints.write(&mut writer).unwrap();
+            let bytes = writer.finalize().unwrap().into_inner();
+            let reader = BitReader::new(Cursor::new(bytes), false);
+            assert_eq!(IntVec::read(reader), Ok(ints));
+        }
+    }
+
+    #[test]
+    fn test_errs() {
+        // A width of 65 bits.
+        let mut bits = vec![];
+        write_gamma_bits(66_u32, UnaryConvention::default(), &mut bits);
+        write_gamma_bits(1_u32, UnaryConvention::default(), &mut bits);
+        let mut writer = BitWriter::new(Cursor::new(vec![]), true);
+        writer.write_bits(&bits).unwrap();
+        let bytes = writer.finalize().unwrap().into_inner();
+        assert!(IntVec::read(BitReader::new(Cursor::new(bytes), true)).is_err());
+
+        // Two integers of 3 bits, but a single one written.
+        let reader = BitReader::new(Cursor::new(vec![0b11000101, 0b10110000]), true);
+        assert!(IntVec::read(reader).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_wide() {
+        IntVec::new(3).push(8);
+    }
+}
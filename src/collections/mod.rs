@@ -1,3 +1,23 @@
+pub mod array;
+pub mod atomic;
+#[cfg(feature = "bitvec")]
+pub mod bitvec_compat;
+pub mod bloom;
+pub mod ewah;
+pub mod gcs;
+pub(crate) mod hash;
+pub mod idset;
+pub mod intvec;
+pub mod slice;
+mod text;
 pub mod vec;
 
+pub use array::BitArray;
+pub use atomic::AtomicBitVec;
+pub use bloom::BloomFilter;
+pub use ewah::Ewah;
+pub use gcs::GolombSet;
+pub use idset::{ChunkKind, IdSet};
+pub use intvec::IntVec;
+pub use slice::BitSlice;
 pub use vec::BitVec;
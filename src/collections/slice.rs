@@ -0,0 +1,146 @@
+use std::ops::{Bound, RangeBounds};
+
+use crate::BitVec;
+
+/// A borrowed view of a range of bits of a [`BitVec`].
+///
+/// The range may start in the middle of a byte, so the view keeps the bytes
+/// that hold it along with the offset of its first bit in the first byte.
+/// The bits are only shifted when the view is copied with
+/// [`BitSlice::to_bitvec`].
+///
+/// # Examples
+///
+/// ```
+/// use idencode::{BitVec, bitvec};
+///
+/// let bitvec = bitvec![true, false, true, true, false, false, true, true, true, false];
+/// let slice = bitvec.slice(3..9);
+/// assert_eq!(slice.len(), 6);
+/// assert_eq!(slice.get(0), Some(true));
+/// assert_eq!(slice.to_bitvec(), bitvec![true, false, false, true, true, true]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitSlice<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    len: usize,
+}
+
+impl<'a> BitSlice<'a> {
+    /// Creates a view of the bits of a bit-vector within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end, or if its end
+    /// is greater than the length of the bit-vector.
+    pub(crate) fn new<R: RangeBounds<usize>>(bitvec: &'a BitVec, range: R) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => bitvec.len(),
+        };
+        assert!(start <= end, "The range starts after it ends.");
+        assert!(end <= bitvec.len(), "The range ends past the bit-vector.");
+        BitSlice {
+            bytes: &bitvec.as_bytes()[start / 8..end.div_ceil(8)],
+            offset: start % 8,
+            len: end - start,
+        }
+    }
+
+    /// Returns the number of bits in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the view contains no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at position `index` of the view, or `None` if it is
+    /// out of bounds.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+        let pos = self.offset + index;
+        Some(self.bytes[pos / 8] & (1 << (7 - pos % 8)) != 0)
+    }
+
+    /// Copies the bits of the view to a new bit-vector, shifting them so that
+    /// the first bit of the view is the first bit of the bit-vector.
+    pub fn to_bitvec(&self) -> BitVec {
+        if self.len == 0 {
+            return BitVec::default();
+        }
+        let n_bytes = self.len.div_ceil(8);
+        let shift = self.offset as u32;
+        let mut bytes: Vec<u8> = (0..n_bytes)
+            .map(|i| {
+                let hi = self.bytes[i];
+                let lo = self.bytes.get(i + 1).copied().unwrap_or(0);
+                match shift {
+                    0 => hi,
+                    _ => (hi << shift) | (lo >> (8 - shift)),
+                }
+            })
+            .collect();
+        if !self.len.is_multiple_of(8) {
+            bytes[n_bytes - 1] &= u8::MAX << (8 - self.len % 8);
+        }
+        BitVec::with_len(bytes, self.len).expect("The length matches the bytes.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+
+    #[test]
+    fn test_ranges() {
+        let bits: Vec<bool> = (0..50).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut bitvec = BitVec::default();
+        bitvec.extend_from_slice(&bits);
+        for (start, end) in [
+            (0, 50),
+            (0, 0),
+            (3, 11),
+            (8, 16),
+            (13, 14),
+            (9, 50),
+            (50, 50),
+        ] {
+            let slice = bitvec.slice(start..end);
+            assert_eq!(slice.len(), end - start);
+            let expected: Vec<bool> = (0..slice.len()).map(|i| slice.get(i).unwrap()).collect();
+            assert_eq!(expected, bits[start..end]);
+            assert_eq!(slice.get(slice.len()), None);
+            assert_eq!(bitvec.subvec(start..end).into_bits(), bits[start..end]);
+        }
+        assert_eq!(bitvec.slice(..).len(), 50);
+        assert_eq!(bitvec.slice(10..=19).len(), 10);
+    }
+
+    #[test]
+    fn test_padding() {
+        let bitvec = bitvec![true; 20];
+        assert_eq!(*bitvec.subvec(3..14).as_bytes(), [0xFF, 0b11100000]);
+        assert!(bitvec.slice(5..5).to_bitvec().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        bitvec![true; 20].slice(5..21);
+    }
+}
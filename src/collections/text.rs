@@ -0,0 +1,211 @@
+use crate::error::ParseBitVecError;
+use crate::BitVec;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+#[cfg(feature = "base64")]
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Splits a textual bit-vector into its length in bits and its payload.
+fn split_len(text: &str) -> Result<(usize, &str), ParseBitVecError> {
+    let (len, payload) = text.split_once(':').ok_or(ParseBitVecError)?;
+    let len = len.parse().map_err(|_| ParseBitVecError)?;
+    Ok((len, payload))
+}
+
+// Creates a bit-vector of the specified length from its bytes, rejecting
+// missing or extra bytes and 1-bits in the padding of the last byte.
+fn from_bytes(bytes: Vec<u8>, len: usize) -> Result<BitVec, ParseBitVecError> {
+    if bytes.len() != len.div_ceil(8) {
+        return Err(ParseBitVecError);
+    }
+    if len == 0 {
+        return Ok(BitVec::default());
+    }
+    if !len.is_multiple_of(8) && bytes[bytes.len() - 1] & (u8::MAX >> (len % 8)) != 0 {
+        return Err(ParseBitVecError);
+    }
+    BitVec::with_len(bytes, len).map_err(|_| ParseBitVecError)
+}
+
+impl BitVec {
+    /// Formats the bit-vector as its length in bits, followed by a colon and
+    /// its bytes in lowercase hexadecimal digits.
+    ///
+    /// The length makes the round trip through [`BitVec::from_hex`] exact,
+    /// even if the last byte is padded. An encoded stream can be formatted by
+    /// wrapping its bytes with [`BitVec::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, false, true, false, true, true, true, true, true, false];
+    /// assert_eq!(bitvec.to_hex(), "10:af80");
+    /// assert_eq!(BitVec::from_hex("10:af80").unwrap(), bitvec);
+    /// assert_eq!(BitVec::new(vec![0x0F, 0x80]).to_hex(), "16:0f80");
+    /// ```
+    pub fn to_hex(&self) -> String {
+        let mut text = format!("{}:", self.len());
+        for byte in &self.as_bytes()[..self.len().div_ceil(8)] {
+            text.push(HEX_DIGITS[usize::from(byte >> 4)] as char);
+            text.push(HEX_DIGITS[usize::from(byte & 0x0F)] as char);
+        }
+        text
+    }
+
+    /// Parses a bit-vector formatted with [`BitVec::to_hex`]. Both lowercase
+    /// and uppercase hexadecimal digits are accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the length or the digits are invalid, if the number
+    /// of bytes does not match the length, or if the padding bits are not 0.
+    pub fn from_hex(text: &str) -> Result<BitVec, ParseBitVecError> {
+        let (len, digits) = split_len(text)?;
+        if digits.len() % 2 != 0 {
+            return Err(ParseBitVecError);
+        }
+        let bytes = digits
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let digit = |d: u8| char::from(d).to_digit(16).ok_or(ParseBitVecError);
+                Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8)
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        from_bytes(bytes, len)
+    }
+
+    /// Formats the bit-vector as its length in bits, followed by a colon and
+    /// its bytes in padded, standard Base64.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, false, true, false, true, true, true, true, true, false];
+    /// assert_eq!(bitvec.to_base64(), "10:r4A=");
+    /// assert_eq!(BitVec::from_base64("10:r4A=").unwrap(), bitvec);
+    /// ```
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> String {
+        let mut text = format!("{}:", self.len());
+        for chunk in self.as_bytes()[..self.len().div_ceil(8)].chunks(3) {
+            let mut group = [0; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let group = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            for i in 0..4 {
+                match i <= chunk.len() {
+                    true => {
+                        text.push(BASE64_DIGITS[(group >> (18 - 6 * i) & 0x3F) as usize] as char)
+                    }
+                    false => text.push('='),
+                }
+            }
+        }
+        text
+    }
+
+    /// Parses a bit-vector formatted with [`BitVec::to_base64`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the length or the Base64 digits are invalid, if the
+    /// number of bytes does not match the length, or if the padding bits are
+    /// not 0.
+    #[cfg(feature = "base64")]
+    pub fn from_base64(text: &str) -> Result<BitVec, ParseBitVecError> {
+        let (len, digits) = split_len(text)?;
+        if digits.len() % 4 != 0 {
+            return Err(ParseBitVecError);
+        }
+        let mut bytes = vec![];
+        for (i, chunk) in digits.as_bytes().chunks(4).enumerate() {
+            let n_padding = chunk
+                .iter()
+                .rev()
+                .take_while(|&&digit| digit == b'=')
+                .count();
+            let is_last = 4 * (i + 1) == digits.len();
+            if n_padding > 2 || (n_padding > 0 && !is_last) {
+                return Err(ParseBitVecError);
+            }
+            let mut group = 0_u32;
+            for &digit in &chunk[..4 - n_padding] {
+                let value = BASE64_DIGITS
+                    .iter()
+                    .position(|&d| d == digit)
+                    .ok_or(ParseBitVecError)?;
+                group = (group << 6) | value as u32;
+            }
+            group <<= 6 * n_padding;
+            let group = group.to_be_bytes();
+            if group[4 - n_padding..].iter().any(|&byte| byte != 0) {
+                return Err(ParseBitVecError);
+            }
+            bytes.extend_from_slice(&group[1..4 - n_padding]);
+        }
+        from_bytes(bytes, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+
+    // Creates bit-vectors of all the lengths up to 40 bits.
+    fn bitvecs() -> Vec<BitVec> {
+        (0..40)
+            .map(|len| {
+                let mut bitvec = BitVec::default();
+                bitvec.extend_from_slice(&(0..len).map(|i| i % 3 != 1).collect::<Vec<_>>());
+                bitvec
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hex() {
+        for bitvec in bitvecs() {
+            assert_eq!(BitVec::from_hex(&bitvec.to_hex()), Ok(bitvec));
+        }
+        assert_eq!(bitvec![].to_hex(), "0:");
+        assert_eq!(
+            BitVec::from_hex("4:A0"),
+            Ok(bitvec![true, false, true, false])
+        );
+    }
+
+    #[test]
+    fn test_hex_errs() {
+        for text in [
+            "", "a0", "x:a0", "4:a", "4:ag", "4:a0ff", "12:a0", "4:a8", "4:é0", "4:+a",
+        ] {
+            assert!(BitVec::from_hex(text).is_err(), "{text}");
+        }
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_base64() {
+        for bitvec in bitvecs() {
+            assert_eq!(BitVec::from_base64(&bitvec.to_base64()), Ok(bitvec));
+        }
+        assert_eq!(BitVec::new(b"Man".to_vec()).to_base64(), "24:TWFu");
+        for text in [
+            "8:TQ",
+            "8:TQ==TQ==",
+            "8:T===",
+            "8:TR==",
+            "8:T!==",
+            "16:TQ==",
+        ] {
+            assert!(BitVec::from_base64(text).is_err(), "{text}");
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use std::cmp::Ordering;
+use std::ops::{RangeBounds, Shl, ShlAssign, Shr, ShrAssign};
+
+use crate::collections::BitSlice;
 use crate::error::BitVecLengthError;
 use crate::io::DEFAULT_BUF_SIZE;
 
@@ -84,7 +88,7 @@ impl BitVec {
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
         BitVec {
-            inner: Vec::with_capacity((capacity + 7) / 8),
+            inner: Vec::with_capacity(capacity.div_ceil(8)),
             bit_pos: 0,
             len: 0,
         }
@@ -123,7 +127,7 @@ impl BitVec {
             .inner
             .last_mut()
             .expect("It is guaranteed that at least one byte exists.");
-        *byte |= (bit as u8) << 7 - self.bit_pos;
+        *byte |= (bit as u8) << (7 - self.bit_pos);
         self.bit_pos = (self.bit_pos + 1) % 8;
         self.len += 1;
     }
@@ -191,6 +195,176 @@ impl BitVec {
         self.inner.insert(index, byte)
     }
 
+    /// Inserts a bit at position `index`, shifting all the bits after it
+    /// towards the end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![true, true, true, true, true, true, true, true];
+    /// bitvec.insert(3, false);
+    /// assert_eq!(*bitvec.as_bytes(), [0b11101111, 0b10000000]);
+    /// assert_eq!(bitvec.len(), 9);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Takes *O*([`BitVec::n_bytes`]) time, since the bytes after the position
+    /// are shifted by one bit each.
+    pub fn insert(&mut self, index: usize, bit: bool) {
+        assert!(index <= self.len, "The index is out of bounds.");
+        self.push(false);
+        let (first, offset) = (index / 8, index % 8);
+        let mut carry = 0;
+        for (i, byte) in self.inner[first..].iter_mut().enumerate() {
+            let old = *byte;
+            *byte = match i {
+                0 => {
+                    let kept = !(u8::MAX >> offset);
+                    (old & kept) | ((bit as u8) << (7 - offset)) | ((old & !kept) >> 1)
+                }
+                _ => (carry << 7) | (old >> 1),
+            };
+            carry = old & 1;
+        }
+    }
+
+    /// Removes and returns the bit at position `index`, shifting all the bits
+    /// after it towards the start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![true, true, false, true, true, true, true, true, true];
+    /// assert!(!bitvec.remove(2));
+    /// assert_eq!(*bitvec.as_bytes(), [0b11111111]);
+    /// assert_eq!(bitvec.len(), 8);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Takes *O*([`BitVec::n_bytes`]) time, since the bytes after the position
+    /// are shifted by one bit each.
+    pub fn remove(&mut self, index: usize) -> bool {
+        assert!(index < self.len, "The index is out of bounds.");
+        let (first, offset) = (index / 8, index % 8);
+        let bit = self.inner[first] & (1 << (7 - offset)) != 0;
+        let n_bytes = self.len.div_ceil(8);
+        for i in first..n_bytes {
+            let next = self.inner.get(i + 1).map_or(0, |byte| byte >> 7);
+            let byte = self.inner[i];
+            self.inner[i] = match i == first {
+                true => {
+                    let kept = !(u8::MAX >> offset);
+                    (byte & kept) | ((byte << 1) & !kept) | next
+                }
+                false => (byte << 1) | next,
+            };
+        }
+        self.truncate(self.len - 1);
+        bit
+    }
+
+    /// Inserts the bits of a view at position `index`, shifting all the bits
+    /// after it towards the end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![true, true, true, true];
+    /// let bits = bitvec![false, false, true, false];
+    /// bitvec.insert_bits(1, &bits.slice(..3));
+    /// assert_eq!(bitvec, bitvec![true, false, false, true, true, true, true]);
+    /// ```
+    pub fn insert_bits(&mut self, index: usize, bits: &BitSlice) {
+        assert!(index <= self.len, "The index is out of bounds.");
+        let tail = self.subvec(index..);
+        self.truncate(index);
+        for i in 0..bits.len() {
+            self.push(bits.get(i).expect("The position is within the view."));
+        }
+        let tail = tail.slice(..);
+        for i in 0..tail.len() {
+            self.push(tail.get(i).expect("The position is within the view."));
+        }
+    }
+
+    /// Overwrites the bits starting at position `dst_offset` with the bits of
+    /// another bit-vector within a range, leaving the length unchanged.
+    ///
+    /// The source bits are copied a byte at a time, shifted to the bit offset
+    /// of the destination, so a field reserved in a header can be filled in
+    /// place once its value is known.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the bounds of the source, or if the
+    /// copied bits do not fit in the bit-vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![false; 12];
+    /// let len = bitvec![false, true, false, true, true];
+    /// bitvec.copy_bits_from(&len, 1.., 6);
+    /// assert_eq!(*bitvec.as_bytes(), [0b00000010, 0b11000000]);
+    /// ```
+    pub fn copy_bits_from<R: RangeBounds<usize>>(
+        &mut self,
+        src: &BitVec,
+        src_range: R,
+        dst_offset: usize,
+    ) {
+        let bits = src.subvec(src_range);
+        let len = bits.len();
+        assert!(
+            dst_offset + len <= self.len,
+            "The bits do not fit in the bit-vector."
+        );
+        let (first, shift) = (dst_offset / 8, (dst_offset % 8) as u32);
+        for (i, &byte) in bits.as_bytes().iter().enumerate() {
+            let mask = u8::MAX << (8 - (len - 8 * i).min(8));
+            let hi_mask = mask >> shift;
+            self.inner[first + i] = (self.inner[first + i] & !hi_mask) | ((byte & mask) >> shift);
+            if shift == 0 {
+                continue;
+            }
+            let lo_mask = mask << (8 - shift);
+            if lo_mask != 0 {
+                let lo = (byte & mask) << (8 - shift);
+                self.inner[first + i + 1] = (self.inner[first + i + 1] & !lo_mask) | lo;
+            }
+        }
+    }
+
+    // Shortens the bit-vector to its first `len` bits.
+    fn truncate(&mut self, len: usize) {
+        self.inner.truncate(len.div_ceil(8));
+        self.len = len;
+        self.bit_pos = (len % 8) as u8;
+        self.clear_padding();
+    }
+
     /// Extends bit-queue from a slice of bits.
     ///
     /// Traverses the slice of bits in-order and sequentially pushes the bits
@@ -336,10 +510,13 @@ impl BitVec {
     /// assert_eq!(bitvec.len(), 3);
     /// bitvec.clear();
     /// assert_eq!(bitvec.len(), 0);
+    /// bitvec.push(true);
+    /// assert_eq!(*bitvec.as_bytes(), [0b10000000]);
     /// ```
     #[inline]
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.bit_pos = 0;
         self.len = 0;
     }
 
@@ -413,6 +590,373 @@ impl BitVec {
         }
         bits
     }
+
+    /// Shifts the bits towards the start of the bit-vector by `n` positions,
+    /// discarding the first `n` bits and filling the end with 0-bits. The
+    /// length of the bit-vector is maintained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![true, false, true, true, false, false, true, true, true, false];
+    /// bitvec.shift_left(3);
+    /// assert_eq!(bitvec, bitvec![true, false, false, true, true, true, false, false, false, false]);
+    /// ```
+    pub fn shift_left(&mut self, n: usize) {
+        let n_bytes = self.len.div_ceil(8);
+        let (byte_shift, bit_shift) = (n / 8, (n % 8) as u32);
+        let bytes = &mut self.inner[..n_bytes];
+        for i in 0..n_bytes {
+            let hi = bytes.get(i + byte_shift).copied().unwrap_or(0);
+            let lo = bytes.get(i + byte_shift + 1).copied().unwrap_or(0);
+            bytes[i] = match bit_shift {
+                0 => hi,
+                _ => (hi << bit_shift) | (lo >> (8 - bit_shift)),
+            };
+        }
+        self.clear_padding();
+    }
+
+    /// Shifts the bits towards the end of the bit-vector by `n` positions,
+    /// discarding the last `n` bits and filling the start with 0-bits. The
+    /// length of the bit-vector is maintained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let mut bitvec = bitvec![true, false, true, true, false, false, true, true, true, false];
+    /// bitvec.shift_right(3);
+    /// assert_eq!(bitvec, bitvec![false, false, false, true, false, true, true, false, false, true]);
+    /// ```
+    pub fn shift_right(&mut self, n: usize) {
+        let n_bytes = self.len.div_ceil(8);
+        let (byte_shift, bit_shift) = (n / 8, (n % 8) as u32);
+        let bytes = &mut self.inner[..n_bytes];
+        for i in (0..n_bytes).rev() {
+            let byte_at = |j: Option<usize>| j.and_then(|j| bytes.get(j)).copied().unwrap_or(0);
+            let lo = byte_at(i.checked_sub(byte_shift));
+            let hi = byte_at(i.checked_sub(byte_shift + 1));
+            bytes[i] = match bit_shift {
+                0 => lo,
+                _ => (lo >> bit_shift) | (hi << (8 - bit_shift)),
+            };
+        }
+        self.clear_padding();
+    }
+
+    /// Returns the position of the first 1-bit, or `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, false, true, false, true];
+    /// assert_eq!(bitvec.first_one(), Some(2));
+    /// assert_eq!(bitvec![false; 100].first_one(), None);
+    /// ```
+    #[inline]
+    pub fn first_one(&self) -> Option<usize> {
+        self.next_bit(0, true)
+    }
+
+    /// Returns the position of the first 1-bit at or after position `from`, or
+    /// `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, false, true, false, true];
+    /// assert_eq!(bitvec.next_one(2), Some(2));
+    /// assert_eq!(bitvec.next_one(3), Some(4));
+    /// assert_eq!(bitvec.next_one(5), None);
+    /// ```
+    #[inline]
+    pub fn next_one(&self, from: usize) -> Option<usize> {
+        self.next_bit(from, true)
+    }
+
+    /// Returns an iterator over the positions of the 1-bits, in increasing
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, true, true, false, true];
+    /// assert_eq!(bitvec.iter_ones().collect::<Vec<_>>(), vec![1, 2, 4]);
+    /// ```
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter_bits(true)
+    }
+
+    /// Returns the position of the first 0-bit, or `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, true, false, true];
+    /// assert_eq!(bitvec.first_zero(), Some(2));
+    /// assert_eq!(bitvec![true; 100].first_zero(), None);
+    /// ```
+    #[inline]
+    pub fn first_zero(&self) -> Option<usize> {
+        self.next_bit(0, false)
+    }
+
+    /// Returns the position of the first 0-bit at or after position `from`, or
+    /// `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, true, false, true, false];
+    /// assert_eq!(bitvec.next_zero(3), Some(4));
+    /// assert_eq!(bitvec.next_zero(5), None);
+    /// ```
+    #[inline]
+    pub fn next_zero(&self, from: usize) -> Option<usize> {
+        self.next_bit(from, false)
+    }
+
+    /// Returns an iterator over the positions of the 0-bits, in increasing
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, true, true, false, true];
+    /// assert_eq!(bitvec.iter_zeros().collect::<Vec<_>>(), vec![0, 3]);
+    /// ```
+    pub fn iter_zeros(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter_bits(false)
+    }
+
+    /// Returns the number of leading bits that are equal in both bit-vectors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let a = bitvec![true, false, true, true];
+    /// let b = bitvec![true, false, true, false, true];
+    /// assert_eq!(a.common_prefix_len(&b), 3);
+    /// assert_eq!(a.common_prefix_len(&a), 4);
+    /// ```
+    pub fn common_prefix_len(&self, other: &BitVec) -> usize {
+        let len = self.len.min(other.len);
+        (0..len.div_ceil(64))
+            .find_map(|idx| {
+                let diff = self.word(idx) ^ other.word(idx);
+                (diff != 0).then(|| idx * 64 + diff.leading_zeros() as usize)
+            })
+            .map_or(len, |pos| pos.min(len))
+    }
+
+    /// Compares the bits of two bit-vectors lexicographically, where a 0-bit
+    /// is less than a 1-bit, and a bit-vector is less than the bit-vectors it
+    /// is a prefix of.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let a = bitvec![true, false, true];
+    /// assert_eq!(a.cmp_bits(&bitvec![true, true]), Ordering::Less);
+    /// assert_eq!(a.cmp_bits(&bitvec![true, false]), Ordering::Greater);
+    /// assert_eq!(a.cmp_bits(&bitvec![true, false, true]), Ordering::Equal);
+    /// ```
+    pub fn cmp_bits(&self, other: &BitVec) -> Ordering {
+        let pos = self.common_prefix_len(other);
+        if pos == self.len.min(other.len) {
+            return self.len.cmp(&other.len);
+        }
+        let bit = |bitvec: &BitVec| bitvec.word(pos / 64) << (pos % 64) >> 63;
+        bit(self).cmp(&bit(other))
+    }
+
+    /// Returns the position of the first occurrence of a bit pattern, or
+    /// `None` if the pattern does not occur. An empty pattern occurs at
+    /// position 0.
+    ///
+    /// The bits at each candidate position are shifted into 64-bit words and
+    /// compared to the pattern a word at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, true, true, false, true, true, false];
+    /// let marker = bitvec![true, true, false];
+    /// assert_eq!(bitvec.find(&marker.slice(..)), Some(1));
+    /// assert_eq!(bitvec.find(&bitvec![false, false].slice(..)), None);
+    /// ```
+    pub fn find(&self, pattern: &BitSlice) -> Option<usize> {
+        self.find_from(&pattern.to_bitvec(), 0)
+    }
+
+    /// Returns an iterator over the positions of all the occurrences of a bit
+    /// pattern, in increasing order, including the ones that overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![false, true, true, false, true, true, true];
+    /// let marker = bitvec![true, true];
+    /// let found: Vec<usize> = bitvec.find_all(&marker.slice(..)).collect();
+    /// assert_eq!(found, vec![1, 4, 5]);
+    /// ```
+    pub fn find_all(&self, pattern: &BitSlice) -> impl Iterator<Item = usize> + '_ {
+        let pattern = pattern.to_bitvec();
+        let mut from = 0;
+        std::iter::from_fn(move || {
+            let pos = self.find_from(&pattern, from)?;
+            from = pos + 1;
+            Some(pos)
+        })
+    }
+
+    /// Returns a borrowed view of the bits within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end, or if its end
+    /// is greater than the length of the bit-vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, false, true, true, false];
+    /// let slice = bitvec.slice(1..4);
+    /// assert_eq!(slice.get(1), Some(true));
+    /// assert_eq!(slice.len(), 3);
+    /// ```
+    #[inline]
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> BitSlice<'_> {
+        BitSlice::new(self, range)
+    }
+
+    /// Copies the bits within a range to a new bit-vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end, or if its end
+    /// is greater than the length of the bit-vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitVec, bitvec};
+    ///
+    /// let bitvec = bitvec![true, false, true, true, false];
+    /// assert_eq!(bitvec.subvec(1..4), bitvec![false, true, true]);
+    /// ```
+    #[inline]
+    pub fn subvec<R: RangeBounds<usize>>(&self, range: R) -> BitVec {
+        self.slice(range).to_bitvec()
+    }
+
+    // Returns the position of the first bit equal to `bit` at or after position
+    // `from`, scanning the bits a 64-bit word at a time.
+    fn next_bit(&self, from: usize, bit: bool) -> Option<usize> {
+        let n_bytes = self.len.div_ceil(8);
+        let mut word_idx = from / 64;
+        let mut mask = u64::MAX >> (from % 64);
+        while from < self.len && word_idx * 8 < n_bytes {
+            let mut word = self.word(word_idx);
+            if !bit {
+                word = !word;
+            }
+            word &= mask;
+            if word != 0 {
+                let pos = word_idx * 64 + word.leading_zeros() as usize;
+                return (pos < self.len).then_some(pos);
+            }
+            mask = u64::MAX;
+            word_idx += 1;
+        }
+        None
+    }
+
+    // Returns the position of the first occurrence of a pattern at or after
+    // position `from`.
+    fn find_from(&self, pattern: &BitVec, from: usize) -> Option<usize> {
+        let n_words = pattern.len.div_ceil(64);
+        let last_mask = match pattern.len % 64 {
+            0 => u64::MAX,
+            n => u64::MAX << (64 - n),
+        };
+        (from..(self.len + 1).checked_sub(pattern.len)?).find(|&pos| {
+            (0..n_words).all(|idx| {
+                let mask = if idx + 1 == n_words {
+                    last_mask
+                } else {
+                    u64::MAX
+                };
+                (self.word_at(pos + idx * 64) ^ pattern.word(idx)) & mask == 0
+            })
+        })
+    }
+
+    // Returns the 64 bits starting at an arbitrary position, padded with
+    // 0-bits past the end of the bit-vector.
+    fn word_at(&self, pos: usize) -> u64 {
+        let (idx, shift) = (pos / 64, pos % 64);
+        match shift {
+            0 => self.word(idx),
+            _ => (self.word(idx) << shift) | (self.word(idx + 1) >> (64 - shift)),
+        }
+    }
+
+    // Returns the 64-bit word of the bits starting at position `64 * idx`,
+    // padded with 0-bits past the end of the bit-vector.
+    fn word(&self, idx: usize) -> u64 {
+        let n_bytes = self.len.div_ceil(8);
+        let start = n_bytes.min(idx * 8);
+        let chunk = &self.inner[start..n_bytes.min(start + 8)];
+        let mut bytes = [0; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        u64::from_be_bytes(bytes)
+    }
+
+    // Returns an iterator over the positions of the bits equal to `bit`.
+    fn iter_bits(&self, bit: bool) -> impl Iterator<Item = usize> + '_ {
+        let mut next = self.next_bit(0, bit);
+        std::iter::from_fn(move || {
+            let pos = next?;
+            next = self.next_bit(pos + 1, bit);
+            Some(pos)
+        })
+    }
+
+    // Sets the bits of the last byte that are past the length to 0.
+    fn clear_padding(&mut self) {
+        let bit_pos = self.len % 8;
+        if bit_pos != 0 {
+            self.inner[self.len / 8] &= u8::MAX << (8 - bit_pos);
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -446,6 +990,36 @@ impl Default for BitVec {
     }
 }
 
+impl Shl<usize> for BitVec {
+    type Output = BitVec;
+
+    fn shl(mut self, n: usize) -> BitVec {
+        self.shift_left(n);
+        self
+    }
+}
+
+impl ShlAssign<usize> for BitVec {
+    fn shl_assign(&mut self, n: usize) {
+        self.shift_left(n);
+    }
+}
+
+impl Shr<usize> for BitVec {
+    type Output = BitVec;
+
+    fn shr(mut self, n: usize) -> BitVec {
+        self.shift_right(n);
+        self
+    }
+}
+
+impl ShrAssign<usize> for BitVec {
+    fn shr_assign(&mut self, n: usize) {
+        self.shift_right(n);
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -467,4 +1041,164 @@ mod tests {
         let bitvec = bitvec![];
         assert_eq!(bitvec.len(), 0);
     }
+
+    #[test]
+    fn test_bit_positions() {
+        let bits: Vec<bool> = (0..300).map(|i| i % 67 == 5 || i == 299).collect();
+        let mut bitvec = BitVec::default();
+        bitvec.extend_from_slice(&bits);
+        let ones: Vec<usize> = (0..300).filter(|i| bits[*i]).collect();
+        let zeros: Vec<usize> = (0..300).filter(|i| !bits[*i]).collect();
+        assert_eq!(bitvec.iter_ones().collect::<Vec<_>>(), ones);
+        assert_eq!(bitvec.iter_zeros().collect::<Vec<_>>(), zeros);
+        assert_eq!(bitvec.next_one(6), Some(72));
+        assert_eq!(bitvec.next_one(300), None);
+        assert_eq!(bitvec.next_zero(299), None);
+
+        // The padding of the last byte is not a 0-bit of the bit-vector.
+        let bitvec = bitvec![true; 70];
+        assert_eq!(bitvec.first_zero(), None);
+        assert_eq!(bitvec.iter_ones().count(), 70);
+        assert_eq!(bitvec![].first_one(), None);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let bits: Vec<bool> = (0..30).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        for index in [0, 1, 7, 8, 15, 29, 30] {
+            for bit in [false, true] {
+                let mut bitvec = BitVec::default();
+                bitvec.extend_from_slice(&bits);
+                bitvec.insert(index, bit);
+                let mut expected = bits.clone();
+                expected.insert(index, bit);
+                assert_eq!(bitvec.clone().into_bits(), expected);
+
+                assert_eq!(bitvec.remove(index), bit);
+                assert_eq!(bitvec.into_bits(), bits);
+            }
+        }
+
+        // Removing the last bit of a byte drops the byte.
+        let mut bitvec = bitvec![true; 9];
+        bitvec.remove(0);
+        assert_eq!(bitvec, bitvec![true; 8]);
+        assert_eq!(bitvec.n_bytes(), 1);
+    }
+
+    #[test]
+    fn test_insert_bits() {
+        let other = bitvec![true, false, true, true, false, false, true, true, true, false];
+        let mut bitvec = bitvec![false; 12];
+        bitvec.insert_bits(5, &other.slice(1..9));
+        let mut expected = vec![false; 5];
+        expected.extend([false, true, true, false, false, true, true, true]);
+        expected.extend([false; 7]);
+        assert_eq!(bitvec.into_bits(), expected);
+    }
+
+    #[test]
+    fn test_copy_bits_from() {
+        let src: Vec<bool> = (0..40).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let mut src_bitvec = BitVec::default();
+        src_bitvec.extend_from_slice(&src);
+        for (start, end, offset) in [
+            (0, 40, 3),
+            (5, 6, 0),
+            (3, 22, 13),
+            (8, 16, 8),
+            (1, 30, 31),
+            (9, 9, 60),
+        ] {
+            let dst: Vec<bool> = (0..60).map(|i| i % 2 == 0).collect();
+            let mut bitvec = BitVec::default();
+            bitvec.extend_from_slice(&dst);
+            bitvec.copy_bits_from(&src_bitvec, start..end, offset);
+
+            let mut expected = dst.clone();
+            expected[offset..offset + end - start].copy_from_slice(&src[start..end]);
+            assert_eq!(bitvec.len(), 60);
+            assert_eq!(bitvec.into_bits(), expected);
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let bits: Vec<bool> = (0..150).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        let bitvec = |bits: &[bool]| {
+            let mut bitvec = BitVec::default();
+            bitvec.extend_from_slice(bits);
+            bitvec
+        };
+        let a = bitvec(&bits);
+        for pos in [0, 5, 63, 64, 100, 149] {
+            let mut flipped = bits.clone();
+            flipped[pos] = !flipped[pos];
+            let b = bitvec(&flipped);
+            assert_eq!(a.common_prefix_len(&b), pos);
+            assert_eq!(a.cmp_bits(&b), bits.cmp(&flipped));
+            assert_eq!(b.cmp_bits(&a), flipped.cmp(&bits));
+        }
+
+        // Prefixes, including ones whose padding matches the longer bits.
+        let prefix = bitvec(&bits[..70]);
+        assert_eq!(a.common_prefix_len(&prefix), 70);
+        assert_eq!(prefix.cmp_bits(&a), Ordering::Less);
+        assert_eq!(
+            bitvec![false].cmp_bits(&bitvec![false, false]),
+            Ordering::Less
+        );
+        assert_eq!(bitvec![].cmp_bits(&bitvec![]), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_find() {
+        let bits: Vec<bool> = (0..300_u32).map(|i| i.count_ones() % 3 == 0).collect();
+        let mut haystack = BitVec::default();
+        haystack.extend_from_slice(&bits);
+        for (start, len) in [(0, 1), (3, 5), (37, 64), (100, 70), (200, 100), (299, 1)] {
+            let pattern = haystack.subvec(start..start + len);
+            let expected: Vec<usize> = (0..=bits.len() - len)
+                .filter(|&i| bits[i..i + len] == bits[start..start + len])
+                .collect();
+            assert_eq!(haystack.find(&pattern.slice(..)), expected.first().copied());
+            assert_eq!(
+                haystack.find_all(&pattern.slice(..)).collect::<Vec<_>>(),
+                expected
+            );
+        }
+
+        let marker = bitvec![true; 12];
+        assert_eq!(haystack.find(&marker.slice(..)), None);
+        assert_eq!(haystack.find(&bitvec![true; 301].slice(..)), None);
+        assert_eq!(haystack.find(&marker.slice(..0)), Some(0));
+        assert_eq!(bitvec![].find_all(&marker.slice(..0)).count(), 1);
+    }
+
+    #[test]
+    fn test_shifts() {
+        let bits: Vec<bool> = (0..29).map(|i| i % 3 == 0 || i % 5 == 0).collect();
+        for n in [0, 1, 7, 8, 9, 17, 28, 29, 100] {
+            let mut bitvec = BitVec::default();
+            bitvec.extend_from_slice(&bits);
+
+            let mut left = bits.iter().skip(n).copied().collect::<Vec<_>>();
+            left.resize(bits.len(), false);
+            let shifted = bitvec.clone() << n;
+            assert_eq!(shifted.len(), bits.len());
+            assert_eq!(shifted.into_bits(), left);
+
+            let mut right = vec![false; n.min(bits.len())];
+            right.extend(&bits[..bits.len() - right.len()]);
+            bitvec >>= n;
+            assert_eq!(bitvec.into_bits(), right);
+        }
+
+        // The bits past the length stay 0.
+        let mut bitvec = bitvec![true; 12];
+        bitvec <<= 4;
+        assert_eq!(*bitvec.as_bytes(), [0xFF, 0x00]);
+        bitvec >>= 12;
+        assert_eq!(*bitvec.as_bytes(), [0x00, 0x00]);
+    }
 }
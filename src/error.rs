@@ -27,13 +27,92 @@ define_error!(
     NoTerminatingBitError,
     "Did not find a terminating 1-bit in the last byte."
 );
+define_error!(
+    TrailingBytesError,
+    "The stream ends with bytes that are not part of a complete segment."
+);
+define_error!(
+    CancelledError,
+    "The operation was cancelled by its progress callback."
+);
+define_error!(
+    InvalidPermutationError,
+    "The order is not a permutation of the IDs."
+);
+define_error!(
+    SelectorTableError,
+    "The cases of the selector table do not fit in its words."
+);
+define_error!(
+    UnsortedPostingsError,
+    "The document IDs are not strictly increasing."
+);
+define_error!(
+    InvalidImpactsError,
+    "The document IDs repeat, or the scores are negative or not finite."
+);
+define_error!(
+    ParseBitVecError,
+    "The text is not a valid representation of a bit-vector."
+);
 
 #[derive(Debug, PartialEq)]
 pub enum InvalidCodeError {
     UnaryCodeError,
     VBCodeError,
+    VB16CodeError,
+    PrefixVarintCodeError,
+    SqliteVarintCodeError,
+    StreamVByteCodeError,
+    ChunkCodeError,
     GammaCodeError,
+    GammaPrimeCodeError,
     DeltaCodeError,
+    OmegaCodeError,
+    ZetaCodeError,
+    RiceCodeError,
+    GolombCodeError,
+    BascCodeError,
+    StartStepStopCodeError,
+    SimpleCodeError,
+    BP128CodeError,
+    PForCodeError,
+    PositionsCodeError,
+    InterpolativeCodeError,
+    ChimpCodeError,
+    GorillaCodeError,
+    DecimalCodeError,
+    TimestampCodeError,
+    SprintzCodeError,
+    RleCodeError,
+    DoubleDeltaCodeError,
+    ZigZagCodeError,
+    GapCodeError,
+    Rle0CodeError,
+    HybridCodeError,
+    HuffmanCodeError,
+    ArithCodeError,
+    RangeCodeError,
+    RansCodeError,
+    MinimalBinaryCodeError,
+    NibbleCodeError,
+    VarGroupCodeError,
+    NullableCodeError,
+    IntVecCodeError,
+    IdSetCodeError,
+    EwahCodeError,
+    TaggedCodeError,
+    SplitCodeError,
+    BloomFilterCodeError,
+    SignedPackCodeError,
+    BitPackCodeError,
+    AutoCodeError,
+    GolombSetCodeError,
+    PostingListCodeError,
+    SegmentCodeError,
+    ZoneMapCodeError,
+    StreamError,
+    CancelledError,
 }
 
 impl fmt::Display for InvalidCodeError {
@@ -45,14 +124,197 @@ impl fmt::Display for InvalidCodeError {
             InvalidCodeError::VBCodeError => {
                 write!(f, "Invalid Variable Byte Code Error.")
             }
+            InvalidCodeError::VB16CodeError => {
+                write!(f, "Invalid 16-bit Variable Word Code Error.")
+            }
+            InvalidCodeError::PrefixVarintCodeError => {
+                write!(f, "Invalid Prefix Varint Code Error.")
+            }
+            InvalidCodeError::SqliteVarintCodeError => {
+                write!(f, "Invalid SQLite Varint Code Error.")
+            }
+            InvalidCodeError::StreamVByteCodeError => {
+                write!(f, "Invalid Stream VByte Code Error.")
+            }
+            InvalidCodeError::ChunkCodeError => {
+                write!(f, "Invalid Multi-Width Chunk Code Error.")
+            }
             InvalidCodeError::GammaCodeError => {
                 write!(f, "Invalid Elias Gamma Code Error.")
             }
+            InvalidCodeError::GammaPrimeCodeError => {
+                write!(f, "Invalid Elias Gamma' Code Error.")
+            }
             InvalidCodeError::DeltaCodeError => {
                 write!(f, "Invalid Elias Delta Code Error.")
             }
+            InvalidCodeError::OmegaCodeError => {
+                write!(f, "Invalid Elias Omega Code Error.")
+            }
+            InvalidCodeError::ZetaCodeError => {
+                write!(f, "Invalid Zeta Code Error.")
+            }
+            InvalidCodeError::RiceCodeError => {
+                write!(f, "Invalid Rice Code Error.")
+            }
+            InvalidCodeError::GolombCodeError => {
+                write!(f, "Invalid Golomb Code Error.")
+            }
+            InvalidCodeError::BascCodeError => {
+                write!(f, "Invalid Binary Adaptive Sequential Code Error.")
+            }
+            InvalidCodeError::StartStepStopCodeError => {
+                write!(f, "Invalid Start-Step-Stop Code Error.")
+            }
+            InvalidCodeError::SimpleCodeError => {
+                write!(f, "Invalid Selector-Based Code Error.")
+            }
+            InvalidCodeError::BP128CodeError => {
+                write!(f, "Invalid Binary Packing Code Error.")
+            }
+            InvalidCodeError::PositionsCodeError => {
+                write!(f, "Invalid Position List Code Error.")
+            }
+            InvalidCodeError::ChimpCodeError => {
+                write!(f, "Invalid Chimp Code Error.")
+            }
+            InvalidCodeError::GorillaCodeError => {
+                write!(f, "Invalid Gorilla Code Error.")
+            }
+            InvalidCodeError::DecimalCodeError => {
+                write!(f, "Invalid Decimal Scaling Code Error.")
+            }
+            InvalidCodeError::TimestampCodeError => {
+                write!(f, "Invalid Timestamp Code Error.")
+            }
+            InvalidCodeError::SprintzCodeError => {
+                write!(f, "Invalid Sprintz Code Error.")
+            }
+            InvalidCodeError::RleCodeError => {
+                write!(f, "Invalid Run-Length Code Error.")
+            }
+            InvalidCodeError::ZigZagCodeError => {
+                write!(f, "Invalid ZigZag Code Error.")
+            }
+            InvalidCodeError::HuffmanCodeError => {
+                write!(f, "Invalid Huffman Code Error.")
+            }
+            InvalidCodeError::ArithCodeError => {
+                write!(f, "Invalid Arithmetic Code Error.")
+            }
+            InvalidCodeError::RangeCodeError => {
+                write!(f, "Invalid Range Code Error.")
+            }
+            InvalidCodeError::RansCodeError => {
+                write!(f, "Invalid rANS Code Error.")
+            }
+            InvalidCodeError::MinimalBinaryCodeError => {
+                write!(f, "Invalid Minimal Binary Code Error.")
+            }
+            InvalidCodeError::NibbleCodeError => {
+                write!(f, "Invalid Nibble Code Error.")
+            }
+            InvalidCodeError::VarGroupCodeError => {
+                write!(f, "Invalid Variable Group Code Error.")
+            }
+            InvalidCodeError::GapCodeError => {
+                write!(f, "Invalid Gap Code Error.")
+            }
+            InvalidCodeError::Rle0CodeError => {
+                write!(f, "Invalid RLE0 Code Error.")
+            }
+            InvalidCodeError::HybridCodeError => {
+                write!(f, "Invalid Hybrid Code Error.")
+            }
+            InvalidCodeError::DoubleDeltaCodeError => {
+                write!(f, "Invalid Double Delta Code Error.")
+            }
+            InvalidCodeError::NullableCodeError => {
+                write!(f, "Invalid Nullable Sequence Code Error.")
+            }
+            InvalidCodeError::IntVecCodeError => {
+                write!(f, "Invalid Packed Integer Vector Code Error.")
+            }
+            InvalidCodeError::IdSetCodeError => {
+                write!(f, "Invalid ID Set Code Error.")
+            }
+            InvalidCodeError::EwahCodeError => {
+                write!(f, "Invalid EWAH Bitmap Code Error.")
+            }
+            InvalidCodeError::TaggedCodeError => {
+                write!(f, "Invalid Tagged Code Error.")
+            }
+            InvalidCodeError::SplitCodeError => {
+                write!(f, "Invalid Split Stream Code Error.")
+            }
+            InvalidCodeError::BloomFilterCodeError => {
+                write!(f, "Invalid Bloom Filter Code Error.")
+            }
+            InvalidCodeError::PForCodeError => {
+                write!(f, "Invalid Patched Frame of Reference Code Error.")
+            }
+            InvalidCodeError::InterpolativeCodeError => {
+                write!(f, "Invalid Binary Interpolative Code Error.")
+            }
+            InvalidCodeError::SignedPackCodeError => {
+                write!(f, "Invalid Signed Packing Code Error.")
+            }
+            InvalidCodeError::BitPackCodeError => {
+                write!(f, "Invalid Bit Packing Code Error.")
+            }
+            InvalidCodeError::AutoCodeError => {
+                write!(f, "Invalid Adaptive Block Code Error.")
+            }
+            InvalidCodeError::GolombSetCodeError => {
+                write!(f, "Invalid Golomb-Coded Set Code Error.")
+            }
+            InvalidCodeError::PostingListCodeError => {
+                write!(f, "Invalid Posting List Code Error.")
+            }
+            InvalidCodeError::SegmentCodeError => {
+                write!(f, "Invalid Segment Code Error.")
+            }
+            InvalidCodeError::ZoneMapCodeError => {
+                write!(f, "Invalid Zone Map Code Error.")
+            }
+            InvalidCodeError::StreamError => {
+                write!(f, "The stream cannot be read, or has no terminating bit.")
+            }
+            InvalidCodeError::CancelledError => {
+                write!(f, "The decoding was cancelled by its progress callback.")
+            }
         }
     }
 }
 
 impl Error for InvalidCodeError {}
+
+/// The errors of encoding into, or decoding from, a buffer provided by the
+/// caller.
+#[derive(Debug, PartialEq)]
+pub enum BufferError {
+    /// The buffer cannot hold all the output.
+    BufferTooSmall,
+    /// A number cannot be encoded with the code.
+    InvalidInput,
+    /// The bytes are not a valid code.
+    InvalidCode(InvalidCodeError),
+}
+
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BufferError::BufferTooSmall => write!(f, "The buffer is too small."),
+            BufferError::InvalidInput => write!(f, "The number cannot be encoded."),
+            BufferError::InvalidCode(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for BufferError {}
+
+impl From<InvalidCodeError> for BufferError {
+    fn from(e: InvalidCodeError) -> Self {
+        BufferError::InvalidCode(e)
+    }
+}
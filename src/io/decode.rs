@@ -0,0 +1,246 @@
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+use crate::code::Decoder;
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// An adapter that wraps a stream of decoded numbers and implements
+/// [`io::Read`], emitting each number as little-endian bytes of the width of
+/// its type.
+///
+/// Byte-oriented consumers, like hashers, writers and other crates, can thus
+/// read decoded data through [`io::copy`] and friends. When the adapter wraps
+/// a lazy decoding iterator, such as the ones of
+/// [`DecodeIterExt`](crate::DecodeIterExt), the numbers are decoded as the
+/// bytes are read, without collecting them in an intermediate vector.
+///
+/// A decoding error is returned as an error of kind
+/// [`io::ErrorKind::InvalidData`], after which the adapter is exhausted.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use idencode::{DecodeIterExt, DecodeReader};
+///
+/// let bytes = vec![0b10000101, 0b10000010, 0b10000001];
+/// let mut reader = DecodeReader::new(bytes.into_iter().vb_decoded::<u16>());
+/// let mut out = vec![];
+/// reader.read_to_end(&mut out).unwrap();
+/// assert_eq!(out, vec![5, 0, 2, 0, 1, 0]);
+/// ```
+pub struct DecodeReader<I, T> {
+    iter: I,
+    limit: Option<usize>,
+    bytes: [u8; 8],
+    pos: usize,
+    len: usize,
+    error: Option<InvalidCodeError>,
+    failed: bool,
+    _num: PhantomData<T>,
+}
+
+impl<I, T> DecodeReader<I, T>
+where
+    I: Iterator<Item = Result<T, InvalidCodeError>>,
+    T: Numeric,
+{
+    /// Creates a new adapter that emits all the numbers of the stream.
+    pub fn new(iter: I) -> Self {
+        DecodeReader {
+            iter,
+            limit: None,
+            bytes: [0; 8],
+            pos: 0,
+            len: 0,
+            error: None,
+            failed: false,
+            _num: PhantomData,
+        }
+    }
+
+    /// Creates a new adapter that emits at most `n` numbers of the stream,
+    /// and leaves the rest of them undecoded.
+    ///
+    /// Unlike [`Read::take`], which bounds the number of bytes, the bound is
+    /// in whole numbers, so a read never stops in the middle of a number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    /// use idencode::{DecodeIterExt, DecodeReader};
+    ///
+    /// let bytes = vec![0b10000101, 0b10000010, 0b10000001];
+    /// let mut reader = DecodeReader::bounded(bytes.into_iter().vb_decoded::<u32>(), 2);
+    /// let mut out = vec![];
+    /// reader.read_to_end(&mut out).unwrap();
+    /// assert_eq!(out, vec![5, 0, 0, 0, 2, 0, 0, 0]);
+    /// assert_eq!(reader.into_inner().next(), Some(Ok(1)));
+    /// ```
+    pub fn bounded(iter: I, n: usize) -> Self {
+        DecodeReader {
+            limit: Some(n),
+            ..Self::new(iter)
+        }
+    }
+
+    /// Returns the number of bytes of each number.
+    #[inline]
+    pub fn width(&self) -> usize {
+        (T::BITS / 8) as usize
+    }
+
+    /// Returns the wrapped stream of numbers.
+    ///
+    /// The bytes of a number that has been partially read are lost.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+
+    // Decodes the next number into the byte buffer, returning `false` if the
+    // stream, or the bound, is exhausted, or if the number is invalid.
+    fn fill(&mut self) -> bool {
+        if self.failed || self.limit == Some(0) {
+            return false;
+        }
+        let num = match self.iter.next() {
+            None => return false,
+            Some(Ok(num)) => num,
+            Some(Err(e)) => {
+                self.error = Some(e);
+                self.failed = true;
+                return false;
+            }
+        };
+        if let Some(limit) = self.limit.as_mut() {
+            *limit -= 1;
+        }
+        self.bytes = num.to_u64().to_le_bytes();
+        self.pos = 0;
+        self.len = self.width();
+        true
+    }
+}
+
+impl<T: Numeric> DecodeReader<std::vec::IntoIter<Result<T, InvalidCodeError>>, T> {
+    /// Creates a new adapter that emits the numbers decoded by a decoder.
+    ///
+    /// Since [`Decoder::decode`] decodes the whole stream at once, the
+    /// numbers are decoded when the adapter is created, and a decoding error
+    /// is returned by the first read, before any bytes are emitted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use idencode::{DecodeReader, GammaDecoder};
+    ///
+    /// let dec = GammaDecoder::new(Cursor::new(vec![0b10110011, 0b10001100]));
+    /// let mut reader = DecodeReader::<_, u8>::from_decoder(dec);
+    /// let mut out = vec![];
+    /// reader.read_to_end(&mut out).unwrap();
+    /// assert_eq!(out, vec![3, 2, 9]);
+    /// ```
+    pub fn from_decoder<R: Read, D: Decoder<R>>(decoder: D) -> Self {
+        let nums = match decoder.decode::<T>() {
+            Ok(nums) => nums.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        Self::new(nums.into_iter())
+    }
+}
+
+impl<I, T> Read for DecodeReader<I, T>
+where
+    I: Iterator<Item = Result<T, InvalidCodeError>>,
+    T: Numeric,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if self.pos == self.len && !self.fill() {
+                break;
+            }
+            let n = (self.len - self.pos).min(buf.len() - written);
+            buf[written..written + n].copy_from_slice(&self.bytes[self.pos..self.pos + n]);
+            self.pos += n;
+            written += n;
+        }
+        // The bytes before a decoding error are emitted first, and the error
+        // is returned by the next read.
+        if written == 0 {
+            if let Some(e) = self.error.take() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodeIterExt, EncodeIterExt, GammaDecoder};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_widths() {
+        let nums = [1_u64, 300, u64::MAX];
+        let bytes: Vec<u8> = nums.iter().copied().vb_encoded().collect();
+        let mut out = vec![];
+        let mut reader = DecodeReader::new(bytes.into_iter().vb_decoded::<u64>());
+        assert_eq!(reader.width(), 8);
+        reader.read_to_end(&mut out).unwrap();
+        let expected: Vec<u8> = nums.iter().flat_map(|n| n.to_le_bytes()).collect();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_small_reads() {
+        let nums: Vec<u32> = (0..100).map(|i| i * 1001).collect();
+        let bytes: Vec<u8> = nums.iter().copied().vb_encoded().collect();
+        let mut reader = DecodeReader::new(bytes.into_iter().vb_decoded::<u32>());
+        let mut out = vec![];
+        let mut buf = [0; 3];
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break,
+                n => out.extend_from_slice(&buf[..n]),
+            }
+        }
+        let expected: Vec<u8> = nums.iter().flat_map(|n| n.to_le_bytes()).collect();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let bytes: Vec<u8> = (1..=10_u8).vb_encoded().collect();
+        let mut reader = DecodeReader::bounded(bytes.into_iter().vb_decoded::<u8>(), 4);
+        let mut out = vec![];
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, vec![1, 2, 3, 4]);
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+
+        let mut reader = DecodeReader::bounded(std::iter::empty::<Result<u8, _>>(), 4);
+        assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_errs() {
+        // A valid number followed by a truncated one.
+        let bytes = vec![0b10000101, 0b00000101];
+        let mut reader = DecodeReader::new(bytes.into_iter().vb_decoded::<u16>());
+        let mut buf = [0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf[..2], [5, 0]);
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+        let dec = GammaDecoder::new(Cursor::new(vec![0b11100000]));
+        let mut reader = DecodeReader::<_, u8>::from_decoder(dec);
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+use crate::code::Encoder;
+use crate::num::Numeric;
+
+/// An adapter that implements [`io::Write`], parsing the written bytes as
+/// little-endian integers of the width of `T` and feeding them to a wrapped
+/// encoder.
+///
+/// A pipeline that writes fixed-width integers, for example `u32`s to a file,
+/// switches to compressed output by writing to the adapter instead. The
+/// bytes of an integer may be split across writes, but the encoding has to
+/// be completed with [`EncodeWriter::finish`], which finalizes the encoder.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Cursor, Write};
+/// use idencode::{Decoder, EncodeWriter, VBDecoder, VBEncoder};
+///
+/// let enc = VBEncoder::new(Cursor::new(vec![]));
+/// let mut writer = EncodeWriter::<_, _, u32>::new(enc);
+/// for num in [5_u32, 300, 7] {
+///     writer.write_all(&num.to_le_bytes()).unwrap();
+/// }
+/// let result = writer.finish().unwrap().into_inner();
+/// assert_eq!(result.len(), 4);
+///
+/// let dec = VBDecoder::new(Cursor::new(result));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![5, 300, 7]);
+/// ```
+pub struct EncodeWriter<E, W, T> {
+    encoder: E,
+    partial: Vec<u8>,
+    _marker: PhantomData<(W, T)>,
+}
+
+impl<E, W, T> EncodeWriter<E, W, T>
+where
+    E: Encoder<W>,
+    W: Write,
+    T: Numeric,
+{
+    /// Creates a new adapter that feeds the integers to the encoder.
+    pub fn new(encoder: E) -> Self {
+        EncodeWriter {
+            encoder,
+            partial: vec![],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of bytes of each integer.
+    #[inline]
+    pub fn width(&self) -> usize {
+        (T::BITS / 8) as usize
+    }
+
+    /// Finalizes the wrapped encoder, returning its writer.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if the bytes
+    /// of the last integer have not been completely written.
+    pub fn finish(self) -> io::Result<W> {
+        if !self.partial.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The last integer is incomplete.",
+            ));
+        }
+        self.encoder.finalize()
+    }
+
+    // Parses an integer from its little-endian bytes.
+    fn parse(bytes: &[u8]) -> T {
+        let mut word = [0; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        T::from_u64(u64::from_le_bytes(word)).expect("The integer fits in its width.")
+    }
+}
+
+impl<E, W, T> Write for EncodeWriter<E, W, T>
+where
+    E: Encoder<W>,
+    W: Write,
+    T: Numeric,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let width = self.width();
+        let mut nums = Vec::with_capacity((self.partial.len() + buf.len()) / width);
+        let mut rest = buf;
+        if !self.partial.is_empty() {
+            let n = (width - self.partial.len()).min(rest.len());
+            self.partial.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+            if self.partial.len() < width {
+                return Ok(buf.len());
+            }
+            nums.push(Self::parse(&self.partial));
+        }
+
+        let chunks = rest.chunks_exact(width);
+        let tail = chunks.remainder();
+        nums.extend(chunks.map(Self::parse));
+        self.encoder.encode(&nums)?;
+        self.partial = tail.to_vec();
+        Ok(buf.len())
+    }
+
+    /// Does nothing, since the encoders write their output when they are
+    /// finalized, or as they encode.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Decoder, GammaDecoder, GammaEncoder};
+    use std::io::Cursor;
+
+    fn encode_gamma<T: Numeric>(nums: &[T]) -> Vec<u8> {
+        let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+        enc.encode(nums).unwrap();
+        enc.finalize().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_matches_encoder() {
+        let nums: Vec<u64> = (1..200).map(|i| i * i * 1009).collect();
+        let bytes: Vec<u8> = nums.iter().flat_map(|n| n.to_le_bytes()).collect();
+        let mut writer = EncodeWriter::<_, _, u64>::new(GammaEncoder::new(Cursor::new(vec![])));
+        assert_eq!(writer.width(), 8);
+        writer.write_all(&bytes).unwrap();
+        let result = writer.finish().unwrap().into_inner();
+        assert_eq!(result, encode_gamma(&nums));
+    }
+
+    #[test]
+    fn test_split_writes() {
+        let nums: Vec<u16> = (1..100).map(|i| i * 601).collect();
+        let bytes: Vec<u8> = nums.iter().flat_map(|n| n.to_le_bytes()).collect();
+        let mut writer = EncodeWriter::<_, _, u16>::new(GammaEncoder::new(Cursor::new(vec![])));
+        for chunk in bytes.chunks(3) {
+            assert_eq!(writer.write(chunk).unwrap(), chunk.len());
+        }
+        writer.write_all(&[]).unwrap();
+        writer.flush().unwrap();
+        let result = writer.finish().unwrap().into_inner();
+        let dec = GammaDecoder::new(Cursor::new(result));
+        assert_eq!(dec.decode::<u16>().unwrap(), nums);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut writer = EncodeWriter::<_, _, u32>::new(GammaEncoder::new(Cursor::new(vec![])));
+        writer.write_all(&[1, 0, 0]).unwrap();
+        let err = writer.finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // The encoder rejects the integer.
+        let enc = crate::RleEncoder::new(Cursor::new(vec![]));
+        let mut writer = EncodeWriter::<_, _, u64>::new(enc);
+        let err = writer.write_all(&u64::MAX.to_le_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
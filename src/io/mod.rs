@@ -1,4 +1,37 @@
+pub mod decode;
+pub mod encode;
 pub mod read;
+pub mod shared;
+pub mod tee;
+#[cfg(feature = "integer-encoding")]
+pub mod varint;
 pub mod write;
 
-pub const DEFAULT_BUF_SIZE: usize = 1 * 1024;
+pub const DEFAULT_BUF_SIZE: usize = 1024;
+
+/// The value of the bits that fill the last byte of a stream, or of a segment,
+/// after its last bit.
+///
+/// When the stream ends with a terminating bit, the terminating bit is the
+/// opposite of the padding bits, so that it can be told apart from them: a
+/// 1-bit followed by zeros, or a 0-bit followed by ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Padding {
+    /// The last byte is filled with 0-bits.
+    #[default]
+    Zeros,
+    /// The last byte is filled with 1-bits.
+    Ones,
+}
+
+impl Padding {
+    /// Returns the value of the padding bits.
+    #[inline]
+    pub fn bit(self) -> bool {
+        self == Padding::Ones
+    }
+}
+
+/// The reserved byte pattern that starts each block of a stream written with
+/// [`BitWriter::with_sync_markers`](crate::BitWriter::with_sync_markers).
+pub const SYNC_MARKER: [u8; 4] = [0xFF, 0xA5, 0x00, 0x5A];
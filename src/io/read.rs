@@ -1,21 +1,110 @@
-use std::io::Read;
+use std::io::{self, Read};
 
 use anyhow::anyhow;
 
-use crate::error::NoTerminatingBitError;
+use crate::code::global::vb::decode_vb_prefix;
+use crate::code::DecodePrefix;
+use crate::error::{InvalidCodeError, NoTerminatingBitError, TrailingBytesError};
+use crate::io::{Padding, SYNC_MARKER};
+use crate::num::Numeric;
 use crate::BitVec;
 
 pub struct BitReader<R> {
     term_bit: bool,
+    padding: Padding,
+    strict: bool,
     inner: R,
 }
 
+impl<I, B> BitReader<ChunkReader<I>>
+where
+    I: Iterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    /// Creates a new `BitReader` over a stream that is split in chunks of
+    /// bytes, like the segments of a file or the frames of a network protocol,
+    /// reading them in order as if they were contiguous.
+    pub fn from_chunks<C: IntoIterator<IntoIter = I>>(chunks: C, term_bit: bool) -> Self {
+        BitReader::new(ChunkReader::new(chunks), term_bit)
+    }
+}
+
 impl<R: Read> BitReader<R> {
     /// Creates a new `BitReader` from a reader.
     pub fn new(reader: R, term_bit: bool) -> Self {
         BitReader {
             inner: reader,
             term_bit,
+            padding: Padding::Zeros,
+            strict: false,
+        }
+    }
+
+    /// Creates a new `BitReader` from a reader, for a stream written with
+    /// [`BitWriter::with_padding`](crate::BitWriter::with_padding) and the
+    /// specified padding.
+    pub fn with_padding(reader: R, term_bit: bool, padding: Padding) -> Self {
+        BitReader {
+            padding,
+            ..BitReader::new(reader, term_bit)
+        }
+    }
+
+    /// Creates a new `BitReader` from a reader, that verifies that the stream
+    /// ends the way the padding declares, instead of skipping what it cannot
+    /// read.
+    ///
+    /// The last byte of the stream, and of each segment, must hold the
+    /// terminating bit, if any, followed only by padding bits, in either
+    /// mode. In strict mode, the reader also returns an error for the bytes
+    /// that it would otherwise ignore: a trailing segment that is cut short,
+    /// which flags a truncated stream, and bytes that are not part of an intact
+    /// segment of a stream with sync markers, which flag garbage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::io::Padding;
+    /// use idencode::BitReader;
+    /// use std::io::Cursor;
+    ///
+    /// // A segment, and the length of one that is cut short.
+    /// let bytes = vec![0x81, 0b10100000, 0x82, 0xFF];
+    /// let reader = BitReader::new(Cursor::new(bytes.clone()), true);
+    /// assert_eq!(reader.read_segments().unwrap().len(), 2);
+    /// let reader = BitReader::strict(Cursor::new(bytes), true, Padding::Zeros);
+    /// assert!(reader.read_segments().is_err());
+    /// ```
+    pub fn strict(reader: R, term_bit: bool, padding: Padding) -> Self {
+        BitReader {
+            strict: true,
+            ..BitReader::with_padding(reader, term_bit, padding)
+        }
+    }
+
+    /// Chains another reader after this one, so that a stream split across
+    /// several buffers or files is read as if it were contiguous, without
+    /// concatenating its parts first.
+    ///
+    /// The stream may be split at any byte, and the terminating bit, if any, is
+    /// looked for at the end of the last part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::BitReader;
+    ///
+    /// let (head, tail) = ([0b10101011_u8], [0b11001000_u8]);
+    /// let reader = BitReader::new(&head[..], true).chain(&tail[..]);
+    /// let bitvec = reader.read_to_end().unwrap();
+    /// assert_eq!(bitvec.len(), 12);
+    /// ```
+    pub fn chain<S: Read>(self, other: S) -> BitReader<io::Chain<R, S>> {
+        BitReader {
+            inner: self.inner.chain(other),
+            term_bit: self.term_bit,
+            padding: self.padding,
+            strict: self.strict,
         }
     }
 
@@ -47,22 +136,426 @@ impl<R: Read> BitReader<R> {
         }
 
         if self.term_bit {
-            with_terminating_bit(buffer)
+            with_terminating_bit(buffer, self.padding)
         } else {
             Ok(BitVec::new(buffer))
         }
     }
+
+    /// Reads the bits of all the segments written with
+    /// [`BitWriter::flush_segment`](crate::BitWriter::flush_segment), removing
+    /// the padding and the terminating bit of each one.
+    ///
+    /// A trailing segment that is cut short is ignored, so that a stream can
+    /// be read while it is still being written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::BitReader;
+    /// use std::io::Cursor;
+    ///
+    /// let reader = Cursor::new(vec![0x81, 0b10100000, 0x81, 0b11000000, 0x82, 0xFF]);
+    /// let bitvec = BitReader::new(reader, true).read_segments().unwrap();
+    /// assert_eq!(bitvec.into_bits(), vec![true, false, true]);
+    /// ```
+    pub fn read_segments(mut self) -> anyhow::Result<BitVec> {
+        let mut buffer = vec![];
+        self.inner.read_to_end(&mut buffer)?;
+
+        let mut bits = vec![];
+        let mut pos = 0;
+        while let Ok((len, len_bytes)) = decode_vb_prefix(&buffer[pos..]) {
+            let start = pos + len_bytes;
+            let Some(segment) = usize::try_from(len)
+                .ok()
+                .and_then(|len| buffer.get(start..start.checked_add(len)?))
+            else {
+                break;
+            };
+            if !segment.is_empty() {
+                let bitvec = match self.term_bit {
+                    true => with_terminating_bit(segment.to_vec(), self.padding)?,
+                    false => BitVec::new(segment.to_vec()),
+                };
+                bits.extend(bitvec.into_bits());
+            }
+            pos = start + segment.len();
+        }
+        if self.strict && pos != buffer.len() {
+            return Err(anyhow!(TrailingBytesError));
+        }
+
+        let mut bitvec = BitVec::default();
+        bitvec.extend_from_slice(&bits);
+        Ok(bitvec)
+    }
+
+    /// Reads the segments of a stream written with
+    /// [`BitWriter::with_sync_markers`](crate::BitWriter::with_sync_markers),
+    /// returning the bits of each intact segment along with its sequence
+    /// number.
+    ///
+    /// A segment is intact if its length fits in the stream, and it is followed
+    /// by the next sync marker or by the end of the stream. When a segment is
+    /// corrupted, the reader scans for the next sync marker and resumes from
+    /// there, so the segments that are lost can be told from the gaps in the
+    /// sequence numbers. A trailing segment that is cut short is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::{BitReader, BitWriter};
+    /// use std::io::Cursor;
+    ///
+    /// let mut bw = BitWriter::with_sync_markers(Cursor::new(vec![]), true);
+    /// bw.write_bits(&[true, false]).unwrap();
+    /// bw.flush_segment().unwrap();
+    /// bw.write_bits(&[false, true]).unwrap();
+    /// let mut bytes = bw.finalize().unwrap().into_inner();
+    ///
+    /// // Corrupt the length of the first segment.
+    /// bytes[8] = 0x85;
+    /// let segments = BitReader::new(Cursor::new(bytes), true)
+    ///     .read_synced_segments()
+    ///     .unwrap();
+    /// assert_eq!(segments.len(), 1);
+    /// assert_eq!(segments[0].0, 1);
+    /// assert_eq!(segments[0].1.clone().into_bits(), vec![false, true]);
+    /// ```
+    pub fn read_synced_segments(mut self) -> anyhow::Result<Vec<(u32, BitVec)>> {
+        let mut buffer = vec![];
+        self.inner.read_to_end(&mut buffer)?;
+
+        let mut segments = vec![];
+        let mut pos = 0;
+        while let Some(marker) = find_sync_marker(&buffer, pos) {
+            if self.strict && marker != pos {
+                return Err(anyhow!(TrailingBytesError));
+            }
+            match self.synced_segment(&buffer, marker) {
+                Some((seq, bitvec, end)) => {
+                    segments.push((seq, bitvec));
+                    pos = end;
+                }
+                None if self.strict => return Err(anyhow!(TrailingBytesError)),
+                None => pos = marker + 1,
+            }
+        }
+        if self.strict && pos != buffer.len() {
+            return Err(anyhow!(TrailingBytesError));
+        }
+        Ok(segments)
+    }
+
+    // Reads the segment after the sync marker at a position, returning its
+    // sequence number, its bits and the position where it ends, or `None` if
+    // it is not intact.
+    fn synced_segment(&self, buffer: &[u8], marker: usize) -> Option<(u32, BitVec, usize)> {
+        let header = marker + SYNC_MARKER.len();
+        let seq = u32::from_be_bytes(buffer.get(header..header + 4)?.try_into().ok()?);
+        let (len, len_bytes) = decode_vb_prefix(&buffer[header + 4..]).ok()?;
+        let start = header + 4 + len_bytes;
+        let end = start.checked_add(usize::try_from(len).ok()?)?;
+        let segment = buffer.get(start..end)?;
+        if segment.is_empty() || !(end == buffer.len() || buffer[end..].starts_with(&SYNC_MARKER)) {
+            return None;
+        }
+        let bitvec = match self.term_bit {
+            true => with_terminating_bit(segment.to_vec(), self.padding).ok()?,
+            false => BitVec::new(segment.to_vec()),
+        };
+        Some((seq, bitvec, end))
+    }
+}
+
+/// Returns the position of the first sync marker at or after a position.
+fn find_sync_marker(buffer: &[u8], from: usize) -> Option<usize> {
+    buffer
+        .get(from..)?
+        .windows(SYNC_MARKER.len())
+        .position(|window| window == SYNC_MARKER)
+        .map(|i| from + i)
+}
+
+/// A cursor over the bits of a buffer of bytes, in most-significant-bit first
+/// order, that reads them in place, without allocating.
+pub(crate) struct ByteBits<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteBits<'a> {
+    /// Creates a cursor at a bit offset of the bytes.
+    pub(crate) fn new(bytes: &'a [u8], pos: usize) -> Self {
+        ByteBits { bytes, pos }
+    }
+
+    /// Returns the position of the cursor, in bits.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Reads the next bit, or returns `None` at the end of the bytes.
+    pub(crate) fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1 == 1;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    /// Reads the next `n` bits, up to 64, as a number, or returns `None` if
+    /// there are fewer bits left.
+    pub(crate) fn read_bits(&mut self, n: usize) -> Option<u64> {
+        debug_assert!(n <= 64, "At most 64 bits fit in a number.");
+        if self.pos.checked_add(n)? > 8 * self.bytes.len() {
+            return None;
+        }
+        let mut num = 0;
+        for _ in 0..n {
+            num = (num << 1) | u64::from(self.read_bit()?);
+        }
+        Some(num)
+    }
+
+    /// Reads the bits up to the next occurrence of the stop bit, returning
+    /// how many preceded it, or `None` if the bytes end before it.
+    pub(crate) fn read_run(&mut self, stop_bit: bool) -> Option<usize> {
+        let mut n = 0;
+        while self.read_bit()? != stop_bit {
+            n += 1;
+        }
+        Some(n)
+    }
+}
+
+/// A reader over a sequence of chunks of bytes, that reads them in order as if
+/// they were contiguous.
+///
+/// The chunks may be of any type that is [`AsRef<[u8]>`](AsRef), like a
+/// `Vec<u8>` or a `&[u8]`, and are pulled from the iterator as they are read,
+/// so the reader can be passed to any decoder to decode a stream that is split
+/// in chunks, without concatenating them first.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::{ChunkReader, Decoder, Encoder, GammaDecoder, GammaEncoder};
+/// use std::io::Cursor;
+///
+/// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+/// enc.encode(&[3_u32, 1, 4, 1, 5, 9, 2, 6]).unwrap();
+/// let bytes = enc.finalize().unwrap().into_inner();
+///
+/// let chunks: Vec<Vec<u8>> = bytes.chunks(2).map(<[u8]>::to_vec).collect();
+/// let dec = GammaDecoder::new(ChunkReader::new(chunks));
+/// assert_eq!(dec.decode::<u32>().unwrap(), vec![3, 1, 4, 1, 5, 9, 2, 6]);
+/// ```
+pub struct ChunkReader<I: Iterator> {
+    chunks: I,
+    current: Option<I::Item>,
+    pos: usize,
+}
+
+impl<I, B> ChunkReader<I>
+where
+    I: Iterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    /// Creates a new `ChunkReader` over a sequence of chunks.
+    pub fn new<C: IntoIterator<IntoIter = I>>(chunks: C) -> Self {
+        ChunkReader {
+            chunks: chunks.into_iter(),
+            current: None,
+            pos: 0,
+        }
+    }
+}
+
+impl<I, B> Read for ChunkReader<I>
+where
+    I: Iterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(chunk) = &self.current {
+                let rest = &chunk.as_ref()[self.pos..];
+                if !rest.is_empty() {
+                    let n = rest.len().min(buf.len());
+                    buf[..n].copy_from_slice(&rest[..n]);
+                    self.pos += n;
+                    return Ok(n);
+                }
+            }
+            // The current chunk is exhausted, or empty, so move to the next.
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.current = Some(chunk);
+                    self.pos = 0;
+                }
+                None => {
+                    self.current = None;
+                    return Ok(0);
+                }
+            }
+        }
+    }
+}
+
+/// The layout of the bits in a stream.
+#[derive(Clone, Copy)]
+enum Layout {
+    Plain,
+    Segmented,
+    Synced,
+}
+
+/// A cursor over the bits of a reader.
+///
+/// The bits are read lazily, the first time they are accessed, and the
+/// cursor keeps track of the position up to which they have been consumed.
+pub(crate) struct BitCursor<R> {
+    reader: Option<BitReader<R>>,
+    layout: Layout,
+    bits: Vec<bool>,
+    pos: usize,
+}
+
+impl<R: Read> BitCursor<R> {
+    pub(crate) fn new(reader: BitReader<R>) -> Self {
+        BitCursor {
+            reader: Some(reader),
+            layout: Layout::Plain,
+            bits: vec![],
+            pos: 0,
+        }
+    }
+
+    /// Creates a cursor over the bits of the segments of a reader, read with
+    /// [`BitReader::read_segments`].
+    pub(crate) fn segmented(reader: BitReader<R>) -> Self {
+        BitCursor {
+            layout: Layout::Segmented,
+            ..Self::new(reader)
+        }
+    }
+
+    /// Creates a cursor over the bits of the intact segments of a reader, read
+    /// with [`BitReader::read_synced_segments`].
+    pub(crate) fn synced(reader: BitReader<R>) -> Self {
+        BitCursor {
+            layout: Layout::Synced,
+            ..Self::new(reader)
+        }
+    }
+
+    /// Returns the bits that have not been consumed yet.
+    ///
+    /// Returns an error if the reader fails, or if the stream does not end
+    /// with a terminating bit.
+    pub(crate) fn remaining(&mut self) -> Result<&[bool], InvalidCodeError> {
+        if let Some(reader) = self.reader.take() {
+            self.bits = match self.layout {
+                Layout::Plain => reader.read_to_end().map(BitVec::into_bits),
+                Layout::Segmented => reader.read_segments().map(BitVec::into_bits),
+                Layout::Synced => reader.read_synced_segments().map(|segments| {
+                    segments
+                        .into_iter()
+                        .flat_map(|(_, bitvec)| bitvec.into_bits())
+                        .collect()
+                }),
+            }
+            .map_err(|_| InvalidCodeError::StreamError)?;
+        }
+        Ok(&self.bits[self.pos..])
+    }
+
+    /// Returns the number of bits that have been consumed.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the cursor to a position, so that the bits after it are the next
+    /// to be consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is past the end of the bits.
+    pub(crate) fn set_position(&mut self, pos: usize) -> Result<(), InvalidCodeError> {
+        let len = self.pos + self.remaining()?.len();
+        assert!(pos <= len, "The position is past the end of the stream.");
+        self.pos = pos;
+        Ok(())
+    }
+
+    /// Decodes the next number with the specified code, consuming its bits.
+    pub(crate) fn decode_next<C: DecodePrefix, T: Numeric>(
+        &mut self,
+    ) -> Result<T, InvalidCodeError> {
+        self.decode_with(C::decode_prefix)
+    }
+
+    /// Decodes the next value with a function that returns the value and the
+    /// number of bits it occupies, consuming its bits.
+    pub(crate) fn decode_with<T>(
+        &mut self,
+        decode: impl FnOnce(&[bool]) -> Result<(T, usize), InvalidCodeError>,
+    ) -> Result<T, InvalidCodeError> {
+        let (value, len) = decode(self.remaining()?)?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Decodes up to `N` values with a function like [`BitCursor::decode_with`]
+    /// into an array, stopping early at the end of the bits, and returns the
+    /// array along with the number of values decoded.
+    pub(crate) fn decode_batch_with<T: Copy + Default, const N: usize>(
+        &mut self,
+        decode: impl Fn(&[bool]) -> Result<(T, usize), InvalidCodeError>,
+    ) -> Result<([T; N], usize), InvalidCodeError> {
+        let mut batch = [T::default(); N];
+        let mut count = 0;
+        while count < N && !self.remaining()?.is_empty() {
+            batch[count] = self.decode_with(&decode)?;
+            count += 1;
+        }
+        Ok((batch, count))
+    }
+
+    /// Decodes the next value with a function like [`BitCursor::decode_with`],
+    /// without consuming its bits.
+    pub(crate) fn peek_with<T>(
+        &mut self,
+        decode: impl FnOnce(&[bool]) -> Result<(T, usize), InvalidCodeError>,
+    ) -> Result<T, InvalidCodeError> {
+        decode(self.remaining()?).map(|(value, _)| value)
+    }
+
+    /// Consumes the bits of the next `n` codewords, whose lengths are computed
+    /// by a function, without decoding them. No bits are consumed on error.
+    pub(crate) fn skip_with(
+        &mut self,
+        n: usize,
+        code_len: impl Fn(&[bool]) -> Result<usize, InvalidCodeError>,
+    ) -> Result<(), InvalidCodeError> {
+        let bits = self.remaining()?;
+        let mut len = 0;
+        for _ in 0..n {
+            len += code_len(&bits[len..])?;
+        }
+        self.pos += len;
+        Ok(())
+    }
 }
 
 // Returns the position of the trailing 1-bit.
 // The position indexing starts from the right.
 fn trailing_one_pos(byte: u8) -> Option<u8> {
-    for i in 0..8 {
-        if byte & (1 << i) != 0 {
-            return Some(i);
-        }
-    }
-    None // No 1-bit found
+    (0..8).find(|&i| byte & (1 << i) != 0)
 }
 
 // Converts a buffer into a `BitVec`, removing the terminating bit.
@@ -71,12 +564,28 @@ fn trailing_one_pos(byte: u8) -> Option<u8> {
 // If the terminating bit is at position 7, the last byte is removed;
 // otherwise, the bit is cleared (set to 0). The resulting `BitVec`
 // is then truncated to the correct length and returned.
-fn with_terminating_bit(mut buffer: Vec<u8>) -> anyhow::Result<BitVec> {
+/// Returns the number of bits before the terminating bit of a buffer of bytes,
+/// or `None` if its last byte has no terminating bit.
+pub(crate) fn terminated_len(bytes: &[u8]) -> Option<usize> {
+    match bytes.last() {
+        None => Some(0),
+        Some(&byte) => {
+            let pos = trailing_one_pos(byte)?;
+            Some(8 * bytes.len() - 1 - pos as usize)
+        }
+    }
+}
+
+fn with_terminating_bit(mut buffer: Vec<u8>, padding: Padding) -> anyhow::Result<BitVec> {
     let &byte = buffer
         .last()
         .expect("The buffer is guaranteed to not be empty.");
-    let term_bit_pos = trailing_one_pos(byte);
-    return match term_bit_pos {
+    // The terminating bit is the last bit that differs from the padding.
+    let term_bit_pos = match padding {
+        Padding::Zeros => trailing_one_pos(byte),
+        Padding::Ones => trailing_one_pos(!byte),
+    };
+    match term_bit_pos {
         None => Err(anyhow!(NoTerminatingBitError)),
         Some(pos) => {
             if pos == 7 {
@@ -87,12 +596,12 @@ fn with_terminating_bit(mut buffer: Vec<u8>) -> anyhow::Result<BitVec> {
                 let byte = buffer
                     .last_mut()
                     .expect("The buffer is guaranteed to not be empty.");
-                *byte &= !(1 << pos);
+                *byte &= !(u8::MAX >> (7 - pos));
                 let len = (buffer.len() - 1) * 8 + (7 - pos) as usize;
                 Ok(BitVec::with_len(buffer, len)?)
             }
         }
-    };
+    }
 }
 
 #[cfg(test)]
@@ -128,12 +637,165 @@ mod tests {
     #[test]
     fn test_with_terminating_bit() {
         let bv = bitvec![true, false, false];
-        assert_eq!(with_terminating_bit(vec![0b10010000]).unwrap(), bv);
+        assert_eq!(
+            with_terminating_bit(vec![0b10010000], Padding::Zeros).unwrap(),
+            bv
+        );
 
         let bv = bitvec![true, false, true, true, false, false, false, true];
         assert_eq!(
-            with_terminating_bit(vec![0b10110001, 0b10000000]).unwrap(),
+            with_terminating_bit(vec![0b10110001, 0b10000000], Padding::Zeros).unwrap(),
+            bv
+        );
+    }
+
+    #[test]
+    fn test_read_segments() {
+        // Segments without terminating bits keep their padding.
+        let reader = Cursor::new(vec![0x81, 0xAB, 0x82, 0x01, 0x02]);
+        let bitvec = BitReader::new(reader, false).read_segments().unwrap();
+        assert_eq!(*bitvec.as_bytes(), [0xAB, 0x01, 0x02]);
+
+        // The trailing segment and the length of the next one are cut short.
+        let reader = Cursor::new(vec![0x81, 0b10010000, 0x83, 0xFF]);
+        let bitvec = BitReader::new(reader, true).read_segments().unwrap();
+        assert_eq!(bitvec, bitvec![true, false, false]);
+        let reader = Cursor::new(vec![0x81, 0b10010000, 0x01]);
+        let bitvec = BitReader::new(reader, true).read_segments().unwrap();
+        assert_eq!(bitvec, bitvec![true, false, false]);
+
+        // A segment without its terminating bit.
+        let reader = Cursor::new(vec![0x81, 0x00]);
+        assert!(BitReader::new(reader, true).read_segments().is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        for (term_bit, padding) in [
+            (true, Padding::Ones),
+            (false, Padding::Ones),
+            (true, Padding::Zeros),
+        ] {
+            let mut bw = crate::BitWriter::with_padding(Cursor::new(vec![]), term_bit, padding);
+            bw.write_bits(&[true, false, true]).unwrap();
+            bw.flush_segment().unwrap();
+            bw.write_bits(&[false; 9]).unwrap();
+            let bytes = bw.finalize().unwrap().into_inner();
+            let last = *bytes.last().unwrap();
+            assert_eq!(last & 1 == 1, padding.bit());
+
+            let reader = BitReader::strict(Cursor::new(bytes), term_bit, padding);
+            let bits = reader.read_segments().unwrap().into_bits();
+            let expected: Vec<bool> = match term_bit {
+                true => [[true, false, true].as_slice(), &[false; 9]].concat(),
+                false => [
+                    [true, false, true].as_slice(),
+                    &[padding.bit(); 5],
+                    &[false; 9],
+                    &[padding.bit(); 7],
+                ]
+                .concat(),
+            };
+            assert_eq!(bits, expected);
+        }
+
+        // A terminating 0-bit, followed by 1-bits.
+        let bv = bitvec![true, true, false, false];
+        assert_eq!(
+            with_terminating_bit(vec![0b11000111], Padding::Ones).unwrap(),
             bv
         );
+        assert!(with_terminating_bit(vec![0xFF], Padding::Ones).is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        let bytes = vec![0x81, 0b10100000, 0x82, 0b11000000];
+        let reader = BitReader::strict(Cursor::new(bytes[..2].to_vec()), true, Padding::Zeros);
+        assert_eq!(reader.read_segments().unwrap(), bitvec![true, false]);
+        let reader = BitReader::strict(Cursor::new(bytes), true, Padding::Zeros);
+        assert!(reader.read_segments().is_err());
+
+        let mut bw = crate::BitWriter::with_sync_markers(Cursor::new(vec![]), true);
+        for _ in 0..2 {
+            bw.write_bits(&[true; 5]).unwrap();
+            bw.flush_segment().unwrap();
+        }
+        let bytes = bw.finalize().unwrap().into_inner();
+        let read = |bytes: Vec<u8>| {
+            BitReader::strict(Cursor::new(bytes), true, Padding::Zeros).read_synced_segments()
+        };
+        assert_eq!(read(bytes.clone()).unwrap().len(), 2);
+        // Garbage before, between and after the segments.
+        assert!(read([&[0x00], bytes.as_slice()].concat()).is_err());
+        assert!(read([&bytes[..10], &[0x00], &bytes[10..]].concat()).is_err());
+        assert!(read([bytes.as_slice(), &[0xFF]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_chain() {
+        let reader = BitReader::new(Cursor::new(vec![0b10101011]), true)
+            .chain(Cursor::new(vec![]))
+            .chain(&[0b11001000_u8][..]);
+        let bitvec = reader.read_to_end().unwrap();
+        assert_eq!(*bitvec.as_bytes(), [0b10101011, 0b11000000]);
+        assert_eq!(bitvec.len(), 12);
+    }
+
+    #[test]
+    fn test_from_chunks() {
+        let bytes: Vec<u8> = (0..200_u8).collect();
+        // Chunks of varying lengths, some of them empty.
+        let chunks: Vec<&[u8]> = vec![
+            &bytes[..0],
+            &bytes[..1],
+            &bytes[1..1],
+            &bytes[1..150],
+            &bytes[150..],
+        ];
+        let mut reader = ChunkReader::new(chunks.clone());
+        let mut buf = [0; 64];
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(reader.read(&mut buf).unwrap(), 64);
+        assert_eq!(buf[0], 1);
+
+        let bitvec = BitReader::from_chunks(chunks, false).read_to_end().unwrap();
+        assert_eq!(bitvec.into_bytes(), bytes);
+
+        let empty: Vec<Vec<u8>> = vec![];
+        let bitvec = BitReader::from_chunks(empty, true).read_to_end().unwrap();
+        assert!(bitvec.is_empty());
+    }
+
+    #[test]
+    fn test_read_synced_segments() {
+        let mut bw = crate::BitWriter::with_sync_markers(Cursor::new(vec![]), true);
+        for i in 0..4_u8 {
+            bw.write_bits(&[i % 2 == 0; 12]).unwrap();
+            bw.flush_segment().unwrap();
+        }
+        let bytes = bw.finalize().unwrap().into_inner();
+        // Each segment is the marker, the sequence number, the length and 2 bytes.
+        assert_eq!(bytes.len(), 4 * 11);
+
+        let read = |bytes: Vec<u8>| {
+            let segments = BitReader::new(Cursor::new(bytes), true)
+                .read_synced_segments()
+                .unwrap();
+            segments.into_iter().map(|s| s.0).collect::<Vec<_>>()
+        };
+        assert_eq!(read(bytes.clone()), vec![0, 1, 2, 3]);
+
+        // Garbage before the stream, a corrupted second segment, and a trailing
+        // segment that is cut short.
+        let mut corrupted = vec![0x12, 0xFF, 0xA5];
+        corrupted.extend_from_slice(&bytes[..bytes.len() - 1]);
+        corrupted[3 + 11 + 8] = 0x8F;
+        assert_eq!(read(corrupted), vec![0, 2]);
+
+        // A segment that lost its terminating bit.
+        let mut corrupted = bytes.clone();
+        corrupted[10] = 0x00;
+        assert_eq!(read(corrupted), vec![1, 2, 3]);
     }
 }
@@ -0,0 +1,207 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::code::global::vb::decode_vb_prefix;
+use crate::code::Decoder;
+use crate::error::InvalidCodeError;
+use crate::num::Numeric;
+
+/// A structure that shares the bytes of a stream written in segments, with
+/// [`BitWriter::flush_segment`](crate::BitWriter::flush_segment), so that its
+/// segments can be decoded independently, and concurrently.
+///
+/// The bytes are held in an [`Arc`], and may be of any type that is
+/// [`AsRef<[u8]>`](AsRef), like a `[u8]`, a `Vec<u8>` or a memory map. Cloning the decoder
+/// clones the `Arc`s of the bytes and of the positions of the segments, so
+/// each thread can hold its own clone, and decode the segments it needs with
+/// the decoder of their code, straight from the shared bytes.
+///
+/// Each segment is decoded on its own, like a stream of a single segment, so
+/// the numbers must not span segments. The decoders of the crate, as well as
+/// [`BitReader`](crate::BitReader) and [`BitVec`](crate::BitVec), are
+/// [`Send`] and [`Sync`] when their reader is, so the decoders of the segments
+/// can also be passed between threads.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::sync::Arc;
+/// use idencode::{Encoder, GammaDecoder, GammaEncoder, SharedDecoder};
+///
+/// let mut enc = GammaEncoder::new(Cursor::new(vec![]));
+/// for block in [[1_u32, 2, 3], [40, 50, 60]] {
+///     enc.encode(&block).unwrap();
+///     enc.flush_segment().unwrap();
+/// }
+/// let bytes: Arc<[u8]> = enc.finalize().unwrap().into_inner().into();
+///
+/// let shared = SharedDecoder::new(bytes);
+/// assert_eq!(shared.num_segments(), 2);
+/// let handles: Vec<_> = (0..2)
+///     .map(|i| {
+///         let shared = shared.clone();
+///         std::thread::spawn(move || shared.decode_segment::<u32, _>(i, GammaDecoder::new))
+///     })
+///     .collect();
+/// let blocks: Vec<_> = handles.into_iter().map(|h| h.join().unwrap().unwrap()).collect();
+/// assert_eq!(blocks, vec![vec![1, 2, 3], vec![40, 50, 60]]);
+/// ```
+#[derive(Debug)]
+pub struct SharedDecoder<B: ?Sized> {
+    bytes: Arc<B>,
+    segments: Arc<[Range<usize>]>,
+}
+
+impl<B: AsRef<[u8]> + ?Sized> SharedDecoder<B> {
+    /// Creates a new decoder over the shared bytes, finding the positions of
+    /// their segments.
+    ///
+    /// A trailing segment that is cut short is ignored, like
+    /// [`BitReader::read_segments`](crate::BitReader::read_segments) does.
+    pub fn new(bytes: Arc<B>) -> Self {
+        let buffer = (*bytes).as_ref();
+        let mut segments = vec![];
+        let mut pos = 0;
+        while let Ok((len, len_bytes)) = decode_vb_prefix(&buffer[pos..]) {
+            let start = pos + len_bytes;
+            let Some(end) = usize::try_from(len)
+                .ok()
+                .and_then(|len| start.checked_add(len))
+                .filter(|&end| end <= buffer.len())
+            else {
+                break;
+            };
+            segments.push(start..end);
+            pos = end;
+        }
+        SharedDecoder {
+            bytes,
+            segments: segments.into(),
+        }
+    }
+
+    /// Returns the number of segments.
+    #[inline]
+    pub fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the bytes of a segment, without its length, or `None` if there
+    /// is no such segment.
+    pub fn segment(&self, index: usize) -> Option<&[u8]> {
+        let range = self.segments.get(index)?.clone();
+        Some(&(*self.bytes).as_ref()[range])
+    }
+
+    /// Decodes the numbers of a segment, with the decoder that `new` creates
+    /// over its bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no segment at the index.
+    pub fn decode_segment<'a, T, D>(
+        &'a self,
+        index: usize,
+        new: impl FnOnce(&'a [u8]) -> D,
+    ) -> Result<Vec<T>, InvalidCodeError>
+    where
+        T: Numeric,
+        D: Decoder<&'a [u8]>,
+    {
+        let segment = self
+            .segment(index)
+            .expect("The segment index is out of bounds.");
+        new(segment).decode()
+    }
+
+    /// Returns a reference to the shared bytes.
+    pub fn get_ref(&self) -> &Arc<B> {
+        &self.bytes
+    }
+}
+
+impl<B: ?Sized> Clone for SharedDecoder<B> {
+    fn clone(&self) -> Self {
+        SharedDecoder {
+            bytes: Arc::clone(&self.bytes),
+            segments: Arc::clone(&self.segments),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BitReader, BitVec, DecodeReader, DeltaDecoder, DeltaEncoder, Encoder, GammaDecoder,
+        VBDecoder, VBEncoder,
+    };
+    use std::io::Cursor;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<SharedDecoder<[u8]>>();
+        assert_send_sync::<SharedDecoder<Vec<u8>>>();
+        assert_send_sync::<BitReader<&[u8]>>();
+        assert_send_sync::<BitVec>();
+        assert_send_sync::<GammaDecoder<&[u8]>>();
+        assert_send_sync::<DeltaDecoder<&[u8]>>();
+        assert_send_sync::<VBDecoder<&[u8]>>();
+        assert_send_sync::<DecodeReader<std::vec::IntoIter<Result<u32, InvalidCodeError>>, u32>>();
+    }
+
+    #[test]
+    fn test_concurrent_decoding() {
+        let blocks: Vec<Vec<u64>> = (0..16_u64)
+            .map(|b| {
+                (0..500_u64)
+                    .map(|i| (b * 500 + i + 1).wrapping_mul(0x9E3779B97F4A7C15) >> 24)
+                    .collect()
+            })
+            .collect();
+        let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+        for block in &blocks {
+            enc.encode(block).unwrap();
+            enc.flush_segment().unwrap();
+        }
+        let bytes: Arc<[u8]> = enc.finalize().unwrap().into_inner().into();
+        let shared = SharedDecoder::new(Arc::clone(&bytes));
+        assert_eq!(shared.num_segments(), blocks.len());
+
+        // Every thread decodes all the segments, each in a different order.
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let shared = shared.clone();
+                let blocks = &blocks;
+                scope.spawn(move || {
+                    for i in (0..blocks.len()).map(|i| (i * 5 + t) % blocks.len()) {
+                        let nums = shared.decode_segment::<u64, _>(i, DeltaDecoder::new);
+                        assert_eq!(nums.unwrap(), blocks[i]);
+                    }
+                });
+            }
+        });
+        // The threads shared the bytes without copying them.
+        assert_eq!(Arc::strong_count(&bytes), 2);
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut enc = VBEncoder::new(Cursor::new(vec![]));
+        enc.encode(&[300_u32]).unwrap();
+        let mut bytes = enc.finalize().unwrap().into_inner();
+        // A segment of the two bytes of 300, and one that is cut short.
+        bytes.insert(0, 0x82);
+        bytes.extend([0x85, 0x01]);
+
+        let shared = SharedDecoder::new(Arc::new(bytes));
+        assert_eq!(shared.num_segments(), 1);
+        assert_eq!(shared.segment(0), Some(&[0b00000010, 0b10101100][..]));
+        assert_eq!(shared.segment(1), None);
+        assert_eq!(shared.decode_segment(0, VBDecoder::new), Ok(vec![300_u32]));
+        assert_eq!(shared.get_ref().len(), 5);
+    }
+}
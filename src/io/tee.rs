@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+
+use crate::io::write::BitWriter;
+
+/// A bit-writer that writes its bytes to two writers at once.
+///
+/// More writers are added by nesting [`TeeWriter`]s.
+pub type TeeBitWriter<A, B> = BitWriter<TeeWriter<A, B>>;
+
+/// A writer that duplicates the bytes written to it to two writers, like a
+/// file and a hasher, or a local file and a socket, so that both receive the
+/// output of an encoder in a single pass.
+///
+/// More writers are added by nesting `TeeWriter`s, as in
+/// `TeeWriter::new(a, TeeWriter::new(b, c))`.
+///
+/// Each write is passed to the first writer, and the bytes that it accepts are
+/// then written in full to the second, so both writers always receive the same
+/// bytes. A first writer that only accepts part of the bytes, or that returns
+/// an error of kind [`io::ErrorKind::WouldBlock`], is thus supported, like
+/// [`BitWriter::resume_flush`] does for a single writer, but the second writer
+/// must accept all the bytes that are passed to it.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::{Encoder, GammaEncoder, TeeWriter};
+///
+/// let mut enc = GammaEncoder::new(TeeWriter::new(Cursor::new(vec![]), vec![]));
+/// enc.encode(&[1_u32, 2, 3]).unwrap();
+/// let (file, copy) = enc.finalize().unwrap().into_inner();
+/// assert_eq!(file.into_inner(), copy);
+/// ```
+#[derive(Debug)]
+pub struct TeeWriter<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    /// Creates a new `TeeWriter` that writes to both writers.
+    pub fn new(first: A, second: B) -> Self {
+        TeeWriter { first, second }
+    }
+
+    /// Returns references to the two writers.
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Returns mutable references to the two writers.
+    ///
+    /// Writing to them directly makes their outputs differ.
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Consumes the `TeeWriter`, returning the two writers.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.first.write(buf)?;
+        self.second.write_all(&buf[..n])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.first.flush()?;
+        self.second.flush()
+    }
+}
+
+impl<A: Write, B: Write> BitWriter<TeeWriter<A, B>> {
+    /// Creates a new bit-writer that writes its bytes to two writers at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::TeeBitWriter;
+    ///
+    /// let mut bw = TeeBitWriter::tee(Cursor::new(vec![]), vec![], true);
+    /// bw.write_bits(&[true, false]).unwrap();
+    /// let (first, second) = bw.finalize().unwrap().into_inner();
+    /// assert_eq!(first.into_inner(), vec![0b10100000]);
+    /// assert_eq!(second, vec![0b10100000]);
+    /// ```
+    pub fn tee(first: A, second: B, term_bit: bool) -> Self {
+        BitWriter::new(TeeWriter::new(first, second), term_bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoder, VBEncoder};
+    use std::hash::Hasher;
+    use std::io::Cursor;
+
+    // A writer that hashes the bytes written to it.
+    #[derive(Default)]
+    struct HashWriter(std::collections::hash_map::DefaultHasher, usize);
+
+    impl Write for HashWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf);
+            self.1 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // A writer that accepts up to 3 bytes at a time, and blocks in between.
+    #[derive(Default)]
+    struct Choppy {
+        bytes: Vec<u8>,
+        ready: bool,
+    }
+
+    impl Write for Choppy {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(3);
+            self.bytes.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_nested_writers() {
+        let nums: Vec<u64> = (0..1000_u64)
+            .map(|i| i.wrapping_mul(0x9E3779B97F4A7C15) >> 40)
+            .collect();
+        let tee = TeeWriter::new(vec![], TeeWriter::new(vec![], HashWriter::default()));
+        let mut enc = VBEncoder::new(tee);
+        enc.encode(&nums).unwrap();
+        let (first, rest) = enc.finalize().unwrap().into_inner();
+        let (second, hasher) = rest.into_inner();
+
+        assert_eq!(first, second);
+        assert_eq!(hasher.1, first.len());
+        let mut expected = std::collections::hash_map::DefaultHasher::new();
+        expected.write(&first);
+        assert_eq!(hasher.0.finish(), expected.finish());
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut bw = TeeBitWriter::tee(Cursor::new(vec![]), vec![], false);
+        for i in 0..3 {
+            bw.write_bits(&[i % 2 == 0; 10]).unwrap();
+            bw.flush_segment().unwrap();
+        }
+        let (first, second) = bw.finalize().unwrap().into_inner();
+        assert_eq!(first.get_ref().len(), 9);
+        assert_eq!(first.into_inner(), second);
+    }
+
+    #[test]
+    fn test_would_block() {
+        let mut bw = TeeBitWriter::tee(Choppy::default(), vec![], true);
+        bw.write_bits(&[true; 60]).unwrap();
+        let mut result = bw.finish();
+        while let Err(e) = result {
+            assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+            result = bw.resume_flush();
+        }
+        let (first, second) = bw.into_inner().into_inner();
+        assert_eq!(first.bytes.len(), 8);
+        assert_eq!(first.bytes, second);
+    }
+}
@@ -0,0 +1,274 @@
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use integer_encoding::VarInt;
+
+use crate::code::Encoder;
+use crate::error::InvalidCodeError;
+
+/// The largest number of bytes of a varint of the `integer-encoding` crate.
+const MAX_VARINT_BYTES: usize = 10;
+
+/// An adapter that implements [`io::Write`], parsing the written bytes as the
+/// varints of the [`integer_encoding`] crate and feeding the numbers to a
+/// wrapped encoder.
+///
+/// Since [`integer_encoding::VarIntWriter`] is implemented for every writer,
+/// code written against it can encode its numbers with any code of this crate
+/// by writing to the adapter. Signed numbers are zigzagged by
+/// `integer-encoding` before they reach the adapter, so they are encoded as
+/// unsigned numbers. Since Elias Gamma and Delta Encoding cannot encode the
+/// number 0, they only fit code that writes positive numbers.
+///
+/// The encoding has to be completed with [`VarIntEncodeWriter::finish`],
+/// which finalizes the encoder.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use integer_encoding::{VarIntReader, VarIntWriter};
+/// use idencode::io::varint::{VarIntDecodeReader, VarIntEncodeWriter};
+/// use idencode::{DecodeIterExt, VBEncoder};
+///
+/// let mut writer = VarIntEncodeWriter::new(VBEncoder::new(Cursor::new(vec![])));
+/// writer.write_varint(300_u32).unwrap();
+/// writer.write_varint(-2_i64).unwrap();
+/// let bytes = writer.finish().unwrap().into_inner();
+///
+/// let mut reader = VarIntDecodeReader::new(bytes.into_iter().vb_decoded::<u64>());
+/// assert_eq!(reader.read_varint::<u32>().unwrap(), 300);
+/// assert_eq!(reader.read_varint::<i64>().unwrap(), -2);
+/// ```
+pub struct VarIntEncodeWriter<E, W> {
+    encoder: E,
+    varint: Vec<u8>,
+    _writer: PhantomData<W>,
+}
+
+impl<E: Encoder<W>, W: Write> VarIntEncodeWriter<E, W> {
+    /// Creates a new adapter that feeds the numbers to the encoder.
+    pub fn new(encoder: E) -> Self {
+        VarIntEncodeWriter {
+            encoder,
+            varint: Vec::with_capacity(MAX_VARINT_BYTES),
+            _writer: PhantomData,
+        }
+    }
+
+    /// Finalizes the wrapped encoder, returning its writer.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if the last
+    /// varint has not been completely written.
+    pub fn finish(self) -> io::Result<W> {
+        if !self.varint.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The last varint is incomplete.",
+            ));
+        }
+        self.encoder.finalize()
+    }
+}
+
+impl<E: Encoder<W>, W: Write> Write for VarIntEncodeWriter<E, W> {
+    /// Parses the varints of the bytes, and encodes the numbers.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a varint is
+    /// longer than the ones of 64-bit numbers.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut nums = vec![];
+        for &byte in buf {
+            self.varint.push(byte);
+            if byte < 0x80 {
+                let (num, _) = u64::decode_var(&self.varint)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid varint."))?;
+                nums.push(num);
+                self.varint.clear();
+            } else if self.varint.len() == MAX_VARINT_BYTES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Varint longer than 10 bytes.",
+                ));
+            }
+        }
+        self.encoder.encode(&nums)?;
+        Ok(buf.len())
+    }
+
+    /// Does nothing, since the encoders write their output when they are
+    /// finalized, or as they encode.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An adapter that wraps a stream of decoded numbers and implements
+/// [`io::Read`], emitting each number as a varint of the [`integer_encoding`]
+/// crate.
+///
+/// Since [`integer_encoding::VarIntReader`] is implemented for every reader,
+/// code written against it can read numbers encoded with any code of this
+/// crate from the adapter.
+///
+/// A decoding error is returned as an error of kind
+/// [`io::ErrorKind::InvalidData`], after which the adapter is exhausted.
+pub struct VarIntDecodeReader<I> {
+    iter: I,
+    bytes: [u8; MAX_VARINT_BYTES],
+    pos: usize,
+    len: usize,
+    error: Option<InvalidCodeError>,
+    failed: bool,
+}
+
+impl<I: Iterator<Item = Result<u64, InvalidCodeError>>> VarIntDecodeReader<I> {
+    /// Creates a new adapter that emits the numbers of the stream.
+    pub fn new(iter: I) -> Self {
+        VarIntDecodeReader {
+            iter,
+            bytes: [0; MAX_VARINT_BYTES],
+            pos: 0,
+            len: 0,
+            error: None,
+            failed: false,
+        }
+    }
+
+    // Encodes the next number into the byte buffer, returning `false` if the
+    // stream is exhausted, or if the number is invalid.
+    fn fill(&mut self) -> bool {
+        if self.failed {
+            return false;
+        }
+        match self.iter.next() {
+            Some(Ok(num)) => {
+                self.len = num.encode_var(&mut self.bytes);
+                self.pos = 0;
+                true
+            }
+            Some(Err(e)) => {
+                self.error = Some(e);
+                self.failed = true;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<u64, InvalidCodeError>>> Read for VarIntDecodeReader<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if self.pos == self.len && !self.fill() {
+                break;
+            }
+            let n = (self.len - self.pos).min(buf.len() - written);
+            buf[written..written + n].copy_from_slice(&self.bytes[self.pos..self.pos + n]);
+            self.pos += n;
+            written += n;
+        }
+        // The bytes before a decoding error are emitted first, and the error
+        // is returned by the next read.
+        if written == 0 {
+            if let Some(e) = self.error.take() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodeIterExt, Decoder, DeltaDecoder, DeltaEncoder, RleEncoder, VBEncoder};
+    use integer_encoding::{VarIntReader, VarIntWriter};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_matches_varint_output() {
+        let nums = [0_u64, 1, 127, 128, 300, 1 << 35, u64::MAX];
+        let mut expected = vec![];
+        for num in nums {
+            expected.write_varint(num).unwrap();
+        }
+
+        let iter = nums.into_iter().map(Ok);
+        let mut bytes = vec![];
+        VarIntDecodeReader::new(iter)
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, expected);
+
+        let mut reader = Cursor::new(bytes);
+        for num in nums {
+            assert_eq!(reader.read_varint::<u64>().unwrap(), num);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let nums: Vec<u64> = (1..300).map(|i| i * i * 7919).collect();
+        let mut writer = VarIntEncodeWriter::new(DeltaEncoder::new(Cursor::new(vec![])));
+        for &num in &nums {
+            writer.write_varint(num).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut enc = DeltaEncoder::new(Cursor::new(vec![]));
+        enc.encode(&nums).unwrap();
+        assert_eq!(bytes, enc.finalize().unwrap().into_inner());
+
+        let decoded = DeltaDecoder::new(Cursor::new(bytes))
+            .decode::<u64>()
+            .unwrap();
+        let mut reader = VarIntDecodeReader::new(decoded.into_iter().map(Ok));
+        for &num in &nums {
+            assert_eq!(reader.read_varint::<u64>().unwrap(), num);
+        }
+        assert!(reader.read_varint::<u64>().is_err());
+    }
+
+    #[test]
+    fn test_split_writes() {
+        let mut expected = vec![];
+        for num in [5_i32, -300, i32::MIN] {
+            expected.write_varint(num).unwrap();
+        }
+        let mut writer = VarIntEncodeWriter::new(VBEncoder::new(Cursor::new(vec![])));
+        for byte in &expected {
+            writer.write_all(&[*byte]).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let iter = bytes.into_iter().vb_decoded::<u64>();
+        let mut reader = VarIntDecodeReader::new(iter);
+        assert_eq!(reader.read_varint::<i32>().unwrap(), 5);
+        assert_eq!(reader.read_varint::<i32>().unwrap(), -300);
+        assert_eq!(reader.read_varint::<i32>().unwrap(), i32::MIN);
+    }
+
+    #[test]
+    fn test_errs() {
+        let mut writer = VarIntEncodeWriter::new(VBEncoder::new(Cursor::new(vec![])));
+        writer.write_all(&[0x80, 0x80]).unwrap();
+        let err = writer.finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut writer = VarIntEncodeWriter::new(VBEncoder::new(Cursor::new(vec![])));
+        let err = writer.write_all(&[0xFF; 11]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // The encoder rejects the number.
+        let mut writer = VarIntEncodeWriter::new(RleEncoder::new(Cursor::new(vec![])));
+        assert!(writer.write_varint(u64::MAX).is_err());
+
+        let bytes = vec![0b10000101, 0b00000101];
+        let mut reader = VarIntDecodeReader::new(bytes.into_iter().vb_decoded::<u64>());
+        assert_eq!(reader.read_varint::<u64>().unwrap(), 5);
+        let err = reader.read_varint::<u64>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
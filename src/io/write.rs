@@ -1,12 +1,19 @@
 use std::io::{self, Write};
 
+use crate::code::global::vb::write_vb_bytes;
 use crate::collections::BitVec;
-use crate::io::DEFAULT_BUF_SIZE;
+use crate::error::BufferError;
+use crate::io::{Padding, DEFAULT_BUF_SIZE, SYNC_MARKER};
 
 /// This structure represents a bit-writer.
 pub struct BitWriter<W> {
     buf: BitVec,
     term_bit: bool,
+    padding: Padding,
+    segmented: bool,
+    seq: Option<u32>,
+    pending: Vec<u8>,
+    finished: bool,
     inner: W,
 }
 
@@ -21,7 +28,68 @@ impl<W: Write> BitWriter<W> {
         BitWriter {
             inner,
             buf: BitVec::with_capacity(capacity),
-            term_bit
+            term_bit,
+            padding: Padding::Zeros,
+            segmented: false,
+            seq: None,
+            pending: vec![],
+            finished: false,
+        }
+    }
+
+    /// Creates a new `BitWriter<W>` that fills the last byte of the stream, and
+    /// of each segment, with the specified padding bits.
+    ///
+    /// With a terminating bit, the terminating bit is the opposite of the
+    /// padding bits, so the stream must be read with
+    /// [`BitReader::with_padding`](crate::BitReader::with_padding) and the same
+    /// padding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::io::Padding;
+    /// use idencode::BitWriter;
+    ///
+    /// let mut bw = BitWriter::with_padding(Cursor::new(vec![]), true, Padding::Ones);
+    /// bw.write_bits(&[true, true, false]).unwrap();
+    /// let result = bw.finalize().unwrap().into_inner();
+    /// assert_eq!(result, vec![0b11001111]);
+    /// ```
+    pub fn with_padding(inner: W, term_bit: bool, padding: Padding) -> BitWriter<W> {
+        BitWriter {
+            padding,
+            ..BitWriter::new(inner, term_bit)
+        }
+    }
+
+    /// Creates a new `BitWriter<W>` that writes its bits in segments, each one
+    /// preceded by a sync marker, so that a reader can resume after a
+    /// corrupted segment.
+    ///
+    /// The marker is the byte pattern [`SYNC_MARKER`], followed by the sequence
+    /// number of the segment as a big-endian `u32`, starting from 0 and
+    /// wrapping around. The segments are flushed with
+    /// [`BitWriter::flush_segment`], and the stream must be read with
+    /// [`BitReader::read_synced_segments`](crate::BitReader::read_synced_segments).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::BitWriter;
+    ///
+    /// let mut bw = BitWriter::with_sync_markers(Cursor::new(vec![]), true);
+    /// bw.write_bits(&[true, false]).unwrap();
+    /// let result = bw.finalize().unwrap().into_inner();
+    /// assert_eq!(result, vec![0xFF, 0xA5, 0x00, 0x5A, 0, 0, 0, 0, 0x81, 0b10100000]);
+    /// ```
+    pub fn with_sync_markers(inner: W, term_bit: bool) -> BitWriter<W> {
+        BitWriter {
+            segmented: true,
+            seq: Some(0),
+            ..BitWriter::new(inner, term_bit)
         }
     }
 
@@ -97,6 +165,171 @@ impl<W: Write> BitWriter<W> {
         self.buf.clear()
     }
 
+    /// Terminates the bits written so far as a segment, and writes it in the
+    /// underlying writer, so that it can be decoded before the writing ends.
+    ///
+    /// The segment is padded to a whole byte, after the terminating bit if
+    /// there is one, and is prefixed by its length in bytes in Variable Byte
+    /// Encoding. Once a segment is flushed, [`BitWriter::finalize`] writes the
+    /// remaining bits as the last segment, and the stream must be read with
+    /// [`BitReader::read_segments`](crate::BitReader::read_segments).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use idencode::BitWriter;
+    ///
+    /// let mut bw = BitWriter::new(Cursor::new(vec![]), true);
+    /// bw.write_bits(&[true, false]).unwrap();
+    /// bw.flush_segment().unwrap();
+    /// assert_eq!(*bw.get_ref().as_bytes(), []);
+    /// bw.write_bit(true).unwrap();
+    /// let result = bw.finalize().unwrap();
+    /// assert_eq!(result.into_inner(), vec![0x81, 0b10100000, 0x81, 0b11000000]);
+    /// ```
+    pub fn flush_segment(&mut self) -> io::Result<()> {
+        self.segmented = true;
+        if !self.buf.is_empty() {
+            self.trace_block();
+            self.terminate();
+            if let Some(seq) = self.seq {
+                self.pending.extend_from_slice(&SYNC_MARKER);
+                self.pending.extend_from_slice(&seq.to_be_bytes());
+                self.seq = Some(seq.wrapping_add(1));
+            }
+            write_vb_bytes(self.buf.as_bytes().len(), &mut self.pending);
+            self.pending.extend_from_slice(self.buf.as_bytes());
+            self.buf.clear();
+        }
+        self.resume_flush()
+    }
+
+    /// Writes the bytes that the underlying writer has not accepted yet, and
+    /// flushes it.
+    ///
+    /// The bytes of a segment, or of the finished stream, are kept in the
+    /// bit-writer until the underlying writer accepts them, so a writer that
+    /// only accepts part of them, or returns an error of kind
+    /// [`io::ErrorKind::WouldBlock`], does not lose any. This allows writing to
+    /// a non-blocking writer, such as a non-blocking socket, by calling
+    /// `resume_flush` whenever the writer is ready again, until it succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{self, Write};
+    /// use idencode::BitWriter;
+    ///
+    /// // A writer that accepts a byte at a time, and blocks in between.
+    /// struct Trickle(Vec<u8>, bool);
+    ///
+    /// impl Write for Trickle {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.1 = !self.1;
+    ///         if self.1 {
+    ///             return Err(io::ErrorKind::WouldBlock.into());
+    ///         }
+    ///         self.0.push(buf[0]);
+    ///         Ok(1)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut bw = BitWriter::new(Trickle(vec![], false), true);
+    /// bw.write_bits(&[true; 12]).unwrap();
+    /// let mut result = bw.finish();
+    /// while let Err(e) = result {
+    ///     assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+    ///     result = bw.resume_flush();
+    /// }
+    /// assert_eq!(bw.into_inner().0, vec![0xFF, 0b11111000]);
+    /// ```
+    pub fn resume_flush(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.inner.write(&self.pending) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "Failed to write the buffered bytes.",
+                    ))
+                }
+                Ok(n) => {
+                    self.pending.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.inner.flush()
+    }
+
+    /// Returns `true` if there are bytes that the underlying writer has not
+    /// accepted yet.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Finishes the writing without consuming the bit-writer, so that it can
+    /// be resumed with [`BitWriter::resume_flush`] if the underlying writer
+    /// does not accept all the bytes.
+    ///
+    /// The bits written so far are terminated like [`BitWriter::finalize`]
+    /// does, only the first time this is called. No bits should be written
+    /// afterward. The underlying writer is then returned by
+    /// [`BitWriter::into_inner`].
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return self.resume_flush();
+        }
+        self.finished = true;
+        if self.segmented {
+            return self.flush_segment();
+        }
+        if !self.buf.is_empty() {
+            self.trace_block();
+            self.terminate();
+            self.pending.extend_from_slice(self.buf.as_bytes());
+            self.buf.clear();
+        }
+        self.resume_flush()
+    }
+
+    /// Consumes the bit-writer, returning the underlying writer.
+    ///
+    /// The bits that have not been written out with [`BitWriter::finish`],
+    /// or [`BitWriter::flush_segment`], are discarded, as are the bytes that
+    /// the underlying writer has not accepted yet.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    // Appends the terminating bit, if any, and fills the last byte with the
+    // padding bits.
+    fn terminate(&mut self) {
+        if self.term_bit {
+            self.buf.push(!self.padding.bit());
+        }
+        if self.padding == Padding::Ones {
+            while !self.buf.len().is_multiple_of(8) {
+                self.buf.push(true);
+            }
+        }
+    }
+
+    // Emits the counters of the bits that are written out in a block, with the
+    // `tracing` feature.
+    fn trace_block(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            monotonic_counter.idencode_bits_written = self.buf.len() as u64,
+            monotonic_counter.idencode_blocks_flushed = 1_u64,
+        );
+    }
+
     /// Consumes the bit-writer and finalizes the writing, returning the
     /// underlying buffer.
     ///
@@ -123,15 +356,56 @@ impl<W: Write> BitWriter<W> {
     /// assert_eq!(result.into_inner(), vec![0b10100000]);
     /// ```
     pub fn finalize(mut self) -> io::Result<W> {
-        if self.buf.is_empty() {
-            return Ok(self.inner);
+        self.finish()?;
+        Ok(self.inner)
+    }
+}
+
+/// A cursor that writes bits in place into a buffer of bytes, in
+/// most-significant-bit first order, without allocating.
+pub(crate) struct ByteBitsMut<'a> {
+    bytes: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> ByteBitsMut<'a> {
+    /// Creates a cursor at the start of the bytes.
+    pub(crate) fn new(bytes: &'a mut [u8]) -> Self {
+        ByteBitsMut { bytes, pos: 0 }
+    }
+
+    /// Writes a bit, or returns an error at the end of the bytes.
+    pub(crate) fn write_bit(&mut self, bit: bool) -> Result<(), BufferError> {
+        let byte = self
+            .bytes
+            .get_mut(self.pos / 8)
+            .ok_or(BufferError::BufferTooSmall)?;
+        let shift = 7 - self.pos % 8;
+        // The bytes are not cleared beforehand, so each bit is set or cleared.
+        *byte = (*byte & !(1 << shift)) | (u8::from(bit) << shift);
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Writes the lowest `n` bits of a number, up to 64, starting from the
+    /// most significant one.
+    pub(crate) fn write_bits(&mut self, num: u64, n: u32) -> Result<(), BufferError> {
+        for i in (0..n).rev() {
+            self.write_bit((num >> i) & 1 == 1)?;
         }
-        if self.term_bit {
-            self.buf.push(true); // Add the terminating bit.
+        Ok(())
+    }
+
+    /// Writes the terminating bit, if any bits were written, and clears the
+    /// rest of the last byte, returning the number of bytes written.
+    pub(crate) fn finish(mut self, term_bit: bool) -> Result<usize, BufferError> {
+        if term_bit && self.pos > 0 {
+            self.write_bit(true)?;
         }
-        self.inner.write_all(self.buf.as_bytes())?;
-        self.inner.flush()?;
-        Ok(self.inner)
+        while !self.pos.is_multiple_of(8) {
+            self.write_bit(false)?;
+        }
+        Ok(self.pos / 8)
     }
 }
 
@@ -155,4 +429,85 @@ mod tests {
             vec![0b00000011, 0b00000001, 0b10000000]
         )
     }
+
+    #[test]
+    fn test_flush_segment() {
+        let mut bw = BitWriter::new(Cursor::new(vec![]), true);
+        bw.flush_segment().unwrap();
+        bw.write_bits(&[false; 9]).unwrap();
+        bw.flush_segment().unwrap();
+        bw.flush_segment().unwrap();
+        bw.write_bit(true).unwrap();
+        let result = bw.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0x82, 0x00, 0b01000000, 0x81, 0b11000000]);
+
+        // A stream whose segments are all flushed ends with its last segment.
+        let mut bw = BitWriter::new(Cursor::new(vec![]), false);
+        bw.write_bits(&[true; 8]).unwrap();
+        bw.flush_segment().unwrap();
+        let result = bw.finalize().unwrap().into_inner();
+        assert_eq!(result, vec![0x81, 0xFF]);
+    }
+
+    #[test]
+    fn test_sync_markers() {
+        let mut bw = BitWriter::with_sync_markers(Cursor::new(vec![]), false);
+        bw.write_bits(&[true; 8]).unwrap();
+        bw.flush_segment().unwrap();
+        bw.flush_segment().unwrap();
+        bw.write_bits(&[false; 8]).unwrap();
+        let result = bw.finalize().unwrap().into_inner();
+        let mut expected = SYNC_MARKER.to_vec();
+        expected.extend([0, 0, 0, 0, 0x81, 0xFF]);
+        expected.extend(SYNC_MARKER);
+        expected.extend([0, 0, 0, 1, 0x81, 0x00]);
+        assert_eq!(result, expected);
+    }
+
+    // A writer that accepts up to two bytes at a time, and blocks in between.
+    struct Choppy {
+        bytes: Vec<u8>,
+        ready: bool,
+    }
+
+    impl Write for Choppy {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let n = buf.len().min(2);
+            self.bytes.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_would_block() {
+        let mut expected = BitWriter::new(Cursor::new(vec![]), true);
+        let choppy = Choppy {
+            bytes: vec![],
+            ready: false,
+        };
+        let mut bw = BitWriter::new(choppy, true);
+        for i in 0..5_u64 {
+            let bits: Vec<bool> = (0..20).map(|j| (i * 20 + j) % 3 == 0).collect();
+            expected.write_bits(&bits).unwrap();
+            expected.flush_segment().unwrap();
+            bw.write_bits(&bits).unwrap();
+            // The segment is kept until the writer accepts it.
+            while let Err(e) = bw.flush_segment() {
+                assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+                assert!(bw.has_pending());
+            }
+        }
+        while bw.finish().is_err() {}
+        assert!(!bw.has_pending());
+        let expected = expected.finalize().unwrap().into_inner();
+        assert_eq!(bw.into_inner().bytes, expected);
+    }
 }
@@ -3,13 +3,72 @@ pub mod collections;
 pub mod error;
 pub mod io;
 pub mod num;
+pub mod postings;
+pub mod reorder;
+pub mod segment;
 
-pub use collections::BitVec;
-pub use io::read::BitReader;
+pub use collections::{BitSlice, BitVec};
+pub use io::decode::DecodeReader;
+pub use io::encode::EncodeWriter;
+pub use io::read::{BitReader, ChunkReader};
+pub use io::shared::SharedDecoder;
+pub use io::tee::{TeeBitWriter, TeeWriter};
 pub use io::write::BitWriter;
+pub use io::Padding;
 
-pub use code::{Encoder, Decoder, EncodeOne, DecodeOne};
-pub use code::global::gamma::{GammaEncoder, GammaDecoder};
-pub use code::global::unary::{UnaryDecoder, UnaryEncoder};
+pub use code::adaptive::{AutoCode, AutoDecoder, AutoEncoder};
+pub use code::byte::chunk::{ChunkDecoder, ChunkEncoder};
+pub use code::byte::nibble::{NibbleDecoder, NibbleEncoder};
+pub use code::byte::prefix_varint::{PrefixVarintDecoder, PrefixVarintEncoder};
+pub use code::byte::sqlite_varint::{SqliteVarintDecoder, SqliteVarintEncoder};
+pub use code::byte::stream_vbyte::{StreamVByteDecoder, StreamVByteEncoder};
+pub use code::byte::vargroup::{VarGroupDecoder, VarGroupEncoder};
+pub use code::byte::vb16::{VB16Decoder, VB16Encoder};
+pub use code::double_delta::{DoubleDeltaDecoder, DoubleDeltaEncoder};
+pub use code::entropy::arith::{ArithDecoder, ArithEncoder};
+pub use code::entropy::huffman::{HuffmanDecoder, HuffmanEncoder, HuffmanTable};
+pub use code::entropy::range::{RangeDecoder, RangeEncoder};
+pub use code::entropy::rans::{RansDecoder, RansEncoder, RansTable};
+pub use code::float::chimp::{ChimpDecoder, ChimpEncoder};
+pub use code::float::decimal::{DecimalDecoder, DecimalEncoder};
+pub use code::global::basc::{BascDecoder, BascEncoder};
+pub use code::global::delta::{DeltaDecoder, DeltaEncoder};
+pub use code::global::escaped_rice::{EscapedRiceDecoder, EscapedRiceEncoder};
+pub use code::global::gamma::{GammaDecoder, GammaEncoder};
+pub use code::global::gamma_prime::{GammaPrimeDecoder, GammaPrimeEncoder};
+pub use code::global::golomb::{GolombDecoder, GolombEncoder};
+pub use code::global::hybrid::{HybridDecoder, HybridEncoder};
+pub use code::global::minimal_binary::{MinimalBinaryDecoder, MinimalBinaryEncoder};
+pub use code::global::omega::{OmegaDecoder, OmegaEncoder};
+pub use code::global::rice::{RiceDecoder, RiceEncoder};
+pub use code::global::sss::{StartStepStopDecoder, StartStepStopEncoder};
+pub use code::global::unary::{UnaryConvention, UnaryDecoder, UnaryEncoder};
 pub use code::global::vb::{VBDecoder, VBEncoder};
-pub use code::global::delta::{DeltaEncoder}; //, DeltaDecoder};
\ No newline at end of file
+pub use code::global::zeta::{ZetaDecoder, ZetaEncoder};
+pub use code::iter::{DecodeIterExt, EncodeIterExt};
+pub use code::nullable::{NullableDecoder, NullableEncoder};
+pub use code::packed::bitpack::{BitPackDecoder, BitPackEncoder};
+pub use code::packed::bp128::{BP128Decoder, BP128Encoder};
+pub use code::packed::pfor::{PForDecoder, PForEncoder};
+pub use code::packed::signed::{SignedPackDecoder, SignedPackEncoder};
+pub use code::packed::simple::{SelectorTable, SimpleDecoder, SimpleEncoder};
+pub use code::packed::simple16::{Simple16Decoder, Simple16Encoder};
+pub use code::packed::simple9::{Simple9Decoder, Simple9Encoder};
+pub use code::positions::{PositionsDecoder, PositionsEncoder};
+pub use code::progress::{Progress, ProgressDecoder, ProgressEncoder};
+pub use code::rle::{RleDecoder, RleEncoder};
+pub use code::sorted::interpolative::{InterpolativeDecoder, InterpolativeEncoder};
+pub use code::spec::{CodecSpec, DescribeCodec};
+pub use code::split::{SplitCode, SplitDecoder, SplitEncoder};
+pub use code::sprintz::{SprintzDecoder, SprintzEncoder};
+pub use code::stats::{Stats, StatsEncoder, Zone, ZoneMap, ZoneMapEncoder};
+pub use code::tagged::{TaggedCode, TaggedDecoder, TaggedEncoder};
+pub use code::timeseries::xor_float::{GorillaDecoder, GorillaEncoder};
+pub use code::timestamp::{TimestampDecoder, TimestampEncoder};
+pub use code::transform::gaps::{GapDecoder, GapEncoder};
+pub use code::transform::rle0::{Rle0Decoder, Rle0Encoder};
+pub use code::transform::{DecodeSigned, EncodeSigned, ZigZag};
+pub use code::{
+    DecodeBytes, DecodeFrom, DecodeOne, DecodePrefix, Decoder, EncodeInto, EncodeOne, Encoder,
+    NonBlockingEncoder, SkipPrefix,
+};
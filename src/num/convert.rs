@@ -1,4 +1,5 @@
 use crate::error::OverflowError;
+use crate::num::pack::{pack_bits, unpack_bits};
 use crate::num::Numeric;
 
 /// Converts a vector of boolean values representing bits (in most significant to
@@ -8,18 +9,11 @@ use crate::num::Numeric;
 /// is interpreted as a binary number. The most significant bit is at the start of the
 /// vector, and the least significant bit is at the end.
 pub fn bits_to_numeric<T: Numeric>(bits: &[bool]) -> Result<T, OverflowError> {
-    if bits.len() > (T::BITS - 1) as usize {
+    if bits.len() > T::BITS as usize {
         return Err(OverflowError);
     }
-    let mut result = T::ZERO;
-    for (i, &bit) in bits.iter().enumerate() {
-        if bit {
-            let shift = u32::try_from(bits.len() - 1 - i)
-                .expect("It is guaranteed that the length of the bits does not exceed u32::MAX.");
-            result |= T::ONE << shift;
-        }
-    }
-    Ok(result)
+    let num = pack_bits(bits);
+    Ok(T::from_u64(num).expect("The bits are guaranteed to fit in the type."))
 }
 
 /// Calculate the offset bits.
@@ -29,17 +23,88 @@ pub fn bits_to_numeric<T: Numeric>(bits: &[bool]) -> Result<T, OverflowError> {
 /// starting from the most significant bit (after the leading one).
 pub(crate) fn write_offset_bits<T: Numeric>(num: &T, buffer: &mut Vec<bool>) {
     let leading_one_idx = T::BITS - num.leading_zeros() - 1;
-    for i in 0..leading_one_idx {
-        let shift = leading_one_idx - i - 1;
-        let base = T::ONE << shift;
-        if (*num & base).is_zero() {
-            buffer.push(false);
-        } else {
-            buffer.push(true);
-        }
+    unpack_bits(num.to_u64(), leading_one_idx, buffer);
+}
+
+/// Writes the lowest `width` bits of the number, starting from the most
+/// significant one.
+pub(crate) fn write_fixed_bits<T: Numeric>(num: &T, width: u32, buffer: &mut Vec<bool>) {
+    unpack_bits(num.to_u64(), width, buffer);
+}
+
+/// Writes a number in minimal binary, the truncated binary code of the
+/// numbers less than `size`: with *s* the bits of `size - 1`, the first
+/// *2^s - size* numbers are written in *s - 1* bits, and the rest are written
+/// as *num + 2^s - size* in *s* bits.
+///
+/// The sizes of the intervals of some codes exceed 64 bits, so the arithmetic
+/// is done in 128 bits. `num` must be less than `size`, which must be between
+/// 1 and *2^127*.
+pub(crate) fn write_minimal_binary(num: u128, size: u128, buffer: &mut Vec<bool>) {
+    let (width, short) = minimal_binary_params(size);
+    let (num, width) = match num < short {
+        true => (num, width - 1),
+        false => (num + short, width),
+    };
+    buffer.extend((0..width).rev().map(|i| (num >> i) & 1 == 1));
+}
+
+/// Reads the number written in minimal binary for the numbers less than
+/// `size` at the start of a buffer of bits, returning it along with the
+/// number of bits it occupies, or `None` if the bits are too few.
+pub(crate) fn read_minimal_binary(bits: &[bool], size: u128) -> Option<(u128, usize)> {
+    let mut pos = 0;
+    read_minimal_binary_with(size, |n| {
+        let group = bits.get(pos..pos + n as usize)?;
+        pos += n as usize;
+        Some(
+            group
+                .iter()
+                .fold(0, |acc, bit| (acc << 1) | u128::from(*bit)),
+        )
+    })
+}
+
+/// Reads the number written in minimal binary for the numbers less than
+/// `size` with a function that reads the next `n` bits as a number,
+/// returning it along with the number of bits it occupies, or `None` if the
+/// bits are too few.
+pub(crate) fn read_minimal_binary_with(
+    size: u128,
+    mut read: impl FnMut(u32) -> Option<u128>,
+) -> Option<(u128, usize)> {
+    let (width, short) = minimal_binary_params(size);
+    if width == 0 {
+        return Some((0, 0));
+    }
+    match read(width - 1)? {
+        num if num < short => Some((num, width as usize - 1)),
+        num => Some((((num << 1) | read(1)?) - short, width as usize)),
     }
 }
 
+/// Returns the number of bits of the long codewords of the minimal binary
+/// code of an interval, and the number of its short codewords.
+fn minimal_binary_params(size: u128) -> (u32, u128) {
+    assert!(
+        size > 0 && size <= 1 << 127,
+        "The size of the interval must be between 1 and 2^127."
+    );
+    let width = u128::BITS - (size - 1).leading_zeros();
+    (width, (1 << width) - size)
+}
+
+/// Maps a signed integer to an unsigned one, interleaving the negative and
+/// the positive integers.
+pub(crate) fn zigzag(num: i64) -> u64 {
+    ((num << 1) ^ (num >> 63)) as u64
+}
+
+/// Maps an unsigned integer back to the signed one it was zigzagged from.
+pub(crate) fn unzigzag(num: u64) -> i64 {
+    (num >> 1) as i64 ^ -((num & 1) as i64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,5 +118,53 @@ mod tests {
         let nums = &[true, false, false, false, true, true, false, true, true];
         assert_eq!(bits_to_numeric::<u32>(nums), Ok(0b100011011));
         assert!(bits_to_numeric::<u8>(nums).is_err());
+        assert_eq!(bits_to_numeric::<u8>(&[true; 8]), Ok(u8::MAX));
+    }
+
+    #[test]
+    fn test_write_fixed_bits() {
+        let mut bits = vec![];
+        write_fixed_bits(&0b101_u8, 5, &mut bits);
+        assert_eq!(bits, vec![false, false, true, false, true]);
+        write_fixed_bits(&u64::MAX, 0, &mut bits);
+        assert_eq!(bits.len(), 5);
+    }
+
+    #[test]
+    fn test_minimal_binary() {
+        // The numbers less than 5 are written as 00, 01, 10, 110 and 111.
+        let mut bits = vec![];
+        for num in 0..5 {
+            write_minimal_binary(num, 5, &mut bits);
+        }
+        let codes = [0b00_u8, 0b01, 0b10, 0b110, 0b111];
+        let mut expected = vec![];
+        for (i, code) in codes.into_iter().enumerate() {
+            write_fixed_bits(&code, 2 + u32::from(i >= 3), &mut expected);
+        }
+        assert_eq!(bits, expected);
+
+        let mut pos = 0;
+        for num in 0..5 {
+            let (decoded, len) = read_minimal_binary(&bits[pos..], 5).unwrap();
+            assert_eq!(decoded, num);
+            pos += len;
+        }
+        assert_eq!(read_minimal_binary(&[true, true], 5), None);
+        assert_eq!(read_minimal_binary(&[], 1), Some((0, 0)));
+
+        let size = (1 << 70) - (1 << 63);
+        let mut bits = vec![];
+        write_minimal_binary(size - 1, size, &mut bits);
+        assert_eq!(bits.len(), 70);
+        assert_eq!(read_minimal_binary(&bits, size), Some((size - 1, 70)));
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for (num, zz) in [(0, 0), (-1, 1), (1, 2), (-2, 3), (i64::MIN, u64::MAX)] {
+            assert_eq!(zigzag(num), zz);
+            assert_eq!(unzigzag(zz), num);
+        }
     }
 }
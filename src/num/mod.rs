@@ -1,4 +1,5 @@
 pub mod convert;
+mod pack;
 
 use std::fmt::Debug;
 use std::ops::{
@@ -57,6 +58,12 @@ pub trait Numeric:
     /// Tries to cast self to usize.
     fn to_usize(self) -> Option<usize>;
 
+    /// Casts self to u64, which can hold every supported type.
+    fn to_u64(self) -> u64;
+
+    /// Tries to cast a u64 to `Self`.
+    fn from_u64(num: u64) -> Option<Self>;
+
     /// Counts the number of leading zeros
     fn leading_zeros(self) -> u32;
 }
@@ -87,6 +94,16 @@ macro_rules! define_numeric {
                 }
             }
 
+            #[inline(always)]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            #[inline(always)]
+            fn from_u64(num: u64) -> Option<Self> {
+                <$t>::try_from(num).ok()
+            }
+
             #[inline(always)]
             fn leading_zeros(self) -> u32 {
                 <$t>::leading_zeros(self)
@@ -100,3 +117,71 @@ define_numeric!(u16);
 define_numeric!(u32);
 define_numeric!(u64);
 define_numeric!(usize);
+
+/// This trait extends the signed integer types with the conversions to and
+/// from `i64`, so that they can be zigzagged into unsigned integers, with
+/// [`ZigZag`](crate::code::transform::ZigZag).
+pub trait Signed: Sized + Copy + Debug {
+    /// Casts self to i64, which can hold every supported type.
+    fn to_i64(self) -> i64;
+
+    /// Tries to cast an i64 to `Self`.
+    fn from_i64(num: i64) -> Option<Self>;
+}
+
+macro_rules! define_signed {
+    ($t:ty) => {
+        impl Signed for $t {
+            #[inline(always)]
+            fn to_i64(self) -> i64 {
+                self as i64
+            }
+
+            #[inline(always)]
+            fn from_i64(num: i64) -> Option<Self> {
+                <$t>::try_from(num).ok()
+            }
+        }
+    };
+}
+
+define_signed!(i8);
+define_signed!(i16);
+define_signed!(i32);
+define_signed!(i64);
+define_signed!(isize);
+
+/// This trait extends the floating-point types with the conversions to and
+/// from their bits, so that consecutive values can be XORed, with
+/// [`GorillaEncoder`](crate::code::timeseries::xor_float::GorillaEncoder).
+pub trait Float: Sized + Copy + Debug {
+    /// The number of bits of the type.
+    const BITS: u32;
+
+    /// Returns the bits of self, in the lowest bits of a u64.
+    fn to_u64_bits(self) -> u64;
+
+    /// Creates a number from the lowest bits of a u64.
+    fn from_u64_bits(bits: u64) -> Self;
+}
+
+macro_rules! define_float {
+    ($t:ty, $bits:ty) => {
+        impl Float for $t {
+            const BITS: u32 = <$bits>::BITS;
+
+            #[inline(always)]
+            fn to_u64_bits(self) -> u64 {
+                u64::from(self.to_bits())
+            }
+
+            #[inline(always)]
+            fn from_u64_bits(bits: u64) -> Self {
+                <$t>::from_bits(bits as $bits)
+            }
+        }
+    };
+}
+
+define_float!(f32, u32);
+define_float!(f64, u64);
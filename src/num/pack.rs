@@ -0,0 +1,158 @@
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use std::sync::OnceLock;
+
+/// The mask of the lowest bit of each byte of a word.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+const BYTE_LSBS: u64 = 0x0101_0101_0101_0101;
+
+/// The kernels that pack and unpack bits on a CPU.
+#[derive(Clone, Copy)]
+struct Kernels {
+    pack: fn(&[bool]) -> u64,
+    unpack: fn(u64, u32, &mut Vec<bool>),
+}
+
+/// The kernels that every CPU supports.
+const SCALAR: Kernels = Kernels {
+    pack: pack_bits_scalar,
+    unpack: unpack_bits_scalar,
+};
+
+/// Returns the fastest kernels that the CPU supports, which are detected
+/// only the first time bits are packed or unpacked.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn kernels() -> Kernels {
+    static KERNELS: OnceLock<Kernels> = OnceLock::new();
+    *KERNELS.get_or_init(|| match std::is_x86_feature_detected!("bmi2") {
+        true => Kernels {
+            pack: pack_bits_detected_bmi2,
+            unpack: unpack_bits_detected_bmi2,
+        },
+        false => SCALAR,
+    })
+}
+
+/// Returns the kernels that every CPU supports.
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn kernels() -> Kernels {
+    SCALAR
+}
+
+/// Packs up to 64 bits, starting from the most significant one, into the
+/// lowest bits of an integer, with the fastest available kernel.
+pub(crate) fn pack_bits(bits: &[bool]) -> u64 {
+    debug_assert!(bits.len() <= 64);
+    (kernels().pack)(bits)
+}
+
+/// Appends the lowest `width` bits of an integer, starting from the most
+/// significant one, to a buffer of bits, with the fastest available kernel.
+pub(crate) fn unpack_bits(num: u64, width: u32, buffer: &mut Vec<bool>) {
+    debug_assert!(width <= u64::BITS);
+    (kernels().unpack)(num, width, buffer)
+}
+
+/// Packs the bits one at a time.
+fn pack_bits_scalar(bits: &[bool]) -> u64 {
+    bits.iter().fold(0, |num, &bit| (num << 1) | u64::from(bit))
+}
+
+/// Unpacks the bits one at a time.
+fn unpack_bits_scalar(num: u64, width: u32, buffer: &mut Vec<bool>) {
+    buffer.extend((0..width).rev().map(|i| (num >> i) & 1 == 1));
+}
+
+/// Packs the bits with [`pack_bits_bmi2`], once [`kernels`] has detected
+/// BMI2.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn pack_bits_detected_bmi2(bits: &[bool]) -> u64 {
+    // SAFETY: The kernel is only selected if the CPU supports BMI2.
+    unsafe { pack_bits_bmi2(bits) }
+}
+
+/// Unpacks the bits with [`unpack_bits_bmi2`], once [`kernels`] has detected
+/// BMI2.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn unpack_bits_detected_bmi2(num: u64, width: u32, buffer: &mut Vec<bool>) {
+    // SAFETY: The kernel is only selected if the CPU supports BMI2.
+    unsafe { unpack_bits_bmi2(num, width, buffer) }
+}
+
+/// Packs the bits eight at a time, gathering the lowest bit of each of the
+/// bytes that hold them with PEXT.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "bmi2")]
+unsafe fn pack_bits_bmi2(bits: &[bool]) -> u64 {
+    use std::arch::x86_64::_pext_u64;
+
+    let (head, chunks) = bits.split_at(bits.len() % 8);
+    let mut num = pack_bits_scalar(head);
+    for chunk in chunks.chunks_exact(8) {
+        let bytes: [u8; 8] = std::array::from_fn(|i| u8::from(chunk[i]));
+        num = (num << 8) | _pext_u64(u64::from_be_bytes(bytes), BYTE_LSBS);
+    }
+    num
+}
+
+/// Unpacks the bits eight at a time, scattering each byte of the integer to
+/// the lowest bits of the bytes of a word with PDEP.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "bmi2")]
+unsafe fn unpack_bits_bmi2(num: u64, width: u32, buffer: &mut Vec<bool>) {
+    use std::arch::x86_64::_pdep_u64;
+
+    let n_bytes = width / 8;
+    if !width.is_multiple_of(8) {
+        unpack_bits_scalar(num >> (8 * n_bytes), width % 8, buffer);
+    }
+    for i in (0..n_bytes).rev() {
+        let bytes = _pdep_u64((num >> (8 * i)) & 0xFF, BYTE_LSBS).to_be_bytes();
+        buffer.extend(bytes.map(|byte| byte != 0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generates integers with all the widths up to 64 bits.
+    fn nums() -> impl Iterator<Item = (u64, u32)> {
+        (0..=64).flat_map(|width| {
+            let mask = u64::MAX.checked_shr(64 - width).unwrap_or(0);
+            (0..20_u64).map(move |i| (i.wrapping_mul(0x9E3779B97F4A7C15) & mask, width))
+        })
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        for (num, width) in nums() {
+            let mut bits = vec![];
+            unpack_bits(num, width, &mut bits);
+            assert_eq!(bits.len(), width as usize);
+            assert_eq!(pack_bits(&bits), num);
+        }
+        let mut bits = vec![true];
+        unpack_bits(0b1011, 4, &mut bits);
+        assert_eq!(bits, vec![true, true, false, true, true]);
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn test_bmi2_matches_scalar() {
+        if !std::is_x86_feature_detected!("bmi2") {
+            return;
+        }
+        for (num, width) in nums() {
+            let (mut scalar, mut bmi2) = (vec![], vec![]);
+            unpack_bits_scalar(num, width, &mut scalar);
+            // SAFETY: The CPU supports BMI2.
+            unsafe { unpack_bits_bmi2(num, width, &mut bmi2) };
+            assert_eq!(scalar, bmi2);
+            // SAFETY: The CPU supports BMI2.
+            assert_eq!(
+                unsafe { pack_bits_bmi2(&scalar) },
+                pack_bits_scalar(&scalar)
+            );
+        }
+    }
+}
@@ -0,0 +1,934 @@
+//! Posting lists of document IDs, and iterators over them for
+//! document-at-a-time query evaluation, and impact-ordered posting lists for
+//! score-at-a-time evaluation.
+
+use std::io::Cursor;
+
+use crate::code::global::vb::{read_vb_bytes, write_vb_bytes};
+use crate::error::{InvalidCodeError, InvalidImpactsError, UnsortedPostingsError};
+use crate::{Decoder, Encoder, GammaDecoder, GammaEncoder};
+
+/// The number of document IDs in a block of a posting list.
+const BLOCK_LEN: usize = 128;
+
+/// The largest quantized score of an impact-ordered posting list.
+const MAX_IMPACT: u8 = u8::MAX;
+
+/// Encodes strictly increasing document IDs as their gaps in Elias Gamma
+/// Encoding. The first gap is from the previous ID, or the first ID
+/// incremented by one if there is none.
+fn encode_gaps(ids: &[u32], mut prev: Option<u32>) -> Vec<u8> {
+    let gaps: Vec<u64> = ids
+        .iter()
+        .map(|id| {
+            let gap = match prev {
+                None => u64::from(*id) + 1,
+                Some(prev) => u64::from(id - prev),
+            };
+            prev = Some(*id);
+            gap
+        })
+        .collect();
+    let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+    ge.encode(&gaps)
+        .expect("Writing to a vector does not fail.");
+    ge.finalize()
+        .expect("Writing to a vector does not fail.")
+        .into_inner()
+}
+
+/// Decodes the document IDs encoded with [`encode_gaps`].
+fn decode_gaps(data: &[u8], prev: Option<u32>) -> Vec<u32> {
+    try_decode_gaps(data, prev).expect("The gaps of a block are valid Elias Gamma codes.")
+}
+
+/// Decodes the document IDs encoded with [`encode_gaps`], or returns `None`
+/// if the gaps are not Elias Gamma codes, or the IDs do not fit in 32 bits.
+fn try_decode_gaps(data: &[u8], mut prev: Option<u32>) -> Option<Vec<u32>> {
+    let gd = GammaDecoder::new(Cursor::new(data));
+    let gaps = gd.decode::<u64>().ok()?;
+    gaps.iter()
+        .map(|gap| {
+            let id = match prev {
+                None => u32::try_from(gap - 1).ok()?,
+                Some(prev) => prev.checked_add(u32::try_from(*gap).ok()?)?,
+            };
+            prev = Some(id);
+            Some(id)
+        })
+        .collect()
+}
+
+/// An iterator over the document IDs of a posting list, in increasing order,
+/// for document-at-a-time evaluation.
+///
+/// The iterator is positioned on its first document when it is created, and
+/// moves forward with [`PostingIterator::next`] and
+/// [`PostingIterator::next_geq`].
+pub trait PostingIterator {
+    /// Returns the current document ID, or `None` if the iterator is
+    /// exhausted.
+    fn doc_id(&self) -> Option<u32>;
+
+    /// Moves to the next document, returning its ID, or `None` if there are
+    /// no more documents.
+    fn next(&mut self) -> Option<u32>;
+
+    /// Moves to the first document whose ID is greater than or equal to the
+    /// target, returning its ID, or `None` if there is no such document.
+    ///
+    /// The iterator never moves backwards: if the current document is already
+    /// greater than or equal to the target, it stays on it.
+    fn next_geq(&mut self, target: u32) -> Option<u32>;
+}
+
+/// A block of a posting list.
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    /// The last document ID of the block.
+    last: u32,
+    /// The gaps of the document IDs, encoded with Elias Gamma Encoding.
+    data: Vec<u8>,
+    /// The largest impact of the documents of the block.
+    max_impact: u32,
+    /// The impacts of the documents, incremented by one and encoded with
+    /// Elias Gamma Encoding, or nothing if the list has no impacts.
+    impacts: Vec<u8>,
+}
+
+/// A posting list of strictly increasing document IDs, split in blocks of
+/// 128 IDs.
+///
+/// The gaps between the IDs of each block are encoded with Elias Gamma
+/// Encoding, while the last ID of each block is kept uncompressed, so that
+/// [`PostingIterator::next_geq`] skips the blocks that end before its target
+/// without decoding them. A list built with
+/// [`BlockPostingList::with_impacts`] also keeps the impact of each document,
+/// like a term frequency or a quantized score, and the largest impact of
+/// each block uncompressed, for dynamic pruning with Block-Max WAND.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::postings::{BlockPostingList, PostingIterator};
+///
+/// let a = BlockPostingList::new(&[1, 4, 9, 16, 25, 36]).unwrap();
+/// let b = BlockPostingList::new(&[2, 4, 6, 8, 10, 12, 14, 16, 18]).unwrap();
+///
+/// // Intersect the lists, document at a time.
+/// let (mut a, mut b) = (a.iter(), b.iter());
+/// let mut common = vec![];
+/// while let (Some(x), Some(y)) = (a.doc_id(), b.doc_id()) {
+///     if x == y {
+///         common.push(x);
+///         a.next();
+///     } else if x < y {
+///         a.next_geq(y);
+///     } else {
+///         b.next_geq(x);
+///     }
+/// }
+/// assert_eq!(common, vec![4, 16]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockPostingList {
+    blocks: Vec<Block>,
+    len: usize,
+}
+
+impl BlockPostingList {
+    /// Creates a posting list from strictly increasing document IDs.
+    pub fn new(doc_ids: &[u32]) -> Result<Self, UnsortedPostingsError> {
+        Self::build(doc_ids, None)
+    }
+
+    /// Creates a posting list from strictly increasing document IDs, with
+    /// the impact of each document.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not as many impacts as document IDs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::postings::{BlockPostingList, PostingIterator};
+    ///
+    /// let ids: Vec<u32> = (0..300).collect();
+    /// let impacts: Vec<u32> = ids.iter().map(|id| if *id == 200 { 9 } else { 1 }).collect();
+    /// let list = BlockPostingList::with_impacts(&ids, &impacts).unwrap();
+    ///
+    /// let mut iter = list.iter();
+    /// assert_eq!(iter.block_max(), Some(1));
+    /// // Skip the blocks that cannot hold a document with an impact of 5.
+    /// assert_eq!(iter.skip_to_block_with_max_ge(5), Some(128));
+    /// assert_eq!(iter.block_max(), Some(9));
+    /// assert_eq!(iter.next_geq(200), Some(200));
+    /// assert_eq!(iter.impact(), Some(9));
+    /// ```
+    pub fn with_impacts(doc_ids: &[u32], impacts: &[u32]) -> Result<Self, UnsortedPostingsError> {
+        assert_eq!(
+            doc_ids.len(),
+            impacts.len(),
+            "Every document ID must have an impact."
+        );
+        Self::build(doc_ids, Some(impacts))
+    }
+
+    fn build(doc_ids: &[u32], impacts: Option<&[u32]>) -> Result<Self, UnsortedPostingsError> {
+        if doc_ids.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(UnsortedPostingsError);
+        }
+
+        let mut blocks = Vec::with_capacity(doc_ids.len().div_ceil(BLOCK_LEN));
+        let mut prev = None;
+        for (i, ids) in doc_ids.chunks(BLOCK_LEN).enumerate() {
+            let data = encode_gaps(ids, prev);
+            let last = *ids.last().expect("Chunks are not empty.");
+            let (max_impact, impacts) = match impacts {
+                Some(impacts) => {
+                    let impacts = &impacts[i * BLOCK_LEN..i * BLOCK_LEN + ids.len()];
+                    let max = *impacts.iter().max().expect("Chunks are not empty.");
+                    (max, encode_impacts(impacts))
+                }
+                None => (0, vec![]),
+            };
+            blocks.push(Block {
+                last,
+                data,
+                max_impact,
+                impacts,
+            });
+            prev = Some(last);
+        }
+        Ok(BlockPostingList {
+            blocks,
+            len: doc_ids.len(),
+        })
+    }
+
+    /// Returns the number of document IDs in the list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the list has no document IDs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends the document IDs of another list, each incremented by an
+    /// offset, like the lists of a term in two segments that are merged.
+    ///
+    /// The blocks of the other list are kept in their encoded form, but for
+    /// its first block, whose first gap changes, so the list may have blocks
+    /// of fewer than 128 IDs in the middle. If only one of the lists has
+    /// impacts, the documents of the other one get impacts of 0.
+    ///
+    /// Returns an error if the first incremented ID is not greater than the
+    /// last ID of the list, or if the last incremented ID overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::postings::{BlockPostingList, PostingIterator};
+    ///
+    /// let mut list = BlockPostingList::new(&[1, 4, 9]).unwrap();
+    /// let other = BlockPostingList::new(&[0, 2]).unwrap();
+    /// list.append_shifted(&other, 10).unwrap();
+    /// assert_eq!(list.doc_ids(), vec![1, 4, 9, 10, 12]);
+    /// assert!(list.append_shifted(&other, 12).is_err());
+    /// ```
+    pub fn append_shifted(
+        &mut self,
+        other: &BlockPostingList,
+        offset: u32,
+    ) -> Result<(), UnsortedPostingsError> {
+        let Some(other_last) = other.last() else {
+            return Ok(());
+        };
+        other_last
+            .checked_add(offset)
+            .ok_or(UnsortedPostingsError)?;
+        let mut ids = other.decode_block(0);
+        if self.last().is_some_and(|last| ids[0] + offset <= last) {
+            return Err(UnsortedPostingsError);
+        }
+
+        let filled;
+        let other = match (self.has_impacts(), other.has_impacts()) {
+            (true, false) => {
+                filled = other.clone().with_zero_impacts();
+                &filled
+            }
+            (false, true) if !self.is_empty() => {
+                *self = std::mem::take(self).with_zero_impacts();
+                other
+            }
+            _ => other,
+        };
+
+        let prev = self.last();
+        for id in &mut ids {
+            *id += offset;
+        }
+        let mut blocks = other.blocks.clone();
+        blocks[0].data = encode_gaps(&ids, prev);
+        for block in &mut blocks {
+            block.last += offset;
+        }
+        self.blocks.extend(blocks);
+        self.len += other.len;
+        Ok(())
+    }
+
+    /// Returns whether the list has the impacts of its documents.
+    fn has_impacts(&self) -> bool {
+        self.blocks
+            .first()
+            .is_some_and(|block| !block.impacts.is_empty())
+    }
+
+    /// Returns the list with impacts of 0 for the documents of its blocks
+    /// without impacts.
+    fn with_zero_impacts(mut self) -> Self {
+        for idx in 0..self.blocks.len() {
+            if self.blocks[idx].impacts.is_empty() {
+                let len = self.decode_block(idx).len();
+                self.blocks[idx].impacts = encode_impacts(&vec![0; len]);
+            }
+        }
+        self
+    }
+
+    /// Decodes all the document IDs of the list, in increasing order.
+    pub fn doc_ids(&self) -> Vec<u32> {
+        (0..self.blocks.len())
+            .flat_map(|idx| self.decode_block(idx))
+            .collect()
+    }
+
+    /// Decodes all the impacts of the list, in the order of the document IDs,
+    /// or returns `None` if the list has no impacts.
+    pub fn impacts(&self) -> Option<Vec<u32>> {
+        self.has_impacts().then(|| {
+            self.blocks
+                .iter()
+                .flat_map(|block| decode_impacts(&block.impacts))
+                .collect()
+        })
+    }
+
+    /// Returns the last document ID of the list, or `None` if it is empty.
+    pub(crate) fn last(&self) -> Option<u32> {
+        self.blocks.last().map(|block| block.last)
+    }
+
+    /// Returns an iterator over the document IDs, positioned on the first one.
+    pub fn iter(&self) -> BlockPostingIter<'_> {
+        let mut iter = BlockPostingIter {
+            list: self,
+            block: 0,
+            ids: vec![],
+            impacts: vec![],
+            pos: 0,
+        };
+        iter.load_block(0);
+        iter
+    }
+
+    /// Serializes the list to bytes: its number of document IDs and of
+    /// blocks, followed by the blocks. Each block is made of its last ID, its
+    /// largest impact and the length of its encoded gaps, followed by them,
+    /// and the length of its encoded impacts, followed by them. The numbers
+    /// are in Variable Byte Encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use idencode::postings::BlockPostingList;
+    ///
+    /// let list = BlockPostingList::with_impacts(&[3, 9, 200], &[1, 4, 2]).unwrap();
+    /// let bytes = list.to_bytes();
+    /// assert_eq!(BlockPostingList::from_bytes(&bytes), Ok(list));
+    /// assert!(BlockPostingList::from_bytes(&bytes[1..]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_vb_bytes(self.len as u64, &mut bytes);
+        write_vb_bytes(self.blocks.len() as u64, &mut bytes);
+        for block in &self.blocks {
+            write_vb_bytes(block.last, &mut bytes);
+            write_vb_bytes(block.max_impact, &mut bytes);
+            write_vb_bytes(block.data.len() as u64, &mut bytes);
+            bytes.extend_from_slice(&block.data);
+            write_vb_bytes(block.impacts.len() as u64, &mut bytes);
+            bytes.extend_from_slice(&block.impacts);
+        }
+        bytes
+    }
+
+    /// Deserializes a posting list from the bytes written by
+    /// [`BlockPostingList::to_bytes`].
+    ///
+    /// Every block is decoded once, so that the iterators over the list
+    /// never meet an invalid block. Returns an error if the bytes are not a
+    /// serialized list.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::PostingListCodeError;
+        let mut pos = 0;
+        let len = read_num(bytes, &mut pos)?;
+        let num_blocks = read_num(bytes, &mut pos)?;
+        if num_blocks > len || len > bytes.len() as u64 * 8 {
+            return Err(err());
+        }
+
+        let mut blocks: Vec<Block> = Vec::with_capacity(num_blocks as usize);
+        let mut total = 0;
+        for _ in 0..num_blocks {
+            let last = u32::try_from(read_num(bytes, &mut pos)?).map_err(|_| err())?;
+            let max_impact = u32::try_from(read_num(bytes, &mut pos)?).map_err(|_| err())?;
+            let data = read_slice(bytes, &mut pos)?.to_vec();
+            let impacts = read_slice(bytes, &mut pos)?.to_vec();
+
+            let prev = blocks.last().map(|block| block.last);
+            let ids = try_decode_gaps(&data, prev).ok_or_else(err)?;
+            if ids.last() != Some(&last) {
+                return Err(err());
+            }
+            // Either every block has impacts, or none of them has.
+            let has_impacts = blocks
+                .first()
+                .map_or(!impacts.is_empty(), |b| !b.impacts.is_empty());
+            match has_impacts {
+                true => {
+                    let decoded = try_decode_impacts(&impacts).ok_or_else(err)?;
+                    if decoded.len() != ids.len() || decoded.iter().max() != Some(&max_impact) {
+                        return Err(err());
+                    }
+                }
+                false if !impacts.is_empty() || max_impact != 0 => return Err(err()),
+                false => {}
+            }
+            total += ids.len() as u64;
+            blocks.push(Block {
+                last,
+                data,
+                max_impact,
+                impacts,
+            });
+        }
+        if total != len || pos != bytes.len() {
+            return Err(err());
+        }
+        Ok(BlockPostingList {
+            blocks,
+            len: len as usize,
+        })
+    }
+
+    /// Decodes the document IDs of a block.
+    fn decode_block(&self, idx: usize) -> Vec<u32> {
+        let prev = match idx {
+            0 => None,
+            idx => Some(self.blocks[idx - 1].last),
+        };
+        decode_gaps(&self.blocks[idx].data, prev)
+    }
+}
+
+/// Decodes the number in Variable Byte Encoding at an offset of a serialized
+/// posting list, and moves the offset past it.
+fn read_num(bytes: &[u8], pos: &mut usize) -> Result<u64, InvalidCodeError> {
+    read_vb_bytes(bytes, pos).map_err(|_| InvalidCodeError::PostingListCodeError)
+}
+
+/// Returns the bytes at an offset of a serialized posting list, preceded by
+/// their number, and moves the offset past them.
+fn read_slice<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a [u8], InvalidCodeError> {
+    let len = usize::try_from(read_num(bytes, pos)?)
+        .map_err(|_| InvalidCodeError::PostingListCodeError)?;
+    let slice = bytes
+        .get(*pos..pos.saturating_add(len))
+        .ok_or(InvalidCodeError::PostingListCodeError)?;
+    *pos += len;
+    Ok(slice)
+}
+
+/// Encodes impacts, incremented by one, in Elias Gamma Encoding.
+fn encode_impacts(impacts: &[u32]) -> Vec<u8> {
+    let impacts: Vec<u64> = impacts
+        .iter()
+        .map(|impact| u64::from(*impact) + 1)
+        .collect();
+    let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+    ge.encode(&impacts)
+        .expect("Writing to a vector does not fail.");
+    ge.finalize()
+        .expect("Writing to a vector does not fail.")
+        .into_inner()
+}
+
+/// Decodes the impacts encoded with [`encode_impacts`].
+fn decode_impacts(data: &[u8]) -> Vec<u32> {
+    try_decode_impacts(data).expect("The impacts of a block are valid Elias Gamma codes.")
+}
+
+/// Decodes the impacts encoded with [`encode_impacts`], or returns `None` if
+/// they are not Elias Gamma codes, or do not fit in 32 bits.
+fn try_decode_impacts(data: &[u8]) -> Option<Vec<u32>> {
+    let gd = GammaDecoder::new(Cursor::new(data));
+    gd.decode::<u64>()
+        .ok()?
+        .into_iter()
+        .map(|impact| u32::try_from(impact - 1).ok())
+        .collect()
+}
+
+/// An iterator over the document IDs of a [`BlockPostingList`], that decodes
+/// one block at a time.
+#[derive(Debug, Clone)]
+pub struct BlockPostingIter<'a> {
+    list: &'a BlockPostingList,
+    block: usize,
+    ids: Vec<u32>,
+    impacts: Vec<u32>,
+    pos: usize,
+}
+
+impl BlockPostingIter<'_> {
+    /// Decodes a block and positions the iterator on its first document, or
+    /// exhausts the iterator if there is no such block.
+    fn load_block(&mut self, block: usize) {
+        self.block = block;
+        self.pos = 0;
+        (self.ids, self.impacts) = match self.list.blocks.get(block) {
+            Some(data) => (self.list.decode_block(block), decode_impacts(&data.impacts)),
+            None => (vec![], vec![]),
+        };
+    }
+
+    /// Returns the impact of the current document, which is 0 if the list
+    /// has no impacts, or `None` if the iterator is exhausted.
+    pub fn impact(&self) -> Option<u32> {
+        self.doc_id()?;
+        Some(self.impacts.get(self.pos).copied().unwrap_or(0))
+    }
+
+    /// Returns the largest impact of the documents of the current block,
+    /// which bounds the impact of every document up to the last one of the
+    /// block, or `None` if the iterator is exhausted.
+    pub fn block_max(&self) -> Option<u32> {
+        self.doc_id()?;
+        Some(self.list.blocks[self.block].max_impact)
+    }
+
+    /// Moves to the first block, from the current one, whose largest impact
+    /// is greater than or equal to the target, without decoding the blocks
+    /// in between, returning the current document ID, or `None` if there is
+    /// no such block.
+    ///
+    /// If the current block qualifies, the iterator stays on its current
+    /// document. Otherwise, it moves to the first document of the block.
+    pub fn skip_to_block_with_max_ge(&mut self, target: u32) -> Option<u32> {
+        self.doc_id()?;
+        let blocks = &self.list.blocks;
+        if blocks[self.block].max_impact < target {
+            let skip = blocks[self.block..]
+                .iter()
+                .position(|block| block.max_impact >= target)
+                .unwrap_or(blocks.len() - self.block);
+            self.load_block(self.block + skip);
+        }
+        self.doc_id()
+    }
+}
+
+impl PostingIterator for BlockPostingIter<'_> {
+    fn doc_id(&self) -> Option<u32> {
+        self.ids.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u32> {
+        if self.pos < self.ids.len() {
+            self.pos += 1;
+        }
+        if self.pos == self.ids.len() && self.block < self.list.blocks.len() {
+            self.load_block(self.block + 1);
+        }
+        self.doc_id()
+    }
+
+    fn next_geq(&mut self, target: u32) -> Option<u32> {
+        let blocks = &self.list.blocks;
+        if self.block >= blocks.len() {
+            return None;
+        }
+        if blocks[self.block].last < target {
+            let skip = blocks[self.block..].partition_point(|b| b.last < target);
+            self.load_block(self.block + skip);
+        }
+        self.pos += self.ids[self.pos.min(self.ids.len())..].partition_point(|id| *id < target);
+        self.doc_id()
+    }
+}
+
+/// A block of an impact-ordered posting list, whose document IDs share a
+/// quantized score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactBlock {
+    impact: u8,
+    max_score: f32,
+    len: usize,
+    /// The gaps of the document IDs, encoded with Elias Gamma Encoding.
+    data: Vec<u8>,
+}
+
+impl ImpactBlock {
+    /// Returns the quantized score of the documents of the block.
+    #[inline]
+    pub fn impact(&self) -> u8 {
+        self.impact
+    }
+
+    /// Returns the largest score of the documents of the block, before it
+    /// was quantized.
+    #[inline]
+    pub fn max_score(&self) -> f32 {
+        self.max_score
+    }
+
+    /// Returns the number of document IDs in the block.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the block has no document IDs, which never holds for
+    /// the blocks of a list.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decodes the document IDs of the block, in increasing order.
+    pub fn doc_ids(&self) -> Vec<u32> {
+        decode_gaps(&self.data, None)
+    }
+}
+
+/// A posting list of document IDs with scores, ordered by impact.
+///
+/// Each score is quantized to an impact from 0 to 255, in proportion to the
+/// largest score of the list. The documents are grouped by impact, in
+/// decreasing order, and the documents of each impact are split in blocks of
+/// 128 IDs, in increasing order. The gaps between the IDs of each block are
+/// encoded with Elias Gamma Encoding, and its header keeps its impact and
+/// the largest score of its documents, so that score-at-a-time evaluation
+/// processes the blocks with the highest scores first, and stops as soon as
+/// the remaining blocks cannot change the top-k documents.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::postings::ImpactPostingList;
+///
+/// let list = ImpactPostingList::new(&[(7, 0.5), (3, 2.0), (12, 0.5), (5, 1.9)]).unwrap();
+/// let blocks: Vec<_> = list
+///     .blocks()
+///     .iter()
+///     .map(|block| (block.impact(), block.max_score(), block.doc_ids()))
+///     .collect();
+/// assert_eq!(blocks, vec![(255, 2.0, vec![3]), (242, 1.9, vec![5]), (64, 0.5, vec![7, 12])]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImpactPostingList {
+    blocks: Vec<ImpactBlock>,
+    max_score: f32,
+    len: usize,
+}
+
+impl ImpactPostingList {
+    /// Creates an impact-ordered posting list from document IDs with their
+    /// scores, in any order.
+    ///
+    /// Returns an error if a document ID repeats, or if a score is negative
+    /// or not finite.
+    pub fn new(postings: &[(u32, f32)]) -> Result<Self, InvalidImpactsError> {
+        if postings
+            .iter()
+            .any(|(_, score)| !score.is_finite() || *score < 0.0)
+        {
+            return Err(InvalidImpactsError);
+        }
+        let max_score = postings.iter().map(|(_, score)| *score).fold(0.0, f32::max);
+
+        let mut ids: Vec<u32> = postings.iter().map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        if ids.windows(2).any(|w| w[0] == w[1]) {
+            return Err(InvalidImpactsError);
+        }
+
+        let mut impacts: Vec<(u8, u32, f32)> = postings
+            .iter()
+            .map(|(id, score)| (quantize(*score, max_score), *id, *score))
+            .collect();
+        impacts.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut blocks = vec![];
+        for group in impacts.chunk_by(|a, b| a.0 == b.0) {
+            for chunk in group.chunks(BLOCK_LEN) {
+                let ids: Vec<u32> = chunk.iter().map(|(_, id, _)| *id).collect();
+                blocks.push(ImpactBlock {
+                    impact: chunk[0].0,
+                    max_score: chunk.iter().map(|(_, _, score)| *score).fold(0.0, f32::max),
+                    len: chunk.len(),
+                    data: encode_gaps(&ids, None),
+                });
+            }
+        }
+        Ok(ImpactPostingList {
+            blocks,
+            max_score,
+            len: postings.len(),
+        })
+    }
+
+    /// Returns the blocks of the list, in decreasing order of impact.
+    #[inline]
+    pub fn blocks(&self) -> &[ImpactBlock] {
+        &self.blocks
+    }
+
+    /// Returns the largest score of the list, which the impact 255 stands
+    /// for.
+    #[inline]
+    pub fn max_score(&self) -> f32 {
+        self.max_score
+    }
+
+    /// Returns the number of document IDs in the list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the list has no document IDs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Quantizes a score to an impact from 0 to 255, in proportion to the largest
+/// score, rounding to the closest impact.
+fn quantize(score: f32, max_score: f32) -> u8 {
+    if max_score == 0.0 {
+        return 0;
+    }
+    (score / max_score * f32::from(MAX_IMPACT)).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert!(BlockPostingList::new(&[1, 3, 3]).is_err());
+        assert!(BlockPostingList::new(&[3, 1]).is_err());
+
+        let ids: Vec<u32> = (0..1000).map(|i| i * 3).collect();
+        let list = BlockPostingList::new(&ids).unwrap();
+        assert_eq!(list.len(), 1000);
+        assert_eq!(list.blocks.len(), 8);
+        assert_eq!(list.decode_block(1), ids[128..256]);
+    }
+
+    #[test]
+    fn test_next() {
+        let ids: Vec<u32> = (0..300).map(|i| i * i).chain([u32::MAX]).collect();
+        let list = BlockPostingList::new(&ids).unwrap();
+        let mut iter = list.iter();
+        let mut decoded = vec![];
+        while let Some(id) = iter.doc_id() {
+            decoded.push(id);
+            iter.next();
+        }
+        assert_eq!(decoded, ids);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_next_geq() {
+        let ids: Vec<u32> = (0..1000).map(|i| i * 10).collect();
+        let list = BlockPostingList::new(&ids).unwrap();
+        let mut iter = list.iter();
+        assert_eq!(iter.next_geq(0), Some(0));
+        assert_eq!(iter.next_geq(15), Some(20));
+        assert_eq!(iter.next_geq(15), Some(20));
+        assert_eq!(iter.next_geq(5000), Some(5000));
+        assert_eq!(iter.block, 3);
+        assert_eq!(iter.next(), Some(5010));
+        assert_eq!(iter.next_geq(9990), Some(9990));
+        assert_eq!(iter.next_geq(9991), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let list = BlockPostingList::new(&[]).unwrap();
+        assert!(list.is_empty());
+        let mut iter = list.iter();
+        assert_eq!(iter.doc_id(), None);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_geq(0), None);
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let ids: Vec<u32> = (0..1000).map(|i| i * i).collect();
+        let impacts: Vec<u32> = (0..1000).map(|i| i % 7).collect();
+        for list in [
+            BlockPostingList::new(&ids).unwrap(),
+            BlockPostingList::with_impacts(&ids, &impacts).unwrap(),
+            BlockPostingList::new(&[u32::MAX]).unwrap(),
+            BlockPostingList::default(),
+        ] {
+            let bytes = list.to_bytes();
+            assert_eq!(BlockPostingList::from_bytes(&bytes), Ok(list));
+            assert!(BlockPostingList::from_bytes(&[&bytes[..], &[0]].concat()).is_err());
+        }
+
+        let err = Err(InvalidCodeError::PostingListCodeError);
+        assert_eq!(BlockPostingList::from_bytes(&[]), err);
+        // The last ID of the block does not match its gaps.
+        let mut bytes = BlockPostingList::new(&[5, 9]).unwrap().to_bytes();
+        assert_eq!(BlockPostingList::from_bytes(&bytes).unwrap().len(), 2);
+        bytes[2] += 1;
+        assert_eq!(BlockPostingList::from_bytes(&bytes), err);
+        // Only some of the blocks have impacts.
+        let mut mixed = BlockPostingList::with_impacts(&ids, &impacts).unwrap();
+        mixed.blocks[1] = BlockPostingList::new(&ids).unwrap().blocks[1].clone();
+        assert_eq!(BlockPostingList::from_bytes(&mixed.to_bytes()), err);
+    }
+
+    #[test]
+    fn test_append_shifted() {
+        let ids: Vec<u32> = (0..300).map(|i| i * 5).collect();
+        let mut list = BlockPostingList::new(&ids).unwrap();
+        let other = BlockPostingList::new(&ids[..200]).unwrap();
+        list.append_shifted(&other, 1500).unwrap();
+        assert_eq!(list.len(), 500);
+        assert_eq!(list.blocks.len(), 5);
+        // The second block of the other list is kept as it is.
+        assert_eq!(list.blocks[4].data, other.blocks[1].data);
+        let shifted = ids[..200].iter().map(|id| id + 1500);
+        let expected: Vec<u32> = ids.iter().copied().chain(shifted).collect();
+        assert_eq!(list.doc_ids(), expected);
+        assert_eq!(list.iter().next_geq(1496), Some(1500));
+
+        assert_eq!(
+            list.append_shifted(&other, 2490),
+            Err(UnsortedPostingsError)
+        );
+        assert_eq!(
+            list.append_shifted(&other, u32::MAX - 100),
+            Err(UnsortedPostingsError)
+        );
+        list.append_shifted(&BlockPostingList::default(), 0)
+            .unwrap();
+        assert_eq!(list.len(), 500);
+
+        // The documents of the list without impacts get impacts of 0.
+        let mut list = BlockPostingList::new(&ids).unwrap();
+        let with = BlockPostingList::with_impacts(&[0, 7], &[3, 9]).unwrap();
+        list.append_shifted(&with, 2000).unwrap();
+        let mut iter = list.iter();
+        assert_eq!(iter.next_geq(2000), Some(2000));
+        assert_eq!((iter.impact(), iter.block_max()), (Some(3), Some(9)));
+        let mut iter = list.iter();
+        assert_eq!((iter.impact(), iter.block_max()), (Some(0), Some(0)));
+        assert_eq!(iter.skip_to_block_with_max_ge(1), Some(2000));
+        assert_eq!(BlockPostingList::from_bytes(&list.to_bytes()), Ok(list));
+
+        let mut list = with.clone();
+        list.append_shifted(&BlockPostingList::new(&ids).unwrap(), 8)
+            .unwrap();
+        assert_eq!(list.iter().next_geq(8), Some(8));
+        assert_eq!(BlockPostingList::from_bytes(&list.to_bytes()), Ok(list));
+    }
+
+    #[test]
+    fn test_impacts() {
+        assert!(ImpactPostingList::new(&[(1, 0.5), (1, 0.7)]).is_err());
+        assert!(ImpactPostingList::new(&[(1, 0.5), (1, 0.5)]).is_err());
+        assert!(ImpactPostingList::new(&[(1, -0.5)]).is_err());
+        assert!(ImpactPostingList::new(&[(1, f32::NAN)]).is_err());
+        assert!(ImpactPostingList::new(&[]).unwrap().is_empty());
+
+        let postings: Vec<(u32, f32)> = (0..1000_u32)
+            .map(|id| (id * 7, [1.0, 3.0, 2.0, 0.0][id as usize % 4]))
+            .collect();
+        let list = ImpactPostingList::new(&postings).unwrap();
+        assert_eq!(list.len(), 1000);
+        assert_eq!(list.max_score(), 3.0);
+
+        let impacts: Vec<u8> = list.blocks().iter().map(ImpactBlock::impact).collect();
+        assert_eq!(impacts, vec![255, 255, 170, 170, 85, 85, 0, 0]);
+        assert_eq!(list.blocks()[0].len(), 128);
+        assert_eq!(list.blocks()[1].len(), 122);
+        for block in list.blocks() {
+            let ids = block.doc_ids();
+            assert_eq!(ids.len(), block.len());
+            assert!(ids.windows(2).all(|w| w[0] < w[1]));
+            for id in ids {
+                let score = postings[id as usize / 7].1;
+                assert_eq!(quantize(score, 3.0), block.impact());
+                assert!(score <= block.max_score());
+            }
+        }
+        let total: usize = list.blocks().iter().map(ImpactBlock::len).sum();
+        assert_eq!(total, 1000);
+    }
+
+    #[test]
+    fn test_block_max() {
+        let ids: Vec<u32> = (0..1000).map(|i| i * 2).collect();
+        let impacts: Vec<u32> = (0..1000).map(|i| (i * 37) % 100).collect();
+        let list = BlockPostingList::with_impacts(&ids, &impacts).unwrap();
+        assert_eq!(list.blocks[2].max_impact, 99);
+        assert_eq!(list.decode_block(2), ids[256..384]);
+
+        let mut iter = list.iter();
+        let mut decoded = vec![];
+        while let Some(impact) = iter.impact() {
+            assert!(impact <= iter.block_max().unwrap());
+            decoded.push(impact);
+            iter.next();
+        }
+        assert_eq!(decoded, impacts);
+        assert_eq!(iter.block_max(), None);
+
+        // The impacts grow by one with every block.
+        let impacts: Vec<u32> = (0..1000).map(|i| i / 128).collect();
+        let list = BlockPostingList::with_impacts(&ids, &impacts).unwrap();
+        let mut iter = list.iter();
+        assert_eq!(iter.next_geq(10), Some(10));
+        assert_eq!(iter.skip_to_block_with_max_ge(0), Some(10));
+        assert_eq!(iter.skip_to_block_with_max_ge(3), Some(768));
+        assert_eq!(iter.impact(), Some(3));
+        assert_eq!(iter.skip_to_block_with_max_ge(3), Some(768));
+        assert_eq!(iter.skip_to_block_with_max_ge(7), Some(1792));
+        assert_eq!(iter.skip_to_block_with_max_ge(8), None);
+        assert_eq!(iter.block_max(), None);
+
+        // Lists without impacts have impacts of 0.
+        let list = BlockPostingList::new(&ids).unwrap();
+        let mut iter = list.iter();
+        assert_eq!((iter.impact(), iter.block_max()), (Some(0), Some(0)));
+        assert_eq!(iter.skip_to_block_with_max_ge(1), None);
+    }
+}
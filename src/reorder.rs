@@ -0,0 +1,343 @@
+//! Reordering of document IDs, to improve the compressibility of the gaps
+//! of posting lists.
+//!
+//! Assigning close IDs to documents that share many terms makes the gaps of
+//! posting lists smaller, and therefore their codes shorter. This module
+//! computes such permutations of document IDs, either by sorting on a key
+//! (for example, the URL of each document), or by recursive graph bisection,
+//! and remaps posting lists with them.
+
+use std::collections::HashMap;
+
+use crate::error::InvalidPermutationError;
+
+/// The size of a partition below which graph bisection stops recursing.
+const MIN_PARTITION_LEN: usize = 16;
+
+/// A permutation of document IDs, mapping each original ID to a new one.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::reorder::Permutation;
+///
+/// // The new ID 0 is assigned to the original ID 2, and so on.
+/// let perm = Permutation::from_order(vec![2, 0, 1]).unwrap();
+/// assert_eq!(perm.new_id(2), 0);
+/// assert_eq!(perm.old_id(2), 1);
+/// assert_eq!(perm.remap(&[0, 2]), vec![0, 1]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permutation {
+    new_ids: Vec<u32>,
+    old_ids: Vec<u32>,
+}
+
+impl Permutation {
+    /// Creates the identity permutation of `len` IDs.
+    pub fn identity(len: usize) -> Self {
+        let ids: Vec<u32> = (0..len as u32).collect();
+        Permutation {
+            new_ids: ids.clone(),
+            old_ids: ids,
+        }
+    }
+
+    /// Creates a permutation from the order of the original IDs, where the
+    /// `i`-th ID of the order is assigned the new ID `i`.
+    ///
+    /// Returns an error if the order is not a permutation of `0..order.len()`.
+    pub fn from_order(order: Vec<u32>) -> Result<Self, InvalidPermutationError> {
+        let mut new_ids = vec![u32::MAX; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            let slot = new_ids
+                .get_mut(old as usize)
+                .ok_or(InvalidPermutationError)?;
+            if *slot != u32::MAX {
+                return Err(InvalidPermutationError);
+            }
+            *slot = new as u32;
+        }
+        Ok(Permutation {
+            new_ids,
+            old_ids: order,
+        })
+    }
+
+    /// Returns the number of IDs of the permutation.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.new_ids.len()
+    }
+
+    /// Returns whether the permutation has no IDs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.new_ids.is_empty()
+    }
+
+    /// Returns the new ID of an original ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID is out of the range of the permutation.
+    #[inline]
+    pub fn new_id(&self, old: u32) -> u32 {
+        self.new_ids[old as usize]
+    }
+
+    /// Returns the original ID of a new ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ID is out of the range of the permutation.
+    #[inline]
+    pub fn old_id(&self, new: u32) -> u32 {
+        self.old_ids[new as usize]
+    }
+
+    /// Returns the inverse permutation, that maps the new IDs back to the
+    /// original ones.
+    pub fn inverse(&self) -> Self {
+        Permutation {
+            new_ids: self.old_ids.clone(),
+            old_ids: self.new_ids.clone(),
+        }
+    }
+
+    /// Remaps a posting list to the new IDs, sorted in increasing order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an ID is out of the range of the permutation.
+    pub fn remap(&self, list: &[u32]) -> Vec<u32> {
+        let mut remapped: Vec<u32> = list.iter().map(|id| self.new_id(*id)).collect();
+        remapped.sort_unstable();
+        remapped
+    }
+}
+
+/// Computes the permutation that orders `len` documents by a key, such as
+/// their URLs. Documents with equal keys keep their original order.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::reorder;
+///
+/// let urls = ["b.org/x", "a.org/y", "b.org/a", "a.org/b"];
+/// let perm = reorder::sort_by_key(urls.len(), |id| urls[id as usize]);
+/// assert_eq!(perm.old_id(0), 3);
+/// assert_eq!(perm.new_id(0), 3);
+/// ```
+pub fn sort_by_key<K: Ord>(len: usize, mut key: impl FnMut(u32) -> K) -> Permutation {
+    let mut order: Vec<u32> = (0..len as u32).collect();
+    order.sort_by_cached_key(|id| key(*id));
+    Permutation::from_order(order).expect("A sorted range is a permutation.")
+}
+
+/// Computes a permutation of `len` documents that clusters the documents of
+/// the posting lists, with recursive graph bisection.
+///
+/// The documents are recursively split in two halves, and in each split,
+/// documents are swapped between the halves for up to `iterations` rounds,
+/// whenever the swap reduces the estimated size of the gaps of the posting
+/// lists. The size of the gaps of a list with *d* documents in a half of *n*
+/// documents is estimated as *d log(n / (d + 1))*.
+///
+/// # Panics
+///
+/// Panics if a posting list contains an ID greater than or equal to `len`.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::reorder;
+///
+/// // Documents alternate between two topics.
+/// let lists = vec![
+///     (0..64).step_by(2).collect::<Vec<u32>>(),
+///     (1..64).step_by(2).collect::<Vec<u32>>(),
+/// ];
+/// let perm = reorder::graph_bisection(64, &lists, 10);
+///
+/// // After reordering, each topic occupies a contiguous range of IDs.
+/// let remapped = perm.remap(&lists[0]);
+/// assert_eq!(remapped[31] - remapped[0], 31);
+/// ```
+pub fn graph_bisection(len: usize, lists: &[Vec<u32>], iterations: usize) -> Permutation {
+    let mut terms: Vec<Vec<u32>> = vec![vec![]; len];
+    for (term, list) in lists.iter().enumerate() {
+        for &doc in list {
+            terms[doc as usize].push(term as u32);
+        }
+    }
+
+    let mut order: Vec<u32> = (0..len as u32).collect();
+    bisect(&mut order, &terms, iterations);
+    Permutation::from_order(order).expect("Swapping documents keeps a permutation.")
+}
+
+/// Returns the estimated number of bits of the gaps of `degree` documents in a
+/// partition of `len` documents.
+fn cost(degree: usize, len: usize) -> f64 {
+    degree as f64 * (len as f64 / (degree + 1) as f64).log2()
+}
+
+/// Returns the reduction of the estimated size when a document moves out of a
+/// partition of `from_len` documents and into one of `to_len` documents.
+fn move_gain(
+    doc_terms: &[u32],
+    degrees: &HashMap<u32, [usize; 2]>,
+    from: usize,
+    from_len: usize,
+    to_len: usize,
+) -> f64 {
+    doc_terms
+        .iter()
+        .map(|term| {
+            let deg = degrees[term];
+            let (d_from, d_to) = (deg[from], deg[1 - from]);
+            cost(d_from, from_len) + cost(d_to, to_len)
+                - cost(d_from - 1, from_len)
+                - cost(d_to + 1, to_len)
+        })
+        .sum()
+}
+
+fn bisect(docs: &mut [u32], terms: &[Vec<u32>], iterations: usize) {
+    if docs.len() < MIN_PARTITION_LEN {
+        return;
+    }
+    let mid = docs.len() / 2;
+    let (left_len, right_len) = (mid, docs.len() - mid);
+
+    for _ in 0..iterations {
+        let mut degrees: HashMap<u32, [usize; 2]> = HashMap::new();
+        for (i, doc) in docs.iter().enumerate() {
+            let side = usize::from(i >= mid);
+            for term in &terms[*doc as usize] {
+                degrees.entry(*term).or_default()[side] += 1;
+            }
+        }
+
+        // Documents with equal gains are ordered by their first term, in
+        // opposite directions in the two halves, so that ties, like in the
+        // perfectly balanced first round, are broken towards clustering.
+        let gains = |docs: &[u32], from: usize, from_len, to_len| {
+            let mut gains: Vec<(f64, u32, usize)> = docs
+                .iter()
+                .enumerate()
+                .map(|(i, doc)| {
+                    let doc_terms = &terms[*doc as usize];
+                    let gain = move_gain(doc_terms, &degrees, from, from_len, to_len);
+                    let first = doc_terms.first().copied().unwrap_or(u32::MAX);
+                    (gain, first, i)
+                })
+                .collect();
+            gains.sort_by(|a, b| {
+                let by_term = if from == 0 {
+                    b.1.cmp(&a.1)
+                } else {
+                    a.1.cmp(&b.1)
+                };
+                b.0.total_cmp(&a.0).then(by_term)
+            });
+            gains
+        };
+        let left_gains = gains(&docs[..mid], 0, left_len, right_len);
+        let right_gains = gains(&docs[mid..], 1, right_len, left_len);
+
+        // The gains are recomputed before each swap, since every swap changes
+        // the degrees of the terms of the swapped documents.
+        let mut swapped = false;
+        for (&(_, _, i), &(_, _, j)) in left_gains.iter().zip(&right_gains) {
+            let (left_doc, right_doc) = (docs[i] as usize, docs[mid + j] as usize);
+            let left_gain = move_gain(&terms[left_doc], &degrees, 0, left_len, right_len);
+            let right_gain = move_gain(&terms[right_doc], &degrees, 1, right_len, left_len);
+            if left_gain + right_gain <= 0.0 {
+                break;
+            }
+            for term in &terms[left_doc] {
+                let deg = degrees.get_mut(term).expect("The term has a degree.");
+                deg[0] -= 1;
+                deg[1] += 1;
+            }
+            for term in &terms[right_doc] {
+                let deg = degrees.get_mut(term).expect("The term has a degree.");
+                deg[1] -= 1;
+                deg[0] += 1;
+            }
+            docs.swap(i, mid + j);
+            swapped = true;
+        }
+        if !swapped {
+            break;
+        }
+    }
+
+    let (left, right) = docs.split_at_mut(mid);
+    bisect(left, terms, iterations);
+    bisect(right, terms, iterations);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoder, GammaEncoder};
+    use std::io::Cursor;
+
+    fn gap_size(lists: &[Vec<u32>]) -> usize {
+        let mut ge = GammaEncoder::new(Cursor::new(vec![]));
+        for list in lists {
+            let mut prev = None;
+            let gaps: Vec<u32> = list
+                .iter()
+                .map(|id| {
+                    let gap = id - prev.map_or(0, |p| p + 1) + 1;
+                    prev = Some(*id);
+                    gap
+                })
+                .collect();
+            ge.encode(&gaps).unwrap();
+        }
+        ge.finalize().unwrap().into_inner().len()
+    }
+
+    #[test]
+    fn test_permutation() {
+        assert!(Permutation::from_order(vec![0, 0]).is_err());
+        assert!(Permutation::from_order(vec![0, 2]).is_err());
+
+        let perm = Permutation::from_order(vec![3, 1, 0, 2]).unwrap();
+        assert_eq!(perm.len(), 4);
+        assert_eq!(perm.remap(&[0, 3]), vec![0, 2]);
+        let inverse = perm.inverse();
+        for id in 0..4 {
+            assert_eq!(inverse.new_id(perm.new_id(id)), id);
+            assert_eq!(perm.old_id(perm.new_id(id)), id);
+        }
+        assert_eq!(Permutation::identity(3).remap(&[2, 0]), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let keys = [5, 1, 5, 0];
+        let perm = sort_by_key(keys.len(), |id| keys[id as usize]);
+        assert_eq!(perm.remap(&[0, 2]), vec![2, 3]);
+        assert_eq!(perm.new_id(3), 0);
+    }
+
+    #[test]
+    fn test_graph_bisection_shrinks_gaps() {
+        let len = 1024;
+        let lists: Vec<Vec<u32>> = (0..16)
+            .map(|topic| (0..len).filter(|doc| doc * 7 % 16 == topic).collect())
+            .collect();
+        let perm = graph_bisection(len as usize, &lists, 20);
+        assert_eq!(perm.len(), len as usize);
+
+        let remapped: Vec<Vec<u32>> = lists.iter().map(|list| perm.remap(list)).collect();
+        assert!(gap_size(&remapped) < gap_size(&lists));
+    }
+}
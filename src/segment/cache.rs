@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::postings::{BlockPostingList, PostingIterator};
+
+/// The document IDs of a posting list, along with their impacts, if any,
+/// fully decoded.
+///
+/// # Examples
+///
+/// ```
+/// use idencode::postings::{BlockPostingList, PostingIterator};
+/// use idencode::segment::DecodedPostings;
+///
+/// let list = BlockPostingList::with_impacts(&[3, 8, 20], &[1, 5, 2]).unwrap();
+/// let decoded = DecodedPostings::new(&list);
+/// assert_eq!(decoded.doc_ids(), &[3, 8, 20]);
+/// assert_eq!(decoded.impacts(), Some(&[1, 5, 2][..]));
+///
+/// let mut iter = decoded.iter();
+/// assert_eq!(iter.next_geq(5), Some(8));
+/// assert_eq!(iter.impact(), Some(5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedPostings {
+    doc_ids: Vec<u32>,
+    /// The impacts of the documents, or nothing if the list has no impacts.
+    impacts: Vec<u32>,
+}
+
+impl DecodedPostings {
+    /// Decodes all the blocks of a posting list.
+    pub fn new(list: &BlockPostingList) -> Self {
+        DecodedPostings {
+            doc_ids: list.doc_ids(),
+            impacts: list.impacts().unwrap_or_default(),
+        }
+    }
+
+    /// Returns the document IDs, in increasing order.
+    #[inline]
+    pub fn doc_ids(&self) -> &[u32] {
+        &self.doc_ids
+    }
+
+    /// Returns the impacts of the documents, or `None` if the list has no
+    /// impacts.
+    pub fn impacts(&self) -> Option<&[u32]> {
+        (self.impacts.len() == self.doc_ids.len() && !self.doc_ids.is_empty())
+            .then_some(self.impacts.as_slice())
+    }
+
+    /// Returns the number of documents.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.doc_ids.len()
+    }
+
+    /// Returns whether there are no documents.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.doc_ids.is_empty()
+    }
+
+    /// Returns the number of bytes of the decoded IDs and impacts, which is
+    /// what the list takes from the budget of a [`DecodeCache`].
+    pub fn n_bytes(&self) -> usize {
+        4 * (self.doc_ids.len() + self.impacts.len())
+    }
+
+    /// Returns an iterator over the document IDs, positioned on the first one.
+    pub fn iter(&self) -> DecodedPostingIter<'_> {
+        DecodedPostingIter {
+            postings: self,
+            pos: 0,
+        }
+    }
+}
+
+/// An iterator over the document IDs of [`DecodedPostings`], which searches
+/// the decoded IDs for [`PostingIterator::next_geq`].
+#[derive(Debug, Clone)]
+pub struct DecodedPostingIter<'a> {
+    postings: &'a DecodedPostings,
+    pos: usize,
+}
+
+impl DecodedPostingIter<'_> {
+    /// Returns the impact of the current document, which is 0 if the list
+    /// has no impacts, or `None` if the iterator is exhausted.
+    pub fn impact(&self) -> Option<u32> {
+        self.doc_id()?;
+        Some(self.postings.impacts.get(self.pos).copied().unwrap_or(0))
+    }
+}
+
+impl PostingIterator for DecodedPostingIter<'_> {
+    fn doc_id(&self) -> Option<u32> {
+        self.postings.doc_ids.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u32> {
+        self.pos = (self.pos + 1).min(self.postings.len());
+        self.doc_id()
+    }
+
+    fn next_geq(&mut self, target: u32) -> Option<u32> {
+        self.pos += self.postings.doc_ids[self.pos..].partition_point(|id| *id < target);
+        self.doc_id()
+    }
+}
+
+/// The key of a posting list in a [`DecodeCache`]: the ID that the cache
+/// gave to its segment, and the offset of the list in the postings of the
+/// segment, which identifies its term.
+type Key = (u64, u64);
+
+/// A cache of decoded posting lists, shared by the readers of segments, that
+/// holds the most recently used lists within a budget of bytes.
+///
+/// A [`SegmentReader`](super::SegmentReader) uses the cache once it is given
+/// one with [`SegmentReader::with_cache`](super::SegmentReader::with_cache),
+/// so that [`SegmentReader::decoded_postings`](super::SegmentReader::decoded_postings)
+/// decodes the posting list of a hot term once, instead of at every query.
+/// When a list does not fit in the budget, the least recently used lists are
+/// evicted, and a list larger than the whole budget is decoded but never
+/// cached. The lists of a reader that is dropped stay in the cache until
+/// they are evicted.
+///
+/// The cache is locked for each lookup, but not while a list is decoded, so
+/// it is shared between threads through an [`Arc`].
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::sync::Arc;
+/// use idencode::postings::BlockPostingList;
+/// use idencode::segment::{DecodeCache, SegmentReader, SegmentWriter};
+///
+/// let mut writer = SegmentWriter::new(Cursor::new(vec![]), 1000);
+/// let ids: Vec<u32> = (0..1000).step_by(5).collect();
+/// writer.add_term(b"hot", &BlockPostingList::new(&ids).unwrap()).unwrap();
+/// let bytes = writer.finish().unwrap().into_inner();
+///
+/// // A budget of 4 KiB, for the 200 IDs of 4 bytes of the list.
+/// let cache = Arc::new(DecodeCache::new(4096));
+/// let reader = SegmentReader::open(bytes).unwrap().with_cache(Arc::clone(&cache));
+/// for _ in 0..3 {
+///     let postings = reader.decoded_postings(b"hot").unwrap().unwrap();
+///     assert_eq!(postings.doc_ids(), ids.as_slice());
+/// }
+/// assert_eq!((cache.hits(), cache.misses()), (2, 1));
+/// assert_eq!(cache.n_bytes(), 800);
+/// ```
+#[derive(Debug)]
+pub struct DecodeCache {
+    budget: usize,
+    lru: Mutex<Lru>,
+    next_segment: AtomicU64,
+}
+
+/// The lists of a [`DecodeCache`], in order of their last use.
+#[derive(Debug, Default)]
+struct Lru {
+    /// Each list, along with the tick of its last use.
+    lists: HashMap<Key, (Arc<DecodedPostings>, u64)>,
+    /// The key of the list last used at each tick.
+    order: BTreeMap<u64, Key>,
+    tick: u64,
+    n_bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl Lru {
+    /// Marks a list as the most recently used one.
+    fn touch(&mut self, key: Key) {
+        if let Some((_, tick)) = self.lists.get_mut(&key) {
+            self.order.remove(tick);
+            self.tick += 1;
+            *tick = self.tick;
+            self.order.insert(self.tick, key);
+        }
+    }
+}
+
+impl DecodeCache {
+    /// Creates an empty cache that holds decoded lists of `budget` bytes in
+    /// total, at most.
+    pub fn new(budget: usize) -> Self {
+        DecodeCache {
+            budget,
+            lru: Mutex::default(),
+            next_segment: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the number of bytes that the cache holds at most.
+    #[inline]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the number of bytes of the lists in the cache.
+    pub fn n_bytes(&self) -> usize {
+        self.lru().n_bytes
+    }
+
+    /// Returns the number of lists in the cache.
+    pub fn len(&self) -> usize {
+        self.lru().lists.len()
+    }
+
+    /// Returns whether the cache holds no lists.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lookups that found their list in the cache.
+    pub fn hits(&self) -> u64 {
+        self.lru().hits
+    }
+
+    /// Returns the number of lookups that decoded their list.
+    pub fn misses(&self) -> u64 {
+        self.lru().misses
+    }
+
+    /// Evicts all the lists.
+    pub fn clear(&self) {
+        let mut lru = self.lru();
+        lru.lists.clear();
+        lru.order.clear();
+        lru.n_bytes = 0;
+    }
+
+    /// Returns a new ID for a segment whose lists go through the cache.
+    pub(crate) fn register(&self) -> u64 {
+        self.next_segment.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Returns the list of a key, decoding it with `decode`, and caching it
+    /// if it fits in the budget, if it is not in the cache.
+    pub(crate) fn get_or_decode<E>(
+        &self,
+        key: Key,
+        decode: impl FnOnce() -> Result<DecodedPostings, E>,
+    ) -> Result<Arc<DecodedPostings>, E> {
+        {
+            let mut lru = self.lru();
+            if let Some((list, _)) = lru.lists.get(&key) {
+                let list = Arc::clone(list);
+                lru.hits += 1;
+                lru.touch(key);
+                return Ok(list);
+            }
+            lru.misses += 1;
+        }
+
+        let list = Arc::new(decode()?);
+        let n_bytes = list.n_bytes();
+        if n_bytes > self.budget {
+            return Ok(list);
+        }
+        let mut lru = self.lru();
+        // Another thread may have decoded the list in the meantime.
+        if let Some((list, _)) = lru.lists.get(&key) {
+            let list = Arc::clone(list);
+            lru.touch(key);
+            return Ok(list);
+        }
+        while lru.n_bytes + n_bytes > self.budget {
+            let (_, oldest) = lru
+                .order
+                .pop_first()
+                .expect("The lists in the cache take all its bytes.");
+            let (evicted, _) = lru.lists.remove(&oldest).expect("Each tick has a list.");
+            lru.n_bytes -= evicted.n_bytes();
+        }
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.lists.insert(key, (Arc::clone(&list), tick));
+        lru.order.insert(tick, key);
+        lru.n_bytes += n_bytes;
+        Ok(list)
+    }
+
+    /// Locks the lists of the cache. A thread that panicked while holding
+    /// the lock left them consistent, since no update of the lists panics
+    /// midway.
+    fn lru(&self) -> MutexGuard<'_, Lru> {
+        self.lru.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn postings(len: u32) -> DecodedPostings {
+        DecodedPostings::new(&BlockPostingList::new(&(0..len).collect::<Vec<_>>()).unwrap())
+    }
+
+    fn get(cache: &DecodeCache, key: Key, len: u32) -> Arc<DecodedPostings> {
+        cache
+            .get_or_decode(key, || Ok::<_, Infallible>(postings(len)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decoded_postings() {
+        let ids: Vec<u32> = (0..500).map(|i| i * 7 + 1).collect();
+        let impacts: Vec<u32> = ids.iter().map(|id| id % 9).collect();
+        let decoded =
+            DecodedPostings::new(&BlockPostingList::with_impacts(&ids, &impacts).unwrap());
+        assert_eq!(decoded.doc_ids(), ids.as_slice());
+        assert_eq!(decoded.impacts(), Some(impacts.as_slice()));
+        assert_eq!(decoded.n_bytes(), 4000);
+
+        let mut iter = decoded.iter();
+        assert_eq!(iter.doc_id(), Some(1));
+        assert_eq!(iter.next_geq(100), Some(106));
+        assert_eq!(iter.impact(), Some(106 % 9));
+        assert_eq!(iter.next_geq(0), Some(106));
+        assert_eq!(iter.next(), Some(113));
+        assert_eq!(iter.next_geq(3494), Some(3494));
+        assert_eq!(iter.next_geq(3495), None);
+        assert_eq!((iter.next(), iter.impact()), (None, None));
+
+        let without = DecodedPostings::new(&BlockPostingList::new(&ids).unwrap());
+        assert_eq!(without.impacts(), None);
+        assert_eq!(without.iter().impact(), Some(0));
+        assert!(DecodedPostings::default().iter().next().is_none());
+    }
+
+    #[test]
+    fn test_lru() {
+        // Lists of 100 IDs, of 400 bytes each.
+        let cache = DecodeCache::new(1000);
+        let (a, b, c) = ((0, 0), (0, 10), (1, 0));
+        get(&cache, a, 100);
+        get(&cache, b, 100);
+        assert_eq!((cache.len(), cache.n_bytes()), (2, 800));
+        // Using `a` leaves `b` as the least recently used list.
+        assert!(Arc::ptr_eq(&get(&cache, a, 100), &get(&cache, a, 100)));
+        get(&cache, c, 100);
+        assert_eq!((cache.len(), cache.n_bytes()), (2, 800));
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+        get(&cache, a, 100);
+        get(&cache, c, 100);
+        assert_eq!((cache.hits(), cache.misses()), (4, 3));
+        get(&cache, b, 100);
+        assert_eq!(cache.misses(), 4);
+
+        // A list larger than the budget is decoded, but not cached.
+        assert_eq!(get(&cache, (2, 0), 300).len(), 300);
+        assert_eq!(cache.len(), 2);
+        // A list of the whole budget evicts all the others.
+        get(&cache, (2, 0), 250);
+        assert_eq!((cache.len(), cache.n_bytes()), (1, 1000));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.n_bytes(), 0);
+        assert_eq!(cache.register(), 0);
+        assert_eq!(cache.register(), 1);
+    }
+
+    #[test]
+    fn test_errs() {
+        let cache = DecodeCache::new(1000);
+        assert_eq!(
+            cache.get_or_decode((0, 0), || Err("corrupt")),
+            Err("corrupt")
+        );
+        assert!(cache.is_empty());
+        assert_eq!(cache.misses(), 1);
+    }
+}
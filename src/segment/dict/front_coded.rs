@@ -0,0 +1,291 @@
+use super::{
+    check_entries, read_entry, read_num, Dictionary, DictionaryKind, TermInfo, Terms, BLOCK_LEN,
+};
+use crate::error::InvalidCodeError;
+
+/// A sorted dictionary of the terms of a segment, front-coded in blocks of
+/// 16 terms.
+///
+/// Each entry holds the length of the prefix that the term shares with the
+/// previous one, the rest of the term, and its [`TermInfo`], in Variable
+/// Byte Encoding. The first term of every block is kept whole, so a lookup
+/// finds its block with a binary search over the first terms, and only
+/// decodes the entries of that block.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::postings::BlockPostingList;
+/// use idencode::segment::{SegmentReader, SegmentWriter};
+///
+/// let mut writer = SegmentWriter::new(Cursor::new(vec![]), 10);
+/// for (term, ids) in [("apple", vec![1, 4]), ("apricot", vec![2]), ("banana", vec![9])] {
+///     let list = BlockPostingList::new(&ids).unwrap();
+///     writer.add_term(term.as_bytes(), &list).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap().into_inner();
+///
+/// let reader = SegmentReader::open(bytes).unwrap();
+/// let dictionary = reader.dictionary();
+/// assert_eq!(dictionary.len(), 3);
+/// assert_eq!(dictionary.get(b"apricot").unwrap().doc_freq(), 1);
+/// assert_eq!(dictionary.get(b"cherry"), None);
+/// let terms: Vec<_> = dictionary.terms_from(b"apq").unwrap().map(|(t, _)| t).collect();
+/// assert_eq!(terms, [b"apricot".to_vec(), b"banana".to_vec()]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontCodedDictionary {
+    bytes: Vec<u8>,
+    /// The first term of each block, along with the offset of its entry.
+    blocks: Vec<(Vec<u8>, usize)>,
+    len: usize,
+}
+
+impl FrontCodedDictionary {
+    /// Deserializes a dictionary from the bytes that the front-coded
+    /// dictionary of a segment is written in.
+    ///
+    /// Every entry is decoded once, to check that the terms are in
+    /// increasing order. Returns an error if the bytes are not a serialized
+    /// dictionary.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidCodeError> {
+        let mut pos = 0;
+        let len = read_num(bytes, &mut pos)?;
+        if len > bytes.len() as u64 {
+            return Err(InvalidCodeError::SegmentCodeError);
+        }
+        let bytes = bytes[pos..].to_vec();
+        let starts = check_entries(&bytes, len as usize, BLOCK_LEN)?;
+
+        let blocks = starts
+            .into_iter()
+            .step_by(BLOCK_LEN)
+            .map(|start| {
+                let mut term = vec![];
+                read_entry(&bytes, &mut start.clone(), &mut term)
+                    .expect("The entries are checked above.");
+                (term, start)
+            })
+            .collect();
+        Ok(FrontCodedDictionary {
+            bytes,
+            blocks,
+            len: len as usize,
+        })
+    }
+
+    /// Returns the number of terms in the dictionary.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the dictionary has no terms.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry of a term, or `None` if the term is not in the
+    /// dictionary.
+    pub fn get(&self, term: &[u8]) -> Option<TermInfo> {
+        let block = self.block_of(term)?;
+        let mut iter = self.iter_from(self.blocks[block].1, BLOCK_LEN);
+        iter.find(|(t, _)| t.as_slice() >= term)
+            .filter(|(t, _)| t == term)
+            .map(|(_, info)| info)
+    }
+
+    /// Returns an iterator over the terms, in increasing order, along with
+    /// their entries.
+    pub fn iter(&self) -> TermIter<'_> {
+        self.iter_from(0, self.len)
+    }
+
+    // Returns the index of the last block whose first term is not greater
+    // than a term, or `None` if the term precedes all the blocks.
+    fn block_of(&self, term: &[u8]) -> Option<usize> {
+        self.blocks
+            .partition_point(|(first, _)| first.as_slice() <= term)
+            .checked_sub(1)
+    }
+
+    // Returns an iterator over `n` entries at most, from the one at an
+    // offset, which must be the first entry of a block.
+    fn iter_from(&self, pos: usize, n: usize) -> TermIter<'_> {
+        TermIter::new(&self.bytes, pos, n)
+    }
+}
+
+impl Dictionary for FrontCodedDictionary {
+    fn kind(&self) -> DictionaryKind {
+        DictionaryKind::FrontCoded
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, term: &[u8]) -> Option<TermInfo> {
+        FrontCodedDictionary::get(self, term)
+    }
+
+    fn terms(&self) -> Terms<'_> {
+        Box::new(self.iter())
+    }
+
+    fn terms_from(&self, start: &[u8]) -> Option<Terms<'_>> {
+        let block = self.block_of(start).unwrap_or(0);
+        let pos = self.blocks.get(block).map_or(0, |&(_, pos)| pos);
+        let iter = self.iter_from(pos, self.len - block * BLOCK_LEN);
+        let start = start.to_vec();
+        Some(Box::new(iter.skip_while(move |(term, _)| *term < start)))
+    }
+}
+
+/// An iterator over the terms of a [`FrontCodedDictionary`], in increasing
+/// order, along with their entries.
+#[derive(Debug, Clone)]
+pub struct TermIter<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    term: Vec<u8>,
+    remaining: usize,
+}
+
+impl<'a> TermIter<'a> {
+    /// Returns an iterator over `n` entries at most, from the one at an
+    /// offset, which must share no prefix with the one before it.
+    pub(super) fn new(bytes: &'a [u8], pos: usize, n: usize) -> Self {
+        TermIter {
+            bytes,
+            pos,
+            term: vec![],
+            remaining: n,
+        }
+    }
+}
+
+impl Iterator for TermIter<'_> {
+    type Item = (Vec<u8>, TermInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.pos == self.bytes.len() {
+            return None;
+        }
+        self.remaining -= 1;
+        let info = read_entry(self.bytes, &mut self.pos, &mut self.term)
+            .expect("The entries of a dictionary are checked when it is read.");
+        Some((self.term.clone(), info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DictionaryBuilder;
+    use super::*;
+
+    fn builder() -> DictionaryBuilder {
+        DictionaryBuilder::new(DictionaryKind::FrontCoded)
+    }
+
+    fn dictionary(terms: &[String]) -> FrontCodedDictionary {
+        let mut builder = builder();
+        for (i, term) in terms.iter().enumerate() {
+            assert!(builder.push(term.as_bytes(), TermInfo::new(i as u32, 10 * i as u64, 10)));
+        }
+        FrontCodedDictionary::from_bytes(&builder.finish()).unwrap()
+    }
+
+    #[test]
+    fn test_get() {
+        let terms: Vec<String> = (0..1000).map(|i| format!("term{:05}", i * 3)).collect();
+        let dictionary = dictionary(&terms);
+        assert_eq!(dictionary.len(), 1000);
+        assert_eq!(dictionary.blocks.len(), 63);
+        for (i, term) in terms.iter().enumerate() {
+            let info = dictionary.get(term.as_bytes()).unwrap();
+            assert_eq!(info, TermInfo::new(i as u32, 10 * i as u64, 10));
+        }
+        for missing in ["", "a", "term00001", "term00002", "term02998", "term9", "z"] {
+            assert_eq!(dictionary.get(missing.as_bytes()), None);
+        }
+
+        let decoded: Vec<Vec<u8>> = dictionary.iter().map(|(term, _)| term).collect();
+        let expected: Vec<Vec<u8>> = terms.iter().map(|t| t.as_bytes().to_vec()).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn test_terms_from() {
+        let terms: Vec<String> = (0..1000).map(|i| format!("term{:05}", i * 3)).collect();
+        let dictionary = dictionary(&terms);
+        for (start, skipped) in [
+            ("", 0),
+            ("term00000", 0),
+            ("term00001", 1),
+            ("term00048", 16),
+            ("term00049", 17),
+            ("term02997", 999),
+            ("term02998", 1000),
+            ("z", 1000),
+        ] {
+            let listed: Vec<Vec<u8>> = dictionary
+                .terms_from(start.as_bytes())
+                .unwrap()
+                .map(|(term, _)| term)
+                .collect();
+            let expected: Vec<Vec<u8>> = terms[skipped..]
+                .iter()
+                .map(|t| t.as_bytes().to_vec())
+                .collect();
+            assert_eq!(listed, expected, "The terms from {start} are wrong.");
+        }
+
+        let prefixed: Vec<_> = dictionary.prefix(b"term001").unwrap().collect();
+        assert_eq!(prefixed.len(), 33);
+        assert_eq!(prefixed[0].0, b"term00102");
+        assert!(dictionary.prefix(b"x").unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_front_coding() {
+        let mut builder = builder();
+        let info = TermInfo::new(1, 0, 1);
+        assert!(builder.push(b"", info));
+        assert!(builder.push(b"ab", info));
+        assert!(builder.push(b"abc", info));
+        assert!(!builder.push(b"abc", info));
+        assert!(!builder.push(b"a", info));
+        assert!(builder.push(b"b", info));
+        let bytes = builder.finish();
+        // The number of terms, and the entries of "", "ab", "abc" and "b".
+        assert_eq!(bytes.len(), 1 + 5 + 7 + 6 + 6);
+        let dictionary = FrontCodedDictionary::from_bytes(&bytes).unwrap();
+        assert_eq!(dictionary.get(b""), Some(info));
+        assert_eq!(dictionary.get(b"abc"), Some(info));
+        assert_eq!(dictionary.get(b"abd"), None);
+    }
+
+    #[test]
+    fn test_errs() {
+        let empty = builder().finish();
+        let empty = FrontCodedDictionary::from_bytes(&empty).unwrap();
+        assert!(empty.is_empty());
+        assert!(empty.terms_from(b"a").unwrap().next().is_none());
+        assert!(FrontCodedDictionary::from_bytes(&[]).is_err());
+
+        let terms: Vec<String> = (0..40).map(|i| format!("{i:02}")).collect();
+        let mut builder = builder();
+        for term in &terms {
+            builder.push(term.as_bytes(), TermInfo::new(1, 0, 1));
+        }
+        let bytes = builder.finish();
+        assert!(FrontCodedDictionary::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        // Make the second term equal to the first one.
+        let mut unsorted = bytes.clone();
+        unsorted[1 + 7 + 2] = b'0';
+        assert!(FrontCodedDictionary::from_bytes(&unsorted).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use super::{
+    check_entries, read_entry, read_num, Dictionary, DictionaryKind, TermInfo, TermIter, Terms,
+};
+use crate::code::global::vb::write_vb_bytes;
+use crate::collections::hash::{reduce, siphash24};
+use crate::error::InvalidCodeError;
+
+/// The value of an empty slot of the table.
+const EMPTY: u64 = u64::MAX;
+
+/// A dictionary of the terms of a segment, laid out as a hash table with
+/// linear probing over the entries of the terms.
+///
+/// The entries are written in increasing order of their terms, as in a
+/// [`FrontCodedDictionary`](super::FrontCodedDictionary), but each term is
+/// kept whole. They are preceded by a table of twice as many slots as terms,
+/// rounded up to a power of two, which hold the offsets of the entries, so a
+/// lookup decodes a single entry on average. In turn, the dictionary does
+/// not answer range or prefix queries.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::postings::BlockPostingList;
+/// use idencode::segment::{DictionaryKind, SegmentReader, SegmentWriter};
+///
+/// let mut writer = SegmentWriter::with_dictionary(Cursor::new(vec![]), 10, DictionaryKind::Hash);
+/// for (term, ids) in [("apple", vec![1, 4]), ("apricot", vec![2]), ("banana", vec![9])] {
+///     let list = BlockPostingList::new(&ids).unwrap();
+///     writer.add_term(term.as_bytes(), &list).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap().into_inner();
+///
+/// let reader = SegmentReader::open(bytes).unwrap();
+/// let dictionary = reader.dictionary();
+/// assert_eq!(dictionary.kind(), DictionaryKind::Hash);
+/// assert_eq!(dictionary.get(b"apple").unwrap().doc_freq(), 2);
+/// assert_eq!(dictionary.get(b"cherry"), None);
+/// assert!(dictionary.terms_from(b"b").is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashDictionary {
+    bytes: Vec<u8>,
+    /// The offset of the entry in each slot of the table, or [`EMPTY`].
+    slots: Vec<u64>,
+    len: usize,
+}
+
+impl HashDictionary {
+    /// Deserializes a dictionary from the bytes that the hash dictionary of
+    /// a segment is written in.
+    ///
+    /// Every entry is decoded once, and every slot is checked to point to a
+    /// distinct entry. Returns an error if the bytes are not a serialized
+    /// dictionary.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::SegmentCodeError;
+        let mut pos = 0;
+        let len = read_num(bytes, &mut pos)?;
+        let num_slots = read_num(bytes, &mut pos)?;
+        // A table with no empty slot would make the probes of a missing term
+        // loop forever.
+        if len >= num_slots || num_slots > (bytes.len() - pos) as u64 / 8 {
+            return Err(err());
+        }
+        let (table, bytes) = bytes[pos..].split_at(8 * num_slots as usize);
+        let slots: Vec<u64> = table
+            .chunks_exact(8)
+            .map(|slot| u64::from_le_bytes(slot.try_into().expect("A slot has 8 bytes.")))
+            .collect();
+
+        // Every term is kept whole, as if it were the first of a block.
+        let starts = check_entries(bytes, len as usize, 1)?;
+        let mut used = vec![false; starts.len()];
+        for &slot in slots.iter().filter(|&&slot| slot != EMPTY) {
+            let entry = starts.binary_search(&(slot as usize)).map_err(|_| err())?;
+            if std::mem::replace(&mut used[entry], true) {
+                return Err(err());
+            }
+        }
+        if used.contains(&false) {
+            return Err(err());
+        }
+        Ok(HashDictionary {
+            bytes: bytes.to_vec(),
+            slots,
+            len: len as usize,
+        })
+    }
+
+    /// Returns the number of terms in the dictionary.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the dictionary has no terms.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry of a term, or `None` if the term is not in the
+    /// dictionary.
+    pub fn get(&self, term: &[u8]) -> Option<TermInfo> {
+        let num_slots = self.slots.len() as u64;
+        let mut slot = reduce(hash_term(term), num_slots);
+        let mut entry = vec![];
+        loop {
+            let mut pos = match self.slots[slot as usize] {
+                EMPTY => return None,
+                pos => pos as usize,
+            };
+            entry.clear();
+            let info = read_entry(&self.bytes, &mut pos, &mut entry)
+                .expect("The entries of a dictionary are checked when it is read.");
+            if entry == term {
+                return Some(info);
+            }
+            slot = (slot + 1) % num_slots;
+        }
+    }
+
+    /// Returns an iterator over the terms, in increasing order, along with
+    /// their entries.
+    pub fn iter(&self) -> TermIter<'_> {
+        TermIter::new(&self.bytes, 0, self.len)
+    }
+}
+
+impl Dictionary for HashDictionary {
+    fn kind(&self) -> DictionaryKind {
+        DictionaryKind::Hash
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, term: &[u8]) -> Option<TermInfo> {
+        HashDictionary::get(self, term)
+    }
+
+    fn terms(&self) -> Terms<'_> {
+        Box::new(self.iter())
+    }
+
+    fn terms_from(&self, _start: &[u8]) -> Option<Terms<'_>> {
+        None
+    }
+}
+
+/// Hashes a term of a hash dictionary, with a hash that does not change
+/// between runs, since the table is written to a segment.
+pub(super) fn hash_term(term: &[u8]) -> u64 {
+    siphash24(0, 0, term)
+}
+
+/// Writes the table of a hash dictionary, from the hash of each term along
+/// with the offset of its entry: the number of slots in Variable Byte
+/// Encoding, followed by the slots, as 64-bit little-endian offsets.
+pub(super) fn write_table(hashes: &[(u64, u64)], bytes: &mut Vec<u8>) {
+    let num_slots = (2 * hashes.len()).next_power_of_two();
+    let mut slots = vec![EMPTY; num_slots];
+    for &(hash, offset) in hashes {
+        let mut slot = reduce(hash, num_slots as u64) as usize;
+        while slots[slot] != EMPTY {
+            slot = (slot + 1) % num_slots;
+        }
+        slots[slot] = offset;
+    }
+    write_vb_bytes(num_slots as u64, bytes);
+    for slot in slots {
+        bytes.extend_from_slice(&slot.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DictionaryBuilder;
+    use super::*;
+
+    fn bytes(terms: &[String]) -> Vec<u8> {
+        let mut builder = DictionaryBuilder::new(DictionaryKind::Hash);
+        for (i, term) in terms.iter().enumerate() {
+            assert!(builder.push(term.as_bytes(), TermInfo::new(i as u32, 10 * i as u64, 10)));
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn test_get() {
+        let terms: Vec<String> = (0..1000).map(|i| format!("term{:05}", i * 3)).collect();
+        let dictionary = HashDictionary::from_bytes(&bytes(&terms)).unwrap();
+        assert_eq!(dictionary.len(), 1000);
+        assert_eq!(dictionary.slots.len(), 2048);
+        for (i, term) in terms.iter().enumerate() {
+            let info = dictionary.get(term.as_bytes()).unwrap();
+            assert_eq!(info, TermInfo::new(i as u32, 10 * i as u64, 10));
+        }
+        for missing in ["", "a", "term00001", "term00002", "term02998", "term9", "z"] {
+            assert_eq!(dictionary.get(missing.as_bytes()), None);
+        }
+
+        let decoded: Vec<Vec<u8>> = dictionary.iter().map(|(term, _)| term).collect();
+        let expected: Vec<Vec<u8>> = terms.iter().map(|t| t.as_bytes().to_vec()).collect();
+        assert_eq!(decoded, expected);
+        assert!(dictionary.prefix(b"term").is_none());
+    }
+
+    #[test]
+    fn test_errs() {
+        let empty = HashDictionary::from_bytes(&bytes(&[])).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.get(b""), None);
+        assert!(HashDictionary::from_bytes(&[]).is_err());
+
+        let terms: Vec<String> = (0..6).map(|i| format!("{i:02}")).collect();
+        let bytes = bytes(&terms);
+        // The number of terms and slots, and the 16 slots of the table.
+        let table = 2 + 16 * 8;
+        assert!(HashDictionary::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(HashDictionary::from_bytes(&bytes[..table]).is_err());
+        // A table with no empty slot.
+        let mut full = bytes.clone();
+        full[1] = 6;
+        assert!(HashDictionary::from_bytes(&full).is_err());
+
+        let slot = |i: usize| 2 + 8 * i..2 + 8 * (i + 1);
+        let used: Vec<usize> = (0..16).filter(|&i| bytes[slot(i)] != [0xFF; 8]).collect();
+        assert_eq!(used.len(), 6);
+        // Two slots that point to the same entry.
+        let mut twice = bytes.clone();
+        twice.copy_within(slot(used[0]), slot(used[1]).start);
+        assert!(HashDictionary::from_bytes(&twice).is_err());
+        // A slot that points within an entry.
+        let mut within = bytes.clone();
+        within[slot(used[0])].copy_from_slice(&1_u64.to_le_bytes());
+        assert!(HashDictionary::from_bytes(&within).is_err());
+        // An entry that shares a prefix with the previous one.
+        let mut shared = bytes.clone();
+        shared[table + 7] = 1;
+        assert!(HashDictionary::from_bytes(&shared).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! The term dictionary of a segment, which maps its terms to the byte ranges
+//! of their posting lists.
+//!
+//! A dictionary is laid out either as sorted blocks of front-coded terms,
+//! which answer range and prefix queries, or as a hash table over the terms,
+//! which only answers exact lookups, but with a single probe on average. The
+//! [`DictionaryKind`] of a segment is picked when it is written, and recorded
+//! in its footer, so a reader opens either one as a [`Dictionary`].
+
+mod front_coded;
+mod hashed;
+
+use std::fmt;
+
+pub use front_coded::{FrontCodedDictionary, TermIter};
+pub use hashed::HashDictionary;
+
+use crate::code::global::vb::{read_vb_bytes, write_vb_bytes};
+use crate::error::InvalidCodeError;
+
+/// The number of terms in a block of a front-coded dictionary. The first
+/// term of each block is kept whole, and the rest share a prefix with their
+/// predecessor.
+const BLOCK_LEN: usize = 16;
+
+/// The entry of a term in a dictionary: the number of documents that contain
+/// it, and the byte range of its posting list in the postings of the
+/// segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermInfo {
+    doc_freq: u32,
+    offset: u64,
+    len: u64,
+}
+
+impl TermInfo {
+    pub(crate) fn new(doc_freq: u32, offset: u64, len: u64) -> Self {
+        TermInfo {
+            doc_freq,
+            offset,
+            len,
+        }
+    }
+
+    /// Returns the number of documents that contain the term.
+    #[inline]
+    pub fn doc_freq(&self) -> u32 {
+        self.doc_freq
+    }
+
+    /// Returns the offset of the posting list of the term, from the start
+    /// of the postings of the segment.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of bytes of the posting list of the term.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the posting list of the term has no bytes, which
+    /// never holds for the terms of a segment.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// The layout of the dictionary of a segment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DictionaryKind {
+    /// Sorted blocks of front-coded terms, as in [`FrontCodedDictionary`],
+    /// for range and prefix queries.
+    #[default]
+    FrontCoded,
+    /// A hash table over the terms, as in [`HashDictionary`], for exact
+    /// lookups only.
+    Hash,
+}
+
+impl DictionaryKind {
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            DictionaryKind::FrontCoded => 0,
+            DictionaryKind::Hash => 1,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(DictionaryKind::FrontCoded),
+            1 => Some(DictionaryKind::Hash),
+            _ => None,
+        }
+    }
+}
+
+/// An iterator over terms of a dictionary, in increasing order, along with
+/// their entries.
+pub type Terms<'a> = Box<dyn Iterator<Item = (Vec<u8>, TermInfo)> + 'a>;
+
+/// A dictionary of the terms of a segment.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::postings::BlockPostingList;
+/// use idencode::segment::{DictionaryKind, SegmentReader, SegmentWriter};
+///
+/// let list = BlockPostingList::new(&[0, 1]).unwrap();
+/// for kind in [DictionaryKind::FrontCoded, DictionaryKind::Hash] {
+///     let mut writer = SegmentWriter::with_dictionary(Cursor::new(vec![]), 2, kind);
+///     for term in ["car", "cart", "cat", "dog"] {
+///         writer.add_term(term.as_bytes(), &list).unwrap();
+///     }
+///     let reader = SegmentReader::open(writer.finish().unwrap().into_inner()).unwrap();
+///     let dictionary = reader.dictionary();
+///     assert_eq!(dictionary.kind(), kind);
+///     assert_eq!(dictionary.get(b"cat").unwrap().doc_freq(), 2);
+///     assert_eq!(dictionary.terms().count(), 4);
+///
+///     // Only the front-coded blocks answer prefix queries.
+///     let prefixed: Option<Vec<Vec<u8>>> =
+///         dictionary.prefix(b"car").map(|terms| terms.map(|(term, _)| term).collect());
+///     match kind {
+///         DictionaryKind::FrontCoded => {
+///             assert_eq!(prefixed, Some(vec![b"car".to_vec(), b"cart".to_vec()]));
+///         }
+///         DictionaryKind::Hash => assert_eq!(prefixed, None),
+///     }
+/// }
+/// ```
+pub trait Dictionary: fmt::Debug + Send + Sync {
+    /// Returns the layout of the dictionary.
+    fn kind(&self) -> DictionaryKind;
+
+    /// Returns the number of terms in the dictionary.
+    fn len(&self) -> usize;
+
+    /// Returns whether the dictionary has no terms.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entry of a term, or `None` if the term is not in the
+    /// dictionary.
+    fn get(&self, term: &[u8]) -> Option<TermInfo>;
+
+    /// Returns an iterator over all the terms, in increasing order, along
+    /// with their entries.
+    fn terms(&self) -> Terms<'_>;
+
+    /// Returns an iterator over the terms from the first one that is greater
+    /// than or equal to `start`, in increasing order, along with their
+    /// entries, or `None` if the dictionary only answers exact lookups.
+    fn terms_from(&self, start: &[u8]) -> Option<Terms<'_>>;
+
+    /// Returns an iterator over the terms that start with a prefix, in
+    /// increasing order, along with their entries, or `None` if the
+    /// dictionary only answers exact lookups.
+    fn prefix<'a>(&'a self, prefix: &'a [u8]) -> Option<Terms<'a>> {
+        let terms = self.terms_from(prefix)?;
+        Some(Box::new(
+            terms.take_while(move |(term, _)| term.starts_with(prefix)),
+        ))
+    }
+}
+
+/// Deserializes a dictionary of a kind from its bytes.
+pub(crate) fn read_dictionary(
+    kind: DictionaryKind,
+    bytes: &[u8],
+) -> Result<Box<dyn Dictionary>, InvalidCodeError> {
+    Ok(match kind {
+        DictionaryKind::FrontCoded => Box::new(FrontCodedDictionary::from_bytes(bytes)?),
+        DictionaryKind::Hash => Box::new(HashDictionary::from_bytes(bytes)?),
+    })
+}
+
+/// Builds the bytes of a dictionary of a kind from terms in increasing
+/// order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DictionaryBuilder {
+    kind: DictionaryKind,
+    bytes: Vec<u8>,
+    last: Vec<u8>,
+    len: usize,
+    /// The hash of each term, along with the offset of its entry, for the
+    /// table of a hash dictionary.
+    hashes: Vec<(u64, u64)>,
+}
+
+impl DictionaryBuilder {
+    pub(crate) fn new(kind: DictionaryKind) -> Self {
+        DictionaryBuilder {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    /// Appends a term, returning `false`, without appending it, if it is not
+    /// greater than the previous one.
+    pub(crate) fn push(&mut self, term: &[u8], info: TermInfo) -> bool {
+        if self.len > 0 && term <= self.last.as_slice() {
+            return false;
+        }
+        let shared = match (self.kind, self.len % BLOCK_LEN) {
+            (DictionaryKind::FrontCoded, 1..) => common_prefix(&self.last, term),
+            _ => 0,
+        };
+        if self.kind == DictionaryKind::Hash {
+            self.hashes
+                .push((hashed::hash_term(term), self.bytes.len() as u64));
+        }
+        write_vb_bytes(shared as u64, &mut self.bytes);
+        write_vb_bytes((term.len() - shared) as u64, &mut self.bytes);
+        self.bytes.extend_from_slice(&term[shared..]);
+        write_vb_bytes(info.doc_freq, &mut self.bytes);
+        write_vb_bytes(info.offset, &mut self.bytes);
+        write_vb_bytes(info.len, &mut self.bytes);
+
+        self.last.clear();
+        self.last.extend_from_slice(term);
+        self.len += 1;
+        true
+    }
+
+    /// Returns the kind of the dictionary.
+    #[inline]
+    pub(crate) fn kind(&self) -> DictionaryKind {
+        self.kind
+    }
+
+    /// Returns the bytes of the dictionary: its number of terms in Variable
+    /// Byte Encoding, followed by the table of a hash dictionary, and by the
+    /// entries.
+    pub(crate) fn finish(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.bytes.len() + 10);
+        write_vb_bytes(self.len as u64, &mut bytes);
+        if self.kind == DictionaryKind::Hash {
+            hashed::write_table(&self.hashes, &mut bytes);
+        }
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+}
+
+/// Returns the length of the common prefix of two byte strings.
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Decodes the number in Variable Byte Encoding at an offset of a serialized
+/// dictionary, and moves the offset past it.
+fn read_num(bytes: &[u8], pos: &mut usize) -> Result<u64, InvalidCodeError> {
+    read_vb_bytes(bytes, pos).map_err(|_| InvalidCodeError::SegmentCodeError)
+}
+
+/// Decodes the entry at an offset of the entries of a dictionary, replacing
+/// the term that precedes it with its own, and moves the offset past it.
+fn read_entry(
+    bytes: &[u8],
+    pos: &mut usize,
+    term: &mut Vec<u8>,
+) -> Result<TermInfo, InvalidCodeError> {
+    let err = || InvalidCodeError::SegmentCodeError;
+    let shared = usize::try_from(read_num(bytes, pos)?).map_err(|_| err())?;
+    let suffix = usize::try_from(read_num(bytes, pos)?).map_err(|_| err())?;
+    if shared > term.len() {
+        return Err(err());
+    }
+    let suffix = bytes
+        .get(*pos..pos.saturating_add(suffix))
+        .ok_or_else(err)?;
+    *pos += suffix.len();
+    term.truncate(shared);
+    term.extend_from_slice(suffix);
+
+    let doc_freq = u32::try_from(read_num(bytes, pos)?).map_err(|_| err())?;
+    let offset = read_num(bytes, pos)?;
+    let len = read_num(bytes, pos)?;
+    Ok(TermInfo::new(doc_freq, offset, len))
+}
+
+/// Decodes the entries of a dictionary, checking that their terms are in
+/// increasing order, and that every entry at a multiple of `block_len`
+/// shares no prefix, and returns the offset of each entry.
+fn check_entries(
+    bytes: &[u8],
+    len: usize,
+    block_len: usize,
+) -> Result<Vec<usize>, InvalidCodeError> {
+    let mut starts = Vec::with_capacity(len);
+    let mut pos = 0;
+    let (mut term, mut prev) = (vec![], vec![]);
+    for i in 0..len {
+        starts.push(pos);
+        if i % block_len == 0 {
+            term.clear();
+        }
+        read_entry(bytes, &mut pos, &mut term)?;
+        if i > 0 && term <= prev {
+            return Err(InvalidCodeError::SegmentCodeError);
+        }
+        prev.clone_from(&term);
+    }
+    if pos != bytes.len() {
+        return Err(InvalidCodeError::SegmentCodeError);
+    }
+    Ok(starts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds() {
+        for kind in [DictionaryKind::FrontCoded, DictionaryKind::Hash] {
+            assert_eq!(DictionaryKind::from_tag(kind.tag()), Some(kind));
+
+            let terms: Vec<String> = (0..500).map(|i| format!("t{:04}", i * 2)).collect();
+            let mut builder = DictionaryBuilder::new(kind);
+            for (i, term) in terms.iter().enumerate() {
+                assert!(builder.push(term.as_bytes(), TermInfo::new(1, i as u64, 1)));
+            }
+            assert!(!builder.push(b"t0000", TermInfo::new(1, 0, 1)));
+            let dictionary = read_dictionary(kind, &builder.finish()).unwrap();
+            assert_eq!(dictionary.kind(), kind);
+            assert_eq!(dictionary.len(), 500);
+            for (i, term) in terms.iter().enumerate() {
+                let info = dictionary.get(term.as_bytes()).unwrap();
+                assert_eq!(info.offset(), i as u64);
+            }
+            assert_eq!(dictionary.get(b"t0001"), None);
+            assert_eq!(dictionary.get(b""), None);
+            let listed: Vec<Vec<u8>> = dictionary.terms().map(|(term, _)| term).collect();
+            let expected: Vec<Vec<u8>> = terms.iter().map(|t| t.clone().into_bytes()).collect();
+            assert_eq!(listed, expected);
+        }
+        assert_eq!(DictionaryKind::from_tag(2), None);
+    }
+}
@@ -0,0 +1,203 @@
+use std::io::{self, Write};
+
+use super::{DictionaryKind, SegmentReader, SegmentWriter, TermInfo, Terms};
+use crate::postings::BlockPostingList;
+
+/// Merges segments into a single one, written to a writer, and returns the
+/// writer.
+///
+/// The documents of each segment follow the documents of the previous ones,
+/// so the document IDs of a segment are incremented by the total number of
+/// documents of the segments before it. The dictionaries are merged in a
+/// single pass over their terms, and the posting lists of a term are joined
+/// with [`BlockPostingList::append_shifted`], which keeps their blocks in
+/// their encoded form, but for the first block of each list. The dictionary
+/// of the merged segment is of the kind of the dictionary of the first
+/// segment.
+///
+/// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the merged
+/// segment has more than `u32::MAX` documents, and of kind
+/// [`io::ErrorKind::InvalidData`] if a posting list of a segment is not
+/// valid.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use idencode::postings::BlockPostingList;
+/// use idencode::segment::{merge_segments, SegmentReader, SegmentWriter};
+///
+/// let segment = |num_docs, terms: &[(&str, &[u32])]| {
+///     let mut writer = SegmentWriter::new(Cursor::new(vec![]), num_docs);
+///     for (term, ids) in terms {
+///         let list = BlockPostingList::new(ids).unwrap();
+///         writer.add_term(term.as_bytes(), &list).unwrap();
+///     }
+///     SegmentReader::open(writer.finish().unwrap().into_inner()).unwrap()
+/// };
+/// let a = segment(3, &[("cat", &[0, 2]), ("dog", &[1])]);
+/// let b = segment(2, &[("bird", &[1]), ("cat", &[0])]);
+///
+/// let merged = merge_segments(&[a, b], Cursor::new(vec![])).unwrap();
+/// let merged = SegmentReader::open(merged.into_inner()).unwrap();
+/// assert_eq!(merged.num_docs(), 5);
+/// let cat = merged.postings(b"cat").unwrap().unwrap();
+/// assert_eq!(cat.doc_ids(), vec![0, 2, 3]);
+/// assert_eq!(merged.dictionary().len(), 3);
+/// ```
+pub fn merge_segments<B: AsRef<[u8]>, W: Write>(
+    segments: &[SegmentReader<B>],
+    writer: W,
+) -> io::Result<W> {
+    let mut offsets = Vec::with_capacity(segments.len());
+    let mut num_docs = 0_u32;
+    for segment in segments {
+        offsets.push(num_docs);
+        num_docs = num_docs.checked_add(segment.num_docs()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The merged segment has more than 2^32 - 1 documents.",
+            )
+        })?;
+    }
+
+    let mut terms: Vec<Terms<'_>> = segments.iter().map(|s| s.dictionary().terms()).collect();
+    let mut heads: Vec<Option<(Vec<u8>, TermInfo)>> =
+        terms.iter_mut().map(Iterator::next).collect();
+    let kind = segments
+        .first()
+        .map_or(DictionaryKind::default(), |s| s.dictionary().kind());
+    let mut writer = SegmentWriter::with_dictionary(writer, num_docs, kind);
+    // The smallest term of the heads of the dictionaries is the next one.
+    while let Some(term) = heads.iter().flatten().map(|(term, _)| term).min().cloned() {
+        let mut list = BlockPostingList::default();
+        for (i, head) in heads.iter_mut().enumerate() {
+            let Some((_, info)) = head.take_if(|(t, _)| *t == term) else {
+                continue;
+            };
+            let postings = segments[i]
+                .read_postings(info)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            list.append_shifted(&postings, offsets[i])
+                .expect("The documents of a segment follow the previous ones.");
+            *head = terms[i].next();
+        }
+        writer.add_term(&term, &list)?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postings::PostingIterator;
+    use std::io::Cursor;
+
+    /// A term, with its document IDs and their impacts, if any.
+    type Term = (Vec<u8>, Vec<u32>, Option<Vec<u32>>);
+
+    fn segment(num_docs: u32, terms: &[Term]) -> Vec<u8> {
+        let mut writer = SegmentWriter::new(Cursor::new(vec![]), num_docs);
+        for (term, ids, impacts) in terms {
+            let list = match impacts {
+                Some(impacts) => BlockPostingList::with_impacts(ids, impacts).unwrap(),
+                None => BlockPostingList::new(ids).unwrap(),
+            };
+            writer.add_term(term, &list).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn postings(list: &BlockPostingList) -> Vec<(u32, u32)> {
+        let mut iter = list.iter();
+        let mut postings = vec![];
+        while let (Some(id), Some(impact)) = (iter.doc_id(), iter.impact()) {
+            postings.push((id, impact));
+            iter.next();
+        }
+        postings
+    }
+
+    #[test]
+    fn test_merge() {
+        // Three segments, whose terms are the multiples of 1, 2 and 3 below
+        // 100, with a posting list of a few hundred IDs each.
+        let num_docs = [1000, 700, 3000];
+        let segments: Vec<Vec<u8>> = (1..=3_u32)
+            .map(|step| {
+                let terms: Vec<_> = (0..100_u32)
+                    .step_by(step as usize)
+                    .map(|t| {
+                        let ids: Vec<u32> = (t % 7..num_docs[step as usize - 1])
+                            .step_by(3 + t as usize)
+                            .collect();
+                        let impacts = (step != 2).then(|| ids.iter().map(|id| id % 5).collect());
+                        (format!("{t:02}").into_bytes(), ids, impacts)
+                    })
+                    .collect();
+                segment(num_docs[step as usize - 1], &terms)
+            })
+            .collect();
+        let readers: Vec<_> = segments
+            .iter()
+            .map(|bytes| SegmentReader::open(bytes).unwrap())
+            .collect();
+
+        let merged = merge_segments(&readers, Cursor::new(vec![]))
+            .unwrap()
+            .into_inner();
+        let merged = SegmentReader::open(merged).unwrap();
+        assert_eq!(merged.num_docs(), 4700);
+        assert_eq!(merged.dictionary().len(), 100);
+        let num_postings: u64 = readers.iter().map(SegmentReader::num_postings).sum();
+        assert_eq!(merged.num_postings(), num_postings);
+
+        for (term, info) in merged.dictionary().terms() {
+            let mut expected = vec![];
+            for (reader, offset) in readers.iter().zip([0, 1000, 1700]) {
+                if let Some(list) = reader.postings(&term).unwrap() {
+                    let postings = postings(&list);
+                    expected.extend(
+                        postings
+                            .into_iter()
+                            .map(|(id, impact)| (id + offset, impact)),
+                    );
+                }
+            }
+            assert_eq!(postings(&merged.read_postings(info).unwrap()), expected);
+        }
+    }
+
+    #[test]
+    fn test_merge_errs() {
+        let big = segment(u32::MAX - 5, &[(b"a".to_vec(), vec![1], None)]);
+        let small = segment(6, &[(b"a".to_vec(), vec![2], None)]);
+        let readers = [big.as_slice(), small.as_slice()].map(|b| SegmentReader::open(b).unwrap());
+        let e = merge_segments(&readers, Cursor::new(vec![])).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert!(merge_segments(&readers[..1], Cursor::new(vec![])).is_ok());
+
+        // The merge of no segments has no documents.
+        let empty = merge_segments::<&[u8], _>(&[], Cursor::new(vec![])).unwrap();
+        let empty = SegmentReader::open(empty.into_inner()).unwrap();
+        assert!(empty.dictionary().is_empty());
+        assert_eq!(empty.num_docs(), 0);
+        assert_eq!(empty.dictionary().kind(), DictionaryKind::FrontCoded);
+    }
+
+    #[test]
+    fn test_merge_kind() {
+        let list = BlockPostingList::new(&[0]).unwrap();
+        let readers = [DictionaryKind::Hash, DictionaryKind::FrontCoded].map(|kind| {
+            let mut writer = SegmentWriter::with_dictionary(Cursor::new(vec![]), 1, kind);
+            writer.add_term(b"a", &list).unwrap();
+            SegmentReader::open(writer.finish().unwrap().into_inner()).unwrap()
+        });
+        for (i, kind) in [(0, DictionaryKind::Hash), (1, DictionaryKind::FrontCoded)] {
+            let merged = merge_segments(&readers[i..], Cursor::new(vec![])).unwrap();
+            let merged = SegmentReader::open(merged.into_inner()).unwrap();
+            assert_eq!(merged.dictionary().kind(), kind);
+            assert_eq!(merged.doc_freq(b"a"), Some(2 - i as u32));
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Segments: files that hold the posting lists of a set of terms, along with
+//! a dictionary of the terms and statistics of the documents, so that an
+//! application persists an index and queries it.
+//!
+//! A segment is made of three sections, followed by a footer:
+//!
+//! - the postings: the posting list of each term, serialized with
+//!   [`BlockPostingList::to_bytes`], in the order of the terms;
+//! - the dictionary: a [`Dictionary`], which maps each term to the number
+//!   of its documents and the byte range of its posting list, laid out as
+//!   front-coded blocks or as a hash table, as its [`DictionaryKind`] says;
+//! - the metadata: the number of documents of the segment and the total
+//!   number of postings, in Variable Byte Encoding.
+//!
+//! The footer is the directory of the sections: the offset and the length
+//! of each, as 64-bit little-endian numbers, followed by the tag of the
+//! kind of the dictionary, in a byte, and by the magic bytes
+//! `IDSG`. Since it is found at the end of the file, a [`SegmentWriter`]
+//! streams the posting lists as they come. The skip metadata of each list,
+//! the last document ID and the largest impact of each of its blocks, is
+//! kept within the list, so that the blocks a query skips are never decoded.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::Cursor;
+//! use idencode::postings::{BlockPostingList, PostingIterator};
+//! use idencode::segment::{SegmentReader, SegmentWriter};
+//!
+//! let mut writer = SegmentWriter::new(Cursor::new(vec![]), 1000);
+//! let quick = BlockPostingList::new(&[3, 50, 700]).unwrap();
+//! let fox = BlockPostingList::with_impacts(&[50, 999], &[2, 7]).unwrap();
+//! writer.add_term(b"fox", &fox).unwrap();
+//! writer.add_term(b"quick", &quick).unwrap();
+//! let bytes = writer.finish().unwrap().into_inner();
+//!
+//! let reader = SegmentReader::open(bytes).unwrap();
+//! assert_eq!((reader.num_docs(), reader.num_postings()), (1000, 5));
+//! assert_eq!(reader.doc_freq(b"quick"), Some(3));
+//!
+//! let list = reader.postings(b"fox").unwrap().unwrap();
+//! let mut iter = list.iter();
+//! assert_eq!(iter.next_geq(100), Some(999));
+//! assert_eq!(iter.impact(), Some(7));
+//! assert_eq!(reader.postings(b"dog"), Ok(None));
+//! ```
+
+mod cache;
+pub mod dict;
+mod merge;
+
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::code::global::vb::{read_vb_bytes, write_vb_bytes};
+use crate::error::InvalidCodeError;
+use crate::postings::BlockPostingList;
+
+pub use cache::{DecodeCache, DecodedPostingIter, DecodedPostings};
+use dict::{read_dictionary, DictionaryBuilder};
+pub use dict::{
+    Dictionary, DictionaryKind, FrontCodedDictionary, HashDictionary, TermInfo, TermIter, Terms,
+};
+pub use merge::merge_segments;
+
+/// The magic bytes at the end of a segment.
+const MAGIC: [u8; 4] = *b"IDSG";
+
+/// The number of sections of a segment.
+const NUM_SECTIONS: usize = 3;
+
+/// The number of bytes of the footer of a segment.
+const FOOTER_LEN: usize = NUM_SECTIONS * 16 + 1 + MAGIC.len();
+
+/// A writer of a segment, that takes the terms in increasing order of their
+/// bytes, along with their posting lists.
+///
+/// The posting lists are written as they come, while the dictionary, which
+/// takes a few bytes per term, is kept in memory and written by
+/// [`SegmentWriter::finish`], along with the metadata and the footer.
+#[derive(Debug)]
+pub struct SegmentWriter<W: Write> {
+    writer: W,
+    num_docs: u32,
+    dictionary: DictionaryBuilder,
+    postings_len: u64,
+    num_postings: u64,
+}
+
+impl<W: Write> SegmentWriter<W> {
+    /// Creates a writer of a segment of documents with IDs from 0 to
+    /// `num_docs - 1`, with a front-coded dictionary.
+    pub fn new(writer: W, num_docs: u32) -> Self {
+        Self::with_dictionary(writer, num_docs, DictionaryKind::default())
+    }
+
+    /// Creates a writer of a segment of documents with IDs from 0 to
+    /// `num_docs - 1`, with a dictionary of a kind.
+    pub fn with_dictionary(writer: W, num_docs: u32, kind: DictionaryKind) -> Self {
+        SegmentWriter {
+            writer,
+            num_docs,
+            dictionary: DictionaryBuilder::new(kind),
+            postings_len: 0,
+            num_postings: 0,
+        }
+    }
+
+    /// Writes the posting list of a term.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the term
+    /// is not greater than the previous one, or if the list is empty or has
+    /// a document ID out of the segment.
+    pub fn add_term(&mut self, term: &[u8], list: &BlockPostingList) -> io::Result<()> {
+        if list.last().is_none_or(|last| last >= self.num_docs) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The posting list is empty, or has a document out of the segment.",
+            ));
+        }
+        let bytes = list.to_bytes();
+        let info = TermInfo::new(list.len() as u32, self.postings_len, bytes.len() as u64);
+        if !self.dictionary.push(term, info) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The terms are not in increasing order.",
+            ));
+        }
+        self.writer.write_all(&bytes)?;
+        self.postings_len += bytes.len() as u64;
+        self.num_postings += list.len() as u64;
+        Ok(())
+    }
+
+    /// Writes the dictionary, the metadata and the footer, and returns the
+    /// writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let kind = self.dictionary.kind();
+        let dictionary = self.dictionary.finish();
+        let mut metadata = vec![];
+        write_vb_bytes(self.num_docs, &mut metadata);
+        write_vb_bytes(self.num_postings, &mut metadata);
+
+        let mut footer = Vec::with_capacity(FOOTER_LEN);
+        let mut offset = 0_u64;
+        for len in [
+            self.postings_len,
+            dictionary.len() as u64,
+            metadata.len() as u64,
+        ] {
+            footer.extend_from_slice(&offset.to_le_bytes());
+            footer.extend_from_slice(&len.to_le_bytes());
+            offset += len;
+        }
+        footer.push(kind.tag());
+        footer.extend_from_slice(&MAGIC);
+
+        self.writer.write_all(&dictionary)?;
+        self.writer.write_all(&metadata)?;
+        self.writer.write_all(&footer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// A reader of a segment written by a [`SegmentWriter`], over its bytes,
+/// like a vector or a memory map of the file.
+///
+/// Opening a segment reads its footer, metadata and dictionary. The posting
+/// list of a term is only read when it is requested, or found in the
+/// [`DecodeCache`] of the reader, if it has one.
+#[derive(Debug)]
+pub struct SegmentReader<B> {
+    bytes: B,
+    postings: Range<usize>,
+    dictionary: Box<dyn Dictionary>,
+    num_docs: u32,
+    num_postings: u64,
+    /// The cache of the decoded posting lists, along with the ID that it
+    /// gave to the segment.
+    cache: Option<(Arc<DecodeCache>, u64)>,
+}
+
+impl<B: AsRef<[u8]>> SegmentReader<B> {
+    /// Opens the segment in a buffer of bytes.
+    ///
+    /// Returns an error if the bytes are not a segment.
+    pub fn open(bytes: B) -> Result<Self, InvalidCodeError> {
+        let err = || InvalidCodeError::SegmentCodeError;
+        let data = bytes.as_ref();
+        let body_len = data.len().checked_sub(FOOTER_LEN).ok_or_else(err)?;
+        let (body, footer) = data.split_at(body_len);
+        if footer[footer.len() - MAGIC.len()..] != MAGIC {
+            return Err(err());
+        }
+
+        // The sections follow each other, and end at the footer.
+        let mut sections = [0..0, 0..0, 0..0];
+        let mut end = 0;
+        for (i, section) in sections.iter_mut().enumerate() {
+            let offset = read_u64_le(&footer[16 * i..]);
+            let len = read_u64_le(&footer[16 * i + 8..]);
+            if offset != end as u64 || len > (body_len - end) as u64 {
+                return Err(err());
+            }
+            *section = end..end + len as usize;
+            end = section.end;
+        }
+        if end != body_len {
+            return Err(err());
+        }
+        let [postings, dictionary, metadata] = sections;
+
+        let kind = DictionaryKind::from_tag(footer[16 * NUM_SECTIONS]).ok_or_else(err)?;
+        let dictionary = read_dictionary(kind, &body[dictionary])?;
+        let metadata = &body[metadata];
+        let mut pos = 0;
+        let num_docs = read_vb_bytes(metadata, &mut pos).map_err(|_| err())?;
+        let num_postings = read_vb_bytes(metadata, &mut pos).map_err(|_| err())?;
+        if pos != metadata.len() {
+            return Err(err());
+        }
+        Ok(SegmentReader {
+            bytes,
+            postings,
+            dictionary,
+            num_docs: u32::try_from(num_docs).map_err(|_| err())?,
+            num_postings,
+            cache: None,
+        })
+    }
+
+    /// Makes the reader keep its decoded posting lists in a cache, which may
+    /// be shared with the readers of other segments.
+    pub fn with_cache(mut self, cache: Arc<DecodeCache>) -> Self {
+        let segment = cache.register();
+        self.cache = Some((cache, segment));
+        self
+    }
+
+    /// Returns the number of documents of the segment, whose IDs are from 0
+    /// to the number minus one.
+    #[inline]
+    pub fn num_docs(&self) -> u32 {
+        self.num_docs
+    }
+
+    /// Returns the total number of postings of the terms of the segment.
+    #[inline]
+    pub fn num_postings(&self) -> u64 {
+        self.num_postings
+    }
+
+    /// Returns the dictionary of the terms of the segment.
+    #[inline]
+    pub fn dictionary(&self) -> &dyn Dictionary {
+        self.dictionary.as_ref()
+    }
+
+    /// Returns the number of documents that contain a term, or `None` if the
+    /// term is not in the segment.
+    pub fn doc_freq(&self, term: &[u8]) -> Option<u32> {
+        self.dictionary.get(term).map(|info| info.doc_freq())
+    }
+
+    /// Reads the posting list of a term, or returns `None` if the term is not
+    /// in the segment.
+    ///
+    /// Returns an error if the bytes of the list are not a posting list of
+    /// the entry of the term.
+    pub fn postings(&self, term: &[u8]) -> Result<Option<BlockPostingList>, InvalidCodeError> {
+        match self.dictionary.get(term) {
+            Some(info) => self.read_postings(info).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the posting list of a dictionary entry.
+    ///
+    /// Returns an error if the bytes of the list are not a posting list of
+    /// the entry.
+    pub fn read_postings(&self, info: TermInfo) -> Result<BlockPostingList, InvalidCodeError> {
+        let list = BlockPostingList::from_bytes(self.postings_bytes(info)?)?;
+        if list.len() != info.doc_freq() as usize
+            || list.last().is_none_or(|last| last >= self.num_docs)
+        {
+            return Err(InvalidCodeError::SegmentCodeError);
+        }
+        Ok(list)
+    }
+
+    /// Decodes the posting list of a term, or returns `None` if the term is
+    /// not in the segment.
+    ///
+    /// If the reader has a [`DecodeCache`], the list is looked up in the
+    /// cache first, and kept in it once it is decoded. Returns an error if
+    /// the bytes of the list are not a posting list of the entry of the term.
+    pub fn decoded_postings(
+        &self,
+        term: &[u8],
+    ) -> Result<Option<Arc<DecodedPostings>>, InvalidCodeError> {
+        match self.dictionary.get(term) {
+            Some(info) => self.read_decoded(info).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the posting list of a dictionary entry, through the
+    /// [`DecodeCache`] of the reader, if it has one.
+    ///
+    /// Returns an error if the bytes of the list are not a posting list of
+    /// the entry.
+    pub fn read_decoded(&self, info: TermInfo) -> Result<Arc<DecodedPostings>, InvalidCodeError> {
+        let decode = || Ok(DecodedPostings::new(&self.read_postings(info)?));
+        match &self.cache {
+            Some((cache, segment)) => cache.get_or_decode((*segment, info.offset()), decode),
+            None => decode().map(Arc::new),
+        }
+    }
+
+    /// Returns the bytes of the posting list of a dictionary entry, or an
+    /// error if they are out of the postings of the segment.
+    fn postings_bytes(&self, info: TermInfo) -> Result<&[u8], InvalidCodeError> {
+        let postings = &self.bytes.as_ref()[self.postings.clone()];
+        usize::try_from(info.offset())
+            .ok()
+            .zip(usize::try_from(info.len()).ok())
+            .and_then(|(offset, len)| postings.get(offset..offset.checked_add(len)?))
+            .ok_or(InvalidCodeError::SegmentCodeError)
+    }
+}
+
+/// Reads a 64-bit little-endian number at the start of a buffer of bytes.
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("The number has 8 bytes."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn segment(terms: &[(String, Vec<u32>)], num_docs: u32, kind: DictionaryKind) -> Vec<u8> {
+        let mut writer = SegmentWriter::with_dictionary(Cursor::new(vec![]), num_docs, kind);
+        for (term, ids) in terms {
+            let list = BlockPostingList::new(ids).unwrap();
+            writer.add_term(term.as_bytes(), &list).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_write_read() {
+        let terms: Vec<(String, Vec<u32>)> = (0..300_u32)
+            .map(|i| {
+                (
+                    format!("term{i:03}"),
+                    (0..1 + i * 7).map(|id| id * 3 + i).collect(),
+                )
+            })
+            .collect();
+        for kind in [DictionaryKind::FrontCoded, DictionaryKind::Hash] {
+            let bytes = segment(&terms, 10_000, kind);
+            let reader = SegmentReader::open(bytes.as_slice()).unwrap();
+            assert_eq!(reader.num_docs(), 10_000);
+            assert_eq!(reader.dictionary().kind(), kind);
+            assert_eq!(reader.dictionary().len(), 300);
+            let num_postings: usize = terms.iter().map(|(_, ids)| ids.len()).sum();
+            assert_eq!(reader.num_postings(), num_postings as u64);
+
+            for (term, ids) in &terms {
+                assert_eq!(reader.doc_freq(term.as_bytes()), Some(ids.len() as u32));
+                let list = reader.postings(term.as_bytes()).unwrap().unwrap();
+                assert_eq!(list, BlockPostingList::new(ids).unwrap());
+            }
+            assert_eq!(reader.postings(b"term"), Ok(None));
+            let listed: Vec<Vec<u8>> = reader.dictionary().terms().map(|(term, _)| term).collect();
+            assert_eq!(listed.len(), 300);
+            assert_eq!(listed[299], b"term299");
+        }
+
+        let empty = segment(&[], 0, DictionaryKind::FrontCoded);
+        assert_eq!(empty.len(), FOOTER_LEN + 3);
+        let reader = SegmentReader::open(empty).unwrap();
+        assert!(reader.dictionary().is_empty());
+        assert_eq!((reader.num_docs(), reader.num_postings()), (0, 0));
+    }
+
+    #[test]
+    fn test_write_errs() {
+        let list = BlockPostingList::new(&[1, 5]).unwrap();
+        let mut writer = SegmentWriter::new(Cursor::new(vec![]), 6);
+        writer.add_term(b"b", &list).unwrap();
+        for term in [&b"a"[..], b"b"] {
+            let e = writer.add_term(term, &list).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+        let out_of_segment = BlockPostingList::new(&[6]).unwrap();
+        assert!(writer.add_term(b"c", &out_of_segment).is_err());
+        assert!(writer.add_term(b"c", &BlockPostingList::default()).is_err());
+        writer.add_term(b"c", &list).unwrap();
+
+        let reader = SegmentReader::open(writer.finish().unwrap().into_inner()).unwrap();
+        assert_eq!(reader.dictionary().len(), 2);
+        assert_eq!(reader.num_postings(), 4);
+    }
+
+    #[test]
+    fn test_cache() {
+        let terms: Vec<(String, Vec<u32>)> = (0..10_u32)
+            .map(|i| (format!("{i}"), (i..1000).step_by(10).collect()))
+            .collect();
+        let bytes = segment(&terms, 1000, DictionaryKind::FrontCoded);
+        // The lists take 400 bytes or less, so the cache holds 5 of them.
+        let cache = Arc::new(DecodeCache::new(2000));
+        let readers = [0, 1].map(|_| {
+            SegmentReader::open(&bytes)
+                .unwrap()
+                .with_cache(Arc::clone(&cache))
+        });
+        for reader in &readers {
+            for (term, ids) in &terms[..5] {
+                let decoded = reader.decoded_postings(term.as_bytes()).unwrap().unwrap();
+                assert_eq!(decoded.doc_ids(), ids.as_slice());
+            }
+        }
+        // The lists of the two segments are cached apart.
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 10, 5));
+        for (term, _) in &terms[..5] {
+            readers[1].decoded_postings(term.as_bytes()).unwrap();
+        }
+        assert_eq!(cache.hits(), 5);
+        assert!(cache.n_bytes() <= cache.budget());
+        assert_eq!(readers[0].decoded_postings(b"x"), Ok(None));
+
+        let uncached = SegmentReader::open(&bytes).unwrap();
+        let decoded = uncached.decoded_postings(b"3").unwrap().unwrap();
+        assert_eq!(decoded.doc_ids(), terms[3].1.as_slice());
+        assert_eq!(cache.misses(), 10);
+    }
+
+    #[test]
+    fn test_read_errs() {
+        let terms = vec![("a".to_string(), vec![1, 2, 3]), ("b".to_string(), vec![4])];
+        let bytes = segment(&terms, 5, DictionaryKind::FrontCoded);
+        assert!(SegmentReader::open(&bytes[..bytes.len() - 1]).is_err());
+        assert!(SegmentReader::open(&bytes[1..]).is_err());
+        assert!(SegmentReader::open(&bytes[..FOOTER_LEN - 1]).is_err());
+
+        // A section that overlaps the next one.
+        let mut overlapping = bytes.clone();
+        let footer = bytes.len() - FOOTER_LEN;
+        overlapping[footer + 8] += 1;
+        assert!(SegmentReader::open(overlapping).is_err());
+
+        // A dictionary of an unknown kind, or of another kind.
+        for tag in [2, DictionaryKind::Hash.tag()] {
+            let mut kind = bytes.clone();
+            kind[footer + 16 * NUM_SECTIONS] = tag;
+            assert!(SegmentReader::open(kind).is_err());
+        }
+
+        // A posting list that is not valid.
+        let mut corrupt = bytes.clone();
+        corrupt[0] += 1;
+        let reader = SegmentReader::open(corrupt).unwrap();
+        assert_eq!(
+            reader.postings(b"a"),
+            Err(InvalidCodeError::PostingListCodeError)
+        );
+        assert!(reader.postings(b"b").is_ok());
+    }
+}